@@ -160,7 +160,7 @@ async fn main() {
     println!("   Config: ${:.0} starting capital", config.starting_capital);
     println!("   Models: {:?}", config.model_priority);
 
-    let trader = AiTrader::new(config);
+    let mut trader = AiTrader::new(config);
 
     // Check Ollama
     println!("\n   Checking Ollama...");
@@ -198,7 +198,7 @@ async fn main() {
     // Run trading cycle
     println!("\n   🧠 AI analyzing market data...\n");
 
-    match trader.run_cycle(&mut db).await {
+    match trader.run_cycle(&mut db, false).await {
         Ok(decisions) => {
             if decisions.is_empty() {
                 println!("   📊 AI decided: No actions to take at this time.");
@@ -41,7 +41,7 @@ async fn main() {
     println!("   Benchmark: {}\n", config.benchmark_symbol);
 
     // 3. Create AI Trader
-    let trader = AiTrader::new(config);
+    let mut trader = AiTrader::new(config);
 
     // 4. Check Ollama
     println!("🔌 Checking Ollama availability...");
@@ -90,7 +90,7 @@ async fn main() {
     println!("║              🤖 RUNNING AUTONOMOUS TRADING CYCLE             ║");
     println!("╚══════════════════════════════════════════════════════════════╝\n");
 
-    match trader.run_cycle(&mut db).await {
+    match trader.run_cycle(&mut db, false).await {
         Ok(decisions) => {
             if decisions.is_empty() {
                 println!("📊 AI decided: No actions to take at this time.\n");
@@ -10,20 +10,24 @@
 //! - Compounding forecast projections
 
 use anyhow::{Context, Result};
-use chrono::{Timelike, Utc};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fs::{self, OpenOptions};
+use std::future::Future;
 use std::io::Write;
 use std::path::PathBuf;
 
-use crate::db::Database;
+use crate::backtest::calculate_metrics;
+use crate::db::{last_close_on, Database};
 use crate::models::{
     AiPerformanceSnapshot, AiTradeDecision, AiTraderConfig, AiTraderStatus, AiTradingSession,
-    BenchmarkComparison, CompoundingForecast,
+    BacktestTrade, BenchmarkComparison, CompoundingForecast, DailyPrice, PerformanceMetrics,
+    TradeDirection,
 };
 use crate::ollama::OllamaClient;
 use crate::signals::SignalEngine;
+use crate::yahoo::PriceFetcher;
 
 // ============================================================================
 // Constants
@@ -32,6 +36,12 @@ use crate::signals::SignalEngine;
 /// Bankruptcy threshold - below this we're done
 pub const BANKRUPTCY_THRESHOLD: f64 = 1000.0;
 
+/// Seconds a model gets to answer a decision query before the next model is tried
+pub const MODEL_QUERY_TIMEOUT_SECS: u64 = 180;
+
+/// Days the latest benchmark bar may lag before it is refetched (spans long weekends)
+pub const BENCHMARK_MAX_AGE_DAYS: i64 = 4;
+
 /// System prompt for the AI trader
 pub const AI_TRADER_SYSTEM_PROMPT: &str = r#"
 You are an autonomous AI trading agent managing a virtual portfolio. Your goal is to MAXIMIZE RETURNS through aggressive trading decisions based on technical analysis.
@@ -416,6 +426,290 @@ pub struct PredictionInfo {
     pub timeframe_days: i32,
 }
 
+// ============================================================================
+// Historical Replay
+// ============================================================================
+
+/// Supplies trading decisions for a market context: the configured models, or a
+/// canned sequence when testing
+pub trait DecisionSource {
+    fn decide(&mut self, context: &MarketContext) -> impl Future<Output = Result<Vec<ParsedDecision>>> + Send;
+}
+
+/// Model server the trader's decisions come from: Ollama live, or a scripted stand-in
+/// so tests can run whole cycles without one
+pub trait DecisionProvider {
+    /// Whether the server is reachable
+    fn is_available(&self) -> impl Future<Output = bool> + Send {
+        async { true }
+    }
+
+    /// Names of the installed models
+    fn installed_models(&self) -> impl Future<Output = Result<Vec<String>>> + Send;
+
+    /// Query a model with thinking enabled, returning (response, thinking)
+    fn query_with_thinking(
+        &self,
+        prompt: &str,
+        system: &str,
+        model: &str,
+    ) -> impl Future<Output = Result<(String, Option<String>)>> + Send;
+}
+
+impl DecisionProvider for OllamaClient {
+    async fn is_available(&self) -> bool {
+        OllamaClient::is_available(self).await
+    }
+
+    async fn installed_models(&self) -> Result<Vec<String>> {
+        Ok(self.list_models().await?.into_iter().map(|m| m.name).collect())
+    }
+
+    async fn query_with_thinking(
+        &self,
+        prompt: &str,
+        system: &str,
+        model: &str,
+    ) -> Result<(String, Option<String>)> {
+        OllamaClient::query_with_thinking(self, prompt, Some(system), Some(model)).await
+    }
+}
+
+/// Source of the current time for trading cycles
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The wall clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock stopped at one instant, for deterministic runs
+#[derive(Debug, Clone, Copy)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// Whether a configured model name refers to an installed one
+/// An untagged name matches its `:latest` tag, as with `ollama run`.
+fn model_installed(model: &str, installed: &[String]) -> bool {
+    installed
+        .iter()
+        .any(|name| name == model || (!model.contains(':') && *name == format!("{}:latest", model)))
+}
+
+/// Queries the trader's models in priority order, without writing decision logs
+pub struct ModelDecisions<'a, P = OllamaClient, C = SystemClock> {
+    trader: &'a AiTrader<P, C>,
+}
+
+impl<'a, P, C> ModelDecisions<'a, P, C> {
+    pub fn new(trader: &'a AiTrader<P, C>) -> Self {
+        Self { trader }
+    }
+}
+
+impl<P: DecisionProvider + Sync, C: Clock + Sync> DecisionSource for ModelDecisions<'_, P, C> {
+    async fn decide(&mut self, context: &MarketContext) -> Result<Vec<ParsedDecision>> {
+        let (_, decisions) = self.trader.query_models(context, false).await?;
+        Ok(decisions)
+    }
+}
+
+/// Returns one scripted batch of decisions per call, then nothing once exhausted
+pub struct ScriptedDecisions {
+    batches: VecDeque<Vec<ParsedDecision>>,
+}
+
+impl ScriptedDecisions {
+    pub fn new(batches: Vec<Vec<ParsedDecision>>) -> Self {
+        Self {
+            batches: batches.into(),
+        }
+    }
+}
+
+impl DecisionSource for ScriptedDecisions {
+    async fn decide(&mut self, _context: &MarketContext) -> Result<Vec<ParsedDecision>> {
+        Ok(self.batches.pop_front().unwrap_or_default())
+    }
+}
+
+/// Outcome of replaying the AI trader over a historical window
+#[derive(Debug, Clone, Serialize)]
+pub struct AiReplayResult {
+    pub symbol: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub initial_capital: f64,
+    pub final_value: f64,
+    pub decisions_count: usize,
+    pub metrics: PerformanceMetrics,
+    pub trades: Vec<BacktestTrade>,
+    pub equity_curve: Vec<(NaiveDate, f64)>,
+}
+
+/// Whole shares a BUY of `quantity_percent` of available cash can afford
+fn buy_quantity(cash: f64, quantity_percent: f64, price: f64) -> f64 {
+    (cash * (quantity_percent / 100.0) / price).floor()
+}
+
+/// ATRs of adverse move a BUY's stop has to allow for, so choppy names aren't
+/// sized as if a tight percentage stop would hold
+pub const RISK_ATR_MULTIPLE: f64 = 2.0;
+
+/// Whole shares whose loss at the stop stays within `risk_per_trade_percent` of equity,
+/// capped at `max_position_percent` of equity. The stop is `stop_loss_percent` below the
+/// price or `RISK_ATR_MULTIPLE` ATRs, whichever is wider.
+pub fn risk_sized_quantity(
+    equity: f64,
+    price: f64,
+    atr: Option<f64>,
+    stop_loss_percent: f64,
+    risk_per_trade_percent: f64,
+    max_position_percent: f64,
+) -> f64 {
+    let stop_distance = (price * stop_loss_percent / 100.0)
+        .max(atr.filter(|a| a.is_finite()).unwrap_or(0.0) * RISK_ATR_MULTIPLE);
+    let cap = (equity * max_position_percent / 100.0 / price).floor();
+    if stop_distance <= 0.0 {
+        return cap;
+    }
+    (equity * risk_per_trade_percent / 100.0 / stop_distance)
+        .floor()
+        .min(cap)
+}
+
+/// Whole shares a SELL of `quantity_percent` of the held position covers
+fn sell_quantity(held: f64, quantity_percent: f64) -> f64 {
+    (held * (quantity_percent / 100.0)).floor()
+}
+
+/// Single-symbol account simulated in memory during a replay
+struct ReplayAccount {
+    symbol: String,
+    cash: f64,
+    quantity: f64,
+    entry_price: f64,
+    entry_date: Option<NaiveDate>,
+    closed_trades: Vec<BacktestTrade>,
+    history: Vec<RecentTrade>,
+}
+
+impl ReplayAccount {
+    fn new(symbol: &str, cash: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            cash,
+            quantity: 0.0,
+            entry_price: 0.0,
+            entry_date: None,
+            closed_trades: Vec::new(),
+            history: Vec::new(),
+        }
+    }
+
+    fn value(&self, price: f64) -> f64 {
+        self.cash + self.quantity * price
+    }
+
+    fn buy(&mut self, date: NaiveDate, price: f64, quantity: f64) {
+        let cost = quantity * price;
+        if quantity < 1.0 || cost > self.cash {
+            return;
+        }
+        self.entry_price = (self.quantity * self.entry_price + cost) / (self.quantity + quantity);
+        self.quantity += quantity;
+        self.cash -= cost;
+        self.entry_date.get_or_insert(date);
+        self.record(date, "BUY", quantity, price, None);
+    }
+
+    fn sell(&mut self, date: NaiveDate, price: f64, quantity: f64, reason: &str) {
+        let quantity = quantity.min(self.quantity);
+        if quantity < 1.0 {
+            return;
+        }
+        let pnl = (price - self.entry_price) * quantity;
+        self.closed_trades.push(BacktestTrade {
+            id: 0,
+            backtest_id: 0,
+            symbol: self.symbol.clone(),
+            direction: TradeDirection::Long,
+            entry_date: self.entry_date.unwrap_or(date),
+            entry_price: self.entry_price,
+            exit_date: Some(date),
+            exit_price: Some(price),
+            shares: quantity,
+            entry_reason: "AI replay".to_string(),
+            exit_reason: Some(reason.chars().take(200).collect()),
+            profit_loss: Some(pnl),
+            profit_loss_percent: Some((price / self.entry_price - 1.0) * 100.0),
+        });
+
+        self.cash += quantity * price;
+        self.quantity -= quantity;
+        if self.quantity < 1.0 {
+            self.quantity = 0.0;
+            self.entry_price = 0.0;
+            self.entry_date = None;
+        }
+        self.record(date, "SELL", quantity, price, Some(pnl));
+    }
+
+    fn record(&mut self, date: NaiveDate, action: &str, quantity: f64, price: f64, pnl: Option<f64>) {
+        self.history.push(RecentTrade {
+            symbol: self.symbol.clone(),
+            action: action.to_string(),
+            quantity,
+            price,
+            pnl,
+            timestamp: date.to_string(),
+        });
+    }
+
+    fn snapshot(&self, price: f64, starting_capital: f64) -> PortfolioSnapshot {
+        let mut positions = Vec::new();
+        if self.quantity > 0.0 {
+            let cost_basis = self.quantity * self.entry_price;
+            let unrealized_pnl = self.quantity * price - cost_basis;
+            positions.push(PositionInfo {
+                symbol: self.symbol.clone(),
+                quantity: self.quantity,
+                entry_price: self.entry_price,
+                current_price: price,
+                unrealized_pnl,
+                unrealized_pnl_percent: (unrealized_pnl / cost_basis) * 100.0,
+            });
+        }
+
+        let total_value = self.value(price);
+        let total_pnl = total_value - starting_capital;
+        PortfolioSnapshot {
+            cash: self.cash,
+            positions,
+            total_value,
+            total_pnl,
+            total_pnl_percent: (total_pnl / starting_capital) * 100.0,
+        }
+    }
+
+    /// Most recent trades first, like the live paper trade query
+    fn recent_trades(&self, limit: usize) -> Vec<RecentTrade> {
+        self.history.iter().rev().take(limit).cloned().collect()
+    }
+}
+
 // ============================================================================
 // Trade Guardrails & Circuit Breaker
 // ============================================================================
@@ -474,8 +768,11 @@ pub struct TradeGuardrails {
     pub max_single_trade_value: f64,
     /// Require confluence signal support for trades
     pub require_confluence: bool,
-    /// Hours to block trading (open/close volatility)
-    pub blocked_hours: Vec<(u8, u8)>,
+    /// Minimum confluence score (-1..1) a buy needs when confluence is required
+    #[serde(default)]
+    pub min_confluence_score: f64,
+    /// Eastern-time windows with no trading (open/close volatility), start inclusive
+    pub blocked_hours: Vec<(NaiveTime, NaiveTime)>,
 }
 
 impl TradeGuardrails {
@@ -488,6 +785,7 @@ impl TradeGuardrails {
                 max_daily_trades: 20,
                 max_single_trade_value: 100_000.0,
                 require_confluence: false,
+                min_confluence_score: 0.0,
                 blocked_hours: vec![],  // No restrictions
             },
             TradingMode::Normal => Self {
@@ -496,7 +794,8 @@ impl TradeGuardrails {
                 max_daily_trades: 10,
                 max_single_trade_value: 50_000.0,
                 require_confluence: true,
-                blocked_hours: vec![(9, 9), (15, 16)],  // 9:00-9:45, 15:45-16:00
+                min_confluence_score: 0.3,
+                blocked_hours: parse_blocked_hours("09:30-09:45,15:45-16:00"),
             },
             TradingMode::Conservative => Self {
                 mode,
@@ -504,7 +803,8 @@ impl TradeGuardrails {
                 max_daily_trades: 5,
                 max_single_trade_value: 25_000.0,
                 require_confluence: true,
-                blocked_hours: vec![(9, 10), (15, 16)],  // Extended blocked
+                min_confluence_score: 0.5,
+                blocked_hours: parse_blocked_hours("09:30-10:00,15:30-16:00"),  // Extended blocked
             },
             TradingMode::Paused => Self {
                 mode,
@@ -512,12 +812,48 @@ impl TradeGuardrails {
                 max_daily_trades: 0,
                 max_single_trade_value: 0.0,
                 require_confluence: true,
-                blocked_hours: vec![(0, 24)],  // All hours blocked
+                min_confluence_score: 1.0,
+                blocked_hours: vec![(NaiveTime::MIN, NaiveTime::from_hms_opt(23, 59, 59).unwrap())],  // All day
             },
         }
     }
 }
 
+/// Parse comma-separated `HH:MM-HH:MM` windows, e.g. `09:30-09:45,15:45-16:00`.
+/// Malformed or empty ranges are skipped with a warning.
+pub fn parse_blocked_hours(spec: &str) -> Vec<(NaiveTime, NaiveTime)> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .filter_map(|part| {
+            let window = part.split_once('-').and_then(|(start, end)| {
+                let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").ok()?;
+                let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").ok()?;
+                (start < end).then_some((start, end))
+            });
+            if window.is_none() {
+                println!("[WARN] Ignoring malformed blocked_hours range '{}'", part);
+            }
+            window
+        })
+        .collect()
+}
+
+/// `nth` (1-based) occurrence of `weekday` in a month
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, nth: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, nth).expect("every month has a first and second Sunday")
+}
+
+/// US Eastern wall-clock time (New York exchange hours). Daylight time runs from
+/// 2:00 local on the second Sunday of March to 2:00 local on the first Sunday of November.
+pub fn to_eastern(now: DateTime<Utc>) -> NaiveDateTime {
+    let year = now.year();
+    let dst_start = nth_weekday(year, 3, Weekday::Sun, 2).and_hms_opt(7, 0, 0).unwrap().and_utc();
+    let dst_end = nth_weekday(year, 11, Weekday::Sun, 1).and_hms_opt(6, 0, 0).unwrap().and_utc();
+    let offset_hours = if now >= dst_start && now < dst_end { 4 } else { 5 };
+    (now - Duration::hours(offset_hours)).naive_utc()
+}
+
 impl Default for TradeGuardrails {
     fn default() -> Self {
         Self::for_mode(TradingMode::Normal)
@@ -584,19 +920,19 @@ impl CircuitBreaker {
         self.daily_pnl_percent = pnl_percent;
     }
 
-    /// Trigger the circuit breaker with a pause duration
-    pub fn trigger(&mut self, pause_hours: u32) {
+    /// Trigger the circuit breaker with a pause duration starting at `now`
+    pub fn trigger(&mut self, now: DateTime<Utc>, pause_hours: u32) {
         self.triggered = true;
-        self.resume_at = Some(Utc::now() + chrono::Duration::hours(pause_hours as i64));
+        self.resume_at = Some(now + chrono::Duration::hours(pause_hours as i64));
     }
 
-    /// Check if pause period has ended
-    pub fn can_resume(&self) -> bool {
+    /// Check if pause period has ended as of `now`
+    pub fn can_resume(&self, now: DateTime<Utc>) -> bool {
         if !self.triggered {
             return true;
         }
         match self.resume_at {
-            Some(resume) => Utc::now() >= resume,
+            Some(resume) => now >= resume,
             None => true,
         }
     }
@@ -736,14 +1072,152 @@ pub struct TradeRejection {
     pub raw_request: Option<String>,
 }
 
+/// Account facts the guardrail rules judge a proposed trade against
+#[derive(Debug, Clone, Copy)]
+pub struct GuardrailInputs {
+    /// Paper trades already executed today
+    pub trades_today: u32,
+    /// Confluence score of the symbol, when it was computed
+    pub confluence_score: Option<f64>,
+    /// Current time, from the trader's clock
+    pub now: DateTime<Utc>,
+    /// Current US Eastern clock time
+    pub time: NaiveTime,
+}
+
+/// Guardrail checks for a trader's current mode, override and circuit breaker.
+/// Each rule reports itself in `rule_triggered`, so rejections name the exact check.
+pub struct GuardrailEngine<'a> {
+    pub guardrails: &'a TradeGuardrails,
+    pub circuit_breaker: &'a CircuitBreaker,
+    /// Position limit in percent, after any active override
+    pub max_position_pct: f64,
+}
+
+impl GuardrailEngine<'_> {
+    /// Run the rules in order (circuit_breaker, max_position, max_daily_trades,
+    /// blocked_hours, confluence), returning the first rejection
+    pub fn evaluate(&self, trade: &ProposedTrade, inputs: &GuardrailInputs) -> Option<TradeRejection> {
+        self.circuit_breaker(trade, inputs)
+            .or_else(|| self.max_position(trade, inputs))
+            .or_else(|| self.max_daily_trades(trade, inputs))
+            .or_else(|| self.blocked_hours(trade, inputs))
+            .or_else(|| self.confluence(trade, inputs))
+    }
+
+    fn reject(&self, trade: &ProposedTrade, inputs: &GuardrailInputs, rule: &str, reason: String) -> TradeRejection {
+        TradeRejection {
+            timestamp: inputs.now.to_rfc3339(),
+            session_id: None,
+            attempted_action: trade.action.to_uppercase(),
+            symbol: trade.symbol.clone(),
+            quantity: Some(trade.quantity),
+            quantity_percent: Some(trade.quantity_percent),
+            estimated_value: Some(trade.estimated_value),
+            reason,
+            rule_triggered: rule.to_string(),
+            trading_mode: self.guardrails.mode.to_string(),
+            raw_request: None,
+        }
+    }
+
+    /// No trading while paused or while a tripped breaker's pause is running
+    pub fn circuit_breaker(&self, trade: &ProposedTrade, inputs: &GuardrailInputs) -> Option<TradeRejection> {
+        if self.guardrails.mode == TradingMode::Paused {
+            return Some(self.reject(trade, inputs, "circuit_breaker", "Trading is paused".to_string()));
+        }
+        if self.circuit_breaker.triggered && !self.circuit_breaker.can_resume(inputs.now) {
+            let until = self
+                .circuit_breaker
+                .resume_at
+                .map_or_else(|| "further notice".to_string(), |t| t.to_rfc3339());
+            return Some(self.reject(trade, inputs, "circuit_breaker", format!("Circuit breaker active until {}", until)));
+        }
+        None
+    }
+
+    /// Buys stay within the position percentage and single-trade dollar limits
+    pub fn max_position(&self, trade: &ProposedTrade, inputs: &GuardrailInputs) -> Option<TradeRejection> {
+        if !trade.action.eq_ignore_ascii_case("BUY") {
+            return None;
+        }
+        if trade.quantity_percent > self.max_position_pct {
+            return Some(self.reject(
+                trade,
+                inputs,
+                "max_position",
+                format!("Position size {:.1}% exceeds max {:.1}%", trade.quantity_percent, self.max_position_pct),
+            ));
+        }
+        if trade.estimated_value > self.guardrails.max_single_trade_value {
+            return Some(self.reject(
+                trade,
+                inputs,
+                "max_position",
+                format!(
+                    "Trade value ${:.2} exceeds max ${:.2}",
+                    trade.estimated_value, self.guardrails.max_single_trade_value
+                ),
+            ));
+        }
+        None
+    }
+
+    /// At most the mode's number of trades per day
+    pub fn max_daily_trades(&self, trade: &ProposedTrade, inputs: &GuardrailInputs) -> Option<TradeRejection> {
+        (inputs.trades_today >= self.guardrails.max_daily_trades).then(|| {
+            self.reject(
+                trade,
+                inputs,
+                "max_daily_trades",
+                format!("Daily trade limit reached ({}/{})", inputs.trades_today, self.guardrails.max_daily_trades),
+            )
+        })
+    }
+
+    /// No trading inside a blocked Eastern-time window
+    pub fn blocked_hours(&self, trade: &ProposedTrade, inputs: &GuardrailInputs) -> Option<TradeRejection> {
+        self.guardrails
+            .blocked_hours
+            .iter()
+            .find(|(start, end)| inputs.time >= *start && inputs.time < *end)
+            .map(|(start, end)| {
+                self.reject(
+                    trade,
+                    inputs,
+                    "blocked_hours",
+                    format!("Trading blocked {}-{} ET", start.format("%H:%M"), end.format("%H:%M")),
+                )
+            })
+    }
+
+    /// Buys need signal confluence when the mode requires it
+    pub fn confluence(&self, trade: &ProposedTrade, inputs: &GuardrailInputs) -> Option<TradeRejection> {
+        if !self.guardrails.require_confluence || !trade.action.eq_ignore_ascii_case("BUY") {
+            return None;
+        }
+        let score = inputs.confluence_score?;
+        (score < self.guardrails.min_confluence_score).then(|| {
+            self.reject(
+                trade,
+                inputs,
+                "confluence",
+                format!("Confluence score {:.2} below required {:.2}", score, self.guardrails.min_confluence_score),
+            )
+        })
+    }
+}
+
 // ============================================================================
 // AiTrader Implementation
 // ============================================================================
 
-/// The main AI trading engine
-pub struct AiTrader {
+/// The main AI trading engine. Decisions come from `P` and time from `C`; the defaults
+/// are the live Ollama server and the wall clock.
+pub struct AiTrader<P = OllamaClient, C = SystemClock> {
     pub config: AiTraderConfig,
-    ollama: OllamaClient,
+    provider: P,
+    clock: C,
     signal_engine: SignalEngine,
     /// Current guardrails (derived from mode)
     pub guardrails: TradeGuardrails,
@@ -751,15 +1225,28 @@ pub struct AiTrader {
     pub circuit_breaker: CircuitBreaker,
     /// STRYK override state
     pub override_state: Override,
+    /// Directory for raw responses and decision logs (`logs/ai_decisions` when unset)
+    logs_dir: Option<String>,
 }
 
 impl AiTrader {
     /// Create a new AI trader with the given configuration
     pub fn new(config: AiTraderConfig) -> Self {
-        let ollama = OllamaClient::new();
+        Self::new_with(config, OllamaClient::new(), SystemClock)
+    }
+
+    /// Create with default configuration
+    pub fn with_defaults() -> Self {
+        Self::new(AiTraderConfig::default())
+    }
+}
+
+impl<P: DecisionProvider + Sync, C: Clock + Sync> AiTrader<P, C> {
+    /// Create a trader that takes decisions from `provider` and the time from `clock`
+    pub fn new_with(config: AiTraderConfig, provider: P, clock: C) -> Self {
         let signal_engine = SignalEngine::new();
         let mode = TradingMode::from_str(&config.trading_mode);
-        let guardrails = TradeGuardrails::for_mode(mode);
+        let guardrails = Self::guardrails_for(&config, mode);
         let circuit_breaker = CircuitBreaker {
             daily_loss_threshold: config.daily_loss_threshold,
             consecutive_loss_limit: config.consecutive_loss_limit as u32,
@@ -769,17 +1256,31 @@ impl AiTrader {
 
         Self {
             config,
-            ollama,
+            provider,
+            clock,
             signal_engine,
             guardrails,
             circuit_breaker,
             override_state: Override::default(),
+            logs_dir: None,
         }
     }
 
-    /// Create with default configuration
-    pub fn with_defaults() -> Self {
-        Self::new(AiTraderConfig::default())
+    /// Write raw responses and decision logs under `dir` instead of `logs/ai_decisions`
+    pub fn with_logs_dir(mut self, dir: impl Into<String>) -> Self {
+        self.logs_dir = Some(dir.into());
+        self
+    }
+
+    /// Mode guardrails plus the configured blocked windows, which apply in every mode
+    fn guardrails_for(config: &AiTraderConfig, mode: TradingMode) -> TradeGuardrails {
+        let mut guardrails = TradeGuardrails::for_mode(mode);
+        for window in parse_blocked_hours(&config.blocked_hours) {
+            if !guardrails.blocked_hours.contains(&window) {
+                guardrails.blocked_hours.push(window);
+            }
+        }
+        guardrails
     }
 
     // ========================================================================
@@ -794,7 +1295,7 @@ impl AiTrader {
     /// Switch trading mode (persists to database)
     pub fn switch_mode(&mut self, db: &Database, new_mode: TradingMode, reason: Option<&str>) -> Result<()> {
         let old_mode = self.guardrails.mode;
-        self.guardrails = TradeGuardrails::for_mode(new_mode);
+        self.guardrails = Self::guardrails_for(&self.config, new_mode);
 
         // Persist to database
         db.update_trading_mode(&new_mode.to_string())?;
@@ -833,30 +1334,68 @@ impl AiTrader {
     // Circuit Breaker
     // ========================================================================
 
-    /// Check and handle circuit breaker
+    /// Load the persisted trip state, so a pause survives restarts
+    pub fn load_circuit_breaker_state(&mut self, db: &Database) -> Result<()> {
+        let (triggered, resume_at) = db.get_circuit_breaker_state()?;
+        self.circuit_breaker.triggered = triggered;
+        self.circuit_breaker.resume_at = resume_at;
+        Ok(())
+    }
+
+    /// Check and handle circuit breaker.
+    ///
+    /// While a pause is running nothing is re-evaluated. Once it has elapsed the breaker
+    /// resets and, if it switched to conservative on the way in, the previous mode is restored.
+    /// The daily loss is measured from the start-of-day value (the last performance snapshot
+    /// before today, else the starting capital); a daily-loss trip re-bases it to the value
+    /// at the trip, so a resumed breaker only trips again on further losses.
     pub fn check_circuit_breaker(&mut self, db: &Database) -> Result<Option<CircuitBreakerTrigger>> {
+        self.load_circuit_breaker_state(db)?;
+        if self.circuit_breaker.triggered {
+            if !self.circuit_breaker.can_resume(self.clock.now()) {
+                return Ok(None);
+            }
+            self.resume_from_circuit_breaker(db)?;
+        }
+
         // Update daily P/L
+        let today = self.clock.now().date_naive();
         let (_, _, total_value) = db.get_paper_portfolio_value()?;
-        let starting_value = self.config.starting_capital;
+        let starting_value = match db.get_daily_loss_baseline()? {
+            Some((date, value)) if date == today => value,
+            _ => db
+                .get_ai_portfolio_value_before(today)?
+                .unwrap_or(self.config.starting_capital),
+        };
         let daily_pnl_pct = ((total_value - starting_value) / starting_value) * 100.0;
         self.circuit_breaker.update_daily_pnl(daily_pnl_pct);
 
+        // Only losses since the last streak trip count, so a resumed breaker doesn't re-trip on them
+        let last_streak_trip = db.get_last_circuit_breaker_trip(&CircuitBreakerTrigger::ConsecutiveLosses.to_string())?;
+        self.circuit_breaker.consecutive_losses = db.get_consecutive_losses_since(last_streak_trip.as_deref())?;
+
         // Check trigger conditions
         if let Some(trigger) = self.circuit_breaker.should_trigger() {
-            self.circuit_breaker.trigger(1); // 1 hour pause
+            let previous_mode = self.guardrails.mode;
+            self.circuit_breaker.trigger(self.clock.now(), 1); // 1 hour pause
+            db.set_circuit_breaker_state(true, self.circuit_breaker.resume_at)?;
+            if trigger == CircuitBreakerTrigger::DailyLossThreshold {
+                db.set_daily_loss_baseline(today, total_value)?;
+            }
 
             // Auto-switch to conservative if configured
             if self.circuit_breaker.auto_conservative_on_trigger {
-                let _ = self.switch_mode(db, TradingMode::Conservative, Some(&trigger.to_string()));
+                self.switch_mode(db, TradingMode::Conservative, Some(&trigger.to_string()))?;
             }
 
             // Log circuit breaker event
             db.log_circuit_breaker_event(
                 &trigger.to_string(),
+                &previous_mode.to_string(),
                 &self.guardrails.mode.to_string(),
-                "conservative",
                 daily_pnl_pct,
                 self.circuit_breaker.consecutive_losses as i32,
+                self.circuit_breaker.resume_at,
             )?;
 
             println!("[AI Trader] CIRCUIT BREAKER TRIGGERED: {:?}", trigger);
@@ -866,6 +1405,21 @@ impl AiTrader {
         Ok(None)
     }
 
+    /// Clear an elapsed pause and put back the mode that was active when it tripped
+    fn resume_from_circuit_breaker(&mut self, db: &Database) -> Result<()> {
+        self.circuit_breaker.reset();
+        db.set_circuit_breaker_state(false, None)?;
+
+        if self.circuit_breaker.auto_conservative_on_trigger {
+            if let Some(previous) = db.get_last_circuit_breaker_previous_mode()? {
+                self.switch_mode(db, TradingMode::from_str(&previous), Some("circuit breaker resume"))?;
+            }
+        }
+
+        println!("[AI Trader] Circuit breaker cleared, resuming in {} mode", self.guardrails.mode);
+        Ok(())
+    }
+
     /// Record trade outcome for circuit breaker
     pub fn record_trade_outcome(&mut self, is_win: bool) {
         if is_win {
@@ -879,85 +1433,42 @@ impl AiTrader {
     // Trade Validation
     // ========================================================================
 
+    /// Guardrail rules for the current mode, override and circuit breaker state
+    pub fn guardrail_engine(&self) -> GuardrailEngine<'_> {
+        GuardrailEngine {
+            guardrails: &self.guardrails,
+            circuit_breaker: &self.circuit_breaker,
+            max_position_pct: self.get_effective_max_position(),
+        }
+    }
+
+    /// Today's trade count and the Eastern clock, plus a confluence score if computed
+    fn guardrail_inputs(&self, db: &Database, confluence_score: Option<f64>) -> Result<GuardrailInputs> {
+        Ok(GuardrailInputs {
+            trades_today: db.get_paper_trades_today()?.len() as u32,
+            confluence_score,
+            now: self.clock.now(),
+            time: to_eastern(self.clock.now()).time(),
+        })
+    }
+
     /// Validate a proposed trade against guardrails
     pub fn validate_trade(
         &self,
         db: &Database,
         proposed: &ProposedTrade,
-        has_confluence: bool,
+        confluence_score: f64,
     ) -> Result<TradeResult> {
-        // Check if paused
-        if self.guardrails.mode == TradingMode::Paused {
-            return Ok(TradeResult::Rejected {
-                reason: "Trading is paused".to_string(),
-                rule_triggered: "mode_paused".to_string(),
-                proposed_trade: proposed.clone(),
-            });
-        }
+        let inputs = self.guardrail_inputs(db, Some(confluence_score))?;
 
-        // Check circuit breaker pause
-        if self.circuit_breaker.triggered && !self.circuit_breaker.can_resume() {
+        if let Some(rejection) = self.guardrail_engine().evaluate(proposed, &inputs) {
             return Ok(TradeResult::Rejected {
-                reason: format!("Circuit breaker active until {:?}",
-                    self.circuit_breaker.resume_at),
-                rule_triggered: "circuit_breaker_pause".to_string(),
+                reason: rejection.reason,
+                rule_triggered: rejection.rule_triggered,
                 proposed_trade: proposed.clone(),
             });
         }
 
-        // Check position size
-        let max_pct = self.get_effective_max_position();
-        if proposed.quantity_percent > max_pct {
-            return Ok(TradeResult::Rejected {
-                reason: format!("Position size {:.1}% exceeds max {:.1}%",
-                    proposed.quantity_percent, max_pct),
-                rule_triggered: "max_position_size".to_string(),
-                proposed_trade: proposed.clone(),
-            });
-        }
-
-        // Check single trade value
-        if proposed.estimated_value > self.guardrails.max_single_trade_value {
-            return Ok(TradeResult::Rejected {
-                reason: format!("Trade value ${:.2} exceeds max ${:.2}",
-                    proposed.estimated_value, self.guardrails.max_single_trade_value),
-                rule_triggered: "max_trade_value".to_string(),
-                proposed_trade: proposed.clone(),
-            });
-        }
-
-        // Check confluence requirement
-        if self.guardrails.require_confluence && !has_confluence {
-            return Ok(TradeResult::Rejected {
-                reason: "Trade requires confluence signal support".to_string(),
-                rule_triggered: "require_confluence".to_string(),
-                proposed_trade: proposed.clone(),
-            });
-        }
-
-        // Check daily trade limit
-        let today_trades = db.get_paper_trades_today()?;
-        if today_trades.len() as u32 >= self.guardrails.max_daily_trades {
-            return Ok(TradeResult::Rejected {
-                reason: format!("Daily trade limit reached ({}/{})",
-                    today_trades.len(), self.guardrails.max_daily_trades),
-                rule_triggered: "max_daily_trades".to_string(),
-                proposed_trade: proposed.clone(),
-            });
-        }
-
-        // Check blocked hours
-        let current_hour = Utc::now().hour() as u8;
-        for (start, end) in &self.guardrails.blocked_hours {
-            if current_hour >= *start && current_hour < *end {
-                return Ok(TradeResult::Rejected {
-                    reason: format!("Trading blocked during hours {}-{}", start, end),
-                    rule_triggered: "blocked_hours".to_string(),
-                    proposed_trade: proposed.clone(),
-                });
-            }
-        }
-
         // All validations passed - trade can proceed
         // Note: Actual execution happens elsewhere, this just validates
         Ok(TradeResult::Executed {
@@ -967,10 +1478,50 @@ impl AiTrader {
             quantity: proposed.quantity,
             price: 0.0, // Will be filled by execute
             value: proposed.estimated_value,
-            timestamp: Utc::now().to_rfc3339(),
+            timestamp: self.clock.now().to_rfc3339(),
         })
     }
 
+    /// Run the guardrails on a trade at the latest price without executing it.
+    /// Returns the priced trade and the rejection it would get (`None` if allowed).
+    pub fn dry_run_trade(
+        &self,
+        db: &Database,
+        action: &str,
+        symbol: &str,
+        quantity: f64,
+    ) -> Result<(ProposedTrade, Option<TradeRejection>)> {
+        let price = db
+            .get_latest_price(symbol)?
+            .ok_or_else(|| anyhow::anyhow!("No price data for {}", symbol))?;
+        let (_, _, total_value) = db.get_paper_portfolio_value()?;
+        let estimated_value = quantity * price;
+        let proposed = ProposedTrade {
+            action: action.to_uppercase(),
+            symbol: symbol.to_string(),
+            quantity,
+            quantity_percent: if total_value > 0.0 { estimated_value / total_value * 100.0 } else { 0.0 },
+            estimated_value,
+            confidence: 0.0,
+            reasoning: "dry run".to_string(),
+        };
+        let confluence_score = if self.guardrails.require_confluence && proposed.action == "BUY" {
+            Some(self.confluence_score(db, symbol)?)
+        } else {
+            None
+        };
+        let inputs = self.guardrail_inputs(db, confluence_score)?;
+        let rejection = self.guardrail_engine().evaluate(&proposed, &inputs);
+        Ok((proposed, rejection))
+    }
+
+    /// Confluence score for a symbol's latest bar
+    pub fn confluence_score(&self, db: &Database, symbol: &str) -> Result<f64> {
+        let prices = db.get_prices(symbol)?;
+        let indicators = db.get_all_indicators(symbol)?;
+        Ok(self.signal_engine.confluence_score(&indicators, &prices))
+    }
+
     /// Log a trade rejection to the database
     pub fn log_rejection(&self, db: &Database, rejection: &TradeRejection) -> Result<()> {
         db.log_trade_rejection(rejection)?;
@@ -984,7 +1535,7 @@ impl AiTrader {
 
     /// Check if Ollama is available
     pub async fn check_ollama(&self) -> bool {
-        self.ollama.is_available().await
+        self.provider.is_available().await
     }
 
     /// Get the current AI trader status
@@ -1034,14 +1585,31 @@ impl AiTrader {
         Ok(None)
     }
 
-    /// Run one autonomous trading cycle
-    pub async fn run_cycle(&self, db: &mut Database) -> Result<Vec<AiTradeDecision>> {
+    /// Run one autonomous trading cycle.
+    /// With `dry_run` the models are queried and guardrails evaluated as usual, but no trades
+    /// execute: decisions are recorded flagged as dry-run with no paper trade attached. A dry
+    /// run only reads the persisted circuit breaker state and writes no decision log files.
+    pub async fn run_cycle(&mut self, db: &mut Database, dry_run: bool) -> Result<Vec<AiTradeDecision>> {
         // Check bankruptcy
         let (_, _, total_value) = db.get_paper_portfolio_value()?;
         if total_value < BANKRUPTCY_THRESHOLD {
             anyhow::bail!("Portfolio is bankrupt (value: ${:.2})", total_value);
         }
 
+        // No trading while the circuit breaker is tripped
+        if dry_run {
+            self.load_circuit_breaker_state(db)?;
+        } else {
+            self.check_circuit_breaker(db)?;
+        }
+        if self.circuit_breaker.triggered && !self.circuit_breaker.can_resume(self.clock.now()) {
+            let until = self
+                .circuit_breaker
+                .resume_at
+                .map_or_else(|| "further notice".to_string(), |t| t.to_rfc3339());
+            anyhow::bail!("Circuit breaker active until {}", until);
+        }
+
         // Get active session
         let session = db.get_active_ai_session()?;
         let session_id = session.map(|s| s.id);
@@ -1050,12 +1618,12 @@ impl AiTrader {
         let context = self.gather_market_context(db)?;
 
         // Query AI for decisions
-        let decisions = self.query_ai_for_decisions(&context).await?;
+        let (model, decisions) = self.query_models(&context, !dry_run).await?;
 
         // Execute decisions
         let mut recorded_decisions = Vec::new();
         for decision in decisions {
-            match self.execute_decision(db, session_id, &decision, &context).await {
+            match self.execute_decision(db, session_id, &decision, &context, &model, dry_run).await {
                 Ok(recorded) => recorded_decisions.push(recorded),
                 Err(e) => {
                     eprintln!("[AI Trader] Failed to execute decision for {}: {}", decision.symbol, e);
@@ -1063,12 +1631,134 @@ impl AiTrader {
             }
         }
 
+        // Nothing traded, so the breaker and the performance history are unchanged
+        if dry_run {
+            return Ok(recorded_decisions);
+        }
+
+        // This cycle's sells may have completed a losing streak
+        self.check_circuit_breaker(db)?;
+
         // Record performance snapshot
         self.record_performance_snapshot(db)?;
 
         Ok(recorded_decisions)
     }
 
+    /// Replay the decision loop over a symbol's daily prices between two dates.
+    ///
+    /// Each bar gets the context a live cycle would have seen, built only from data up to
+    /// that date. Decisions for the symbol fill at the bar's close in an in-memory account,
+    /// so the paper account and decision logs are left untouched.
+    pub async fn replay<S: DecisionSource>(
+        &self,
+        db: &mut Database,
+        symbol: &str,
+        from_date: NaiveDate,
+        to_date: NaiveDate,
+        source: &mut S,
+    ) -> Result<AiReplayResult> {
+        let prices: Vec<DailyPrice> = db
+            .get_prices(symbol)?
+            .into_iter()
+            .filter(|p| p.date <= to_date)
+            .collect();
+        let start = prices
+            .iter()
+            .position(|p| p.date >= from_date)
+            .with_context(|| format!("No {} prices between {} and {}", symbol, from_date, to_date))?;
+
+        // Indicators only look backwards, so one pass over the full history is safe to index by date
+        let indicator_list = crate::indicators::calculate_all(&prices);
+        let mut indicators_by_date: HashMap<NaiveDate, HashMap<String, f64>> = HashMap::new();
+        for ind in &indicator_list {
+            indicators_by_date
+                .entry(ind.date)
+                .or_default()
+                .insert(ind.indicator_name.clone(), ind.value);
+        }
+
+        let signals = db.get_signals(symbol, false)?;
+        let prediction_accuracy = db.get_ai_prediction_accuracy()?.accuracy_percent;
+        let gate_on_confluence = self.guardrails.require_confluence && self.config.require_confluence;
+
+        let starting_capital = self.config.starting_capital;
+        let mut account = ReplayAccount::new(symbol, starting_capital);
+        let mut decisions_count = 0;
+        let mut equity_curve = Vec::new();
+
+        for i in start..prices.len() {
+            let bar = &prices[i];
+            let price = bar.close;
+
+            // Signals are newest first
+            let signal_summaries = signals
+                .iter()
+                .filter(|s| s.timestamp <= bar.date)
+                .take(5)
+                .map(|s| SignalSummary {
+                    signal_type: format!("{:?}", s.signal_type),
+                    direction: format!("{:?}", s.direction),
+                    strength: s.strength,
+                })
+                .collect();
+            let indicators = indicators_by_date.remove(&bar.date).unwrap_or_default();
+
+            let context = MarketContext {
+                timestamp: bar.date.to_string(),
+                portfolio: account.snapshot(price, starting_capital),
+                symbols_data: vec![self.build_symbol_context(symbol, &prices[..=i], indicators, signal_summaries)],
+                recent_trades: account.recent_trades(10),
+                prediction_accuracy,
+                constraints: self.trading_constraints(),
+            };
+
+            let decisions = source.decide(&context).await?;
+            for decision in decisions.iter().filter(|d| d.symbol.eq_ignore_ascii_case(symbol)) {
+                decisions_count += 1;
+                match decision.action.to_uppercase().as_str() {
+                    "BUY" => {
+                        if gate_on_confluence {
+                            let known: Vec<_> = indicator_list
+                                .iter()
+                                .filter(|ind| ind.date <= bar.date)
+                                .cloned()
+                                .collect();
+                            let score = self.signal_engine.confluence_score(&known, &prices[..=i]);
+                            if score < self.guardrails.min_confluence_score {
+                                continue;
+                            }
+                        }
+                        let quantity = buy_quantity(account.cash, decision.quantity_percent, price);
+                        account.buy(bar.date, price, quantity);
+                    }
+                    "SELL" => {
+                        let quantity = sell_quantity(account.quantity, decision.quantity_percent);
+                        account.sell(bar.date, price, quantity, &decision.reasoning);
+                    }
+                    _ => {}
+                }
+            }
+
+            equity_curve.push((bar.date, account.value(price)));
+        }
+
+        let equity_history: Vec<f64> = equity_curve.iter().map(|(_, value)| *value).collect();
+        let metrics = calculate_metrics(starting_capital, &account.closed_trades, &equity_history);
+
+        Ok(AiReplayResult {
+            symbol: symbol.to_string(),
+            start_date: prices[start].date,
+            end_date: prices.last().map(|p| p.date).unwrap_or(to_date),
+            initial_capital: starting_capital,
+            final_value: equity_history.last().copied().unwrap_or(starting_capital),
+            decisions_count,
+            metrics,
+            trades: account.closed_trades,
+            equity_curve,
+        })
+    }
+
     /// Gather market context for AI decision making
     pub fn gather_market_context(&self, db: &Database) -> Result<MarketContext> {
         let (cash, positions_value, total_value) = db.get_paper_portfolio_value()?;
@@ -1152,20 +1842,24 @@ impl AiTrader {
             .collect();
 
         Ok(MarketContext {
-            timestamp: Utc::now().to_rfc3339(),
+            timestamp: self.clock.now().to_rfc3339(),
             portfolio,
             symbols_data,
             recent_trades: recent,
             prediction_accuracy: accuracy.accuracy_percent,
-            constraints: TradingConstraints {
-                max_position_size_percent: self.config.max_position_size_percent,
-                stop_loss_percent: self.config.stop_loss_percent,
-                take_profit_percent: self.config.take_profit_percent,
-                min_cash_reserve_percent: 20.0,
-            },
+            constraints: self.trading_constraints(),
         })
     }
 
+    fn trading_constraints(&self) -> TradingConstraints {
+        TradingConstraints {
+            max_position_size_percent: self.config.max_position_size_percent,
+            stop_loss_percent: self.config.stop_loss_percent,
+            take_profit_percent: self.config.take_profit_percent,
+            min_cash_reserve_percent: 20.0,
+        }
+    }
+
     /// Gather context for a single symbol
     fn gather_symbol_context(&self, db: &Database, symbol: &str) -> Result<SymbolContext> {
         let prices = db.get_prices(symbol)?;
@@ -1173,18 +1867,6 @@ impl AiTrader {
             anyhow::bail!("No price data for {}", symbol);
         }
 
-        let current_price = prices.last().map(|p| p.close).unwrap_or(0.0);
-        let prev_price = if prices.len() >= 2 {
-            prices[prices.len() - 2].close
-        } else {
-            current_price
-        };
-        let price_change_percent = if prev_price > 0.0 {
-            ((current_price - prev_price) / prev_price) * 100.0
-        } else {
-            0.0
-        };
-
         // Get indicators
         let indicators_list = db.get_all_indicators(symbol)?;
         let mut indicators: HashMap<String, f64> = HashMap::new();
@@ -1204,6 +1886,29 @@ impl AiTrader {
             })
             .collect();
 
+        Ok(self.build_symbol_context(symbol, &prices, indicators, signal_summaries))
+    }
+
+    /// Build symbol context from prices up to the decision bar and that bar's indicators
+    fn build_symbol_context(
+        &self,
+        symbol: &str,
+        prices: &[DailyPrice],
+        indicators: HashMap<String, f64>,
+        signals: Vec<SignalSummary>,
+    ) -> SymbolContext {
+        let current_price = prices.last().map(|p| p.close).unwrap_or(0.0);
+        let prev_price = if prices.len() >= 2 {
+            prices[prices.len() - 2].close
+        } else {
+            current_price
+        };
+        let price_change_percent = if prev_price > 0.0 {
+            ((current_price - prev_price) / prev_price) * 100.0
+        } else {
+            0.0
+        };
+
         // Check for confluence using signal engine
         let latest_price = prices.last().unwrap();
         let confluence = if let Some(c) = self.signal_engine.detect_confluence_signal(
@@ -1221,31 +1926,55 @@ impl AiTrader {
             None
         };
 
-        Ok(SymbolContext {
+        SymbolContext {
             symbol: symbol.to_string(),
             current_price,
             price_change_percent,
             indicators,
-            signals: signal_summaries,
+            signals,
             confluence,
-        })
+        }
     }
 
-    /// Query AI for trading decisions (with THINKING mode enabled)
-    async fn query_ai_for_decisions(&self, context: &MarketContext) -> Result<Vec<ParsedDecision>> {
+    /// Query AI for trading decisions (with THINKING mode enabled), returning the
+    /// model that answered. Configured models that aren't installed are skipped; the rest
+    /// are tried in priority order until one gives a valid response within the timeout.
+    /// `record` writes the raw response, decision log and decision index entries.
+    async fn query_models(&self, context: &MarketContext, record: bool) -> Result<(String, Vec<ParsedDecision>)> {
+        let installed = self
+            .provider
+            .installed_models()
+            .await
+            .context("Could not list installed Ollama models")?;
+        let candidates: Vec<&String> = self
+            .config
+            .model_priority
+            .iter()
+            .filter(|model| model_installed(model, &installed))
+            .collect();
+        if candidates.is_empty() {
+            anyhow::bail!(
+                "None of the configured models are installed ({}). Pull one with: ollama pull <model>",
+                self.config.model_priority.join(", ")
+            );
+        }
+
         let prompt = self.format_context_prompt(context);
+        let mut failures = Vec::new();
 
         // Try models in priority order
-        for model in &self.config.model_priority {
+        for model in candidates {
             let mut log_entry = AiTradeLog::new(model, &prompt);
 
             // Use query_with_thinking for extended reasoning before decisions
             println!("[AI Trader] Querying {} with THINKING mode enabled...", model);
-            match self
-                .ollama
-                .query_with_thinking(&prompt, Some(AI_TRADER_SYSTEM_PROMPT), Some(model))
-                .await
-            {
+            let query = self.provider.query_with_thinking(&prompt, AI_TRADER_SYSTEM_PROMPT, model);
+            let timeout = std::time::Duration::from_secs(MODEL_QUERY_TIMEOUT_SECS);
+            let result = match tokio::time::timeout(timeout, query).await {
+                Ok(result) => result,
+                Err(_) => Err(anyhow::anyhow!("timed out after {}s", MODEL_QUERY_TIMEOUT_SECS)),
+            };
+            match result {
                 Ok((response, thinking)) => {
                     // Log thinking process if available
                     if let Some(ref think_content) = thinking {
@@ -1260,7 +1989,19 @@ impl AiTrader {
                         log_entry.raw_response = response.clone();
                     }
 
-                    if let Err(e) = log_raw_response(model, &prompt, &log_entry.raw_response, None) {
+                    // Replays only need the decisions, not the on-disk logs
+                    if !record {
+                        match self.parse_ai_response(&response) {
+                            Ok(parsed) => return Ok((model.clone(), parsed.decisions)),
+                            Err(e) => {
+                                eprintln!("[AI Trader] Model {} returned invalid response, trying next", model);
+                                failures.push(format!("{}: {}", model, e));
+                                continue;
+                            }
+                        }
+                    }
+
+                    if let Err(e) = log_raw_response(model, &prompt, &log_entry.raw_response, self.logs_dir.as_deref()) {
                         eprintln!("[AI Trader] WARNING: Failed to log raw response: {}", e);
                     }
 
@@ -1269,44 +2010,48 @@ impl AiTrader {
                             log_entry.parsed_decisions = Some(parsed.clone());
 
                             // Log to both individual file and daily JSONL
-                            let log_file = match log_entry.save(None) {
+                            let log_file = match log_entry.save(self.logs_dir.as_deref()) {
                                 Ok(path) => path.to_string_lossy().to_string(),
                                 Err(e) => {
                                     eprintln!("[AI Trader] WARNING: Failed to save decision log: {}", e);
                                     "unknown".to_string()
                                 }
                             };
-                            if let Err(e) = log_entry.append_to_daily_log(None) {
+                            if let Err(e) = log_entry.append_to_daily_log(self.logs_dir.as_deref()) {
                                 eprintln!("[AI Trader] WARNING: Failed to append to daily log: {}", e);
                             }
 
                             // Index each decision for outcome tracking
                             for decision in &parsed.decisions {
-                                if let Err(e) = index_decision(model, decision, &log_file, None) {
+                                if let Err(e) = index_decision(model, decision, &log_file, self.logs_dir.as_deref()) {
                                     eprintln!("[AI Trader] WARNING: Failed to index decision: {}", e);
                                 }
                             }
 
                             println!("[AI Trader] Decision received from model: {} (thinking: {})",
                                 model, if thinking.is_some() { "YES" } else { "NO" });
-                            return Ok(parsed.decisions);
+                            return Ok((model.clone(), parsed.decisions));
                         }
                         Err(e) => {
                             log_entry.error = Some(format!("Parse error: {}", e));
-                            let _ = log_entry.save(None); // Log the failure too
+                            let _ = log_entry.save(self.logs_dir.as_deref()); // Log the failure too
                             eprintln!("[AI Trader] Model {} returned invalid response, trying next", model);
+                            failures.push(format!("{}: {}", model, e));
                         }
                     }
                 }
                 Err(e) => {
                     log_entry.error = Some(format!("Query error: {}", e));
-                    let _ = log_entry.save(None); // Log the failure too
+                    if record {
+                        let _ = log_entry.save(self.logs_dir.as_deref()); // Log the failure too
+                    }
                     eprintln!("[AI Trader] Model {} failed: {}, trying next", model, e);
+                    failures.push(format!("{}: {}", model, e));
                 }
             }
         }
 
-        anyhow::bail!("All models failed to provide valid decisions")
+        anyhow::bail!("All installed models failed to provide valid decisions ({})", failures.join("; "))
     }
 
     /// Format market context into a prompt
@@ -1424,6 +2169,8 @@ impl AiTrader {
         session_id: Option<i64>,
         decision: &ParsedDecision,
         context: &MarketContext,
+        model: &str,
+        dry_run: bool,
     ) -> Result<AiTradeDecision> {
         let (cash, _, total_value) = db.get_paper_portfolio_value()?;
 
@@ -1437,24 +2184,69 @@ impl AiTrader {
 
         match decision.action.to_uppercase().as_str() {
             "BUY" => {
-                // Calculate quantity based on percentage of available cash
-                let max_spend = cash * (decision.quantity_percent / 100.0);
-                let quantity = (max_spend / current_price).floor();
-
-                if quantity >= 1.0 {
-                    let trade = db.execute_paper_trade(
-                        &decision.symbol,
-                        crate::models::PaperTradeAction::Buy,
-                        quantity,
+                // Percentage of available cash, limited by the per-trade risk budget
+                let atr = db.get_latest_indicator_value(&decision.symbol, "ATR_14")?;
+                let quantity = buy_quantity(cash, decision.quantity_percent, current_price).min(
+                    risk_sized_quantity(
+                        total_value,
                         current_price,
-                        None,
-                        Some(&format!("AI: {}", &decision.reasoning[..decision.reasoning.len().min(200)])),
-                    )?;
+                        atr,
+                        self.config.stop_loss_percent,
+                        self.config.risk_per_trade_percent,
+                        self.config.max_position_size_percent,
+                    ),
+                );
+
+                let confluence_score = if self.guardrails.require_confluence && self.config.require_confluence {
+                    Some(self.confluence_score(db, &decision.symbol)?)
+                } else {
+                    None
+                };
+                // Sized as a share of the whole portfolio, as `dry_run_trade` does
+                let estimated_value = quantity * current_price;
+                let proposed = ProposedTrade {
+                    action: "BUY".to_string(),
+                    symbol: decision.symbol.clone(),
+                    quantity,
+                    quantity_percent: if total_value > 0.0 { estimated_value / total_value * 100.0 } else { 0.0 },
+                    estimated_value,
+                    confidence: decision.confidence,
+                    reasoning: decision.reasoning.clone(),
+                };
+                let inputs = self.guardrail_inputs(db, confluence_score)?;
+
+                if let Some(rejection) = self.guardrail_engine().evaluate(&proposed, &inputs) {
+                    if dry_run {
+                        println!("[AI Trader] DRY RUN: BUY {} blocked by {}", decision.symbol, rejection.rule_triggered);
+                    } else {
+                        self.log_rejection(db, &TradeRejection { session_id, ..rejection })?;
+                    }
+                } else if dry_run {
+                    println!(
+                        "[AI Trader] DRY RUN: would BUY {} x {:.0} @ ${:.2}",
+                        decision.symbol,
+                        quantity,
+                        current_price
+                    );
+                } else if quantity >= 1.0 {
+                    let notes = format!("AI: {}", &decision.reasoning[..decision.reasoning.len().min(200)]);
+                    let trade = if self.config.allow_partial_fills {
+                        db.execute_paper_buy_affordable(&decision.symbol, quantity, current_price, None, Some(&notes))?
+                    } else {
+                        db.execute_paper_trade(
+                            &decision.symbol,
+                            crate::models::PaperTradeAction::Buy,
+                            quantity,
+                            current_price,
+                            None,
+                            Some(&notes),
+                        )?
+                    };
                     paper_trade_id = Some(trade.id);
                     println!(
                         "[AI Trader] BUY: {} x {:.0} @ ${:.2}",
                         decision.symbol,
-                        quantity,
+                        trade.quantity,
                         current_price
                     );
                 }
@@ -1462,8 +2254,32 @@ impl AiTrader {
             "SELL" => {
                 // Get current position
                 if let Some(pos) = db.get_paper_position(&decision.symbol)? {
-                    let quantity = (pos.quantity * (decision.quantity_percent / 100.0)).floor();
-                    if quantity >= 1.0 {
+                    let quantity = sell_quantity(pos.quantity, decision.quantity_percent);
+                    let proposed = ProposedTrade {
+                        action: "SELL".to_string(),
+                        symbol: decision.symbol.clone(),
+                        quantity,
+                        quantity_percent: decision.quantity_percent,
+                        estimated_value: quantity * current_price,
+                        confidence: decision.confidence,
+                        reasoning: decision.reasoning.clone(),
+                    };
+                    let inputs = self.guardrail_inputs(db, None)?;
+
+                    if let Some(rejection) = self.guardrail_engine().evaluate(&proposed, &inputs) {
+                        if dry_run {
+                            println!("[AI Trader] DRY RUN: SELL {} blocked by {}", decision.symbol, rejection.rule_triggered);
+                        } else {
+                            self.log_rejection(db, &TradeRejection { session_id, ..rejection })?;
+                        }
+                    } else if dry_run {
+                        println!(
+                            "[AI Trader] DRY RUN: would SELL {} x {:.0} @ ${:.2}",
+                            decision.symbol,
+                            quantity,
+                            current_price
+                        );
+                    } else if quantity >= 1.0 {
                         let trade = db.execute_paper_trade(
                             &decision.symbol,
                             crate::models::PaperTradeAction::Sell,
@@ -1494,14 +2310,14 @@ impl AiTrader {
         let ai_decision = AiTradeDecision {
             id: 0, // Will be set by DB
             session_id,
-            timestamp: Utc::now().to_rfc3339(),
+            timestamp: self.clock.now().to_rfc3339(),
             action: decision.action.clone(),
             symbol: decision.symbol.clone(),
             quantity: Some(decision.quantity_percent),
             price_at_decision: Some(current_price),
             confidence: decision.confidence,
             reasoning: decision.reasoning.clone(),
-            model_used: self.config.model_priority.first().cloned().unwrap_or_default(),
+            model_used: model.to_string(),
             predicted_direction: decision.prediction.as_ref().map(|p| p.direction.clone()),
             predicted_price_target: decision.prediction.as_ref().map(|p| p.price_target),
             predicted_timeframe_days: decision.prediction.as_ref().map(|p| p.timeframe_days),
@@ -1509,6 +2325,7 @@ impl AiTrader {
             actual_price_at_timeframe: None,
             prediction_accurate: None,
             paper_trade_id,
+            dry_run,
         };
 
         let decision_id = db.record_ai_decision(&ai_decision)?;
@@ -1547,7 +2364,8 @@ impl AiTrader {
 
         let snapshot = AiPerformanceSnapshot {
             id: 0,
-            timestamp: Utc::now().to_rfc3339(),
+            // Same shape as SQLite's CURRENT_TIMESTAMP, which older rows were stamped with
+            timestamp: self.clock.now().format("%Y-%m-%d %H:%M:%S").to_string(),
             portfolio_value: total_value,
             cash,
             positions_value,
@@ -1590,27 +2408,47 @@ impl AiTrader {
         Ok(starting_capital)
     }
 
-    /// Calculate benchmark comparison
-    pub fn get_benchmark_comparison(&self, db: &Database) -> Result<BenchmarkComparison> {
+    /// Calculate benchmark comparison against the configured benchmark's closes.
+    /// Missing or stale benchmark prices are fetched through `fetcher` first.
+    /// Each snapshot is paired with the benchmark's close on or before its date, and
+    /// snapshots older than the first benchmark bar are left out, so both series
+    /// share the same timestamps. The benchmark is rebased to the first portfolio value.
+    pub fn get_benchmark_comparison(
+        &self,
+        db: &mut Database,
+        fetcher: &impl PriceFetcher,
+    ) -> Result<BenchmarkComparison> {
         let snapshots = db.get_ai_performance_snapshots(365)?;
 
         if snapshots.is_empty() {
             return Ok(BenchmarkComparison::default());
         }
 
-        let first = &snapshots[0];
-        let last = snapshots.last().unwrap();
+        let closes = self.benchmark_closes(db, fetcher)?;
+        let aligned: Vec<(&str, f64, f64)> = snapshots
+            .iter()
+            .filter_map(|s| {
+                let date = NaiveDate::parse_from_str(s.timestamp.get(..10)?, "%Y-%m-%d").ok()?;
+                let close = last_close_on(&closes, date)?;
+                Some((s.timestamp.as_str(), s.portfolio_value, close))
+            })
+            .collect();
+
+        let (Some(&(_, first_value, first_close)), Some(&(_, last_value, last_close))) =
+            (aligned.first(), aligned.last())
+        else {
+            return Ok(BenchmarkComparison::default());
+        };
 
-        let portfolio_return = ((last.portfolio_value - first.portfolio_value)
-            / first.portfolio_value)
-            * 100.0;
-        let benchmark_return =
-            ((last.benchmark_value - first.benchmark_value) / first.benchmark_value) * 100.0;
+        let portfolio_return = ((last_value - first_value) / first_value) * 100.0;
+        let benchmark_return = ((last_close - first_close) / first_close) * 100.0;
         let alpha = portfolio_return - benchmark_return;
 
-        let tracking_data: Vec<(String, f64, f64)> = snapshots
+        let tracking_data: Vec<(String, f64, f64)> = aligned
             .iter()
-            .map(|s| (s.timestamp.clone(), s.portfolio_value, s.benchmark_value))
+            .map(|&(timestamp, value, close)| {
+                (timestamp.to_string(), value, first_value * close / first_close)
+            })
             .collect();
 
         Ok(BenchmarkComparison {
@@ -1621,6 +2459,37 @@ impl AiTrader {
         })
     }
 
+    /// Date-sorted benchmark closes, fetching a year of history when none are stored
+    /// or the latest is older than `BENCHMARK_MAX_AGE_DAYS`. A failed fetch falls back
+    /// to the stored closes and is only an error when there are none.
+    fn benchmark_closes(
+        &self,
+        db: &mut Database,
+        fetcher: &impl PriceFetcher,
+    ) -> Result<Vec<(NaiveDate, f64)>> {
+        let symbol = &self.config.benchmark_symbol;
+        let today = self.clock.now().date_naive();
+        let mut prices = db.get_prices(symbol)?;
+
+        let is_stale = prices
+            .last()
+            .is_none_or(|p| (today - p.date).num_days() > BENCHMARK_MAX_AGE_DAYS);
+        if is_stale {
+            match fetcher.fetch_prices(symbol, "1y") {
+                Ok(fetched) => {
+                    db.upsert_daily_prices(&fetched)?;
+                    prices = db.get_prices(symbol)?;
+                }
+                Err(e) if prices.is_empty() => {
+                    return Err(e).with_context(|| format!("No prices for benchmark {}", symbol));
+                }
+                Err(e) => println!("[WARN] Using stale {} prices for benchmark: {}", symbol, e),
+            }
+        }
+
+        Ok(prices.iter().map(|p| (p.date, p.close)).collect())
+    }
+
     /// Calculate compounding forecast
     pub fn get_compounding_forecast(&self, db: &Database) -> Result<CompoundingForecast> {
         let snapshots = db.get_ai_performance_snapshots(30)?;
@@ -1719,6 +2588,7 @@ impl AiTrader {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::PaperTradeAction;
 
     #[test]
     fn test_ai_trader_creation() {
@@ -1751,4 +2621,602 @@ mod tests {
         assert_eq!(parsed.decisions[0].symbol, "AAPL");
         assert_eq!(parsed.decisions[0].action, "BUY");
     }
+
+    #[test]
+    fn test_risk_sizing_buys_fewer_shares_of_volatile_symbols() {
+        // $100k equity risking 1% ($1,000) with a 5% stop on a $100 stock
+        let calm = risk_sized_quantity(100_000.0, 100.0, Some(1.0), 5.0, 1.0, 25.0);
+        let choppy = risk_sized_quantity(100_000.0, 100.0, Some(5.0), 5.0, 1.0, 25.0);
+        assert_eq!(calm, 200.0); // $5 stop governs
+        assert_eq!(choppy, 100.0); // 2 ATR = $10 stop
+        assert!(choppy < calm);
+
+        // A tiny ATR can't push the position past the size cap
+        assert_eq!(risk_sized_quantity(100_000.0, 100.0, Some(0.1), 5.0, 5.0, 10.0), 100.0);
+    }
+
+    fn proposed(action: &str, quantity_percent: f64, estimated_value: f64) -> ProposedTrade {
+        ProposedTrade {
+            action: action.to_string(),
+            symbol: "TEST".to_string(),
+            quantity: 10.0,
+            quantity_percent,
+            estimated_value,
+            confidence: 0.8,
+            reasoning: "test".to_string(),
+        }
+    }
+
+    const CALM_INPUTS: GuardrailInputs = GuardrailInputs {
+        trades_today: 0,
+        confluence_score: Some(0.5),
+        now: DateTime::<Utc>::UNIX_EPOCH,
+        time: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+    };
+
+    fn rule_of(rejection: Option<TradeRejection>) -> Option<String> {
+        rejection.map(|r| r.rule_triggered)
+    }
+
+    #[test]
+    fn test_guardrail_circuit_breaker_rule() {
+        let normal = TradeGuardrails::default();
+        let mut breaker = CircuitBreaker::default();
+        let engine = GuardrailEngine { guardrails: &normal, circuit_breaker: &breaker, max_position_pct: 10.0 };
+        assert!(engine.circuit_breaker(&proposed("BUY", 5.0, 1_000.0), &CALM_INPUTS).is_none());
+
+        breaker.trigger(CALM_INPUTS.now, 1);
+        let engine = GuardrailEngine { guardrails: &normal, circuit_breaker: &breaker, max_position_pct: 10.0 };
+        let rule = rule_of(engine.circuit_breaker(&proposed("SELL", 5.0, 1_000.0), &CALM_INPUTS));
+        assert_eq!(rule.as_deref(), Some("circuit_breaker"));
+
+        let paused = TradeGuardrails::for_mode(TradingMode::Paused);
+        let idle = CircuitBreaker::default();
+        let engine = GuardrailEngine { guardrails: &paused, circuit_breaker: &idle, max_position_pct: 0.0 };
+        assert!(engine.circuit_breaker(&proposed("BUY", 5.0, 1_000.0), &CALM_INPUTS).is_some());
+    }
+
+    #[test]
+    fn test_guardrail_max_position_rule() {
+        let normal = TradeGuardrails::default();
+        let breaker = CircuitBreaker::default();
+        let engine = GuardrailEngine { guardrails: &normal, circuit_breaker: &breaker, max_position_pct: 10.0 };
+
+        let rule = rule_of(engine.max_position(&proposed("BUY", 15.0, 1_000.0), &CALM_INPUTS));
+        assert_eq!(rule.as_deref(), Some("max_position"));
+        // Over the $50k single-trade value limit
+        assert!(engine.max_position(&proposed("BUY", 5.0, 60_000.0), &CALM_INPUTS).is_some());
+        assert!(engine.max_position(&proposed("BUY", 10.0, 1_000.0), &CALM_INPUTS).is_none());
+        // Sells reduce exposure and are never size-limited
+        assert!(engine.max_position(&proposed("SELL", 15.0, 60_000.0), &CALM_INPUTS).is_none());
+    }
+
+    #[test]
+    fn test_guardrail_max_daily_trades_rule() {
+        let normal = TradeGuardrails::default();
+        let breaker = CircuitBreaker::default();
+        let engine = GuardrailEngine { guardrails: &normal, circuit_breaker: &breaker, max_position_pct: 10.0 };
+        let trade = proposed("BUY", 5.0, 1_000.0);
+
+        let busy = GuardrailInputs { trades_today: 10, ..CALM_INPUTS };
+        assert_eq!(rule_of(engine.max_daily_trades(&trade, &busy)).as_deref(), Some("max_daily_trades"));
+        let one_left = GuardrailInputs { trades_today: 9, ..CALM_INPUTS };
+        assert!(engine.max_daily_trades(&trade, &one_left).is_none());
+    }
+
+    #[test]
+    fn test_guardrail_blocked_hours_rule() {
+        let normal = TradeGuardrails::default();
+        let breaker = CircuitBreaker::default();
+        let engine = GuardrailEngine { guardrails: &normal, circuit_breaker: &breaker, max_position_pct: 10.0 };
+        let trade = proposed("BUY", 5.0, 1_000.0);
+
+        let near_close = GuardrailInputs { time: NaiveTime::from_hms_opt(15, 50, 0).unwrap(), ..CALM_INPUTS };
+        assert_eq!(rule_of(engine.blocked_hours(&trade, &near_close)).as_deref(), Some("blocked_hours"));
+        assert!(engine.blocked_hours(&trade, &CALM_INPUTS).is_none());
+    }
+
+    #[test]
+    fn test_blocked_hours_use_the_eastern_clock() {
+        let trader = AiTrader::new(AiTraderConfig {
+            trading_mode: "aggressive".to_string(),
+            blocked_hours: "09:30-09:45, junk, 10:00-09:00".to_string(),
+            ..AiTraderConfig::default()
+        });
+        // Only the well-formed window survives; aggressive mode adds none of its own
+        assert_eq!(trader.guardrails.blocked_hours.len(), 1);
+
+        let engine = trader.guardrail_engine();
+        let trade = proposed("BUY", 5.0, 1_000.0);
+        let at = |utc: &str| GuardrailInputs {
+            time: to_eastern(utc.parse::<DateTime<Utc>>().unwrap()).time(),
+            ..CALM_INPUTS
+        };
+
+        // 14:35 UTC is 09:35 EST in January but 10:35 EDT in July
+        assert!(engine.blocked_hours(&trade, &at("2026-01-05T14:35:00Z")).is_some());
+        assert!(engine.blocked_hours(&trade, &at("2026-07-06T14:35:00Z")).is_none());
+        assert!(engine.blocked_hours(&trade, &at("2026-07-06T13:35:00Z")).is_some());
+        assert!(engine.blocked_hours(&trade, &at("2026-01-05T14:50:00Z")).is_none());
+    }
+
+    #[test]
+    fn test_guardrail_confluence_rule() {
+        let normal = TradeGuardrails::default();
+        let breaker = CircuitBreaker::default();
+        let engine = GuardrailEngine { guardrails: &normal, circuit_breaker: &breaker, max_position_pct: 10.0 };
+        let buy = proposed("BUY", 5.0, 1_000.0);
+
+        let weak = GuardrailInputs { confluence_score: Some(0.1), ..CALM_INPUTS };
+        assert_eq!(rule_of(engine.confluence(&buy, &weak)).as_deref(), Some("confluence"));
+        assert!(engine.confluence(&buy, &CALM_INPUTS).is_none());
+        assert!(engine.confluence(&proposed("SELL", 5.0, 1_000.0), &weak).is_none());
+        let unscored = GuardrailInputs { confluence_score: None, ..CALM_INPUTS };
+        assert!(engine.confluence(&buy, &unscored).is_none());
+    }
+
+    #[test]
+    fn test_guardrail_evaluate_reports_first_failing_rule() {
+        let paused = TradeGuardrails::for_mode(TradingMode::Paused);
+        let breaker = CircuitBreaker::default();
+        let engine = GuardrailEngine { guardrails: &paused, circuit_breaker: &breaker, max_position_pct: 0.0 };
+        let rejection = engine.evaluate(&proposed("BUY", 50.0, 1_000_000.0), &CALM_INPUTS).unwrap();
+        assert_eq!(rejection.rule_triggered, "circuit_breaker");
+        assert_eq!(rejection.trading_mode, "paused");
+
+        let normal = TradeGuardrails::default();
+        let engine = GuardrailEngine { guardrails: &normal, circuit_breaker: &breaker, max_position_pct: 10.0 };
+        assert!(engine.evaluate(&proposed("BUY", 5.0, 1_000.0), &CALM_INPUTS).is_none());
+    }
+
+    fn scripted(action: &str, symbol: &str, quantity_percent: f64) -> ParsedDecision {
+        ParsedDecision {
+            action: action.to_string(),
+            symbol: symbol.to_string(),
+            quantity_percent,
+            confidence: 0.8,
+            reasoning: "scripted".to_string(),
+            prediction: None,
+        }
+    }
+
+    /// Serves canned responses; listed `failing` models error instead
+    struct ScriptedProvider {
+        installed: Vec<String>,
+        failing: Vec<String>,
+        response: String,
+    }
+
+    impl DecisionProvider for ScriptedProvider {
+        async fn installed_models(&self) -> Result<Vec<String>> {
+            Ok(self.installed.clone())
+        }
+
+        async fn query_with_thinking(
+            &self,
+            _prompt: &str,
+            _system: &str,
+            model: &str,
+        ) -> Result<(String, Option<String>)> {
+            if self.failing.iter().any(|m| m == model) {
+                anyhow::bail!("model {} crashed", model);
+            }
+            Ok((self.response.clone(), None))
+        }
+    }
+
+    /// Empty per-test log directory, so recorded cycles stay out of the working tree
+    fn test_logs_dir(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("fp_ai_logs_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.to_string_lossy().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_next_installed_model() {
+        let mut db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        db.upsert_daily_prices(&[DailyPrice {
+            symbol: "TEST".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 1, 5).unwrap(),
+            open: 100.0,
+            high: 101.0,
+            low: 99.0,
+            close: 100.0,
+            volume: 1000,
+            adjusted_close: None,
+            source: "test".to_string(),
+        }])
+        .unwrap();
+
+        let config = AiTraderConfig {
+            model_priority: vec!["missing".to_string(), "alpha".to_string(), "beta:7b".to_string()],
+            ..AiTraderConfig::default()
+        };
+        let backend = ScriptedProvider {
+            installed: vec!["alpha:latest".to_string(), "beta:7b".to_string()],
+            failing: vec!["alpha".to_string()],
+            response: r#"{"decisions": [{"action": "HOLD", "symbol": "TEST", "quantity_percent": 0.0,
+                "confidence": 0.5, "reasoning": "wait", "prediction": null}]}"#
+                .to_string(),
+        };
+        let trader = AiTrader::new_with(config.clone(), backend, SystemClock);
+
+        let context = trader.gather_market_context(&db).unwrap();
+        let (model, decisions) = trader.query_models(&context, false).await.unwrap();
+        assert_eq!(model, "beta:7b");
+
+        let recorded = trader
+            .execute_decision(&mut db, None, &decisions[0], &context, &model, false)
+            .await
+            .unwrap();
+        assert_eq!(recorded.model_used, "beta:7b");
+
+        let none_installed = ScriptedProvider {
+            installed: vec!["other".to_string()],
+            failing: vec![],
+            response: String::new(),
+        };
+        let err = AiTrader::new_with(config, none_installed, SystemClock)
+            .query_models(&context, false)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("None of the configured models are installed"));
+    }
+
+    /// Serves canned bars and counts how often it was asked
+    struct StubFetcher {
+        prices: Vec<DailyPrice>,
+        calls: std::cell::Cell<usize>,
+    }
+
+    impl PriceFetcher for StubFetcher {
+        fn fetch_prices(&self, _symbol: &str, _period: &str) -> crate::error::Result<Vec<DailyPrice>> {
+            self.calls.set(self.calls.get() + 1);
+            Ok(self.prices.clone())
+        }
+    }
+
+    #[test]
+    fn test_benchmark_comparison_fetches_missing_benchmark() {
+        let mut db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+
+        // Snapshots are read relative to the real date, so build everything around it
+        let now = Utc::now();
+        let today = now.date_naive();
+        let fetcher = StubFetcher {
+            prices: (0..=5)
+                .map(|i| DailyPrice {
+                    symbol: "SPY".to_string(),
+                    date: today - Duration::days(5 - i),
+                    open: 100.0 + i as f64,
+                    high: 100.0 + i as f64,
+                    low: 100.0 + i as f64,
+                    close: 100.0 + i as f64,
+                    volume: 1000,
+                    adjusted_close: None,
+                    source: "test".to_string(),
+                })
+                .collect(),
+            calls: std::cell::Cell::new(0),
+        };
+        // The first snapshot predates every benchmark bar and can't be compared
+        for (days_ago, value) in [(10, 9000.0), (3, 10000.0), (1, 10500.0)] {
+            db.record_ai_performance_snapshot(&AiPerformanceSnapshot {
+                id: 0,
+                timestamp: (now - Duration::days(days_ago)).format("%Y-%m-%d %H:%M:%S").to_string(),
+                portfolio_value: value,
+                cash: value,
+                positions_value: 0.0,
+                benchmark_value: 0.0,
+                benchmark_symbol: "SPY".to_string(),
+                total_pnl: 0.0,
+                total_pnl_percent: 0.0,
+                benchmark_pnl_percent: 0.0,
+                prediction_accuracy: None,
+                trades_to_date: 0,
+                winning_trades: 0,
+                losing_trades: 0,
+                win_rate: None,
+            })
+            .unwrap();
+        }
+        assert!(db.get_prices("SPY").unwrap().is_empty());
+
+        let provider = ScriptedProvider { installed: vec![], failing: vec![], response: String::new() };
+        let trader = AiTrader::new_with(AiTraderConfig::default(), provider, FixedClock(now));
+        let comparison = trader.get_benchmark_comparison(&mut db, &fetcher).unwrap();
+
+        assert_eq!(fetcher.calls.get(), 1);
+        assert_eq!(db.get_prices("SPY").unwrap().len(), 6);
+        assert_eq!(comparison.tracking_data.len(), 2);
+        assert!((comparison.portfolio_return_percent - 5.0).abs() < 1e-9);
+        assert!((comparison.benchmark_return_percent - 2.0 / 102.0 * 100.0).abs() < 1e-9);
+        assert!((comparison.tracking_data[1].2 - 10000.0 * 104.0 / 102.0).abs() < 1e-9);
+
+        // Now stored and current, so no second fetch
+        trader.get_benchmark_comparison(&mut db, &fetcher).unwrap();
+        assert_eq!(fetcher.calls.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_cycle_with_scripted_buy_creates_paper_trade() {
+        let mut db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        db.upsert_daily_prices(&[DailyPrice {
+            symbol: "TEST".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 1, 5).unwrap(),
+            open: 100.0,
+            high: 101.0,
+            low: 99.0,
+            close: 100.0,
+            volume: 1000,
+            adjusted_close: None,
+            source: "test".to_string(),
+        }])
+        .unwrap();
+
+        let provider = ScriptedProvider {
+            installed: vec!["scripted".to_string()],
+            failing: vec![],
+            response: r#"{"decisions": [{"action": "BUY", "symbol": "TEST", "quantity_percent": 2.0,
+                "confidence": 0.9, "reasoning": "breakout", "prediction": null}]}"#
+                .to_string(),
+        };
+        // Aggressive mode skips the confluence check, which one bar of history can't pass
+        let config = AiTraderConfig {
+            model_priority: vec!["scripted".to_string()],
+            trading_mode: "aggressive".to_string(),
+            ..AiTraderConfig::default()
+        };
+        let clock = FixedClock("2026-01-06T17:00:00Z".parse().unwrap());
+        let logs_dir = test_logs_dir("scripted_buy");
+        let mut trader = AiTrader::new_with(config, provider, clock).with_logs_dir(logs_dir.clone());
+
+        let decisions = trader.run_cycle(&mut db, false).await.unwrap();
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].model_used, "scripted");
+
+        let trades = db.get_paper_trades(Some("TEST"), 10).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].action, PaperTradeAction::Buy);
+
+        // Raw response, decision log and index all land in the configured directory
+        assert!(std::path::Path::new(&logs_dir).join("raw").is_dir());
+        assert!(std::path::Path::new(&logs_dir).join("index.json").is_file());
+        let _ = fs::remove_dir_all(&logs_dir);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_cycle_records_decisions_without_trading() {
+        let mut db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        db.upsert_daily_prices(&[DailyPrice {
+            symbol: "TEST".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 1, 5).unwrap(),
+            open: 100.0,
+            high: 101.0,
+            low: 99.0,
+            close: 100.0,
+            volume: 1000,
+            adjusted_close: None,
+            source: "test".to_string(),
+        }])
+        .unwrap();
+        // Down 15%, which a live cycle's breaker check would trip on
+        db.reset_paper_account(850_000.0).unwrap();
+        let cash_before = db.get_paper_wallet().unwrap().cash;
+        let mode_before = db.get_trading_mode().unwrap();
+
+        let provider = ScriptedProvider {
+            installed: vec!["scripted".to_string()],
+            failing: vec![],
+            response: r#"{"decisions": [{"action": "BUY", "symbol": "TEST", "quantity_percent": 2.0,
+                "confidence": 0.9, "reasoning": "breakout", "prediction": null}]}"#
+                .to_string(),
+        };
+        let config = AiTraderConfig {
+            model_priority: vec!["scripted".to_string()],
+            trading_mode: "aggressive".to_string(),
+            ..AiTraderConfig::default()
+        };
+        let clock = FixedClock("2026-01-06T17:00:00Z".parse().unwrap());
+        let logs_dir = test_logs_dir("dry_run");
+        let mut trader = AiTrader::new_with(config, provider, clock).with_logs_dir(logs_dir.clone());
+
+        let decisions = trader.run_cycle(&mut db, true).await.unwrap();
+        assert_eq!(decisions.len(), 1);
+        assert!(decisions[0].dry_run);
+        assert_eq!(decisions[0].paper_trade_id, None);
+
+        let stored = db.get_ai_decisions(None, Some("TEST"), 10).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert!(stored[0].dry_run);
+        assert!(db.get_paper_trades(Some("TEST"), 10).unwrap().is_empty());
+        assert_eq!(db.get_paper_wallet().unwrap().cash, cash_before);
+
+        // The breaker was only read: no trip, mode switch, event or log files
+        assert_eq!(db.get_circuit_breaker_state().unwrap(), (false, None));
+        assert_eq!(db.get_trading_mode().unwrap(), mode_before);
+        assert_eq!(trader.get_mode(), TradingMode::Aggressive);
+        assert!(db.get_circuit_breaker_events(10).unwrap().is_empty());
+        assert!(!std::path::Path::new(&logs_dir).exists());
+
+        // A running pause still refuses the dry run, and is left as it was
+        let until: DateTime<Utc> = "2026-01-06T17:30:00Z".parse().unwrap();
+        db.set_circuit_breaker_state(true, Some(until)).unwrap();
+        let err = trader.run_cycle(&mut db, true).await.unwrap_err();
+        assert!(err.to_string().contains("Circuit breaker active"));
+        let (triggered, resume_at) = db.get_circuit_breaker_state().unwrap();
+        assert!(triggered);
+        assert_eq!(resume_at.map(|t| t.timestamp()), Some(until.timestamp()));
+        assert!(db.get_circuit_breaker_events(10).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_live_cycle_enforces_daily_trade_limit() {
+        let mut db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        db.upsert_daily_prices(&[DailyPrice {
+            symbol: "TEST".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 1, 5).unwrap(),
+            open: 100.0,
+            high: 101.0,
+            low: 99.0,
+            close: 100.0,
+            volume: 1000,
+            adjusted_close: None,
+            source: "test".to_string(),
+        }])
+        .unwrap();
+        // Aggressive mode allows 20 trades a day
+        for _ in 0..20 {
+            db.execute_paper_trade("TEST", crate::models::PaperTradeAction::Buy, 1.0, 100.0, None, None)
+                .unwrap();
+        }
+
+        let provider = ScriptedProvider {
+            installed: vec!["scripted".to_string()],
+            failing: vec![],
+            response: r#"{"decisions": [{"action": "BUY", "symbol": "TEST", "quantity_percent": 2.0,
+                "confidence": 0.9, "reasoning": "breakout", "prediction": null}]}"#
+                .to_string(),
+        };
+        let config = AiTraderConfig {
+            model_priority: vec!["scripted".to_string()],
+            trading_mode: "aggressive".to_string(),
+            ..AiTraderConfig::default()
+        };
+        let clock = FixedClock("2026-01-06T17:00:00Z".parse().unwrap());
+        let logs_dir = test_logs_dir("daily_limit");
+        let mut trader = AiTrader::new_with(config, provider, clock).with_logs_dir(logs_dir.clone());
+
+        let decisions = trader.run_cycle(&mut db, false).await.unwrap();
+        let _ = fs::remove_dir_all(&logs_dir);
+        assert_eq!(decisions[0].paper_trade_id, None);
+        assert_eq!(db.get_paper_trades(Some("TEST"), 100).unwrap().len(), 20);
+        let rejections = db.get_trade_rejections(10).unwrap();
+        assert_eq!(rejections[0].5, "max_daily_trades");
+    }
+
+    #[tokio::test]
+    async fn test_daily_loss_trips_persisted_circuit_breaker() {
+        let mut db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        db.reset_paper_account(850_000.0).unwrap();
+
+        let mut trader = AiTrader::new(AiTraderConfig::default());
+        let trigger = trader.check_circuit_breaker(&db).unwrap();
+        assert_eq!(trigger, Some(CircuitBreakerTrigger::DailyLossThreshold));
+        assert_eq!(trader.get_mode(), TradingMode::Conservative);
+
+        let (triggered, until) = db.get_circuit_breaker_state().unwrap();
+        assert!(triggered);
+        assert!(until.unwrap() > Utc::now());
+
+        // A fresh trader picks up the pause and refuses to run a cycle
+        let backend = ScriptedProvider { installed: vec![], failing: vec![], response: String::new() };
+        let mut restarted = AiTrader::new_with(db.get_ai_trader_config().unwrap(), backend, SystemClock);
+        let err = restarted.run_cycle(&mut db, false).await.unwrap_err();
+        assert!(err.to_string().contains("Circuit breaker active"));
+
+        // Once the pause has elapsed the breaker clears and the previous mode comes back.
+        // The account is still down 15%, but that loss was already acted on.
+        db.set_circuit_breaker_state(true, Some(Utc::now() - Duration::minutes(1))).unwrap();
+        assert_eq!(restarted.check_circuit_breaker(&db).unwrap(), None);
+        assert!(!restarted.circuit_breaker.triggered);
+        assert_eq!(restarted.get_mode(), TradingMode::Normal);
+        assert_eq!(db.get_circuit_breaker_state().unwrap(), (false, None));
+
+        assert_eq!(restarted.check_circuit_breaker(&db).unwrap(), None);
+        assert_eq!(db.get_circuit_breaker_state().unwrap(), (false, None));
+        assert_eq!(restarted.get_mode(), TradingMode::Normal);
+        assert_eq!(db.get_circuit_breaker_events(10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_losing_streak_trips_circuit_breaker_at_limit() {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        db.execute_paper_trade("TEST", PaperTradeAction::Buy, 40.0, 100.0, None, None).unwrap();
+        for _ in 0..3 {
+            db.execute_paper_trade("TEST", PaperTradeAction::Sell, 10.0, 90.0, None, None).unwrap();
+        }
+        assert_eq!(db.get_consecutive_losses().unwrap(), 3);
+
+        let mut lenient = AiTrader::new(AiTraderConfig { consecutive_loss_limit: 4, ..AiTraderConfig::default() });
+        assert_eq!(lenient.check_circuit_breaker(&db).unwrap(), None);
+
+        let mut strict = AiTrader::new(AiTraderConfig { consecutive_loss_limit: 3, ..AiTraderConfig::default() });
+        assert_eq!(
+            strict.check_circuit_breaker(&db).unwrap(),
+            Some(CircuitBreakerTrigger::ConsecutiveLosses)
+        );
+        let events = db.get_circuit_breaker_events(1).unwrap();
+        assert_eq!(events[0].2, "consecutive_losses");
+
+        // A winning sell ends the streak
+        db.execute_paper_trade("TEST", PaperTradeAction::Sell, 10.0, 120.0, None, None).unwrap();
+        assert_eq!(db.get_consecutive_losses().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_replay_follows_scripted_decisions() {
+        let mut db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        let closes = [100.0, 110.0, 120.0, 90.0, 100.0];
+        let prices: Vec<DailyPrice> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| DailyPrice {
+                symbol: "TEST".to_string(),
+                date: NaiveDate::from_ymd_opt(2026, 1, 5 + i as u32).unwrap(),
+                open: close,
+                high: close + 1.0,
+                low: close - 1.0,
+                close,
+                volume: 1000,
+                adjusted_close: None,
+                source: "test".to_string(),
+            })
+            .collect();
+        db.upsert_daily_prices(&prices).unwrap();
+
+        let trader = AiTrader::new(AiTraderConfig {
+            starting_capital: 10_000.0,
+            require_confluence: false,
+            ..AiTraderConfig::default()
+        });
+        let mut script = ScriptedDecisions::new(vec![
+            vec![scripted("BUY", "TEST", 50.0), scripted("BUY", "OTHER", 50.0)],
+            vec![scripted("HOLD", "TEST", 0.0)],
+            vec![scripted("SELL", "TEST", 100.0)],
+            vec![scripted("BUY", "TEST", 100.0)],
+        ]);
+
+        let result = trader
+            .replay(
+                &mut db,
+                "TEST",
+                NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 1, 31).unwrap(),
+                &mut script,
+            )
+            .await
+            .unwrap();
+
+        // 50 shares @ 100, sold @ 120 (+1000), then 122 shares @ 90 leaving $20 cash
+        let values: Vec<f64> = result.equity_curve.iter().map(|(_, v)| *v).collect();
+        assert_eq!(values, vec![10_000.0, 10_500.0, 11_000.0, 11_000.0, 12_220.0]);
+        assert_eq!(result.final_value, 12_220.0);
+        assert_eq!(result.decisions_count, 4);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].profit_loss, Some(1_000.0));
+        assert_eq!(result.metrics.winning_trades, 1);
+        assert!((result.metrics.total_return - 22.2).abs() < 1e-9);
+
+        // The replay never touches the real paper account
+        assert!(db.get_paper_trades(None, 10).unwrap().is_empty());
+    }
 }
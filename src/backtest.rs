@@ -3,17 +3,29 @@
 //! Simulates trading strategies against historical data
 
 use crate::models::{
-    BacktestResult, BacktestTrade, DailyPrice, PerformanceMetrics, Strategy, StrategyConditionType,
-    TechnicalIndicator, TradeDirection,
+    BacktestResult, BacktestTrade, Condition, DailyPrice, PerformanceMetrics,
+    PortfolioBacktestResult, Strategy, StrategyConditionType, StrategyRule, SymbolContribution,
+    TechnicalIndicator, TradeDirection, WalkForwardResult,
 };
+use crate::error::{PipelineError, Result};
 use chrono::NaiveDate;
-use std::collections::HashMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Upper bound on backtests a single optimization may run
+pub const MAX_OPTIMIZATION_COMBINATIONS: usize = 10_000;
 
 /// Backtest configuration
 #[derive(Debug, Clone)]
 pub struct BacktestConfig {
     pub initial_capital: f64,
     pub commission_per_trade: f64,
+    pub commission_percent: f64, // % of trade notional
+    pub slippage_bps: f64,       // fills are worse than the close by this many basis points
+    pub min_commission: f64,
 }
 
 impl Default for BacktestConfig {
@@ -21,17 +33,363 @@ impl Default for BacktestConfig {
         Self {
             initial_capital: 10000.0,
             commission_per_trade: 0.0,
+            commission_percent: 0.0,
+            slippage_bps: 0.0,
+            min_commission: 0.0,
+        }
+    }
+}
+
+impl BacktestConfig {
+    /// Commission for a fill: the greater of flat, percent-of-notional and minimum
+    pub fn commission(&self, notional: f64) -> f64 {
+        self.commission_per_trade
+            .max(notional.abs() * self.commission_percent / 100.0)
+            .max(self.min_commission)
+    }
+
+    /// Largest notional that, with its own commission added, still fits in `budget`
+    pub fn max_notional(&self, budget: f64) -> f64 {
+        (budget - self.commission_per_trade)
+            .min(budget - self.min_commission)
+            .min(budget / (1.0 + self.commission_percent / 100.0))
+    }
+
+    /// Fill price after slippage (buys fill higher, sells fill lower)
+    pub fn fill_price(&self, price: f64, is_buy: bool) -> f64 {
+        let slippage = self.slippage_bps / 10_000.0;
+        if is_buy {
+            price * (1.0 + slippage)
+        } else {
+            price * (1.0 - slippage)
+        }
+    }
+}
+
+/// Parameter ranges for a strategy sweep. An empty list keeps the base
+/// strategy's value for that parameter.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParamGrid {
+    pub entry_thresholds: Vec<f64>,
+    pub exit_thresholds: Vec<f64>,
+    pub stop_loss_percents: Vec<Option<f64>>,
+    pub take_profit_percents: Vec<Option<f64>>,
+}
+
+impl ParamGrid {
+    /// Number of backtests this grid expands to
+    pub fn combinations(&self) -> usize {
+        [
+            self.entry_thresholds.len(),
+            self.exit_thresholds.len(),
+            self.stop_loss_percents.len(),
+            self.take_profit_percents.len(),
+        ]
+        .iter()
+        .map(|&n| n.max(1))
+        .product()
+    }
+
+    /// Expand the grid into concrete strategies derived from `base`
+    fn expand(&self, base: &Strategy) -> Vec<Strategy> {
+        fn or_base<T: Copy>(values: &[T], base: T) -> Vec<T> {
+            if values.is_empty() {
+                vec![base]
+            } else {
+                values.to_vec()
+            }
+        }
+
+        let entries = or_base(&self.entry_thresholds, base.entry_threshold);
+        let exits = or_base(&self.exit_thresholds, base.exit_threshold);
+        let stops = or_base(&self.stop_loss_percents, base.stop_loss_percent);
+        let targets = or_base(&self.take_profit_percents, base.take_profit_percent);
+
+        let mut strategies = Vec::with_capacity(self.combinations());
+        for &entry_threshold in &entries {
+            for &exit_threshold in &exits {
+                for &stop_loss_percent in &stops {
+                    for &take_profit_percent in &targets {
+                        strategies.push(Strategy {
+                            entry_threshold,
+                            exit_threshold,
+                            stop_loss_percent,
+                            take_profit_percent,
+                            ..base.clone()
+                        });
+                    }
+                }
+            }
         }
+        strategies
     }
 }
 
+/// Metric used to rank optimization runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OptimizationObjective {
+    TotalReturn,
+    SharpeRatio,
+    ProfitFactor,
+}
+
+impl OptimizationObjective {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OptimizationObjective::TotalReturn => "total_return",
+            OptimizationObjective::SharpeRatio => "sharpe",
+            OptimizationObjective::ProfitFactor => "profit_factor",
+        }
+    }
+
+    /// Score a run; higher is better
+    pub fn score(&self, metrics: &PerformanceMetrics) -> f64 {
+        match self {
+            OptimizationObjective::TotalReturn => metrics.total_return,
+            OptimizationObjective::SharpeRatio => metrics.sharpe_ratio,
+            OptimizationObjective::ProfitFactor => metrics.profit_factor,
+        }
+    }
+}
+
+impl std::str::FromStr for OptimizationObjective {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "total_return" => Ok(OptimizationObjective::TotalReturn),
+            "sharpe" | "sharpe_ratio" => Ok(OptimizationObjective::SharpeRatio),
+            "profit_factor" => Ok(OptimizationObjective::ProfitFactor),
+            _ => Err(format!(
+                "Invalid objective '{}'. Use 'total_return', 'sharpe', or 'profit_factor'",
+                s
+            )),
+        }
+    }
+}
+
+/// One parameter combination from an optimization run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptimizationResult {
+    pub strategy: Strategy,
+    pub metrics: PerformanceMetrics,
+    pub final_capital: f64,
+    pub score: f64,
+}
+
+/// Equity falling to this fraction of starting capital counts as ruin in Monte Carlo runs
+pub const MONTE_CARLO_RUIN_FRACTION: f64 = 0.5;
+
+/// 5th, 50th and 95th percentile of a simulated distribution
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Percentiles {
+    pub p5: f64,
+    pub p50: f64,
+    pub p95: f64,
+}
+
+impl Percentiles {
+    /// Nearest-rank percentiles of `values` (all zero when empty)
+    fn of(mut values: Vec<f64>) -> Self {
+        if values.is_empty() {
+            return Self { p5: 0.0, p50: 0.0, p95: 0.0 };
+        }
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let at = |q: f64| values[((values.len() - 1) as f64 * q).round() as usize];
+        Self {
+            p5: at(0.05),
+            p50: at(0.50),
+            p95: at(0.95),
+        }
+    }
+}
+
+/// Distribution of outcomes from resampling a backtest's trades
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonteCarloResult {
+    pub runs: usize,
+    pub trades_per_run: usize,
+    pub initial_capital: f64,
+    pub final_equity: Percentiles,
+    /// Percent drawdown from peak, as in `PerformanceMetrics::max_drawdown`
+    pub max_drawdown: Percentiles,
+    /// Share of runs (0-1) whose equity fell to `MONTE_CARLO_RUIN_FRACTION` of the start
+    pub probability_of_ruin: f64,
+}
+
 /// Open position during backtest
 #[derive(Debug, Clone)]
 struct OpenPosition {
+    direction: TradeDirection,
     entry_date: NaiveDate,
     entry_price: f64,
     shares: f64,
+    entry_commission: f64,
     entry_reason: String,
+    extreme_price: f64, // highest close since entry for longs, lowest for shorts
+}
+
+impl OpenPosition {
+    /// Ratchet the high-water mark (longs) or low-water mark (shorts) with a new close
+    fn track_extreme(&mut self, price: f64) {
+        self.extreme_price = match self.direction {
+            TradeDirection::Long => self.extreme_price.max(price),
+            TradeDirection::Short => self.extreme_price.min(price),
+        };
+    }
+
+    /// Whether closing this position is a buy (covering a short)
+    fn exit_is_buy(&self) -> bool {
+        self.direction == TradeDirection::Short
+    }
+
+    /// Gross profit/loss if closed at `price`
+    fn profit_loss(&self, price: f64) -> f64 {
+        match self.direction {
+            TradeDirection::Long => (price - self.entry_price) * self.shares,
+            TradeDirection::Short => (self.entry_price - price) * self.shares,
+        }
+    }
+
+    /// Profit/loss as a percentage of the entry price
+    fn profit_loss_percent(&self, price: f64) -> f64 {
+        match self.direction {
+            TradeDirection::Long => (price - self.entry_price) / self.entry_price * 100.0,
+            TradeDirection::Short => (self.entry_price - price) / self.entry_price * 100.0,
+        }
+    }
+
+    /// Cash returned when closing at `price` (shorts get back their reserved
+    /// collateral plus or minus the move)
+    fn market_value(&self, price: f64) -> f64 {
+        match self.direction {
+            TradeDirection::Long => self.shares * price,
+            TradeDirection::Short => self.shares * self.entry_price + self.profit_loss(price),
+        }
+    }
+}
+
+/// Percent drawdown from the running peak at each point (peak starts at `initial`)
+fn drawdown_series(initial: f64, equity_history: &[f64]) -> Vec<f64> {
+    let mut peak = initial;
+    equity_history
+        .iter()
+        .map(|&equity| {
+            if equity > peak {
+                peak = equity;
+            }
+            (peak - equity) / peak * 100.0
+        })
+        .collect()
+}
+
+/// Check whether a single strategy condition holds on a bar.
+/// Stop-loss and take-profit are position exits, never rule conditions.
+pub fn condition_met(
+    condition: &Condition,
+    price: f64,
+    today: &HashMap<String, f64>,
+    prev: Option<&HashMap<String, f64>>,
+) -> bool {
+    match condition.condition {
+        StrategyConditionType::RsiOversold => {
+            today.get("RSI_14").map_or(false, |&rsi| rsi < condition.threshold)
+        }
+        StrategyConditionType::RsiOverbought => {
+            today.get("RSI_14").map_or(false, |&rsi| rsi > condition.threshold)
+        }
+        StrategyConditionType::MacdCrossUp => {
+            if let (Some(prev_ind), Some(macd), Some(signal)) = (
+                prev,
+                today.get("MACD_12_26"),
+                today.get("MACD_SIGNAL_12_26_9"),
+            ) {
+                if let (Some(&prev_macd), Some(&prev_signal)) = (
+                    prev_ind.get("MACD_12_26"),
+                    prev_ind.get("MACD_SIGNAL_12_26_9"),
+                ) {
+                    prev_macd <= prev_signal && *macd > *signal
+                } else {
+                    false
+                }
+            } else {
+                false
+            }
+        }
+        StrategyConditionType::MacdCrossDown => {
+            if let (Some(prev_ind), Some(macd), Some(signal)) = (
+                prev,
+                today.get("MACD_12_26"),
+                today.get("MACD_SIGNAL_12_26_9"),
+            ) {
+                if let (Some(&prev_macd), Some(&prev_signal)) = (
+                    prev_ind.get("MACD_12_26"),
+                    prev_ind.get("MACD_SIGNAL_12_26_9"),
+                ) {
+                    prev_macd >= prev_signal && *macd < *signal
+                } else {
+                    false
+                }
+            } else {
+                false
+            }
+        }
+        StrategyConditionType::PriceAboveSma => {
+            today.get("SMA_20").map_or(false, |&sma| price > sma)
+        }
+        StrategyConditionType::PriceBelowSma => {
+            today.get("SMA_20").map_or(false, |&sma| price < sma)
+        }
+        StrategyConditionType::SmaCrossUp => {
+            if let (Some(prev_ind), Some(&fast), Some(&slow)) =
+                (prev, today.get("SMA_20"), today.get("SMA_50"))
+            {
+                if let (Some(&prev_fast), Some(&prev_slow)) =
+                    (prev_ind.get("SMA_20"), prev_ind.get("SMA_50"))
+                {
+                    prev_fast <= prev_slow && fast > slow
+                } else {
+                    false
+                }
+            } else {
+                false
+            }
+        }
+        StrategyConditionType::SmaCrossDown => {
+            if let (Some(prev_ind), Some(&fast), Some(&slow)) =
+                (prev, today.get("SMA_20"), today.get("SMA_50"))
+            {
+                if let (Some(&prev_fast), Some(&prev_slow)) =
+                    (prev_ind.get("SMA_20"), prev_ind.get("SMA_50"))
+                {
+                    prev_fast >= prev_slow && fast < slow
+                } else {
+                    false
+                }
+            } else {
+                false
+            }
+        }
+        // StopLoss and TakeProfit are exit-only conditions
+        StrategyConditionType::StopLoss | StrategyConditionType::TakeProfit => false,
+    }
+}
+
+/// Evaluate a composite rule on a bar. Empty rules never trigger.
+pub fn rule_met(
+    rule: &StrategyRule,
+    price: f64,
+    today: &HashMap<String, f64>,
+    prev: Option<&HashMap<String, f64>>,
+) -> bool {
+    let conditions = rule.conditions();
+    if conditions.is_empty() {
+        return false;
+    }
+    match rule {
+        StrategyRule::All(_) => conditions.iter().all(|c| condition_met(c, price, today, prev)),
+        StrategyRule::Any(_) => conditions.iter().any(|c| condition_met(c, price, today, prev)),
+    }
 }
 
 /// Main backtesting engine
@@ -66,7 +424,7 @@ impl BacktestEngine {
         map
     }
 
-    /// Check if entry condition is met
+    /// Check if entry rule is met
     fn check_entry_condition(
         &self,
         strategy: &Strategy,
@@ -74,88 +432,7 @@ impl BacktestEngine {
         today: &HashMap<String, f64>,
         prev: Option<&HashMap<String, f64>>,
     ) -> bool {
-        match strategy.entry_condition {
-            StrategyConditionType::RsiOversold => {
-                today.get("RSI_14").map_or(false, |&rsi| rsi < strategy.entry_threshold)
-            }
-            StrategyConditionType::RsiOverbought => {
-                today.get("RSI_14").map_or(false, |&rsi| rsi > strategy.entry_threshold)
-            }
-            StrategyConditionType::MacdCrossUp => {
-                if let (Some(prev_ind), Some(macd), Some(signal)) = (
-                    prev,
-                    today.get("MACD_12_26"),
-                    today.get("MACD_SIGNAL_9"),
-                ) {
-                    if let (Some(&prev_macd), Some(&prev_signal)) = (
-                        prev_ind.get("MACD_12_26"),
-                        prev_ind.get("MACD_SIGNAL_9"),
-                    ) {
-                        prev_macd <= prev_signal && *macd > *signal
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
-            }
-            StrategyConditionType::MacdCrossDown => {
-                if let (Some(prev_ind), Some(macd), Some(signal)) = (
-                    prev,
-                    today.get("MACD_12_26"),
-                    today.get("MACD_SIGNAL_9"),
-                ) {
-                    if let (Some(&prev_macd), Some(&prev_signal)) = (
-                        prev_ind.get("MACD_12_26"),
-                        prev_ind.get("MACD_SIGNAL_9"),
-                    ) {
-                        prev_macd >= prev_signal && *macd < *signal
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
-            }
-            StrategyConditionType::PriceAboveSma => {
-                today.get("SMA_20").map_or(false, |&sma| price > sma)
-            }
-            StrategyConditionType::PriceBelowSma => {
-                today.get("SMA_20").map_or(false, |&sma| price < sma)
-            }
-            StrategyConditionType::SmaCrossUp => {
-                if let (Some(prev_ind), Some(&fast), Some(&slow)) =
-                    (prev, today.get("SMA_20"), today.get("SMA_50"))
-                {
-                    if let (Some(&prev_fast), Some(&prev_slow)) =
-                        (prev_ind.get("SMA_20"), prev_ind.get("SMA_50"))
-                    {
-                        prev_fast <= prev_slow && fast > slow
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
-            }
-            StrategyConditionType::SmaCrossDown => {
-                if let (Some(prev_ind), Some(&fast), Some(&slow)) =
-                    (prev, today.get("SMA_20"), today.get("SMA_50"))
-                {
-                    if let (Some(&prev_fast), Some(&prev_slow)) =
-                        (prev_ind.get("SMA_20"), prev_ind.get("SMA_50"))
-                    {
-                        prev_fast >= prev_slow && fast < slow
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
-            }
-            // StopLoss and TakeProfit are exit-only conditions
-            StrategyConditionType::StopLoss | StrategyConditionType::TakeProfit => false,
-        }
+        rule_met(&strategy.resolved_entry_rule(), price, today, prev)
     }
 
     /// Check if exit condition is met
@@ -163,111 +440,67 @@ impl BacktestEngine {
         &self,
         strategy: &Strategy,
         price: f64,
-        entry_price: f64,
+        position: &OpenPosition,
         today: &HashMap<String, f64>,
         prev: Option<&HashMap<String, f64>>,
     ) -> (bool, String) {
-        // Check stop loss
-        if let Some(stop_loss_pct) = strategy.stop_loss_percent {
-            let stop_price = entry_price * (1.0 - stop_loss_pct / 100.0);
-            if price <= stop_price {
-                return (true, "stop_loss".to_string());
+        let is_short = strategy.direction == TradeDirection::Short;
+        let entry_price = position.entry_price;
+
+        // Stop levels: fixed from entry, trailing from the best close since entry.
+        // Whichever sits closer to the market is the one that applies.
+        let fixed_stop = strategy.stop_loss_percent.map(|pct| {
+            let level = if is_short {
+                entry_price * (1.0 + pct / 100.0)
+            } else {
+                entry_price * (1.0 - pct / 100.0)
+            };
+            (level, "stop_loss")
+        });
+        let trailing_stop = strategy.trailing_stop_percent.map(|pct| {
+            let level = if is_short {
+                position.extreme_price * (1.0 + pct / 100.0)
+            } else {
+                position.extreme_price * (1.0 - pct / 100.0)
+            };
+            (level, "trailing_stop")
+        });
+        let stop = match (fixed_stop, trailing_stop) {
+            (Some(fixed), Some(trailing)) => {
+                let trailing_tighter = if is_short {
+                    trailing.0 < fixed.0
+                } else {
+                    trailing.0 > fixed.0
+                };
+                Some(if trailing_tighter { trailing } else { fixed })
+            }
+            (fixed, trailing) => fixed.or(trailing),
+        };
+
+        // Check stop (below the level for longs, above it for shorts)
+        if let Some((level, reason)) = stop {
+            let hit = if is_short { price >= level } else { price <= level };
+            if hit {
+                return (true, reason.to_string());
             }
         }
 
-        // Check take profit
+        // Check take profit (above entry for longs, below entry for shorts)
         if let Some(take_profit_pct) = strategy.take_profit_percent {
-            let target_price = entry_price * (1.0 + take_profit_pct / 100.0);
-            if price >= target_price {
+            let hit = if is_short {
+                price <= entry_price * (1.0 - take_profit_pct / 100.0)
+            } else {
+                price >= entry_price * (1.0 + take_profit_pct / 100.0)
+            };
+            if hit {
                 return (true, "take_profit".to_string());
             }
         }
 
-        // Check strategy exit condition
-        let condition_met = match strategy.exit_condition {
-            StrategyConditionType::RsiOversold => {
-                today.get("RSI_14").map_or(false, |&rsi| rsi < strategy.exit_threshold)
-            }
-            StrategyConditionType::RsiOverbought => {
-                today.get("RSI_14").map_or(false, |&rsi| rsi > strategy.exit_threshold)
-            }
-            StrategyConditionType::MacdCrossUp => {
-                if let (Some(prev_ind), Some(macd), Some(signal)) = (
-                    prev,
-                    today.get("MACD_12_26"),
-                    today.get("MACD_SIGNAL_9"),
-                ) {
-                    if let (Some(&prev_macd), Some(&prev_signal)) = (
-                        prev_ind.get("MACD_12_26"),
-                        prev_ind.get("MACD_SIGNAL_9"),
-                    ) {
-                        prev_macd <= prev_signal && *macd > *signal
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
-            }
-            StrategyConditionType::MacdCrossDown => {
-                if let (Some(prev_ind), Some(macd), Some(signal)) = (
-                    prev,
-                    today.get("MACD_12_26"),
-                    today.get("MACD_SIGNAL_9"),
-                ) {
-                    if let (Some(&prev_macd), Some(&prev_signal)) = (
-                        prev_ind.get("MACD_12_26"),
-                        prev_ind.get("MACD_SIGNAL_9"),
-                    ) {
-                        prev_macd >= prev_signal && *macd < *signal
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
-            }
-            StrategyConditionType::PriceAboveSma => {
-                today.get("SMA_20").map_or(false, |&sma| price > sma)
-            }
-            StrategyConditionType::PriceBelowSma => {
-                today.get("SMA_20").map_or(false, |&sma| price < sma)
-            }
-            StrategyConditionType::SmaCrossUp => {
-                if let (Some(prev_ind), Some(&fast), Some(&slow)) =
-                    (prev, today.get("SMA_20"), today.get("SMA_50"))
-                {
-                    if let (Some(&prev_fast), Some(&prev_slow)) =
-                        (prev_ind.get("SMA_20"), prev_ind.get("SMA_50"))
-                    {
-                        prev_fast <= prev_slow && fast > slow
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
-            }
-            StrategyConditionType::SmaCrossDown => {
-                if let (Some(prev_ind), Some(&fast), Some(&slow)) =
-                    (prev, today.get("SMA_20"), today.get("SMA_50"))
-                {
-                    if let (Some(&prev_fast), Some(&prev_slow)) =
-                        (prev_ind.get("SMA_20"), prev_ind.get("SMA_50"))
-                    {
-                        prev_fast >= prev_slow && fast < slow
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
-            }
-            StrategyConditionType::StopLoss | StrategyConditionType::TakeProfit => false,
-        };
-
-        if condition_met {
-            (true, strategy.exit_condition.as_str().to_string())
+        // Check strategy exit rule
+        let rule = strategy.resolved_exit_rule();
+        if rule_met(&rule, price, today, prev) {
+            (true, rule.describe())
         } else {
             (false, String::new())
         }
@@ -306,12 +539,16 @@ impl BacktestEngine {
 
             // Calculate current equity
             let current_equity = if let Some(ref pos) = position {
-                cash + pos.shares * price
+                cash + pos.market_value(price)
             } else {
                 cash
             };
             equity_history.push(current_equity);
 
+            if let Some(ref mut pos) = position {
+                pos.track_extreme(price);
+            }
+
             // Skip if no indicators for today
             let Some(today) = today_indicators else {
                 continue;
@@ -320,52 +557,24 @@ impl BacktestEngine {
             // If we have a position, check exit conditions
             if let Some(ref pos) = position {
                 let (should_exit, exit_reason) =
-                    self.check_exit_condition(strategy, price, pos.entry_price, today, prev_indicators);
+                    self.check_exit_condition(strategy, price, pos, today, prev_indicators);
 
                 if should_exit {
-                    // Close position
-                    let profit_loss = (price - pos.entry_price) * pos.shares - self.config.commission_per_trade;
-                    let profit_loss_percent = (price - pos.entry_price) / pos.entry_price * 100.0;
-
-                    cash += pos.shares * price - self.config.commission_per_trade;
-
-                    trades.push(BacktestTrade {
-                        id: 0,
-                        backtest_id: 0,
-                        symbol: symbol.to_string(),
-                        direction: TradeDirection::Long,
-                        entry_date: pos.entry_date,
-                        entry_price: pos.entry_price,
-                        exit_date: Some(date),
-                        exit_price: Some(price),
-                        shares: pos.shares,
-                        entry_reason: pos.entry_reason.clone(),
-                        exit_reason: Some(exit_reason),
-                        profit_loss: Some(profit_loss),
-                        profit_loss_percent: Some(profit_loss_percent),
-                    });
-
-                    position = None;
+                    if let Some(pos) = position.take() {
+                        let (proceeds, trade) =
+                            self.close_position(pos, symbol, date, price, exit_reason);
+                        cash += proceeds;
+                        trades.push(trade);
+                    }
                 }
             }
 
             // If no position, check entry conditions
-            if position.is_none() {
-                if self.check_entry_condition(strategy, price, today, prev_indicators) {
-                    // Open position
-                    let position_value = cash * (strategy.position_size_percent / 100.0);
-                    let shares = (position_value - self.config.commission_per_trade) / price;
-
-                    if shares > 0.0 {
-                        cash -= shares * price + self.config.commission_per_trade;
-
-                        position = Some(OpenPosition {
-                            entry_date: date,
-                            entry_price: price,
-                            shares,
-                            entry_reason: strategy.entry_condition.as_str().to_string(),
-                        });
-                    }
+            if position.is_none() && self.check_entry_condition(strategy, price, today, prev_indicators) {
+                let budget = cash * (strategy.position_size_percent / 100.0);
+                if let Some((cost, pos)) = self.open_position(strategy, date, price, budget) {
+                    cash -= cost;
+                    position = Some(pos);
                 }
             }
         }
@@ -373,33 +582,36 @@ impl BacktestEngine {
         // Close any remaining position at end
         if let Some(pos) = position {
             if let Some(last_price) = sorted_prices.last() {
-                let profit_loss =
-                    (last_price.close - pos.entry_price) * pos.shares - self.config.commission_per_trade;
-                let profit_loss_percent =
-                    (last_price.close - pos.entry_price) / pos.entry_price * 100.0;
-
-                cash += pos.shares * last_price.close;
-
-                trades.push(BacktestTrade {
-                    id: 0,
-                    backtest_id: 0,
-                    symbol: symbol.to_string(),
-                    direction: TradeDirection::Long,
-                    entry_date: pos.entry_date,
-                    entry_price: pos.entry_price,
-                    exit_date: Some(last_price.date),
-                    exit_price: Some(last_price.close),
-                    shares: pos.shares,
-                    entry_reason: pos.entry_reason,
-                    exit_reason: Some("end_of_data".to_string()),
-                    profit_loss: Some(profit_loss),
-                    profit_loss_percent: Some(profit_loss_percent),
-                });
+                let (proceeds, trade) = self.close_position(
+                    pos,
+                    symbol,
+                    last_price.date,
+                    last_price.close,
+                    "end_of_data".to_string(),
+                );
+                cash += proceeds;
+                trades.push(trade);
             }
         }
+        // The last bar's mark includes the closing costs of anything still open
+        if let Some(last) = equity_history.last_mut() {
+            *last = cash;
+        }
 
         // Calculate metrics
-        let metrics = self.calculate_metrics(&trades, &equity_history);
+        let metrics = calculate_metrics(self.config.initial_capital, &trades, &equity_history);
+
+        let drawdowns = drawdown_series(self.config.initial_capital, &equity_history);
+        let equity_curve = sorted_prices
+            .iter()
+            .zip(&equity_history)
+            .map(|(p, &equity)| (p.date, equity))
+            .collect();
+        let drawdown_curve = sorted_prices
+            .iter()
+            .zip(drawdowns)
+            .map(|(p, drawdown)| (p.date, drawdown))
+            .collect();
 
         let start_date = sorted_prices.first().map(|p| p.date).unwrap_or_else(|| {
             NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()
@@ -419,145 +631,974 @@ impl BacktestEngine {
             final_capital: cash,
             metrics,
             trades,
+            equity_curve,
+            drawdown_curve,
             created_at: String::new(),
         }
     }
 
-    /// Calculate performance metrics
-    fn calculate_metrics(&self, trades: &[BacktestTrade], equity_history: &[f64]) -> PerformanceMetrics {
-        let initial = self.config.initial_capital;
-        let final_equity = *equity_history.last().unwrap_or(&initial);
+    /// Run the strategy on an in-sample window and a held-out out-of-sample window.
+    ///
+    /// Prices are split chronologically: the first `train_ratio` of bars form the
+    /// in-sample window and the rest the out-of-sample window. Each window starts
+    /// with fresh capital, so positions never carry across the split.
+    pub fn run_walk_forward(
+        &self,
+        strategy: &Strategy,
+        symbol: &str,
+        prices: &[DailyPrice],
+        indicators: &[TechnicalIndicator],
+        train_ratio: f64,
+    ) -> WalkForwardResult {
+        let mut sorted_prices = prices.to_vec();
+        sorted_prices.sort_by_key(|p| p.date);
+
+        // Keep at least one bar on each side when there are two or more
+        let n = sorted_prices.len();
+        let split_index = if n >= 2 {
+            ((n as f64 * train_ratio.clamp(0.0, 1.0)).round() as usize).clamp(1, n - 1)
+        } else {
+            n
+        };
+        let (train, test) = sorted_prices.split_at(split_index);
 
-        let total_return_dollars = final_equity - initial;
-        let total_return = (total_return_dollars / initial) * 100.0;
+        let in_sample = self.run(strategy, symbol, train, indicators);
+        let out_of_sample = self.run(strategy, symbol, test, indicators);
 
-        // Max drawdown
-        let mut max_drawdown = 0.0;
-        let mut peak = initial;
-        for &equity in equity_history {
-            if equity > peak {
-                peak = equity;
+        let degradation_ratio = if in_sample.metrics.total_return.abs() > f64::EPSILON {
+            out_of_sample.metrics.total_return / in_sample.metrics.total_return
+        } else {
+            0.0
+        };
+
+        WalkForwardResult {
+            strategy_id: strategy.id,
+            strategy_name: strategy.name.clone(),
+            symbol: symbol.to_string(),
+            train_ratio,
+            split_date: out_of_sample.start_date,
+            in_sample,
+            out_of_sample,
+            degradation_ratio,
+        }
+    }
+
+    /// Backtest every combination in `grid` against one symbol and return them
+    /// ranked by `objective`, best first. Callers take the top N they need.
+    ///
+    /// Grids larger than `MAX_OPTIMIZATION_COMBINATIONS` are rejected rather than
+    /// truncated so results are never silently partial.
+    pub fn optimize(
+        &self,
+        base_strategy: &Strategy,
+        symbol: &str,
+        prices: &[DailyPrice],
+        indicators: &[TechnicalIndicator],
+        grid: &ParamGrid,
+        objective: OptimizationObjective,
+    ) -> Result<Vec<OptimizationResult>> {
+        let combinations = grid.combinations();
+        if combinations > MAX_OPTIMIZATION_COMBINATIONS {
+            return Err(PipelineError::Config(format!(
+                "Parameter grid has {} combinations (max {})",
+                combinations, MAX_OPTIMIZATION_COMBINATIONS
+            )));
+        }
+
+        let mut results: Vec<OptimizationResult> = grid
+            .expand(base_strategy)
+            .into_par_iter()
+            .map(|strategy| {
+                let result = self.run(&strategy, symbol, prices, indicators);
+                OptimizationResult {
+                    score: objective.score(&result.metrics),
+                    metrics: result.metrics,
+                    final_capital: result.final_capital,
+                    strategy,
+                }
+            })
+            .collect();
+
+        // NaN scores sort last
+        results.sort_by(|a, b| {
+            let a = if a.score.is_nan() { f64::NEG_INFINITY } else { a.score };
+            let b = if b.score.is_nan() { f64::NEG_INFINITY } else { b.score };
+            b.total_cmp(&a)
+        });
+
+        Ok(results)
+    }
+
+    /// Open a position with up to `budget` of capital. Returns the cash consumed
+    /// (shorts reserve the same notional as collateral) and the new position.
+    fn open_position(
+        &self,
+        strategy: &Strategy,
+        date: NaiveDate,
+        price: f64,
+        budget: f64,
+    ) -> Option<(f64, OpenPosition)> {
+        let is_buy = strategy.direction == TradeDirection::Long;
+        let entry_price = self.config.fill_price(price, is_buy);
+        let shares = self.config.max_notional(budget) / entry_price;
+        let entry_commission = self.config.commission(shares * entry_price);
+
+        if shares <= 0.0 {
+            return None;
+        }
+
+        Some((
+            shares * entry_price + entry_commission,
+            OpenPosition {
+                direction: strategy.direction,
+                entry_date: date,
+                entry_price,
+                shares,
+                entry_commission,
+                entry_reason: strategy.resolved_entry_rule().describe(),
+                extreme_price: price,
+            },
+        ))
+    }
+
+    /// Close a position at `price`. Returns the cash released and the completed trade.
+    fn close_position(
+        &self,
+        pos: OpenPosition,
+        symbol: &str,
+        date: NaiveDate,
+        price: f64,
+        exit_reason: String,
+    ) -> (f64, BacktestTrade) {
+        let exit_price = self.config.fill_price(price, pos.exit_is_buy());
+        let exit_commission = self.config.commission(pos.shares * exit_price);
+        let profit_loss = pos.profit_loss(exit_price) - pos.entry_commission - exit_commission;
+        let profit_loss_percent = pos.profit_loss_percent(exit_price);
+        let proceeds = pos.market_value(exit_price) - exit_commission;
+
+        let trade = BacktestTrade {
+            id: 0,
+            backtest_id: 0,
+            symbol: symbol.to_string(),
+            direction: pos.direction,
+            entry_date: pos.entry_date,
+            entry_price: pos.entry_price,
+            exit_date: Some(date),
+            exit_price: Some(exit_price),
+            shares: pos.shares,
+            entry_reason: pos.entry_reason,
+            exit_reason: Some(exit_reason),
+            profit_loss: Some(profit_loss),
+            profit_loss_percent: Some(profit_loss_percent),
+        };
+
+        (proceeds, trade)
+    }
+
+    /// Run a strategy across several symbols sharing one capital pool.
+    ///
+    /// Steps through the union of all symbols' dates. Each entry is sized at
+    /// `position_size_percent` of current equity but never more than the cash
+    /// left, so positions opened on the same day can't spend the same dollars.
+    /// Exits are processed before entries so freed cash is available that day.
+    pub fn run_portfolio(
+        &self,
+        strategy: &Strategy,
+        symbols: &[&str],
+        prices_map: &HashMap<String, Vec<DailyPrice>>,
+        indicators_map: &HashMap<String, Vec<TechnicalIndicator>>,
+    ) -> PortfolioBacktestResult {
+        // Per-symbol close and indicator lookups by date
+        let mut closes: HashMap<&str, HashMap<NaiveDate, f64>> = HashMap::new();
+        let mut indicator_maps: HashMap<&str, HashMap<NaiveDate, HashMap<String, f64>>> =
+            HashMap::new();
+        let mut dates: BTreeSet<NaiveDate> = BTreeSet::new();
+
+        for &symbol in symbols {
+            let by_date: HashMap<NaiveDate, f64> = prices_map
+                .get(symbol)
+                .map(|prices| prices.iter().map(|p| (p.date, p.close)).collect())
+                .unwrap_or_default();
+            dates.extend(by_date.keys().copied());
+            closes.insert(symbol, by_date);
+
+            let indicators = indicators_map.get(symbol).map(Vec::as_slice).unwrap_or(&[]);
+            indicator_maps.insert(symbol, self.build_indicator_map(indicators));
+        }
+
+        let mut cash = self.config.initial_capital;
+        let mut positions: HashMap<&str, OpenPosition> = HashMap::new();
+        let mut last_close: HashMap<&str, (NaiveDate, f64)> = HashMap::new();
+        let mut prev_date: HashMap<&str, NaiveDate> = HashMap::new();
+        let mut trades: Vec<BacktestTrade> = Vec::new();
+        let mut equity_history: Vec<f64> = Vec::new();
+
+        for &date in &dates {
+            // Symbols with a bar today
+            let trading: Vec<(&str, f64)> = symbols
+                .iter()
+                .filter_map(|&sym| closes[sym].get(&date).map(|&close| (sym, close)))
+                .collect();
+
+            for &(sym, close) in &trading {
+                last_close.insert(sym, (date, close));
+                if let Some(pos) = positions.get_mut(sym) {
+                    pos.track_extreme(close);
+                }
             }
-            let drawdown = (peak - equity) / peak * 100.0;
-            if drawdown > max_drawdown {
-                max_drawdown = drawdown;
+
+            // Mark to market with each symbol's most recent close
+            let equity = cash
+                + positions
+                    .iter()
+                    .map(|(sym, pos)| pos.market_value(last_close[sym].1))
+                    .sum::<f64>();
+            equity_history.push(equity);
+
+            // Exits first
+            for &(sym, close) in &trading {
+                let Some(today) = indicator_maps[sym].get(&date) else {
+                    continue;
+                };
+                let prev = prev_date.get(sym).and_then(|d| indicator_maps[sym].get(d));
+
+                let should_exit = positions
+                    .get(sym)
+                    .map(|pos| self.check_exit_condition(strategy, close, pos, today, prev));
+                if let Some((true, exit_reason)) = should_exit {
+                    if let Some(pos) = positions.remove(sym) {
+                        let (proceeds, trade) = self.close_position(pos, sym, date, close, exit_reason);
+                        cash += proceeds;
+                        trades.push(trade);
+                    }
+                }
+            }
+
+            // Then entries, drawing down the shared cash pool
+            for &(sym, close) in &trading {
+                if positions.contains_key(sym) {
+                    continue;
+                }
+                let Some(today) = indicator_maps[sym].get(&date) else {
+                    continue;
+                };
+                let prev = prev_date.get(sym).and_then(|d| indicator_maps[sym].get(d));
+
+                if self.check_entry_condition(strategy, close, today, prev) {
+                    let budget = (equity * strategy.position_size_percent / 100.0).min(cash);
+                    if budget <= 0.0 {
+                        continue;
+                    }
+                    if let Some((cost, pos)) = self.open_position(strategy, date, close, budget) {
+                        cash -= cost;
+                        positions.insert(sym, pos);
+                    }
+                }
+            }
+
+            for &(sym, _) in &trading {
+                prev_date.insert(sym, date);
             }
         }
 
-        // Trade statistics
-        let winning_trades: Vec<_> = trades
-            .iter()
-            .filter(|t| t.profit_loss.unwrap_or(0.0) > 0.0)
-            .collect();
-        let losing_trades: Vec<_> = trades
+        // Close anything still open at each symbol's last close
+        for &symbol in symbols {
+            if let Some(pos) = positions.remove(symbol) {
+                let (date, close) = last_close[symbol];
+                let (proceeds, trade) =
+                    self.close_position(pos, symbol, date, close, "end_of_data".to_string());
+                cash += proceeds;
+                trades.push(trade);
+            }
+        }
+        if let Some(last) = equity_history.last_mut() {
+            *last = cash;
+        }
+
+        let metrics = calculate_metrics(self.config.initial_capital, &trades, &equity_history);
+
+        let contributions = symbols
             .iter()
-            .filter(|t| t.profit_loss.unwrap_or(0.0) < 0.0)
+            .map(|&symbol| {
+                let symbol_trades: Vec<&BacktestTrade> =
+                    trades.iter().filter(|t| t.symbol == symbol).collect();
+                let profit_loss: f64 = symbol_trades
+                    .iter()
+                    .map(|t| t.profit_loss.unwrap_or(0.0))
+                    .sum();
+                SymbolContribution {
+                    symbol: symbol.to_string(),
+                    trades: symbol_trades.len(),
+                    profit_loss,
+                    contribution_percent: profit_loss / self.config.initial_capital * 100.0,
+                }
+            })
             .collect();
 
-        let total_trades = trades.len();
-        let num_winners = winning_trades.len();
-        let num_losers = losing_trades.len();
+        let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
 
-        let win_rate = if total_trades > 0 {
-            (num_winners as f64 / total_trades as f64) * 100.0
-        } else {
-            0.0
-        };
+        PortfolioBacktestResult {
+            strategy_id: strategy.id,
+            strategy_name: strategy.name.clone(),
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+            start_date: dates.first().copied().unwrap_or(epoch),
+            end_date: dates.last().copied().unwrap_or(epoch),
+            initial_capital: self.config.initial_capital,
+            final_capital: cash,
+            metrics,
+            trades,
+            contributions,
+        }
+    }
 
-        let avg_win = if !winning_trades.is_empty() {
-            winning_trades
-                .iter()
-                .map(|t| t.profit_loss_percent.unwrap_or(0.0))
-                .sum::<f64>()
-                / winning_trades.len() as f64
-        } else {
-            0.0
-        };
+    /// Monte Carlo over a backtest's closed trades, seeded from the clock.
+    /// See `monte_carlo_with_seed`.
+    pub fn monte_carlo(trades: &[BacktestTrade], runs: usize, initial_capital: f64) -> MonteCarloResult {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Self::monte_carlo_with_seed(trades, runs, initial_capital, seed)
+    }
 
-        let avg_loss = if !losing_trades.is_empty() {
-            losing_trades
-                .iter()
-                .map(|t| t.profit_loss_percent.unwrap_or(0.0).abs())
-                .sum::<f64>()
-                / losing_trades.len() as f64
-        } else {
-            0.0
-        };
+    /// Resample the trades' returns with replacement `runs` times and compound each
+    /// sequence from `initial_capital`.
+    ///
+    /// Each trade's return is its P&L relative to equity at the time it closed on the
+    /// original path, so position sizing carries over. Open trades are ignored. The
+    /// same seed always gives the same result.
+    pub fn monte_carlo_with_seed(
+        trades: &[BacktestTrade],
+        runs: usize,
+        initial_capital: f64,
+        seed: u64,
+    ) -> MonteCarloResult {
+        let mut closed: Vec<&BacktestTrade> = trades.iter().filter(|t| t.exit_date.is_some()).collect();
+        closed.sort_by_key(|t| t.exit_date);
+
+        let mut equity = initial_capital;
+        let mut returns = Vec::with_capacity(closed.len());
+        for trade in closed {
+            if equity <= 0.0 {
+                break;
+            }
+            let pnl = trade.profit_loss.unwrap_or(0.0);
+            returns.push(pnl / equity);
+            equity += pnl;
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let ruin_level = initial_capital * MONTE_CARLO_RUIN_FRACTION;
+        let mut final_equity = Vec::with_capacity(runs);
+        let mut max_drawdown = Vec::with_capacity(runs);
+        let mut ruined = 0;
+
+        for _ in 0..runs {
+            let mut path = Vec::with_capacity(returns.len());
+            let mut equity = initial_capital;
+            for _ in 0..returns.len() {
+                equity = (equity * (1.0 + returns[rng.gen_range(0..returns.len())])).max(0.0);
+                path.push(equity);
+            }
+            if path.iter().any(|&e| e <= ruin_level) {
+                ruined += 1;
+            }
+            max_drawdown.push(drawdown_series(initial_capital, &path).into_iter().fold(0.0, f64::max));
+            final_equity.push(equity);
+        }
+
+        MonteCarloResult {
+            runs,
+            trades_per_run: returns.len(),
+            initial_capital,
+            final_equity: Percentiles::of(final_equity),
+            max_drawdown: Percentiles::of(max_drawdown),
+            probability_of_ruin: if runs > 0 { ruined as f64 / runs as f64 } else { 0.0 },
+        }
+    }
+}
+
+/// Calculate performance metrics from closed trades and the per-bar equity history
+pub(crate) fn calculate_metrics(
+    initial: f64,
+    trades: &[BacktestTrade],
+    equity_history: &[f64],
+) -> PerformanceMetrics {
+    let final_equity = *equity_history.last().unwrap_or(&initial);
 
-        let gross_profit: f64 = winning_trades
+    let total_return_dollars = final_equity - initial;
+    let total_return = (total_return_dollars / initial) * 100.0;
+
+    // Max drawdown
+    let max_drawdown = drawdown_series(initial, equity_history)
+        .into_iter()
+        .fold(0.0, f64::max);
+
+    // Trade statistics
+    let winning_trades: Vec<_> = trades
+        .iter()
+        .filter(|t| t.profit_loss.unwrap_or(0.0) > 0.0)
+        .collect();
+    let losing_trades: Vec<_> = trades
+        .iter()
+        .filter(|t| t.profit_loss.unwrap_or(0.0) < 0.0)
+        .collect();
+
+    let total_trades = trades.len();
+    let num_winners = winning_trades.len();
+    let num_losers = losing_trades.len();
+
+    let win_rate = if total_trades > 0 {
+        (num_winners as f64 / total_trades as f64) * 100.0
+    } else {
+        0.0
+    };
+
+    let avg_win = if !winning_trades.is_empty() {
+        winning_trades
             .iter()
-            .map(|t| t.profit_loss.unwrap_or(0.0))
-            .sum();
-        let gross_loss: f64 = losing_trades
+            .map(|t| t.profit_loss_percent.unwrap_or(0.0))
+            .sum::<f64>()
+            / winning_trades.len() as f64
+    } else {
+        0.0
+    };
+
+    let avg_loss = if !losing_trades.is_empty() {
+        losing_trades
             .iter()
-            .map(|t| t.profit_loss.unwrap_or(0.0).abs())
-            .sum();
+            .map(|t| t.profit_loss_percent.unwrap_or(0.0).abs())
+            .sum::<f64>()
+            / losing_trades.len() as f64
+    } else {
+        0.0
+    };
 
-        let profit_factor = if gross_loss > 0.0 {
-            gross_profit / gross_loss
-        } else if gross_profit > 0.0 {
-            f64::INFINITY
-        } else {
-            0.0
-        };
+    let gross_profit: f64 = winning_trades
+        .iter()
+        .map(|t| t.profit_loss.unwrap_or(0.0))
+        .sum();
+    let gross_loss: f64 = losing_trades
+        .iter()
+        .map(|t| t.profit_loss.unwrap_or(0.0).abs())
+        .sum();
 
-        // Average trade duration
-        let avg_duration = if !trades.is_empty() {
-            trades
-                .iter()
-                .filter_map(|t| {
-                    t.exit_date.map(|exit| {
-                        (exit - t.entry_date).num_days() as f64
-                    })
+    let profit_factor = if gross_loss > 0.0 {
+        gross_profit / gross_loss
+    } else if gross_profit > 0.0 {
+        f64::INFINITY
+    } else {
+        0.0
+    };
+
+    // Average trade duration
+    let avg_duration = if !trades.is_empty() {
+        trades
+            .iter()
+            .filter_map(|t| {
+                t.exit_date.map(|exit| {
+                    (exit - t.entry_date).num_days() as f64
                 })
-                .sum::<f64>()
-                / trades.len() as f64
-        } else {
-            0.0
+            })
+            .sum::<f64>()
+            / trades.len() as f64
+    } else {
+        0.0
+    };
+
+    // Simple Sharpe ratio approximation (assuming 252 trading days)
+    let daily_returns: Vec<f64> = equity_history
+        .windows(2)
+        .map(|w| (w[1] - w[0]) / w[0])
+        .collect();
+
+    let avg_return = if !daily_returns.is_empty() {
+        daily_returns.iter().sum::<f64>() / daily_returns.len() as f64
+    } else {
+        0.0
+    };
+
+    let std_dev = if daily_returns.len() > 1 {
+        let variance = daily_returns
+            .iter()
+            .map(|r| (r - avg_return).powi(2))
+            .sum::<f64>()
+            / daily_returns.len() as f64;
+        variance.sqrt()
+    } else {
+        0.0
+    };
+
+    let sharpe_ratio = if std_dev > 0.0 {
+        (avg_return / std_dev) * (252.0_f64).sqrt()
+    } else {
+        0.0
+    };
+
+    PerformanceMetrics {
+        total_return,
+        total_return_dollars,
+        max_drawdown,
+        sharpe_ratio,
+        win_rate,
+        total_trades,
+        winning_trades: num_winners,
+        losing_trades: num_losers,
+        avg_win_percent: avg_win,
+        avg_loss_percent: avg_loss,
+        profit_factor,
+        avg_trade_duration_days: avg_duration,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn declining_prices(days: u32) -> Vec<DailyPrice> {
+        (0..days)
+            .map(|i| {
+                let close = 100.0 - i as f64;
+                let start = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+                DailyPrice {
+                    symbol: "TEST".to_string(),
+                    date: start + chrono::Duration::days(i as i64),
+                    open: close,
+                    high: close + 0.5,
+                    low: close - 0.5,
+                    close,
+                    volume: 1000,
+                    adjusted_close: None,
+                    source: "test".to_string(),
+                }
+            })
+            .collect()
+    }
+
+    fn rsi_series(prices: &[DailyPrice], first: f64, rest: f64) -> Vec<TechnicalIndicator> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, p)| TechnicalIndicator {
+                symbol: p.symbol.clone(),
+                date: p.date,
+                indicator_name: "RSI_14".to_string(),
+                value: if i == 0 { first } else { rest },
+            })
+            .collect()
+    }
+
+    fn overbought_strategy(direction: TradeDirection) -> Strategy {
+        Strategy {
+            id: 1,
+            name: "fade_overbought".to_string(),
+            description: None,
+            entry_condition: StrategyConditionType::RsiOverbought,
+            entry_threshold: 70.0,
+            exit_condition: StrategyConditionType::RsiOversold,
+            exit_threshold: 0.0,
+            stop_loss_percent: None,
+            take_profit_percent: None,
+            trailing_stop_percent: None,
+            position_size_percent: 100.0,
+            direction,
+            entry_rule: None,
+            exit_rule: None,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_short_profits_on_declining_prices() {
+        let prices = declining_prices(20);
+        let indicators = rsi_series(&prices, 80.0, 50.0);
+        let engine = BacktestEngine::default();
+
+        let result = engine.run(
+            &overbought_strategy(TradeDirection::Short),
+            "TEST",
+            &prices,
+            &indicators,
+        );
+
+        assert_eq!(result.trades.len(), 1);
+        let trade = &result.trades[0];
+        assert_eq!(trade.direction, TradeDirection::Short);
+        // Shorted 100 shares at 100, covered at 81
+        assert!((trade.profit_loss.unwrap() - 1900.0).abs() < 1e-6);
+        assert!((result.final_capital - 11900.0).abs() < 1e-6);
+        assert_eq!(result.metrics.win_rate, 100.0);
+        assert_eq!(result.metrics.max_drawdown, 0.0);
+    }
+
+    #[test]
+    fn test_long_loses_on_declining_prices() {
+        let prices = declining_prices(20);
+        let indicators = rsi_series(&prices, 80.0, 50.0);
+        let engine = BacktestEngine::default();
+
+        let result = engine.run(
+            &overbought_strategy(TradeDirection::Long),
+            "TEST",
+            &prices,
+            &indicators,
+        );
+
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].direction, TradeDirection::Long);
+        assert!((result.trades[0].profit_loss.unwrap() + 1900.0).abs() < 1e-6);
+        assert_eq!(result.metrics.win_rate, 0.0);
+    }
+
+    #[test]
+    fn test_short_stop_loss_triggers_above_entry() {
+        let mut prices = declining_prices(5);
+        for (i, p) in prices.iter_mut().enumerate() {
+            p.close = 100.0 + i as f64 * 5.0;
+        }
+        let indicators = rsi_series(&prices, 80.0, 50.0);
+        let mut strategy = overbought_strategy(TradeDirection::Short);
+        strategy.stop_loss_percent = Some(8.0);
+
+        let result = BacktestEngine::default().run(&strategy, "TEST", &prices, &indicators);
+
+        assert_eq!(result.trades[0].exit_reason.as_deref(), Some("stop_loss"));
+        assert_eq!(result.trades[0].exit_price, Some(110.0));
+    }
+
+    #[test]
+    fn test_slippage_and_commission_reduce_round_trip_pnl() {
+        let mut prices = declining_prices(2);
+        prices[0].close = 100.0;
+        prices[1].close = 110.0;
+        let indicators = rsi_series(&prices, 80.0, 50.0);
+        let strategy = overbought_strategy(TradeDirection::Long);
+
+        let frictionless = BacktestEngine::default().run(&strategy, "TEST", &prices, &indicators);
+        let costly = BacktestEngine::new(BacktestConfig {
+            commission_percent: 0.1,
+            slippage_bps: 10.0,
+            ..BacktestConfig::default()
+        })
+        .run(&strategy, "TEST", &prices, &indicators);
+
+        // Entry: fill at 100.10, 0.1% commission on the filled notional, which
+        // together use the whole $10,000
+        let notional = 10_000.0 / 1.001;
+        let shares = notional / 100.1;
+        let entry_commission = notional * 0.001;
+        let exit_fill = 110.0 * 0.999;
+        let exit_commission = shares * exit_fill * 0.001;
+        let expected = (exit_fill - 100.1) * shares - entry_commission - exit_commission;
+
+        assert!((frictionless.trades[0].profit_loss.unwrap() - 1000.0).abs() < 1e-6);
+        assert!((costly.trades[0].profit_loss.unwrap() - expected).abs() < 1e-6);
+        assert!((costly.final_capital - (10_000.0 + expected)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_commission_uses_greatest_of_flat_percent_min() {
+        let config = BacktestConfig {
+            commission_per_trade: 1.0,
+            commission_percent: 0.1,
+            min_commission: 5.0,
+            ..BacktestConfig::default()
         };
 
-        // Simple Sharpe ratio approximation (assuming 252 trading days)
-        let daily_returns: Vec<f64> = equity_history
-            .windows(2)
-            .map(|w| (w[1] - w[0]) / w[0])
+        assert_eq!(config.commission(1_000.0), 5.0);
+        assert!((config.commission(10_000.0) - 10.0).abs() < 1e-9);
+        // $5 minimum binds below $5,000; above it the 0.1% does
+        assert_eq!(config.max_notional(1_000.0), 995.0);
+        assert!((config.max_notional(10_010.0) - 10_000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_trailing_stop_exits_near_peak() {
+        // Rise from 100 to 150, then fall back to 100
+        let closes: Vec<f64> = (0..=10)
+            .map(|i| 100.0 + i as f64 * 5.0)
+            .chain((1..=10).map(|i| 150.0 - i as f64 * 5.0))
             .collect();
+        let mut prices = declining_prices(closes.len() as u32);
+        for (p, close) in prices.iter_mut().zip(&closes) {
+            p.close = *close;
+        }
+        let indicators = rsi_series(&prices, 80.0, 50.0);
+        let mut strategy = overbought_strategy(TradeDirection::Long);
+        strategy.stop_loss_percent = Some(20.0);
+        strategy.trailing_stop_percent = Some(5.0);
 
-        let avg_return = if !daily_returns.is_empty() {
-            daily_returns.iter().sum::<f64>() / daily_returns.len() as f64
-        } else {
-            0.0
+        let result = BacktestEngine::default().run(&strategy, "TEST", &prices, &indicators);
+
+        let trade = &result.trades[0];
+        assert_eq!(trade.exit_reason.as_deref(), Some("trailing_stop"));
+        // 5% below the 150 peak is 142.5, first close at or below that is 140
+        assert_eq!(trade.exit_price, Some(140.0));
+    }
+
+    #[test]
+    fn test_fixed_stop_applies_when_tighter_than_trailing() {
+        let mut prices = declining_prices(4);
+        for (p, close) in prices.iter_mut().zip([100.0, 102.0, 96.0, 90.0]) {
+            p.close = close;
+        }
+        let indicators = rsi_series(&prices, 80.0, 50.0);
+        let mut strategy = overbought_strategy(TradeDirection::Long);
+        strategy.stop_loss_percent = Some(3.0);
+        strategy.trailing_stop_percent = Some(10.0);
+
+        let result = BacktestEngine::default().run(&strategy, "TEST", &prices, &indicators);
+
+        assert_eq!(result.trades[0].exit_reason.as_deref(), Some("stop_loss"));
+        assert_eq!(result.trades[0].exit_price, Some(96.0));
+    }
+
+    #[test]
+    fn test_portfolio_does_not_double_spend_capital() {
+        let mut aaa = declining_prices(3);
+        let mut bbb = declining_prices(3);
+        for (i, (a, b)) in aaa.iter_mut().zip(bbb.iter_mut()).enumerate() {
+            a.symbol = "AAA".to_string();
+            a.close = 100.0 + i as f64 * 10.0;
+            b.symbol = "BBB".to_string();
+            b.close = 50.0;
+        }
+        let prices_map: HashMap<String, Vec<DailyPrice>> = HashMap::from([
+            ("AAA".to_string(), aaa.clone()),
+            ("BBB".to_string(), bbb.clone()),
+        ]);
+        // Both symbols signal an entry on the same first day
+        let indicators_map: HashMap<String, Vec<TechnicalIndicator>> = HashMap::from([
+            ("AAA".to_string(), rsi_series(&aaa, 80.0, 50.0)),
+            ("BBB".to_string(), rsi_series(&bbb, 80.0, 50.0)),
+        ]);
+        let mut strategy = overbought_strategy(TradeDirection::Long);
+        strategy.position_size_percent = 60.0;
+
+        let result = BacktestEngine::default().run_portfolio(
+            &strategy,
+            &["AAA", "BBB"],
+            &prices_map,
+            &indicators_map,
+        );
+
+        assert_eq!(result.trades.len(), 2);
+        let invested: f64 = result.trades.iter().map(|t| t.shares * t.entry_price).sum();
+        // AAA takes 60% and BBB only gets the 40% that is left, not another 60%
+        assert!((invested - 10_000.0).abs() < 1e-6);
+        let aaa_trade = result.trades.iter().find(|t| t.symbol == "AAA").unwrap();
+        let bbb_trade = result.trades.iter().find(|t| t.symbol == "BBB").unwrap();
+        assert!((aaa_trade.shares * 100.0 - 6_000.0).abs() < 1e-6);
+        assert!((bbb_trade.shares * 50.0 - 4_000.0).abs() < 1e-6);
+
+        // AAA rose 20% on 60 shares, BBB was flat
+        assert!((result.final_capital - 11_200.0).abs() < 1e-6);
+        assert!((result.contributions[0].profit_loss - 1_200.0).abs() < 1e-6);
+        assert_eq!(result.contributions[1].profit_loss, 0.0);
+    }
+
+    #[test]
+    fn test_walk_forward_split_is_disjoint_and_contiguous() {
+        let prices = declining_prices(10);
+        let indicators = rsi_series(&prices, 80.0, 50.0);
+        let strategy = overbought_strategy(TradeDirection::Long);
+
+        let result = BacktestEngine::default().run_walk_forward(
+            &strategy,
+            "TEST",
+            &prices,
+            &indicators,
+            0.7,
+        );
+
+        // 7 training bars (Jan 1-7), 3 held out (Jan 8-10)
+        assert_eq!(result.in_sample.start_date, prices[0].date);
+        assert_eq!(result.in_sample.end_date, prices[6].date);
+        assert_eq!(result.split_date, prices[7].date);
+        assert_eq!(result.out_of_sample.start_date, prices[7].date);
+        assert_eq!(result.out_of_sample.end_date, prices[9].date);
+        assert_eq!(
+            result.in_sample.end_date.succ_opt(),
+            Some(result.out_of_sample.start_date)
+        );
+
+        // Long from 100 down to 94 in-sample; no RSI > 70 entry out-of-sample
+        assert!((result.in_sample.metrics.total_return + 6.0).abs() < 1e-6);
+        assert!(result.out_of_sample.trades.is_empty());
+        assert_eq!(result.degradation_ratio, 0.0);
+    }
+
+    #[test]
+    fn test_optimize_returns_best_combination_first() {
+        // Steady rise from 100 to 119 with an overbought reading on day one
+        let mut prices = declining_prices(20);
+        for (i, p) in prices.iter_mut().enumerate() {
+            p.close = 100.0 + i as f64;
+        }
+        let indicators = rsi_series(&prices, 80.0, 50.0);
+        let base = overbought_strategy(TradeDirection::Long);
+        let grid = ParamGrid {
+            entry_thresholds: vec![70.0, 90.0],
+            take_profit_percents: vec![None, Some(5.0), Some(10.0)],
+            ..ParamGrid::default()
         };
+        let engine = BacktestEngine::default();
 
-        let std_dev = if daily_returns.len() > 1 {
-            let variance = daily_returns
-                .iter()
-                .map(|r| (r - avg_return).powi(2))
-                .sum::<f64>()
-                / daily_returns.len() as f64;
-            variance.sqrt()
-        } else {
-            0.0
+        let results = engine
+            .optimize(&base, "TEST", &prices, &indicators, &grid, OptimizationObjective::TotalReturn)
+            .unwrap();
+
+        assert_eq!(results.len(), 6);
+        // Holding to the end beats either take-profit; never entering (90) earns nothing
+        assert_eq!(results[0].strategy.entry_threshold, 70.0);
+        assert_eq!(results[0].strategy.take_profit_percent, None);
+        assert!((results[0].score - 19.0).abs() < 1e-6);
+        assert!(results.windows(2).all(|w| w[0].score >= w[1].score));
+
+        // The winning params reproduce the best metric of every combination
+        let best_manual = grid
+            .expand(&base)
+            .iter()
+            .map(|s| engine.run(s, "TEST", &prices, &indicators).metrics.total_return)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let rerun = engine.run(&results[0].strategy, "TEST", &prices, &indicators);
+        assert_eq!(rerun.metrics.total_return, best_manual);
+    }
+
+    #[test]
+    fn test_optimize_rejects_oversized_grid() {
+        let grid = ParamGrid {
+            entry_thresholds: (0..200).map(f64::from).collect(),
+            exit_thresholds: (0..100).map(f64::from).collect(),
+            ..ParamGrid::default()
         };
+        let result = BacktestEngine::default().optimize(
+            &overbought_strategy(TradeDirection::Long),
+            "TEST",
+            &[],
+            &[],
+            &grid,
+            OptimizationObjective::TotalReturn,
+        );
+        assert!(result.is_err());
+    }
 
-        let sharpe_ratio = if std_dev > 0.0 {
-            (avg_return / std_dev) * (252.0_f64).sqrt()
-        } else {
-            0.0
+    #[test]
+    fn test_equity_and_drawdown_curves_match_summary() {
+        let mut prices = declining_prices(6);
+        for (p, close) in prices.iter_mut().zip([100.0, 110.0, 99.0, 105.0, 120.0, 90.0]) {
+            p.close = close;
+        }
+        let indicators = rsi_series(&prices, 80.0, 50.0);
+        let engine = BacktestEngine::new(BacktestConfig {
+            commission_percent: 0.1,
+            slippage_bps: 5.0,
+            ..BacktestConfig::default()
+        });
+
+        let result = engine.run(&overbought_strategy(TradeDirection::Long), "TEST", &prices, &indicators);
+
+        assert_eq!(result.equity_curve.len(), prices.len());
+        assert_eq!(result.drawdown_curve.len(), prices.len());
+        assert_eq!(result.equity_curve.last().unwrap().0, prices[5].date);
+        assert_eq!(result.equity_curve.last().unwrap().1, result.final_capital);
+        let max_dd = result
+            .drawdown_curve
+            .iter()
+            .map(|&(_, dd)| dd)
+            .fold(0.0, f64::max);
+        assert_eq!(max_dd, result.metrics.max_drawdown);
+        assert!(max_dd > 0.0);
+    }
+
+    #[test]
+    fn test_all_rule_requires_both_conditions_on_same_bar() {
+        // Day 0: RSI low only. Day 1: price above SMA only. Day 2: both.
+        let mut prices = declining_prices(4);
+        for p in prices.iter_mut() {
+            p.close = 100.0;
+        }
+        let day = |i: usize, name: &str, value: f64| TechnicalIndicator {
+            symbol: "TEST".to_string(),
+            date: prices[i].date,
+            indicator_name: name.to_string(),
+            value,
         };
+        let indicators = vec![
+            day(0, "RSI_14", 25.0),
+            day(0, "SMA_20", 110.0),
+            day(1, "RSI_14", 45.0),
+            day(1, "SMA_20", 90.0),
+            day(2, "RSI_14", 25.0),
+            day(2, "SMA_20", 90.0),
+            day(3, "RSI_14", 45.0),
+            day(3, "SMA_20", 90.0),
+        ];
+        let mut strategy = overbought_strategy(TradeDirection::Long);
+        strategy.entry_rule = Some(StrategyRule::All(vec![
+            Condition { condition: StrategyConditionType::RsiOversold, threshold: 30.0 },
+            Condition { condition: StrategyConditionType::PriceAboveSma, threshold: 0.0 },
+        ]));
+
+        let result = BacktestEngine::default().run(&strategy, "TEST", &prices, &indicators);
+
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].entry_date, prices[2].date);
+        assert_eq!(result.trades[0].entry_reason, "rsi_oversold+price_above_sma");
 
-        PerformanceMetrics {
-            total_return,
-            total_return_dollars,
-            max_drawdown,
-            sharpe_ratio,
-            win_rate,
-            total_trades,
-            winning_trades: num_winners,
-            losing_trades: num_losers,
-            avg_win_percent: avg_win,
-            avg_loss_percent: avg_loss,
-            profit_factor,
-            avg_trade_duration_days: avg_duration,
+        // The same conditions as Any fire on the first bar
+        strategy.entry_rule = Some(StrategyRule::Any(strategy.entry_rule.unwrap().conditions().to_vec()));
+        let result = BacktestEngine::default().run(&strategy, "TEST", &prices, &indicators);
+        assert_eq!(result.trades[0].entry_date, prices[0].date);
+    }
+
+    #[test]
+    fn test_single_condition_strategy_is_one_element_all() {
+        let strategy = overbought_strategy(TradeDirection::Long);
+        assert_eq!(
+            strategy.resolved_entry_rule(),
+            StrategyRule::All(vec![Condition {
+                condition: StrategyConditionType::RsiOverbought,
+                threshold: 70.0,
+            }])
+        );
+    }
+
+    fn closed_trade(day: u32, profit_loss: f64) -> BacktestTrade {
+        let date = NaiveDate::from_ymd_opt(2026, 1, day).unwrap();
+        BacktestTrade {
+            id: day as i64,
+            backtest_id: 1,
+            symbol: "TEST".to_string(),
+            direction: TradeDirection::Long,
+            entry_date: date,
+            entry_price: 100.0,
+            exit_date: Some(date),
+            exit_price: Some(100.0 + profit_loss / 10.0),
+            shares: 10.0,
+            entry_reason: "test".to_string(),
+            exit_reason: Some("test".to_string()),
+            profit_loss: Some(profit_loss),
+            profit_loss_percent: Some(profit_loss / 10.0),
         }
     }
+
+    #[test]
+    fn test_monte_carlo_all_winners_never_ruins() {
+        let trades: Vec<BacktestTrade> = (1..=10).map(|d| closed_trade(d, 50.0 + d as f64 * 10.0)).collect();
+
+        let result = BacktestEngine::monte_carlo_with_seed(&trades, 500, 10_000.0, 42);
+
+        assert_eq!(result.runs, 500);
+        assert_eq!(result.trades_per_run, 10);
+        assert_eq!(result.probability_of_ruin, 0.0);
+        assert_eq!(result.max_drawdown.p95, 0.0);
+        assert!(result.final_equity.p5 > 10_000.0);
+        assert!(result.final_equity.p5 <= result.final_equity.p50);
+        assert!(result.final_equity.p50 <= result.final_equity.p95);
+
+        // Same seed, same distribution
+        let again = BacktestEngine::monte_carlo_with_seed(&trades, 500, 10_000.0, 42);
+        assert_eq!(again.final_equity, result.final_equity);
+    }
 }
@@ -10,6 +10,20 @@ use std::time::Duration;
 const CLAUDE_API_URL: &str = "https://api.anthropic.com/v1/messages";
 const DEFAULT_MODEL: &str = "claude-sonnet-4-20250514";
 const MAX_TOKENS: u32 = 4096;
+/// Default token budget for prior conversation turns sent with a query
+pub const DEFAULT_HISTORY_TOKEN_BUDGET: usize = 20_000;
+/// Default token budget for the market context in the system prompt
+pub const DEFAULT_MAX_CONTEXT_TOKENS: usize = 4_000;
+
+/// USD per million (input, output) tokens, matched by model name substring in order
+const MODEL_PRICING: &[(&str, f64, f64)] = &[
+    ("opus-4-5", 5.0, 25.0),
+    ("opus", 15.0, 75.0),
+    ("sonnet", 3.0, 15.0),
+    ("haiku-4-5", 1.0, 5.0),
+    ("3-5-haiku", 0.8, 4.0),
+    ("haiku", 0.25, 1.25),
+];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaudeMessage {
@@ -69,12 +83,63 @@ pub struct PriceContext {
     pub price: f64,
     pub change_percent: Option<f64>,
     pub date: String,
+    /// Favorited symbols are kept first when the context is trimmed
+    #[serde(default)]
+    pub favorited: bool,
+}
+
+impl PriceContext {
+    fn prompt_line(&self) -> String {
+        let change_str = self.change_percent
+            .map(|c| format!(" ({:+.2}%)", c))
+            .unwrap_or_default();
+        format!("- {}: ${:.2}{} ({})\n", self.symbol, self.price, change_str, self.date)
+    }
+}
+
+impl FinancialContext {
+    /// Trim price entries (favorites first) so the rendered context fits in `max_tokens`.
+    /// The symbol list is reduced to the symbols whose prices were kept.
+    pub fn truncated(&self, max_tokens: usize) -> FinancialContext {
+        let mut ordered: Vec<&PriceContext> = self.recent_prices.iter().collect();
+        ordered.sort_by_key(|p| !p.favorited);
+
+        let mut used = 0;
+        let mut kept = Vec::new();
+        for price in ordered {
+            // Each entry costs its price line plus its name in the tracked symbol list
+            let cost = estimate_tokens(&price.prompt_line()) + estimate_tokens(&price.symbol) + 1;
+            if used + cost > max_tokens {
+                break;
+            }
+            used += cost;
+            kept.push(price.clone());
+        }
+
+        if kept.len() == self.recent_prices.len() {
+            return self.clone();
+        }
+
+        let symbols = self
+            .symbols
+            .iter()
+            .filter(|s| kept.iter().any(|p| &p.symbol == *s))
+            .cloned()
+            .collect();
+
+        FinancialContext {
+            symbols,
+            recent_prices: kept,
+            query: self.query.clone(),
+        }
+    }
 }
 
 pub struct ClaudeClient {
     client: Client,
     api_key: String,
     model: String,
+    max_context_tokens: usize,
 }
 
 impl ClaudeClient {
@@ -92,6 +157,7 @@ impl ClaudeClient {
             client,
             api_key,
             model: DEFAULT_MODEL.to_string(),
+            max_context_tokens: DEFAULT_MAX_CONTEXT_TOKENS,
         })
     }
 
@@ -109,6 +175,7 @@ impl ClaudeClient {
             client,
             api_key,
             model: DEFAULT_MODEL.to_string(),
+            max_context_tokens: DEFAULT_MAX_CONTEXT_TOKENS,
         })
     }
 
@@ -118,6 +185,12 @@ impl ClaudeClient {
         self
     }
 
+    /// Set the token budget for market context in the system prompt
+    pub fn with_max_context_tokens(mut self, max_context_tokens: usize) -> Self {
+        self.max_context_tokens = max_context_tokens;
+        self
+    }
+
     /// Query Claude with financial context
     pub fn query_with_context(
         &self,
@@ -125,29 +198,7 @@ impl ClaudeClient {
         context: Option<&FinancialContext>,
         conversation_history: Option<&[ClaudeMessage]>,
     ) -> Result<ChatResult> {
-        // Build system prompt with financial context
-        let system_prompt = self.build_system_prompt(context);
-
-        // Build messages
-        let mut messages: Vec<ClaudeMessage> = Vec::new();
-
-        // Add conversation history if provided
-        if let Some(history) = conversation_history {
-            messages.extend(history.iter().cloned());
-        }
-
-        // Add current query
-        messages.push(ClaudeMessage {
-            role: "user".to_string(),
-            content: query.to_string(),
-        });
-
-        let request = ClaudeRequest {
-            model: self.model.clone(),
-            max_tokens: MAX_TOKENS,
-            system: Some(system_prompt),
-            messages,
-        };
+        let request = self.build_request(query, context, conversation_history);
 
         let response = self.client
             .post(CLAUDE_API_URL)
@@ -187,6 +238,37 @@ impl ClaudeClient {
         self.query_with_context(query, None, None)
     }
 
+    fn build_request(
+        &self,
+        query: &str,
+        context: Option<&FinancialContext>,
+        conversation_history: Option<&[ClaudeMessage]>,
+    ) -> ClaudeRequest {
+        // Build system prompt with financial context
+        let system_prompt = self.build_system_prompt(context);
+
+        // Build messages
+        let mut messages: Vec<ClaudeMessage> = Vec::new();
+
+        // Add conversation history if provided
+        if let Some(history) = conversation_history {
+            messages.extend(history.iter().cloned());
+        }
+
+        // Add current query
+        messages.push(ClaudeMessage {
+            role: "user".to_string(),
+            content: query.to_string(),
+        });
+
+        ClaudeRequest {
+            model: self.model.clone(),
+            max_tokens: MAX_TOKENS,
+            system: Some(system_prompt),
+            messages,
+        }
+    }
+
     fn build_system_prompt(&self, context: Option<&FinancialContext>) -> String {
         let mut prompt = String::from(
             "You are a financial analysis assistant with expertise in stock markets, \
@@ -194,7 +276,8 @@ impl ClaudeClient {
             insights based on the data provided. Be concise but thorough.\n\n"
         );
 
-        if let Some(ctx) = context {
+        if let Some(full) = context {
+            let ctx = full.truncated(self.max_context_tokens);
             prompt.push_str("CURRENT MARKET CONTEXT:\n");
 
             if !ctx.symbols.is_empty() {
@@ -204,15 +287,14 @@ impl ClaudeClient {
             if !ctx.recent_prices.is_empty() {
                 prompt.push_str("\nRecent prices:\n");
                 for price in &ctx.recent_prices {
-                    let change_str = price.change_percent
-                        .map(|c| format!(" ({:+.2}%)", c))
-                        .unwrap_or_default();
-                    prompt.push_str(&format!(
-                        "- {}: ${:.2}{} ({})\n",
-                        price.symbol, price.price, change_str, price.date
-                    ));
+                    prompt.push_str(&price.prompt_line());
                 }
             }
+
+            let omitted = full.recent_prices.len() - ctx.recent_prices.len();
+            if omitted > 0 {
+                prompt.push_str(&format!("({} more symbols omitted to fit the context budget)\n", omitted));
+            }
             prompt.push_str("\n");
         }
 
@@ -228,6 +310,42 @@ impl ClaudeClient {
     }
 }
 
+/// Rough token count (~4 characters per token) for budgeting prompts
+pub fn estimate_tokens(text: &str) -> usize {
+    text.len().div_ceil(4)
+}
+
+/// Estimate the USD cost of a request from its token usage.
+/// Unknown models are priced as Sonnet.
+pub fn estimate_claude_cost(input_tokens: u32, output_tokens: u32, model: &str) -> f64 {
+    let (input_rate, output_rate) = MODEL_PRICING
+        .iter()
+        .find(|(name, _, _)| model.contains(name))
+        .map(|&(_, input, output)| (input, output))
+        .unwrap_or((3.0, 15.0));
+
+    (input_tokens as f64 * input_rate + output_tokens as f64 * output_rate) / 1_000_000.0
+}
+
+/// Keep the most recent messages that fit in `max_tokens`, dropping the oldest first.
+/// The result always starts with a user turn, as the Messages API requires.
+pub fn trim_history(history: &[ClaudeMessage], max_tokens: usize) -> Vec<ClaudeMessage> {
+    let mut used = 0;
+    let mut start = history.len();
+    for (i, message) in history.iter().enumerate().rev() {
+        used += estimate_tokens(&message.content);
+        if used > max_tokens {
+            break;
+        }
+        start = i;
+    }
+
+    while start < history.len() && history[start].role != "user" {
+        start += 1;
+    }
+    history[start..].to_vec()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -238,6 +356,7 @@ mod tests {
             client: Client::new(),
             api_key: "test".to_string(),
             model: DEFAULT_MODEL.to_string(),
+            max_context_tokens: DEFAULT_MAX_CONTEXT_TOKENS,
         };
 
         let context = FinancialContext {
@@ -248,6 +367,7 @@ mod tests {
                     price: 260.94,
                     change_percent: Some(1.5),
                     date: "2026-01-14".to_string(),
+                    favorited: false,
                 },
             ],
             query: "Test".to_string(),
@@ -257,4 +377,99 @@ mod tests {
         assert!(prompt.contains("AAPL"));
         assert!(prompt.contains("260.94"));
     }
+
+    fn message(role: &str, content: &str) -> ClaudeMessage {
+        ClaudeMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_second_turn_carries_first_turn() {
+        let db = crate::db::Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        let conversation_id = db.create_conversation("AAPL outlook", "claude").unwrap();
+        db.add_conversation_message(conversation_id, "user", "Is AAPL overbought?").unwrap();
+        db.add_conversation_message(conversation_id, "assistant", "RSI is 74, so yes.").unwrap();
+
+        let history: Vec<ClaudeMessage> = db
+            .get_conversation_messages(conversation_id)
+            .unwrap()
+            .into_iter()
+            .map(|m| message(&m.role, &m.content))
+            .collect();
+        let client = ClaudeClient::with_api_key("test".to_string()).unwrap();
+        let request = client.build_request("What about MSFT?", None, Some(&trim_history(&history, 1000)));
+        let payload = serde_json::to_string(&request).unwrap();
+
+        assert_eq!(request.messages.len(), 3);
+        assert!(payload.contains("Is AAPL overbought?"));
+        assert!(payload.contains("RSI is 74, so yes."));
+        assert!(payload.contains("What about MSFT?"));
+    }
+
+    #[test]
+    fn test_trim_history_drops_oldest_and_starts_with_user() {
+        let history = vec![
+            message("user", &"a".repeat(400)),
+            message("assistant", &"b".repeat(400)),
+            message("user", &"c".repeat(40)),
+            message("assistant", &"d".repeat(40)),
+        ];
+
+        // Budget fits the last three messages, but the oldest kept must be a user turn
+        let trimmed = trim_history(&history, 120);
+        assert_eq!(trimmed.len(), 2);
+        assert_eq!(trimmed[0].role, "user");
+        assert!(trimmed[0].content.starts_with('c'));
+
+        assert_eq!(trim_history(&history, 10_000).len(), 4);
+    }
+
+    #[test]
+    fn test_large_context_trimmed_to_budget_with_favorites_first() {
+        let recent_prices: Vec<PriceContext> = (0..500)
+            .map(|i| PriceContext {
+                symbol: format!("SYM{}", i),
+                price: 100.0 + i as f64,
+                change_percent: Some(0.5),
+                date: "2026-01-14".to_string(),
+                favorited: i >= 495,
+            })
+            .collect();
+        let context = FinancialContext {
+            symbols: recent_prices.iter().map(|p| p.symbol.clone()).collect(),
+            recent_prices,
+            query: "Test".to_string(),
+        };
+
+        let trimmed = context.truncated(500);
+        assert!(trimmed.recent_prices.len() < 500);
+        assert_eq!(trimmed.symbols.len(), trimmed.recent_prices.len());
+        assert!(trimmed.recent_prices[..5].iter().all(|p| p.favorited));
+
+        let rendered: String = trimmed.recent_prices.iter().map(|p| p.prompt_line()).collect();
+        assert!(estimate_tokens(&rendered) + estimate_tokens(&trimmed.symbols.join(", ")) <= 500);
+
+        let client = ClaudeClient::with_api_key("test".to_string())
+            .unwrap()
+            .with_max_context_tokens(500);
+        let prompt = client.build_system_prompt(Some(&context));
+        assert!(prompt.contains("SYM499"));
+        assert!(!prompt.contains("SYM300"));
+        assert!(prompt.contains("more symbols omitted"));
+    }
+
+    #[test]
+    fn test_estimate_claude_cost_uses_pricing_table() {
+        let sonnet = estimate_claude_cost(1_000_000, 1_000_000, "claude-sonnet-4-20250514");
+        assert!((sonnet - 18.0).abs() < 1e-9);
+
+        let haiku = estimate_claude_cost(2_000, 1_000, "claude-3-5-haiku-20241022");
+        assert!((haiku - (2_000.0 * 0.8 + 1_000.0 * 4.0) / 1_000_000.0).abs() < 1e-12);
+
+        let opus = estimate_claude_cost(1_000, 0, "claude-opus-4-20250514");
+        assert!((opus - 0.015).abs() < 1e-12);
+    }
 }
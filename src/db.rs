@@ -1,23 +1,43 @@
 //! SQLite database layer for Financial Pipeline
 
-use chrono::{NaiveDate, Utc};
-use rusqlite::{params, Connection, OptionalExtension, Result as SqliteResult};
-use std::path::Path;
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use rayon::prelude::*;
+use serde::Serialize;
+use rusqlite::{params, Connection, DatabaseName, OpenFlags, OptionalExtension, Result as SqliteResult};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 use crate::error::Result;
+use crate::finnhub::PriceReaction;
+use crate::indicators::{
+    calculate_atr, calculate_incremental, canonical_indicator_name, pearson, LEGACY_INDICATOR_NAMES, MIN_CORRELATION_OVERLAP,
+    MIN_INDICATOR_BARS,
+};
+use crate::search::{match_score, MIN_MATCH_SCORE};
 use crate::models::{
-    AlertCondition, BacktestResult, BacktestTrade, DailyPrice, IndicatorAlert,
+    AlertCondition, AllocationBucket, BacktestResult, BenchmarkComparison, BacktestTrade, DailyPrice, Dividend, EarningsEvent, IndicatorAlert, IntradayPrice,
     IndicatorAlertCondition, IndicatorAlertType, MacroData, PerformanceMetrics, Position,
-    PositionType, PriceAlert, Signal, SignalDirection, SignalType, Strategy,
-    StrategyConditionType, Symbol, TechnicalIndicator, TradeDirection,
+    PortfolioAllocation, PositionType, PriceAlert, PriceStats, PrunedTable, RebalanceTrade, RefreshSettings, DataFreshness, DuplicateSymbol, StopSuggestion, Signal, SymbolMatch, Webhook, WebhookEvent, SignalDirection, SignalType, Strategy,
+    StrategyConditionType, StrategyRule, Symbol, TechnicalIndicator, IndicatorRunSummary, TradeDirection,
     PaperWallet, PaperPosition, PaperTrade, PaperTradeAction,
+    PaperOrder, PaperOrderStatus, PaperOrderType, CostBasisMethod,
     // AI Trading types
     AiTraderConfig, AiTradingSession, AiTradeDecision, AiPerformanceSnapshot, AiPredictionAccuracy,
     // DC Trader types
-    DcWallet, DcPosition, DcTrade, PortfolioSnapshot, TeamConfig, ImportResult, CompetitionStats,
+    DcWallet, DcPosition, DcTrade, TradeLogEntry, PortfolioSnapshot, TeamConfig, TradeFees, ImportResult, CompetitionStats,
+    ConfigExport, WatchlistConfig,
+    // Chat history types
+    Conversation, ConversationMessage,
+    AuditLogEntry, normalize_symbol,
 };
+use crate::signals::{SignalConfig, SignalWeights};
 use crate::trends::TrendData;
 
+/// app_settings key for the paper/DC cost basis method
+pub const SETTING_COST_BASIS_METHOD: &str = "cost_basis_method";
+const LOT_ACCOUNT_PAPER: &str = "paper";
+const LOT_ACCOUNT_DC: &str = "dc";
+
 /// Extension trait for pipe-style method chaining
 trait Pipe: Sized {
     fn pipe<F, R>(self, f: F) -> R
@@ -35,24 +55,111 @@ pub struct Database {
     conn: Connection,
 }
 
+/// Connection settings applied when a database is opened
+#[derive(Debug, Clone)]
+pub struct DatabaseOptions {
+    /// Use write-ahead logging so readers don't block the writer
+    pub wal: bool,
+    /// How long to wait on a locked database before failing (ms)
+    pub busy_timeout_ms: u64,
+    /// Enable `PRAGMA foreign_keys` on every connection, pooled ones included
+    pub foreign_keys: bool,
+}
+
+impl Default for DatabaseOptions {
+    fn default() -> Self {
+        Self {
+            wal: true,
+            busy_timeout_ms: 5000,
+            foreign_keys: true,
+        }
+    }
+}
+
+/// Pool of `Database` handles on one file, for callers that read concurrently
+pub type DatabasePool = r2d2::Pool<DatabaseManager>;
+
+/// r2d2 manager that opens each pooled connection with the same pragmas
+#[derive(Debug)]
+pub struct DatabaseManager {
+    path: PathBuf,
+    opts: DatabaseOptions,
+}
+
+impl DatabaseManager {
+    pub fn new<P: AsRef<Path>>(path: P, opts: DatabaseOptions) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            opts,
+        }
+    }
+}
+
+impl r2d2::ManageConnection for DatabaseManager {
+    type Connection = Database;
+    type Error = crate::error::PipelineError;
+
+    fn connect(&self) -> Result<Database> {
+        Database::open_with_pragmas(&self.path, &self.opts)
+    }
+
+    fn is_valid(&self, db: &mut Database) -> Result<()> {
+        db.conn.execute_batch("SELECT 1")?;
+        Ok(())
+    }
+
+    fn has_broken(&self, _db: &mut Database) -> bool {
+        false
+    }
+}
+
 impl Database {
     /// Open or create database at the given path
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open_with_pragmas(path, &DatabaseOptions::default())
+    }
+
+    /// Open or create database at the given path with explicit connection settings
+    pub fn open_with_pragmas<P: AsRef<Path>>(path: P, opts: &DatabaseOptions) -> Result<Self> {
         // Ensure parent directory exists
         if let Some(parent) = path.as_ref().parent() {
             std::fs::create_dir_all(parent)?;
         }
 
         let conn = Connection::open(path)?;
+        Self::apply_pragmas(&conn, opts)?;
         Ok(Self { conn })
     }
 
+    /// Open a pool of up to `max_size` connections to the database at the given path.
+    /// WAL mode (on by default) lets pooled readers run alongside a writer.
+    pub fn pool<P: AsRef<Path>>(path: P, opts: &DatabaseOptions, max_size: u32) -> Result<DatabasePool> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let pool = r2d2::Pool::builder()
+            .max_size(max_size.max(1))
+            .build(DatabaseManager::new(path, opts.clone()))?;
+        Ok(pool)
+    }
+
     /// Open an in-memory database (for testing)
     pub fn open_in_memory() -> Result<Self> {
         let conn = Connection::open_in_memory()?;
+        Self::apply_pragmas(&conn, &DatabaseOptions::default())?;
         Ok(Self { conn })
     }
 
+    fn apply_pragmas(conn: &Connection, opts: &DatabaseOptions) -> Result<()> {
+        conn.busy_timeout(std::time::Duration::from_millis(opts.busy_timeout_ms))?;
+        if opts.wal {
+            // journal_mode returns the resulting mode, so it has to be queried
+            let _mode: String = conn.query_row("PRAGMA journal_mode=WAL", [], |row| row.get(0))?;
+        }
+        conn.pragma_update(None, "foreign_keys", opts.foreign_keys)?;
+        Ok(())
+    }
+
     /// Initialize database schema
     pub fn init_schema(&self) -> Result<()> {
         self.conn.execute_batch(SCHEMA_SQL)?;
@@ -62,82 +169,53 @@ impl Database {
         Ok(())
     }
 
-    /// Run database migrations for existing tables
+    /// Apply every migration newer than the recorded schema version, each in its own
+    /// transaction that also records the new version
     fn run_migrations(&self) -> Result<()> {
-        // Add favorited column to symbols table if it doesn't exist
-        let columns: Vec<String> = self
-            .conn
-            .prepare("PRAGMA table_info(symbols)")?
-            .query_map([], |row| row.get::<_, String>(1))?
-            .collect::<SqliteResult<Vec<_>>>()?;
-
-        if !columns.contains(&"favorited".to_string()) {
-            self.conn.execute(
-                "ALTER TABLE symbols ADD COLUMN favorited INTEGER DEFAULT 0",
-                [],
-            )?;
-            println!("[MIGRATION] Added favorited column to symbols table");
-        }
-
-        // Migrate ai_trader_config table with new guardrails columns
-        let ai_config_columns: Vec<String> = self
-            .conn
-            .prepare("PRAGMA table_info(ai_trader_config)")?
-            .query_map([], |row| row.get::<_, String>(1))?
-            .collect::<SqliteResult<Vec<_>>>()?;
-
-        // Add trading_mode column
-        if !ai_config_columns.contains(&"trading_mode".to_string()) {
-            self.conn.execute(
-                "ALTER TABLE ai_trader_config ADD COLUMN trading_mode TEXT NOT NULL DEFAULT 'normal'",
-                [],
+        let current = self.schema_version()?;
+        for migration in migrations() {
+            if migration.version <= current {
+                continue;
+            }
+            let tx = self.conn.unchecked_transaction()?;
+            (migration.apply)(&tx)?;
+            tx.execute(
+                "INSERT INTO schema_version (version, description) VALUES (?1, ?2)",
+                params![migration.version, migration.description],
             )?;
-            println!("[MIGRATION] Added trading_mode column to ai_trader_config");
-        }
-
-        // Add circuit breaker columns
-        if !ai_config_columns.contains(&"daily_loss_threshold".to_string()) {
-            self.conn.execute_batch(r#"
-                ALTER TABLE ai_trader_config ADD COLUMN daily_loss_threshold REAL NOT NULL DEFAULT -10.0;
-                ALTER TABLE ai_trader_config ADD COLUMN consecutive_loss_limit INTEGER NOT NULL DEFAULT 5;
-                ALTER TABLE ai_trader_config ADD COLUMN auto_conservative_on_trigger INTEGER NOT NULL DEFAULT 1;
-                ALTER TABLE ai_trader_config ADD COLUMN circuit_breaker_triggered INTEGER NOT NULL DEFAULT 0;
-                ALTER TABLE ai_trader_config ADD COLUMN circuit_breaker_until TIMESTAMP;
-            "#)?;
-            println!("[MIGRATION] Added circuit breaker columns to ai_trader_config");
-        }
-
-        // Add override columns
-        if !ai_config_columns.contains(&"override_enabled".to_string()) {
-            self.conn.execute_batch(r#"
-                ALTER TABLE ai_trader_config ADD COLUMN override_enabled INTEGER NOT NULL DEFAULT 0;
-                ALTER TABLE ai_trader_config ADD COLUMN override_expires_at TIMESTAMP;
-                ALTER TABLE ai_trader_config ADD COLUMN override_max_position_pct REAL;
-            "#)?;
-            println!("[MIGRATION] Added override columns to ai_trader_config");
-        }
-
-        // Add guardrail columns
-        if !ai_config_columns.contains(&"max_daily_trades".to_string()) {
-            self.conn.execute_batch(r#"
-                ALTER TABLE ai_trader_config ADD COLUMN max_daily_trades INTEGER NOT NULL DEFAULT 10;
-                ALTER TABLE ai_trader_config ADD COLUMN max_single_trade_value REAL NOT NULL DEFAULT 50000.0;
-                ALTER TABLE ai_trader_config ADD COLUMN require_confluence INTEGER NOT NULL DEFAULT 1;
-                ALTER TABLE ai_trader_config ADD COLUMN blocked_hours TEXT DEFAULT '09:30-09:45,15:45-16:00';
-            "#)?;
-            println!("[MIGRATION] Added guardrail columns to ai_trader_config");
+            tx.commit()?;
+            println!(
+                "[MIGRATION] Schema version {}: {}",
+                migration.version, migration.description
+            );
         }
-
         Ok(())
     }
 
-    /// Insert or update a symbol
+    /// Highest migration version applied to this database (0 before any have run)
+    pub fn schema_version(&self) -> Result<i64> {
+        read_schema_version(&self.conn)
+    }
+
+    /// Insert a symbol, or fill in metadata still missing on an existing row.
+    /// Non-null values already stored (and the favorited flag) are kept.
     pub fn upsert_symbol(&self, symbol: &Symbol) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT OR REPLACE INTO symbols
+            INSERT INTO symbols
             (symbol, name, sector, industry, market_cap, country, exchange, currency, isin, asset_class)
             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            ON CONFLICT(symbol) DO UPDATE SET
+                name = COALESCE(symbols.name, excluded.name),
+                sector = COALESCE(symbols.sector, excluded.sector),
+                industry = COALESCE(symbols.industry, excluded.industry),
+                market_cap = COALESCE(symbols.market_cap, excluded.market_cap),
+                country = COALESCE(symbols.country, excluded.country),
+                exchange = COALESCE(symbols.exchange, excluded.exchange),
+                currency = COALESCE(symbols.currency, excluded.currency),
+                isin = COALESCE(symbols.isin, excluded.isin),
+                asset_class = COALESCE(symbols.asset_class, excluded.asset_class),
+                updated_at = CURRENT_TIMESTAMP
             "#,
             params![
                 symbol.symbol,
@@ -155,13 +233,114 @@ impl Database {
         Ok(())
     }
 
+    /// Get a symbol's metadata row, if it exists
+    pub fn get_symbol(&self, symbol: &str) -> Result<Option<Symbol>> {
+        let row = self
+            .conn
+            .query_row(
+                r#"
+                SELECT symbol, name, sector, industry, market_cap, country, exchange, currency, isin, asset_class
+                FROM symbols WHERE symbol = ?1
+                "#,
+                params![symbol],
+                |row| {
+                    Ok(Symbol {
+                        symbol: row.get(0)?,
+                        name: row.get(1)?,
+                        sector: row.get(2)?,
+                        industry: row.get(3)?,
+                        market_cap: row.get(4)?,
+                        country: row.get(5)?,
+                        exchange: row.get(6)?,
+                        currency: row.get(7)?,
+                        isin: row.get(8)?,
+                        asset_class: row.get(9)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(row)
+    }
+
+    /// Add (or repoint) a search alias for a symbol
+    pub fn add_symbol_alias(&self, alias: &str, symbol: &str) -> Result<()> {
+        let alias = alias.trim().to_lowercase();
+        let symbol = symbol.trim().to_uppercase();
+        if alias.is_empty() || symbol.is_empty() {
+            return Err(crate::error::PipelineError::ApiError(
+                "Alias and symbol are required".to_string(),
+            ));
+        }
+
+        self.conn.execute(
+            r#"INSERT INTO symbol_aliases (alias, symbol) VALUES (?1, ?2)
+               ON CONFLICT(alias) DO UPDATE SET symbol = excluded.symbol"#,
+            params![alias, symbol],
+        )?;
+        Ok(())
+    }
+
+    /// Fuzzy search symbols by ticker, alias or company name, best matches first
+    pub fn search_symbols(&self, query: &str, limit: usize) -> Result<Vec<SymbolMatch>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT a.symbol, a.alias, s.name FROM symbol_aliases a
+            LEFT JOIN symbols s ON s.symbol = a.symbol
+            UNION ALL
+            SELECT symbol, name, name FROM symbols
+            "#,
+        )?;
+        let candidates = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        // Best score per symbol across its ticker, aliases and stored name
+        let mut best: HashMap<String, SymbolMatch> = HashMap::new();
+        for (symbol, text, name) in candidates {
+            let score = text
+                .as_deref()
+                .map(|t| match_score(query, t))
+                .unwrap_or(0.0)
+                .max(match_score(query, &symbol));
+            if score < MIN_MATCH_SCORE {
+                continue;
+            }
+
+            let entry = best.entry(symbol.clone()).or_insert(SymbolMatch {
+                symbol,
+                name: None,
+                score,
+            });
+            entry.score = entry.score.max(score);
+            if entry.name.is_none() {
+                entry.name = name;
+            }
+        }
+
+        let mut matches: Vec<SymbolMatch> = best.into_values().collect();
+        matches.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.symbol.cmp(&b.symbol))
+        });
+        matches.truncate(limit);
+        Ok(matches)
+    }
+
     /// Insert or update daily price data
     pub fn upsert_daily_price(&self, price: &DailyPrice) -> Result<()> {
         self.conn.execute(
             r#"
             INSERT OR REPLACE INTO daily_prices
-            (symbol, timestamp, open, high, low, close, volume, source)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            (symbol, timestamp, open, high, low, close, volume, source, adjusted_close)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
             "#,
             params![
                 price.symbol,
@@ -172,6 +351,7 @@ impl Database {
                 price.close,
                 price.volume,
                 price.source,
+                price.adjusted_close,
             ],
         )?;
         Ok(())
@@ -186,8 +366,8 @@ impl Database {
             let mut stmt = tx.prepare(
                 r#"
                 INSERT OR REPLACE INTO daily_prices
-                (symbol, timestamp, open, high, low, close, volume, source)
-                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                (symbol, timestamp, open, high, low, close, volume, source, adjusted_close)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
                 "#,
             )?;
 
@@ -201,6 +381,41 @@ impl Database {
                     price.close,
                     price.volume,
                     price.source,
+                    price.adjusted_close,
+                ])?;
+                count += 1;
+            }
+        }
+
+        tx.commit()?;
+        Ok(count)
+    }
+
+    /// Batch store intraday bars, one row per (symbol, timestamp, resolution)
+    pub fn upsert_intraday_prices(&mut self, prices: &[IntradayPrice]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut count = 0;
+
+        {
+            let mut stmt = tx.prepare(
+                r#"
+                INSERT OR REPLACE INTO intraday_prices
+                (symbol, timestamp, resolution, open, high, low, close, volume, source)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                "#,
+            )?;
+
+            for price in prices {
+                stmt.execute(params![
+                    price.symbol,
+                    price.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+                    price.resolution,
+                    price.open,
+                    price.high,
+                    price.low,
+                    price.close,
+                    price.volume,
+                    price.source,
                 ])?;
                 count += 1;
             }
@@ -210,14 +425,100 @@ impl Database {
         Ok(count)
     }
 
+    /// Get stored intraday bars for a symbol at one resolution, oldest first
+    pub fn get_intraday_prices(&self, symbol: &str, resolution: &str) -> Result<Vec<IntradayPrice>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT symbol, timestamp, resolution, open, high, low, close, volume, source
+            FROM intraday_prices
+            WHERE symbol = ?1 AND resolution = ?2
+            ORDER BY timestamp ASC
+            "#,
+        )?;
+
+        let prices = stmt
+            .query_map(params![symbol, resolution], |row| {
+                let ts: String = row.get(1)?;
+                Ok(IntradayPrice {
+                    symbol: row.get(0)?,
+                    timestamp: DateTime::parse_from_rfc3339(&ts)
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .unwrap_or_default(),
+                    resolution: row.get(2)?,
+                    open: row.get(3)?,
+                    high: row.get(4)?,
+                    low: row.get(5)?,
+                    close: row.get(6)?,
+                    volume: row.get(7)?,
+                    source: row.get(8)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(prices)
+    }
+
+    /// Reaction to an event from stored intraday bars: the close of the last bar that
+    /// finished before `event_time` (at most `hours_window` earlier) against the close of
+    /// the last bar opening within `hours_window` after it. `None` when either side has no
+    /// bar, so callers can fall back to daily prices.
+    pub fn intraday_price_reaction(
+        &self,
+        symbol: &str,
+        resolution: &str,
+        event_time: DateTime<Utc>,
+        hours_window: i64,
+    ) -> Result<Option<PriceReaction>> {
+        let bars = self.get_intraday_prices(symbol, resolution)?;
+        let bar_length = chrono::Duration::minutes(resolution.parse().unwrap_or(0));
+        let window = chrono::Duration::hours(hours_window.max(1));
+
+        let pre = bars
+            .iter()
+            .rfind(|b| b.timestamp + bar_length <= event_time && b.timestamp >= event_time - window);
+        let post_bars: Vec<&IntradayPrice> = bars
+            .iter()
+            .filter(|b| b.timestamp >= event_time && b.timestamp <= event_time + window)
+            .collect();
+        let (Some(pre), Some(post)) = (pre, post_bars.last()) else {
+            return Ok(None);
+        };
+
+        // Average bar volume after the event vs. the last bar before it
+        let post_volume = post_bars.iter().map(|b| b.volume as f64).sum::<f64>() / post_bars.len() as f64;
+        let volume_change_percent = if pre.volume > 0 {
+            (post_volume - pre.volume as f64) / pre.volume as f64 * 100.0
+        } else {
+            0.0
+        };
+
+        Ok(Some(PriceReaction {
+            symbol: symbol.to_string(),
+            event_date: event_time.to_rfc3339_opts(SecondsFormat::Secs, true),
+            start_date: pre.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+            end_date: post.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+            pre_price: pre.close,
+            post_price: post.close,
+            price_change_percent: (post.close - pre.close) / pre.close * 100.0,
+            volume_change_percent,
+            candle_count: post_bars.len() + 1,
+        }))
+    }
+
     /// Insert macro data
     pub fn upsert_macro_data(&self, data: &MacroData) -> Result<()> {
         self.conn.execute(
             r#"
-            INSERT OR REPLACE INTO macro_data (indicator, date, value, source)
-            VALUES (?1, ?2, ?3, ?4)
+            INSERT OR REPLACE INTO macro_data (indicator, date, value, source, frequency)
+            VALUES (?1, ?2, ?3, ?4, ?5)
             "#,
-            params![data.indicator, data.date.to_string(), data.value, data.source,],
+            params![
+                data.indicator,
+                data.date.to_string(),
+                data.value,
+                data.source,
+                data.frequency.as_deref().unwrap_or(""),
+            ],
         )?;
         Ok(())
     }
@@ -230,13 +531,19 @@ impl Database {
         {
             let mut stmt = tx.prepare(
                 r#"
-                INSERT OR REPLACE INTO macro_data (indicator, date, value, source)
-                VALUES (?1, ?2, ?3, ?4)
+                INSERT OR REPLACE INTO macro_data (indicator, date, value, source, frequency)
+                VALUES (?1, ?2, ?3, ?4, ?5)
                 "#,
             )?;
 
             for d in data {
-                stmt.execute(params![d.indicator, d.date.to_string(), d.value, d.source,])?;
+                stmt.execute(params![
+                    d.indicator,
+                    d.date.to_string(),
+                    d.value,
+                    d.source,
+                    d.frequency.as_deref().unwrap_or(""),
+                ])?;
                 count += 1;
             }
         }
@@ -249,7 +556,7 @@ impl Database {
     pub fn get_macro_data(&self, indicator: &str) -> Result<Vec<MacroData>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT indicator, date, value, source
+            SELECT indicator, date, value, source, frequency
             FROM macro_data
             WHERE indicator = ?1
             ORDER BY date DESC
@@ -258,21 +565,43 @@ impl Database {
         )?;
 
         let data = stmt
-            .query_map(params![indicator], |row| {
-                let date_str: String = row.get(1)?;
-                Ok(MacroData {
-                    indicator: row.get(0)?,
-                    date: NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                        .unwrap_or_else(|_| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()),
-                    value: row.get(2)?,
-                    source: row.get(3)?,
-                })
-            })?
+            .query_map(params![indicator], Self::map_macro_data)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(data)
+    }
+
+    /// Get an indicator's observations from the last `days` days, oldest first (for charting)
+    pub fn get_macro_history(&self, indicator: &str, days: i32) -> Result<Vec<MacroData>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT indicator, date, value, source, frequency
+            FROM macro_data
+            WHERE indicator = ?1 AND date >= date('now', '-' || ?2 || ' days')
+            ORDER BY frequency, date ASC
+            "#,
+        )?;
+
+        let data = stmt
+            .query_map(params![indicator, days], Self::map_macro_data)?
             .collect::<SqliteResult<Vec<_>>>()?;
 
         Ok(data)
     }
 
+    fn map_macro_data(row: &rusqlite::Row) -> SqliteResult<MacroData> {
+        let date_str: String = row.get(1)?;
+        let frequency: String = row.get(4)?;
+        Ok(MacroData {
+            indicator: row.get(0)?,
+            date: NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+                .unwrap_or_else(|_| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()),
+            value: row.get(2)?,
+            source: row.get(3)?,
+            frequency: (!frequency.is_empty()).then_some(frequency),
+        })
+    }
+
     /// Get all unique macro indicators
     pub fn get_macro_indicators(&self) -> Result<Vec<String>> {
         let mut stmt = self.conn.prepare(
@@ -288,102 +617,322 @@ impl Database {
         Ok(indicators)
     }
 
-    /// Get latest value for each macro indicator
+    /// Get latest value for each macro indicator (one row per stored frequency)
     pub fn get_macro_summary(&self) -> Result<Vec<MacroData>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT m.indicator, m.date, m.value, m.source
+            SELECT m.indicator, m.date, m.value, m.source, m.frequency
             FROM macro_data m
             INNER JOIN (
-                SELECT indicator, MAX(date) as max_date
+                SELECT indicator, frequency, MAX(date) as max_date
                 FROM macro_data
-                GROUP BY indicator
-            ) latest ON m.indicator = latest.indicator AND m.date = latest.max_date
-            ORDER BY m.indicator
+                GROUP BY indicator, frequency
+            ) latest ON m.indicator = latest.indicator
+                AND m.frequency = latest.frequency
+                AND m.date = latest.max_date
+            ORDER BY m.indicator, m.frequency
             "#,
         )?;
 
         let data = stmt
-            .query_map([], |row| {
-                let date_str: String = row.get(1)?;
-                Ok(MacroData {
-                    indicator: row.get(0)?,
-                    date: NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                        .unwrap_or_else(|_| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()),
-                    value: row.get(2)?,
-                    source: row.get(3)?,
-                })
-            })?
+            .query_map([], Self::map_macro_data)?
             .collect::<SqliteResult<Vec<_>>>()?;
 
         Ok(data)
     }
 
-    /// Log an API call
-    pub fn log_api_call(&self, source: &str, endpoint: &str, symbol: &str) -> Result<()> {
-        self.conn.execute(
-            r#"
-            INSERT INTO api_calls (source, endpoint, symbol, timestamp)
-            VALUES (?1, ?2, ?3, ?4)
-            "#,
-            params![source, endpoint, symbol, Utc::now().to_rfc3339()],
-        )?;
-        Ok(())
+    /// Insert or refresh earnings events (keyed on symbol + date)
+    pub fn upsert_earnings(&mut self, events: &[EarningsEvent]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+
+        {
+            let mut stmt = tx.prepare(
+                r#"
+                INSERT INTO earnings
+                (symbol, date, hour, quarter, year, eps_estimate, eps_actual, revenue_estimate, revenue_actual)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+                ON CONFLICT(symbol, date) DO UPDATE SET
+                    hour = excluded.hour,
+                    quarter = excluded.quarter,
+                    year = excluded.year,
+                    eps_estimate = excluded.eps_estimate,
+                    eps_actual = excluded.eps_actual,
+                    revenue_estimate = excluded.revenue_estimate,
+                    revenue_actual = excluded.revenue_actual,
+                    updated_at = CURRENT_TIMESTAMP
+                "#,
+            )?;
+
+            for e in events {
+                stmt.execute(params![
+                    e.symbol,
+                    e.date.to_string(),
+                    e.hour,
+                    e.quarter,
+                    e.year,
+                    e.eps_estimate,
+                    e.eps_actual,
+                    e.revenue_estimate,
+                    e.revenue_actual,
+                ])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(events.len())
     }
 
-    /// Get latest price for a symbol
-    pub fn get_latest_price(&self, symbol: &str) -> Result<Option<f64>> {
+    /// Earnings events for a symbol on or after `from`, soonest first
+    pub fn get_upcoming_earnings(&self, symbol: &str, from: NaiveDate) -> Result<Vec<EarningsEvent>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT close FROM daily_prices
-            WHERE symbol = ?1
-            ORDER BY timestamp DESC
-            LIMIT 1
+            SELECT symbol, date, hour, quarter, year, eps_estimate, eps_actual, revenue_estimate, revenue_actual
+            FROM earnings
+            WHERE symbol = ?1 AND date >= ?2
+            ORDER BY date ASC
             "#,
         )?;
 
-        let result: SqliteResult<f64> = stmt.query_row(params![symbol], |row| row.get(0));
+        let events = stmt
+            .query_map(params![symbol, from.to_string()], |row| {
+                let date_str: String = row.get(1)?;
+                Ok(EarningsEvent {
+                    symbol: row.get(0)?,
+                    date: NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+                        .unwrap_or_else(|_| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()),
+                    hour: row.get(2)?,
+                    quarter: row.get(3)?,
+                    year: row.get(4)?,
+                    eps_estimate: row.get(5)?,
+                    eps_actual: row.get(6)?,
+                    revenue_estimate: row.get(7)?,
+                    revenue_actual: row.get(8)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
 
-        match result {
-            Ok(price) => Ok(Some(price)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(e.into()),
+        Ok(events)
+    }
+
+    /// Insert or update dividends (keyed by symbol and ex-date)
+    pub fn upsert_dividends(&mut self, dividends: &[Dividend]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+
+        {
+            let mut stmt = tx.prepare(
+                r#"
+                INSERT INTO dividends (symbol, ex_date, amount)
+                VALUES (?1, ?2, ?3)
+                ON CONFLICT(symbol, ex_date) DO UPDATE SET amount = excluded.amount
+                "#,
+            )?;
+
+            for d in dividends {
+                stmt.execute(params![d.symbol, d.ex_date.to_string(), d.amount])?;
+            }
         }
+
+        tx.commit()?;
+        Ok(dividends.len())
     }
 
-    /// Get all prices for a symbol
-    pub fn get_prices(&self, symbol: &str) -> Result<Vec<DailyPrice>> {
+    /// Dividends for a symbol with ex-date on or after `since` (all if None), oldest first
+    pub fn get_dividends(&self, symbol: &str, since: Option<NaiveDate>) -> Result<Vec<Dividend>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT symbol, timestamp, open, high, low, close, volume, source
-            FROM daily_prices
-            WHERE symbol = ?1
-            ORDER BY timestamp ASC
+            SELECT symbol, ex_date, amount
+            FROM dividends
+            WHERE symbol = ?1 AND (?2 IS NULL OR ex_date >= ?2)
+            ORDER BY ex_date ASC
             "#,
         )?;
 
-        let prices = stmt
-            .query_map(params![symbol], |row| {
+        let dividends = stmt
+            .query_map(params![symbol, since.map(|d| d.to_string())], |row| {
                 let date_str: String = row.get(1)?;
-                Ok(DailyPrice {
+                Ok(Dividend {
                     symbol: row.get(0)?,
-                    date: NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+                    ex_date: NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
                         .unwrap_or_else(|_| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()),
-                    open: row.get(2)?,
-                    high: row.get(3)?,
-                    low: row.get(4)?,
-                    close: row.get(5)?,
-                    volume: row.get(6)?,
-                    source: row.get(7)?,
+                    amount: row.get(2)?,
                 })
             })?
             .collect::<SqliteResult<Vec<_>>>()?;
 
-        Ok(prices)
+        Ok(dividends)
     }
 
-    /// Get all symbols with price data
-    pub fn get_symbols_with_data(&self) -> Result<Vec<String>> {
+    /// Dividend income earned on a portfolio holding: each dividend's per-share amount
+    /// times the net shares held before its ex-date (buys minus sells in the position
+    /// ledger). Only ex-dates on or after `since` count when given.
+    pub fn get_position_dividend_income(&self, symbol: &str, since: Option<NaiveDate>) -> Result<f64> {
+        let income = self.conn.query_row(
+            r#"
+            SELECT COALESCE(SUM(d.amount * (
+                SELECT COALESCE(SUM(CASE WHEN p.position_type = 'buy' THEN p.quantity ELSE -p.quantity END), 0)
+                FROM portfolio_positions p
+                WHERE p.symbol = d.symbol AND DATE(p.date) < d.ex_date AND p.deleted_at IS NULL
+            )), 0)
+            FROM dividends d
+            WHERE d.symbol = ?1 AND (?2 IS NULL OR d.ex_date >= ?2)
+            "#,
+            params![symbol, since.map(|d| d.to_string())],
+            |row| row.get(0),
+        )?;
+        Ok(income)
+    }
+
+    /// Log an API call
+    pub fn log_api_call(&self, source: &str, endpoint: &str, symbol: &str) -> Result<()> {
+        self.conn.execute(
+            r#"
+            INSERT INTO api_calls (source, endpoint, symbol, timestamp)
+            VALUES (?1, ?2, ?3, ?4)
+            "#,
+            params![source, endpoint, symbol, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Number of calls logged for a source since midnight UTC
+    pub fn calls_today(&self, source: &str) -> Result<u32> {
+        let count = self.conn.query_row(
+            "SELECT COUNT(*) FROM api_calls WHERE source = ?1 AND DATE(timestamp) = DATE('now')",
+            params![source],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// Number of calls logged for a source at or after `since`
+    pub fn calls_since(&self, source: &str, since: DateTime<Utc>) -> Result<u32> {
+        let count = self.conn.query_row(
+            "SELECT COUNT(*) FROM api_calls WHERE source = ?1 AND julianday(timestamp) >= julianday(?2)",
+            params![source, since.to_rfc3339()],
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
+    /// Get latest price for a symbol
+    pub fn get_latest_price(&self, symbol: &str) -> Result<Option<f64>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT close FROM daily_prices
+            WHERE symbol = ?1
+            ORDER BY timestamp DESC
+            LIMIT 1
+            "#,
+        )?;
+
+        let result: SqliteResult<f64> = stmt.query_row(params![symbol], |row| row.get(0));
+
+        match result {
+            Ok(price) => Ok(Some(price)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Get all prices for a symbol
+    pub fn get_prices(&self, symbol: &str) -> Result<Vec<DailyPrice>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT symbol, timestamp, open, high, low, close, volume, source, adjusted_close
+            FROM daily_prices
+            WHERE symbol = ?1
+            ORDER BY timestamp ASC
+            "#,
+        )?;
+
+        let prices = stmt
+            .query_map(params![symbol], Self::map_daily_price)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(prices)
+    }
+
+    /// Get a page of prices for a symbol, oldest first
+    pub fn get_prices_paginated(
+        &self,
+        symbol: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<DailyPrice>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT symbol, timestamp, open, high, low, close, volume, source, adjusted_close
+            FROM daily_prices
+            WHERE symbol = ?1
+            ORDER BY timestamp ASC
+            LIMIT ?2 OFFSET ?3
+            "#,
+        )?;
+
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let offset = i64::try_from(offset).unwrap_or(i64::MAX);
+
+        let prices = stmt
+            .query_map(params![symbol, limit, offset], Self::map_daily_price)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(prices)
+    }
+
+    /// Get prices for a symbol between two dates (inclusive), oldest first
+    pub fn get_price_range(
+        &self,
+        symbol: &str,
+        from_date: NaiveDate,
+        to_date: NaiveDate,
+    ) -> Result<Vec<DailyPrice>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT symbol, timestamp, open, high, low, close, volume, source, adjusted_close
+            FROM daily_prices
+            WHERE symbol = ?1 AND timestamp >= ?2 AND timestamp <= ?3
+            ORDER BY timestamp ASC
+            "#,
+        )?;
+
+        let prices = stmt
+            .query_map(
+                params![symbol, from_date.to_string(), to_date.to_string()],
+                Self::map_daily_price,
+            )?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(prices)
+    }
+
+    fn map_daily_price(row: &rusqlite::Row) -> SqliteResult<DailyPrice> {
+        let date_str: String = row.get(1)?;
+        Ok(DailyPrice {
+            symbol: row.get(0)?,
+            date: NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+                .unwrap_or_else(|_| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()),
+            open: row.get(2)?,
+            high: row.get(3)?,
+            low: row.get(4)?,
+            close: row.get(5)?,
+            volume: row.get(6)?,
+            adjusted_close: row.get(8)?,
+            source: row.get(7)?,
+        })
+    }
+
+    /// Get all prices for a symbol with OHLC adjusted for splits/dividends
+    /// Rows without a stored adjusted close keep their raw values
+    pub fn get_adjusted_prices(&self, symbol: &str) -> Result<Vec<DailyPrice>> {
+        Ok(self
+            .get_prices(symbol)?
+            .iter()
+            .map(DailyPrice::adjusted)
+            .collect())
+    }
+
+    /// Get all symbols with price data
+    pub fn get_symbols_with_data(&self) -> Result<Vec<String>> {
         let mut stmt = self
             .conn
             .prepare("SELECT DISTINCT symbol FROM daily_prices")?;
@@ -393,6 +942,84 @@ impl Database {
         Ok(symbols)
     }
 
+    /// Latest daily bar of every symbol with price data and how many days old it is,
+    /// flagged stale past the refresh settings' `stale_after_days`. Oldest first.
+    pub fn get_data_freshness(&self) -> Result<Vec<DataFreshness>> {
+        let stale_after_days = self.get_refresh_settings()?.stale_after_days as i64;
+        let today = Utc::now().date_naive();
+
+        let mut stmt = self.conn.prepare(
+            "SELECT symbol, MAX(timestamp) FROM daily_prices GROUP BY symbol ORDER BY MAX(timestamp), symbol",
+        )?;
+        let freshness = stmt
+            .query_map([], |row| {
+                let date_str: String = row.get(1)?;
+                let last_date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+                    .unwrap_or_else(|_| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
+                let days_stale = (today - last_date).num_days();
+                Ok(DataFreshness {
+                    symbol: row.get(0)?,
+                    last_date,
+                    days_stale,
+                    stale: days_stale > stale_after_days,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(freshness)
+    }
+
+    /// High/low (with the dates they were set) and average close and volume over the
+    /// latest `days` daily bars, or all of them if there are fewer. `None` without prices.
+    /// Ties go to the most recent bar.
+    pub fn get_price_stats(&self, symbol: &str, days: u32) -> Result<Option<PriceStats>> {
+        if days == 0 {
+            return Err(crate::error::PipelineError::ApiError(
+                "Price stats need at least 1 day".to_string(),
+            ));
+        }
+        let stats = self
+            .conn
+            .query_row(
+                r#"
+                WITH recent AS (
+                    SELECT timestamp, high, low, close, volume FROM daily_prices
+                    WHERE symbol = ?1
+                    ORDER BY timestamp DESC
+                    LIMIT ?2
+                )
+                SELECT COUNT(*),
+                       MAX(high),
+                       (SELECT timestamp FROM recent ORDER BY high DESC, timestamp DESC LIMIT 1),
+                       MIN(low),
+                       (SELECT timestamp FROM recent ORDER BY low ASC, timestamp DESC LIMIT 1),
+                       AVG(close),
+                       AVG(volume)
+                FROM recent
+                HAVING COUNT(*) > 0
+                "#,
+                params![symbol, days],
+                |row| {
+                    let date = |idx: usize| -> SqliteResult<NaiveDate> {
+                        let date_str: String = row.get(idx)?;
+                        Ok(NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+                            .unwrap_or_else(|_| NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()))
+                    };
+                    Ok(PriceStats {
+                        symbol: symbol.to_string(),
+                        bars: row.get(0)?,
+                        high: row.get(1)?,
+                        high_date: date(2)?,
+                        low: row.get(3)?,
+                        low_date: date(4)?,
+                        avg_close: row.get(5)?,
+                        avg_volume: row.get(6)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(stats)
+    }
+
     /// Clear price data for a symbol
     pub fn clear_symbol_prices(&self, symbol: &str) -> Result<()> {
         self.conn.execute(
@@ -490,7 +1117,11 @@ impl Database {
         symbols: &[String],
         description: Option<&str>,
     ) -> Result<i64> {
-        // Delete existing watchlist entries
+        // Delete existing watchlist entries (symbols first, foreign key)
+        self.conn.execute(
+            "DELETE FROM watchlist_symbols WHERE watchlist_id IN (SELECT id FROM watchlists WHERE name = ?1)",
+            params![name],
+        )?;
         self.conn
             .execute("DELETE FROM watchlists WHERE name = ?1", params![name])?;
 
@@ -697,6 +1328,65 @@ impl Database {
         Ok(())
     }
 
+    /// Copy the database to `path` with SQLite's online backup API (safe while in use)
+    pub fn backup_to<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.conn.backup(DatabaseName::Main, path, None)?;
+        println!("[OK] Database backed up to {}", path.display());
+        Ok(())
+    }
+
+    /// Replace this database's contents with the backup at `path`.
+    /// The source must pass an integrity check and have the same schema version,
+    /// otherwise nothing is overwritten.
+    pub fn restore_from<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Err(crate::error::PipelineError::ApiError(format!(
+                "Backup file not found: {}",
+                path.display()
+            )));
+        }
+
+        let unreadable = |e: rusqlite::Error| {
+            crate::error::PipelineError::ApiError(format!(
+                "{} is not a readable SQLite database: {}",
+                path.display(),
+                e
+            ))
+        };
+        let source = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(unreadable)?;
+        let check: String = source
+            .query_row("PRAGMA quick_check", [], |row| row.get(0))
+            .map_err(unreadable)?;
+        if check != "ok" {
+            return Err(crate::error::PipelineError::ApiError(format!(
+                "Backup {} failed integrity check: {}",
+                path.display(),
+                check
+            )));
+        }
+
+        let source_version = read_schema_version(&source)?;
+        let current_version = self.schema_version()?;
+        if source_version != current_version {
+            return Err(crate::error::PipelineError::ApiError(format!(
+                "Backup schema version {} does not match database version {}",
+                source_version, current_version
+            )));
+        }
+        drop(source);
+
+        self.conn
+            .restore(DatabaseName::Main, path, None::<fn(rusqlite::backup::Progress)>)?;
+        println!("[OK] Database restored from {}", path.display());
+        Ok(())
+    }
+
     /// Store a technical indicator value
     pub fn upsert_indicator(&self, ind: &TechnicalIndicator) -> Result<()> {
         self.conn.execute(
@@ -739,6 +1429,50 @@ impl Database {
         Ok(count)
     }
 
+    /// Calculate and store indicators for every symbol with price data, extending
+    /// the stored series unless `full_recompute` is set. Symbols with fewer than
+    /// `MIN_INDICATOR_BARS` bars are skipped. Calculation runs in parallel; the reads
+    /// before it and the writes after it stay on this connection.
+    pub fn calculate_all_indicators(&mut self, full_recompute: bool) -> Result<Vec<IndicatorRunSummary>> {
+        let mut symbols = self.get_symbols_with_data()?;
+        symbols.sort();
+
+        let mut inputs = Vec::with_capacity(symbols.len());
+        for symbol in symbols {
+            let prices = self.get_prices(&symbol)?;
+            let existing = if full_recompute || prices.len() < MIN_INDICATOR_BARS {
+                Vec::new()
+            } else {
+                self.get_latest_indicators(&symbol)?
+            };
+            inputs.push((symbol, prices, existing));
+        }
+
+        let computed: Vec<(String, usize, Option<Vec<TechnicalIndicator>>)> = inputs
+            .into_par_iter()
+            .map(|(symbol, prices, existing)| {
+                let indicators = (prices.len() >= MIN_INDICATOR_BARS)
+                    .then(|| calculate_incremental(&prices, &existing));
+                (symbol, prices.len(), indicators)
+            })
+            .collect();
+
+        let mut summary = Vec::with_capacity(computed.len());
+        for (symbol, bars, indicators) in computed {
+            let written = match &indicators {
+                Some(indicators) => self.upsert_indicators(indicators)?,
+                None => 0,
+            };
+            summary.push(IndicatorRunSummary {
+                symbol,
+                bars,
+                indicators: written,
+                skipped: indicators.is_none(),
+            });
+        }
+        Ok(summary)
+    }
+
     /// Get latest indicators for a symbol
     pub fn get_latest_indicators(&self, symbol: &str) -> Result<Vec<TechnicalIndicator>> {
         let mut stmt = self.conn.prepare(
@@ -772,7 +1506,40 @@ impl Database {
         Ok(indicators)
     }
 
-    /// Get indicator history for a symbol
+    /// Distinct indicator names stored for a symbol, sorted
+    pub fn get_available_indicators(&self, symbol: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT DISTINCT indicator_name
+            FROM technical_indicators
+            WHERE symbol = ?1
+            ORDER BY indicator_name
+            "#,
+        )?;
+
+        let names = stmt
+            .query_map(params![symbol], |row| row.get(0))?
+            .collect::<SqliteResult<Vec<String>>>()?;
+
+        Ok(names)
+    }
+
+    /// Date of the most recent stored value of an indicator, if any
+    pub fn get_last_indicator_date(
+        &self,
+        symbol: &str,
+        indicator_name: &str,
+    ) -> Result<Option<NaiveDate>> {
+        let date: Option<String> = self.conn.query_row(
+            "SELECT MAX(timestamp) FROM technical_indicators WHERE symbol = ?1 AND indicator_name = ?2",
+            params![symbol, canonical_indicator_name(indicator_name)],
+            |row| row.get(0),
+        )?;
+        Ok(date.and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()))
+    }
+
+    /// Get indicator history for a symbol (legacy unqualified names are accepted)
+    /// Projected series (Ichimoku Senkou spans) include rows dated after the last price bar
     pub fn get_indicator_history(
         &self,
         symbol: &str,
@@ -788,7 +1555,7 @@ impl Database {
         )?;
 
         let indicators = stmt
-            .query_map(params![symbol, indicator_name], |row| {
+            .query_map(params![symbol, canonical_indicator_name(indicator_name)], |row| {
                 let date_str: String = row.get(1)?;
                 Ok(TechnicalIndicator {
                     symbol: row.get(0)?,
@@ -803,12 +1570,10 @@ impl Database {
         Ok(indicators)
     }
 
-    /// Add a price alert
+    /// Add a price alert. Relative conditions store their percent/multiple in `target_price`.
     pub fn add_alert(&self, symbol: &str, target_price: f64, condition: AlertCondition) -> Result<i64> {
-        let condition_str = match condition {
-            AlertCondition::Above => "above",
-            AlertCondition::Below => "below",
-        };
+        let condition_str = condition.as_str();
+        let target_price = condition.threshold().unwrap_or(target_price);
 
         self.conn.execute(
             r#"
@@ -818,7 +1583,10 @@ impl Database {
             params![symbol, target_price, condition_str],
         )?;
 
-        Ok(self.conn.last_insert_rowid())
+        let alert_id = self.conn.last_insert_rowid();
+        let created = self.get_alert(alert_id)?;
+        self.record_audit(AUDIT_PRICE_ALERT, alert_id, "create", None, created.as_ref())?;
+        Ok(alert_id)
     }
 
     /// Get all alerts (optionally filter by triggered status)
@@ -832,37 +1600,59 @@ impl Database {
         let mut stmt = self.conn.prepare(sql)?;
 
         let alerts = stmt
-            .query_map([], |row| {
-                let condition_str: String = row.get(3)?;
-                let condition = if condition_str == "above" {
-                    AlertCondition::Above
-                } else {
-                    AlertCondition::Below
-                };
-
-                Ok(PriceAlert {
-                    id: row.get(0)?,
-                    symbol: row.get(1)?,
-                    target_price: row.get(2)?,
-                    condition,
-                    triggered: row.get(4)?,
-                    created_at: row.get(5)?,
-                })
-            })?
+            .query_map([], Self::map_price_alert)?
             .collect::<SqliteResult<Vec<_>>>()?;
 
         Ok(alerts)
     }
 
+    /// Get a single alert by id
+    pub fn get_alert(&self, alert_id: i64) -> Result<Option<PriceAlert>> {
+        let alert = self
+            .conn
+            .query_row(
+                "SELECT id, symbol, target_price, condition, triggered, created_at FROM price_alerts WHERE id = ?1",
+                params![alert_id],
+                Self::map_price_alert,
+            )
+            .optional()?;
+        Ok(alert)
+    }
+
+    fn map_price_alert(row: &rusqlite::Row) -> SqliteResult<PriceAlert> {
+        let target_price: f64 = row.get(2)?;
+        let condition_str: String = row.get(3)?;
+        let condition = AlertCondition::from_str(&condition_str, target_price)
+            .unwrap_or(AlertCondition::Below);
+
+        Ok(PriceAlert {
+            id: row.get(0)?,
+            symbol: row.get(1)?,
+            target_price,
+            condition,
+            triggered: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    }
+
     /// Delete an alert
     pub fn delete_alert(&self, alert_id: i64) -> Result<()> {
+        let before = self.get_alert(alert_id)?;
         self.conn.execute("DELETE FROM price_alerts WHERE id = ?1", params![alert_id])?;
+        if before.is_some() {
+            self.record_audit(AUDIT_PRICE_ALERT, alert_id, "delete", before.as_ref(), None)?;
+        }
         Ok(())
     }
 
     /// Mark an alert as triggered
     pub fn trigger_alert(&self, alert_id: i64) -> Result<()> {
+        let before = self.get_alert(alert_id)?;
         self.conn.execute("UPDATE price_alerts SET triggered = 1 WHERE id = ?1", params![alert_id])?;
+        if before.is_some() {
+            let after = self.get_alert(alert_id)?;
+            self.record_audit(AUDIT_PRICE_ALERT, alert_id, "update", before.as_ref(), after.as_ref())?;
+        }
         Ok(())
     }
 
@@ -872,25 +1662,72 @@ impl Database {
         let mut triggered = Vec::new();
 
         for alert in alerts {
-            if let Ok(Some(current_price)) = self.get_latest_price(&alert.symbol) {
-                let should_trigger = match alert.condition {
-                    AlertCondition::Above => current_price >= alert.target_price,
-                    AlertCondition::Below => current_price <= alert.target_price,
-                };
-
-                if should_trigger {
-                    self.trigger_alert(alert.id)?;
-                    triggered.push(PriceAlert {
-                        triggered: true,
-                        ..alert
-                    });
+            let should_trigger = match alert.condition {
+                AlertCondition::Above | AlertCondition::Below => {
+                    match self.get_latest_price(&alert.symbol) {
+                        Ok(Some(current_price)) => match alert.condition {
+                            AlertCondition::Above => current_price >= alert.target_price,
+                            _ => current_price <= alert.target_price,
+                        },
+                        _ => false,
+                    }
+                }
+                _ => {
+                    let bars = self.get_latest_bars(&alert.symbol, 2)?;
+                    match bars.as_slice() {
+                        [latest, previous] => Self::relative_condition_met(alert.condition, previous, latest),
+                        _ => false,
+                    }
                 }
+            };
+
+            if should_trigger {
+                self.trigger_alert(alert.id)?;
+                triggered.push(PriceAlert {
+                    triggered: true,
+                    ..alert
+                });
             }
         }
 
         Ok(triggered)
     }
 
+    /// Evaluate a percent-change or volume-spike condition between two consecutive bars
+    fn relative_condition_met(condition: AlertCondition, previous: &DailyPrice, latest: &DailyPrice) -> bool {
+        match condition {
+            AlertCondition::PercentChangeUp(pct) if previous.close > 0.0 => {
+                (latest.close - previous.close) / previous.close * 100.0 >= pct.abs()
+            }
+            AlertCondition::PercentChangeDown(pct) if previous.close > 0.0 => {
+                (previous.close - latest.close) / previous.close * 100.0 >= pct.abs()
+            }
+            AlertCondition::VolumeMultiple(multiple) if previous.volume > 0 => {
+                latest.volume as f64 >= previous.volume as f64 * multiple
+            }
+            _ => false,
+        }
+    }
+
+    /// Get the most recent `count` bars for a symbol, newest first
+    pub fn get_latest_bars(&self, symbol: &str, count: usize) -> Result<Vec<DailyPrice>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT symbol, timestamp, open, high, low, close, volume, source, adjusted_close
+            FROM daily_prices
+            WHERE symbol = ?1
+            ORDER BY timestamp DESC
+            LIMIT ?2
+            "#,
+        )?;
+
+        let prices = stmt
+            .query_map(params![symbol, count as i64], Self::map_daily_price)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(prices)
+    }
+
     /// Add a portfolio position
     pub fn add_position(
         &self,
@@ -914,50 +1751,382 @@ impl Database {
             params![symbol, quantity, price, type_str, date, notes],
         )?;
 
-        Ok(self.conn.last_insert_rowid())
+        let position_id = self.conn.last_insert_rowid();
+        let created = self.get_position(position_id)?;
+        self.record_audit(AUDIT_POSITION, position_id, "create", None, created.as_ref())?;
+        Ok(position_id)
     }
 
-    /// Get all portfolio positions
+    /// Get all portfolio positions, excluding soft-deleted ones
     pub fn get_positions(&self) -> Result<Vec<Position>> {
+        self.get_positions_filtered(false)
+    }
+
+    /// Get portfolio positions, optionally including soft-deleted ones
+    pub fn get_positions_filtered(&self, include_deleted: bool) -> Result<Vec<Position>> {
         let mut stmt = self.conn.prepare(
             r#"
             SELECT id, symbol, quantity, price, position_type, date, notes
             FROM portfolio_positions
+            WHERE ?1 OR deleted_at IS NULL
             ORDER BY date DESC
             "#,
         )?;
 
         let positions = stmt
-            .query_map([], |row| {
-                let type_str: String = row.get(4)?;
-                let position_type = if type_str == "buy" {
-                    PositionType::Buy
-                } else {
-                    PositionType::Sell
-                };
-
-                Ok(Position {
-                    id: row.get(0)?,
-                    symbol: row.get(1)?,
-                    quantity: row.get(2)?,
-                    price: row.get(3)?,
-                    position_type,
-                    date: row.get(5)?,
-                    notes: row.get(6)?,
-                })
-            })?
+            .query_map(params![include_deleted], Self::map_position)?
             .collect::<SqliteResult<Vec<_>>>()?;
 
         Ok(positions)
     }
 
-    /// Delete a portfolio position
-    pub fn delete_position(&self, position_id: i64) -> Result<()> {
-        self.conn.execute(
-            "DELETE FROM portfolio_positions WHERE id = ?1",
-            params![position_id],
-        )?;
-        Ok(())
+    /// Get a single position by id, whether or not it has been deleted
+    pub fn get_position(&self, position_id: i64) -> Result<Option<Position>> {
+        let position = self
+            .conn
+            .query_row(
+                r#"
+                SELECT id, symbol, quantity, price, position_type, date, notes
+                FROM portfolio_positions
+                WHERE id = ?1
+                "#,
+                params![position_id],
+                Self::map_position,
+            )
+            .optional()?;
+        Ok(position)
+    }
+
+    fn map_position(row: &rusqlite::Row) -> SqliteResult<Position> {
+        let type_str: String = row.get(4)?;
+        let position_type = if type_str == "buy" {
+            PositionType::Buy
+        } else {
+            PositionType::Sell
+        };
+
+        Ok(Position {
+            id: row.get(0)?,
+            symbol: row.get(1)?,
+            quantity: row.get(2)?,
+            price: row.get(3)?,
+            position_type,
+            date: row.get(5)?,
+            notes: row.get(6)?,
+        })
+    }
+
+    /// Portfolio value grouped by sector and asset class, as percentages of the total.
+    /// Positions are valued at the latest close (entry price if none); short positions
+    /// count by their absolute exposure. Missing sector/asset class is bucketed as "Unknown".
+    pub fn get_portfolio_allocation(&self) -> Result<PortfolioAllocation> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT p.quantity,
+                   COALESCE(
+                       (SELECT d.close FROM daily_prices d WHERE d.symbol = p.symbol
+                        ORDER BY d.timestamp DESC LIMIT 1),
+                       p.price
+                   ),
+                   COALESCE(NULLIF(TRIM(s.sector), ''), 'Unknown'),
+                   COALESCE(NULLIF(TRIM(s.asset_class), ''), 'Unknown')
+            FROM portfolio_positions p
+            LEFT JOIN symbols s ON s.symbol = p.symbol
+            WHERE p.deleted_at IS NULL
+            "#,
+        )?;
+
+        let holdings = stmt
+            .query_map([], |row| {
+                let quantity: f64 = row.get(0)?;
+                let price: f64 = row.get(1)?;
+                Ok(((quantity * price).abs(), row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        let total_value: f64 = holdings.iter().map(|(value, _, _)| value).sum();
+
+        let group = |key: fn(&(f64, String, String)) -> &String| {
+            let mut totals: Vec<(String, f64)> = Vec::new();
+            for holding in &holdings {
+                match totals.iter_mut().find(|(name, _)| name == key(holding)) {
+                    Some((_, value)) => *value += holding.0,
+                    None => totals.push((key(holding).clone(), holding.0)),
+                }
+            }
+            totals.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+            totals
+                .into_iter()
+                .map(|(name, value)| AllocationBucket {
+                    name,
+                    value,
+                    percent: if total_value > 0.0 { value / total_value * 100.0 } else { 0.0 },
+                })
+                .collect()
+        };
+
+        Ok(PortfolioAllocation {
+            total_value,
+            by_sector: group(|h| &h.1),
+            by_asset_class: group(|h| &h.2),
+        })
+    }
+
+    /// Trades that move the portfolio toward `targets` (symbol -> percent of current
+    /// portfolio value). Held symbols without a target are sold off; whatever the
+    /// targets leave unallocated stays as cash. Trades worth less than
+    /// `min_trade_value` are skipped. Sells come first, largest first.
+    pub fn rebalance_plan(
+        &self,
+        targets: &HashMap<String, f64>,
+        min_trade_value: f64,
+    ) -> Result<Vec<RebalanceTrade>> {
+        if let Some((symbol, pct)) = targets.iter().find(|(_, pct)| !(0.0..=100.0).contains(*pct)) {
+            return Err(crate::error::PipelineError::ApiError(format!(
+                "Target for {} must be between 0 and 100%, got {}",
+                symbol, pct
+            )));
+        }
+        let target_sum: f64 = targets.values().sum();
+        if target_sum > 100.0 + 1e-9 {
+            return Err(crate::error::PipelineError::ApiError(format!(
+                "Targets add up to {:.2}%, more than 100%",
+                target_sum
+            )));
+        }
+        let targets: HashMap<String, f64> = targets
+            .iter()
+            .map(|(symbol, pct)| (symbol.to_uppercase(), *pct))
+            .collect();
+
+        // Net shares per symbol from the position ledger (shorts count against longs)
+        let mut holdings: HashMap<String, f64> = HashMap::new();
+        for pos in self.get_positions()? {
+            let signed = match pos.position_type {
+                PositionType::Buy => pos.quantity,
+                PositionType::Sell => -pos.quantity,
+            };
+            *holdings.entry(pos.symbol).or_default() += signed;
+        }
+
+        let mut symbols: Vec<String> = holdings.keys().chain(targets.keys()).cloned().collect();
+        symbols.sort();
+        symbols.dedup();
+
+        let mut prices = HashMap::new();
+        for symbol in &symbols {
+            let price = self.get_latest_price(symbol)?.ok_or_else(|| {
+                crate::error::PipelineError::ApiError(format!("No price data for {}", symbol))
+            })?;
+            prices.insert(symbol.clone(), price);
+        }
+
+        let total_value: f64 = holdings
+            .iter()
+            .map(|(symbol, shares)| shares * prices[symbol])
+            .sum();
+        if total_value <= 0.0 {
+            return Err(crate::error::PipelineError::ApiError(
+                "Portfolio has no value to rebalance".to_string(),
+            ));
+        }
+
+        let mut plan = Vec::new();
+        for symbol in symbols {
+            let price = prices[&symbol];
+            let held = holdings.get(&symbol).copied().unwrap_or(0.0);
+            let target_value = total_value * targets.get(&symbol).copied().unwrap_or(0.0) / 100.0;
+            let diff = target_value - held * price;
+
+            // Whole shares, except that a symbol dropped from the targets is sold in full
+            let shares = if target_value == 0.0 && held > 0.0 {
+                held
+            } else {
+                (diff.abs() / price).floor()
+            };
+            let est_value = shares * price;
+            if shares <= 0.0 || est_value < min_trade_value {
+                continue;
+            }
+
+            plan.push(RebalanceTrade {
+                symbol,
+                action: if diff < 0.0 { PositionType::Sell } else { PositionType::Buy },
+                shares,
+                price,
+                est_value,
+            });
+        }
+
+        plan.sort_by(|a, b| {
+            (a.action == PositionType::Buy)
+                .cmp(&(b.action == PositionType::Buy))
+                .then(b.est_value.partial_cmp(&a.est_value).unwrap_or(std::cmp::Ordering::Equal))
+        });
+        Ok(plan)
+    }
+
+    /// Compare portfolio positions with a benchmark over the last `days` calendar days
+    /// (ending at the benchmark's latest bar). The portfolio series is time-weighted:
+    /// each day's return is the P&L of positions opened before that day divided by their
+    /// gross value the day before, so adding positions doesn't read as performance.
+    /// Short (sell) positions gain when the price falls. Adjusted closes are used, with a
+    /// symbol's last close carried over days it has no bar. Tracking data is growth of
+    /// 100 for both series.
+    pub fn portfolio_vs_benchmark(
+        &self,
+        benchmark_symbol: &str,
+        days: i64,
+    ) -> Result<BenchmarkComparison> {
+        let benchmark: Vec<DailyPrice> = self
+            .get_prices(benchmark_symbol)?
+            .iter()
+            .map(DailyPrice::adjusted)
+            .collect();
+        let Some(latest) = benchmark.last().map(|p| p.date) else {
+            return Err(crate::error::PipelineError::NoData(benchmark_symbol.to_string()));
+        };
+        let start = latest - chrono::Duration::days(days.max(1));
+        let window: Vec<&DailyPrice> = benchmark.iter().filter(|p| p.date >= start).collect();
+        if window.len() < 2 {
+            return Ok(BenchmarkComparison::default());
+        }
+
+        let positions = self.get_positions()?;
+        let mut closes: HashMap<String, Vec<(NaiveDate, f64)>> = HashMap::new();
+        for pos in &positions {
+            if !closes.contains_key(&pos.symbol) {
+                let series = self
+                    .get_prices(&pos.symbol)?
+                    .iter()
+                    .map(|p| (p.date, p.adjusted().close))
+                    .collect();
+                closes.insert(pos.symbol.clone(), series);
+            }
+        }
+        let close_on = |symbol: &str, date: NaiveDate| {
+            closes.get(symbol).and_then(|series| last_close_on(series, date))
+        };
+
+        let mut portfolio_index = 100.0;
+        let first_benchmark = window[0].close;
+        let mut tracking_data = vec![(window[0].date.to_string(), portfolio_index, 100.0)];
+        for pair in window.windows(2) {
+            let (prev, today) = (pair[0].date, pair[1].date);
+            let mut pnl = 0.0;
+            let mut exposure = 0.0;
+            for pos in &positions {
+                let opened = NaiveDate::parse_from_str(&pos.date, "%Y-%m-%d").unwrap_or(NaiveDate::MIN);
+                if opened > prev {
+                    continue;
+                }
+                let (Some(before), Some(after)) =
+                    (close_on(&pos.symbol, prev), close_on(&pos.symbol, today))
+                else {
+                    continue;
+                };
+                pnl += position_sign(pos.position_type) * pos.quantity * (after - before);
+                exposure += pos.quantity * before;
+            }
+            if exposure > 0.0 {
+                portfolio_index *= 1.0 + pnl / exposure;
+            }
+            tracking_data.push((
+                today.to_string(),
+                portfolio_index,
+                pair[1].close / first_benchmark * 100.0,
+            ));
+        }
+
+        let portfolio_return_percent = portfolio_index - 100.0;
+        let benchmark_return_percent = (window[window.len() - 1].close / first_benchmark - 1.0) * 100.0;
+        Ok(BenchmarkComparison {
+            portfolio_return_percent,
+            benchmark_return_percent,
+            alpha: portfolio_return_percent - benchmark_return_percent,
+            tracking_data,
+        })
+    }
+
+    /// Soft-delete a portfolio position. The row is hidden from `get_positions` and
+    /// portfolio valuations but can be brought back with `restore_position`.
+    pub fn delete_position(&self, position_id: i64) -> Result<()> {
+        let before = self.get_position(position_id)?;
+        let deleted = self.conn.execute(
+            "UPDATE portfolio_positions SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+            params![position_id, Utc::now().to_rfc3339()],
+        )?;
+        if deleted > 0 {
+            self.record_audit(AUDIT_POSITION, position_id, "delete", before.as_ref(), None)?;
+        }
+        Ok(())
+    }
+
+    /// Undo a soft delete. Returns false if the position doesn't exist or isn't deleted.
+    pub fn restore_position(&self, position_id: i64) -> Result<bool> {
+        let restored = self.conn.execute(
+            "UPDATE portfolio_positions SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![position_id],
+        )?;
+        if restored == 0 {
+            return Ok(false);
+        }
+        let after = self.get_position(position_id)?;
+        self.record_audit(AUDIT_POSITION, position_id, "restore", None, after.as_ref())?;
+        Ok(true)
+    }
+
+    // ========================================================================
+    // Audit Log
+    // ========================================================================
+
+    /// Record a change to an audited row, storing it as JSON before and after
+    fn record_audit<T: Serialize>(
+        &self,
+        entity_type: &str,
+        entity_id: i64,
+        action: &str,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> Result<()> {
+        let before = before.map(serde_json::to_string).transpose()?;
+        let after = after.map(serde_json::to_string).transpose()?;
+        self.conn.execute(
+            r#"
+            INSERT INTO audit_log (entity_type, entity_id, action, before_json, after_json)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+            params![entity_type, entity_id, action, before, after],
+        )?;
+        Ok(())
+    }
+
+    /// Most recent audit log entries, newest first
+    pub fn get_audit_log(&self, limit: usize) -> Result<Vec<AuditLogEntry>> {
+        let mut stmt = self.conn.prepare(
+            r#"
+            SELECT id, entity_type, entity_id, action, before_json, after_json, created_at
+            FROM audit_log
+            ORDER BY id DESC
+            LIMIT ?1
+            "#,
+        )?;
+
+        let entries = stmt
+            .query_map(params![limit as i64], |row| {
+                Ok(AuditLogEntry {
+                    id: row.get(0)?,
+                    entity_type: row.get(1)?,
+                    entity_id: row.get(2)?,
+                    action: row.get(3)?,
+                    before: row.get(4)?,
+                    after: row.get(5)?,
+                    created_at: row.get(6)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(entries)
     }
 
     /// Store Google Trends data
@@ -1009,6 +2178,52 @@ impl Database {
         Ok(trends)
     }
 
+    /// Correlation between a keyword's search interest and a symbol's daily returns
+    /// `lag_days` trading days later (0 = the same day's return). Trends are resampled
+    /// onto price dates by carrying each reading forward until the next one, for at
+    /// most `TRENDS_MAX_FILL_DAYS`, so weekly and daily series line up.
+    /// Returns None with fewer than `MIN_CORRELATION_OVERLAP` pairs or a flat series.
+    pub fn trends_price_correlation(&self, keyword: &str, symbol: &str, lag_days: usize) -> Result<Option<f64>> {
+        let trends: Vec<(NaiveDate, f64)> = self
+            .get_trends(keyword)?
+            .iter()
+            .map(|t| (t.date, t.value as f64))
+            .collect();
+        let closes: Vec<(NaiveDate, f64)> = self
+            .get_adjusted_prices(symbol)?
+            .iter()
+            .filter(|p| p.close > 0.0)
+            .map(|p| (p.date, p.close))
+            .collect();
+
+        let mut interest = Vec::new();
+        let mut returns = Vec::new();
+        for (i, &(date, _)) in closes.iter().enumerate() {
+            let idx = trends.partition_point(|(d, _)| *d <= date);
+            let Some(&(trend_date, value)) = idx.checked_sub(1).map(|i| &trends[i]) else {
+                continue;
+            };
+            if (date - trend_date).num_days() > TRENDS_MAX_FILL_DAYS {
+                continue;
+            }
+            // One-day return into the bar `lag_days` after this one
+            let end = i + lag_days;
+            let (Some(&(_, prev_close)), Some(&(_, close))) =
+                (end.checked_sub(1).and_then(|j| closes.get(j)), closes.get(end))
+            else {
+                continue;
+            };
+            interest.push(value);
+            returns.push(close / prev_close - 1.0);
+        }
+
+        if interest.len() < MIN_CORRELATION_OVERLAP {
+            return Ok(None);
+        }
+        let correlation = pearson(&interest, &returns);
+        Ok((!correlation.is_nan()).then_some(correlation))
+    }
+
     // ========================================================================
     // Signal Methods
     // ========================================================================
@@ -1326,7 +2541,10 @@ impl Database {
             "#,
         )?;
 
-        let result: SqliteResult<f64> = stmt.query_row(params![symbol, indicator_name], |row| row.get(0));
+        let result: SqliteResult<f64> = stmt.query_row(
+            params![symbol, canonical_indicator_name(indicator_name)],
+            |row| row.get(0),
+        );
 
         match result {
             Ok(value) => Ok(Some(value)),
@@ -1346,7 +2564,10 @@ impl Database {
             "#,
         )?;
 
-        let result: SqliteResult<f64> = stmt.query_row(params![symbol, indicator_name], |row| row.get(0));
+        let result: SqliteResult<f64> = stmt.query_row(
+            params![symbol, canonical_indicator_name(indicator_name)],
+            |row| row.get(0),
+        );
 
         match result {
             Ok(value) => Ok(Some(value)),
@@ -1438,13 +2659,31 @@ impl Database {
 
     /// Save a strategy
     pub fn save_strategy(&self, strategy: &Strategy) -> Result<i64> {
+        // Composite rules are stored as JSON; NULL means the single-condition columns apply
+        let entry_rule = strategy.entry_rule.as_ref().map(serde_json::to_string).transpose()?;
+        let exit_rule = strategy.exit_rule.as_ref().map(serde_json::to_string).transpose()?;
+
         self.conn.execute(
             r#"
-            INSERT OR REPLACE INTO strategies
+            INSERT INTO strategies
             (name, description, entry_condition, entry_threshold,
              exit_condition, exit_threshold,
-             stop_loss_percent, take_profit_percent, position_size_percent)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             stop_loss_percent, take_profit_percent, position_size_percent, direction,
+             trailing_stop_percent, entry_rule, exit_rule)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+            ON CONFLICT(name) DO UPDATE SET
+                description = excluded.description,
+                entry_condition = excluded.entry_condition,
+                entry_threshold = excluded.entry_threshold,
+                exit_condition = excluded.exit_condition,
+                exit_threshold = excluded.exit_threshold,
+                stop_loss_percent = excluded.stop_loss_percent,
+                take_profit_percent = excluded.take_profit_percent,
+                position_size_percent = excluded.position_size_percent,
+                direction = excluded.direction,
+                trailing_stop_percent = excluded.trailing_stop_percent,
+                entry_rule = excluded.entry_rule,
+                exit_rule = excluded.exit_rule
             "#,
             params![
                 strategy.name,
@@ -1456,10 +2695,20 @@ impl Database {
                 strategy.stop_loss_percent,
                 strategy.take_profit_percent,
                 strategy.position_size_percent,
+                strategy.direction.as_str(),
+                strategy.trailing_stop_percent,
+                entry_rule,
+                exit_rule,
             ],
         )?;
 
-        Ok(self.conn.last_insert_rowid())
+        // Updates keep the existing id (backtest runs reference it)
+        let id = self.conn.query_row(
+            "SELECT id FROM strategies WHERE name = ?1",
+            params![strategy.name],
+            |row| row.get(0),
+        )?;
+        Ok(id)
     }
 
     /// Get all strategies
@@ -1468,7 +2717,8 @@ impl Database {
             r#"
             SELECT id, name, description, entry_condition, entry_threshold,
                    exit_condition, exit_threshold,
-                   stop_loss_percent, take_profit_percent, position_size_percent, created_at
+                   stop_loss_percent, take_profit_percent, position_size_percent, created_at,
+                   direction, trailing_stop_percent, entry_rule, exit_rule
             FROM strategies
             ORDER BY name ASC
             "#,
@@ -1478,6 +2728,7 @@ impl Database {
             .query_map([], |row| {
                 let entry_cond_str: String = row.get(3)?;
                 let exit_cond_str: String = row.get(5)?;
+                let direction_str: String = row.get(11)?;
 
                 Ok(Strategy {
                     id: row.get(0)?,
@@ -1491,7 +2742,11 @@ impl Database {
                     exit_threshold: row.get(6)?,
                     stop_loss_percent: row.get(7)?,
                     take_profit_percent: row.get(8)?,
+                    trailing_stop_percent: row.get(12)?,
                     position_size_percent: row.get(9)?,
+                    direction: TradeDirection::from_str(&direction_str),
+                    entry_rule: Self::parse_rule(row.get(13)?),
+                    exit_rule: Self::parse_rule(row.get(14)?),
                     created_at: row.get(10)?,
                 })
             })?
@@ -1506,7 +2761,8 @@ impl Database {
             r#"
             SELECT id, name, description, entry_condition, entry_threshold,
                    exit_condition, exit_threshold,
-                   stop_loss_percent, take_profit_percent, position_size_percent, created_at
+                   stop_loss_percent, take_profit_percent, position_size_percent, created_at,
+                   direction, trailing_stop_percent, entry_rule, exit_rule
             FROM strategies
             WHERE name = ?1
             "#,
@@ -1515,6 +2771,7 @@ impl Database {
         let result = stmt.query_row(params![name], |row| {
             let entry_cond_str: String = row.get(3)?;
             let exit_cond_str: String = row.get(5)?;
+            let direction_str: String = row.get(11)?;
 
             Ok(Strategy {
                 id: row.get(0)?,
@@ -1528,7 +2785,11 @@ impl Database {
                 exit_threshold: row.get(6)?,
                 stop_loss_percent: row.get(7)?,
                 take_profit_percent: row.get(8)?,
+                trailing_stop_percent: row.get(12)?,
                 position_size_percent: row.get(9)?,
+                direction: TradeDirection::from_str(&direction_str),
+                entry_rule: Self::parse_rule(row.get(13)?),
+                exit_rule: Self::parse_rule(row.get(14)?),
                 created_at: row.get(10)?,
             })
         });
@@ -1540,10 +2801,29 @@ impl Database {
         }
     }
 
-    /// Delete a strategy
+    fn parse_rule(json: Option<String>) -> Option<StrategyRule> {
+        json.and_then(|j| serde_json::from_str(&j).ok())
+    }
+
+    /// Delete a strategy along with its backtest runs and trades
     pub fn delete_strategy(&self, name: &str) -> Result<()> {
-        self.conn
-            .execute("DELETE FROM strategies WHERE name = ?1", params![name])?;
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            r#"
+            DELETE FROM backtest_trades WHERE backtest_id IN (
+                SELECT r.id FROM backtest_runs r
+                JOIN strategies s ON s.id = r.strategy_id
+                WHERE s.name = ?1
+            )
+            "#,
+            params![name],
+        )?;
+        tx.execute(
+            "DELETE FROM backtest_runs WHERE strategy_id IN (SELECT id FROM strategies WHERE name = ?1)",
+            params![name],
+        )?;
+        tx.execute("DELETE FROM strategies WHERE name = ?1", params![name])?;
+        tx.commit()?;
         Ok(())
     }
 
@@ -1559,8 +2839,8 @@ impl Database {
              initial_capital, final_capital, total_return, total_return_dollars,
              max_drawdown, sharpe_ratio, win_rate, total_trades, winning_trades,
              losing_trades, avg_win_percent, avg_loss_percent, profit_factor,
-             avg_trade_duration_days)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)
+             avg_trade_duration_days, equity_curve, drawdown_curve)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)
             "#,
             params![
                 result.strategy_id,
@@ -1582,6 +2862,8 @@ impl Database {
                 result.metrics.avg_loss_percent,
                 result.metrics.profit_factor,
                 result.metrics.avg_trade_duration_days,
+                serde_json::to_string(&result.equity_curve)?,
+                serde_json::to_string(&result.drawdown_curve)?,
             ],
         )?;
 
@@ -1709,6 +2991,8 @@ impl Database {
                 avg_trade_duration_days: row.get(19)?,
             },
             trades: Vec::new(), // Trades loaded separately if needed
+            equity_curve: Vec::new(), // Curves loaded with the detail view
+            drawdown_curve: Vec::new(),
             created_at: row.get(20)?,
         })
     }
@@ -1773,6 +3057,19 @@ impl Database {
 
         backtest.trades = trades;
 
+        // Load equity/drawdown curves (NULL for runs saved before they were recorded)
+        let (equity_json, drawdown_json): (Option<String>, Option<String>) = self.conn.query_row(
+            "SELECT equity_curve, drawdown_curve FROM backtest_runs WHERE id = ?1",
+            params![backtest_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        if let Some(json) = equity_json {
+            backtest.equity_curve = serde_json::from_str(&json)?;
+        }
+        if let Some(json) = drawdown_json {
+            backtest.drawdown_curve = serde_json::from_str(&json)?;
+        }
+
         Ok(Some(backtest))
     }
 
@@ -1798,15 +3095,21 @@ impl Database {
     /// Get paper wallet balance
     pub fn get_paper_wallet(&self) -> Result<PaperWallet> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, cash, created_at, updated_at FROM paper_wallet WHERE id = 1",
+            "SELECT id, cash, starting_capital, created_at, updated_at, per_trade_fee, fee_percent
+             FROM paper_wallet WHERE id = 1",
         )?;
 
         let wallet = stmt.query_row([], |row| {
             Ok(PaperWallet {
                 id: row.get(0)?,
                 cash: row.get(1)?,
-                created_at: row.get(2)?,
-                updated_at: row.get(3)?,
+                starting_capital: row.get(2)?,
+                fees: TradeFees {
+                    per_trade_fee: row.get(5)?,
+                    fee_percent: row.get(6)?,
+                },
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
             })
         })?;
 
@@ -1822,11 +3125,22 @@ impl Database {
         Ok(())
     }
 
+    /// Set the commission charged on paper (KALIC) trades from now on
+    pub fn set_paper_fees(&self, fees: &TradeFees) -> Result<()> {
+        validate_trade_fees(fees)?;
+        self.conn.execute(
+            "UPDATE paper_wallet SET per_trade_fee = ?1, fee_percent = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
+            params![fees.per_trade_fee, fees.fee_percent],
+        )?;
+        Ok(())
+    }
+
     /// Get all paper positions
     pub fn get_paper_positions(&self) -> Result<Vec<PaperPosition>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, symbol, quantity, entry_price, entry_date, linked_event_id
+            SELECT id, symbol, quantity, entry_price, entry_date, linked_event_id,
+                   stop_price, take_price
             FROM paper_positions
             ORDER BY entry_date DESC
             "#,
@@ -1841,6 +3155,8 @@ impl Database {
                     entry_price: row.get(3)?,
                     entry_date: row.get(4)?,
                     linked_event_id: row.get(5)?,
+                    stop_price: row.get(6)?,
+                    take_price: row.get(7)?,
                 })
             })?
             .collect::<SqliteResult<Vec<_>>>()?;
@@ -1852,7 +3168,8 @@ impl Database {
     pub fn get_paper_position(&self, symbol: &str) -> Result<Option<PaperPosition>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, symbol, quantity, entry_price, entry_date, linked_event_id
+            SELECT id, symbol, quantity, entry_price, entry_date, linked_event_id,
+                   stop_price, take_price
             FROM paper_positions
             WHERE symbol = ?1
             "#,
@@ -1866,6 +3183,8 @@ impl Database {
                 entry_price: row.get(3)?,
                 entry_date: row.get(4)?,
                 linked_event_id: row.get(5)?,
+                stop_price: row.get(6)?,
+                take_price: row.get(7)?,
             })
         });
 
@@ -1876,6 +3195,24 @@ impl Database {
         }
     }
 
+    /// Buy up to `quantity` shares, filling only the whole shares the cash (after fees)
+    /// covers when the full order doesn't fit. The returned trade carries the filled quantity.
+    pub fn execute_paper_buy_affordable(
+        &self,
+        symbol: &str,
+        quantity: f64,
+        price: f64,
+        linked_event_id: Option<i64>,
+        notes: Option<&str>,
+    ) -> Result<PaperTrade> {
+        let wallet = self.get_paper_wallet()?;
+        let filled = fill_quantity(&wallet.fees, wallet.cash, quantity, price)?;
+        if filled < quantity {
+            println!("[PAPER] Partial fill: {} of {} {} affordable", filled, quantity, symbol);
+        }
+        self.execute_paper_trade(symbol, PaperTradeAction::Buy, filled, price, linked_event_id, notes)
+    }
+
     /// Execute a paper trade (BUY or SELL)
     /// Returns the trade record on success
     pub fn execute_paper_trade(
@@ -1889,19 +3226,20 @@ impl Database {
     ) -> Result<PaperTrade> {
         let wallet = self.get_paper_wallet()?;
         let cost = quantity * price;
+        let fee = wallet.fees.fee_for(cost);
 
         match action {
             PaperTradeAction::Buy => {
-                // Validate sufficient cash
-                if wallet.cash < cost {
+                // Validate sufficient cash (including commission)
+                if wallet.cash < cost + fee {
                     return Err(crate::error::PipelineError::ApiError(format!(
                         "Insufficient cash: have ${:.2}, need ${:.2}",
-                        wallet.cash, cost
+                        wallet.cash, cost + fee
                     )));
                 }
 
                 // Deduct cash
-                self.update_paper_cash(wallet.cash - cost)?;
+                self.update_paper_cash(wallet.cash - cost - fee)?;
 
                 // Add or update position
                 let existing = self.get_paper_position(symbol)?;
@@ -1925,13 +3263,16 @@ impl Database {
                     )?;
                 }
 
+                // Record the lot before the trade so last_insert_rowid() is the trade
+                self.add_lot(LOT_ACCOUNT_PAPER, symbol, quantity, price)?;
+
                 // Record trade
                 self.conn.execute(
                     r#"
-                    INSERT INTO paper_trades (symbol, action, quantity, price, linked_event_id, notes)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                    INSERT INTO paper_trades (symbol, action, quantity, price, linked_event_id, notes, fee)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
                     "#,
-                    params![symbol, "BUY", quantity, price, linked_event_id, notes],
+                    params![symbol, "BUY", quantity, price, linked_event_id, notes, fee],
                 )?;
             }
             PaperTradeAction::Sell => {
@@ -1951,11 +3292,17 @@ impl Database {
                     )));
                 }
 
-                // Calculate P&L
-                let pnl = (price - pos.entry_price) * quantity;
+                // Calculate P&L and the cost basis left on the remaining shares
+                let (pnl, remaining_entry) = self.realize_sale(
+                    LOT_ACCOUNT_PAPER,
+                    symbol,
+                    quantity,
+                    price,
+                    (pos.quantity, pos.entry_price),
+                )?;
 
-                // Add proceeds to cash
-                self.update_paper_cash(wallet.cash + cost)?;
+                // Add proceeds to cash, net of commission
+                self.update_paper_cash(wallet.cash + cost - fee)?;
 
                 // Update or delete position
                 let remaining = pos.quantity - quantity;
@@ -1968,18 +3315,18 @@ impl Database {
                 } else {
                     // Reduce position
                     self.conn.execute(
-                        "UPDATE paper_positions SET quantity = ?1 WHERE id = ?2",
-                        params![remaining, pos.id],
+                        "UPDATE paper_positions SET quantity = ?1, entry_price = ?2 WHERE id = ?3",
+                        params![remaining, remaining_entry, pos.id],
                     )?;
                 }
 
                 // Record trade with P&L
                 self.conn.execute(
                     r#"
-                    INSERT INTO paper_trades (symbol, action, quantity, price, pnl, linked_event_id, notes)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                    INSERT INTO paper_trades (symbol, action, quantity, price, pnl, linked_event_id, notes, fee)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
                     "#,
-                    params![symbol, "SELL", quantity, price, pnl, linked_event_id, notes],
+                    params![symbol, "SELL", quantity, price, pnl, linked_event_id, notes, fee],
                 )?;
             }
         }
@@ -1988,7 +3335,7 @@ impl Database {
         let trade_id = self.conn.last_insert_rowid();
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, symbol, action, quantity, price, pnl, timestamp, linked_event_id, notes
+            SELECT id, symbol, action, quantity, price, pnl, timestamp, linked_event_id, notes, fee
             FROM paper_trades WHERE id = ?1
             "#,
         )?;
@@ -2002,12 +3349,14 @@ impl Database {
                 quantity: row.get(3)?,
                 price: row.get(4)?,
                 pnl: row.get(5)?,
+                fee: row.get(9)?,
                 timestamp: row.get(6)?,
                 linked_event_id: row.get(7)?,
                 notes: row.get(8)?,
             })
         })?;
 
+        self.record_audit(AUDIT_PAPER_TRADE, trade.id, "create", None, Some(&trade))?;
         Ok(trade)
     }
 
@@ -2015,14 +3364,14 @@ impl Database {
     pub fn get_paper_trades(&self, symbol: Option<&str>, limit: usize) -> Result<Vec<PaperTrade>> {
         let sql = match symbol {
             Some(_) => r#"
-                SELECT id, symbol, action, quantity, price, pnl, timestamp, linked_event_id, notes
+                SELECT id, symbol, action, quantity, price, pnl, timestamp, linked_event_id, notes, fee
                 FROM paper_trades
                 WHERE symbol = ?1
                 ORDER BY timestamp DESC
                 LIMIT ?2
             "#,
             None => r#"
-                SELECT id, symbol, action, quantity, price, pnl, timestamp, linked_event_id, notes
+                SELECT id, symbol, action, quantity, price, pnl, timestamp, linked_event_id, notes, fee
                 FROM paper_trades
                 ORDER BY timestamp DESC
                 LIMIT ?1
@@ -2042,6 +3391,7 @@ impl Database {
                         quantity: row.get(3)?,
                         price: row.get(4)?,
                         pnl: row.get(5)?,
+                        fee: row.get(9)?,
                         timestamp: row.get(6)?,
                         linked_event_id: row.get(7)?,
                         notes: row.get(8)?,
@@ -2059,6 +3409,7 @@ impl Database {
                         quantity: row.get(3)?,
                         price: row.get(4)?,
                         pnl: row.get(5)?,
+                        fee: row.get(9)?,
                         timestamp: row.get(6)?,
                         linked_event_id: row.get(7)?,
                         notes: row.get(8)?,
@@ -2071,66 +3422,403 @@ impl Database {
         Ok(trades)
     }
 
-    /// Reset paper trading account (clear all positions, trades, reset cash)
-    pub fn reset_paper_account(&self, starting_cash: f64) -> Result<()> {
-        let tx = self.conn.unchecked_transaction()?;
-        tx.execute("DELETE FROM paper_positions", [])?;
-        tx.execute("DELETE FROM paper_trades", [])?;
-        tx.execute(
-            "UPDATE paper_wallet SET cash = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
-            params![starting_cash],
+    // ========================================================================
+    // Tax Lot Methods (shared by paper and DC accounts)
+    // ========================================================================
+
+    /// Cost basis method used when selling (defaults to average cost)
+    pub fn get_cost_basis_method(&self) -> Result<CostBasisMethod> {
+        Ok(self
+            .get_setting(SETTING_COST_BASIS_METHOD)?
+            .map(|s| CostBasisMethod::from_str(&s))
+            .unwrap_or_default())
+    }
+
+    /// Set the cost basis method for subsequent sells
+    pub fn set_cost_basis_method(&self, method: CostBasisMethod) -> Result<()> {
+        self.set_setting(SETTING_COST_BASIS_METHOD, method.as_str())
+    }
+
+    /// Record a buy lot
+    fn add_lot(&self, account: &str, symbol: &str, quantity: f64, price: f64) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO paper_lots (account, symbol, quantity, price) VALUES (?1, ?2, ?3, ?4)",
+            params![account, symbol, quantity, price],
         )?;
-        tx.commit()?;
-        println!("[OK] Paper trading account reset with ${:.2}", starting_cash);
         Ok(())
     }
 
-    /// Calculate total paper portfolio value (cash + positions at current prices)
-    /// Returns (cash, positions_value, total_equity)
-    pub fn get_paper_portfolio_value(&self) -> Result<(f64, f64, f64)> {
-        let wallet = self.get_paper_wallet()?;
-        let positions = self.get_paper_positions()?;
+    /// Consume lots oldest-first for a sale and return (realized P&L, entry price of the
+    /// remaining shares). Lots are always consumed so they stay in step with the position;
+    /// the cost basis method only decides which cost the P&L is measured against.
+    /// Shares bought before lots were tracked count as the oldest lot.
+    fn realize_sale(
+        &self,
+        account: &str,
+        symbol: &str,
+        quantity: f64,
+        price: f64,
+        (position_qty, position_entry): (f64, f64),
+    ) -> Result<(f64, f64)> {
+        let lots: Vec<(i64, f64, f64)> = self
+            .conn
+            .prepare(
+                "SELECT id, quantity, price FROM paper_lots WHERE account = ?1 AND symbol = ?2 ORDER BY id ASC",
+            )?
+            .query_map(params![account, symbol], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<SqliteResult<Vec<_>>>()?;
 
-        let mut positions_value = 0.0;
-        for pos in positions {
-            // Try to get current price, fall back to entry price
-            let current_price = self
-                .get_latest_price(&pos.symbol)?
-                .unwrap_or(pos.entry_price);
-            positions_value += pos.quantity * current_price;
+        let position_cost = position_qty * position_entry;
+        let lot_qty: f64 = lots.iter().map(|(_, q, _)| q).sum();
+        let lot_cost: f64 = lots.iter().map(|(_, q, p)| q * p).sum();
+        let untracked_qty = position_qty - lot_qty;
+
+        let mut to_sell = quantity;
+        let mut fifo_cost = 0.0;
+        if untracked_qty > 0.0001 {
+            let take = untracked_qty.min(to_sell);
+            fifo_cost += take * (position_cost - lot_cost) / untracked_qty;
+            to_sell -= take;
+        }
+        for (id, lot_qty, lot_price) in lots {
+            if to_sell <= 0.0001 {
+                break;
+            }
+            let take = lot_qty.min(to_sell);
+            fifo_cost += take * lot_price;
+            to_sell -= take;
+            if lot_qty - take <= 0.0001 {
+                self.conn.execute("DELETE FROM paper_lots WHERE id = ?1", params![id])?;
+            } else {
+                self.conn.execute(
+                    "UPDATE paper_lots SET quantity = ?1 WHERE id = ?2",
+                    params![lot_qty - take, id],
+                )?;
+            }
         }
 
-        let total_equity = wallet.cash + positions_value;
-        Ok((wallet.cash, positions_value, total_equity))
+        let remaining = position_qty - quantity;
+        match self.get_cost_basis_method()? {
+            CostBasisMethod::Average => Ok(((price - position_entry) * quantity, position_entry)),
+            CostBasisMethod::Fifo => {
+                let remaining_entry = if remaining > 0.0001 {
+                    (position_cost - fifo_cost) / remaining
+                } else {
+                    position_entry
+                };
+                Ok((price * quantity - fifo_cost, remaining_entry))
+            }
+        }
     }
 
     // ========================================================================
-    // DC Trader Methods (Separate from KALIC AI paper trading)
+    // Paper Order Methods
     // ========================================================================
 
-    /// Initialize DC wallet if not exists
-    pub fn init_dc_wallet(&self) -> Result<()> {
+    /// Queue a limit or stop order; it fills later in `process_pending_paper_orders`
+    pub fn place_paper_order(
+        &self,
+        symbol: &str,
+        action: PaperTradeAction,
+        order_type: PaperOrderType,
+        quantity: f64,
+        limit_price: Option<f64>,
+        stop_price: Option<f64>,
+    ) -> Result<PaperOrder> {
+        if quantity <= 0.0 {
+            return Err(crate::error::PipelineError::ApiError(format!(
+                "Order quantity must be positive, got {}",
+                quantity
+            )));
+        }
+        let trigger = match order_type {
+            PaperOrderType::Limit => limit_price,
+            PaperOrderType::Stop => stop_price,
+        };
+        if !trigger.is_some_and(|p| p > 0.0) {
+            return Err(crate::error::PipelineError::ApiError(format!(
+                "{} order requires a positive {} price",
+                order_type.as_str(),
+                order_type.as_str().to_lowercase()
+            )));
+        }
+
         self.conn.execute(
-            "INSERT OR IGNORE INTO dc_wallet (id, cash, starting_capital) VALUES (1, 1000000.0, 1000000.0)",
-            [],
+            r#"
+            INSERT INTO paper_orders (symbol, action, order_type, quantity, limit_price, stop_price)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            "#,
+            params![
+                symbol,
+                action.as_str(),
+                order_type.as_str(),
+                quantity,
+                limit_price,
+                stop_price
+            ],
         )?;
-        Ok(())
+
+        let id = self.conn.last_insert_rowid();
+        self.get_paper_order(id)?
+            .ok_or_else(|| crate::error::PipelineError::NoData(format!("paper order {}", id)))
     }
 
-    /// Get DC wallet balance
-    pub fn get_dc_wallet(&self) -> Result<DcWallet> {
-        // Ensure wallet exists
-        self.init_dc_wallet()?;
+    fn map_paper_order(row: &rusqlite::Row) -> SqliteResult<PaperOrder> {
+        let action: String = row.get(2)?;
+        let order_type: String = row.get(3)?;
+        let status: String = row.get(7)?;
+        Ok(PaperOrder {
+            id: row.get(0)?,
+            symbol: row.get(1)?,
+            action: PaperTradeAction::from_str(&action),
+            order_type: PaperOrderType::from_str(&order_type).unwrap_or(PaperOrderType::Limit),
+            quantity: row.get(4)?,
+            limit_price: row.get(5)?,
+            stop_price: row.get(6)?,
+            status: PaperOrderStatus::from_str(&status),
+            paper_trade_id: row.get(8)?,
+            created_at: row.get(9)?,
+            filled_at: row.get(10)?,
+        })
+    }
+
+    /// Get a single paper order by ID
+    pub fn get_paper_order(&self, id: i64) -> Result<Option<PaperOrder>> {
+        let order = self
+            .conn
+            .query_row(
+                r#"
+                SELECT id, symbol, action, order_type, quantity, limit_price, stop_price,
+                       status, paper_trade_id, created_at, filled_at
+                FROM paper_orders WHERE id = ?1
+                "#,
+                params![id],
+                Self::map_paper_order,
+            )
+            .optional()?;
+        Ok(order)
+    }
 
+    /// Get paper orders, optionally filtered by status (newest first)
+    pub fn get_paper_orders(&self, status: Option<PaperOrderStatus>) -> Result<Vec<PaperOrder>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, cash, starting_capital, created_at, updated_at FROM dc_wallet WHERE id = 1",
+            r#"
+            SELECT id, symbol, action, order_type, quantity, limit_price, stop_price,
+                   status, paper_trade_id, created_at, filled_at
+            FROM paper_orders
+            WHERE ?1 IS NULL OR status = ?1
+            ORDER BY id DESC
+            "#,
         )?;
 
-        let wallet = stmt.query_row([], |row| {
-            Ok(DcWallet {
+        let orders = stmt
+            .query_map(params![status.map(|s| s.as_str())], Self::map_paper_order)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(orders)
+    }
+
+    /// Cancel a pending paper order. Returns false if it was already filled or cancelled.
+    pub fn cancel_paper_order(&self, id: i64) -> Result<bool> {
+        let updated = self.conn.execute(
+            "UPDATE paper_orders SET status = 'CANCELLED' WHERE id = ?1 AND status = 'PENDING'",
+            params![id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Fill pending orders whose trigger is met by each symbol's latest price
+    /// Orders that can't fill (insufficient cash/shares) stay pending.
+    /// Returns the orders filled in this pass.
+    pub fn process_pending_paper_orders(&self) -> Result<Vec<PaperOrder>> {
+        let pending = self.get_paper_orders(Some(PaperOrderStatus::Pending))?;
+        let mut filled = Vec::new();
+
+        // Oldest first so earlier orders get first claim on cash
+        for order in pending.into_iter().rev() {
+            let Some(price) = self.get_latest_price(&order.symbol)? else {
+                continue;
+            };
+            if !order.is_triggered(price) {
+                continue;
+            }
+
+            let notes = format!("{} order #{}", order.order_type.as_str(), order.id);
+            let trade = match self.execute_paper_trade(
+                &order.symbol,
+                order.action,
+                order.quantity,
+                price,
+                None,
+                Some(&notes),
+            ) {
+                Ok(trade) => trade,
+                Err(crate::error::PipelineError::ApiError(msg)) => {
+                    println!("[WARN] Paper order #{} not filled: {}", order.id, msg);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            self.conn.execute(
+                r#"
+                UPDATE paper_orders
+                SET status = 'FILLED', paper_trade_id = ?1, filled_at = CURRENT_TIMESTAMP
+                WHERE id = ?2
+                "#,
+                params![trade.id, order.id],
+            )?;
+            if let Some(order) = self.get_paper_order(order.id)? {
+                filled.push(order);
+            }
+        }
+
+        Ok(filled)
+    }
+
+    /// Set (or clear with None) a paper position's stop-loss and take-profit prices.
+    /// Returns false if there is no open position in the symbol.
+    pub fn set_paper_position_exits(
+        &self,
+        symbol: &str,
+        stop_price: Option<f64>,
+        take_price: Option<f64>,
+    ) -> Result<bool> {
+        validate_exit_levels(stop_price, take_price)?;
+        let updated = self.conn.execute(
+            "UPDATE paper_positions SET stop_price = ?1, take_price = ?2 WHERE symbol = ?3",
+            params![stop_price, take_price, symbol],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Close paper positions whose latest price has hit a stop-loss or take-profit.
+    /// A position's own stop_price/take_price wins; otherwise `stop_pct`/`take_pct`
+    /// (percent from entry_price) apply when given. Returns the SELL trades recorded.
+    pub fn check_paper_stops(
+        &self,
+        stop_pct: Option<f64>,
+        take_pct: Option<f64>,
+    ) -> Result<Vec<PaperTrade>> {
+        let mut closed = Vec::new();
+        for pos in self.get_paper_positions()? {
+            let Some(price) = self.get_latest_price(&pos.symbol)? else {
+                continue;
+            };
+            let Some(note) = exit_trigger(
+                pos.entry_price,
+                price,
+                pos.stop_price.or(stop_pct.map(|pct| pos.entry_price * (1.0 - pct / 100.0))),
+                pos.take_price.or(take_pct.map(|pct| pos.entry_price * (1.0 + pct / 100.0))),
+            ) else {
+                continue;
+            };
+
+            let trade = self.execute_paper_trade(
+                &pos.symbol,
+                PaperTradeAction::Sell,
+                pos.quantity,
+                price,
+                pos.linked_event_id,
+                Some(&note),
+            )?;
+            println!("[OK] Paper {} closed: {}", pos.symbol, note);
+            closed.push(trade);
+        }
+        Ok(closed)
+    }
+
+    /// Reset paper trading account (clear all positions, trades, reset cash)
+    pub fn reset_paper_account(&self, starting_cash: f64) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM paper_positions", [])?;
+        tx.execute("DELETE FROM paper_lots WHERE account = 'paper'", [])?;
+        // AI decisions keep their history but lose the link to the deleted trades
+        tx.execute("UPDATE ai_trade_decisions SET paper_trade_id = NULL", [])?;
+        tx.execute("DELETE FROM paper_orders", [])?;
+        tx.execute("DELETE FROM paper_trades", [])?;
+        tx.execute(
+            "UPDATE paper_wallet SET cash = ?1, starting_capital = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
+            params![starting_cash],
+        )?;
+        tx.commit()?;
+        println!("[OK] Paper trading account reset with ${:.2}", starting_cash);
+        Ok(())
+    }
+
+    /// Calculate total paper portfolio value (cash + positions at current prices)
+    /// Returns (cash, positions_value, total_equity)
+    pub fn get_paper_portfolio_value(&self) -> Result<(f64, f64, f64)> {
+        let wallet = self.get_paper_wallet()?;
+        let positions = self.get_paper_positions()?;
+
+        let mut positions_value = 0.0;
+        for pos in positions {
+            // Try to get current price, fall back to entry price
+            let current_price = self
+                .get_latest_price(&pos.symbol)?
+                .unwrap_or(pos.entry_price);
+            positions_value += pos.quantity * current_price;
+        }
+
+        let total_equity = wallet.cash + positions_value;
+        Ok((wallet.cash, positions_value, total_equity))
+    }
+
+    /// Realized paper P&L: sum of booked gains/losses on sell trades
+    pub fn get_paper_realized_pnl(&self) -> Result<f64> {
+        let pnl = self.conn.query_row(
+            "SELECT COALESCE(SUM(pnl), 0.0) FROM paper_trades WHERE action = 'SELL'",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(pnl)
+    }
+
+    /// Unrealized paper P&L: open positions marked to the latest price
+    pub fn get_paper_unrealized_pnl(&self) -> Result<f64> {
+        let mut pnl = 0.0;
+        for pos in self.get_paper_positions()? {
+            let current_price = self
+                .get_latest_price(&pos.symbol)?
+                .unwrap_or(pos.entry_price);
+            pnl += (current_price - pos.entry_price) * pos.quantity;
+        }
+        Ok(pnl)
+    }
+
+    // ========================================================================
+    // DC Trader Methods (Separate from KALIC AI paper trading)
+    // ========================================================================
+
+    /// Initialize DC wallet if not exists
+    pub fn init_dc_wallet(&self) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO dc_wallet (id, cash, starting_capital) VALUES (1, 1000000.0, 1000000.0)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Get DC wallet balance
+    pub fn get_dc_wallet(&self) -> Result<DcWallet> {
+        // Ensure wallet exists
+        self.init_dc_wallet()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT id, cash, starting_capital, created_at, updated_at, per_trade_fee, fee_percent
+             FROM dc_wallet WHERE id = 1",
+        )?;
+
+        let wallet = stmt.query_row([], |row| {
+            Ok(DcWallet {
                 id: row.get(0)?,
                 cash: row.get(1)?,
                 starting_capital: row.get(2)?,
+                fees: TradeFees {
+                    per_trade_fee: row.get(5)?,
+                    fee_percent: row.get(6)?,
+                },
                 created_at: row.get(3)?,
                 updated_at: row.get(4)?,
             })
@@ -2148,11 +3836,21 @@ impl Database {
         Ok(())
     }
 
+    /// Set the commission charged on DC trades from now on
+    pub fn set_dc_fees(&self, fees: &TradeFees) -> Result<()> {
+        validate_trade_fees(fees)?;
+        self.conn.execute(
+            "UPDATE dc_wallet SET per_trade_fee = ?1, fee_percent = ?2, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
+            params![fees.per_trade_fee, fees.fee_percent],
+        )?;
+        Ok(())
+    }
+
     /// Get all DC positions
     pub fn get_dc_positions(&self) -> Result<Vec<DcPosition>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, symbol, quantity, entry_price, entry_date
+            SELECT id, symbol, quantity, entry_price, entry_date, stop_price, take_price
             FROM dc_positions
             ORDER BY entry_date DESC
             "#,
@@ -2166,6 +3864,8 @@ impl Database {
                     quantity: row.get(2)?,
                     entry_price: row.get(3)?,
                     entry_date: row.get(4)?,
+                    stop_price: row.get(5)?,
+                    take_price: row.get(6)?,
                 })
             })?
             .collect::<SqliteResult<Vec<_>>>()?;
@@ -2177,7 +3877,7 @@ impl Database {
     pub fn get_dc_position(&self, symbol: &str) -> Result<Option<DcPosition>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, symbol, quantity, entry_price, entry_date
+            SELECT id, symbol, quantity, entry_price, entry_date, stop_price, take_price
             FROM dc_positions
             WHERE symbol = ?1
             "#,
@@ -2190,6 +3890,8 @@ impl Database {
                 quantity: row.get(2)?,
                 entry_price: row.get(3)?,
                 entry_date: row.get(4)?,
+                stop_price: row.get(5)?,
+                take_price: row.get(6)?,
             })
         });
 
@@ -2200,6 +3902,22 @@ impl Database {
         }
     }
 
+    /// DC counterpart of `execute_paper_buy_affordable`
+    pub fn execute_dc_buy_affordable(
+        &self,
+        symbol: &str,
+        quantity: f64,
+        price: f64,
+        notes: Option<&str>,
+    ) -> Result<DcTrade> {
+        let wallet = self.get_dc_wallet()?;
+        let filled = fill_quantity(&wallet.fees, wallet.cash, quantity, price)?;
+        if filled < quantity {
+            println!("[DC] Partial fill: {} of {} {} affordable", filled, quantity, symbol);
+        }
+        self.execute_dc_trade(symbol, "BUY", filled, price, notes)
+    }
+
     /// Execute a DC trade (BUY or SELL)
     pub fn execute_dc_trade(
         &self,
@@ -2211,20 +3929,21 @@ impl Database {
     ) -> Result<DcTrade> {
         let wallet = self.get_dc_wallet()?;
         let cost = quantity * price;
+        let fee = wallet.fees.fee_for(cost);
         let action_upper = action.to_uppercase();
 
         match action_upper.as_str() {
             "BUY" => {
-                // Validate sufficient cash
-                if wallet.cash < cost {
+                // Validate sufficient cash (including commission)
+                if wallet.cash < cost + fee {
                     return Err(crate::error::PipelineError::ApiError(format!(
                         "Insufficient cash: have ${:.2}, need ${:.2}",
-                        wallet.cash, cost
+                        wallet.cash, cost + fee
                     )));
                 }
 
                 // Deduct cash
-                self.update_dc_cash(wallet.cash - cost)?;
+                self.update_dc_cash(wallet.cash - cost - fee)?;
 
                 // Add or update position
                 let existing = self.get_dc_position(symbol)?;
@@ -2247,13 +3966,16 @@ impl Database {
                     )?;
                 }
 
+                // Record the lot before the trade so last_insert_rowid() is the trade
+                self.add_lot(LOT_ACCOUNT_DC, symbol, quantity, price)?;
+
                 // Record trade
                 self.conn.execute(
                     r#"
-                    INSERT INTO dc_trades (symbol, action, quantity, price, notes)
-                    VALUES (?1, ?2, ?3, ?4, ?5)
+                    INSERT INTO dc_trades (symbol, action, quantity, price, notes, fee)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
                     "#,
-                    params![symbol, "BUY", quantity, price, notes],
+                    params![symbol, "BUY", quantity, price, notes, fee],
                 )?;
             }
             "SELL" => {
@@ -2273,11 +3995,17 @@ impl Database {
                     )));
                 }
 
-                // Calculate P&L
-                let pnl = (price - pos.entry_price) * quantity;
+                // Calculate P&L and the cost basis left on the remaining shares
+                let (pnl, remaining_entry) = self.realize_sale(
+                    LOT_ACCOUNT_DC,
+                    symbol,
+                    quantity,
+                    price,
+                    (pos.quantity, pos.entry_price),
+                )?;
 
-                // Add proceeds to cash
-                self.update_dc_cash(wallet.cash + cost)?;
+                // Add proceeds to cash, net of commission
+                self.update_dc_cash(wallet.cash + cost - fee)?;
 
                 // Update or delete position
                 let remaining = pos.quantity - quantity;
@@ -2288,18 +4016,18 @@ impl Database {
                     )?;
                 } else {
                     self.conn.execute(
-                        "UPDATE dc_positions SET quantity = ?1 WHERE id = ?2",
-                        params![remaining, pos.id],
+                        "UPDATE dc_positions SET quantity = ?1, entry_price = ?2 WHERE id = ?3",
+                        params![remaining, remaining_entry, pos.id],
                     )?;
                 }
 
                 // Record trade with P&L
                 self.conn.execute(
                     r#"
-                    INSERT INTO dc_trades (symbol, action, quantity, price, pnl, notes)
-                    VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                    INSERT INTO dc_trades (symbol, action, quantity, price, pnl, notes, fee)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
                     "#,
-                    params![symbol, "SELL", quantity, price, pnl, notes],
+                    params![symbol, "SELL", quantity, price, pnl, notes, fee],
                 )?;
             }
             _ => {
@@ -2314,7 +4042,7 @@ impl Database {
         let trade_id = self.conn.last_insert_rowid();
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, symbol, action, quantity, price, pnl, timestamp, notes
+            SELECT id, symbol, action, quantity, price, pnl, timestamp, notes, fee
             FROM dc_trades WHERE id = ?1
             "#,
         )?;
@@ -2327,6 +4055,7 @@ impl Database {
                 quantity: row.get(3)?,
                 price: row.get(4)?,
                 pnl: row.get(5)?,
+                fee: row.get(8)?,
                 timestamp: row.get(6)?,
                 notes: row.get(7)?,
             })
@@ -2339,7 +4068,7 @@ impl Database {
     pub fn get_dc_trades(&self, limit: usize) -> Result<Vec<DcTrade>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, symbol, action, quantity, price, pnl, timestamp, notes
+            SELECT id, symbol, action, quantity, price, pnl, timestamp, notes, fee
             FROM dc_trades
             ORDER BY timestamp DESC
             LIMIT ?1
@@ -2355,6 +4084,7 @@ impl Database {
                     quantity: row.get(3)?,
                     price: row.get(4)?,
                     pnl: row.get(5)?,
+                    fee: row.get(8)?,
                     timestamp: row.get(6)?,
                     notes: row.get(7)?,
                 })
@@ -2366,8 +4096,11 @@ impl Database {
 
     /// Reset DC trading account
     pub fn reset_dc_account(&self, starting_cash: f64) -> Result<()> {
+        // The wallet row is created lazily; make sure the update below has a row to hit
+        self.init_dc_wallet()?;
         let tx = self.conn.unchecked_transaction()?;
         tx.execute("DELETE FROM dc_positions", [])?;
+        tx.execute("DELETE FROM paper_lots WHERE account = 'dc'", [])?;
         tx.execute("DELETE FROM dc_trades", [])?;
         tx.execute(
             "UPDATE dc_wallet SET cash = ?1, starting_capital = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
@@ -2403,6 +4136,68 @@ impl Database {
         Ok((wallet.cash, positions_value, total_equity))
     }
 
+    /// Realized DC P&L: sum of booked gains/losses on sell trades
+    pub fn get_dc_realized_pnl(&self) -> Result<f64> {
+        let pnl = self.conn.query_row(
+            "SELECT COALESCE(SUM(pnl), 0.0) FROM dc_trades WHERE action = 'SELL'",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(pnl)
+    }
+
+    /// Unrealized DC P&L: open positions marked to the latest price
+    pub fn get_dc_unrealized_pnl(&self) -> Result<f64> {
+        let mut pnl = 0.0;
+        for pos in self.get_dc_positions()? {
+            let current_price = self
+                .get_latest_price(&pos.symbol)?
+                .unwrap_or(pos.entry_price);
+            pnl += (current_price - pos.entry_price) * pos.quantity;
+        }
+        Ok(pnl)
+    }
+
+    /// Set (or clear with None) a DC position's stop-loss and take-profit prices.
+    /// Returns false if there is no open position in the symbol.
+    pub fn set_dc_position_exits(
+        &self,
+        symbol: &str,
+        stop_price: Option<f64>,
+        take_price: Option<f64>,
+    ) -> Result<bool> {
+        validate_exit_levels(stop_price, take_price)?;
+        let updated = self.conn.execute(
+            "UPDATE dc_positions SET stop_price = ?1, take_price = ?2 WHERE symbol = ?3",
+            params![stop_price, take_price, symbol],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Close DC positions whose latest price has hit a stop-loss or take-profit
+    /// (same rules as `check_paper_stops`). Returns the SELL trades recorded.
+    pub fn check_dc_stops(&self, stop_pct: Option<f64>, take_pct: Option<f64>) -> Result<Vec<DcTrade>> {
+        let mut closed = Vec::new();
+        for pos in self.get_dc_positions()? {
+            let Some(price) = self.get_latest_price(&pos.symbol)? else {
+                continue;
+            };
+            let Some(note) = exit_trigger(
+                pos.entry_price,
+                price,
+                pos.stop_price.or(stop_pct.map(|pct| pos.entry_price * (1.0 - pct / 100.0))),
+                pos.take_price.or(take_pct.map(|pct| pos.entry_price * (1.0 + pct / 100.0))),
+            ) else {
+                continue;
+            };
+
+            let trade = self.execute_dc_trade(&pos.symbol, "SELL", pos.quantity, price, Some(&note))?;
+            println!("[OK] DC {} closed: {}", pos.symbol, note);
+            closed.push(trade);
+        }
+        Ok(closed)
+    }
+
     /// Import multiple DC trades from JSON
     pub fn import_dc_trades_json(&self, trades_json: &str) -> Result<ImportResult> {
         let trades: Vec<serde_json::Value> = serde_json::from_str(trades_json)
@@ -2413,7 +4208,7 @@ impl Database {
         let mut errors: Vec<String> = Vec::new();
 
         for (i, trade) in trades.iter().enumerate() {
-            let symbol = trade["symbol"].as_str().unwrap_or("").to_uppercase();
+            let symbol = trade["symbol"].as_str().and_then(normalize_symbol).unwrap_or_default();
             let action = trade["action"].as_str().unwrap_or("BUY").to_uppercase();
             let quantity = trade["quantity"].as_f64().unwrap_or(0.0);
             let price = trade["price"].as_f64();
@@ -2456,6 +4251,51 @@ impl Database {
         })
     }
 
+    /// Full trade log for a team ("KALIC" paper account or "DC"), oldest first
+    pub fn get_team_trade_log(&self, team: &str) -> Result<Vec<TradeLogEntry>> {
+        let table = match team {
+            "KALIC" => "paper_trades",
+            "DC" => "dc_trades",
+            _ => return Err(crate::error::PipelineError::ApiError(format!("Invalid team: {}", team))),
+        };
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT symbol, action, quantity, price, pnl, timestamp, notes FROM {} ORDER BY id ASC",
+            table
+        ))?;
+
+        let trades = stmt
+            .query_map([], |row| {
+                Ok(TradeLogEntry {
+                    symbol: row.get(0)?,
+                    action: row.get(1)?,
+                    quantity: row.get(2)?,
+                    price: row.get(3)?,
+                    pnl: row.get(4)?,
+                    timestamp: row.get(5)?,
+                    notes: row.get(6)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(trades)
+    }
+
+    /// Export a team's trade log as CSV (symbol, action, quantity, price, pnl, timestamp, notes)
+    pub fn export_trades_csv(&self, team: &str) -> Result<String> {
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        for trade in self.get_team_trade_log(team)? {
+            wtr.serialize(trade)?;
+        }
+        let bytes = wtr.into_inner().map_err(|e| e.into_error())?;
+        String::from_utf8(bytes).map_err(|e| crate::error::PipelineError::ApiError(e.to_string()))
+    }
+
+    /// Export a team's trade log as a JSON array, in the format `import_dc_trades_json` accepts
+    pub fn export_trades_json(&self, team: &str) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.get_team_trade_log(team)?)?)
+    }
+
     /// Import multiple DC trades from CSV
     pub fn import_dc_trades_csv(&self, csv_content: &str) -> Result<ImportResult> {
         let mut success_count = 0;
@@ -2482,7 +4322,14 @@ impl Database {
                 continue;
             }
 
-            let symbol = parts[0].to_uppercase();
+            let symbol = match normalize_symbol(parts[0]) {
+                Some(s) => s,
+                None => {
+                    error_count += 1;
+                    errors.push(format!("Row {}: Invalid symbol '{}'", i + 1, parts[0]));
+                    continue;
+                }
+            };
             let action = parts[1].to_uppercase();
             let quantity: f64 = match parts[2].parse() {
                 Ok(q) => q,
@@ -2554,16 +4401,87 @@ impl Database {
         })
     }
 
-    /// Record a portfolio snapshot for charting
-    pub fn record_portfolio_snapshot(&self, team: &str) -> Result<()> {
-        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    /// Current (cash, positions_value, total_value) of a snapshot team
+    fn snapshot_values(&self, team: &str) -> Result<(f64, f64, f64)> {
+        match team {
+            "KALIC" => self.get_paper_portfolio_value(),
+            "DC" => self.get_dc_portfolio_value(),
+            PORTFOLIO_TEAM => {
+                let mut value = 0.0;
+                for pos in self.get_positions()? {
+                    let price = self.get_latest_price(&pos.symbol)?.unwrap_or(pos.price);
+                    value += position_sign(pos.position_type) * pos.quantity * price;
+                }
+                Ok((0.0, value, value))
+            }
+            _ => Err(crate::error::PipelineError::ApiError(format!("Invalid team: {}", team))),
+        }
+    }
 
-        let (cash, positions_value, total_value) = match team {
-            "KALIC" => self.get_paper_portfolio_value()?,
-            "DC" => self.get_dc_portfolio_value()?,
+    /// Suggested stop for each long position of a team ("KALIC", "DC" or "PORTFOLIO"):
+    /// the latest close minus `atr_multiple` times the STOP_ATR_PERIOD-day ATR.
+    /// Positions without enough stored history for an ATR are skipped.
+    pub fn suggest_stops(&self, team: &str, atr_multiple: f64) -> Result<Vec<StopSuggestion>> {
+        if !atr_multiple.is_finite() || atr_multiple <= 0.0 {
+            return Err(crate::error::PipelineError::ApiError(format!(
+                "ATR multiple must be positive, got {}",
+                atr_multiple
+            )));
+        }
+        let longs: Vec<(String, f64)> = match team {
+            "KALIC" => self
+                .get_paper_positions()?
+                .into_iter()
+                .map(|pos| (pos.symbol, pos.entry_price))
+                .collect(),
+            "DC" => self
+                .get_dc_positions()?
+                .into_iter()
+                .map(|pos| (pos.symbol, pos.entry_price))
+                .collect(),
+            PORTFOLIO_TEAM => {
+                // Net the buy/sell ledger per symbol; entry is the average cost of the buys
+                let mut ledger: BTreeMap<String, (f64, f64, f64)> = BTreeMap::new();
+                for pos in self.get_positions()? {
+                    let (net, bought, cost) = ledger.entry(pos.symbol).or_default();
+                    *net += position_sign(pos.position_type) * pos.quantity;
+                    if pos.position_type == PositionType::Buy {
+                        *bought += pos.quantity;
+                        *cost += pos.quantity * pos.price;
+                    }
+                }
+                ledger
+                    .into_iter()
+                    .filter(|(_, (net, bought, _))| *net > 0.0 && *bought > 0.0)
+                    .map(|(symbol, (_, bought, cost))| (symbol, cost / bought))
+                    .collect()
+            }
             _ => return Err(crate::error::PipelineError::ApiError(format!("Invalid team: {}", team))),
         };
 
+        let mut suggestions = Vec::new();
+        for (symbol, entry_price) in longs {
+            let prices = self.get_prices(&symbol)?;
+            let atr = calculate_atr(&prices, STOP_ATR_PERIOD).last().map(|ind| ind.value);
+            let (Some(atr), Some(latest)) = (atr, prices.last()) else {
+                continue;
+            };
+            suggestions.push(StopSuggestion {
+                suggested_stop: latest.close - atr_multiple * atr,
+                symbol,
+                entry_price,
+                current_price: latest.close,
+                atr,
+            });
+        }
+        Ok(suggestions)
+    }
+
+    /// Record a portfolio snapshot for charting
+    pub fn record_portfolio_snapshot(&self, team: &str) -> Result<()> {
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let (cash, positions_value, total_value) = self.snapshot_values(team)?;
+
         self.conn.execute(
             r#"
             INSERT OR REPLACE INTO portfolio_snapshots (team, date, total_value, cash, positions_value)
@@ -2575,6 +4493,149 @@ impl Database {
         Ok(())
     }
 
+    /// Record today's snapshot for every team that doesn't have one yet.
+    /// Existing rows are left alone, so this is safe to run on every tick.
+    /// Returns the number of snapshots written.
+    pub fn record_missing_snapshots(&self) -> Result<usize> {
+        let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+        let mut written = 0;
+        for team in SNAPSHOT_TEAMS {
+            let (cash, positions_value, total_value) = self.snapshot_values(team)?;
+            written += self.conn.execute(
+                r#"
+                INSERT OR IGNORE INTO portfolio_snapshots (team, date, total_value, cash, positions_value)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                "#,
+                params![team, today, total_value, cash, positions_value],
+            )?;
+        }
+        Ok(written)
+    }
+
+    /// Fill in missing snapshots for trading days in the last `days` days (before today).
+    /// Holdings on each day are marked to that day's close from `daily_prices`: the real
+    /// portfolio from positions opened by then, KALIC and DC by unwinding later trades
+    /// from their current cash and holdings. Since the trade log is cleared on reset,
+    /// days before the last reset are reconstructed as if the account started there.
+    /// Existing snapshots are kept. Returns the number written.
+    pub fn backfill_snapshots(&self, days: i64) -> Result<usize> {
+        let today = chrono::Local::now().date_naive();
+        let start = today - chrono::Duration::days(days.max(1));
+        let dates: Vec<NaiveDate> = self
+            .conn
+            .prepare(
+                "SELECT DISTINCT timestamp FROM daily_prices
+                 WHERE timestamp >= ?1 AND timestamp < ?2 ORDER BY timestamp",
+            )?
+            .query_map(params![start.to_string(), today.to_string()], |row| row.get::<_, String>(0))?
+            .collect::<SqliteResult<Vec<_>>>()?
+            .iter()
+            .filter_map(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .collect();
+        if dates.is_empty() {
+            return Ok(0);
+        }
+
+        let positions = self.get_positions()?;
+        let kalic_trades = self.load_snapshot_trades("paper_trades")?;
+        let dc_trades = self.load_snapshot_trades("dc_trades")?;
+        let kalic_cash = self.get_paper_wallet()?.cash;
+        let dc_cash = self.get_dc_wallet()?.cash;
+
+        let mut closes: HashMap<String, Vec<(NaiveDate, f64)>> = HashMap::new();
+        let symbols = positions
+            .iter()
+            .map(|p| &p.symbol)
+            .chain(kalic_trades.iter().chain(&dc_trades).map(|t| &t.1));
+        for symbol in symbols {
+            if !closes.contains_key(symbol) {
+                let series = self.get_prices(symbol)?.iter().map(|p| (p.date, p.close)).collect();
+                closes.insert(symbol.clone(), series);
+            }
+        }
+        let close_on = |symbol: &str, date: NaiveDate| {
+            closes.get(symbol).and_then(|series| last_close_on(series, date))
+        };
+
+        // (cash, positions_value) of a trade-logged account at the end of `date`
+        let replay = |trades: &[SnapshotTrade], cash_now: f64, date: NaiveDate| {
+            let mut cash = cash_now;
+            let mut holdings: HashMap<&str, f64> = HashMap::new();
+            for (day, symbol, signed_qty, price, fee) in trades {
+                if *day > date {
+                    // Undo the cash flow (and commission) of trades that hadn't happened yet
+                    cash += signed_qty * price + fee;
+                } else {
+                    *holdings.entry(symbol.as_str()).or_default() += signed_qty;
+                }
+            }
+            let value: f64 = holdings
+                .iter()
+                .filter(|(_, qty)| qty.abs() > f64::EPSILON)
+                .map(|(symbol, qty)| qty * close_on(symbol, date).unwrap_or(0.0))
+                .sum();
+            (cash, value)
+        };
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut written = 0;
+        for date in dates {
+            let portfolio: f64 = positions
+                .iter()
+                .filter(|p| {
+                    p.date
+                        .get(..10)
+                        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                        .is_none_or(|opened| opened <= date)
+                })
+                .map(|p| {
+                    let price = close_on(&p.symbol, date).unwrap_or(p.price);
+                    position_sign(p.position_type) * p.quantity * price
+                })
+                .sum();
+            let rows = [
+                ("KALIC", replay(&kalic_trades, kalic_cash, date)),
+                ("DC", replay(&dc_trades, dc_cash, date)),
+                (PORTFOLIO_TEAM, (0.0, portfolio)),
+            ];
+            for (team, (cash, positions_value)) in rows {
+                written += tx.execute(
+                    r#"
+                    INSERT OR IGNORE INTO portfolio_snapshots (team, date, total_value, cash, positions_value)
+                    VALUES (?1, ?2, ?3, ?4, ?5)
+                    "#,
+                    params![team, date.to_string(), cash + positions_value, cash, positions_value],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(written)
+    }
+
+    /// Every trade in a paper/DC trade log, oldest first
+    fn load_snapshot_trades(&self, table: &str) -> Result<Vec<SnapshotTrade>> {
+        let trades = self
+            .conn
+            .prepare(&format!(
+                "SELECT substr(timestamp, 1, 10), symbol, action, quantity, price, fee FROM {} ORDER BY id",
+                table
+            ))?
+            .query_map([], |row| {
+                let day: String = row.get(0)?;
+                let action: String = row.get(2)?;
+                let quantity: f64 = row.get(3)?;
+                Ok((
+                    NaiveDate::parse_from_str(&day, "%Y-%m-%d").unwrap_or(NaiveDate::MIN),
+                    row.get(1)?,
+                    if action == "SELL" { -quantity } else { quantity },
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(trades)
+    }
+
     /// Get portfolio snapshots for charting
     pub fn get_portfolio_snapshots(&self, team: Option<&str>, days: i32) -> Result<Vec<PortfolioSnapshot>> {
         let sql = match team {
@@ -2626,17 +4687,27 @@ impl Database {
         Ok(snapshots)
     }
 
-    /// Save a team configuration
+    /// Save a team configuration (current cash and fee settings of both accounts)
     pub fn save_team_config(&self, name: &str, description: Option<&str>) -> Result<i64> {
         let kalic_wallet = self.get_paper_wallet()?;
         let dc_wallet = self.get_dc_wallet()?;
 
         self.conn.execute(
             r#"
-            INSERT OR REPLACE INTO trading_teams (name, description, kalic_starting_capital, dc_starting_capital)
-            VALUES (?1, ?2, ?3, ?4)
+            INSERT OR REPLACE INTO trading_teams (name, description, kalic_starting_capital, dc_starting_capital,
+                kalic_per_trade_fee, kalic_fee_percent, dc_per_trade_fee, dc_fee_percent)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
             "#,
-            params![name, description, kalic_wallet.cash, dc_wallet.cash],
+            params![
+                name,
+                description,
+                kalic_wallet.cash,
+                dc_wallet.cash,
+                kalic_wallet.fees.per_trade_fee,
+                kalic_wallet.fees.fee_percent,
+                dc_wallet.fees.per_trade_fee,
+                dc_wallet.fees.fee_percent,
+            ],
         )?;
 
         Ok(self.conn.last_insert_rowid())
@@ -2646,22 +4717,14 @@ impl Database {
     pub fn load_team_config(&self, name: &str) -> Result<TeamConfig> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, name, description, kalic_starting_capital, dc_starting_capital, created_at
+            SELECT id, name, description, kalic_starting_capital, dc_starting_capital, created_at,
+                   kalic_per_trade_fee, kalic_fee_percent, dc_per_trade_fee, dc_fee_percent
             FROM trading_teams
             WHERE name = ?1
             "#,
         )?;
 
-        let config = stmt.query_row(params![name], |row| {
-            Ok(TeamConfig {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                kalic_starting_capital: row.get(3)?,
-                dc_starting_capital: row.get(4)?,
-                created_at: row.get(5)?,
-            })
-        })?;
+        let config = stmt.query_row(params![name], map_team_config)?;
 
         Ok(config)
     }
@@ -2670,23 +4733,15 @@ impl Database {
     pub fn list_team_configs(&self) -> Result<Vec<TeamConfig>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT id, name, description, kalic_starting_capital, dc_starting_capital, created_at
+            SELECT id, name, description, kalic_starting_capital, dc_starting_capital, created_at,
+                   kalic_per_trade_fee, kalic_fee_percent, dc_per_trade_fee, dc_fee_percent
             FROM trading_teams
             ORDER BY created_at DESC
             "#,
         )?;
 
         let configs = stmt
-            .query_map([], |row| {
-                Ok(TeamConfig {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    description: row.get(2)?,
-                    kalic_starting_capital: row.get(3)?,
-                    dc_starting_capital: row.get(4)?,
-                    created_at: row.get(5)?,
-                })
-            })?
+            .query_map([], map_team_config)?
             .collect::<SqliteResult<Vec<_>>>()?;
 
         Ok(configs)
@@ -2711,16 +4766,25 @@ impl Database {
             [],
             |row| row.get(0),
         )?;
+        let kalic_fees_paid: f64 = self.conn.query_row(
+            "SELECT COALESCE(SUM(fee), 0.0) FROM paper_trades",
+            [],
+            |row| row.get(0),
+        )?;
+        let dc_fees_paid: f64 = self.conn.query_row(
+            "SELECT COALESCE(SUM(fee), 0.0) FROM dc_trades",
+            [],
+            |row| row.get(0),
+        )?;
 
-        // Calculate P&L percentages
-        let kalic_starting = 1000000.0; // Default starting capital
-        let kalic_pnl_pct = ((kalic_total - kalic_starting) / kalic_starting) * 100.0;
-        let dc_pnl_pct = ((dc_total - dc_wallet.starting_capital) / dc_wallet.starting_capital) * 100.0;
+        // Both sides are measured against their own stored starting capital
+        let kalic_pnl_pct = kalic_wallet.pnl_percent(kalic_total);
+        let dc_pnl_pct = dc_wallet.pnl_percent(dc_total);
 
-        // Determine leader
-        let leader = if kalic_total > dc_total {
+        // Determine leader by return, not raw account size
+        let leader = if kalic_pnl_pct > dc_pnl_pct {
             "KALIC".to_string()
-        } else if dc_total > kalic_total {
+        } else if dc_pnl_pct > kalic_pnl_pct {
             "DC".to_string()
         } else {
             "TIE".to_string()
@@ -2732,13 +4796,15 @@ impl Database {
             kalic_positions,
             kalic_pnl_pct,
             kalic_trades,
+            kalic_fees_paid,
             dc_total,
             dc_cash,
             dc_positions,
             dc_pnl_pct,
             dc_trades,
+            dc_fees_paid,
             leader,
-            lead_amount: (kalic_total - dc_total).abs(),
+            lead_amount: (kalic_pnl_pct - dc_pnl_pct).abs(),
         })
     }
 
@@ -2753,7 +4819,7 @@ impl Database {
                     take_profit_percent, session_duration_minutes, benchmark_symbol, model_priority,
                     trading_mode, daily_loss_threshold, consecutive_loss_limit,
                     auto_conservative_on_trigger, max_daily_trades, max_single_trade_value,
-                    require_confluence, blocked_hours
+                    require_confluence, blocked_hours, risk_per_trade_percent, allow_partial_fills
              FROM ai_trader_config WHERE id = 1"#,
             [],
             |row| {
@@ -2776,6 +4842,8 @@ impl Database {
                     max_single_trade_value: row.get(12)?,
                     require_confluence: row.get::<_, i32>(13)? != 0,
                     blocked_hours: row.get(14)?,
+                    risk_per_trade_percent: row.get(15)?,
+                    allow_partial_fills: row.get::<_, i32>(16)? != 0,
                 })
             },
         )?;
@@ -2793,6 +4861,7 @@ impl Database {
                 consecutive_loss_limit = ?10, auto_conservative_on_trigger = ?11,
                 max_daily_trades = ?12, max_single_trade_value = ?13,
                 require_confluence = ?14, blocked_hours = ?15,
+                risk_per_trade_percent = ?16, allow_partial_fills = ?17,
                 updated_at = CURRENT_TIMESTAMP
              WHERE id = 1"#,
             params![
@@ -2811,6 +4880,8 @@ impl Database {
                 config.max_single_trade_value,
                 config.require_confluence as i32,
                 config.blocked_hours,
+                config.risk_per_trade_percent,
+                config.allow_partial_fills as i32,
             ],
         )?;
         Ok(())
@@ -2901,8 +4972,8 @@ impl Database {
             "INSERT INTO ai_trade_decisions
                 (session_id, action, symbol, quantity, price_at_decision, confidence, reasoning,
                  model_used, predicted_direction, predicted_price_target, predicted_timeframe_days,
-                 paper_trade_id)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                 paper_trade_id, dry_run)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
             params![
                 decision.session_id,
                 decision.action,
@@ -2916,11 +4987,12 @@ impl Database {
                 decision.predicted_price_target,
                 decision.predicted_timeframe_days,
                 decision.paper_trade_id,
+                decision.dry_run as i32,
             ],
         )?;
 
-        // Update session decision count
-        if let Some(sid) = decision.session_id {
+        // Update session decision count (dry runs don't count towards the session)
+        if let Some(sid) = decision.session_id.filter(|_| !decision.dry_run) {
             self.conn.execute(
                 "UPDATE ai_trading_sessions SET decisions_count = decisions_count + 1 WHERE id = ?1",
                 params![sid],
@@ -2947,7 +5019,7 @@ impl Database {
             "SELECT id, session_id, timestamp, action, symbol, quantity, price_at_decision,
                     confidence, reasoning, model_used, predicted_direction, predicted_price_target,
                     predicted_timeframe_days, actual_outcome, actual_price_at_timeframe,
-                    prediction_accurate, paper_trade_id
+                    prediction_accurate, paper_trade_id, dry_run
              FROM ai_trade_decisions WHERE 1=1"
         );
 
@@ -2998,6 +5070,7 @@ impl Database {
             actual_price_at_timeframe: row.get(14)?,
             prediction_accurate: row.get(15)?,
             paper_trade_id: row.get(16)?,
+            dry_run: row.get::<_, i32>(17)? != 0,
         })
     }
 
@@ -3007,8 +5080,8 @@ impl Database {
             "INSERT INTO ai_performance_snapshots
                 (portfolio_value, cash, positions_value, benchmark_value, benchmark_symbol,
                  total_pnl, total_pnl_percent, benchmark_pnl_percent, prediction_accuracy,
-                 trades_to_date, winning_trades, losing_trades, win_rate)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                 trades_to_date, winning_trades, losing_trades, win_rate, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
             params![
                 snapshot.portfolio_value,
                 snapshot.cash,
@@ -3023,6 +5096,7 @@ impl Database {
                 snapshot.winning_trades,
                 snapshot.losing_trades,
                 snapshot.win_rate,
+                snapshot.timestamp,
             ],
         )?;
         Ok(self.conn.last_insert_rowid())
@@ -3122,7 +5196,7 @@ impl Database {
             "SELECT id, session_id, timestamp, action, symbol, quantity, price_at_decision,
                     confidence, reasoning, model_used, predicted_direction, predicted_price_target,
                     predicted_timeframe_days, actual_outcome, actual_price_at_timeframe,
-                    prediction_accurate, paper_trade_id
+                    prediction_accurate, paper_trade_id, dry_run
              FROM ai_trade_decisions
              WHERE prediction_accurate IS NULL
                AND predicted_timeframe_days IS NOT NULL
@@ -3185,11 +5259,14 @@ impl Database {
         let tx = self.conn.unchecked_transaction()?;
         tx.execute("DELETE FROM ai_performance_snapshots", [])?;
         tx.execute("DELETE FROM ai_trade_decisions", [])?;
+        tx.execute("DELETE FROM trade_rejections", [])?;
         tx.execute("DELETE FROM ai_trading_sessions", [])?;
         tx.execute("DELETE FROM paper_positions", [])?;
+        tx.execute("DELETE FROM paper_lots WHERE account = 'paper'", [])?;
+        tx.execute("DELETE FROM paper_orders", [])?;
         tx.execute("DELETE FROM paper_trades", [])?;
         tx.execute(
-            "UPDATE paper_wallet SET cash = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
+            "UPDATE paper_wallet SET cash = ?1, starting_capital = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
             params![starting_capital],
         )?;
         tx.commit()?;
@@ -3227,12 +5304,20 @@ impl Database {
         new_mode: &str,
         daily_pnl: f64,
         consecutive_losses: i32,
+        resume_at: Option<DateTime<Utc>>,
     ) -> Result<i64> {
         self.conn.execute(
             r#"INSERT INTO circuit_breaker_events
                (trigger_type, previous_mode, new_mode, daily_pnl, consecutive_losses, resume_at)
-               VALUES (?1, ?2, ?3, ?4, ?5, datetime('now', '+1 hour'))"#,
-            params![trigger_type, previous_mode, new_mode, daily_pnl, consecutive_losses],
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+            params![
+                trigger_type,
+                previous_mode,
+                new_mode,
+                daily_pnl,
+                consecutive_losses,
+                resume_at.map(|dt| dt.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            ],
         )?;
         Ok(self.conn.last_insert_rowid())
     }
@@ -3263,7 +5348,7 @@ impl Database {
     /// Get paper trades from today only
     pub fn get_paper_trades_today(&self) -> Result<Vec<PaperTrade>> {
         let mut stmt = self.conn.prepare(
-            r#"SELECT id, symbol, action, quantity, price, pnl, timestamp, linked_event_id, notes
+            r#"SELECT id, symbol, action, quantity, price, pnl, timestamp, linked_event_id, notes, fee
                FROM paper_trades
                WHERE date(timestamp) = date('now')
                ORDER BY timestamp DESC"#
@@ -3277,6 +5362,7 @@ impl Database {
                 quantity: row.get(3)?,
                 price: row.get(4)?,
                 pnl: row.get(5)?,
+                fee: row.get(9)?,
                 timestamp: row.get(6)?,
                 linked_event_id: row.get(7)?,
                 notes: row.get(8)?,
@@ -3335,6 +5421,119 @@ impl Database {
         Ok(events)
     }
 
+    /// Persisted circuit breaker state: whether it is tripped and when trading may resume
+    pub fn get_circuit_breaker_state(&self) -> Result<(bool, Option<DateTime<Utc>>)> {
+        let (triggered, until): (i32, Option<String>) = self.conn.query_row(
+            "SELECT circuit_breaker_triggered, circuit_breaker_until FROM ai_trader_config WHERE id = 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let until = until
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc));
+        Ok((triggered != 0, until))
+    }
+
+    /// Persist the circuit breaker state so a pause survives restarts
+    pub fn set_circuit_breaker_state(&self, triggered: bool, until: Option<DateTime<Utc>>) -> Result<()> {
+        self.conn.execute(
+            r#"UPDATE ai_trader_config SET
+               circuit_breaker_triggered = ?1,
+               circuit_breaker_until = ?2,
+               updated_at = CURRENT_TIMESTAMP
+               WHERE id = 1"#,
+            params![
+                triggered as i32,
+                until.map(|dt| dt.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Portfolio value the daily loss is measured from, and the day it applies to
+    pub fn get_daily_loss_baseline(&self) -> Result<Option<(NaiveDate, f64)>> {
+        let (date, value): (Option<String>, Option<f64>) = self.conn.query_row(
+            "SELECT daily_loss_baseline_date, daily_loss_baseline FROM ai_trader_config WHERE id = 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let date = date.and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok());
+        Ok(date.zip(value))
+    }
+
+    /// Re-base the daily loss for `date`, so a loss the breaker already acted on isn't counted again
+    pub fn set_daily_loss_baseline(&self, date: NaiveDate, value: f64) -> Result<()> {
+        self.conn.execute(
+            r#"UPDATE ai_trader_config SET
+               daily_loss_baseline_date = ?1,
+               daily_loss_baseline = ?2,
+               updated_at = CURRENT_TIMESTAMP
+               WHERE id = 1"#,
+            params![date.format("%Y-%m-%d").to_string(), value],
+        )?;
+        Ok(())
+    }
+
+    /// Portfolio value from the last performance snapshot taken before `date`
+    pub fn get_ai_portfolio_value_before(&self, date: NaiveDate) -> Result<Option<f64>> {
+        let value = self.conn.query_row(
+            "SELECT portfolio_value FROM ai_performance_snapshots
+             WHERE date(timestamp) < ?1 ORDER BY timestamp DESC LIMIT 1",
+            params![date.format("%Y-%m-%d").to_string()],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(value)
+    }
+
+    /// Mode in force before the most recent circuit breaker trip
+    pub fn get_last_circuit_breaker_previous_mode(&self) -> Result<Option<String>> {
+        let mode = self.conn.query_row(
+            "SELECT previous_mode FROM circuit_breaker_events ORDER BY id DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(mode)
+    }
+
+    /// When the breaker last tripped for the given trigger type
+    pub fn get_last_circuit_breaker_trip(&self, trigger_type: &str) -> Result<Option<String>> {
+        let timestamp = self.conn.query_row(
+            "SELECT timestamp FROM circuit_breaker_events WHERE trigger_type = ?1 ORDER BY id DESC LIMIT 1",
+            params![trigger_type],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(timestamp)
+    }
+
+    /// Current losing streak: paper sells newest-first, counted until the first win
+    pub fn get_consecutive_losses(&self) -> Result<u32> {
+        self.get_consecutive_losses_since(None)
+    }
+
+    /// Losing streak counting only sells recorded after `since` (a `timestamp` value).
+    /// Break-even sells neither extend nor end the streak.
+    pub fn get_consecutive_losses_since(&self, since: Option<&str>) -> Result<u32> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT pnl FROM paper_trades
+               WHERE action = 'SELL' AND pnl IS NOT NULL
+                 AND (?1 IS NULL OR timestamp > ?1)
+               ORDER BY id DESC"#,
+        )?;
+        let mut rows = stmt.query(params![since])?;
+
+        let mut losses = 0;
+        while let Some(row) = rows.next()? {
+            let pnl: f64 = row.get(0)?;
+            if pnl > 0.0 {
+                break;
+            }
+            if pnl < 0.0 {
+                losses += 1;
+            }
+        }
+        Ok(losses)
+    }
+
     /// Update circuit breaker settings in config
     pub fn update_circuit_breaker_settings(
         &self,
@@ -3353,505 +5552,3509 @@ impl Database {
         )?;
         Ok(())
     }
-}
 
-/// Database schema SQL
-const SCHEMA_SQL: &str = r#"
--- Symbol master table
-CREATE TABLE IF NOT EXISTS symbols (
-    symbol TEXT PRIMARY KEY,
-    name TEXT,
-    sector TEXT,
-    industry TEXT,
-    market_cap REAL,
-    country TEXT,
-    exchange TEXT,
-    currency TEXT,
-    isin TEXT,
-    asset_class TEXT,
-    favorited INTEGER DEFAULT 0,
-    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-);
+    // ========================================================================
+    // App Settings Methods
+    // ========================================================================
 
+    /// Get an app setting value by key
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let value = self
+            .conn
+            .query_row(
+                "SELECT value FROM app_settings WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value)
+    }
 
--- Daily price data
-CREATE TABLE IF NOT EXISTS daily_prices (
-    symbol TEXT,
-    timestamp DATE,
-    open REAL,
-    high REAL,
-    low REAL,
-    close REAL,
-    volume INTEGER,
-    adjusted_close REAL,
-    source TEXT,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-    PRIMARY KEY (symbol, timestamp)
-);
+    /// Set an app setting value (insert or overwrite)
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            r#"INSERT INTO app_settings (key, value, updated_at)
+               VALUES (?1, ?2, CURRENT_TIMESTAMP)
+               ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP"#,
+            params![key, value],
+        )?;
+        Ok(())
+    }
 
--- Macro economic indicators
-CREATE TABLE IF NOT EXISTS macro_data (
-    indicator TEXT,
-    date DATE,
-    value REAL,
-    frequency TEXT,
-    source TEXT DEFAULT 'FRED',
-    PRIMARY KEY (indicator, date)
-);
+    // ========================================================================
+    // Signal Config Methods
+    // ========================================================================
 
--- Watchlists
-CREATE TABLE IF NOT EXISTS watchlists (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    name TEXT UNIQUE,
-    description TEXT,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-);
+    /// Get the persisted signal detection thresholds
+    pub fn get_signal_config(&self) -> Result<SignalConfig> {
+        let config = self.conn.query_row(
+            r#"SELECT rsi_overbought, rsi_oversold, adx_strong_trend, adx_weak_trend,
+                    stoch_overbought, stoch_oversold, willr_overbought, willr_oversold,
+                    cci_overbought, cci_oversold, mfi_overbought, mfi_oversold, macd_min_spread,
+                    uo_overbought, uo_oversold, gap_min_percent,
+                    stoch_rsi_overbought, stoch_rsi_oversold
+             FROM signal_config WHERE id = 1"#,
+            [],
+            |row| {
+                Ok(SignalConfig {
+                    rsi_overbought: row.get(0)?,
+                    rsi_oversold: row.get(1)?,
+                    adx_strong_trend: row.get(2)?,
+                    adx_weak_trend: row.get(3)?,
+                    stoch_overbought: row.get(4)?,
+                    stoch_oversold: row.get(5)?,
+                    willr_overbought: row.get(6)?,
+                    willr_oversold: row.get(7)?,
+                    cci_overbought: row.get(8)?,
+                    cci_oversold: row.get(9)?,
+                    mfi_overbought: row.get(10)?,
+                    mfi_oversold: row.get(11)?,
+                    macd_min_spread: row.get(12)?,
+                    uo_overbought: row.get(13)?,
+                    uo_oversold: row.get(14)?,
+                    gap_min_percent: row.get(15)?,
+                    stoch_rsi_overbought: row.get(16)?,
+                    stoch_rsi_oversold: row.get(17)?,
+                })
+            },
+        )?;
+        Ok(config)
+    }
 
-CREATE TABLE IF NOT EXISTS watchlist_symbols (
-    watchlist_id INTEGER,
-    symbol TEXT,
-    added_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-    notes TEXT,
-    PRIMARY KEY (watchlist_id, symbol),
-    FOREIGN KEY (watchlist_id) REFERENCES watchlists(id)
-);
+    /// Validate and persist signal detection thresholds
+    pub fn update_signal_config(&self, config: &SignalConfig) -> Result<()> {
+        config.validate()?;
+        self.conn.execute(
+            r#"UPDATE signal_config SET
+                rsi_overbought = ?1, rsi_oversold = ?2,
+                adx_strong_trend = ?3, adx_weak_trend = ?4,
+                stoch_overbought = ?5, stoch_oversold = ?6,
+                willr_overbought = ?7, willr_oversold = ?8,
+                cci_overbought = ?9, cci_oversold = ?10,
+                mfi_overbought = ?11, mfi_oversold = ?12,
+                macd_min_spread = ?13,
+                uo_overbought = ?14, uo_oversold = ?15,
+                gap_min_percent = ?16,
+                stoch_rsi_overbought = ?17, stoch_rsi_oversold = ?18,
+                updated_at = CURRENT_TIMESTAMP
+             WHERE id = 1"#,
+            params![
+                config.rsi_overbought,
+                config.rsi_oversold,
+                config.adx_strong_trend,
+                config.adx_weak_trend,
+                config.stoch_overbought,
+                config.stoch_oversold,
+                config.willr_overbought,
+                config.willr_oversold,
+                config.cci_overbought,
+                config.cci_oversold,
+                config.mfi_overbought,
+                config.mfi_oversold,
+                config.macd_min_spread,
+                config.uo_overbought,
+                config.uo_oversold,
+                config.gap_min_percent,
+                config.stoch_rsi_overbought,
+                config.stoch_rsi_oversold,
+            ],
+        )?;
+        Ok(())
+    }
 
--- API call tracking
-CREATE TABLE IF NOT EXISTS api_calls (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    source TEXT,
-    endpoint TEXT,
-    symbol TEXT,
-    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
-    success BOOLEAN,
-    error_message TEXT
-);
+    /// Get the stored per signal type strength multipliers
+    pub fn get_signal_weights(&self) -> Result<SignalWeights> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT signal_type, weight FROM signal_weights")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))?;
+
+        let mut weights = SignalWeights::default();
+        for row in rows {
+            let (name, weight) = row?;
+            if let Some(signal_type) = SignalType::from_str(&name) {
+                weights.set(signal_type, weight);
+            }
+        }
+        Ok(weights)
+    }
 
--- Indexes
-CREATE INDEX IF NOT EXISTS idx_prices_symbol ON daily_prices(symbol);
-CREATE INDEX IF NOT EXISTS idx_prices_timestamp ON daily_prices(timestamp);
-CREATE INDEX IF NOT EXISTS idx_prices_source ON daily_prices(source);
-CREATE INDEX IF NOT EXISTS idx_symbols_sector ON symbols(sector);
-CREATE INDEX IF NOT EXISTS idx_macro_indicator ON macro_data(indicator);
-CREATE INDEX IF NOT EXISTS idx_macro_date ON macro_data(date);
-CREATE INDEX IF NOT EXISTS idx_api_calls_source ON api_calls(source);
-CREATE INDEX IF NOT EXISTS idx_api_calls_timestamp ON api_calls(timestamp);
+    /// Validate and replace the stored signal weights
+    pub fn update_signal_weights(&mut self, weights: &SignalWeights) -> Result<()> {
+        weights.validate()?;
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM signal_weights", [])?;
+        for (name, weight) in weights.iter() {
+            tx.execute(
+                "INSERT INTO signal_weights (signal_type, weight) VALUES (?1, ?2)",
+                params![name, weight],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
 
--- Views
-CREATE VIEW IF NOT EXISTS latest_prices AS
-SELECT p.*
-FROM daily_prices p
-INNER JOIN (
-    SELECT symbol, MAX(timestamp) as max_date
-    FROM daily_prices
-    GROUP BY symbol
-) latest ON p.symbol = latest.symbol AND p.timestamp = latest.max_date;
+    // ========================================================================
+    // Refresh Settings Methods
+    // ========================================================================
 
-CREATE VIEW IF NOT EXISTS api_rate_limits AS
-SELECT
-    source,
-    COUNT(*) as calls_today,
-    MAX(timestamp) as last_call
-FROM api_calls
-WHERE DATE(timestamp) = DATE('now')
-GROUP BY source;
+    /// Get the favorited-symbol auto-refresh settings
+    pub fn get_refresh_settings(&self) -> Result<RefreshSettings> {
+        let settings = self.conn.query_row(
+            "SELECT enabled, interval_minutes, stale_after_days FROM refresh_settings WHERE id = 1",
+            [],
+            |row| {
+                Ok(RefreshSettings {
+                    enabled: row.get::<_, i32>(0)? != 0,
+                    interval_minutes: row.get(1)?,
+                    stale_after_days: row.get(2)?,
+                })
+            },
+        )?;
+        Ok(settings)
+    }
 
--- Technical indicators
-CREATE TABLE IF NOT EXISTS technical_indicators (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    symbol TEXT NOT NULL,
-    timestamp DATE NOT NULL,
-    indicator_name TEXT NOT NULL,
-    value REAL NOT NULL,
-    params TEXT,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-    UNIQUE(symbol, timestamp, indicator_name)
-);
+    /// Persist auto-refresh settings (interval and staleness threshold must be at least one)
+    pub fn update_refresh_settings(&self, settings: &RefreshSettings) -> Result<()> {
+        if settings.interval_minutes == 0 {
+            return Err(crate::error::PipelineError::ApiError(
+                "Refresh interval must be at least 1 minute".to_string(),
+            ));
+        }
+        if settings.stale_after_days == 0 {
+            return Err(crate::error::PipelineError::ApiError(
+                "Staleness threshold must be at least 1 day".to_string(),
+            ));
+        }
+        self.conn.execute(
+            r#"UPDATE refresh_settings SET
+                enabled = ?1, interval_minutes = ?2, stale_after_days = ?3,
+                updated_at = CURRENT_TIMESTAMP
+             WHERE id = 1"#,
+            params![settings.enabled as i32, settings.interval_minutes, settings.stale_after_days],
+        )?;
+        Ok(())
+    }
 
-CREATE INDEX IF NOT EXISTS idx_ti_symbol_date ON technical_indicators(symbol, timestamp);
-CREATE INDEX IF NOT EXISTS idx_ti_indicator ON technical_indicators(indicator_name);
+    // ========================================================================
+    // Retention Methods
+    // ========================================================================
 
--- Price alerts
-CREATE TABLE IF NOT EXISTS price_alerts (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    symbol TEXT NOT NULL,
-    target_price REAL NOT NULL,
-    condition TEXT NOT NULL CHECK(condition IN ('above', 'below')),
-    triggered BOOLEAN DEFAULT 0,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-);
+    /// Per-table retention overrides (days), keyed by table name
+    pub fn get_retention_overrides(&self) -> Result<HashMap<String, u32>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT table_name, retention_days FROM retention_settings")?;
+        let overrides = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<SqliteResult<HashMap<String, u32>>>()?;
+        Ok(overrides)
+    }
 
-CREATE INDEX IF NOT EXISTS idx_alerts_symbol ON price_alerts(symbol);
-CREATE INDEX IF NOT EXISTS idx_alerts_triggered ON price_alerts(triggered);
+    /// Set how many days of rows a prunable table keeps; `None` falls back to the prune default
+    pub fn set_retention_override(&self, table: &str, days: Option<u32>) -> Result<()> {
+        if !PRUNABLE_TABLES.iter().any(|(name, _)| *name == table) {
+            return Err(crate::error::PipelineError::ApiError(format!(
+                "{} is not a prunable table",
+                table
+            )));
+        }
+        match days {
+            Some(0) => Err(crate::error::PipelineError::ApiError(
+                "Retention must be at least 1 day".to_string(),
+            )),
+            Some(days) => {
+                self.conn.execute(
+                    r#"INSERT INTO retention_settings (table_name, retention_days) VALUES (?1, ?2)
+                       ON CONFLICT(table_name) DO UPDATE SET
+                           retention_days = excluded.retention_days, updated_at = CURRENT_TIMESTAMP"#,
+                    params![table, days],
+                )?;
+                Ok(())
+            }
+            None => {
+                self.conn.execute(
+                    "DELETE FROM retention_settings WHERE table_name = ?1",
+                    params![table],
+                )?;
+                Ok(())
+            }
+        }
+    }
 
--- Portfolio positions
-CREATE TABLE IF NOT EXISTS portfolio_positions (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    symbol TEXT NOT NULL,
-    quantity REAL NOT NULL,
+    /// Delete rows older than the retention window from every prunable table in one transaction.
+    /// Tables with an override in retention_settings use it instead of `retention_days`.
+    pub fn prune_old_data(&self, retention_days: u32) -> Result<Vec<PrunedTable>> {
+        if retention_days == 0 {
+            return Err(crate::error::PipelineError::ApiError(
+                "Retention must be at least 1 day".to_string(),
+            ));
+        }
+        let overrides = self.get_retention_overrides()?;
+
+        let tx = self.conn.unchecked_transaction()?;
+        let mut pruned = Vec::with_capacity(PRUNABLE_TABLES.len());
+        for (table, column) in PRUNABLE_TABLES {
+            let days = overrides.get(table).copied().unwrap_or(retention_days);
+            // julianday() reads both plain dates and RFC 3339 timestamps
+            let deleted = tx.execute(
+                &format!(
+                    "DELETE FROM {} WHERE julianday({}) < julianday('now', ?1)",
+                    table, column
+                ),
+                params![format!("-{} days", days)],
+            )?;
+            pruned.push(PrunedTable {
+                table: table.to_string(),
+                retention_days: days,
+                deleted,
+            });
+        }
+        tx.commit()?;
+        Ok(pruned)
+    }
+
+    // ========================================================================
+    // Symbol Merge Methods
+    // ========================================================================
+
+    /// Symbols stored under more than one casing (e.g. `aapl` and `AAPL`)
+    pub fn find_duplicate_symbols(&self) -> Result<Vec<DuplicateSymbol>> {
+        let union = SYMBOL_KEYED_TABLES
+            .iter()
+            .chain(POSITION_TABLES.iter())
+            .map(|table| format!("SELECT symbol FROM {}", table))
+            .collect::<Vec<_>>()
+            .join(" UNION ");
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT symbol FROM ({}) WHERE symbol IS NOT NULL ORDER BY symbol",
+            union
+        ))?;
+        let symbols = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for symbol in symbols {
+            groups.entry(symbol.to_uppercase()).or_default().push(symbol);
+        }
+        Ok(groups
+            .into_iter()
+            .filter(|(_, variants)| variants.len() > 1)
+            .map(|(canonical, variants)| DuplicateSymbol { canonical, variants })
+            .collect())
+    }
+
+    /// Move every row stored under `from` to `to` in one transaction. Open paper and DC
+    /// positions are combined into one at the quantity-weighted average entry price.
+    /// Returns the number of rows moved or combined.
+    pub fn merge_symbol(&mut self, from: &str, to: &str) -> Result<usize> {
+        let (from, to) = (from.trim(), to.trim());
+        if from.is_empty() || to.is_empty() || from == to {
+            return Err(crate::error::PipelineError::ApiError(
+                "Merge needs two different symbols".to_string(),
+            ));
+        }
+
+        let tx = self.conn.transaction()?;
+        let mut moved = 0;
+        for table in SYMBOL_KEYED_TABLES {
+            moved += tx.execute(
+                &format!("UPDATE OR IGNORE {} SET symbol = ?2 WHERE symbol = ?1", table),
+                params![from, to],
+            )?;
+            tx.execute(&format!("DELETE FROM {} WHERE symbol = ?1", table), params![from])?;
+        }
+
+        for table in POSITION_TABLES {
+            // The target's own row sorts first and survives, keeping its stops and entry date
+            let rows = tx
+                .prepare(&format!(
+                    "SELECT id, symbol, quantity, entry_price FROM {} WHERE symbol IN (?1, ?2) ORDER BY symbol = ?2 DESC, id",
+                    table
+                ))?
+                .query_map(params![from, to], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, f64>(2)?,
+                        row.get::<_, f64>(3)?,
+                    ))
+                })?
+                .collect::<SqliteResult<Vec<_>>>()?;
+            let Some((keep_id, _, _, first_price)) = rows.first().cloned() else {
+                continue;
+            };
+            let quantity: f64 = rows.iter().map(|(_, _, qty, _)| qty).sum();
+            let cost: f64 = rows.iter().map(|(_, _, qty, price)| qty * price).sum();
+            let entry_price = if quantity > 0.0 { cost / quantity } else { first_price };
+
+            tx.execute(
+                &format!("DELETE FROM {} WHERE symbol IN (?1, ?2) AND id != ?3", table),
+                params![from, to, keep_id],
+            )?;
+            tx.execute(
+                &format!("UPDATE {} SET symbol = ?1, quantity = ?2, entry_price = ?3 WHERE id = ?4", table),
+                params![to, quantity, entry_price, keep_id],
+            )?;
+            moved += rows.iter().filter(|(_, symbol, _, _)| symbol == from).count();
+        }
+        tx.commit()?;
+        Ok(moved)
+    }
+
+    // ========================================================================
+    // Configuration Export/Import Methods
+    // ========================================================================
+
+    /// Watchlists, strategies, alerts and team configs as one JSON document
+    pub fn export_config(&self) -> Result<serde_json::Value> {
+        let mut watchlists = Vec::new();
+        for (_, name, description, _) in self.get_all_watchlists()? {
+            let symbols = self.get_watchlist(&name)?;
+            watchlists.push(WatchlistConfig { name, description, symbols });
+        }
+        let export = ConfigExport {
+            version: CONFIG_EXPORT_VERSION,
+            watchlists,
+            strategies: self.get_strategies()?,
+            price_alerts: self.get_alerts(false)?,
+            indicator_alerts: self.get_indicator_alerts(false)?,
+            team_configs: self.list_team_configs()?,
+        };
+        Ok(serde_json::to_value(export)?)
+    }
+
+    /// Load a document produced by `export_config` in one transaction, returning the rows written.
+    /// With `merge`, named rows (watchlists, strategies, team configs) are upserted and alerts
+    /// already present are skipped; otherwise existing configuration is cleared first, except
+    /// strategies that have saved backtest runs.
+    pub fn import_config(&self, value: serde_json::Value, merge: bool) -> Result<usize> {
+        let config: ConfigExport = serde_json::from_value(value)?;
+        if config.version > CONFIG_EXPORT_VERSION {
+            return Err(crate::error::PipelineError::Config(format!(
+                "Config version {} is newer than supported version {}",
+                config.version, CONFIG_EXPORT_VERSION
+            )));
+        }
+
+        let tx = self.conn.unchecked_transaction()?;
+        if !merge {
+            tx.execute("DELETE FROM watchlist_symbols", [])?;
+            tx.execute("DELETE FROM watchlists", [])?;
+            tx.execute(
+                "DELETE FROM strategies WHERE id NOT IN (SELECT strategy_id FROM backtest_runs)",
+                [],
+            )?;
+            // Same audit trail as delete_alert, written through the open transaction
+            for alert in self.get_alerts(false)? {
+                tx.execute("DELETE FROM price_alerts WHERE id = ?1", params![alert.id])?;
+                tx.execute(
+                    r#"
+                    INSERT INTO audit_log (entity_type, entity_id, action, before_json, after_json)
+                    VALUES (?1, ?2, 'delete', ?3, NULL)
+                    "#,
+                    params![AUDIT_PRICE_ALERT, alert.id, serde_json::to_string(&alert)?],
+                )?;
+            }
+            tx.execute("DELETE FROM indicator_alerts", [])?;
+            tx.execute("DELETE FROM trading_teams", [])?;
+        }
+
+        let mut written = 0;
+        for watchlist in &config.watchlists {
+            self.create_watchlist(&watchlist.name, &watchlist.symbols, watchlist.description.as_deref())?;
+            written += 1;
+        }
+        for strategy in &config.strategies {
+            self.save_strategy(strategy)?;
+            written += 1;
+        }
+
+        let existing_alerts = self.get_alerts(false)?;
+        for alert in &config.price_alerts {
+            let duplicate = existing_alerts.iter().any(|a| {
+                a.symbol == alert.symbol && a.condition == alert.condition && a.target_price == alert.target_price
+            });
+            if duplicate {
+                continue;
+            }
+            let id = self.add_alert(&alert.symbol, alert.target_price, alert.condition)?;
+            if alert.triggered {
+                self.trigger_alert(id)?;
+            }
+            written += 1;
+        }
+
+        let existing_indicator_alerts = self.get_indicator_alerts(false)?;
+        for alert in &config.indicator_alerts {
+            let duplicate = existing_indicator_alerts.iter().any(|a| {
+                a.symbol == alert.symbol
+                    && a.alert_type == alert.alert_type
+                    && a.indicator_name == alert.indicator_name
+                    && a.secondary_indicator == alert.secondary_indicator
+                    && a.condition == alert.condition
+                    && a.threshold == alert.threshold
+            });
+            if duplicate {
+                continue;
+            }
+            let id = self.add_indicator_alert(alert)?;
+            if alert.triggered {
+                self.trigger_indicator_alert(id)?;
+            }
+            written += 1;
+        }
+
+        for team in &config.team_configs {
+            tx.execute(
+                r#"
+                INSERT INTO trading_teams (name, description, kalic_starting_capital, dc_starting_capital,
+                    kalic_per_trade_fee, kalic_fee_percent, dc_per_trade_fee, dc_fee_percent)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                ON CONFLICT(name) DO UPDATE SET
+                    description = excluded.description,
+                    kalic_starting_capital = excluded.kalic_starting_capital,
+                    dc_starting_capital = excluded.dc_starting_capital,
+                    kalic_per_trade_fee = excluded.kalic_per_trade_fee,
+                    kalic_fee_percent = excluded.kalic_fee_percent,
+                    dc_per_trade_fee = excluded.dc_per_trade_fee,
+                    dc_fee_percent = excluded.dc_fee_percent
+                "#,
+                params![
+                    team.name,
+                    team.description,
+                    team.kalic_starting_capital,
+                    team.dc_starting_capital,
+                    team.kalic_fees.per_trade_fee,
+                    team.kalic_fees.fee_percent,
+                    team.dc_fees.per_trade_fee,
+                    team.dc_fees.fee_percent,
+                ],
+            )?;
+            written += 1;
+        }
+
+        tx.commit()?;
+        Ok(written)
+    }
+
+    // ========================================================================
+    // Webhook Methods
+    // ========================================================================
+
+    /// Register a webhook, returns its id
+    pub fn add_webhook(
+        &self,
+        url: &str,
+        event_type: Option<WebhookEvent>,
+        secret: Option<&str>,
+    ) -> Result<i64> {
+        if !(url.starts_with("http://") || url.starts_with("https://")) {
+            return Err(crate::error::PipelineError::ApiError(format!(
+                "Webhook URL must start with http:// or https://: {}",
+                url
+            )));
+        }
+        let secret = secret.filter(|s| !s.is_empty());
+        self.conn.execute(
+            "INSERT INTO webhooks (url, event_type, secret) VALUES (?1, ?2, ?3)",
+            params![url, event_type.map(|e| e.as_str()), secret],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Get all webhooks, oldest first
+    pub fn list_webhooks(&self) -> Result<Vec<Webhook>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, url, event_type, secret, enabled, created_at FROM webhooks ORDER BY id",
+        )?;
+        let webhooks = stmt
+            .query_map([], |row| {
+                let event_type: Option<String> = row.get(2)?;
+                Ok(Webhook {
+                    id: row.get(0)?,
+                    url: row.get(1)?,
+                    event_type: event_type.and_then(|e| e.parse().ok()),
+                    secret: row.get(3)?,
+                    enabled: row.get::<_, i32>(4)? != 0,
+                    created_at: row.get(5)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(webhooks)
+    }
+
+    /// Enabled webhooks that should receive the given event
+    pub fn get_webhooks_for_event(&self, event: WebhookEvent) -> Result<Vec<Webhook>> {
+        Ok(self
+            .list_webhooks()?
+            .into_iter()
+            .filter(|w| w.accepts(event))
+            .collect())
+    }
+
+    /// Pause or resume deliveries to a webhook
+    pub fn set_webhook_enabled(&self, webhook_id: i64, enabled: bool) -> Result<()> {
+        self.conn.execute(
+            "UPDATE webhooks SET enabled = ?1 WHERE id = ?2",
+            params![enabled as i32, webhook_id],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a webhook
+    pub fn delete_webhook(&self, webhook_id: i64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM webhooks WHERE id = ?1", params![webhook_id])?;
+        Ok(())
+    }
+
+    // ========================================================================
+    // Conversation Methods
+    // ========================================================================
+
+    /// Start a new conversation and return its id
+    pub fn create_conversation(&self, title: &str, provider: &str) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO conversations (title, provider) VALUES (?1, ?2)",
+            params![title, provider],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Append a message to a conversation and bump its updated_at
+    pub fn add_conversation_message(&self, conversation_id: i64, role: &str, content: &str) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO messages (conversation_id, role, content) VALUES (?1, ?2, ?3)",
+            params![conversation_id, role, content],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        self.conn.execute(
+            "UPDATE conversations SET updated_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![conversation_id],
+        )?;
+        Ok(id)
+    }
+
+    /// Get a conversation's messages, oldest first
+    pub fn get_conversation_messages(&self, conversation_id: i64) -> Result<Vec<ConversationMessage>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, conversation_id, role, content, created_at FROM messages
+             WHERE conversation_id = ?1 ORDER BY id ASC",
+        )?;
+
+        let messages = stmt
+            .query_map(params![conversation_id], |row| {
+                Ok(ConversationMessage {
+                    id: row.get(0)?,
+                    conversation_id: row.get(1)?,
+                    role: row.get(2)?,
+                    content: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(messages)
+    }
+
+    /// Get a single conversation, or None if it does not exist
+    pub fn get_conversation(&self, conversation_id: i64) -> Result<Option<Conversation>> {
+        let conversation = self
+            .conn
+            .query_row(
+                r#"SELECT c.id, c.title, c.provider, c.created_at, c.updated_at,
+                          (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id)
+                   FROM conversations c WHERE c.id = ?1"#,
+                params![conversation_id],
+                Self::map_conversation,
+            )
+            .optional()?;
+        Ok(conversation)
+    }
+
+    /// List conversations, most recently updated first
+    pub fn list_conversations(&self) -> Result<Vec<Conversation>> {
+        let mut stmt = self.conn.prepare(
+            r#"SELECT c.id, c.title, c.provider, c.created_at, c.updated_at,
+                      (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id)
+               FROM conversations c ORDER BY c.updated_at DESC, c.id DESC"#,
+        )?;
+
+        let conversations = stmt
+            .query_map([], Self::map_conversation)?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(conversations)
+    }
+
+    fn map_conversation(row: &rusqlite::Row) -> SqliteResult<Conversation> {
+        Ok(Conversation {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            provider: row.get(2)?,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+            message_count: row.get(5)?,
+        })
+    }
+
+    /// Delete a conversation and its messages. Returns false if it did not exist
+    pub fn delete_conversation(&self, conversation_id: i64) -> Result<bool> {
+        self.conn.execute(
+            "DELETE FROM messages WHERE conversation_id = ?1",
+            params![conversation_id],
+        )?;
+        let deleted = self.conn.execute(
+            "DELETE FROM conversations WHERE id = ?1",
+            params![conversation_id],
+        )?;
+        Ok(deleted > 0)
+    }
+}
+
+/// Explanatory note if `price` has reached the stop or take level, None otherwise
+fn exit_trigger(
+    entry_price: f64,
+    price: f64,
+    stop_price: Option<f64>,
+    take_price: Option<f64>,
+) -> Option<String> {
+    let change = if entry_price > 0.0 {
+        (price / entry_price - 1.0) * 100.0
+    } else {
+        0.0
+    };
+    if let Some(stop) = stop_price.filter(|&stop| price <= stop) {
+        return Some(format!(
+            "Stop-loss: {:.2} at or below stop {:.2} ({:+.2}% from entry {:.2})",
+            price, stop, change, entry_price
+        ));
+    }
+    if let Some(take) = take_price.filter(|&take| price >= take) {
+        return Some(format!(
+            "Take-profit: {:.2} at or above target {:.2} ({:+.2}% from entry {:.2})",
+            price, take, change, entry_price
+        ));
+    }
+    None
+}
+
+/// (date, symbol, signed quantity, price, fee) of a logged paper/DC trade
+type SnapshotTrade = (NaiveDate, String, f64, f64, f64);
+
+/// Fees can't be negative (that would pay the account for trading)
+fn validate_trade_fees(fees: &TradeFees) -> Result<()> {
+    if fees.per_trade_fee < 0.0 || fees.fee_percent < 0.0 {
+        return Err(crate::error::PipelineError::ApiError(
+            "Trade fees must not be negative".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn map_team_config(row: &rusqlite::Row) -> SqliteResult<TeamConfig> {
+    Ok(TeamConfig {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        kalic_starting_capital: row.get(3)?,
+        dc_starting_capital: row.get(4)?,
+        kalic_fees: TradeFees {
+            per_trade_fee: row.get(6)?,
+            fee_percent: row.get(7)?,
+        },
+        dc_fees: TradeFees {
+            per_trade_fee: row.get(8)?,
+            fee_percent: row.get(9)?,
+        },
+        created_at: row.get(5)?,
+    })
+}
+
+fn validate_exit_levels(stop_price: Option<f64>, take_price: Option<f64>) -> Result<()> {
+    if stop_price.is_some_and(|p| p <= 0.0) || take_price.is_some_and(|p| p <= 0.0) {
+        return Err(crate::error::PipelineError::ApiError(
+            "Stop and take prices must be positive".to_string(),
+        ));
+    }
+    if let (Some(stop), Some(take)) = (stop_price, take_price) {
+        if stop >= take {
+            return Err(crate::error::PipelineError::ApiError(format!(
+                "Stop price {:.2} must be below take price {:.2}",
+                stop, take
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// A schema change applied once, in version order
+struct Migration {
+    version: i64,
+    description: &'static str,
+    apply: fn(&Connection) -> Result<()>,
+}
+
+/// All migrations, oldest first. Versions must increase monotonically; never renumber
+/// or edit a released migration, append a new one instead.
+fn migrations() -> Vec<Migration> {
+    vec![Migration {
+        version: 1,
+        description: "legacy column additions and table rebuilds",
+        apply: migrate_legacy_columns,
+    }, Migration {
+        version: 2,
+        description: "per-position stop/take levels on paper and DC positions",
+        apply: migrate_position_exit_levels,
+    }, Migration {
+        version: 3,
+        description: "persisted starting capital on the paper wallet",
+        apply: migrate_paper_starting_capital,
+    }, Migration {
+        version: 4,
+        description: "portfolio snapshots for the real portfolio",
+        apply: migrate_portfolio_snapshot_team,
+    }, Migration {
+        version: 5,
+        description: "period-qualified names for multi-output indicators",
+        apply: migrate_legacy_indicator_names,
+    }, Migration {
+        version: 6,
+        description: "per-trade risk budget for AI trader position sizing",
+        apply: migrate_ai_risk_per_trade,
+    }, Migration {
+        version: 7,
+        description: "Ultimate Oscillator signal thresholds",
+        apply: migrate_signal_config_uo,
+    }, Migration {
+        version: 8,
+        description: "soft delete for portfolio positions",
+        apply: migrate_position_soft_delete,
+    }, Migration {
+        version: 9,
+        description: "gap signal threshold",
+        apply: migrate_signal_config_gap,
+    }, Migration {
+        version: 10,
+        description: "price staleness threshold",
+        apply: migrate_refresh_stale_after_days,
+    }, Migration {
+        version: 11,
+        description: "competition trade fees",
+        apply: migrate_trade_fees,
+    }, Migration {
+        version: 12,
+        description: "stochastic RSI signal thresholds",
+        apply: migrate_signal_config_stoch_rsi,
+    }, Migration {
+        version: 13,
+        description: "dry-run AI decisions",
+        apply: migrate_ai_decision_dry_run,
+    }, Migration {
+        version: 14,
+        description: "AI trader partial fills",
+        apply: migrate_ai_partial_fills,
+    }]
+}
+
+/// Version recorded in a database's schema_version table (0 if it has none)
+fn read_schema_version(conn: &Connection) -> Result<i64> {
+    let has_table: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version')",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_table {
+        return Ok(0);
+    }
+    Ok(conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )?)
+}
+
+/// Longest gap a trends reading is carried forward over (covers weekly data)
+pub const TRENDS_MAX_FILL_DAYS: i64 = 7;
+
+/// `audit_log.entity_type` values
+const AUDIT_POSITION: &str = "position";
+const AUDIT_PAPER_TRADE: &str = "paper_trade";
+const AUDIT_PRICE_ALERT: &str = "price_alert";
+
+/// Snapshot team for the real (manually entered) portfolio
+pub const PORTFOLIO_TEAM: &str = "PORTFOLIO";
+
+/// ATR lookback used by `suggest_stops`
+pub const STOP_ATR_PERIOD: usize = 14;
+
+/// Teams recorded by the daily snapshot job
+pub const SNAPSHOT_TEAMS: [&str; 3] = ["KALIC", "DC", PORTFOLIO_TEAM];
+
+/// Tables trimmed by `prune_old_data`, each with the column its row age is read from
+pub const PRUNABLE_TABLES: [(&str, &str); 3] = [
+    ("signals", "timestamp"),
+    ("api_calls", "timestamp"),
+    ("ai_performance_snapshots", "timestamp"),
+];
+
+/// Tables whose rows `merge_symbol` moves to the surviving symbol: every table with a
+/// `symbol` column except the position tables, history and logs included. Rows that
+/// would collide with one the target already has (same bar, alias, watchlist entry...)
+/// are dropped in favour of the target's.
+pub const SYMBOL_KEYED_TABLES: [&str; 21] = [
+    "symbols",
+    "symbol_aliases",
+    "daily_prices",
+    "intraday_prices",
+    "technical_indicators",
+    "signals",
+    "dividends",
+    "earnings",
+    "watchlist_symbols",
+    "price_alerts",
+    "indicator_alerts",
+    "portfolio_positions",
+    "paper_trades",
+    "paper_lots",
+    "paper_orders",
+    "dc_trades",
+    "ai_trade_decisions",
+    "trade_rejections",
+    "backtest_runs",
+    "backtest_trades",
+    "api_calls",
+];
+
+/// Open-position tables holding one row per symbol, combined by `merge_symbol`
+const POSITION_TABLES: [&str; 2] = ["paper_positions", "dc_positions"];
+
+/// Format version written by `export_config`; imports from newer versions are rejected
+pub const CONFIG_EXPORT_VERSION: u32 = 1;
+
+/// Shares a partial-fill buy takes: the request if the cash covers it, else the most whole
+/// shares it does. Errors when not even one share is affordable.
+fn fill_quantity(fees: &TradeFees, cash: f64, quantity: f64, price: f64) -> Result<f64> {
+    if cash >= quantity * price + fees.fee_for(quantity * price) {
+        return Ok(quantity);
+    }
+    let affordable = fees.max_affordable_shares(cash, price).min(quantity);
+    if affordable <= 0.0 {
+        return Err(crate::error::PipelineError::ApiError(format!(
+            "Insufficient cash: have ${:.2}, one share costs ${:.2} with fees",
+            cash,
+            price + fees.fee_for(price)
+        )));
+    }
+    Ok(affordable)
+}
+
+/// +1 for long (buy) positions, -1 for shorts
+fn position_sign(position_type: PositionType) -> f64 {
+    match position_type {
+        PositionType::Buy => 1.0,
+        PositionType::Sell => -1.0,
+    }
+}
+
+/// Last close on or before `date` in a date-sorted (date, close) series
+pub(crate) fn last_close_on(series: &[(NaiveDate, f64)], date: NaiveDate) -> Option<f64> {
+    let idx = series.partition_point(|(d, _)| *d <= date);
+    idx.checked_sub(1).map(|i| series[i].1)
+}
+
+/// Column names of a table
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>> {
+    let columns = conn
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(columns)
+}
+
+fn migrate_position_exit_levels(conn: &Connection) -> Result<()> {
+    for table in ["paper_positions", "dc_positions"] {
+        if !table_columns(conn, table)?.contains(&"stop_price".to_string()) {
+            conn.execute_batch(&format!(
+                "ALTER TABLE {table} ADD COLUMN stop_price REAL;
+                 ALTER TABLE {table} ADD COLUMN take_price REAL;"
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// Wallets created before this column existed were reported against $100k
+fn migrate_paper_starting_capital(conn: &Connection) -> Result<()> {
+    if !table_columns(conn, "paper_wallet")?.contains(&"starting_capital".to_string()) {
+        conn.execute_batch(
+            "ALTER TABLE paper_wallet ADD COLUMN starting_capital REAL NOT NULL DEFAULT 100000.0;",
+        )?;
+    }
+    Ok(())
+}
+
+/// The team CHECK only allowed KALIC and DC; SQLite can't alter it, so rebuild
+fn migrate_portfolio_snapshot_team(conn: &Connection) -> Result<()> {
+    let sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'portfolio_snapshots'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !sql.contains("'PORTFOLIO'") {
+        conn.execute_batch(
+            r#"
+            ALTER TABLE portfolio_snapshots RENAME TO portfolio_snapshots_old;
+            CREATE TABLE portfolio_snapshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                team TEXT NOT NULL CHECK(team IN ('KALIC', 'DC', 'PORTFOLIO')),
+                date TEXT NOT NULL,
+                total_value REAL NOT NULL,
+                cash REAL NOT NULL,
+                positions_value REAL NOT NULL,
+                UNIQUE(team, date)
+            );
+            INSERT INTO portfolio_snapshots (id, team, date, total_value, cash, positions_value)
+                SELECT id, team, date, total_value, cash, positions_value FROM portfolio_snapshots_old;
+            DROP TABLE portfolio_snapshots_old;
+            CREATE INDEX IF NOT EXISTS idx_portfolio_snapshots_team_date ON portfolio_snapshots(team, date);
+            "#,
+        )?;
+    }
+    Ok(())
+}
+
+/// Rename rows stored under the old ambiguous indicator names. Where a qualified
+/// row already exists for the same date it is newer, so the legacy row is dropped.
+fn migrate_legacy_indicator_names(conn: &Connection) -> Result<()> {
+    for (legacy, current) in LEGACY_INDICATOR_NAMES {
+        conn.execute(
+            "UPDATE OR IGNORE technical_indicators SET indicator_name = ?2 WHERE indicator_name = ?1",
+            params![legacy, current],
+        )?;
+        conn.execute(
+            "DELETE FROM technical_indicators WHERE indicator_name = ?1",
+            params![legacy],
+        )?;
+        conn.execute(
+            "UPDATE indicator_alerts SET indicator_name = ?2 WHERE indicator_name = ?1",
+            params![legacy, current],
+        )?;
+        conn.execute(
+            "UPDATE indicator_alerts SET secondary_indicator = ?2 WHERE secondary_indicator = ?1",
+            params![legacy, current],
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_ai_risk_per_trade(conn: &Connection) -> Result<()> {
+    if !table_columns(conn, "ai_trader_config")?.contains(&"risk_per_trade_percent".to_string()) {
+        conn.execute_batch(
+            "ALTER TABLE ai_trader_config ADD COLUMN risk_per_trade_percent REAL NOT NULL DEFAULT 1.0;",
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_signal_config_uo(conn: &Connection) -> Result<()> {
+    if !table_columns(conn, "signal_config")?.contains(&"uo_overbought".to_string()) {
+        conn.execute_batch(
+            r#"
+            ALTER TABLE signal_config ADD COLUMN uo_overbought REAL NOT NULL DEFAULT 70.0;
+            ALTER TABLE signal_config ADD COLUMN uo_oversold REAL NOT NULL DEFAULT 30.0;
+            "#,
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_position_soft_delete(conn: &Connection) -> Result<()> {
+    if !table_columns(conn, "portfolio_positions")?.contains(&"deleted_at".to_string()) {
+        conn.execute_batch("ALTER TABLE portfolio_positions ADD COLUMN deleted_at TEXT;")?;
+    }
+    Ok(())
+}
+
+fn migrate_signal_config_gap(conn: &Connection) -> Result<()> {
+    if !table_columns(conn, "signal_config")?.contains(&"gap_min_percent".to_string()) {
+        conn.execute_batch(
+            "ALTER TABLE signal_config ADD COLUMN gap_min_percent REAL NOT NULL DEFAULT 2.0;",
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_refresh_stale_after_days(conn: &Connection) -> Result<()> {
+    if !table_columns(conn, "refresh_settings")?.contains(&"stale_after_days".to_string()) {
+        conn.execute_batch(
+            "ALTER TABLE refresh_settings ADD COLUMN stale_after_days INTEGER NOT NULL DEFAULT 4;",
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_trade_fees(conn: &Connection) -> Result<()> {
+    let additions: [(&str, &[&str]); 5] = [
+        ("paper_wallet", &["per_trade_fee", "fee_percent"]),
+        ("dc_wallet", &["per_trade_fee", "fee_percent"]),
+        ("paper_trades", &["fee"]),
+        ("dc_trades", &["fee"]),
+        ("trading_teams", &["kalic_per_trade_fee", "kalic_fee_percent", "dc_per_trade_fee", "dc_fee_percent"]),
+    ];
+    for (table, columns) in additions {
+        let existing = table_columns(conn, table)?;
+        for column in columns {
+            if !existing.contains(&column.to_string()) {
+                conn.execute_batch(&format!(
+                    "ALTER TABLE {} ADD COLUMN {} REAL NOT NULL DEFAULT 0.0;",
+                    table, column
+                ))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn migrate_signal_config_stoch_rsi(conn: &Connection) -> Result<()> {
+    if !table_columns(conn, "signal_config")?.contains(&"stoch_rsi_overbought".to_string()) {
+        conn.execute_batch(
+            r#"
+            ALTER TABLE signal_config ADD COLUMN stoch_rsi_overbought REAL NOT NULL DEFAULT 80.0;
+            ALTER TABLE signal_config ADD COLUMN stoch_rsi_oversold REAL NOT NULL DEFAULT 20.0;
+            "#,
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_ai_decision_dry_run(conn: &Connection) -> Result<()> {
+    if !table_columns(conn, "ai_trade_decisions")?.contains(&"dry_run".to_string()) {
+        conn.execute_batch(
+            "ALTER TABLE ai_trade_decisions ADD COLUMN dry_run INTEGER NOT NULL DEFAULT 0;",
+        )?;
+    }
+    Ok(())
+}
+
+fn migrate_ai_partial_fills(conn: &Connection) -> Result<()> {
+    if !table_columns(conn, "ai_trader_config")?.contains(&"allow_partial_fills".to_string()) {
+        conn.execute_batch(
+            "ALTER TABLE ai_trader_config ADD COLUMN allow_partial_fills INTEGER NOT NULL DEFAULT 0;",
+        )?;
+    }
+    Ok(())
+}
+
+/// Ad-hoc upgrades from before versioned migrations. Each step checks the current
+/// table shape first, so databases created from the latest SCHEMA_SQL are left as is.
+fn migrate_legacy_columns(conn: &Connection) -> Result<()> {
+    // Add favorited column to symbols table if it doesn't exist
+    let columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(symbols)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    if !columns.contains(&"favorited".to_string()) {
+        conn.execute(
+            "ALTER TABLE symbols ADD COLUMN favorited INTEGER DEFAULT 0",
+            [],
+        )?;
+        println!("[MIGRATION] Added favorited column to symbols table");
+    }
+
+    // Migrate ai_trader_config table with new guardrails columns
+    let ai_config_columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(ai_trader_config)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    // Add trading_mode column
+    if !ai_config_columns.contains(&"trading_mode".to_string()) {
+        conn.execute(
+            "ALTER TABLE ai_trader_config ADD COLUMN trading_mode TEXT NOT NULL DEFAULT 'normal'",
+            [],
+        )?;
+        println!("[MIGRATION] Added trading_mode column to ai_trader_config");
+    }
+
+    // Add circuit breaker columns
+    if !ai_config_columns.contains(&"daily_loss_threshold".to_string()) {
+        conn.execute_batch(r#"
+            ALTER TABLE ai_trader_config ADD COLUMN daily_loss_threshold REAL NOT NULL DEFAULT -10.0;
+            ALTER TABLE ai_trader_config ADD COLUMN consecutive_loss_limit INTEGER NOT NULL DEFAULT 5;
+            ALTER TABLE ai_trader_config ADD COLUMN auto_conservative_on_trigger INTEGER NOT NULL DEFAULT 1;
+            ALTER TABLE ai_trader_config ADD COLUMN circuit_breaker_triggered INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE ai_trader_config ADD COLUMN circuit_breaker_until TIMESTAMP;
+        "#)?;
+        println!("[MIGRATION] Added circuit breaker columns to ai_trader_config");
+    }
+
+    if !ai_config_columns.contains(&"daily_loss_baseline".to_string()) {
+        conn.execute_batch(r#"
+            ALTER TABLE ai_trader_config ADD COLUMN daily_loss_baseline REAL;
+            ALTER TABLE ai_trader_config ADD COLUMN daily_loss_baseline_date TEXT;
+        "#)?;
+        println!("[MIGRATION] Added daily loss baseline columns to ai_trader_config");
+    }
+
+    // Add override columns
+    if !ai_config_columns.contains(&"override_enabled".to_string()) {
+        conn.execute_batch(r#"
+            ALTER TABLE ai_trader_config ADD COLUMN override_enabled INTEGER NOT NULL DEFAULT 0;
+            ALTER TABLE ai_trader_config ADD COLUMN override_expires_at TIMESTAMP;
+            ALTER TABLE ai_trader_config ADD COLUMN override_max_position_pct REAL;
+        "#)?;
+        println!("[MIGRATION] Added override columns to ai_trader_config");
+    }
+
+    // Add guardrail columns
+    if !ai_config_columns.contains(&"max_daily_trades".to_string()) {
+        conn.execute_batch(r#"
+            ALTER TABLE ai_trader_config ADD COLUMN max_daily_trades INTEGER NOT NULL DEFAULT 10;
+            ALTER TABLE ai_trader_config ADD COLUMN max_single_trade_value REAL NOT NULL DEFAULT 50000.0;
+            ALTER TABLE ai_trader_config ADD COLUMN require_confluence INTEGER NOT NULL DEFAULT 1;
+            ALTER TABLE ai_trader_config ADD COLUMN blocked_hours TEXT DEFAULT '09:30-09:45,15:45-16:00';
+        "#)?;
+        println!("[MIGRATION] Added guardrail columns to ai_trader_config");
+    }
+
+    // Add adjusted_close column to daily_prices (existing rows stay NULL)
+    let price_columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(daily_prices)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    if !price_columns.contains(&"adjusted_close".to_string()) {
+        conn.execute(
+            "ALTER TABLE daily_prices ADD COLUMN adjusted_close REAL",
+            [],
+        )?;
+        println!("[MIGRATION] Added adjusted_close column to daily_prices table");
+    }
+
+    // Add direction column to strategies table (long/short)
+    let strategy_columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(strategies)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    if !strategy_columns.contains(&"direction".to_string()) {
+        conn.execute(
+            "ALTER TABLE strategies ADD COLUMN direction TEXT NOT NULL DEFAULT 'long'",
+            [],
+        )?;
+        println!("[MIGRATION] Added direction column to strategies table");
+    }
+
+    if !strategy_columns.contains(&"trailing_stop_percent".to_string()) {
+        conn.execute(
+            "ALTER TABLE strategies ADD COLUMN trailing_stop_percent REAL",
+            [],
+        )?;
+        println!("[MIGRATION] Added trailing_stop_percent column to strategies table");
+    }
+
+    if !strategy_columns.contains(&"entry_rule".to_string()) {
+        conn.execute_batch(
+            "ALTER TABLE strategies ADD COLUMN entry_rule TEXT;
+             ALTER TABLE strategies ADD COLUMN exit_rule TEXT;",
+        )?;
+        println!("[MIGRATION] Added composite rule columns to strategies table");
+    }
+
+    // Add equity/drawdown curve columns to backtest_runs
+    let backtest_columns: Vec<String> = conn
+        .prepare("PRAGMA table_info(backtest_runs)")?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    if !backtest_columns.contains(&"equity_curve".to_string()) {
+        conn.execute_batch(
+            "ALTER TABLE backtest_runs ADD COLUMN equity_curve TEXT;
+             ALTER TABLE backtest_runs ADD COLUMN drawdown_curve TEXT;",
+        )?;
+        println!("[MIGRATION] Added equity/drawdown curve columns to backtest_runs");
+    }
+
+    // Widen the price_alerts condition CHECK for percent-change and volume alerts.
+    // SQLite can't alter a CHECK constraint, so the table is rebuilt.
+    let alerts_sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'price_alerts'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if !alerts_sql.contains("volume_multiple") {
+        conn.execute_batch(
+            r#"
+            ALTER TABLE price_alerts RENAME TO price_alerts_old;
+            CREATE TABLE price_alerts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                symbol TEXT NOT NULL,
+                target_price REAL NOT NULL,
+                condition TEXT NOT NULL CHECK(condition IN ('above', 'below', 'pct_up', 'pct_down', 'volume_multiple')),
+                triggered BOOLEAN DEFAULT 0,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            );
+            INSERT INTO price_alerts (id, symbol, target_price, condition, triggered, created_at)
+                SELECT id, symbol, target_price, condition, triggered, created_at FROM price_alerts_old;
+            DROP TABLE price_alerts_old;
+            CREATE INDEX IF NOT EXISTS idx_alerts_symbol ON price_alerts(symbol);
+            CREATE INDEX IF NOT EXISTS idx_alerts_triggered ON price_alerts(triggered);
+            "#,
+        )?;
+        println!("[MIGRATION] Extended price_alerts conditions (pct_up, pct_down, volume_multiple)");
+    }
+
+    // paper_positions used to declare a foreign key to market_events, which lives in
+    // vectors.db. With foreign_keys enabled every insert fails, so drop the constraint.
+    let positions_sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'paper_positions'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if positions_sql.contains("REFERENCES market_events") {
+        conn.execute_batch(
+            r#"
+            ALTER TABLE paper_positions RENAME TO paper_positions_old;
+            CREATE TABLE paper_positions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                symbol TEXT NOT NULL,
+                quantity REAL NOT NULL,
+                entry_price REAL NOT NULL,
+                entry_date TEXT DEFAULT CURRENT_TIMESTAMP,
+                linked_event_id INTEGER
+            );
+            INSERT INTO paper_positions (id, symbol, quantity, entry_price, entry_date, linked_event_id)
+                SELECT id, symbol, quantity, entry_price, entry_date, linked_event_id FROM paper_positions_old;
+            DROP TABLE paper_positions_old;
+            CREATE INDEX IF NOT EXISTS idx_paper_positions_symbol ON paper_positions(symbol);
+            "#,
+        )?;
+        println!("[MIGRATION] Removed cross-database foreign key from paper_positions");
+    }
+
+    // macro_data was keyed on (indicator, date) only, so fetching a series at a second
+    // frequency overwrote the first. Rebuild with frequency in the key ('' = native).
+    let macro_sql: String = conn.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'macro_data'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if !macro_sql.contains("PRIMARY KEY (indicator, date, frequency)") {
+        conn.execute_batch(
+            r#"
+            ALTER TABLE macro_data RENAME TO macro_data_old;
+            CREATE TABLE macro_data (
+                indicator TEXT,
+                date DATE,
+                value REAL,
+                frequency TEXT NOT NULL DEFAULT '',
+                source TEXT DEFAULT 'FRED',
+                PRIMARY KEY (indicator, date, frequency)
+            );
+            INSERT INTO macro_data (indicator, date, value, frequency, source)
+                SELECT indicator, date, value, COALESCE(frequency, ''), source FROM macro_data_old;
+            DROP TABLE macro_data_old;
+            CREATE INDEX IF NOT EXISTS idx_macro_indicator ON macro_data(indicator);
+            CREATE INDEX IF NOT EXISTS idx_macro_date ON macro_data(date);
+            "#,
+        )?;
+        println!("[MIGRATION] Added frequency to the macro_data primary key");
+    }
+
+    Ok(())
+}
+
+/// Database schema SQL
+const SCHEMA_SQL: &str = r#"
+-- Symbol master table
+CREATE TABLE IF NOT EXISTS symbols (
+    symbol TEXT PRIMARY KEY,
+    name TEXT,
+    sector TEXT,
+    industry TEXT,
+    market_cap REAL,
+    country TEXT,
+    exchange TEXT,
+    currency TEXT,
+    isin TEXT,
+    asset_class TEXT,
+    favorited INTEGER DEFAULT 0,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+
+-- Daily price data
+CREATE TABLE IF NOT EXISTS daily_prices (
+    symbol TEXT,
+    timestamp DATE,
+    open REAL,
+    high REAL,
+    low REAL,
+    close REAL,
+    volume INTEGER,
+    adjusted_close REAL,
+    source TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (symbol, timestamp)
+);
+
+-- Macro economic indicators
+CREATE TABLE IF NOT EXISTS macro_data (
+    indicator TEXT,
+    date DATE,
+    value REAL,
+    frequency TEXT NOT NULL DEFAULT '',
+    source TEXT DEFAULT 'FRED',
+    PRIMARY KEY (indicator, date, frequency)
+);
+
+-- Watchlists
+CREATE TABLE IF NOT EXISTS watchlists (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT UNIQUE,
+    description TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS watchlist_symbols (
+    watchlist_id INTEGER,
+    symbol TEXT,
+    added_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    notes TEXT,
+    PRIMARY KEY (watchlist_id, symbol),
+    FOREIGN KEY (watchlist_id) REFERENCES watchlists(id)
+);
+
+-- API call tracking
+CREATE TABLE IF NOT EXISTS api_calls (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    source TEXT,
+    endpoint TEXT,
+    symbol TEXT,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+    success BOOLEAN,
+    error_message TEXT
+);
+
+-- Indexes
+CREATE INDEX IF NOT EXISTS idx_prices_symbol ON daily_prices(symbol);
+CREATE INDEX IF NOT EXISTS idx_prices_timestamp ON daily_prices(timestamp);
+CREATE INDEX IF NOT EXISTS idx_prices_source ON daily_prices(source);
+CREATE INDEX IF NOT EXISTS idx_symbols_sector ON symbols(sector);
+CREATE INDEX IF NOT EXISTS idx_macro_indicator ON macro_data(indicator);
+CREATE INDEX IF NOT EXISTS idx_macro_date ON macro_data(date);
+CREATE INDEX IF NOT EXISTS idx_api_calls_source ON api_calls(source);
+CREATE INDEX IF NOT EXISTS idx_api_calls_timestamp ON api_calls(timestamp);
+
+-- Views
+CREATE VIEW IF NOT EXISTS latest_prices AS
+SELECT p.*
+FROM daily_prices p
+INNER JOIN (
+    SELECT symbol, MAX(timestamp) as max_date
+    FROM daily_prices
+    GROUP BY symbol
+) latest ON p.symbol = latest.symbol AND p.timestamp = latest.max_date;
+
+CREATE VIEW IF NOT EXISTS api_rate_limits AS
+SELECT
+    source,
+    COUNT(*) as calls_today,
+    MAX(timestamp) as last_call
+FROM api_calls
+WHERE DATE(timestamp) = DATE('now')
+GROUP BY source;
+
+-- Technical indicators
+CREATE TABLE IF NOT EXISTS technical_indicators (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    symbol TEXT NOT NULL,
+    timestamp DATE NOT NULL,
+    indicator_name TEXT NOT NULL,
+    value REAL NOT NULL,
+    params TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(symbol, timestamp, indicator_name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ti_symbol_date ON technical_indicators(symbol, timestamp);
+CREATE INDEX IF NOT EXISTS idx_ti_indicator ON technical_indicators(indicator_name);
+
+-- Price alerts
+CREATE TABLE IF NOT EXISTS price_alerts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    symbol TEXT NOT NULL,
+    target_price REAL NOT NULL,
+    condition TEXT NOT NULL CHECK(condition IN ('above', 'below', 'pct_up', 'pct_down', 'volume_multiple')),
+    triggered BOOLEAN DEFAULT 0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_alerts_symbol ON price_alerts(symbol);
+CREATE INDEX IF NOT EXISTS idx_alerts_triggered ON price_alerts(triggered);
+
+-- Portfolio positions
+CREATE TABLE IF NOT EXISTS portfolio_positions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    symbol TEXT NOT NULL,
+    quantity REAL NOT NULL,
+    price REAL NOT NULL,
+    position_type TEXT NOT NULL CHECK(position_type IN ('buy', 'sell')),
+    date TEXT NOT NULL,
+    notes TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    deleted_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_positions_symbol ON portfolio_positions(symbol);
+
+-- Google Trends data
+CREATE TABLE IF NOT EXISTS trends_data (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    keyword TEXT NOT NULL,
+    date DATE NOT NULL,
+    value INTEGER NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(keyword, date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_trends_keyword ON trends_data(keyword);
+CREATE INDEX IF NOT EXISTS idx_trends_date ON trends_data(date);
+
+-- Trading signals
+CREATE TABLE IF NOT EXISTS signals (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    symbol TEXT NOT NULL,
+    signal_type TEXT NOT NULL,
+    direction TEXT NOT NULL CHECK(direction IN ('bullish', 'bearish', 'neutral')),
+    strength REAL NOT NULL CHECK(strength >= 0.0 AND strength <= 1.0),
+    price_at_signal REAL NOT NULL,
+    triggered_by TEXT NOT NULL,
+    trigger_value REAL NOT NULL,
+    timestamp DATE NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    acknowledged BOOLEAN DEFAULT 0,
+    UNIQUE(symbol, signal_type, timestamp)
+);
+
+CREATE INDEX IF NOT EXISTS idx_signals_symbol ON signals(symbol);
+CREATE INDEX IF NOT EXISTS idx_signals_type ON signals(signal_type);
+CREATE INDEX IF NOT EXISTS idx_signals_timestamp ON signals(timestamp);
+CREATE INDEX IF NOT EXISTS idx_signals_direction ON signals(direction);
+CREATE INDEX IF NOT EXISTS idx_signals_acknowledged ON signals(acknowledged);
+
+-- Indicator-based alerts
+CREATE TABLE IF NOT EXISTS indicator_alerts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    symbol TEXT NOT NULL,
+    alert_type TEXT NOT NULL CHECK(alert_type IN ('threshold', 'crossover', 'band_touch')),
+    indicator_name TEXT NOT NULL,
+    secondary_indicator TEXT,
+    condition TEXT NOT NULL CHECK(condition IN (
+        'crosses_above', 'crosses_below', 'bullish_crossover', 'bearish_crossover'
+    )),
+    threshold REAL,
+    triggered BOOLEAN DEFAULT 0,
+    last_value REAL,
+    message TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_ind_alerts_symbol ON indicator_alerts(symbol);
+CREATE INDEX IF NOT EXISTS idx_ind_alerts_triggered ON indicator_alerts(triggered);
+
+-- Backtesting strategies
+CREATE TABLE IF NOT EXISTS strategies (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT UNIQUE NOT NULL,
+    description TEXT,
+    entry_condition TEXT NOT NULL,
+    entry_threshold REAL NOT NULL,
+    exit_condition TEXT NOT NULL,
+    exit_threshold REAL NOT NULL,
+    stop_loss_percent REAL,
+    take_profit_percent REAL,
+    position_size_percent REAL NOT NULL DEFAULT 100.0,
+    direction TEXT NOT NULL DEFAULT 'long',
+    trailing_stop_percent REAL,
+    entry_rule TEXT, -- JSON StrategyRule, overrides entry_condition when set
+    exit_rule TEXT, -- JSON StrategyRule, overrides exit_condition when set
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_strategies_name ON strategies(name);
+
+-- Backtest runs
+CREATE TABLE IF NOT EXISTS backtest_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    strategy_id INTEGER NOT NULL,
+    strategy_name TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL,
+    initial_capital REAL NOT NULL,
+    final_capital REAL NOT NULL,
+    total_return REAL NOT NULL,
+    total_return_dollars REAL NOT NULL,
+    max_drawdown REAL NOT NULL,
+    sharpe_ratio REAL NOT NULL,
+    win_rate REAL NOT NULL,
+    total_trades INTEGER NOT NULL,
+    winning_trades INTEGER NOT NULL,
+    losing_trades INTEGER NOT NULL,
+    avg_win_percent REAL NOT NULL,
+    avg_loss_percent REAL NOT NULL,
+    profit_factor REAL NOT NULL,
+    avg_trade_duration_days REAL NOT NULL,
+    equity_curve TEXT, -- JSON [[date, value], ...]
+    drawdown_curve TEXT, -- JSON [[date, percent], ...]
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (strategy_id) REFERENCES strategies(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_backtest_runs_strategy ON backtest_runs(strategy_id);
+CREATE INDEX IF NOT EXISTS idx_backtest_runs_symbol ON backtest_runs(symbol);
+CREATE INDEX IF NOT EXISTS idx_backtest_runs_date ON backtest_runs(created_at);
+
+-- Backtest trades
+CREATE TABLE IF NOT EXISTS backtest_trades (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    backtest_id INTEGER NOT NULL,
+    symbol TEXT NOT NULL,
+    direction TEXT NOT NULL CHECK(direction IN ('long', 'short')),
+    entry_date DATE NOT NULL,
+    entry_price REAL NOT NULL,
+    entry_reason TEXT NOT NULL,
+    exit_date DATE,
+    exit_price REAL,
+    exit_reason TEXT,
+    shares REAL NOT NULL,
+    profit_loss REAL,
+    profit_loss_percent REAL,
+    FOREIGN KEY (backtest_id) REFERENCES backtest_runs(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_backtest_trades_run ON backtest_trades(backtest_id);
+CREATE INDEX IF NOT EXISTS idx_backtest_trades_symbol ON backtest_trades(symbol);
+
+-- Paper trading wallet (singleton - one paper account)
+CREATE TABLE IF NOT EXISTS paper_wallet (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    cash REAL NOT NULL DEFAULT 1000000.0,
+    starting_capital REAL NOT NULL DEFAULT 1000000.0,
+    per_trade_fee REAL NOT NULL DEFAULT 0.0,
+    fee_percent REAL NOT NULL DEFAULT 0.0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Initialize wallet if not exists (default $1M for AI trading)
+INSERT OR IGNORE INTO paper_wallet (id, cash) VALUES (1, 1000000.0);
+
+-- Paper trading positions (open positions)
+CREATE TABLE IF NOT EXISTS paper_positions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    symbol TEXT NOT NULL,
+    quantity REAL NOT NULL,
+    entry_price REAL NOT NULL,
+    entry_date TEXT DEFAULT CURRENT_TIMESTAMP,
+    linked_event_id INTEGER,  -- market_events.id in vectors.db (no FK across databases)
+    stop_price REAL,
+    take_price REAL
+);
+
+CREATE INDEX IF NOT EXISTS idx_paper_positions_symbol ON paper_positions(symbol);
+
+-- Paper trading history (all trades)
+CREATE TABLE IF NOT EXISTS paper_trades (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    symbol TEXT NOT NULL,
+    action TEXT NOT NULL CHECK(action IN ('BUY', 'SELL')),
+    quantity REAL NOT NULL,
     price REAL NOT NULL,
-    position_type TEXT NOT NULL CHECK(position_type IN ('buy', 'sell')),
-    date TEXT NOT NULL,
+    pnl REAL,
+    timestamp TEXT DEFAULT CURRENT_TIMESTAMP,
+    linked_event_id INTEGER,
+    notes TEXT,
+    fee REAL NOT NULL DEFAULT 0.0
+);
+
+CREATE INDEX IF NOT EXISTS idx_paper_trades_symbol ON paper_trades(symbol);
+CREATE INDEX IF NOT EXISTS idx_paper_trades_timestamp ON paper_trades(timestamp);
+
+-- Buy lots per account ('paper' or 'dc'), consumed oldest-first on sells
+CREATE TABLE IF NOT EXISTS paper_lots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    account TEXT NOT NULL CHECK(account IN ('paper', 'dc')),
+    symbol TEXT NOT NULL,
+    quantity REAL NOT NULL,
+    price REAL NOT NULL,
+    acquired_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_paper_lots_account_symbol ON paper_lots(account, symbol);
+
+-- Pending limit/stop orders (filled into paper_trades when triggered)
+CREATE TABLE IF NOT EXISTS paper_orders (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    symbol TEXT NOT NULL,
+    action TEXT NOT NULL CHECK(action IN ('BUY', 'SELL')),
+    order_type TEXT NOT NULL CHECK(order_type IN ('LIMIT', 'STOP')),
+    quantity REAL NOT NULL,
+    limit_price REAL,
+    stop_price REAL,
+    status TEXT NOT NULL DEFAULT 'PENDING' CHECK(status IN ('PENDING', 'FILLED', 'CANCELLED')),
+    paper_trade_id INTEGER REFERENCES paper_trades(id),
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    filled_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_paper_orders_status ON paper_orders(status);
+
+-- ============================================================================
+-- AI Trading Simulator Tables
+-- ============================================================================
+
+-- AI Trading Sessions
+CREATE TABLE IF NOT EXISTS ai_trading_sessions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    start_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    end_time DATETIME,
+    starting_portfolio_value REAL NOT NULL,
+    ending_portfolio_value REAL,
+    decisions_count INTEGER DEFAULT 0,
+    trades_count INTEGER DEFAULT 0,
+    session_notes TEXT,
+    status TEXT DEFAULT 'active' CHECK(status IN ('active', 'completed', 'interrupted'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_ai_sessions_status ON ai_trading_sessions(status);
+CREATE INDEX IF NOT EXISTS idx_ai_sessions_start ON ai_trading_sessions(start_time);
+
+-- AI Trade Decisions (detailed reasoning log)
+CREATE TABLE IF NOT EXISTS ai_trade_decisions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    session_id INTEGER REFERENCES ai_trading_sessions(id),
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+    action TEXT NOT NULL CHECK(action IN ('BUY', 'SELL', 'HOLD')),
+    symbol TEXT NOT NULL,
+    quantity REAL,
+    price_at_decision REAL,
+    confidence REAL NOT NULL,
+    reasoning TEXT NOT NULL,
+    model_used TEXT NOT NULL,
+    predicted_direction TEXT CHECK(predicted_direction IN ('bullish', 'bearish', 'neutral')),
+    predicted_price_target REAL,
+    predicted_timeframe_days INTEGER,
+    actual_outcome TEXT,
+    actual_price_at_timeframe REAL,
+    prediction_accurate INTEGER,
+    paper_trade_id INTEGER REFERENCES paper_trades(id),
+    dry_run INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_ai_decisions_session ON ai_trade_decisions(session_id);
+CREATE INDEX IF NOT EXISTS idx_ai_decisions_symbol ON ai_trade_decisions(symbol);
+CREATE INDEX IF NOT EXISTS idx_ai_decisions_timestamp ON ai_trade_decisions(timestamp);
+
+-- AI Performance Snapshots (for charting equity curve)
+CREATE TABLE IF NOT EXISTS ai_performance_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+    portfolio_value REAL NOT NULL,
+    cash REAL NOT NULL,
+    positions_value REAL NOT NULL,
+    benchmark_value REAL NOT NULL,
+    benchmark_symbol TEXT DEFAULT 'SPY',
+    total_pnl REAL NOT NULL,
+    total_pnl_percent REAL NOT NULL,
+    benchmark_pnl_percent REAL NOT NULL,
+    prediction_accuracy REAL,
+    trades_to_date INTEGER NOT NULL,
+    winning_trades INTEGER NOT NULL,
+    losing_trades INTEGER NOT NULL,
+    win_rate REAL
+);
+
+CREATE INDEX IF NOT EXISTS idx_ai_performance_timestamp ON ai_performance_snapshots(timestamp);
+
+-- AI Trader Configuration (singleton like paper_wallet)
+CREATE TABLE IF NOT EXISTS ai_trader_config (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    starting_capital REAL NOT NULL DEFAULT 1000000.0,
+    max_position_size_percent REAL NOT NULL DEFAULT 10.0,
+    stop_loss_percent REAL NOT NULL DEFAULT 5.0,
+    take_profit_percent REAL NOT NULL DEFAULT 15.0,
+    risk_per_trade_percent REAL NOT NULL DEFAULT 1.0,
+    session_duration_minutes INTEGER NOT NULL DEFAULT 60,
+    benchmark_symbol TEXT NOT NULL DEFAULT 'SPY',
+    model_priority TEXT NOT NULL DEFAULT 'deepseek-v3.2:cloud,gpt-oss:120b-cloud,qwen3:235b',
+    -- Trading mode: 'aggressive', 'normal', 'conservative', 'paused'
+    trading_mode TEXT NOT NULL DEFAULT 'normal',
+    -- Circuit breaker settings
+    daily_loss_threshold REAL NOT NULL DEFAULT -10.0,
+    consecutive_loss_limit INTEGER NOT NULL DEFAULT 5,
+    auto_conservative_on_trigger INTEGER NOT NULL DEFAULT 1,
+    circuit_breaker_triggered INTEGER NOT NULL DEFAULT 0,
+    circuit_breaker_until TIMESTAMP,
+    daily_loss_baseline REAL,
+    daily_loss_baseline_date TEXT,
+    -- STRYK override settings
+    override_enabled INTEGER NOT NULL DEFAULT 0,
+    override_expires_at TIMESTAMP,
+    override_max_position_pct REAL,
+    -- Guardrail settings
+    max_daily_trades INTEGER NOT NULL DEFAULT 10,
+    max_single_trade_value REAL NOT NULL DEFAULT 50000.0,
+    require_confluence INTEGER NOT NULL DEFAULT 1,
+    blocked_hours TEXT DEFAULT '09:30-09:45,15:45-16:00',
+    allow_partial_fills INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO ai_trader_config (id) VALUES (1);
+
+-- Trade rejections audit log
+CREATE TABLE IF NOT EXISTS trade_rejections (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    session_id INTEGER,
+    attempted_action TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    quantity REAL,
+    quantity_percent REAL,
+    estimated_value REAL,
+    reason TEXT NOT NULL,
+    rule_triggered TEXT NOT NULL,
+    trading_mode TEXT,
+    raw_request TEXT,
+    FOREIGN KEY (session_id) REFERENCES ai_trading_sessions(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_trade_rejections_timestamp ON trade_rejections(timestamp);
+CREATE INDEX IF NOT EXISTS idx_trade_rejections_rule ON trade_rejections(rule_triggered);
+
+-- Circuit breaker event log
+CREATE TABLE IF NOT EXISTS circuit_breaker_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    trigger_type TEXT NOT NULL,
+    previous_mode TEXT NOT NULL,
+    new_mode TEXT NOT NULL,
+    daily_pnl REAL,
+    consecutive_losses INTEGER,
+    resume_at TIMESTAMP,
+    notes TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_circuit_breaker_timestamp ON circuit_breaker_events(timestamp);
+
+-- ============================================================================
+-- DC TRADER TABLES (Separate from KALIC AI paper trading)
+-- ============================================================================
+
+-- DC trader wallet (mirrors paper_wallet structure)
+CREATE TABLE IF NOT EXISTS dc_wallet (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    cash REAL NOT NULL DEFAULT 1000000.0,
+    starting_capital REAL NOT NULL DEFAULT 1000000.0,
+    per_trade_fee REAL NOT NULL DEFAULT 0.0,
+    fee_percent REAL NOT NULL DEFAULT 0.0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- DC positions (mirrors paper_positions structure)
+CREATE TABLE IF NOT EXISTS dc_positions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    symbol TEXT NOT NULL,
+    quantity REAL NOT NULL,
+    entry_price REAL NOT NULL,
+    entry_date TEXT DEFAULT CURRENT_TIMESTAMP,
+    stop_price REAL,
+    take_price REAL
+);
+
+-- DC trades (mirrors paper_trades structure)
+CREATE TABLE IF NOT EXISTS dc_trades (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    symbol TEXT NOT NULL,
+    action TEXT NOT NULL CHECK(action IN ('BUY', 'SELL')),
+    quantity REAL NOT NULL,
+    price REAL NOT NULL,
+    pnl REAL,
+    timestamp TEXT DEFAULT CURRENT_TIMESTAMP,
     notes TEXT,
+    fee REAL NOT NULL DEFAULT 0.0
+);
+
+CREATE INDEX IF NOT EXISTS idx_dc_trades_timestamp ON dc_trades(timestamp);
+CREATE INDEX IF NOT EXISTS idx_dc_trades_symbol ON dc_trades(symbol);
+
+-- Portfolio snapshots for performance charting (KALIC, DC and the real portfolio)
+CREATE TABLE IF NOT EXISTS portfolio_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    team TEXT NOT NULL CHECK(team IN ('KALIC', 'DC', 'PORTFOLIO')),
+    date TEXT NOT NULL,
+    total_value REAL NOT NULL,
+    cash REAL NOT NULL,
+    positions_value REAL NOT NULL,
+    UNIQUE(team, date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_portfolio_snapshots_team_date ON portfolio_snapshots(team, date);
+
+-- Team configurations (saveable competition presets)
+CREATE TABLE IF NOT EXISTS trading_teams (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT UNIQUE NOT NULL,
+    description TEXT,
+    kalic_starting_capital REAL DEFAULT 1000000.0,
+    dc_starting_capital REAL DEFAULT 1000000.0,
+    kalic_per_trade_fee REAL NOT NULL DEFAULT 0.0,
+    kalic_fee_percent REAL NOT NULL DEFAULT 0.0,
+    dc_per_trade_fee REAL NOT NULL DEFAULT 0.0,
+    dc_fee_percent REAL NOT NULL DEFAULT 0.0,
     created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
 );
 
-CREATE INDEX IF NOT EXISTS idx_positions_symbol ON portfolio_positions(symbol);
+-- App settings (key/value, e.g. notification preferences)
+CREATE TABLE IF NOT EXISTS app_settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
 
--- Google Trends data
-CREATE TABLE IF NOT EXISTS trends_data (
+-- Signal detection thresholds (singleton, defaults match SignalConfig::default)
+CREATE TABLE IF NOT EXISTS signal_config (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    rsi_overbought REAL NOT NULL DEFAULT 70.0,
+    rsi_oversold REAL NOT NULL DEFAULT 30.0,
+    adx_strong_trend REAL NOT NULL DEFAULT 25.0,
+    adx_weak_trend REAL NOT NULL DEFAULT 20.0,
+    stoch_overbought REAL NOT NULL DEFAULT 80.0,
+    stoch_oversold REAL NOT NULL DEFAULT 20.0,
+    willr_overbought REAL NOT NULL DEFAULT -20.0,
+    willr_oversold REAL NOT NULL DEFAULT -80.0,
+    cci_overbought REAL NOT NULL DEFAULT 100.0,
+    cci_oversold REAL NOT NULL DEFAULT -100.0,
+    mfi_overbought REAL NOT NULL DEFAULT 80.0,
+    mfi_oversold REAL NOT NULL DEFAULT 20.0,
+    macd_min_spread REAL NOT NULL DEFAULT 0.0,
+    uo_overbought REAL NOT NULL DEFAULT 70.0,
+    uo_oversold REAL NOT NULL DEFAULT 30.0,
+    gap_min_percent REAL NOT NULL DEFAULT 2.0,
+    stoch_rsi_overbought REAL NOT NULL DEFAULT 80.0,
+    stoch_rsi_oversold REAL NOT NULL DEFAULT 20.0,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO signal_config (id) VALUES (1);
+
+-- Strength multipliers by signal type (types without a row weigh 1.0)
+CREATE TABLE IF NOT EXISTS signal_weights (
+    signal_type TEXT PRIMARY KEY,
+    weight REAL NOT NULL CHECK(weight >= 0.0),
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Saved chat conversations (Claude / Ollama)
+CREATE TABLE IF NOT EXISTS conversations (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
-    keyword TEXT NOT NULL,
+    title TEXT NOT NULL,
+    provider TEXT NOT NULL DEFAULT 'claude',
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS messages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    conversation_id INTEGER NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    role TEXT NOT NULL CHECK(role IN ('user', 'assistant')),
+    content TEXT NOT NULL,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_messages_conversation ON messages(conversation_id);
+
+-- Company names / nicknames used by symbol search (aliases stored lowercase)
+CREATE TABLE IF NOT EXISTS symbol_aliases (
+    alias TEXT PRIMARY KEY,
+    symbol TEXT NOT NULL,
+    created_at TEXT DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_symbol_aliases_symbol ON symbol_aliases(symbol);
+
+INSERT OR IGNORE INTO symbol_aliases (alias, symbol) VALUES
+    ('apple', 'AAPL'),
+    ('microsoft', 'MSFT'),
+    ('google', 'GOOGL'),
+    ('alphabet', 'GOOGL'),
+    ('amazon', 'AMZN'),
+    ('meta', 'META'),
+    ('facebook', 'META'),
+    ('nvidia', 'NVDA'),
+    ('tesla', 'TSLA'),
+    ('netflix', 'NFLX'),
+    ('intel', 'INTC'),
+    ('amd', 'AMD'),
+    ('cisco', 'CSCO'),
+    ('oracle', 'ORCL'),
+    ('ibm', 'IBM'),
+    ('salesforce', 'CRM'),
+    ('adobe', 'ADBE'),
+    ('paypal', 'PYPL'),
+    ('uber', 'UBER'),
+    ('airbnb', 'ABNB'),
+    ('spotify', 'SPOT'),
+    ('snap', 'SNAP'),
+    ('snapchat', 'SNAP'),
+    ('twitter', 'X'),
+    ('palantir', 'PLTR'),
+    ('jpmorgan', 'JPM'),
+    ('jp morgan', 'JPM'),
+    ('goldman', 'GS'),
+    ('goldman sachs', 'GS'),
+    ('morgan stanley', 'MS'),
+    ('bank of america', 'BAC'),
+    ('wells fargo', 'WFC'),
+    ('visa', 'V'),
+    ('mastercard', 'MA'),
+    ('berkshire', 'BRK.B'),
+    ('walmart', 'WMT'),
+    ('costco', 'COST'),
+    ('target', 'TGT'),
+    ('home depot', 'HD'),
+    ('lowes', 'LOW'),
+    ('nike', 'NKE'),
+    ('starbucks', 'SBUX'),
+    ('mcdonalds', 'MCD'),
+    ('coca cola', 'KO'),
+    ('coke', 'KO'),
+    ('pepsi', 'PEP'),
+    ('disney', 'DIS'),
+    ('johnson', 'JNJ'),
+    ('pfizer', 'PFE'),
+    ('moderna', 'MRNA'),
+    ('unitedhealth', 'UNH'),
+    ('exxon', 'XOM'),
+    ('chevron', 'CVX'),
+    ('s&p', 'SPY'),
+    ('s&p 500', 'SPY'),
+    ('spy', 'SPY'),
+    ('nasdaq', 'QQQ'),
+    ('qqq', 'QQQ'),
+    ('dow', 'DIA'),
+    ('dow jones', 'DIA'),
+    ('micron', 'MU'),
+    ('broadcom', 'AVGO'),
+    ('qualcomm', 'QCOM'),
+    ('texas instruments', 'TXN'),
+    ('taiwan semiconductor', 'TSM'),
+    ('tsmc', 'TSM'),
+    ('asml', 'ASML'),
+    ('sap', 'SAP'),
+    ('nestle', 'NESN.SW'),
+    ('lvmh', 'MC.PA'),
+    ('siemens', 'SIE.DE'),
+    ('volkswagen', 'VOW3.DE'),
+    ('bmw', 'BMW.DE'),
+    ('novo nordisk', 'NVO'),
+    ('shell', 'SHEL'),
+    ('bp', 'BP'),
+    ('unilever', 'UL'),
+    ('astrazeneca', 'AZN'),
+    ('hsbc', 'HSBC'),
+    ('toyota', 'TM'),
+    ('sony', 'SONY'),
+    ('samsung', '005930.KS'),
+    ('alibaba', 'BABA'),
+    ('eli lilly', 'LLY'),
+    ('lilly', 'LLY'),
+    ('merck', 'MRK'),
+    ('abbvie', 'ABBV'),
+    ('boeing', 'BA'),
+    ('caterpillar', 'CAT'),
+    ('russell 2000', 'IWM'),
+    ('gold', 'GLD'),
+    ('bitcoin', 'BTC-USD'),
+    ('btc', 'BTC-USD'),
+    ('ethereum', 'ETH-USD'),
+    ('ether', 'ETH-USD'),
+    ('eth', 'ETH-USD'),
+    ('solana', 'SOL-USD'),
+    ('ripple', 'XRP-USD'),
+    ('xrp', 'XRP-USD'),
+    ('cardano', 'ADA-USD'),
+    ('dogecoin', 'DOGE-USD'),
+    ('doge', 'DOGE-USD'),
+    ('litecoin', 'LTC-USD'),
+    ('polkadot', 'DOT-USD'),
+    ('chainlink', 'LINK-USD'),
+    ('avalanche', 'AVAX-USD'),
+    ('binance coin', 'BNB-USD'),
+    ('tether', 'USDT-USD'),
+    ('euro dollar', 'EURUSD=X'),
+    ('eurusd', 'EURUSD=X'),
+    ('pound dollar', 'GBPUSD=X'),
+    ('cable', 'GBPUSD=X'),
+    ('gbpusd', 'GBPUSD=X'),
+    ('dollar yen', 'JPY=X'),
+    ('usdjpy', 'JPY=X'),
+    ('aussie dollar', 'AUDUSD=X'),
+    ('audusd', 'AUDUSD=X'),
+    ('swiss franc', 'CHF=X'),
+    ('loonie', 'CAD=X');
+
+-- Earnings calendar (Finnhub); actuals fill in once reported
+CREATE TABLE IF NOT EXISTS earnings (
+    symbol TEXT NOT NULL,
     date DATE NOT NULL,
-    value INTEGER NOT NULL,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-    UNIQUE(keyword, date)
+    hour TEXT,
+    quarter INTEGER,
+    year INTEGER,
+    eps_estimate REAL,
+    eps_actual REAL,
+    revenue_estimate REAL,
+    revenue_actual REAL,
+    updated_at TEXT DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (symbol, date)
 );
 
-CREATE INDEX IF NOT EXISTS idx_trends_keyword ON trends_data(keyword);
-CREATE INDEX IF NOT EXISTS idx_trends_date ON trends_data(date);
+CREATE INDEX IF NOT EXISTS idx_earnings_date ON earnings(date);
 
--- Trading signals
-CREATE TABLE IF NOT EXISTS signals (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    symbol TEXT NOT NULL,
-    signal_type TEXT NOT NULL,
-    direction TEXT NOT NULL CHECK(direction IN ('bullish', 'bearish', 'neutral')),
-    strength REAL NOT NULL CHECK(strength >= 0.0 AND strength <= 1.0),
-    price_at_signal REAL NOT NULL,
-    triggered_by TEXT NOT NULL,
-    trigger_value REAL NOT NULL,
-    timestamp DATE NOT NULL,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-    acknowledged BOOLEAN DEFAULT 0,
-    UNIQUE(symbol, signal_type, timestamp)
+-- Auto-refresh of favorited symbols (singleton, defaults match RefreshSettings::default)
+CREATE TABLE IF NOT EXISTS refresh_settings (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    enabled INTEGER NOT NULL DEFAULT 0,
+    interval_minutes INTEGER NOT NULL DEFAULT 15,
+    stale_after_days INTEGER NOT NULL DEFAULT 4,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
 );
 
-CREATE INDEX IF NOT EXISTS idx_signals_symbol ON signals(symbol);
-CREATE INDEX IF NOT EXISTS idx_signals_type ON signals(signal_type);
-CREATE INDEX IF NOT EXISTS idx_signals_timestamp ON signals(timestamp);
-CREATE INDEX IF NOT EXISTS idx_signals_direction ON signals(direction);
-CREATE INDEX IF NOT EXISTS idx_signals_acknowledged ON signals(acknowledged);
+INSERT OR IGNORE INTO refresh_settings (id) VALUES (1);
 
--- Indicator-based alerts
-CREATE TABLE IF NOT EXISTS indicator_alerts (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
+-- Cash dividends per share (Yahoo), keyed by ex-dividend date
+CREATE TABLE IF NOT EXISTS dividends (
     symbol TEXT NOT NULL,
-    alert_type TEXT NOT NULL CHECK(alert_type IN ('threshold', 'crossover', 'band_touch')),
-    indicator_name TEXT NOT NULL,
-    secondary_indicator TEXT,
-    condition TEXT NOT NULL CHECK(condition IN (
-        'crosses_above', 'crosses_below', 'bullish_crossover', 'bearish_crossover'
-    )),
-    threshold REAL,
-    triggered BOOLEAN DEFAULT 0,
-    last_value REAL,
-    message TEXT,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+    ex_date DATE NOT NULL,
+    amount REAL NOT NULL,
+    PRIMARY KEY (symbol, ex_date)
 );
 
-CREATE INDEX IF NOT EXISTS idx_ind_alerts_symbol ON indicator_alerts(symbol);
-CREATE INDEX IF NOT EXISTS idx_ind_alerts_triggered ON indicator_alerts(triggered);
-
--- Backtesting strategies
-CREATE TABLE IF NOT EXISTS strategies (
+-- HTTP endpoints notified of triggered signals/alerts (NULL event_type = all events)
+CREATE TABLE IF NOT EXISTS webhooks (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
-    name TEXT UNIQUE NOT NULL,
-    description TEXT,
-    entry_condition TEXT NOT NULL,
-    entry_threshold REAL NOT NULL,
-    exit_condition TEXT NOT NULL,
-    exit_threshold REAL NOT NULL,
-    stop_loss_percent REAL,
-    take_profit_percent REAL,
-    position_size_percent REAL NOT NULL DEFAULT 100.0,
+    url TEXT NOT NULL,
+    event_type TEXT CHECK(event_type IN ('signal', 'price_alert', 'indicator_alert')),
+    secret TEXT,
+    enabled INTEGER NOT NULL DEFAULT 1,
     created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
 );
 
-CREATE INDEX IF NOT EXISTS idx_strategies_name ON strategies(name);
-
--- Backtest runs
-CREATE TABLE IF NOT EXISTS backtest_runs (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    strategy_id INTEGER NOT NULL,
-    strategy_name TEXT NOT NULL,
+-- Sub-daily bars; timestamp is the bar open time as RFC 3339 UTC
+CREATE TABLE IF NOT EXISTS intraday_prices (
     symbol TEXT NOT NULL,
-    start_date DATE NOT NULL,
-    end_date DATE NOT NULL,
-    initial_capital REAL NOT NULL,
-    final_capital REAL NOT NULL,
-    total_return REAL NOT NULL,
-    total_return_dollars REAL NOT NULL,
-    max_drawdown REAL NOT NULL,
-    sharpe_ratio REAL NOT NULL,
-    win_rate REAL NOT NULL,
-    total_trades INTEGER NOT NULL,
-    winning_trades INTEGER NOT NULL,
-    losing_trades INTEGER NOT NULL,
-    avg_win_percent REAL NOT NULL,
-    avg_loss_percent REAL NOT NULL,
-    profit_factor REAL NOT NULL,
-    avg_trade_duration_days REAL NOT NULL,
+    timestamp TEXT NOT NULL,
+    resolution TEXT NOT NULL,
+    open REAL,
+    high REAL,
+    low REAL,
+    close REAL,
+    volume INTEGER,
+    source TEXT,
     created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-    FOREIGN KEY (strategy_id) REFERENCES strategies(id)
+    PRIMARY KEY (symbol, timestamp, resolution)
 );
 
-CREATE INDEX IF NOT EXISTS idx_backtest_runs_strategy ON backtest_runs(strategy_id);
-CREATE INDEX IF NOT EXISTS idx_backtest_runs_symbol ON backtest_runs(symbol);
-CREATE INDEX IF NOT EXISTS idx_backtest_runs_date ON backtest_runs(created_at);
-
--- Backtest trades
-CREATE TABLE IF NOT EXISTS backtest_trades (
+-- Create/update/delete history for positions, paper trades and price alerts
+CREATE TABLE IF NOT EXISTS audit_log (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
-    backtest_id INTEGER NOT NULL,
-    symbol TEXT NOT NULL,
-    direction TEXT NOT NULL CHECK(direction IN ('long', 'short')),
-    entry_date DATE NOT NULL,
-    entry_price REAL NOT NULL,
-    entry_reason TEXT NOT NULL,
-    exit_date DATE,
-    exit_price REAL,
-    exit_reason TEXT,
-    shares REAL NOT NULL,
-    profit_loss REAL,
-    profit_loss_percent REAL,
-    FOREIGN KEY (backtest_id) REFERENCES backtest_runs(id)
+    entity_type TEXT NOT NULL CHECK(entity_type IN ('position', 'paper_trade', 'price_alert')),
+    entity_id INTEGER NOT NULL,
+    action TEXT NOT NULL CHECK(action IN ('create', 'update', 'delete', 'restore')),
+    before_json TEXT,
+    after_json TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity_type, entity_id);
+
+-- Per-table overrides for prune_old_data (tables listed in PRUNABLE_TABLES)
+CREATE TABLE IF NOT EXISTS retention_settings (
+    table_name TEXT PRIMARY KEY,
+    retention_days INTEGER NOT NULL CHECK(retention_days > 0),
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Applied migrations, one row per version (see migrations())
+CREATE TABLE IF NOT EXISTS schema_version (
+    version INTEGER PRIMARY KEY,
+    description TEXT NOT NULL,
+    applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
 );
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> Database {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        db
+    }
+
+    fn price(day: u32, close: f64, adjusted_close: Option<f64>) -> DailyPrice {
+        DailyPrice {
+            symbol: "TEST".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 1, day).unwrap(),
+            open: close,
+            high: close + 1.0,
+            low: close - 1.0,
+            close,
+            volume: 1000,
+            adjusted_close,
+            source: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_price_stats_over_recent_bars() {
+        let mut db = test_db();
+        // Highs are close + 1 and lows close - 1
+        let closes = [100.0, 120.0, 90.0, 110.0, 105.0];
+        let prices: Vec<DailyPrice> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| DailyPrice { volume: 1000 * (i as i64 + 1), ..price(i as u32 + 1, close, None) })
+            .collect();
+        db.upsert_daily_prices(&prices).unwrap();
+        let day = |d| NaiveDate::from_ymd_opt(2026, 1, d).unwrap();
+
+        let all = db.get_price_stats("TEST", 252).unwrap().unwrap();
+        assert_eq!(all.bars, 5);
+        assert_eq!((all.high, all.high_date), (121.0, day(2)));
+        assert_eq!((all.low, all.low_date), (89.0, day(3)));
+        assert!((all.avg_close - 105.0).abs() < 1e-9);
+        assert!((all.avg_volume - 3000.0).abs() < 1e-9);
+
+        // The last two bars only
+        let recent = db.get_price_stats("TEST", 2).unwrap().unwrap();
+        assert_eq!(recent.bars, 2);
+        assert_eq!((recent.high, recent.high_date), (111.0, day(4)));
+        assert_eq!((recent.low, recent.low_date), (104.0, day(5)));
+
+        assert!(db.get_price_stats("NONE", 252).unwrap().is_none());
+        assert!(db.get_price_stats("TEST", 0).is_err());
+    }
+
+    #[test]
+    fn test_adjusted_close_round_trip() {
+        let mut db = test_db();
+        db.upsert_daily_prices(&[price(1, 100.0, Some(50.0)), price(2, 102.0, None)])
+            .unwrap();
+
+        let raw = db.get_prices("TEST").unwrap();
+        assert_eq!(raw[0].adjusted_close, Some(50.0));
+        assert_eq!(raw[1].adjusted_close, None);
+        assert_eq!(raw[0].close, 100.0);
+
+        let adjusted = db.get_adjusted_prices("TEST").unwrap();
+        assert_eq!(adjusted[0].close, 50.0);
+        assert_eq!(adjusted[0].high, 50.5);
+        assert_eq!(adjusted[1].close, 102.0);
+    }
+
+    #[test]
+    fn test_ema_periods_coexist_and_legacy_names_migrate() {
+        let mut db = test_db();
+        let prices: Vec<DailyPrice> = (1..=31).map(|d| price(d, 100.0 + d as f64, None)).collect();
+        let indicators = crate::indicators::calculate_all_with_config(
+            &prices,
+            &crate::indicators::IndicatorConfig::default(),
+        );
+        db.upsert_indicators(&indicators).unwrap();
+
+        let last_day = NaiveDate::from_ymd_opt(2026, 1, 31).unwrap();
+        let ema_12 = db.get_indicator_history("TEST", "EMA_12").unwrap();
+        let ema_26 = db.get_indicator_history("TEST", "EMA_26").unwrap();
+        assert_eq!(ema_12.last().unwrap().date, last_day);
+        assert_eq!(ema_26.last().unwrap().date, last_day);
+        assert!(ema_12.last().unwrap().value > ema_26.last().unwrap().value);
+
+        let available = db.get_available_indicators("TEST").unwrap();
+        assert!(available.contains(&"EMA_12".to_string()));
+        assert!(available.contains(&"EMA_26".to_string()));
+
+        let legacy = |name: &str, value: f64| TechnicalIndicator {
+            symbol: "TEST".to_string(),
+            date: last_day,
+            indicator_name: name.to_string(),
+            value,
+        };
+        db.upsert_indicator(&legacy("SUPERTREND_DIR", 1.0)).unwrap();
+        db.upsert_indicator(&legacy("MACD_HIST", 0.5)).unwrap();
+        migrate_legacy_indicator_names(&db.conn).unwrap();
+
+        let available = db.get_available_indicators("TEST").unwrap();
+        assert!(!available.contains(&"SUPERTREND_DIR".to_string()));
+        assert!(!available.contains(&"MACD_HIST".to_string()));
+        assert_eq!(db.get_latest_indicator_value("TEST", "MACD_HIST").unwrap(), Some(0.5));
+        assert_eq!(db.get_latest_indicator_value("TEST", "MACD_HIST_12_26_9").unwrap(), Some(0.5));
+        // The computed direction already existed for that date, so it wins over the legacy row
+        let dir = db.get_latest_indicator_value("TEST", "SUPERTREND_DIR_10").unwrap();
+        assert!(matches!(dir, Some(d) if d.abs() == 1.0));
+    }
+
+    #[test]
+    fn test_intraday_bars_on_the_same_day_are_distinct_rows() {
+        let mut db = test_db();
+        let bar = |minute: u32, close: f64| IntradayPrice {
+            symbol: "TEST".to_string(),
+            timestamp: NaiveDate::from_ymd_opt(2026, 1, 5)
+                .unwrap()
+                .and_hms_opt(14, minute, 0)
+                .unwrap()
+                .and_utc(),
+            resolution: "5".to_string(),
+            open: close,
+            high: close + 0.5,
+            low: close - 0.5,
+            close,
+            volume: 500,
+            source: "test".to_string(),
+        };
+        db.upsert_intraday_prices(&[bar(30, 100.0), bar(35, 101.0)]).unwrap();
+        db.upsert_intraday_prices(&[bar(35, 101.5)]).unwrap();
+
+        let stored = db.get_intraday_prices("TEST", "5").unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].timestamp, bar(30, 0.0).timestamp);
+        assert_eq!(stored[1].close, 101.5);
+        assert!(db.get_intraday_prices("TEST", "15").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_intraday_reaction_picks_bars_around_event() {
+        let mut db = test_db();
+        let bar = |hour: u32, minute: u32, close: f64| IntradayPrice {
+            symbol: "TEST".to_string(),
+            timestamp: NaiveDate::from_ymd_opt(2026, 1, 5)
+                .unwrap()
+                .and_hms_opt(hour, minute, 0)
+                .unwrap()
+                .and_utc(),
+            resolution: "5".to_string(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1000,
+            source: "test".to_string(),
+        };
+        db.upsert_intraday_prices(&[
+            bar(9, 0, 90.0),   // more than 2h before the event
+            bar(13, 50, 99.0),
+            bar(13, 55, 100.0), // last bar to finish before 14:02
+            bar(14, 0, 104.0),  // straddles the headline, so it's neither side
+            bar(14, 5, 106.0),
+            bar(15, 55, 110.0), // last bar opening within 2h after
+            bar(16, 5, 120.0),
+        ])
+        .unwrap();
+
+        let event = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap().and_hms_opt(14, 2, 0).unwrap().and_utc();
+        let reaction = db.intraday_price_reaction("TEST", "5", event, 2).unwrap().unwrap();
+        assert_eq!(reaction.pre_price, 100.0);
+        assert_eq!(reaction.post_price, 110.0);
+        assert!((reaction.price_change_percent - 10.0).abs() < 1e-9);
+        assert_eq!(reaction.start_date, "2026-01-05T13:55:00Z");
+        assert_eq!(reaction.end_date, "2026-01-05T15:55:00Z");
+
+        // No bars at this resolution: caller falls back to daily data
+        assert!(db.intraday_price_reaction("TEST", "15", event, 2).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_prices_paginated_and_range() {
+        let mut db = test_db();
+        let prices: Vec<DailyPrice> = (1..=10).map(|d| price(d, 100.0 + d as f64, None)).collect();
+        db.upsert_daily_prices(&prices).unwrap();
+
+        let page = db.get_prices_paginated("TEST", 2, 3).unwrap();
+        assert_eq!(page.len(), 3);
+        assert_eq!(page[0].close, 103.0);
+        assert_eq!(page[2].close, 105.0);
+
+        let all = db.get_prices_paginated("TEST", 0, usize::MAX).unwrap();
+        assert_eq!(all.len(), 10);
+
+        let range = db
+            .get_price_range(
+                "TEST",
+                NaiveDate::from_ymd_opt(2026, 1, 4).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 1, 6).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(range.len(), 3);
+        assert_eq!(range[0].close, 104.0);
+    }
+
+    #[test]
+    fn test_migrations_are_idempotent() {
+        let db = test_db();
+        let latest = migrations().last().unwrap().version;
+        assert_eq!(db.schema_version().unwrap(), latest);
+
+        db.run_migrations().unwrap();
+        db.init_schema().unwrap();
+
+        let applied: i64 = db
+            .conn
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(applied, migrations().len() as i64);
+        assert_eq!(db.schema_version().unwrap(), latest);
+    }
+
+    #[test]
+    fn test_schema_version_starts_at_zero() {
+        let db = Database::open_in_memory().unwrap();
+        assert_eq!(db.schema_version().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_position_dividend_income() {
+        let mut db = test_db();
+        db.add_position("TEST", 10.0, 100.0, PositionType::Buy, "2026-01-05", None).unwrap();
+        db.add_position("TEST", 5.0, 105.0, PositionType::Buy, "2026-02-10", None).unwrap();
+        let dividend = |month, amount| Dividend {
+            symbol: "TEST".to_string(),
+            ex_date: NaiveDate::from_ymd_opt(2026, month, 1).unwrap(),
+            amount,
+        };
+        db.upsert_dividends(&[dividend(2, 0.50), dividend(3, 0.60)]).unwrap();
+
+        // 10 shares held on Feb 1, 15 on Mar 1
+        let income = db.get_position_dividend_income("TEST", None).unwrap();
+        assert!((income - (10.0 * 0.50 + 15.0 * 0.60)).abs() < 1e-9);
+
+        let since = NaiveDate::from_ymd_opt(2026, 2, 15);
+        let recent = db.get_position_dividend_income("TEST", since).unwrap();
+        assert!((recent - 9.0).abs() < 1e-9);
+        assert_eq!(db.get_position_dividend_income("OTHER", None).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_rebalance_sells_overweight_position() {
+        let mut db = test_db();
+        let last = |symbol: &str, close: f64| DailyPrice {
+            symbol: symbol.to_string(),
+            ..price(1, close, None)
+        };
+        db.upsert_daily_prices(&[last("AAA", 100.0), last("BBB", 50.0)]).unwrap();
+        db.add_position("AAA", 30.0, 80.0, PositionType::Buy, "2026-01-02", None).unwrap();
+        db.add_position("BBB", 20.0, 50.0, PositionType::Buy, "2026-01-02", None).unwrap();
+
+        // $3000 AAA + $1000 BBB; 50/50 means $2000 each
+        let targets = HashMap::from([("AAA".to_string(), 50.0), ("bbb".to_string(), 50.0)]);
+        let plan = db.rebalance_plan(&targets, 0.0).unwrap();
+        assert_eq!(plan.len(), 2);
+        assert_eq!((plan[0].symbol.as_str(), plan[0].action, plan[0].shares), ("AAA", PositionType::Sell, 10.0));
+        assert_eq!((plan[1].symbol.as_str(), plan[1].action, plan[1].shares), ("BBB", PositionType::Buy, 20.0));
+        assert_eq!(plan[0].est_value, 1000.0);
+
+        // Small trades are dropped and over-allocated targets rejected
+        assert!(db.rebalance_plan(&targets, 1500.0).unwrap().is_empty());
+        let too_much = HashMap::from([("AAA".to_string(), 60.0), ("BBB".to_string(), 50.0)]);
+        assert!(db.rebalance_plan(&too_much, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_portfolio_holding_only_benchmark_has_no_alpha() {
+        let mut db = test_db();
+        let spy = |day: u32, close: f64| DailyPrice {
+            symbol: "SPY".to_string(),
+            ..price(day, close, None)
+        };
+        db.upsert_daily_prices(&[spy(2, 400.0), spy(5, 404.0), spy(6, 398.0), spy(7, 410.0), spy(8, 412.0)])
+            .unwrap();
+        db.add_position("SPY", 10.0, 400.0, PositionType::Buy, "2026-01-02", None).unwrap();
+
+        let comparison = db.portfolio_vs_benchmark("SPY", 30).unwrap();
+        assert!((comparison.benchmark_return_percent - 3.0).abs() < 1e-9);
+        assert!((comparison.portfolio_return_percent - 3.0).abs() < 1e-9);
+        assert!(comparison.alpha.abs() < 1e-9);
+        assert_eq!(comparison.tracking_data.len(), 5);
+
+        assert!(db.portfolio_vs_benchmark("MISSING", 30).is_err());
+    }
+
+    #[test]
+    fn test_daily_snapshot_job_records_once_per_day() {
+        let db = test_db();
+
+        assert_eq!(db.record_missing_snapshots().unwrap(), SNAPSHOT_TEAMS.len());
+        assert_eq!(db.record_missing_snapshots().unwrap(), 0);
+
+        let snapshots = db.get_portfolio_snapshots(None, 1).unwrap();
+        assert_eq!(snapshots.len(), SNAPSHOT_TEAMS.len());
+        assert!(snapshots.iter().any(|s| s.team == PORTFOLIO_TEAM));
+    }
+
+    #[test]
+    fn test_circuit_breaker_event_and_daily_loss_baseline_round_trip() {
+        let db = test_db();
+        let resume_at: DateTime<Utc> = "2026-01-06T18:00:00Z".parse().unwrap();
+        db.log_circuit_breaker_event("daily_loss_threshold", "normal", "conservative", -12.0, 0, Some(resume_at))
+            .unwrap();
+
+        let stored: String = db
+            .conn
+            .query_row("SELECT resume_at FROM circuit_breaker_events", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, "2026-01-06T18:00:00Z");
+
+        let day = NaiveDate::from_ymd_opt(2026, 1, 6).unwrap();
+        assert_eq!(db.get_daily_loss_baseline().unwrap(), None);
+        db.set_daily_loss_baseline(day, 850_000.0).unwrap();
+        assert_eq!(db.get_daily_loss_baseline().unwrap(), Some((day, 850_000.0)));
+    }
+
+    #[test]
+    fn test_backfill_marks_positions_to_historical_closes() {
+        let mut db = test_db();
+        let today = chrono::Local::now().date_naive();
+        let bar = |days_ago: i64, close: f64| DailyPrice {
+            date: today - chrono::Duration::days(days_ago),
+            ..price(1, close, None)
+        };
+        db.upsert_daily_prices(&[bar(3, 100.0), bar(2, 105.0), bar(1, 110.0)]).unwrap();
+        let opened = (today - chrono::Duration::days(2)).to_string();
+        db.add_position("TEST", 10.0, 104.0, PositionType::Buy, &opened, None).unwrap();
+
+        // Three days for each of the three teams
+        assert_eq!(db.backfill_snapshots(5).unwrap(), 9);
+        assert_eq!(db.backfill_snapshots(5).unwrap(), 0);
+
+        let portfolio = db.get_portfolio_snapshots(Some(PORTFOLIO_TEAM), 5).unwrap();
+        let values: Vec<f64> = portfolio.iter().map(|s| s.total_value).collect();
+        assert_eq!(values, vec![0.0, 1050.0, 1100.0]);
+    }
+
+    #[test]
+    fn test_pooled_reads_run_concurrently() {
+        let path = std::env::temp_dir().join(format!("fp_pool_test_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let pool = Database::pool(&path, &DatabaseOptions::default(), 2).unwrap();
+        {
+            let mut db = pool.get().unwrap();
+            db.init_schema().unwrap();
+            let prices: Vec<DailyPrice> = (1..=5).map(|d| price(d, 100.0 + d as f64, None)).collect();
+            db.upsert_daily_prices(&prices).unwrap();
+        }
+
+        // Both readers hold a connection at the barrier; a single shared lock would deadlock here
+        let barrier = std::sync::Barrier::new(2);
+        let counts: Vec<usize> = std::thread::scope(|scope| {
+            let readers: Vec<_> = (0..2)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let db = pool.get().unwrap();
+                        barrier.wait();
+                        db.get_symbols_with_data()
+                            .unwrap()
+                            .iter()
+                            .map(|symbol| db.get_prices(symbol).unwrap().len())
+                            .sum()
+                    })
+                })
+                .collect();
+            readers.into_iter().map(|r| r.join().unwrap()).collect()
+        });
+
+        assert_eq!(counts, vec![5, 5]);
+        assert_eq!(pool.state().connections, 2);
+
+        drop(pool);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn test_backup_and_restore() {
+        let path = std::env::temp_dir().join(format!("fp_backup_test_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut db = test_db();
+        let prices: Vec<DailyPrice> = (1..=5).map(|d| price(d, 100.0 + d as f64, None)).collect();
+        db.upsert_daily_prices(&prices).unwrap();
+        db.backup_to(&path).unwrap();
+
+        let reopened = Database::open(&path).unwrap();
+        let restored_prices = reopened.get_prices("TEST").unwrap();
+        assert_eq!(restored_prices.len(), 5);
+        assert_eq!(restored_prices[4].close, 105.0);
+        drop(reopened);
+
+        let mut fresh = test_db();
+        fresh.restore_from(&path).unwrap();
+        assert_eq!(fresh.get_prices("TEST").unwrap().len(), 5);
+
+        // A non-SQLite file is rejected and leaves the database untouched
+        std::fs::write(&path, b"not a database").unwrap();
+        assert!(fresh.restore_from(&path).is_err());
+        assert_eq!(fresh.get_prices("TEST").unwrap().len(), 5);
+
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn test_second_connection_waits_for_lock() {
+        let path = std::env::temp_dir().join(format!("fp_lock_test_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let first = Database::open(&path).unwrap();
+        first.init_schema().unwrap();
+        let second = Database::open(&path).unwrap();
+
+        // Hold a write lock on the first connection for a moment
+        first.conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+        first
+            .conn
+            .execute("INSERT INTO symbols (symbol) VALUES ('LOCK')", [])
+            .unwrap();
+        let writer = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            first.conn.execute_batch("COMMIT").unwrap();
+        });
+
+        // busy_timeout makes this wait instead of failing with "database is locked"
+        let result = second
+            .conn
+            .execute("INSERT INTO symbols (symbol) VALUES ('WAIT')", []);
+        writer.join().unwrap();
+
+        assert!(result.is_ok(), "{:?}", result);
+        let mode: String = second
+            .conn
+            .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        drop(second);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn test_settings_upsert() {
+        let db = test_db();
+        assert_eq!(db.get_setting("notifications_enabled").unwrap(), None);
+
+        db.set_setting("notifications_enabled", "true").unwrap();
+        db.set_setting("notifications_enabled", "false").unwrap();
+        assert_eq!(
+            db.get_setting("notifications_enabled").unwrap(),
+            Some("false".to_string())
+        );
+    }
+
+    #[test]
+    fn test_signal_config_roundtrip_and_validation() {
+        let db = test_db();
+        assert_eq!(db.get_signal_config().unwrap(), SignalConfig::default());
+
+        let config = SignalConfig {
+            rsi_oversold: 40.0,
+            ..SignalConfig::default()
+        };
+        db.update_signal_config(&config).unwrap();
+        assert_eq!(db.get_signal_config().unwrap(), config);
+
+        let inverted = SignalConfig {
+            rsi_oversold: 80.0,
+            ..SignalConfig::default()
+        };
+        assert!(db.update_signal_config(&inverted).is_err());
+        assert_eq!(db.get_signal_config().unwrap().rsi_oversold, 40.0);
+    }
+
+    #[test]
+    fn test_refresh_settings_roundtrip() {
+        let db = test_db();
+        assert_eq!(db.get_refresh_settings().unwrap(), RefreshSettings::default());
+
+        let settings = RefreshSettings {
+            enabled: true,
+            interval_minutes: 5,
+            stale_after_days: 7,
+        };
+        db.update_refresh_settings(&settings).unwrap();
+        assert_eq!(db.get_refresh_settings().unwrap(), settings);
+
+        let zero = RefreshSettings {
+            interval_minutes: 0,
+            ..settings.clone()
+        };
+        assert!(db.update_refresh_settings(&zero).is_err());
+        assert_eq!(db.get_refresh_settings().unwrap(), settings);
+    }
+
+    #[test]
+    fn test_merge_symbol_consolidates_case_drifted_rows() {
+        let mut db = test_db();
+        let bar = |symbol: &str, day| DailyPrice { symbol: symbol.to_string(), ..price(day, 100.0, None) };
+        db.upsert_daily_prices(&[bar("aapl", 1), bar("aapl", 2), bar("AAPL", 2), bar("AAPL", 3)])
+            .unwrap();
+        db.conn
+            .execute_batch(
+                r#"INSERT INTO paper_positions (symbol, quantity, entry_price) VALUES ('aapl', 10, 100), ('AAPL', 30, 120);
+                   INSERT INTO paper_trades (symbol, action, quantity, price) VALUES ('aapl', 'BUY', 10, 100);
+                   INSERT INTO portfolio_positions (symbol, quantity, price, position_type, date)
+                   VALUES ('aapl', 5, 90, 'buy', '2026-01-01');
+                   INSERT INTO ai_trade_decisions (action, symbol, confidence, reasoning, model_used)
+                   VALUES ('BUY', 'aapl', 0.8, 'test', 'test');
+                   INSERT INTO trade_rejections (attempted_action, symbol, reason, rule_triggered)
+                   VALUES ('BUY', 'aapl', 'test', 'max_position');"#,
+            )
+            .unwrap();
+
+        let duplicates = db.find_duplicate_symbols().unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].canonical, "AAPL");
+        assert_eq!(duplicates[0].variants, vec!["AAPL", "aapl"]);
+
+        assert!(db.merge_symbol("AAPL", "AAPL").is_err());
+        db.merge_symbol("aapl", "AAPL").unwrap();
+
+        // 10 @ 100 and 30 @ 120 average to 40 @ 115
+        let position = db.get_paper_position("AAPL").unwrap().unwrap();
+        assert_eq!(position.quantity, 40.0);
+        assert_eq!(position.entry_price, 115.0);
+        assert_eq!(db.get_prices("AAPL").unwrap().len(), 3);
+        assert_eq!(db.get_paper_trades(Some("AAPL"), 10).unwrap().len(), 1);
+
+        // Every table with a symbol column, not just the ones merge_symbol knows about
+        let tables: Vec<String> = db
+            .conn
+            .prepare(
+                "SELECT m.name FROM sqlite_master m, pragma_table_info(m.name) c
+                 WHERE m.type = 'table' AND c.name = 'symbol'",
+            )
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<SqliteResult<_>>()
+            .unwrap();
+        let mut merged: Vec<&str> = SYMBOL_KEYED_TABLES.iter().chain(POSITION_TABLES.iter()).copied().collect();
+        let mut found: Vec<&str> = tables.iter().map(String::as_str).collect();
+        merged.sort_unstable();
+        found.sort_unstable();
+        assert_eq!(found, merged, "merge_symbol must cover every table with a symbol column");
+        for table in &tables {
+            let left: i64 = db
+                .conn
+                .query_row(&format!("SELECT COUNT(*) FROM {} WHERE symbol = 'aapl'", table), [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(left, 0, "{} still has aapl rows", table);
+        }
+        assert!(db.find_duplicate_symbols().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_prune_old_data_keeps_rows_inside_window() {
+        let db = test_db();
+        let days_ago = |days: i64| Utc::now() - chrono::Duration::days(days);
+        for age in [400, 10] {
+            db.conn
+                .execute(
+                    "INSERT INTO api_calls (source, endpoint, symbol, timestamp) VALUES ('yahoo', 'chart', 'TEST', ?1)",
+                    params![days_ago(age).to_rfc3339()],
+                )
+                .unwrap();
+        }
+        for age in [400, 60, 10] {
+            db.conn
+                .execute(
+                    r#"INSERT INTO signals (symbol, signal_type, direction, strength, price_at_signal,
+                           triggered_by, trigger_value, timestamp)
+                       VALUES ('TEST', 'RSI_OVERSOLD', 'bullish', 0.5, 100.0, 'RSI_14', 25.0, ?1)"#,
+                    params![days_ago(age).format("%Y-%m-%d").to_string()],
+                )
+                .unwrap();
+        }
+        db.set_retention_override("signals", Some(30)).unwrap();
+        assert!(db.set_retention_override("daily_prices", Some(30)).is_err());
+        assert!(db.prune_old_data(0).is_err());
+
+        let pruned = db.prune_old_data(90).unwrap();
+        let deleted = |table: &str| pruned.iter().find(|p| p.table == table).unwrap().deleted;
+        assert_eq!(deleted("api_calls"), 1);
+        assert_eq!(deleted("signals"), 2);
+        assert_eq!(deleted("ai_performance_snapshots"), 0);
+
+        let count = |table: &str| -> i64 {
+            db.conn
+                .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+                .unwrap()
+        };
+        assert_eq!(count("api_calls"), 1);
+        assert_eq!(count("signals"), 1);
+    }
+
+    #[test]
+    fn test_webhook_event_filter() {
+        let db = test_db();
+        let all = db.add_webhook("https://example.com/all", None, Some("s3cret")).unwrap();
+        let signals = db
+            .add_webhook("https://example.com/signals", Some(WebhookEvent::Signal), None)
+            .unwrap();
+        assert!(db.add_webhook("ftp://example.com", None, None).is_err());
+
+        let ids = |event| -> Vec<i64> {
+            db.get_webhooks_for_event(event).unwrap().iter().map(|w| w.id).collect()
+        };
+        assert_eq!(ids(WebhookEvent::Signal), vec![all, signals]);
+        assert_eq!(ids(WebhookEvent::PriceAlert), vec![all]);
+
+        db.set_webhook_enabled(all, false).unwrap();
+        assert_eq!(ids(WebhookEvent::Signal), vec![signals]);
+
+        db.delete_webhook(signals).unwrap();
+        let remaining = db.list_webhooks().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].secret.as_deref(), Some("s3cret"));
+        assert!(!remaining[0].enabled);
+    }
+
+    #[test]
+    fn test_last_indicator_date() {
+        let mut db = test_db();
+        assert_eq!(db.get_last_indicator_date("TEST", "RSI_14").unwrap(), None);
+
+        let ind = |day, value| TechnicalIndicator {
+            symbol: "TEST".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 1, day).unwrap(),
+            indicator_name: "RSI_14".to_string(),
+            value,
+        };
+        db.upsert_indicators(&[ind(3, 40.0), ind(5, 45.0), ind(4, 42.0)]).unwrap();
+        assert_eq!(
+            db.get_last_indicator_date("TEST", "RSI_14").unwrap(),
+            NaiveDate::from_ymd_opt(2026, 1, 5)
+        );
+    }
+
+    #[test]
+    fn test_paper_buy_with_foreign_keys_enabled() {
+        let db = test_db();
+        let trade = db
+            .execute_paper_trade("TEST", PaperTradeAction::Buy, 5.0, 100.0, None, None)
+            .unwrap();
+        assert_eq!(trade.quantity, 5.0);
+        assert_eq!(db.get_paper_position("TEST").unwrap().unwrap().quantity, 5.0);
+    }
+
+    #[test]
+    fn test_limit_buy_waits_for_price() {
+        let mut db = test_db();
+        db.upsert_daily_prices(&[price(1, 100.0, None)]).unwrap();
+
+        let order = db
+            .place_paper_order("TEST", PaperTradeAction::Buy, PaperOrderType::Limit, 10.0, Some(95.0), None)
+            .unwrap();
+        assert!(db.process_pending_paper_orders().unwrap().is_empty());
+        assert_eq!(db.get_paper_order(order.id).unwrap().unwrap().status, PaperOrderStatus::Pending);
+
+        db.upsert_daily_prices(&[price(2, 94.0, None)]).unwrap();
+        let filled = db.process_pending_paper_orders().unwrap();
+        assert_eq!(filled.len(), 1);
+        assert_eq!(filled[0].status, PaperOrderStatus::Filled);
+
+        let trades = db.get_paper_trades(Some("TEST"), 10).unwrap();
+        assert_eq!(trades.len(), 1);
+        assert_eq!(Some(trades[0].id), filled[0].paper_trade_id);
+        assert_eq!(trades[0].price, 94.0);
+        assert!(!db.cancel_paper_order(order.id).unwrap());
+    }
+
+    #[test]
+    fn test_stop_sell_and_cancel() {
+        let mut db = test_db();
+        db.upsert_daily_prices(&[price(1, 100.0, None)]).unwrap();
+        db.execute_paper_trade("TEST", PaperTradeAction::Buy, 10.0, 100.0, None, None).unwrap();
+
+        let stop = db
+            .place_paper_order("TEST", PaperTradeAction::Sell, PaperOrderType::Stop, 10.0, None, Some(90.0))
+            .unwrap();
+        let cancelled = db
+            .place_paper_order("TEST", PaperTradeAction::Sell, PaperOrderType::Limit, 10.0, Some(120.0), None)
+            .unwrap();
+        assert!(db.cancel_paper_order(cancelled.id).unwrap());
+        assert!(db
+            .place_paper_order("TEST", PaperTradeAction::Sell, PaperOrderType::Stop, 10.0, Some(90.0), None)
+            .is_err());
+
+        db.upsert_daily_prices(&[price(2, 89.0, None)]).unwrap();
+        let filled = db.process_pending_paper_orders().unwrap();
+        assert_eq!(filled.iter().map(|o| o.id).collect::<Vec<_>>(), vec![stop.id]);
+        assert!(db.get_paper_position("TEST").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_reset_paper_account_persists_starting_capital() {
+        let mut db = test_db();
+        assert_eq!(db.get_paper_wallet().unwrap().starting_capital, 1_000_000.0);
+
+        db.reset_paper_account(50_000.0).unwrap();
+        db.upsert_daily_prices(&[price(1, 100.0, None)]).unwrap();
+        db.execute_paper_trade("TEST", PaperTradeAction::Buy, 100.0, 100.0, None, None).unwrap();
+        db.upsert_daily_prices(&[price(2, 110.0, None)]).unwrap();
+
+        let wallet = db.get_paper_wallet().unwrap();
+        let (_, _, total_equity) = db.get_paper_portfolio_value().unwrap();
+        assert_eq!(wallet.starting_capital, 50_000.0);
+        assert_eq!(total_equity, 51_000.0);
+        assert!((wallet.pnl_percent(total_equity) - 2.0).abs() < 1e-9);
+        assert!((db.get_competition_stats().unwrap().kalic_pnl_pct - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_competition_stats_use_each_starting_capital() {
+        let mut db = test_db();
+        db.reset_paper_account(1_000_000.0).unwrap();
+        db.reset_dc_account(50_000.0).unwrap();
+        db.upsert_daily_prices(&[price(1, 100.0, None)]).unwrap();
+        db.execute_paper_trade("TEST", PaperTradeAction::Buy, 1000.0, 100.0, None, None).unwrap();
+        db.execute_dc_trade("TEST", "BUY", 400.0, 100.0, None).unwrap();
+        db.upsert_daily_prices(&[price(2, 110.0, None)]).unwrap();
+
+        let stats = db.get_competition_stats().unwrap();
+        // KALIC: +$10k on $1M; DC: +$4k on $50k
+        assert_eq!(stats.kalic_total, 1_010_000.0);
+        assert_eq!(stats.dc_total, 54_000.0);
+        assert!((stats.kalic_pnl_pct - 1.0).abs() < 1e-9);
+        assert!((stats.dc_pnl_pct - 8.0).abs() < 1e-9);
+        assert_eq!(stats.leader, "DC");
+        assert!((stats.lead_amount - 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_flat_fee_reduces_cash_per_trade() {
+        let mut db = test_db();
+        db.reset_dc_account(50_000.0).unwrap();
+        db.upsert_daily_prices(&[price(1, 100.0, None)]).unwrap();
+        db.set_dc_fees(&TradeFees { per_trade_fee: 5.0, fee_percent: 0.0 }).unwrap();
+        assert!(db.set_dc_fees(&TradeFees { per_trade_fee: -1.0, fee_percent: 0.0 }).is_err());
+
+        let buy = db.execute_dc_trade("TEST", "BUY", 100.0, 100.0, None).unwrap();
+        assert_eq!(buy.fee, 5.0);
+        assert_eq!(db.get_dc_wallet().unwrap().cash, 50_000.0 - 10_000.0 - 5.0);
+
+        db.execute_dc_trade("TEST", "SELL", 100.0, 100.0, None).unwrap();
+        assert_eq!(db.get_dc_wallet().unwrap().cash, 50_000.0 - 10.0);
+
+        let stats = db.get_competition_stats().unwrap();
+        assert_eq!(stats.dc_fees_paid, 10.0);
+        assert_eq!(stats.dc_total, 49_990.0);
+        // KALIC is still commission-free
+        db.execute_paper_trade("TEST", PaperTradeAction::Buy, 1.0, 100.0, None, None).unwrap();
+        assert_eq!(db.get_competition_stats().unwrap().kalic_fees_paid, 0.0);
+
+        // Saved presets carry the fee settings
+        db.save_team_config("fees", None).unwrap();
+        let config = db.load_team_config("fees").unwrap();
+        assert_eq!(config.dc_fees.per_trade_fee, 5.0);
+        assert_eq!(config.kalic_fees, TradeFees::default());
+    }
+
+    #[test]
+    fn test_partial_buy_fills_affordable_shares() {
+        let mut db = test_db();
+        db.reset_paper_account(1_000.0).unwrap();
+        db.upsert_daily_prices(&[price(1, 300.0, None)]).unwrap();
+        db.set_paper_fees(&TradeFees { per_trade_fee: 5.0, fee_percent: 0.0 }).unwrap();
+
+        // Strict mode still rejects the whole order
+        assert!(db.execute_paper_trade("TEST", PaperTradeAction::Buy, 10.0, 300.0, None, None).is_err());
+        assert_eq!(db.get_paper_wallet().unwrap().cash, 1_000.0);
+
+        // $995 after the fee buys three $300 shares
+        let trade = db.execute_paper_buy_affordable("TEST", 10.0, 300.0, None, None).unwrap();
+        assert_eq!(trade.quantity, 3.0);
+        assert_eq!(db.get_paper_trades(Some("TEST"), 10).unwrap()[0].quantity, 3.0);
+        assert_eq!(db.get_paper_position("TEST").unwrap().unwrap().quantity, 3.0);
+        assert_eq!(db.get_paper_wallet().unwrap().cash, 1_000.0 - 900.0 - 5.0);
+
+        // Orders that fit are filled in full; $95 left can't cover one share
+        assert_eq!(db.execute_paper_buy_affordable("TEST", 0.25, 300.0, None, None).unwrap().quantity, 0.25);
+        assert!(db.execute_paper_buy_affordable("TEST", 1.0, 300.0, None, None).is_err());
+    }
+
+    #[test]
+    fn test_suggest_stops_uses_atr_below_latest_close() {
+        let mut db = test_db();
+        // Constant closes with high/low one point either side: every true range is 2
+        let bars: Vec<DailyPrice> = (1..=20).map(|day| price(day, 100.0, None)).collect();
+        db.upsert_daily_prices(&bars).unwrap();
+        db.execute_paper_trade("TEST", PaperTradeAction::Buy, 10.0, 90.0, None, None).unwrap();
+        // Too little history for a 14-day ATR
+        db.upsert_daily_prices(&[DailyPrice { symbol: "THIN".to_string(), ..price(1, 50.0, None) }])
+            .unwrap();
+        db.execute_paper_trade("THIN", PaperTradeAction::Buy, 1.0, 50.0, None, None).unwrap();
+
+        let stops = db.suggest_stops("KALIC", 2.5).unwrap();
+        assert_eq!(stops.len(), 1);
+        assert_eq!(stops[0].symbol, "TEST");
+        assert_eq!(stops[0].entry_price, 90.0);
+        assert_eq!(stops[0].current_price, 100.0);
+        assert!((stops[0].atr - 2.0).abs() < 1e-9);
+        assert!((stops[0].suggested_stop - (100.0 - 2.5 * 2.0)).abs() < 1e-9);
+
+        // Portfolio ledger rows are netted: two buys give one suggestion at their average
+        // cost, and a symbol that has been sold out gets none
+        let sold_out: Vec<DailyPrice> = (1..=20)
+            .map(|day| DailyPrice { symbol: "GONE".to_string(), ..price(day, 100.0, None) })
+            .collect();
+        db.upsert_daily_prices(&sold_out).unwrap();
+        db.add_position("TEST", 10.0, 80.0, PositionType::Buy, "2026-01-02", None).unwrap();
+        db.add_position("TEST", 30.0, 120.0, PositionType::Buy, "2026-01-03", None).unwrap();
+        db.add_position("GONE", 5.0, 100.0, PositionType::Buy, "2026-01-02", None).unwrap();
+        db.add_position("GONE", 5.0, 110.0, PositionType::Sell, "2026-01-03", None).unwrap();
+
+        let stops = db.suggest_stops(PORTFOLIO_TEAM, 2.5).unwrap();
+        assert_eq!(stops.len(), 1);
+        assert_eq!(stops[0].symbol, "TEST");
+        assert_eq!(stops[0].entry_price, 110.0);
+        assert!((stops[0].suggested_stop - 95.0).abs() < 1e-9);
+
+        assert!(db.suggest_stops("KALIC", 0.0).is_err());
+        assert!(db.suggest_stops("NOBODY", 2.0).is_err());
+    }
+
+    #[test]
+    fn test_paper_stop_closes_position() {
+        let mut db = test_db();
+        db.upsert_daily_prices(&[price(1, 100.0, None)]).unwrap();
+        db.execute_paper_trade("TEST", PaperTradeAction::Buy, 10.0, 100.0, None, None).unwrap();
+        assert!(db.set_paper_position_exits("TEST", Some(95.0), Some(120.0)).unwrap());
+        assert!(db.set_paper_position_exits("TEST", Some(130.0), Some(120.0)).is_err());
+
+        // The position's own stop at 95 takes precedence over the 10% default (90), so 96 holds
+        db.upsert_daily_prices(&[price(2, 96.0, None)]).unwrap();
+        assert!(db.check_paper_stops(Some(10.0), None).unwrap().is_empty());
+
+        db.upsert_daily_prices(&[price(3, 94.0, None)]).unwrap();
+        let closed = db.check_paper_stops(Some(10.0), None).unwrap();
+        assert_eq!(closed.len(), 1);
+        assert_eq!((closed[0].action, closed[0].quantity, closed[0].price), (PaperTradeAction::Sell, 10.0, 94.0));
+        assert!(closed[0].notes.as_deref().unwrap().starts_with("Stop-loss"));
+        assert!(db.get_paper_position("TEST").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_dc_take_profit_from_percent() {
+        let mut db = test_db();
+        db.upsert_daily_prices(&[price(1, 100.0, None)]).unwrap();
+        db.execute_dc_trade("TEST", "BUY", 5.0, 100.0, None).unwrap();
 
-CREATE INDEX IF NOT EXISTS idx_backtest_trades_run ON backtest_trades(backtest_id);
-CREATE INDEX IF NOT EXISTS idx_backtest_trades_symbol ON backtest_trades(symbol);
+        db.upsert_daily_prices(&[price(2, 109.0, None)]).unwrap();
+        assert!(db.check_dc_stops(None, Some(10.0)).unwrap().is_empty());
 
--- Paper trading wallet (singleton - one paper account)
-CREATE TABLE IF NOT EXISTS paper_wallet (
-    id INTEGER PRIMARY KEY CHECK (id = 1),
-    cash REAL NOT NULL DEFAULT 1000000.0,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-);
+        db.upsert_daily_prices(&[price(3, 111.0, None)]).unwrap();
+        let closed = db.check_dc_stops(None, Some(10.0)).unwrap();
+        assert_eq!(closed.len(), 1);
+        assert!(closed[0].notes.as_deref().unwrap().starts_with("Take-profit"));
+        assert!(db.get_dc_position("TEST").unwrap().is_none());
+    }
 
--- Initialize wallet if not exists (default $1M for AI trading)
-INSERT OR IGNORE INTO paper_wallet (id, cash) VALUES (1, 1000000.0);
+    #[test]
+    fn test_paper_realized_vs_unrealized_pnl() {
+        let mut db = test_db();
+        db.execute_paper_trade("TEST", PaperTradeAction::Buy, 10.0, 100.0, None, None).unwrap();
+        db.execute_paper_trade("TEST", PaperTradeAction::Sell, 4.0, 110.0, None, None).unwrap();
+        db.upsert_daily_prices(&[price(1, 120.0, None)]).unwrap();
+
+        // 4 shares sold at +10, 6 remaining marked at +20
+        assert!((db.get_paper_realized_pnl().unwrap() - 40.0).abs() < 1e-9);
+        assert!((db.get_paper_unrealized_pnl().unwrap() - 120.0).abs() < 1e-9);
+
+        // Closing the rest books it without losing the earlier gain
+        db.execute_paper_trade("TEST", PaperTradeAction::Sell, 6.0, 120.0, None, None).unwrap();
+        assert!((db.get_paper_realized_pnl().unwrap() - 160.0).abs() < 1e-9);
+        assert_eq!(db.get_paper_unrealized_pnl().unwrap(), 0.0);
+    }
 
--- Paper trading positions (open positions)
-CREATE TABLE IF NOT EXISTS paper_positions (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    symbol TEXT NOT NULL,
-    quantity REAL NOT NULL,
-    entry_price REAL NOT NULL,
-    entry_date TEXT DEFAULT CURRENT_TIMESTAMP,
-    linked_event_id INTEGER,
-    FOREIGN KEY (linked_event_id) REFERENCES market_events(id)
-);
+    #[test]
+    fn test_fifo_vs_average_realized_pnl() {
+        let realized_after_partial_sell = |method: CostBasisMethod| {
+            let db = test_db();
+            db.set_cost_basis_method(method).unwrap();
+            db.execute_paper_trade("TEST", PaperTradeAction::Buy, 10.0, 100.0, None, None).unwrap();
+            db.execute_paper_trade("TEST", PaperTradeAction::Buy, 10.0, 120.0, None, None).unwrap();
+            let sell = db
+                .execute_paper_trade("TEST", PaperTradeAction::Sell, 10.0, 130.0, None, None)
+                .unwrap();
+            let remaining_entry = db.get_paper_position("TEST").unwrap().unwrap().entry_price;
+
+            // Lot and trade ids have diverged by now; the returned trade must still be the buy
+            let rebuy = db
+                .execute_paper_trade("TEST", PaperTradeAction::Buy, 1.0, 90.0, None, None)
+                .unwrap();
+            assert_eq!((rebuy.action, rebuy.price), (PaperTradeAction::Buy, 90.0));
+
+            (sell.pnl.unwrap(), remaining_entry)
+        };
 
-CREATE INDEX IF NOT EXISTS idx_paper_positions_symbol ON paper_positions(symbol);
+        // Average: all 20 shares cost 110
+        assert_eq!(realized_after_partial_sell(CostBasisMethod::Average), (200.0, 110.0));
+        // FIFO: the 100 lot is sold first, leaving the 120 lot
+        assert_eq!(realized_after_partial_sell(CostBasisMethod::Fifo), (300.0, 120.0));
+    }
 
--- Paper trading history (all trades)
-CREATE TABLE IF NOT EXISTS paper_trades (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    symbol TEXT NOT NULL,
-    action TEXT NOT NULL CHECK(action IN ('BUY', 'SELL')),
-    quantity REAL NOT NULL,
-    price REAL NOT NULL,
-    pnl REAL,
-    timestamp TEXT DEFAULT CURRENT_TIMESTAMP,
-    linked_event_id INTEGER,
-    notes TEXT
-);
+    #[test]
+    fn test_dc_trades_json_round_trip() {
+        let db = test_db();
+        db.init_dc_wallet().unwrap();
+        db.reset_dc_account(100_000.0).unwrap();
+        db.execute_dc_trade("AAA", "BUY", 10.0, 50.0, Some("first")).unwrap();
+        db.execute_dc_trade("BBB", "BUY", 5.0, 200.0, None).unwrap();
+        db.execute_dc_trade("AAA", "SELL", 4.0, 60.0, None).unwrap();
+
+        let positions = |db: &Database| {
+            let mut p: Vec<(String, f64, f64)> = db
+                .get_dc_positions()
+                .unwrap()
+                .into_iter()
+                .map(|p| (p.symbol, p.quantity, p.entry_price))
+                .collect();
+            p.sort_by(|a, b| a.0.cmp(&b.0));
+            p
+        };
+        let before = positions(&db);
+        let exported = db.export_trades_json("DC").unwrap();
+        assert!(db.export_trades_csv("DC").unwrap().starts_with("symbol,action,quantity,price,pnl,timestamp,notes"));
 
-CREATE INDEX IF NOT EXISTS idx_paper_trades_symbol ON paper_trades(symbol);
-CREATE INDEX IF NOT EXISTS idx_paper_trades_timestamp ON paper_trades(timestamp);
+        db.reset_dc_account(100_000.0).unwrap();
+        let result = db.import_dc_trades_json(&exported).unwrap();
 
--- ============================================================================
--- AI Trading Simulator Tables
--- ============================================================================
+        assert_eq!(result.success_count, 3, "{:?}", result.errors);
+        assert_eq!(positions(&db), before);
+        assert_eq!(db.get_dc_wallet().unwrap().cash, 100_000.0 - 500.0 - 1000.0 + 240.0);
+    }
 
--- AI Trading Sessions
-CREATE TABLE IF NOT EXISTS ai_trading_sessions (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    start_time DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-    end_time DATETIME,
-    starting_portfolio_value REAL NOT NULL,
-    ending_portfolio_value REAL,
-    decisions_count INTEGER DEFAULT 0,
-    trades_count INTEGER DEFAULT 0,
-    session_notes TEXT,
-    status TEXT DEFAULT 'active' CHECK(status IN ('active', 'completed', 'interrupted'))
-);
+    #[test]
+    fn test_conversation_round_trip_and_delete() {
+        let db = test_db();
+        let id = db.create_conversation("Rates", "claude").unwrap();
+        db.add_conversation_message(id, "user", "Where are rates going?").unwrap();
+        db.add_conversation_message(id, "assistant", "Lower, probably.").unwrap();
+
+        let conversations = db.list_conversations().unwrap();
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0].message_count, 2);
+
+        let messages = db.get_conversation_messages(id).unwrap();
+        assert_eq!(messages[0].role, "user");
+        assert_eq!(messages[1].content, "Lower, probably.");
+
+        assert!(db.delete_conversation(id).unwrap());
+        assert!(!db.delete_conversation(id).unwrap());
+        assert!(db.get_conversation(id).unwrap().is_none());
+        assert!(db.get_conversation_messages(id).unwrap().is_empty());
+    }
 
-CREATE INDEX IF NOT EXISTS idx_ai_sessions_status ON ai_trading_sessions(status);
-CREATE INDEX IF NOT EXISTS idx_ai_sessions_start ON ai_trading_sessions(start_time);
+    #[test]
+    fn test_portfolio_allocation_by_sector_and_asset_class() {
+        let mut db = test_db();
+        for (symbol, sector) in [("AAPL", "Technology"), ("XOM", "Energy")] {
+            db.upsert_symbol(&Symbol {
+                symbol: symbol.to_string(),
+                name: None,
+                sector: Some(sector.to_string()),
+                industry: None,
+                market_cap: None,
+                country: None,
+                exchange: None,
+                currency: None,
+                isin: None,
+                asset_class: Some("Equity".to_string()),
+            })
+            .unwrap();
+        }
+        let mut aapl = price(5, 200.0, None);
+        aapl.symbol = "AAPL".to_string();
+        db.upsert_daily_prices(&[aapl]).unwrap();
+
+        // AAPL marked at 200 (30 x 200 = 6000), XOM at its entry price (40 x 100 = 4000)
+        db.add_position("AAPL", 30.0, 150.0, PositionType::Buy, "2026-01-02", None).unwrap();
+        db.add_position("XOM", 40.0, 100.0, PositionType::Buy, "2026-01-02", None).unwrap();
+
+        let allocation = db.get_portfolio_allocation().unwrap();
+        assert!((allocation.total_value - 10_000.0).abs() < 1e-9);
+        assert_eq!(allocation.by_sector[0].name, "Technology");
+        assert!((allocation.by_sector[0].percent - 60.0).abs() < 1e-9);
+        assert!((allocation.by_sector[1].percent - 40.0).abs() < 1e-9);
+        let sector_total: f64 = allocation.by_sector.iter().map(|b| b.percent).sum();
+        assert!((sector_total - 100.0).abs() < 1e-9);
+        assert_eq!(allocation.by_asset_class.len(), 1);
+        assert!((allocation.by_asset_class[0].percent - 100.0).abs() < 1e-9);
+
+        // A symbol without metadata lands in the Unknown bucket
+        db.add_position("ZZZ", 10.0, 100.0, PositionType::Buy, "2026-01-02", None).unwrap();
+        let allocation = db.get_portfolio_allocation().unwrap();
+        let unknown = allocation.by_sector.iter().find(|b| b.name == "Unknown").unwrap();
+        assert!((unknown.percent - 1_000.0 / 11_000.0 * 100.0).abs() < 1e-9);
+    }
 
--- AI Trade Decisions (detailed reasoning log)
-CREATE TABLE IF NOT EXISTS ai_trade_decisions (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    session_id INTEGER REFERENCES ai_trading_sessions(id),
-    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
-    action TEXT NOT NULL CHECK(action IN ('BUY', 'SELL', 'HOLD')),
-    symbol TEXT NOT NULL,
-    quantity REAL,
-    price_at_decision REAL,
-    confidence REAL NOT NULL,
-    reasoning TEXT NOT NULL,
-    model_used TEXT NOT NULL,
-    predicted_direction TEXT CHECK(predicted_direction IN ('bullish', 'bearish', 'neutral')),
-    predicted_price_target REAL,
-    predicted_timeframe_days INTEGER,
-    actual_outcome TEXT,
-    actual_price_at_timeframe REAL,
-    prediction_accurate INTEGER,
-    paper_trade_id INTEGER REFERENCES paper_trades(id)
-);
+    #[test]
+    fn test_search_symbols_tolerates_typos_and_custom_aliases() {
+        let db = test_db();
 
-CREATE INDEX IF NOT EXISTS idx_ai_decisions_session ON ai_trade_decisions(session_id);
-CREATE INDEX IF NOT EXISTS idx_ai_decisions_symbol ON ai_trade_decisions(symbol);
-CREATE INDEX IF NOT EXISTS idx_ai_decisions_timestamp ON ai_trade_decisions(timestamp);
+        let results = db.search_symbols("microsft", 5).unwrap();
+        assert_eq!(results[0].symbol, "MSFT");
 
--- AI Performance Snapshots (for charting equity curve)
-CREATE TABLE IF NOT EXISTS ai_performance_snapshots (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
-    portfolio_value REAL NOT NULL,
-    cash REAL NOT NULL,
-    positions_value REAL NOT NULL,
-    benchmark_value REAL NOT NULL,
-    benchmark_symbol TEXT DEFAULT 'SPY',
-    total_pnl REAL NOT NULL,
-    total_pnl_percent REAL NOT NULL,
-    benchmark_pnl_percent REAL NOT NULL,
-    prediction_accuracy REAL,
-    trades_to_date INTEGER NOT NULL,
-    winning_trades INTEGER NOT NULL,
-    losing_trades INTEGER NOT NULL,
-    win_rate REAL
-);
+        let results = db.search_symbols("micron", 5).unwrap();
+        assert_eq!(results[0].symbol, "MU");
+        assert_eq!(results[0].score, 1.0);
 
-CREATE INDEX IF NOT EXISTS idx_ai_performance_timestamp ON ai_performance_snapshots(timestamp);
+        assert!(db.search_symbols("rheinmetall", 5).unwrap().iter().all(|m| m.symbol != "RHM.DE"));
+        db.add_symbol_alias("Rheinmetall", "rhm.de").unwrap();
+        let results = db.search_symbols("rheinmetal", 5).unwrap();
+        assert_eq!(results[0].symbol, "RHM.DE");
+    }
 
--- AI Trader Configuration (singleton like paper_wallet)
-CREATE TABLE IF NOT EXISTS ai_trader_config (
-    id INTEGER PRIMARY KEY CHECK (id = 1),
-    starting_capital REAL NOT NULL DEFAULT 1000000.0,
-    max_position_size_percent REAL NOT NULL DEFAULT 10.0,
-    stop_loss_percent REAL NOT NULL DEFAULT 5.0,
-    take_profit_percent REAL NOT NULL DEFAULT 15.0,
-    session_duration_minutes INTEGER NOT NULL DEFAULT 60,
-    benchmark_symbol TEXT NOT NULL DEFAULT 'SPY',
-    model_priority TEXT NOT NULL DEFAULT 'deepseek-v3.2:cloud,gpt-oss:120b-cloud,qwen3:235b',
-    -- Trading mode: 'aggressive', 'normal', 'conservative', 'paused'
-    trading_mode TEXT NOT NULL DEFAULT 'normal',
-    -- Circuit breaker settings
-    daily_loss_threshold REAL NOT NULL DEFAULT -10.0,
-    consecutive_loss_limit INTEGER NOT NULL DEFAULT 5,
-    auto_conservative_on_trigger INTEGER NOT NULL DEFAULT 1,
-    circuit_breaker_triggered INTEGER NOT NULL DEFAULT 0,
-    circuit_breaker_until TIMESTAMP,
-    -- STRYK override settings
-    override_enabled INTEGER NOT NULL DEFAULT 0,
-    override_expires_at TIMESTAMP,
-    override_max_position_pct REAL,
-    -- Guardrail settings
-    max_daily_trades INTEGER NOT NULL DEFAULT 10,
-    max_single_trade_value REAL NOT NULL DEFAULT 50000.0,
-    require_confluence INTEGER NOT NULL DEFAULT 1,
-    blocked_hours TEXT DEFAULT '09:30-09:45,15:45-16:00',
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-);
+    fn two_bar_alert(condition: AlertCondition, prev: (f64, i64), latest: (f64, i64)) -> bool {
+        let mut db = test_db();
+        let mut bars = vec![price(1, prev.0, None), price(2, latest.0, None)];
+        bars[0].volume = prev.1;
+        bars[1].volume = latest.1;
+        db.upsert_daily_prices(&bars).unwrap();
+        db.add_alert("TEST", 0.0, condition).unwrap();
+        !db.check_alerts().unwrap().is_empty()
+    }
 
-INSERT OR IGNORE INTO ai_trader_config (id) VALUES (1);
+    #[test]
+    fn test_percent_change_up_alert() {
+        assert!(two_bar_alert(AlertCondition::PercentChangeUp(5.0), (100.0, 1000), (106.0, 1000)));
+        assert!(!two_bar_alert(AlertCondition::PercentChangeUp(5.0), (100.0, 1000), (104.0, 1000)));
+    }
 
--- Trade rejections audit log
-CREATE TABLE IF NOT EXISTS trade_rejections (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    timestamp TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-    session_id INTEGER,
-    attempted_action TEXT NOT NULL,
-    symbol TEXT NOT NULL,
-    quantity REAL,
-    quantity_percent REAL,
-    estimated_value REAL,
-    reason TEXT NOT NULL,
-    rule_triggered TEXT NOT NULL,
-    trading_mode TEXT,
-    raw_request TEXT,
-    FOREIGN KEY (session_id) REFERENCES ai_trading_sessions(id)
-);
+    #[test]
+    fn test_percent_change_down_alert() {
+        assert!(two_bar_alert(AlertCondition::PercentChangeDown(5.0), (100.0, 1000), (94.0, 1000)));
+        assert!(!two_bar_alert(AlertCondition::PercentChangeDown(5.0), (100.0, 1000), (97.0, 1000)));
+        assert!(!two_bar_alert(AlertCondition::PercentChangeDown(5.0), (100.0, 1000), (110.0, 1000)));
+    }
 
-CREATE INDEX IF NOT EXISTS idx_trade_rejections_timestamp ON trade_rejections(timestamp);
-CREATE INDEX IF NOT EXISTS idx_trade_rejections_rule ON trade_rejections(rule_triggered);
+    #[test]
+    fn test_volume_multiple_alert() {
+        assert!(two_bar_alert(AlertCondition::VolumeMultiple(3.0), (100.0, 1000), (100.0, 3500)));
+        assert!(!two_bar_alert(AlertCondition::VolumeMultiple(3.0), (100.0, 1000), (100.0, 2000)));
+    }
 
--- Circuit breaker event log
-CREATE TABLE IF NOT EXISTS circuit_breaker_events (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    timestamp TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-    trigger_type TEXT NOT NULL,
-    previous_mode TEXT NOT NULL,
-    new_mode TEXT NOT NULL,
-    daily_pnl REAL,
-    consecutive_losses INTEGER,
-    resume_at TIMESTAMP,
-    notes TEXT
-);
+    #[test]
+    fn test_relative_alert_round_trip() {
+        let db = test_db();
+        db.add_alert("TEST", 0.0, AlertCondition::VolumeMultiple(2.5)).unwrap();
+        let alerts = db.get_alerts(true).unwrap();
+        assert_eq!(alerts[0].condition, AlertCondition::VolumeMultiple(2.5));
+        assert_eq!(alerts[0].target_price, 2.5);
+    }
 
-CREATE INDEX IF NOT EXISTS idx_circuit_breaker_timestamp ON circuit_breaker_events(timestamp);
+    #[test]
+    fn test_add_alert_returns_new_row_id() {
+        let db = test_db();
+        let first = db.add_alert("TEST", 100.0, AlertCondition::Above).unwrap();
+        let second = db.add_alert("TEST", 90.0, AlertCondition::Below).unwrap();
+        assert_ne!(first, second);
+
+        let alerts = db.get_alerts(false).unwrap();
+        let found = alerts.iter().find(|a| a.id == second).unwrap();
+        assert_eq!(found.target_price, 90.0);
+        assert_eq!(found.condition, AlertCondition::Below);
+    }
 
--- ============================================================================
--- DC TRADER TABLES (Separate from KALIC AI paper trading)
--- ============================================================================
+    #[test]
+    fn test_backtest_curves_round_trip() {
+        let db = test_db();
+        let strategy = Strategy {
+            id: 0,
+            name: "curves".to_string(),
+            description: None,
+            entry_condition: StrategyConditionType::RsiOversold,
+            entry_threshold: 30.0,
+            exit_condition: StrategyConditionType::RsiOverbought,
+            exit_threshold: 70.0,
+            stop_loss_percent: None,
+            take_profit_percent: None,
+            trailing_stop_percent: None,
+            position_size_percent: 100.0,
+            direction: TradeDirection::Long,
+            entry_rule: None,
+            exit_rule: None,
+            created_at: String::new(),
+        };
+        let strategy_id = db.save_strategy(&strategy).unwrap();
+        let prices = vec![price(1, 100.0, None), price(2, 90.0, None)];
+        let mut result = crate::backtest::BacktestEngine::default().run(&strategy, "TEST", &prices, &[]);
+        result.strategy_id = strategy_id;
 
--- DC trader wallet (mirrors paper_wallet structure)
-CREATE TABLE IF NOT EXISTS dc_wallet (
-    id INTEGER PRIMARY KEY CHECK (id = 1),
-    cash REAL NOT NULL DEFAULT 1000000.0,
-    starting_capital REAL NOT NULL DEFAULT 1000000.0,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
-    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-);
+        let id = db.save_backtest_result(&result).unwrap();
+        let loaded = db.get_backtest_detail(id).unwrap().unwrap();
 
--- DC positions (mirrors paper_positions structure)
-CREATE TABLE IF NOT EXISTS dc_positions (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    symbol TEXT NOT NULL,
-    quantity REAL NOT NULL,
-    entry_price REAL NOT NULL,
-    entry_date TEXT DEFAULT CURRENT_TIMESTAMP
-);
+        assert_eq!(loaded.equity_curve, result.equity_curve);
+        assert_eq!(loaded.drawdown_curve, result.drawdown_curve);
+        assert_eq!(loaded.equity_curve.len(), 2);
+    }
 
--- DC trades (mirrors paper_trades structure)
-CREATE TABLE IF NOT EXISTS dc_trades (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    symbol TEXT NOT NULL,
-    action TEXT NOT NULL CHECK(action IN ('BUY', 'SELL')),
-    quantity REAL NOT NULL,
-    price REAL NOT NULL,
-    pnl REAL,
-    timestamp TEXT DEFAULT CURRENT_TIMESTAMP,
-    notes TEXT
-);
+    #[test]
+    fn test_config_export_import_round_trip() {
+        let source = test_db();
+        source
+            .create_watchlist("tech", &["AAPL".to_string(), "MSFT".to_string()], Some("Big tech"))
+            .unwrap();
+        source.create_watchlist("energy", &["XOM".to_string()], None).unwrap();
+        source
+            .save_strategy(&Strategy {
+                id: 0,
+                name: "rsi swing".to_string(),
+                description: Some("Buy oversold".to_string()),
+                entry_condition: StrategyConditionType::RsiOversold,
+                entry_threshold: 25.0,
+                exit_condition: StrategyConditionType::RsiOverbought,
+                exit_threshold: 75.0,
+                stop_loss_percent: Some(5.0),
+                take_profit_percent: None,
+                trailing_stop_percent: Some(3.0),
+                position_size_percent: 50.0,
+                direction: TradeDirection::Long,
+                entry_rule: None,
+                exit_rule: None,
+                created_at: String::new(),
+            })
+            .unwrap();
+        source.add_alert("AAPL", 150.0, AlertCondition::Below).unwrap();
+        let exported = source.export_config().unwrap();
+
+        let target = test_db();
+        target.create_watchlist("stale", &["GE".to_string()], None).unwrap();
+        let stale_alert = target.add_alert("GE", 10.0, AlertCondition::Above).unwrap();
+        assert_eq!(target.import_config(exported.clone(), false).unwrap(), 4);
+        let deleted = &target.get_audit_log(10).unwrap()[1];
+        assert_eq!((deleted.entity_id, deleted.action.as_str()), (stale_alert, "delete"));
+        // Merging the same file again only upserts the named rows
+        assert_eq!(target.import_config(exported.clone(), true).unwrap(), 3);
+        assert_eq!(target.get_alerts(false).unwrap().len(), 1);
+
+        // Ids and timestamps are per-database; everything else must match
+        let portable = |mut config: serde_json::Value| {
+            for section in ["strategies", "price_alerts"] {
+                for row in config[section].as_array_mut().unwrap() {
+                    row["id"] = serde_json::Value::Null;
+                    row["created_at"] = serde_json::Value::Null;
+                }
+            }
+            config
+        };
+        assert_eq!(portable(target.export_config().unwrap()), portable(exported));
 
-CREATE INDEX IF NOT EXISTS idx_dc_trades_timestamp ON dc_trades(timestamp);
-CREATE INDEX IF NOT EXISTS idx_dc_trades_symbol ON dc_trades(symbol);
+        let newer = serde_json::json!({ "version": CONFIG_EXPORT_VERSION + 1 });
+        assert!(matches!(
+            target.import_config(newer, true),
+            Err(crate::error::PipelineError::Config(_))
+        ));
+    }
 
--- Portfolio snapshots for performance charting (both KALIC and DC)
-CREATE TABLE IF NOT EXISTS portfolio_snapshots (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    team TEXT NOT NULL CHECK(team IN ('KALIC', 'DC')),
-    date TEXT NOT NULL,
-    total_value REAL NOT NULL,
-    cash REAL NOT NULL,
-    positions_value REAL NOT NULL,
-    UNIQUE(team, date)
-);
+    #[test]
+    fn test_composite_rule_round_trip() {
+        let db = test_db();
+        let rule = StrategyRule::Any(vec![
+            crate::models::Condition {
+                condition: StrategyConditionType::RsiOversold,
+                threshold: 30.0,
+            },
+            crate::models::Condition {
+                condition: StrategyConditionType::MacdCrossUp,
+                threshold: 0.0,
+            },
+        ]);
+        let strategy = Strategy {
+            id: 0,
+            name: "composite".to_string(),
+            description: None,
+            entry_condition: StrategyConditionType::RsiOversold,
+            entry_threshold: 30.0,
+            exit_condition: StrategyConditionType::RsiOverbought,
+            exit_threshold: 70.0,
+            stop_loss_percent: None,
+            take_profit_percent: None,
+            trailing_stop_percent: None,
+            position_size_percent: 100.0,
+            direction: TradeDirection::Long,
+            entry_rule: Some(rule.clone()),
+            exit_rule: None,
+            created_at: String::new(),
+        };
+        db.save_strategy(&strategy).unwrap();
 
-CREATE INDEX IF NOT EXISTS idx_portfolio_snapshots_team_date ON portfolio_snapshots(team, date);
+        let loaded = db.get_strategy("composite").unwrap().unwrap();
+        assert_eq!(loaded.entry_rule, Some(rule));
+        assert_eq!(loaded.exit_rule, None);
+    }
 
--- Team configurations (saveable competition presets)
-CREATE TABLE IF NOT EXISTS trading_teams (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    name TEXT UNIQUE NOT NULL,
-    description TEXT,
-    kalic_starting_capital REAL DEFAULT 1000000.0,
-    dc_starting_capital REAL DEFAULT 1000000.0,
-    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-);
-"#;
+    #[test]
+    fn test_deleted_position_is_hidden_but_restorable() {
+        let db = test_db();
+        let id = db
+            .add_position("AAPL", 10.0, 150.0, PositionType::Buy, "2026-01-02", None)
+            .unwrap();
+
+        db.delete_position(id).unwrap();
+        assert!(db.get_positions().unwrap().is_empty());
+        assert_eq!(db.get_positions_filtered(true).unwrap().len(), 1);
+
+        let log = db.get_audit_log(10).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!((log[0].entity_type.as_str(), log[0].action.as_str()), ("position", "delete"));
+        assert_eq!(log[0].entity_id, id);
+        assert!(log[0].before.as_deref().unwrap().contains("AAPL"));
+        assert_eq!(log[0].after, None);
+        assert_eq!(log[1].action, "create");
+
+        assert!(db.restore_position(id).unwrap());
+        assert!(!db.restore_position(id).unwrap());
+        let positions = db.get_positions().unwrap();
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].id, id);
+        assert_eq!(db.get_audit_log(1).unwrap()[0].action, "restore");
+    }
+
+    #[test]
+    fn test_weekly_trends_lead_returns_at_lag() {
+        use chrono::Datelike;
+
+        let mut db = test_db();
+        const LAG: usize = 3;
+
+        // Weekday bars from Monday 2026-01-05; interest is read weekly on Mondays
+        let dates: Vec<NaiveDate> = (0..140)
+            .map(|d| NaiveDate::from_ymd_opt(2026, 1, 5).unwrap() + chrono::Duration::days(d))
+            .filter(|d| d.weekday().num_days_from_monday() < 5)
+            .collect();
+        let weekly: Vec<TrendData> = dates
+            .iter()
+            .filter(|d| d.weekday() == chrono::Weekday::Mon)
+            .enumerate()
+            .map(|(week, &date)| TrendData {
+                keyword: "widgets".to_string(),
+                date,
+                value: (week as i32 * 37) % 100,
+            })
+            .collect();
+        db.upsert_trends(&weekly).unwrap();
+
+        // Each day's return is set by the interest reading LAG bars earlier
+        let interest_on = |date: NaiveDate| {
+            weekly.iter().rev().find(|t| t.date <= date).unwrap().value as f64
+        };
+        let mut close = 100.0;
+        let prices: Vec<DailyPrice> = dates
+            .iter()
+            .enumerate()
+            .map(|(i, &date)| {
+                if i >= LAG {
+                    close *= 1.0 + (interest_on(dates[i - LAG]) - 50.0) / 1000.0;
+                }
+                DailyPrice { symbol: "WDGT".to_string(), date, ..price(1, close, None) }
+            })
+            .collect();
+        db.upsert_daily_prices(&prices).unwrap();
+
+        let at_lag = db.trends_price_correlation("widgets", "WDGT", LAG).unwrap().unwrap();
+        assert!(at_lag > 0.99, "correlation at lag {}: {}", LAG, at_lag);
+        let same_day = db.trends_price_correlation("widgets", "WDGT", 0).unwrap().unwrap();
+        assert!(same_day < at_lag);
+        assert_eq!(db.trends_price_correlation("nothing", "WDGT", LAG).unwrap(), None);
+    }
+
+    #[test]
+    fn test_calculate_all_indicators_covers_every_symbol() {
+        let mut db = test_db();
+        let series = |symbol: &str, bars: u32| -> Vec<DailyPrice> {
+            (0..bars)
+                .map(|i| DailyPrice {
+                    symbol: symbol.to_string(),
+                    date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap() + chrono::Duration::days(i as i64),
+                    ..price(1, 100.0 + (i % 7) as f64, None)
+                })
+                .collect()
+        };
+        for (symbol, bars) in [("AAA", 60), ("BBB", 40), ("TINY", 5)] {
+            db.upsert_daily_prices(&series(symbol, bars)).unwrap();
+        }
+
+        let summary = db.calculate_all_indicators(false).unwrap();
+        let symbols: Vec<&str> = summary.iter().map(|s| s.symbol.as_str()).collect();
+        assert_eq!(symbols, ["AAA", "BBB", "TINY"]);
+
+        for (run, bars) in summary.iter().zip([60, 40]) {
+            assert!(!run.skipped);
+            assert_eq!(run.bars, bars);
+            assert_eq!(run.indicators, crate::indicators::calculate_all(&db.get_prices(&run.symbol).unwrap()).len());
+            assert!(!db.get_latest_indicators(&run.symbol).unwrap().is_empty());
+        }
+        assert!(summary[2].skipped);
+        assert_eq!(summary[2].indicators, 0);
+        assert!(db.get_latest_indicators("TINY").unwrap().is_empty());
+
+        // Nothing new since the first run
+        let rerun = db.calculate_all_indicators(false).unwrap();
+        assert!(rerun.iter().all(|run| run.indicators == 0));
+    }
+
+    #[test]
+    fn test_data_freshness_flags_stale_symbols() {
+        let mut db = test_db();
+        let today = Utc::now().date_naive();
+        for (symbol, days_ago) in [("FRESH", 1), ("OLD", 10)] {
+            let bars: Vec<DailyPrice> = (days_ago..days_ago + 3)
+                .map(|d| DailyPrice {
+                    symbol: symbol.to_string(),
+                    date: today - chrono::Duration::days(d),
+                    ..price(1, 100.0, None)
+                })
+                .collect();
+            db.upsert_daily_prices(&bars).unwrap();
+        }
+
+        let freshness = db.get_data_freshness().unwrap();
+        assert_eq!(freshness.len(), 2);
+        assert_eq!(freshness[0].symbol, "OLD");
+        assert_eq!(freshness[0].last_date, today - chrono::Duration::days(10));
+        assert_eq!(freshness[0].days_stale, 10);
+        assert!(freshness[0].stale);
+        assert_eq!((freshness[1].symbol.as_str(), freshness[1].days_stale), ("FRESH", 1));
+        assert!(!freshness[1].stale);
+
+        db.update_refresh_settings(&RefreshSettings { stale_after_days: 14, ..RefreshSettings::default() })
+            .unwrap();
+        assert!(db.get_data_freshness().unwrap().iter().all(|f| !f.stale));
+    }
+}
@@ -1,5 +1,6 @@
 //! Error types for Financial Pipeline
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -7,6 +8,9 @@ pub enum PipelineError {
     #[error("Database error: {0}")]
     Database(#[from] rusqlite::Error),
 
+    #[error("Connection pool error: {0}")]
+    Pool(#[from] r2d2::Error),
+
     #[error("HTTP request error: {0}")]
     Http(#[from] reqwest::Error),
 
@@ -30,6 +34,141 @@ pub enum PipelineError {
 
     #[error("API error: {0}")]
     ApiError(String),
+
+    #[error("Rate limit reached: {0}")]
+    RateLimited(String),
 }
 
 pub type Result<T> = std::result::Result<T, PipelineError>;
+
+impl PipelineError {
+    /// Stable machine-readable code for this kind of failure
+    pub fn code(&self) -> &'static str {
+        match self {
+            PipelineError::Database(e) if is_busy(e) => "DATABASE_BUSY",
+            PipelineError::Database(_) => "DATABASE_ERROR",
+            PipelineError::Pool(_) => "DATABASE_BUSY",
+            PipelineError::Http(_) => "NETWORK_ERROR",
+            PipelineError::Json(_) | PipelineError::Csv(_) => "PARSE_ERROR",
+            PipelineError::Io(_) => "IO_ERROR",
+            PipelineError::NoData(_) => "NO_DATA",
+            PipelineError::Config(_) => "INVALID_CONFIG",
+            PipelineError::DateParse(_) => "INVALID_INPUT",
+            PipelineError::ApiError(_) => "API_ERROR",
+            PipelineError::RateLimited(_) => "RATE_LIMITED",
+        }
+    }
+
+    /// Whether the same request may succeed if tried again later
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            PipelineError::Database(e) => is_busy(e),
+            PipelineError::Pool(_) | PipelineError::RateLimited(_) => true,
+            PipelineError::Http(e) => {
+                e.is_timeout() || e.is_connect() || e.status().is_some_and(|s| s.is_server_error())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// SQLite reported the database as busy or locked by another connection
+fn is_busy(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+    )
+}
+
+/// Error returned by GUI commands: a stable `code` the frontend can branch on,
+/// a human-readable `message`, and whether offering a retry makes sense
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandError {
+    pub code: String,
+    pub message: String,
+    pub retryable: bool,
+}
+
+impl CommandError {
+    pub fn new(code: &str, message: impl Into<String>, retryable: bool) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.into(),
+            retryable,
+        }
+    }
+
+    /// The request itself was malformed or out of range
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new("INVALID_INPUT", message, false)
+    }
+
+    /// Nothing stored (or returned) for what was asked for
+    pub fn no_data(message: impl Into<String>) -> Self {
+        Self::new("NO_DATA", message, false)
+    }
+
+    /// A required local service (e.g. Ollama) isn't reachable right now
+    pub fn unavailable(message: impl Into<String>) -> Self {
+        Self::new("SERVICE_UNAVAILABLE", message, true)
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<PipelineError> for CommandError {
+    fn from(e: PipelineError) -> Self {
+        Self::new(e.code(), e.to_string(), e.is_retryable())
+    }
+}
+
+impl From<r2d2::Error> for CommandError {
+    fn from(e: r2d2::Error) -> Self {
+        PipelineError::Pool(e).into()
+    }
+}
+
+impl From<anyhow::Error> for CommandError {
+    fn from(e: anyhow::Error) -> Self {
+        match e.downcast::<PipelineError>() {
+            Ok(pipeline) => pipeline.into(),
+            Err(other) => Self::new("COMMAND_FAILED", format!("{:#}", other), false),
+        }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        Self::new("COMMAND_FAILED", message, false)
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limited_maps_to_retryable_code() {
+        let err = CommandError::from(PipelineError::RateLimited("yahoo: 100/100 calls today".to_string()));
+        assert_eq!(err.code, "RATE_LIMITED");
+        assert!(err.retryable);
+        assert_eq!(err.message, "Rate limit reached: yahoo: 100/100 calls today");
+
+        let no_data = CommandError::from(PipelineError::NoData("ZZZZ".to_string()));
+        assert_eq!(no_data.code, "NO_DATA");
+        assert!(!no_data.retryable);
+
+        let wrapped = CommandError::from(anyhow::Error::new(PipelineError::RateLimited("finnhub".to_string())));
+        assert_eq!(wrapped.code, "RATE_LIMITED");
+    }
+}
@@ -3,9 +3,16 @@
 
 use anyhow::{anyhow, Result};
 use reqwest::blocking::Client;
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use chrono::{NaiveDate, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::models::{EarningsEvent, IntradayPrice};
+use crate::ratelimit::{RateLimiter, SOURCE_FINNHUB};
 
 const FINNHUB_API_URL: &str = "https://finnhub.io/api/v1";
 
@@ -98,6 +105,30 @@ pub struct Candles {
     pub status: String,
 }
 
+impl Candles {
+    /// Convert to bars keyed on their full UTC timestamp
+    /// Candles with an out-of-range timestamp are skipped
+    pub fn to_intraday_prices(&self, symbol: &str, resolution: &str) -> Vec<IntradayPrice> {
+        self.timestamp
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &ts)| {
+                Some(IntradayPrice {
+                    symbol: symbol.to_uppercase(),
+                    timestamp: DateTime::from_timestamp(ts, 0)?,
+                    resolution: resolution.to_string(),
+                    open: *self.open.get(i)?,
+                    high: *self.high.get(i)?,
+                    low: *self.low.get(i)?,
+                    close: *self.close.get(i)?,
+                    volume: self.volume.get(i).copied().unwrap_or(0),
+                    source: "finnhub".to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
 /// Price pattern data calculated from candles around an event
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PriceReaction {
@@ -112,10 +143,172 @@ pub struct PriceReaction {
     pub candle_count: usize,
 }
 
+/// Response from Finnhub /calendar/earnings endpoint
+#[derive(Debug, Deserialize)]
+struct EarningsCalendarResponse {
+    #[serde(rename = "earningsCalendar", default)]
+    earnings_calendar: Vec<EarningsCalendarEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EarningsCalendarEntry {
+    symbol: String,
+    date: String,
+    hour: Option<String>,
+    quarter: Option<i32>,
+    year: Option<i32>,
+    eps_estimate: Option<f64>,
+    eps_actual: Option<f64>,
+    revenue_estimate: Option<f64>,
+    revenue_actual: Option<f64>,
+}
+
+/// Convert an earnings calendar response, skipping entries with unparseable dates
+fn parse_earnings(body: &str) -> Result<Vec<EarningsEvent>> {
+    let response: EarningsCalendarResponse = serde_json::from_str(body)?;
+
+    Ok(response
+        .earnings_calendar
+        .into_iter()
+        .filter_map(|e| {
+            let date = NaiveDate::parse_from_str(&e.date, "%Y-%m-%d").ok()?;
+            Some(EarningsEvent {
+                symbol: e.symbol.to_uppercase(),
+                date,
+                hour: e.hour.filter(|h| !h.is_empty()),
+                quarter: e.quarter,
+                year: e.year,
+                eps_estimate: e.eps_estimate,
+                eps_actual: e.eps_actual,
+                revenue_estimate: e.revenue_estimate,
+                revenue_actual: e.revenue_actual,
+            })
+        })
+        .collect())
+}
+
+/// How long cached Finnhub responses stay fresh, per endpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheTtl {
+    pub quote: Duration,
+    pub news: Duration,
+    pub candles: Duration,
+    pub earnings: Duration,
+}
+
+impl Default for CacheTtl {
+    fn default() -> Self {
+        Self {
+            quote: Duration::from_secs(15 * 60),
+            news: Duration::from_secs(6 * 60 * 60),
+            candles: Duration::from_secs(60 * 60),
+            earnings: Duration::from_secs(6 * 60 * 60),
+        }
+    }
+}
+
+/// Response cache keyed by endpoint + query (without the API token).
+/// Always holds entries in memory; with a SQLite file they also survive across clients.
+pub struct FinnhubCache {
+    memory: Mutex<HashMap<String, (i64, String)>>,
+    conn: Option<Mutex<Connection>>,
+}
+
+impl FinnhubCache {
+    /// Cache that lives only as long as this value
+    pub fn in_memory() -> Self {
+        Self {
+            memory: Mutex::new(HashMap::new()),
+            conn: None,
+        }
+    }
+
+    /// Cache persisted to a `finnhub_cache` table in the given SQLite database
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS finnhub_cache (
+                cache_key TEXT PRIMARY KEY,
+                body TEXT NOT NULL,
+                fetched_at INTEGER NOT NULL
+            );
+            "#,
+        )?;
+        Ok(Self {
+            memory: Mutex::new(HashMap::new()),
+            conn: Some(Mutex::new(conn)),
+        })
+    }
+
+    /// Cached body for `key` if fetched less than `ttl` ago
+    fn get(&self, key: &str, ttl: Duration) -> Option<String> {
+        let now = Utc::now().timestamp();
+        let fresh = |fetched_at: i64| now - fetched_at < ttl.as_secs() as i64;
+
+        if let Some((fetched_at, body)) = self.memory.lock().ok()?.get(key) {
+            if fresh(*fetched_at) {
+                return Some(body.clone());
+            }
+        }
+
+        let conn = self.conn.as_ref()?.lock().ok()?;
+        let (fetched_at, body): (i64, String) = conn
+            .query_row(
+                "SELECT fetched_at, body FROM finnhub_cache WHERE cache_key = ?1",
+                params![key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .ok()??;
+        if !fresh(fetched_at) {
+            return None;
+        }
+        if let Ok(mut memory) = self.memory.lock() {
+            memory.insert(key.to_string(), (fetched_at, body.clone()));
+        }
+        Some(body)
+    }
+
+    fn put(&self, key: &str, body: &str) {
+        let now = Utc::now().timestamp();
+        if let Ok(mut memory) = self.memory.lock() {
+            memory.insert(key.to_string(), (now, body.to_string()));
+        }
+        if let Some(Ok(conn)) = self.conn.as_ref().map(|c| c.lock()) {
+            // A failed write only costs a refetch later
+            let _ = conn.execute(
+                "INSERT OR REPLACE INTO finnhub_cache (cache_key, body, fetched_at) VALUES (?1, ?2, ?3)",
+                params![key, body, now],
+            );
+        }
+    }
+
+    /// Drop every cached response; returns how many persisted entries were removed
+    pub fn clear(&self) -> Result<usize> {
+        if let Ok(mut memory) = self.memory.lock() {
+            memory.clear();
+        }
+        match &self.conn {
+            Some(conn) => {
+                let conn = conn.lock().map_err(|e| anyhow!("Cache lock poisoned: {}", e))?;
+                Ok(conn.execute("DELETE FROM finnhub_cache", [])?)
+            }
+            None => Ok(0),
+        }
+    }
+}
+
 /// Finnhub API client
 pub struct FinnhubClient {
     client: Client,
     api_key: String,
+    base_url: String,
+    cache: Arc<FinnhubCache>,
+    ttl: CacheTtl,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl FinnhubClient {
@@ -129,7 +322,72 @@ impl FinnhubClient {
             .timeout(Duration::from_secs(30))
             .build()?;
 
-        Ok(Self { client, api_key })
+        Ok(Self {
+            client,
+            api_key,
+            base_url: FINNHUB_API_URL.to_string(),
+            cache: Arc::new(FinnhubCache::in_memory()),
+            ttl: CacheTtl::default(),
+            rate_limiter: None,
+        })
+    }
+
+    /// Set how long responses are served from the cache (zero disables caching)
+    pub fn with_cache_ttl(mut self, ttl: CacheTtl) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Share a cache between clients, e.g. one persisted with `FinnhubCache::open`
+    pub fn with_cache(mut self, cache: Arc<FinnhubCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Check (and log) every uncached request against a shared rate limiter
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Point the client at a different host (used for testing)
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// GET an endpoint, serving it from the cache when a fresh copy exists
+    fn get_cached(&self, endpoint: &str, query: &[(&str, String)], ttl: Duration, label: &str) -> Result<String> {
+        let params: Vec<String> = query.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        let key = format!("{}?{}", endpoint, params.join("&"));
+
+        if let Some(body) = self.cache.get(&key, ttl) {
+            return Ok(body);
+        }
+
+        if let Some(limiter) = &self.rate_limiter {
+            let symbol = query.iter().find(|(k, _)| *k == "symbol").map_or("", |(_, v)| v.as_str());
+            limiter.acquire(SOURCE_FINNHUB, endpoint.trim_start_matches('/'), symbol)?;
+        }
+
+        let response = self
+            .client
+            .get(format!("{}{}", self.base_url, endpoint))
+            .query(query)
+            .query(&[("token", &self.api_key)])
+            .send()?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().unwrap_or_default();
+            return Err(anyhow!("Finnhub {} error: {} - {}", label, status, body));
+        }
+
+        let body = response.text()?;
+        if !ttl.is_zero() {
+            self.cache.put(&key, &body);
+        }
+        Ok(body)
     }
 
     /// Fetch company news for a symbol
@@ -149,28 +407,18 @@ impl FinnhubClient {
         let to_date = to.unwrap_or(today);
         let from_date = from.unwrap_or_else(|| today - chrono::Duration::days(7));
 
-        let url = format!(
-            "{}/company-news?symbol={}&from={}&to={}&token={}",
-            FINNHUB_API_URL,
-            symbol.to_uppercase(),
-            from_date.format("%Y-%m-%d"),
-            to_date.format("%Y-%m-%d"),
-            self.api_key
-        );
-
-        let response = self.client.get(&url).send()?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().unwrap_or_default();
-            return Err(anyhow!(
-                "Finnhub API error: {} - {}",
-                status,
-                body
-            ));
-        }
-
-        let news: Vec<NewsItem> = response.json()?;
+        let body = self.get_cached(
+            "/company-news",
+            &[
+                ("symbol", symbol.to_uppercase()),
+                ("from", from_date.format("%Y-%m-%d").to_string()),
+                ("to", to_date.format("%Y-%m-%d").to_string()),
+            ],
+            self.ttl.news,
+            "API",
+        )?;
+
+        let news: Vec<NewsItem> = serde_json::from_str(&body)?;
         Ok(news)
     }
 
@@ -204,22 +452,14 @@ impl FinnhubClient {
     /// Fetch current quote for a symbol
     /// GET /quote?symbol=X&token=Y
     pub fn fetch_quote(&self, symbol: &str) -> Result<Quote> {
-        let url = format!(
-            "{}/quote?symbol={}&token={}",
-            FINNHUB_API_URL,
-            symbol.to_uppercase(),
-            self.api_key
-        );
-
-        let response = self.client.get(&url).send()?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().unwrap_or_default();
-            return Err(anyhow!("Finnhub quote error: {} - {}", status, body));
-        }
-
-        let quote: Quote = response.json()?;
+        let body = self.get_cached(
+            "/quote",
+            &[("symbol", symbol.to_uppercase())],
+            self.ttl.quote,
+            "quote",
+        )?;
+
+        let quote: Quote = serde_json::from_str(&body)?;
         Ok(quote)
     }
 
@@ -227,25 +467,19 @@ impl FinnhubClient {
     /// GET /stock/candle?symbol=X&resolution=D&from=T1&to=T2&token=Y
     /// resolution: 1, 5, 15, 30, 60, D, W, M
     pub fn fetch_candles(&self, symbol: &str, resolution: &str, from: i64, to: i64) -> Result<Candles> {
-        let url = format!(
-            "{}/stock/candle?symbol={}&resolution={}&from={}&to={}&token={}",
-            FINNHUB_API_URL,
-            symbol.to_uppercase(),
-            resolution,
-            from,
-            to,
-            self.api_key
-        );
-
-        let response = self.client.get(&url).send()?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response.text().unwrap_or_default();
-            return Err(anyhow!("Finnhub candle error: {} - {}", status, body));
-        }
-
-        let candles: Candles = response.json()?;
+        let body = self.get_cached(
+            "/stock/candle",
+            &[
+                ("symbol", symbol.to_uppercase()),
+                ("resolution", resolution.to_string()),
+                ("from", from.to_string()),
+                ("to", to.to_string()),
+            ],
+            self.ttl.candles,
+            "candle",
+        )?;
+
+        let candles: Candles = serde_json::from_str(&body)?;
 
         if candles.status == "no_data" {
             return Err(anyhow!("No candle data available for {} in the specified range", symbol));
@@ -254,6 +488,23 @@ impl FinnhubClient {
         Ok(candles)
     }
 
+    /// Fetch scheduled and reported earnings for a symbol between two dates
+    /// GET /calendar/earnings?symbol=X&from=YYYY-MM-DD&to=YYYY-MM-DD&token=Y
+    pub fn fetch_earnings(&self, symbol: &str, from: NaiveDate, to: NaiveDate) -> Result<Vec<EarningsEvent>> {
+        let body = self.get_cached(
+            "/calendar/earnings",
+            &[
+                ("symbol", symbol.to_uppercase()),
+                ("from", from.format("%Y-%m-%d").to_string()),
+                ("to", to.format("%Y-%m-%d").to_string()),
+            ],
+            self.ttl.earnings,
+            "earnings",
+        )?;
+
+        parse_earnings(&body)
+    }
+
     /// Fetch candles around an event date and calculate price reaction
     /// Returns price change from 3 days before to 3 days after the event
     pub fn fetch_price_reaction(&self, symbol: &str, event_date: &str, days_window: i64) -> Result<PriceReaction> {
@@ -314,6 +565,11 @@ impl FinnhubClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_http::serve_counting;
+    use std::sync::atomic::Ordering;
+
+    const NEWS_BODY: &str = r#"[{"category":"company","datetime":1767225600,"headline":"Test","id":1,
+        "image":null,"related":"AAPL","source":"Test","summary":"","url":"https://example.com"}]"#;
 
     #[test]
     #[ignore] // Requires API key
@@ -323,4 +579,81 @@ mod tests {
         let news = client.fetch_company_news("AAPL").unwrap();
         assert!(!news.is_empty());
     }
+
+    #[test]
+    fn test_parse_earnings() {
+        let body = r#"{"earningsCalendar":[
+            {"date":"2026-01-29","epsActual":2.4,"epsEstimate":2.35,"hour":"amc","quarter":1,
+             "revenueActual":124300000000,"revenueEstimate":124100000000,"symbol":"AAPL","year":2026},
+            {"date":"2026-04-30","epsActual":null,"epsEstimate":1.62,"hour":"","quarter":2,
+             "revenueActual":null,"revenueEstimate":95000000000,"symbol":"aapl","year":2026},
+            {"date":"not-a-date","symbol":"AAPL"}
+        ]}"#;
+
+        let events = parse_earnings(body).unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].date, NaiveDate::from_ymd_opt(2026, 1, 29).unwrap());
+        assert_eq!(events[0].hour.as_deref(), Some("amc"));
+        assert_eq!(events[0].eps_actual, Some(2.4));
+        assert_eq!(events[0].revenue_estimate, Some(124_100_000_000.0));
+        assert_eq!(events[1].symbol, "AAPL");
+        assert_eq!(events[1].hour, None);
+        assert_eq!(events[1].eps_actual, None);
+        assert_eq!(events[1].quarter, Some(2));
+
+        assert!(parse_earnings("{}").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_news_served_from_cache_within_ttl() {
+        let (url, hits) = serve_counting(NEWS_BODY);
+        let from = NaiveDate::from_ymd_opt(2026, 1, 1);
+        let to = NaiveDate::from_ymd_opt(2026, 1, 7);
+
+        let client = FinnhubClient::new("test".to_string()).unwrap().with_base_url(&url);
+        assert_eq!(client.fetch_company_news_range("AAPL", from, to).unwrap().len(), 1);
+        assert_eq!(client.fetch_company_news_range("aapl", from, to).unwrap().len(), 1);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // Different parameters are a different cache entry
+        client.fetch_company_news_range("MSFT", from, to).unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        let uncached = FinnhubClient::new("test".to_string())
+            .unwrap()
+            .with_base_url(&url)
+            .with_cache_ttl(CacheTtl { news: Duration::ZERO, ..CacheTtl::default() });
+        uncached.fetch_company_news_range("AAPL", from, to).unwrap();
+        uncached.fetch_company_news_range("AAPL", from, to).unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_persisted_cache_shared_between_clients() {
+        let (url, hits) = serve_counting(NEWS_BODY);
+        let path = std::env::temp_dir().join(format!("finnhub_cache_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let from = NaiveDate::from_ymd_opt(2026, 1, 1);
+
+        let first = FinnhubClient::new("test".to_string())
+            .unwrap()
+            .with_base_url(&url)
+            .with_cache(Arc::new(FinnhubCache::open(&path).unwrap()));
+        first.fetch_company_news_range("AAPL", from, from).unwrap();
+
+        let cache = Arc::new(FinnhubCache::open(&path).unwrap());
+        let second = FinnhubClient::new("test".to_string())
+            .unwrap()
+            .with_base_url(&url)
+            .with_cache(cache.clone());
+        second.fetch_company_news_range("AAPL", from, from).unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        assert_eq!(cache.clear().unwrap(), 1);
+        second.fetch_company_news_range("AAPL", from, from).unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        let _ = std::fs::remove_file(&path);
+    }
 }
@@ -10,10 +10,62 @@ use reqwest::blocking::Client;
 use crate::db::Database;
 use crate::error::{PipelineError, Result};
 use crate::models::MacroData;
+use crate::ratelimit::{RateLimiter, SOURCE_FRED};
+use std::sync::Arc;
+
+const BASE_URL: &str = "https://fred.stlouisfed.org";
+
+/// Observation frequency FRED aggregates a series to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Quarterly,
+    Annual,
+}
+
+impl Frequency {
+    /// Short code stored in `macro_data.frequency` (d/w/m/q/a)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "d",
+            Frequency::Weekly => "w",
+            Frequency::Monthly => "m",
+            Frequency::Quarterly => "q",
+            Frequency::Annual => "a",
+        }
+    }
+
+    /// Parse a short code or full name ("m", "monthly", ...)
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "d" | "daily" => Some(Frequency::Daily),
+            "w" | "weekly" => Some(Frequency::Weekly),
+            "m" | "monthly" => Some(Frequency::Monthly),
+            "q" | "quarterly" => Some(Frequency::Quarterly),
+            "a" | "annual" => Some(Frequency::Annual),
+            _ => None,
+        }
+    }
+
+    /// Value of the fredgraph `fq` parameter
+    fn graph_param(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "Daily",
+            Frequency::Weekly => "Weekly, Ending Friday",
+            Frequency::Monthly => "Monthly",
+            Frequency::Quarterly => "Quarterly",
+            Frequency::Annual => "Annual",
+        }
+    }
+}
 
 /// FRED API client
 pub struct Fred {
     client: Client,
+    base_url: String,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl Default for Fred {
@@ -30,26 +82,66 @@ impl Fred {
                 .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64)")
                 .build()
                 .expect("Failed to create HTTP client"),
+            base_url: BASE_URL.to_string(),
+            rate_limiter: None,
         }
     }
 
+    /// Check (and log) every request against a shared rate limiter
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Point the client at a different host (used for testing)
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
     /// Fetch macro data for an indicator
     ///
     /// # Arguments
     /// * `indicator` - FRED series ID (e.g., "GDP", "UNRATE", "DFF", "CPI")
     ///
     /// # Returns
-    /// Vector of macro data records
+    /// Vector of macro data records at the series' native frequency
     pub fn fetch_indicator(&self, indicator: &str) -> Result<Vec<MacroData>> {
+        self.fetch_series(indicator, None, None)
+    }
+
+    /// Fetch an indicator aggregated to the given frequency
+    pub fn fetch_indicator_with_frequency(
+        &self,
+        indicator: &str,
+        frequency: Frequency,
+    ) -> Result<Vec<MacroData>> {
+        self.fetch_series(indicator, Some(frequency), None)
+    }
+
+    fn fetch_series(
+        &self,
+        indicator: &str,
+        frequency: Option<Frequency>,
+        range: Option<(NaiveDate, NaiveDate)>,
+    ) -> Result<Vec<MacroData>> {
         println!("[FETCH] Fetching {} from FRED...", indicator);
 
         // FRED CSV endpoint (no API key required)
-        let url = format!(
-            "https://fred.stlouisfed.org/graph/fredgraph.csv?id={}",
-            indicator
-        );
+        let url = format!("{}/graph/fredgraph.csv", self.base_url);
+        let mut query = vec![("id", indicator.to_string())];
+        if let Some(freq) = frequency {
+            query.push(("fq", freq.graph_param().to_string()));
+        }
+        if let Some((start, end)) = range {
+            query.push(("cosd", start.to_string()));
+            query.push(("coed", end.to_string()));
+        }
 
-        let response = self.client.get(&url).send()?;
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire(SOURCE_FRED, "graph", indicator)?;
+        }
+        let response = self.client.get(&url).query(&query).send()?;
 
         if !response.status().is_success() {
             return Err(PipelineError::NoData(format!(
@@ -60,8 +152,18 @@ impl Fred {
         }
 
         let csv_text = response.text()?;
+        let data = Self::parse_csv(&csv_text, indicator, frequency)?;
 
-        // Parse CSV
+        println!("[OK] Fetched {} records for {}", data.len(), indicator);
+        Ok(data)
+    }
+
+    /// Parse a fredgraph CSV (date column, value column)
+    fn parse_csv(
+        csv_text: &str,
+        indicator: &str,
+        frequency: Option<Frequency>,
+    ) -> Result<Vec<MacroData>> {
         let mut reader = ReaderBuilder::new()
             .has_headers(true)
             .from_reader(csv_text.as_bytes());
@@ -101,20 +203,56 @@ impl Fred {
                 date,
                 value,
                 source: "FRED".to_string(),
+                frequency: frequency.map(|f| f.code().to_string()),
             });
         }
 
-        println!("[OK] Fetched {} records for {}", data.len(), indicator);
         Ok(data)
     }
 
+    fn store(&self, db: &mut Database, indicator: &str, data: &[MacroData]) -> Result<usize> {
+        let count = db.upsert_macro_data_batch(data)?;
+        // With a limiter the request was already logged when it was made
+        if self.rate_limiter.is_none() {
+            db.log_api_call(SOURCE_FRED, "graph", indicator)?;
+        }
+        println!("[OK] Stored {} records for {}", count, indicator);
+        Ok(count)
+    }
+
     /// Fetch and store indicator data directly to database
     pub fn fetch_and_store(&self, db: &mut Database, indicator: &str) -> Result<usize> {
         let data = self.fetch_indicator(indicator)?;
-        let count = db.upsert_macro_data_batch(&data)?;
-        db.log_api_call("FRED", "graph", indicator)?;
-        println!("[OK] Stored {} records for {}", count, indicator);
-        Ok(count)
+        self.store(db, indicator, &data)
+    }
+
+    /// Fetch an indicator at the given frequency and store it tagged with that frequency
+    pub fn fetch_and_store_with_frequency(
+        &self,
+        db: &mut Database,
+        indicator: &str,
+        frequency: Frequency,
+    ) -> Result<usize> {
+        let data = self.fetch_indicator_with_frequency(indicator, frequency)?;
+        self.store(db, indicator, &data)
+    }
+
+    /// Fetch and store only the observations between `start` and `end` (inclusive)
+    pub fn fetch_range(
+        &self,
+        db: &mut Database,
+        indicator: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<usize> {
+        if start > end {
+            return Err(PipelineError::ApiError(format!(
+                "Invalid range for {}: {} is after {}",
+                indicator, start, end
+            )));
+        }
+        let data = self.fetch_series(indicator, None, Some((start, end)))?;
+        self.store(db, indicator, &data)
     }
 
     /// Fetch multiple indicators
@@ -192,6 +330,7 @@ pub mod indicators {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_http::serve;
 
     #[test]
     fn test_fetch_fed_funds() {
@@ -200,4 +339,37 @@ mod tests {
         assert!(!data.is_empty());
         assert_eq!(data[0].indicator, "DFF");
     }
+
+    #[test]
+    fn test_frequencies_stored_separately() {
+        let (url, requests) = serve(vec![
+            (200, "observation_date,DFF\n2026-01-01,4.33\n2026-01-02,4.31\n".to_string()),
+            (200, "observation_date,DFF\n2026-01-01,4.32\n".to_string()),
+        ]);
+        let fred = Fred::new().with_base_url(&url);
+        let mut db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+
+        fred.fetch_and_store_with_frequency(&mut db, "DFF", Frequency::Daily)
+            .unwrap();
+        fred.fetch_and_store_with_frequency(&mut db, "DFF", Frequency::Monthly)
+            .unwrap();
+
+        assert!(requests.recv().unwrap().contains("fq=Daily"));
+        assert!(requests.recv().unwrap().contains("fq=Monthly"));
+
+        let data = db.get_macro_data("DFF").unwrap();
+        assert_eq!(data.len(), 3);
+        let on_first: Vec<_> = data
+            .iter()
+            .filter(|d| d.date == NaiveDate::from_ymd_opt(2026, 1, 1).unwrap())
+            .collect();
+        assert_eq!(on_first.len(), 2);
+        let monthly = on_first
+            .iter()
+            .find(|d| d.frequency.as_deref() == Some("m"))
+            .unwrap();
+        assert_eq!(monthly.value, 4.32);
+        assert!(on_first.iter().any(|d| d.frequency.as_deref() == Some("d")));
+    }
 }
@@ -1,11 +1,15 @@
 //! Technical indicators calculator
 
 use crate::models::{DailyPrice, TechnicalIndicator};
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
 
 /// Calculate RSI (Relative Strength Index)
 /// Period is typically 14
 pub fn calculate_rsi(prices: &[DailyPrice], period: usize) -> Vec<TechnicalIndicator> {
-    if prices.len() < period + 1 {
+    if period == 0 || prices.len() < period + 1 {
         return vec![];
     }
 
@@ -63,7 +67,7 @@ pub fn calculate_rsi(prices: &[DailyPrice], period: usize) -> Vec<TechnicalIndic
 
 /// Calculate SMA (Simple Moving Average)
 pub fn calculate_sma(prices: &[DailyPrice], period: usize) -> Vec<TechnicalIndicator> {
-    if prices.len() < period {
+    if period == 0 || prices.len() < period {
         return vec![];
     }
 
@@ -89,7 +93,7 @@ pub fn calculate_sma(prices: &[DailyPrice], period: usize) -> Vec<TechnicalIndic
 
 /// Calculate EMA (Exponential Moving Average)
 pub fn calculate_ema(prices: &[DailyPrice], period: usize) -> Vec<TechnicalIndicator> {
-    if prices.len() < period {
+    if period == 0 || prices.len() < period {
         return vec![];
     }
 
@@ -122,7 +126,7 @@ pub fn calculate_ema(prices: &[DailyPrice], period: usize) -> Vec<TechnicalIndic
 }
 
 /// Calculate MACD (Moving Average Convergence Divergence)
-/// Returns MACD line, signal line, and histogram
+/// Returns MACD line, signal line, and histogram, each named by all three periods
 pub fn calculate_macd(
     prices: &[DailyPrice],
     fast: usize,
@@ -180,14 +184,14 @@ pub fn calculate_macd(
             indicators.push(TechnicalIndicator {
                 symbol: prices[0].symbol.clone(),
                 date: *date,
-                indicator_name: format!("MACD_SIGNAL_{}", signal),
+                indicator_name: format!("MACD_SIGNAL_{}_{}_{}", fast, slow, signal),
                 value: signal_ema,
             });
 
             indicators.push(TechnicalIndicator {
                 symbol: prices[0].symbol.clone(),
                 date: *date,
-                indicator_name: "MACD_HIST".to_string(),
+                indicator_name: format!("MACD_HIST_{}_{}_{}", fast, slow, signal),
                 value: histogram,
             });
         }
@@ -197,7 +201,10 @@ pub fn calculate_macd(
 }
 
 /// Calculate Bollinger Bands
-/// Returns upper band, middle band (SMA), and lower band
+/// Returns upper band, middle band (SMA), and lower band, plus the derived
+/// %B (close's position within the bands: 0 at the lower, 1 at the upper) and
+/// bandwidth ((upper - lower) / middle). Either is skipped on bars where its
+/// denominator is zero (a flat window, or a zero middle band).
 /// Default: 20-period SMA with 2 standard deviations
 pub fn calculate_bollinger_bands(
     prices: &[DailyPrice],
@@ -252,6 +259,84 @@ pub fn calculate_bollinger_bands(
             indicator_name: format!("BB_LOWER_{}", period),
             value: lower,
         });
+
+        if upper > lower {
+            indicators.push(TechnicalIndicator {
+                symbol: prices[0].symbol.clone(),
+                date: prices[i].date,
+                indicator_name: format!("BB_PERCENT_B_{}", period),
+                value: (prices[i].close - lower) / (upper - lower),
+            });
+        }
+
+        if sma != 0.0 {
+            indicators.push(TechnicalIndicator {
+                symbol: prices[0].symbol.clone(),
+                date: prices[i].date,
+                indicator_name: format!("BB_BANDWIDTH_{}", period),
+                value: (upper - lower) / sma,
+            });
+        }
+    }
+
+    indicators
+}
+
+/// Calculate Keltner Channels
+/// Returns upper, middle (EMA) and lower channel, offset by a multiple of ATR.
+/// The ATR uses the same period as the EMA. Default: 20-period EMA with 2x ATR
+pub fn calculate_keltner(prices: &[DailyPrice], ema_period: usize, atr_mult: f64) -> Vec<TechnicalIndicator> {
+    let atr_by_date: HashMap<NaiveDate, f64> = calculate_atr(prices, ema_period)
+        .into_iter()
+        .map(|ind| (ind.date, ind.value))
+        .collect();
+
+    let mut indicators = Vec::new();
+
+    for ema in calculate_ema(prices, ema_period) {
+        let Some(&atr) = atr_by_date.get(&ema.date) else {
+            continue;
+        };
+        for (band, value) in [
+            ("UPPER", ema.value + atr_mult * atr),
+            ("MIDDLE", ema.value),
+            ("LOWER", ema.value - atr_mult * atr),
+        ] {
+            indicators.push(TechnicalIndicator {
+                symbol: ema.symbol.clone(),
+                date: ema.date,
+                indicator_name: format!("KELTNER_{}_{}", band, ema_period),
+                value,
+            });
+        }
+    }
+
+    indicators
+}
+
+/// Calculate Donchian Channels
+/// Upper = highest high and lower = lowest low of the last `period` bars
+/// (including the current one), middle = their average. Default period is 20
+pub fn calculate_donchian(prices: &[DailyPrice], period: usize) -> Vec<TechnicalIndicator> {
+    if period == 0 || prices.len() < period {
+        return vec![];
+    }
+
+    let mut indicators = Vec::new();
+
+    for i in (period - 1)..prices.len() {
+        let window = &prices[(i + 1 - period)..=i];
+        let upper = window.iter().map(|p| p.high).fold(f64::MIN, f64::max);
+        let lower = window.iter().map(|p| p.low).fold(f64::MAX, f64::min);
+
+        for (band, value) in [("UPPER", upper), ("MIDDLE", (upper + lower) / 2.0), ("LOWER", lower)] {
+            indicators.push(TechnicalIndicator {
+                symbol: prices[0].symbol.clone(),
+                date: prices[i].date,
+                indicator_name: format!("DONCHIAN_{}_{}", band, period),
+                value,
+            });
+        }
     }
 
     indicators
@@ -365,7 +450,70 @@ pub fn calculate_stochastic(
         indicators.push(TechnicalIndicator {
             symbol: prices[0].symbol.clone(),
             date: k_values[i].0,
-            indicator_name: format!("STOCH_D_{}", d_period),
+            indicator_name: format!("STOCH_D_{}_{}", k_period, d_period),
+            value: d,
+        });
+    }
+
+    indicators
+}
+
+/// Calculate Stochastic RSI
+/// Raw = (RSI - Lowest RSI) / (Highest RSI - Lowest RSI) * 100 over `stoch_period` RSI values
+/// %K = SMA of Raw over `k_period`, %D = SMA of %K over `d_period`
+/// Default: 14-period RSI, 14-period stochastic, 3/3 smoothing
+pub fn calculate_stoch_rsi(
+    prices: &[DailyPrice],
+    rsi_period: usize,
+    stoch_period: usize,
+    k_period: usize,
+    d_period: usize,
+) -> Vec<TechnicalIndicator> {
+    if rsi_period == 0 || stoch_period == 0 || k_period == 0 || d_period == 0 {
+        return vec![];
+    }
+    // First %K needs rsi_period bars of changes, then stoch_period RSI values, then k_period raw values
+    let rsi = calculate_rsi(prices, rsi_period);
+    if rsi.len() < stoch_period + k_period - 1 {
+        return vec![];
+    }
+
+    let mut raw = Vec::with_capacity(rsi.len() + 1 - stoch_period);
+    for i in (stoch_period - 1)..rsi.len() {
+        let window = &rsi[(i + 1 - stoch_period)..=i];
+        let lowest = window.iter().map(|r| r.value).fold(f64::INFINITY, f64::min);
+        let highest = window.iter().map(|r| r.value).fold(f64::NEG_INFINITY, f64::max);
+        let range = highest - lowest;
+        let value = if range == 0.0 {
+            50.0 // Neutral if RSI didn't move
+        } else {
+            (rsi[i].value - lowest) / range * 100.0
+        };
+        raw.push((rsi[i].date, value));
+    }
+
+    let mut indicators = Vec::new();
+    let mut k_values = Vec::with_capacity(raw.len() + 1 - k_period);
+    for i in (k_period - 1)..raw.len() {
+        let k = raw[(i + 1 - k_period)..=i].iter().map(|(_, v)| v).sum::<f64>() / k_period as f64;
+        k_values.push((raw[i].0, k));
+        indicators.push(TechnicalIndicator {
+            symbol: prices[0].symbol.clone(),
+            date: raw[i].0,
+            indicator_name: format!("STOCHRSI_K_{}_{}_{}", rsi_period, stoch_period, k_period),
+            value: k,
+        });
+    }
+
+    for i in (d_period - 1)..k_values.len() {
+        let d = k_values[(i + 1 - d_period)..=i].iter().map(|(_, k)| k).sum::<f64>() / d_period as f64;
+        indicators.push(TechnicalIndicator {
+            symbol: prices[0].symbol.clone(),
+            date: k_values[i].0,
+            indicator_name: format!(
+                "STOCHRSI_D_{}_{}_{}_{}",
+                rsi_period, stoch_period, k_period, d_period
+            ),
             value: d,
         });
     }
@@ -711,50 +859,1665 @@ pub fn calculate_roc(prices: &[DailyPrice], period: usize) -> Vec<TechnicalIndic
     indicators
 }
 
-/// Calculate all standard indicators for a symbol
-pub fn calculate_all(prices: &[DailyPrice]) -> Vec<TechnicalIndicator> {
-    let mut all = Vec::new();
+/// EMA of a raw series, seeded with the SMA of its first `period` values
+/// Entry `k` lines up with `values[k + period - 1]`
+fn ema_series(values: &[f64], period: usize) -> Vec<f64> {
+    if period == 0 || values.len() < period {
+        return vec![];
+    }
 
-    // RSI 14
-    all.extend(calculate_rsi(prices, 14));
+    let multiplier = 2.0 / (period as f64 + 1.0);
+    let mut ema = values[..period].iter().sum::<f64>() / period as f64;
+    let mut out = Vec::with_capacity(values.len() - period + 1);
+    out.push(ema);
+    for value in &values[period..] {
+        ema = (value - ema) * multiplier + ema;
+        out.push(ema);
+    }
+    out
+}
 
-    // SMA 20, 50
-    all.extend(calculate_sma(prices, 20));
-    all.extend(calculate_sma(prices, 50));
+/// Calculate TRIX
+/// One-bar percent change of a triple-smoothed EMA of the close; the smoothing
+/// filters out cycles shorter than `period`. Default period is 15
+pub fn calculate_trix(prices: &[DailyPrice], period: usize) -> Vec<TechnicalIndicator> {
+    let closes: Vec<f64> = prices.iter().map(|p| p.close).collect();
+    let triple = ema_series(&ema_series(&ema_series(&closes, period), period), period);
+    if triple.len() < 2 {
+        return vec![];
+    }
 
-    // EMA 12, 26
-    all.extend(calculate_ema(prices, 12));
-    all.extend(calculate_ema(prices, 26));
+    // Each smoothing pass starts period - 1 bars later
+    let offset = 3 * (period - 1);
+    triple
+        .windows(2)
+        .enumerate()
+        .map(|(k, w)| TechnicalIndicator {
+            symbol: prices[0].symbol.clone(),
+            date: prices[k + 1 + offset].date,
+            indicator_name: format!("TRIX_{}", period),
+            value: if w[0] == 0.0 { 0.0 } else { (w[1] - w[0]) / w[0] * 100.0 },
+        })
+        .collect()
+}
 
-    // MACD 12, 26, 9
-    all.extend(calculate_macd(prices, 12, 26, 9));
+/// Calculate the Ultimate Oscillator
+/// Buying pressure over true range, averaged across three timeframes weighted 4:2:1
+/// (shortest heaviest). UO > 70 = overbought, UO < 30 = oversold
+/// Default periods are 7, 14, 28
+pub fn calculate_ultimate_oscillator(
+    prices: &[DailyPrice],
+    short: usize,
+    medium: usize,
+    long: usize,
+) -> Vec<TechnicalIndicator> {
+    let longest = short.max(medium).max(long);
+    if short == 0 || medium == 0 || long == 0 || prices.len() <= longest {
+        return vec![];
+    }
 
-    // Bollinger Bands 20, 2
-    all.extend(calculate_bollinger_bands(prices, 20, 2.0));
+    // Buying pressure and true range from bar 1 on; index j is bar j + 1
+    let (pressure, range): (Vec<f64>, Vec<f64>) = prices
+        .windows(2)
+        .map(|w| {
+            let prev_close = w[0].close;
+            let floor = w[1].low.min(prev_close);
+            (w[1].close - floor, w[1].high.max(prev_close) - floor)
+        })
+        .unzip();
+
+    let average = |end: usize, period: usize| {
+        let start = end + 1 - period;
+        let tr: f64 = range[start..=end].iter().sum();
+        if tr == 0.0 {
+            0.5 // Neutral if the window never moved
+        } else {
+            pressure[start..=end].iter().sum::<f64>() / tr
+        }
+    };
 
-    // ATR 14
-    all.extend(calculate_atr(prices, 14));
+    let mut indicators = Vec::new();
+    for i in longest..prices.len() {
+        let j = i - 1;
+        let uo = 100.0 * (4.0 * average(j, short) + 2.0 * average(j, medium) + average(j, long)) / 7.0;
 
-    // Stochastic 14, 3
-    all.extend(calculate_stochastic(prices, 14, 3));
+        indicators.push(TechnicalIndicator {
+            symbol: prices[0].symbol.clone(),
+            date: prices[i].date,
+            indicator_name: format!("UO_{}_{}_{}", short, medium, long),
+            value: uo,
+        });
+    }
 
-    // OBV
-    all.extend(calculate_obv(prices));
+    indicators
+}
 
-    // ADX 14
-    all.extend(calculate_adx(prices, 14));
+/// Calculate CMF (Chaikin Money Flow)
+/// Volume-weighted average of where each close sits in its bar's range, from -1
+/// (every close on the low) to +1 (every close on the high). Default period is 20
+pub fn calculate_cmf(prices: &[DailyPrice], period: usize) -> Vec<TechnicalIndicator> {
+    if period == 0 || prices.len() < period {
+        return vec![];
+    }
 
-    // Williams %R 14
-    all.extend(calculate_williams_r(prices, 14));
+    // Money flow volume: the close's position in the range scaled by volume
+    let flow_volumes: Vec<f64> = prices
+        .iter()
+        .map(|p| {
+            let range = p.high - p.low;
+            let multiplier = if range == 0.0 {
+                0.0
+            } else {
+                ((p.close - p.low) - (p.high - p.close)) / range
+            };
+            multiplier * p.volume as f64
+        })
+        .collect();
 
-    // CCI 20
-    all.extend(calculate_cci(prices, 20));
+    let mut indicators = Vec::new();
+    for i in (period - 1)..prices.len() {
+        let start = i + 1 - period;
+        let volume: f64 = prices[start..=i].iter().map(|p| p.volume as f64).sum();
+        let cmf = if volume == 0.0 {
+            0.0
+        } else {
+            flow_volumes[start..=i].iter().sum::<f64>() / volume
+        };
 
-    // MFI 14
-    all.extend(calculate_mfi(prices, 14));
+        indicators.push(TechnicalIndicator {
+            symbol: prices[0].symbol.clone(),
+            date: prices[i].date,
+            indicator_name: format!("CMF_{}", period),
+            value: cmf,
+        });
+    }
 
-    // ROC 12
-    all.extend(calculate_roc(prices, 12));
+    indicators
+}
 
-    all
+/// Calculate Aroon Up/Down
+/// Measures how recently the highest high and lowest low of the last `period`
+/// bars occurred: 100 = on this bar, 0 = `period` bars ago.
+/// Up crossing above Down signals a new uptrend. Default period is 25
+pub fn calculate_aroon(prices: &[DailyPrice], period: usize) -> Vec<TechnicalIndicator> {
+    if period == 0 || prices.len() <= period {
+        return vec![];
+    }
+
+    let mut indicators = Vec::new();
+
+    for i in period..prices.len() {
+        let window = &prices[(i - period)..=i];
+
+        // Most recent bar wins ties, so a re-tested extreme counts as fresh
+        let mut high_idx = 0;
+        let mut low_idx = 0;
+        for (j, p) in window.iter().enumerate() {
+            if p.high >= window[high_idx].high {
+                high_idx = j;
+            }
+            if p.low <= window[low_idx].low {
+                low_idx = j;
+            }
+        }
+
+        let aroon_up = 100.0 * high_idx as f64 / period as f64;
+        let aroon_down = 100.0 * low_idx as f64 / period as f64;
+
+        indicators.push(TechnicalIndicator {
+            symbol: prices[0].symbol.clone(),
+            date: prices[i].date,
+            indicator_name: format!("AROON_UP_{}", period),
+            value: aroon_up,
+        });
+        indicators.push(TechnicalIndicator {
+            symbol: prices[0].symbol.clone(),
+            date: prices[i].date,
+            indicator_name: format!("AROON_DOWN_{}", period),
+            value: aroon_down,
+        });
+    }
+
+    indicators
+}
+
+/// Default Parabolic SAR acceleration factor step and cap
+pub const PSAR_AF_STEP: f64 = 0.02;
+pub const PSAR_AF_MAX: f64 = 0.2;
+
+/// Calculate Parabolic SAR
+/// Wilder's stop-and-reverse: the SAR trails the extreme point by an acceleration
+/// factor that grows by `af_step` (up to `af_max`) with each new extreme, and flips to
+/// the prior extreme when price crosses it. Emits `PSAR` plus `PSAR_TREND`
+/// (1 = uptrend, -1 = downtrend) so reversals can be detected.
+pub fn calculate_psar(prices: &[DailyPrice], af_step: f64, af_max: f64) -> Vec<TechnicalIndicator> {
+    if prices.len() < 2 {
+        return vec![];
+    }
+
+    let mut uptrend = prices[1].close >= prices[0].close;
+    let mut af = af_step;
+    let (mut sar, mut ep) = if uptrend {
+        (prices[0].low.min(prices[1].low), prices[0].high.max(prices[1].high))
+    } else {
+        (prices[0].high.max(prices[1].high), prices[0].low.min(prices[1].low))
+    };
+
+    let mut indicators = Vec::new();
+    let mut push = |date: NaiveDate, sar: f64, uptrend: bool| {
+        indicators.push(TechnicalIndicator {
+            symbol: prices[0].symbol.clone(),
+            date,
+            indicator_name: "PSAR".to_string(),
+            value: sar,
+        });
+        indicators.push(TechnicalIndicator {
+            symbol: prices[0].symbol.clone(),
+            date,
+            indicator_name: "PSAR_TREND".to_string(),
+            value: if uptrend { 1.0 } else { -1.0 },
+        });
+    };
+    push(prices[1].date, sar, uptrend);
+
+    for i in 2..prices.len() {
+        let bar = &prices[i];
+        let mut next = sar + af * (ep - sar);
+
+        if uptrend {
+            // SAR may not move into the prior two bars' range
+            next = next.min(prices[i - 1].low).min(prices[i - 2].low);
+            if bar.low < next {
+                uptrend = false;
+                next = ep;
+                ep = bar.low;
+                af = af_step;
+            } else if bar.high > ep {
+                ep = bar.high;
+                af = (af + af_step).min(af_max);
+            }
+        } else {
+            next = next.max(prices[i - 1].high).max(prices[i - 2].high);
+            if bar.high > next {
+                uptrend = true;
+                next = ep;
+                ep = bar.high;
+                af = af_step;
+            } else if bar.low < ep {
+                ep = bar.low;
+                af = (af + af_step).min(af_max);
+            }
+        }
+
+        sar = next;
+        push(bar.date, sar, uptrend);
+    }
+
+    indicators
+}
+
+/// Default Supertrend ATR period and band multiplier
+pub const SUPERTREND_ATR_PERIOD: usize = 10;
+pub const SUPERTREND_MULTIPLIER: f64 = 3.0;
+
+/// Calculate Supertrend
+/// Bands sit `multiplier` ATRs above and below the bar midpoint (high + low) / 2 and
+/// only ratchet toward price: the lower band never falls while price holds above it,
+/// the upper band never rises while price holds below it. A close through the active
+/// band flips the trend. Emits `SUPERTREND_{atr_period}` (the active band: lower in an
+/// uptrend, upper in a downtrend) and `SUPERTREND_DIR_{p}` (1 up, -1 down).
+pub fn calculate_supertrend(prices: &[DailyPrice], atr_period: usize, multiplier: f64) -> Vec<TechnicalIndicator> {
+    let atr_by_date: HashMap<NaiveDate, f64> = calculate_atr(prices, atr_period)
+        .into_iter()
+        .map(|ind| (ind.date, ind.value))
+        .collect();
+
+    let mut indicators = Vec::new();
+    // (final upper, final lower, uptrend, previous close)
+    let mut state: Option<(f64, f64, bool, f64)> = None;
+
+    for bar in prices {
+        let Some(&atr) = atr_by_date.get(&bar.date) else {
+            continue;
+        };
+        let mid = (bar.high + bar.low) / 2.0;
+        let basic_upper = mid + multiplier * atr;
+        let basic_lower = mid - multiplier * atr;
+
+        let (upper, lower, uptrend) = match state {
+            None => (basic_upper, basic_lower, bar.close >= mid),
+            Some((prev_upper, prev_lower, prev_up, prev_close)) => {
+                let upper = if basic_upper < prev_upper || prev_close > prev_upper {
+                    basic_upper
+                } else {
+                    prev_upper
+                };
+                let lower = if basic_lower > prev_lower || prev_close < prev_lower {
+                    basic_lower
+                } else {
+                    prev_lower
+                };
+                let uptrend = if prev_up { bar.close >= lower } else { bar.close > upper };
+                (upper, lower, uptrend)
+            }
+        };
+        state = Some((upper, lower, uptrend, bar.close));
+
+        let row = |name: String, value: f64| TechnicalIndicator {
+            symbol: bar.symbol.clone(),
+            date: bar.date,
+            indicator_name: name,
+            value,
+        };
+        indicators.push(row(
+            format!("SUPERTREND_{}", atr_period),
+            if uptrend { lower } else { upper },
+        ));
+        indicators.push(row(
+            format!("SUPERTREND_DIR_{}", atr_period),
+            if uptrend { 1.0 } else { -1.0 },
+        ));
+    }
+
+    indicators
+}
+
+/// Ichimoku lookbacks: Tenkan-sen, Kijun-sen and Senkou Span B
+pub const ICHIMOKU_TENKAN: usize = 9;
+pub const ICHIMOKU_KIJUN: usize = 26;
+pub const ICHIMOKU_SENKOU_B: usize = 52;
+/// Bars the Senkou spans are shifted forward and the Chikou span backward
+pub const ICHIMOKU_DISPLACEMENT: usize = 26;
+
+/// Whether an indicator series is stored at dates after the bar it was computed from
+/// (the Senkou spans), so its latest row may lie beyond the last price
+pub fn is_projected_indicator(name: &str) -> bool {
+    name.starts_with("ICHIMOKU_SENKOU")
+}
+
+/// Names emitted before every multi-output series carried its periods, and the
+/// period-qualified names that replaced them
+pub const LEGACY_INDICATOR_NAMES: [(&str, &str); 5] = [
+    ("MACD_SIGNAL_9", "MACD_SIGNAL_12_26_9"),
+    ("MACD_HIST", "MACD_HIST_12_26_9"),
+    ("STOCH_D_3", "STOCH_D_14_3"),
+    ("ICHIMOKU_SENKOU_A", "ICHIMOKU_SENKOU_A_9_26"),
+    ("SUPERTREND_DIR", "SUPERTREND_DIR_10"),
+];
+
+/// Current name for an indicator, mapping legacy ambiguous names to their qualified form
+pub fn canonical_indicator_name(name: &str) -> &str {
+    LEGACY_INDICATOR_NAMES
+        .iter()
+        .find(|(legacy, _)| *legacy == name)
+        .map_or(name, |(_, current)| current)
+}
+
+/// Next weekday after `date`
+fn next_weekday(date: NaiveDate) -> NaiveDate {
+    let mut next = date + chrono::Duration::days(1);
+    while matches!(next.weekday(), Weekday::Sat | Weekday::Sun) {
+        next += chrono::Duration::days(1);
+    }
+    next
+}
+
+/// Calculate Ichimoku Cloud
+/// Tenkan (9), Kijun (26) and Senkou B (52) are high/low midpoints; Senkou A is the
+/// Tenkan/Kijun average. Both Senkou spans are shifted 26 bars forward and the Chikou
+/// span is the close shifted 26 bars back.
+///
+/// Senkou values projected past the last bar are stored at synthetic future dates,
+/// stepping forward one weekday per bar (holidays are not skipped). They are
+/// overwritten as real bars arrive.
+pub fn calculate_ichimoku(prices: &[DailyPrice]) -> Vec<TechnicalIndicator> {
+    if prices.len() < ICHIMOKU_TENKAN {
+        return vec![];
+    }
+
+    let midpoint = |end: usize, period: usize| -> Option<f64> {
+        if end + 1 < period {
+            return None;
+        }
+        let window = &prices[end + 1 - period..=end];
+        let high = window.iter().map(|p| p.high).fold(f64::MIN, f64::max);
+        let low = window.iter().map(|p| p.low).fold(f64::MAX, f64::min);
+        Some((high + low) / 2.0)
+    };
+
+    // Dates for bars i + 26, continuing past the end of the data
+    let mut future = Vec::with_capacity(ICHIMOKU_DISPLACEMENT);
+    let mut last = prices[prices.len() - 1].date;
+    for _ in 0..ICHIMOKU_DISPLACEMENT {
+        last = next_weekday(last);
+        future.push(last);
+    }
+    let shifted_date = |i: usize| {
+        let target = i + ICHIMOKU_DISPLACEMENT;
+        prices
+            .get(target)
+            .map(|p| p.date)
+            .unwrap_or_else(|| future[target - prices.len()])
+    };
+
+    let symbol = &prices[0].symbol;
+    let row = |date: NaiveDate, name: String, value: f64| TechnicalIndicator {
+        symbol: symbol.clone(),
+        date,
+        indicator_name: name,
+        value,
+    };
+
+    let mut indicators = Vec::new();
+
+    for i in 0..prices.len() {
+        let tenkan = midpoint(i, ICHIMOKU_TENKAN);
+        let kijun = midpoint(i, ICHIMOKU_KIJUN);
+
+        if let Some(t) = tenkan {
+            indicators.push(row(prices[i].date, format!("ICHIMOKU_TENKAN_{}", ICHIMOKU_TENKAN), t));
+        }
+        if let Some(k) = kijun {
+            indicators.push(row(prices[i].date, format!("ICHIMOKU_KIJUN_{}", ICHIMOKU_KIJUN), k));
+        }
+        if let (Some(t), Some(k)) = (tenkan, kijun) {
+            indicators.push(row(
+                shifted_date(i),
+                format!("ICHIMOKU_SENKOU_A_{}_{}", ICHIMOKU_TENKAN, ICHIMOKU_KIJUN),
+                (t + k) / 2.0,
+            ));
+        }
+        if let Some(b) = midpoint(i, ICHIMOKU_SENKOU_B) {
+            indicators.push(row(
+                shifted_date(i),
+                format!("ICHIMOKU_SENKOU_B_{}", ICHIMOKU_SENKOU_B),
+                b,
+            ));
+        }
+        if i >= ICHIMOKU_DISPLACEMENT {
+            indicators.push(row(
+                prices[i - ICHIMOKU_DISPLACEMENT].date,
+                format!("ICHIMOKU_CHIKOU_{}", ICHIMOKU_DISPLACEMENT),
+                prices[i].close,
+            ));
+        }
+    }
+
+    indicators
+}
+
+/// Trading days per year used to annualize daily volatility
+pub const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
+/// Calculate historical volatility
+/// Sample standard deviation of daily log returns over `window` returns, annualized by √252
+/// Windows longer than the available data produce no values
+pub fn calculate_historical_volatility(prices: &[DailyPrice], window: usize) -> Vec<TechnicalIndicator> {
+    if window < 2 || prices.len() <= window {
+        return vec![];
+    }
+
+    let returns: Vec<f64> = prices
+        .windows(2)
+        .map(|w| {
+            if w[0].close > 0.0 && w[1].close > 0.0 {
+                (w[1].close / w[0].close).ln()
+            } else {
+                0.0
+            }
+        })
+        .collect();
+
+    let mut indicators = Vec::new();
+
+    for i in window..=returns.len() {
+        let slice = &returns[i - window..i];
+        let mean = slice.iter().sum::<f64>() / window as f64;
+        let variance =
+            slice.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (window - 1) as f64;
+
+        indicators.push(TechnicalIndicator {
+            symbol: prices[0].symbol.clone(),
+            // returns[i - 1] is the move into prices[i]
+            date: prices[i].date,
+            indicator_name: format!("HV_{}", window),
+            value: variance.sqrt() * TRADING_DAYS_PER_YEAR.sqrt(),
+        });
+    }
+
+    indicators
+}
+
+/// Calculate VWAP (Volume Weighted Average Price)
+/// Cumulative (typical price * volume) / cumulative volume. Intraday bars
+/// (several bars sharing a date) reset at each new day and yield one value per date,
+/// the VWAP at the day's last bar, since stored indicators are keyed by date; daily
+/// bars run over the whole series. Zero-volume bars carry the previous VWAP forward.
+pub fn calculate_vwap(prices: &[DailyPrice]) -> Vec<TechnicalIndicator> {
+    if prices.is_empty() {
+        return vec![];
+    }
+
+    let intraday = prices.windows(2).any(|w| w[0].date == w[1].date);
+
+    let mut indicators: Vec<TechnicalIndicator> = Vec::new();
+    let mut cum_pv = 0.0;
+    let mut cum_volume = 0.0;
+    let mut vwap: Option<f64> = None;
+
+    for (i, price) in prices.iter().enumerate() {
+        if intraday && i > 0 && price.date != prices[i - 1].date {
+            cum_pv = 0.0;
+            cum_volume = 0.0;
+        }
+
+        let typical_price = (price.high + price.low + price.close) / 3.0;
+        let volume = price.volume.max(0) as f64;
+        cum_pv += typical_price * volume;
+        cum_volume += volume;
+
+        if cum_volume > 0.0 {
+            vwap = Some(cum_pv / cum_volume);
+        }
+
+        // Nothing to report until the first bar with volume
+        let Some(value) = vwap else {
+            continue;
+        };
+
+        match indicators.last_mut() {
+            Some(last) if last.date == price.date => last.value = value,
+            _ => indicators.push(TechnicalIndicator {
+                symbol: prices[0].symbol.clone(),
+                date: price.date,
+                indicator_name: "VWAP".to_string(),
+                value,
+            }),
+        }
+    }
+
+    indicators
+}
+
+/// Heikin-Ashi candles built from raw OHLC
+/// HA close is the bar's OHLC average and HA open the midpoint of the previous HA
+/// open/close (the first bar seeds from its own raw open/close). High and low are
+/// widened to include the HA open/close; volume is passed through unchanged.
+pub fn heikin_ashi(prices: &[DailyPrice]) -> Vec<DailyPrice> {
+    let mut candles: Vec<DailyPrice> = Vec::with_capacity(prices.len());
+
+    for p in prices {
+        let close = (p.open + p.high + p.low + p.close) / 4.0;
+        let open = match candles.last() {
+            Some(prev) => (prev.open + prev.close) / 2.0,
+            None => (p.open + p.close) / 2.0,
+        };
+        candles.push(DailyPrice {
+            open,
+            high: p.high.max(open).max(close),
+            low: p.low.min(open).min(close),
+            close,
+            adjusted_close: None,
+            ..p.clone()
+        });
+    }
+
+    candles
+}
+
+/// Bar size for `resample`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timeframe {
+    /// ISO week (Monday to Sunday)
+    Weekly,
+    /// Calendar month
+    Monthly,
+}
+
+impl Timeframe {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Timeframe::Weekly => "weekly",
+            Timeframe::Monthly => "monthly",
+        }
+    }
+
+    /// Key shared by every date in the same period
+    fn period(&self, date: NaiveDate) -> (i32, u32) {
+        match self {
+            Timeframe::Weekly => {
+                let week = date.iso_week();
+                (week.year(), week.week())
+            }
+            Timeframe::Monthly => (date.year(), date.month()),
+        }
+    }
+}
+
+impl std::str::FromStr for Timeframe {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "weekly" | "week" | "1w" => Ok(Timeframe::Weekly),
+            "monthly" | "month" | "1mo" => Ok(Timeframe::Monthly),
+            other => Err(format!("Invalid timeframe '{}'. Use 'weekly' or 'monthly'", other)),
+        }
+    }
+}
+
+/// Aggregate daily bars (oldest first) into weekly or monthly bars
+/// Each bar takes the period's first open, highest high, lowest low, last close and
+/// summed volume, and is dated on the last trading day seen in the period, so a
+/// partial final period is still emitted and lines up with real price dates.
+/// The output can be passed straight to `calculate_all`.
+pub fn resample(prices: &[DailyPrice], timeframe: Timeframe) -> Vec<DailyPrice> {
+    let mut bars: Vec<DailyPrice> = Vec::new();
+    let mut current: Option<(i32, u32)> = None;
+
+    for p in prices {
+        let period = timeframe.period(p.date);
+        match bars.last_mut() {
+            Some(bar) if current == Some(period) => {
+                bar.date = p.date;
+                bar.high = bar.high.max(p.high);
+                bar.low = bar.low.min(p.low);
+                bar.close = p.close;
+                bar.adjusted_close = p.adjusted_close;
+                bar.volume += p.volume;
+            }
+            _ => {
+                current = Some(period);
+                bars.push(p.clone());
+            }
+        }
+    }
+
+    bars
+}
+
+/// Classic floor-trader pivot levels for one session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PivotPoints {
+    pub pivot: f64,
+    pub r1: f64,
+    pub r2: f64,
+    pub r3: f64,
+    pub s1: f64,
+    pub s2: f64,
+    pub s3: f64,
+}
+
+/// Classic pivot points from the previous session's high, low and close
+/// P = (H + L + C) / 3, R1 = 2P - L, S1 = 2P - H, R2/S2 = P +/- (H - L),
+/// R3 = H + 2(P - L), S3 = L - 2(H - P)
+pub fn pivot_points(prev_high: f64, prev_low: f64, prev_close: f64) -> PivotPoints {
+    let pivot = (prev_high + prev_low + prev_close) / 3.0;
+    let range = prev_high - prev_low;
+    PivotPoints {
+        pivot,
+        r1: 2.0 * pivot - prev_low,
+        r2: pivot + range,
+        r3: prev_high + 2.0 * (pivot - prev_low),
+        s1: 2.0 * pivot - prev_high,
+        s2: pivot - range,
+        s3: prev_low - 2.0 * (prev_high - pivot),
+    }
+}
+
+/// Standard Fibonacci retracement ratios
+pub const FIB_RATIOS: [f64; 5] = [0.236, 0.382, 0.5, 0.618, 0.786];
+
+/// A single Fibonacci retracement level
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FibLevel {
+    pub ratio: f64,
+    pub price: f64,
+}
+
+/// Fibonacci retracements of a swing, measured down from the swing high
+/// (level = high - ratio * (high - low)). For a falling swing, the bounce
+/// level for ratio r is the one listed at 1 - r.
+pub fn fib_levels(swing_high: f64, swing_low: f64) -> Vec<FibLevel> {
+    let range = swing_high - swing_low;
+    FIB_RATIOS
+        .iter()
+        .map(|&ratio| FibLevel {
+            ratio,
+            price: swing_high - ratio * range,
+        })
+        .collect()
+}
+
+/// Minimum number of overlapping daily returns required to correlate two series
+pub const MIN_CORRELATION_OVERLAP: usize = 20;
+
+/// Pairwise Pearson correlation of daily log returns (on adjusted closes)
+/// Each pair is compared over the dates both series have. Pairs with fewer than
+/// `MIN_CORRELATION_OVERLAP` overlapping returns, or a flat series, are NaN.
+pub fn correlation_matrix(series: &[(String, Vec<DailyPrice>)]) -> Vec<Vec<f64>> {
+    let closes: Vec<HashMap<NaiveDate, f64>> = series
+        .iter()
+        .map(|(_, prices)| {
+            prices
+                .iter()
+                .map(|p| (p.date, p.adjusted().close))
+                .filter(|(_, close)| *close > 0.0)
+                .collect()
+        })
+        .collect();
+
+    let n = series.len();
+    let mut matrix = vec![vec![f64::NAN; n]; n];
+
+    for i in 0..n {
+        for j in i..n {
+            let mut dates: Vec<NaiveDate> = closes[i]
+                .keys()
+                .filter(|d| closes[j].contains_key(d))
+                .copied()
+                .collect();
+            dates.sort();
+
+            let returns = |closes: &HashMap<NaiveDate, f64>| -> Vec<f64> {
+                dates
+                    .windows(2)
+                    .map(|w| (closes[&w[1]] / closes[&w[0]]).ln())
+                    .collect()
+            };
+            let a = returns(&closes[i]);
+            let b = returns(&closes[j]);
+
+            let value = if a.len() < MIN_CORRELATION_OVERLAP {
+                f64::NAN
+            } else {
+                pearson(&a, &b)
+            };
+            matrix[i][j] = value;
+            matrix[j][i] = value;
+        }
+    }
+
+    matrix
+}
+
+/// Pearson correlation coefficient (NaN when either side has no variance)
+pub(crate) fn pearson(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len() as f64;
+    let mean_a = a.iter().sum::<f64>() / n;
+    let mean_b = b.iter().sum::<f64>() / n;
+
+    let mut cov = 0.0;
+    let mut var_a = 0.0;
+    let mut var_b = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        cov += (x - mean_a) * (y - mean_b);
+        var_a += (x - mean_a).powi(2);
+        var_b += (y - mean_b).powi(2);
+    }
+
+    if var_a <= f64::EPSILON || var_b <= f64::EPSILON {
+        return f64::NAN;
+    }
+    (cov / (var_a.sqrt() * var_b.sqrt())).clamp(-1.0, 1.0)
+}
+
+/// What to do with a bar whose OHLC has a NaN, infinite, zero or negative value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BadBarPolicy {
+    /// Replace it with a flat, zero-volume bar at the prior valid close
+    #[default]
+    ForwardFill,
+    /// Drop it from the series
+    Skip,
+}
+
+/// Whether every OHLC value of a bar is finite and positive
+fn is_valid_bar(bar: &DailyPrice) -> bool {
+    [bar.open, bar.high, bar.low, bar.close]
+        .iter()
+        .all(|v| v.is_finite() && *v > 0.0)
+}
+
+/// Repair or drop bars with unusable OHLC values (e.g. bad source data)
+/// Bad bars before the first valid one are always dropped, having nothing to fill from.
+/// Borrows the input unchanged when every bar is valid.
+pub fn sanitize_prices(prices: &[DailyPrice], policy: BadBarPolicy) -> Cow<'_, [DailyPrice]> {
+    if prices.iter().all(is_valid_bar) {
+        return Cow::Borrowed(prices);
+    }
+
+    let mut cleaned: Vec<DailyPrice> = Vec::with_capacity(prices.len());
+    let mut last_close: Option<f64> = None;
+    for bar in prices {
+        if is_valid_bar(bar) {
+            last_close = Some(bar.close);
+            cleaned.push(bar.clone());
+            continue;
+        }
+        match (policy, last_close) {
+            (BadBarPolicy::ForwardFill, Some(close)) => {
+                println!("[WARN] {} {}: invalid OHLC, forward-filled from {:.4}", bar.symbol, bar.date, close);
+                cleaned.push(DailyPrice {
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    volume: 0,
+                    adjusted_close: None,
+                    ..bar.clone()
+                });
+            }
+            _ => println!("[WARN] {} {}: invalid OHLC, bar skipped", bar.symbol, bar.date),
+        }
+    }
+    Cow::Owned(cleaned)
+}
+
+/// Lookback periods used by `calculate_all_with_config`
+/// Each period produces its own series, e.g. `SMA_20` and `SMA_200`
+/// Missing fields take their defaults when deserialized
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndicatorConfig {
+    pub rsi_periods: Vec<usize>,
+    pub sma_periods: Vec<usize>,
+    pub ema_periods: Vec<usize>,
+    /// Handling of bars with invalid OHLC values
+    pub bad_bars: BadBarPolicy,
+}
+
+impl Default for IndicatorConfig {
+    fn default() -> Self {
+        Self {
+            rsi_periods: vec![14],
+            sma_periods: vec![20, 50],
+            ema_periods: vec![12, 26],
+            bad_bars: BadBarPolicy::default(),
+        }
+    }
+}
+
+/// Calculate all standard indicators for a symbol
+/// Every series name carries its periods (parameter-free series like OBV and PSAR
+/// excepted), so no two series share a name on the same date
+pub fn calculate_all(prices: &[DailyPrice]) -> Vec<TechnicalIndicator> {
+    calculate_all_with_config(prices, &IndicatorConfig::default())
+}
+
+/// Calculate all standard indicators using the given RSI/SMA/EMA periods
+/// Prices are sanitized first and only finite values are returned
+pub fn calculate_all_with_config(
+    prices: &[DailyPrice],
+    config: &IndicatorConfig,
+) -> Vec<TechnicalIndicator> {
+    let prices = &*sanitize_prices(prices, config.bad_bars);
+    let mut all = Vec::new();
+
+    // RSI
+    for &period in &config.rsi_periods {
+        all.extend(calculate_rsi(prices, period));
+    }
+
+    // SMA
+    for &period in &config.sma_periods {
+        all.extend(calculate_sma(prices, period));
+    }
+
+    // EMA
+    for &period in &config.ema_periods {
+        all.extend(calculate_ema(prices, period));
+    }
+
+    // MACD 12, 26, 9
+    all.extend(calculate_macd(prices, 12, 26, 9));
+
+    // Bollinger Bands 20, 2
+    all.extend(calculate_bollinger_bands(prices, 20, 2.0));
+
+    // Keltner Channels 20, 2
+    all.extend(calculate_keltner(prices, 20, 2.0));
+
+    // Donchian Channels 20
+    all.extend(calculate_donchian(prices, 20));
+
+    // ATR 14
+    all.extend(calculate_atr(prices, 14));
+
+    // Stochastic 14, 3
+    all.extend(calculate_stochastic(prices, 14, 3));
+
+    // Stochastic RSI 14, 14, 3, 3
+    all.extend(calculate_stoch_rsi(prices, 14, 14, 3, 3));
+
+    // OBV
+    all.extend(calculate_obv(prices));
+
+    // ADX 14
+    all.extend(calculate_adx(prices, 14));
+
+    // Williams %R 14
+    all.extend(calculate_williams_r(prices, 14));
+
+    // CCI 20
+    all.extend(calculate_cci(prices, 20));
+
+    // MFI 14
+    all.extend(calculate_mfi(prices, 14));
+
+    // ROC 12
+    all.extend(calculate_roc(prices, 12));
+
+    // TRIX 15
+    all.extend(calculate_trix(prices, 15));
+
+    // Ultimate Oscillator 7, 14, 28
+    all.extend(calculate_ultimate_oscillator(prices, 7, 14, 28));
+
+    // CMF 20
+    all.extend(calculate_cmf(prices, 20));
+
+    // Aroon 25
+    all.extend(calculate_aroon(prices, 25));
+
+    // VWAP
+    all.extend(calculate_vwap(prices));
+
+    // Historical volatility 20
+    all.extend(calculate_historical_volatility(prices, 20));
+
+    // Ichimoku 9, 26, 52
+    all.extend(calculate_ichimoku(prices));
+
+    // Parabolic SAR 0.02, 0.2
+    all.extend(calculate_psar(prices, PSAR_AF_STEP, PSAR_AF_MAX));
+
+    // Supertrend 10, 3
+    all.extend(calculate_supertrend(prices, SUPERTREND_ATR_PERIOD, SUPERTREND_MULTIPLIER));
+
+    // Flat windows can still divide by zero (e.g. a zero high-low range)
+    all.retain(|ind| ind.value.is_finite());
+    all
+}
+
+/// Calculate indicators for caller-supplied bars without storing anything
+/// Bars must be in strictly increasing date order.
+pub fn calculate_adhoc(
+    prices: &[DailyPrice],
+    config: &IndicatorConfig,
+) -> Result<Vec<TechnicalIndicator>, String> {
+    if let Some(w) = prices.windows(2).find(|w| w[1].date <= w[0].date) {
+        return Err(format!(
+            "Prices must be in chronological order: {} follows {}",
+            w[1].date, w[0].date
+        ));
+    }
+    Ok(calculate_all_with_config(prices, config))
+}
+
+/// Fewest daily bars worth calculating indicators for (MACD's slow EMA period)
+pub const MIN_INDICATOR_BARS: usize = 26;
+
+/// Bars of history replayed before the first new bar when extending indicators
+/// whose state isn't recoverable from their last value (RSI/ATR/ADX smoothing,
+/// MACD signal line, rolling windows). Long enough for the smoothing to converge.
+pub const INCREMENTAL_WARMUP_BARS: usize = 250;
+
+/// Extend stored indicator series with values for bars newer than `existing`
+pub fn calculate_incremental(
+    prices: &[DailyPrice],
+    existing: &[TechnicalIndicator],
+) -> Vec<TechnicalIndicator> {
+    calculate_incremental_with_config(prices, existing, &IndicatorConfig::default())
+}
+
+/// Incremental variant of `calculate_all_with_config`
+///
+/// `existing` only needs the latest stored value of each indicator. EMA and OBV
+/// continue exactly from that value; everything else is recomputed over a warm-up
+/// window and trimmed to the new dates. Falls back to a full recompute when nothing
+/// is stored, when the last stored date is no longer in `prices` (gap or rewritten
+/// history), or for intraday bars.
+pub fn calculate_incremental_with_config(
+    prices: &[DailyPrice],
+    existing: &[TechnicalIndicator],
+    config: &IndicatorConfig,
+) -> Vec<TechnicalIndicator> {
+    let prices = &*sanitize_prices(prices, config.bad_bars);
+    let mut latest: HashMap<&str, &TechnicalIndicator> = HashMap::new();
+    for ind in existing {
+        let entry = latest.entry(ind.indicator_name.as_str()).or_insert(ind);
+        if ind.date > entry.date {
+            *entry = ind;
+        }
+    }
+
+    let intraday = prices.windows(2).any(|w| w[0].date == w[1].date);
+    let last_idx = latest
+        .values()
+        .filter(|ind| !is_projected_indicator(&ind.indicator_name))
+        .map(|ind| ind.date)
+        .max()
+        .and_then(|last_date| prices.iter().position(|p| p.date == last_date));
+    let Some(last_idx) = last_idx.filter(|_| !intraday) else {
+        return calculate_all_with_config(prices, config);
+    };
+
+    let last_date = prices[last_idx].date;
+    let new_bars = &prices[last_idx + 1..];
+    if new_bars.is_empty() {
+        return vec![];
+    }
+    let symbol = &prices[0].symbol;
+    let continues = |name: &str| latest.get(name).filter(|prev| prev.date == last_date);
+
+    let mut all = Vec::new();
+    let mut extended: Vec<String> = Vec::new();
+
+    // EMA: the last value is the whole state
+    for &period in &config.ema_periods {
+        let name = format!("EMA_{}", period);
+        let Some(prev) = continues(&name) else {
+            continue;
+        };
+        let multiplier = 2.0 / (period as f64 + 1.0);
+        let mut ema = prev.value;
+        for bar in new_bars {
+            ema = (bar.close - ema) * multiplier + ema;
+            all.push(TechnicalIndicator {
+                symbol: symbol.clone(),
+                date: bar.date,
+                indicator_name: name.clone(),
+                value: ema,
+            });
+        }
+        extended.push(name);
+    }
+
+    // OBV: running total, same up/down rule as calculate_obv
+    if let Some(prev) = continues("OBV") {
+        let mut obv = prev.value;
+        let mut prev_close = prices[last_idx].close;
+        for bar in new_bars {
+            if bar.close > prev_close {
+                obv += bar.volume as f64;
+            } else if bar.close < prev_close {
+                obv -= bar.volume as f64;
+            }
+            prev_close = bar.close;
+            all.push(TechnicalIndicator {
+                symbol: symbol.clone(),
+                date: bar.date,
+                indicator_name: "OBV".to_string(),
+                value: obv,
+            });
+        }
+        extended.push("OBV".to_string());
+    }
+
+    // Cumulative from the first bar, so neither can be replayed from a window
+    let mut from_full_history = calculate_vwap(prices);
+    if !extended.iter().any(|name| name == "OBV") {
+        from_full_history.extend(calculate_obv(prices));
+    }
+
+    let longest_window = config.sma_periods.iter().copied().max().unwrap_or(0) + 1;
+    let start = (last_idx + 1).saturating_sub(INCREMENTAL_WARMUP_BARS.max(longest_window));
+    let replayed = calculate_all_with_config(&prices[start..], config)
+        .into_iter()
+        .filter(|ind| ind.indicator_name != "OBV" && ind.indicator_name != "VWAP");
+
+    all.extend(
+        replayed
+            .chain(from_full_history)
+            .filter(|ind| !extended.contains(&ind.indicator_name))
+            .filter(|ind| {
+                latest
+                    .get(ind.indicator_name.as_str())
+                    .map_or(ind.date > last_date, |prev| ind.date > prev.date)
+            }),
+    );
+
+    all.retain(|ind| ind.value.is_finite());
+    all
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(day: u32, high: f64, low: f64, close: f64, volume: i64) -> DailyPrice {
+        DailyPrice {
+            symbol: "TEST".to_string(),
+            date: NaiveDate::from_ymd_opt(2026, 1, day).unwrap(),
+            open: close,
+            high,
+            low,
+            close,
+            volume,
+            adjusted_close: None,
+            source: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_heikin_ashi_averages() {
+        let prices = vec![
+            DailyPrice { open: 10.0, ..bar(1, 12.0, 9.0, 11.0, 100) },
+            DailyPrice { open: 11.0, ..bar(2, 14.0, 10.5, 13.0, 200) },
+            DailyPrice { open: 13.0, ..bar(3, 13.5, 11.0, 12.0, 300) },
+        ];
+        let ha = heikin_ashi(&prices);
+
+        assert_eq!(ha.len(), 3);
+        // First bar seeds its open from the raw bar
+        assert_eq!(ha[0].open, 10.5);
+        for (candle, raw) in ha.iter().zip(&prices) {
+            assert_eq!(candle.close, (raw.open + raw.high + raw.low + raw.close) / 4.0);
+            assert_eq!(candle.volume, raw.volume);
+            assert!(candle.high >= candle.open.max(candle.close));
+            assert!(candle.low <= candle.open.min(candle.close));
+        }
+        for i in 1..ha.len() {
+            assert_eq!(ha[i].open, (ha[i - 1].open + ha[i - 1].close) / 2.0);
+        }
+    }
+
+    #[test]
+    fn test_vwap_daily_is_cumulative() {
+        let prices = vec![
+            bar(1, 11.0, 9.0, 10.0, 100),
+            bar(2, 21.0, 19.0, 20.0, 300),
+        ];
+
+        let vwap = calculate_vwap(&prices);
+
+        assert_eq!(vwap.len(), 2);
+        assert!((vwap[0].value - 10.0).abs() < 1e-9);
+        // (10 * 100 + 20 * 300) / 400
+        assert!((vwap[1].value - 17.5).abs() < 1e-9);
+        assert_eq!(vwap[1].indicator_name, "VWAP");
+    }
+
+    #[test]
+    fn test_vwap_zero_volume_carries_forward() {
+        let prices = vec![
+            bar(1, 11.0, 9.0, 10.0, 100),
+            bar(2, 51.0, 49.0, 50.0, 0),
+        ];
+
+        let vwap = calculate_vwap(&prices);
+
+        assert_eq!(vwap.len(), 2);
+        assert!((vwap[1].value - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_vwap_intraday_resets_each_day() {
+        let prices = vec![
+            bar(1, 11.0, 9.0, 10.0, 100),
+            bar(1, 21.0, 19.0, 20.0, 100),
+            bar(2, 31.0, 29.0, 30.0, 100),
+        ];
+
+        let vwap = calculate_vwap(&prices);
+
+        // One value per date: the VWAP as of each day's last bar
+        assert_eq!(vwap.len(), 2);
+        assert!((vwap[0].value - 15.0).abs() < 1e-9);
+        assert!((vwap[1].value - 30.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_calculate_all_with_config_distinct_sma_series() {
+        let prices: Vec<DailyPrice> = (1..=30)
+            .map(|d| {
+                let close = 100.0 + d as f64;
+                bar(d, close + 1.0, close - 1.0, close, 1000)
+            })
+            .collect();
+        let config = IndicatorConfig {
+            rsi_periods: vec![9],
+            sma_periods: vec![5, 10],
+            ema_periods: vec![],
+            ..IndicatorConfig::default()
+        };
+
+        let all = calculate_all_with_config(&prices, &config);
+        let count = |name: &str| all.iter().filter(|i| i.indicator_name == name).count();
+
+        assert_eq!(count("SMA_5"), 26);
+        assert_eq!(count("SMA_10"), 21);
+        assert_eq!(count("RSI_9"), 21);
+        assert_eq!(count("SMA_20"), 0);
+        assert!(all.iter().all(|i| !i.indicator_name.starts_with("EMA_")));
+    }
+
+    #[test]
+    fn test_zero_periods_yield_no_series() {
+        let prices: Vec<DailyPrice> = (1..=30).map(|d| bar(d, 101.0, 99.0, 100.0, 1000)).collect();
+        let config = IndicatorConfig {
+            rsi_periods: vec![0],
+            sma_periods: vec![0],
+            ema_periods: vec![0],
+            ..IndicatorConfig::default()
+        };
+
+        let all = calculate_all_with_config(&prices, &config);
+        assert!(all.iter().all(|i| !["RSI_0", "SMA_0", "EMA_0"].contains(&i.indicator_name.as_str())));
+    }
+
+    fn trending_bars(count: i64) -> Vec<DailyPrice> {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        (0..count)
+            .map(|i| {
+                let close = 100.0 + (i as f64 * 0.7).sin() * 5.0 + i as f64 * 0.2;
+                DailyPrice {
+                    date: start + chrono::Duration::days(i),
+                    ..bar(1, close + 1.5, close - 1.5, close, 1000 + i * 10)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_nan_close_produces_only_finite_indicators() {
+        let mut prices = trending_bars(80);
+        prices[40].close = f64::NAN;
+
+        let filled = calculate_all(&prices);
+        assert!(!filled.is_empty());
+        assert!(filled.iter().all(|ind| ind.value.is_finite()));
+        assert!(filled.iter().any(|ind| ind.date == prices[40].date && ind.indicator_name == "SMA_20"));
+
+        let config = IndicatorConfig {
+            bad_bars: BadBarPolicy::Skip,
+            ..IndicatorConfig::default()
+        };
+        let skipped = calculate_all_with_config(&prices, &config);
+        assert!(skipped.iter().all(|ind| ind.value.is_finite()));
+        assert!(!skipped.iter().any(|ind| ind.date == prices[40].date && ind.indicator_name == "SMA_20"));
+    }
+
+    #[test]
+    fn test_calculate_adhoc_returns_sma_and_rejects_unordered_bars() {
+        let prices: Vec<DailyPrice> = (1..=6)
+            .map(|d| {
+                let close = 10.0 * d as f64;
+                bar(d, close + 1.0, close - 1.0, close, 1000)
+            })
+            .collect();
+        let config = IndicatorConfig {
+            rsi_periods: vec![],
+            sma_periods: vec![3],
+            ema_periods: vec![],
+            ..IndicatorConfig::default()
+        };
+
+        let all = calculate_adhoc(&prices, &config).unwrap();
+        let sma: Vec<f64> = all
+            .iter()
+            .filter(|i| i.indicator_name == "SMA_3")
+            .map(|i| i.value)
+            .collect();
+        assert_eq!(sma, vec![20.0, 30.0, 40.0, 50.0]);
+
+        let mut unordered = prices.clone();
+        unordered.swap(2, 3);
+        assert!(calculate_adhoc(&unordered, &config).is_err());
+    }
+
+    #[test]
+    fn test_incremental_adds_one_value_per_indicator() {
+        let prices = trending_bars(80);
+        let existing = calculate_all(&prices[..79]);
+        let new_date = prices[79].date;
+
+        let incremental = calculate_incremental(&prices, &existing);
+
+        let mut names: Vec<&str> = existing.iter().map(|i| i.indicator_name.as_str()).collect();
+        names.sort();
+        names.dedup();
+        assert_eq!(incremental.len(), names.len());
+        // Displaced Ichimoku spans land 26 bars before/after the new bar
+        assert!(incremental
+            .iter()
+            .filter(|i| !i.indicator_name.starts_with("ICHIMOKU_SENKOU"))
+            .filter(|i| !i.indicator_name.starts_with("ICHIMOKU_CHIKOU"))
+            .all(|i| i.date == new_date));
+
+        let full = calculate_all(&prices);
+        for ind in &incremental {
+            let expected = full
+                .iter()
+                .find(|f| f.indicator_name == ind.indicator_name && f.date == ind.date)
+                .unwrap();
+            assert!(
+                (ind.value - expected.value).abs() < 1e-6,
+                "{} diverged from full recompute",
+                ind.indicator_name
+            );
+        }
+    }
+
+    #[test]
+    fn test_incremental_falls_back_on_gap() {
+        let prices = trending_bars(40);
+        let mut existing = calculate_all(&prices[..30]);
+        for ind in &mut existing {
+            ind.date -= chrono::Duration::days(100);
+        }
+
+        assert_eq!(calculate_incremental(&prices, &existing).len(), calculate_all(&prices).len());
+        assert!(calculate_incremental(&prices[..30], &calculate_all(&prices[..30])).is_empty());
+    }
+
+    #[test]
+    fn test_correlation_matrix() {
+        // Log returns vary day to day; the inverted series takes the opposite of each move
+        let series = |symbol: &str, sign: f64, days: u32| -> Vec<DailyPrice> {
+            let mut log_price = 0.0;
+            (1..=days)
+                .map(|d| {
+                    log_price += sign * ((d * 7 % 5) as f64 - 2.0) * 0.01;
+                    let mut p = bar(d, 0.0, 0.0, 100.0 * f64::exp(log_price), 1000);
+                    p.symbol = symbol.to_string();
+                    p
+                })
+                .collect()
+        };
+        let input = vec![
+            ("A".to_string(), series("A", 1.0, 28)),
+            ("B".to_string(), series("B", 1.0, 28)),
+            ("C".to_string(), series("C", -1.0, 28)),
+            ("D".to_string(), series("D", 1.0, 10)),
+        ];
+
+        let matrix = correlation_matrix(&input);
+
+        assert!((matrix[0][0] - 1.0).abs() < 1e-9);
+        assert!((matrix[0][1] - 1.0).abs() < 1e-9);
+        assert!((matrix[0][2] + 1.0).abs() < 1e-9);
+        assert_eq!(matrix[0][2], matrix[2][0]);
+        // Too little overlap with the short series
+        assert!(matrix[0][3].is_nan());
+        assert!(matrix[3][3].is_nan());
+    }
+
+    #[test]
+    fn test_historical_volatility() {
+        let flat: Vec<DailyPrice> = (1..=25).map(|d| bar(d, 10.0, 10.0, 10.0, 100)).collect();
+
+        let hv = calculate_historical_volatility(&flat, 20);
+
+        // 24 returns -> windows ending at returns 20..=24
+        assert_eq!(hv.len(), 5);
+        assert_eq!(hv[0].indicator_name, "HV_20");
+        assert_eq!(hv[0].date, flat[20].date);
+        assert!(hv.iter().all(|v| v.value.abs() < 1e-12));
+
+        assert!(calculate_historical_volatility(&flat, 30).is_empty());
+
+        // Alternating ±r returns: sample std dev of [r, -r, r] is r * sqrt(4/3)
+        let r: f64 = 0.01;
+        let swing: Vec<DailyPrice> = (1..=4)
+            .map(|d| bar(d, 0.0, 0.0, 100.0 * (r * (d % 2) as f64).exp(), 100))
+            .collect();
+        let hv = calculate_historical_volatility(&swing, 3);
+        assert_eq!(hv.len(), 1);
+        let expected = r * (4.0f64 / 3.0).sqrt() * 252f64.sqrt();
+        assert!((hv[0].value - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ichimoku() {
+        let prices = trending_bars(60);
+
+        let ichimoku = calculate_ichimoku(&prices);
+        let series = |name: &str| -> Vec<&TechnicalIndicator> {
+            ichimoku.iter().filter(|i| i.indicator_name == name).collect()
+        };
+
+        // Tenkan is the midpoint of the 9-bar high/low range
+        let tenkan = series("ICHIMOKU_TENKAN_9");
+        assert_eq!(tenkan.len(), 52);
+        for (t, i) in tenkan.iter().zip(8..) {
+            let window = &prices[i - 8..=i];
+            let high = window.iter().map(|p| p.high).fold(f64::MIN, f64::max);
+            let low = window.iter().map(|p| p.low).fold(f64::MAX, f64::min);
+            assert_eq!(t.date, prices[i].date);
+            assert!((t.value - (high + low) / 2.0).abs() < 1e-9);
+        }
+
+        // Senkou spans project past the last bar onto weekdays
+        let senkou_a = series("ICHIMOKU_SENKOU_A_9_26");
+        assert_eq!(senkou_a[0].date, prices[25 + 26].date);
+        let last = senkou_a.last().unwrap().date;
+        assert!(last > prices[59].date);
+        assert!(!matches!(last.weekday(), Weekday::Sat | Weekday::Sun));
+
+        let chikou = series("ICHIMOKU_CHIKOU_26");
+        assert_eq!(chikou[0].date, prices[0].date);
+        assert_eq!(chikou[0].value, prices[26].close);
+    }
+
+    #[test]
+    fn test_psar_trails_uptrend_and_flips() {
+        // 20 rising bars, then 10 falling ones
+        let prices: Vec<DailyPrice> = (0..30)
+            .map(|i| {
+                let close = if i < 20 { 100.0 + i as f64 * 2.0 } else { 138.0 - (i - 19) as f64 * 3.0 };
+                DailyPrice {
+                    date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap() + chrono::Duration::days(i),
+                    ..bar(1, close + 1.0, close - 1.0, close, 1000)
+                }
+            })
+            .collect();
+
+        let psar = calculate_psar(&prices, PSAR_AF_STEP, PSAR_AF_MAX);
+        let sar: Vec<f64> = psar.iter().filter(|i| i.indicator_name == "PSAR").map(|i| i.value).collect();
+        let trend: Vec<f64> = psar
+            .iter()
+            .filter(|i| i.indicator_name == "PSAR_TREND")
+            .map(|i| i.value)
+            .collect();
+        assert_eq!(sar.len(), 29);
+
+        // sar[k] belongs to prices[k + 1]
+        for k in 0..19 {
+            assert_eq!(trend[k], 1.0);
+            assert!(sar[k] < prices[k + 1].low);
+        }
+
+        let flip = trend.iter().position(|&t| t == -1.0).unwrap() + 1;
+        assert!((20..24).contains(&flip), "flipped at bar {}", flip);
+        for k in flip - 1..sar.len() {
+            assert_eq!(trend[k], -1.0);
+            assert!(sar[k] > prices[k + 1].high);
+        }
+    }
+
+    #[test]
+    fn test_supertrend_trails_uptrend_and_flips_on_reversal() {
+        // 30 rising bars, then a sharp 10-bar selloff
+        let prices: Vec<DailyPrice> = (0..40)
+            .map(|i| {
+                let close = if i < 30 { 100.0 + i as f64 * 2.0 } else { 158.0 - (i - 29) as f64 * 6.0 };
+                DailyPrice {
+                    date: NaiveDate::from_ymd_opt(2026, 1, 1).unwrap() + chrono::Duration::days(i),
+                    ..bar(1, close + 1.0, close - 1.0, close, 1000)
+                }
+            })
+            .collect();
+
+        let st = calculate_supertrend(&prices, 10, 3.0);
+        let by_date = |name: &str| -> HashMap<NaiveDate, f64> {
+            st.iter()
+                .filter(|i| i.indicator_name == name)
+                .map(|i| (i.date, i.value))
+                .collect()
+        };
+        let line = by_date("SUPERTREND_10");
+        let dir = by_date("SUPERTREND_DIR_10");
+        assert_eq!(line.len(), 30);
+
+        for p in &prices[10..30] {
+            assert_eq!(dir[&p.date], 1.0);
+            assert!(line[&p.date] < p.low, "line above price on {}", p.date);
+        }
+        let flip = prices
+            .iter()
+            .position(|p| dir.get(&p.date) == Some(&-1.0))
+            .unwrap();
+        assert!((30..34).contains(&flip), "flipped at bar {}", flip);
+        let last = prices.last().unwrap();
+        assert_eq!(dir[&last.date], -1.0);
+        assert!(line[&last.date] > last.high);
+    }
+
+    #[test]
+    fn test_donchian_upper_is_window_high() {
+        let prices = trending_bars(60);
+        let donchian = calculate_donchian(&prices, 20);
+        let upper: Vec<&TechnicalIndicator> = donchian
+            .iter()
+            .filter(|i| i.indicator_name == "DONCHIAN_UPPER_20")
+            .collect();
+        assert_eq!(upper.len(), 41);
+
+        for (offset, ind) in upper.iter().enumerate() {
+            let window = &prices[offset..offset + 20];
+            let max_high = window.iter().map(|p| p.high).fold(f64::MIN, f64::max);
+            assert_eq!(ind.date, window[19].date);
+            assert_eq!(ind.value, max_high);
+        }
+
+        let keltner = calculate_keltner(&prices, 20, 2.0);
+        let band = |name: &str| keltner.iter().find(|i| i.indicator_name == name).unwrap().value;
+        assert!(band("KELTNER_UPPER_20") > band("KELTNER_MIDDLE_20"));
+        let midpoint = (band("KELTNER_UPPER_20") + band("KELTNER_LOWER_20")) / 2.0;
+        assert!((midpoint - band("KELTNER_MIDDLE_20")).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_bollinger_percent_b_at_the_bands() {
+        // With a 2-bar window and 1 std dev the bands sit exactly on the two closes
+        let up = [bar(1, 10.0, 10.0, 10.0, 100), bar(2, 12.0, 12.0, 12.0, 100)];
+        let down = [bar(1, 12.0, 12.0, 12.0, 100), bar(2, 10.0, 10.0, 10.0, 100)];
+        let value = |bb: &[TechnicalIndicator], name: &str| {
+            bb.iter().find(|i| i.indicator_name == name).unwrap().value
+        };
+
+        let bb = calculate_bollinger_bands(&up, 2, 1.0);
+        assert_eq!(value(&bb, "BB_UPPER_2"), 12.0);
+        assert_eq!(value(&bb, "BB_PERCENT_B_2"), 1.0);
+        assert!((value(&bb, "BB_BANDWIDTH_2") - 2.0 / 11.0).abs() < 1e-12);
+
+        let bb = calculate_bollinger_bands(&down, 2, 1.0);
+        assert_eq!(value(&bb, "BB_LOWER_2"), 10.0);
+        assert_eq!(value(&bb, "BB_PERCENT_B_2"), 0.0);
+
+        // Flat window: zero-width bands have no %B; zero middle band has no bandwidth
+        let flat = [bar(1, 0.0, 0.0, 0.0, 100), bar(2, 0.0, 0.0, 0.0, 100)];
+        let bb = calculate_bollinger_bands(&flat, 2, 1.0);
+        assert!(bb.iter().all(|i| !i.indicator_name.starts_with("BB_PERCENT_B")
+            && !i.indicator_name.starts_with("BB_BANDWIDTH")));
+    }
+
+    #[test]
+    fn test_pivot_points_classic_formulas() {
+        let levels = pivot_points(110.0, 90.0, 105.0);
+        let pivot = (110.0 + 90.0 + 105.0) / 3.0;
+
+        assert!((levels.pivot - pivot).abs() < 1e-9);
+        assert!((levels.r1 - (2.0 * pivot - 90.0)).abs() < 1e-9);
+        assert!((levels.s1 - (2.0 * pivot - 110.0)).abs() < 1e-9);
+        assert!((levels.r2 - (pivot + 20.0)).abs() < 1e-9);
+        assert!((levels.s2 - (pivot - 20.0)).abs() < 1e-9);
+        assert!(levels.s3 < levels.s2 && levels.s2 < levels.s1 && levels.s1 < levels.pivot);
+        assert!(levels.pivot < levels.r1 && levels.r1 < levels.r2 && levels.r2 < levels.r3);
+    }
+
+    #[test]
+    fn test_fib_levels_golden_ratio() {
+        let levels = fib_levels(200.0, 100.0);
+
+        assert_eq!(levels.len(), FIB_RATIOS.len());
+        let golden = levels.iter().find(|l| l.ratio == 0.618).unwrap();
+        assert!((golden.price - 138.2).abs() < 1e-9);
+        let half = levels.iter().find(|l| l.ratio == 0.5).unwrap();
+        assert_eq!(half.price, 150.0);
+    }
+
+    #[test]
+    fn test_trix_of_a_straight_line() {
+        // With period 2 each EMA pass trails the one before by half a bar, so the
+        // triple EMA reads 2.5, 3.5, 4.5 on days 4-6
+        let prices: Vec<DailyPrice> = (1..=6).map(|d| bar(d, d as f64, d as f64, d as f64, 100)).collect();
+        let trix = calculate_trix(&prices, 2);
+
+        assert_eq!(trix.len(), 2);
+        assert_eq!(trix[0].indicator_name, "TRIX_2");
+        assert_eq!(trix[0].date, prices[4].date);
+        assert!((trix[0].value - 40.0).abs() < 1e-9);
+        assert!((trix[1].value - 100.0 / 3.5).abs() < 1e-9);
+
+        assert!(calculate_trix(&prices[..4], 2).is_empty());
+        assert!(calculate_trix(&[], 15).is_empty());
+    }
+
+    #[test]
+    fn test_stoch_rsi_k_by_hand() {
+        let closes = [10.0, 11.0, 10.5, 11.5, 12.0, 11.0, 12.5];
+        let prices: Vec<DailyPrice> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| bar(i as u32 + 1, c, c, c, 100))
+            .collect();
+        let stoch_rsi = calculate_stoch_rsi(&prices, 2, 3, 2, 2);
+        let series = |name: &str| -> Vec<f64> {
+            stoch_rsi.iter().filter(|i| i.indicator_name == name).map(|i| i.value).collect()
+        };
+
+        // RSI_2 reads 2/3, 6/7, 10/11, 10/27, 58/75 (x100). Over 3-value windows the
+        // last three place at 100, 0 and (58/75 - 10/27) / (10/11 - 10/27) = 74.8
+        let k = series("STOCHRSI_K_2_3_2");
+        assert_eq!(k.len(), 2);
+        assert!((k[0] - 50.0).abs() < 1e-9);
+        assert!((k[1] - 37.4).abs() < 1e-9);
+        let d = series("STOCHRSI_D_2_3_2_2");
+        assert_eq!(d.len(), 1);
+        assert!((d[0] - 43.7).abs() < 1e-9);
+
+        // Six bars are the minimum for one %K (no %D yet); one fewer gives nothing
+        let short = calculate_stoch_rsi(&prices[..6], 2, 3, 2, 2);
+        assert_eq!(short.len(), 1);
+        assert_eq!(short[0].indicator_name, "STOCHRSI_K_2_3_2");
+        assert!(calculate_stoch_rsi(&prices[..5], 2, 3, 2, 2).is_empty());
+        assert!(calculate_stoch_rsi(&prices, 14, 14, 3, 3).is_empty());
+    }
+
+    #[test]
+    fn test_ultimate_oscillator_weights_three_windows() {
+        let prices = vec![
+            bar(1, 10.0, 10.0, 10.0, 100),
+            bar(2, 12.0, 9.0, 11.0, 100),  // pressure 2, range 3
+            bar(3, 13.0, 10.0, 12.0, 100), // pressure 2, range 3
+            bar(4, 12.0, 10.0, 10.0, 100), // pressure 0, range 2
+        ];
+        let uo = calculate_ultimate_oscillator(&prices, 1, 2, 3);
+
+        // Averages 0/2, 2/5 and 4/8, weighted 4:2:1
+        assert_eq!(uo.len(), 1);
+        assert_eq!(uo[0].indicator_name, "UO_1_2_3");
+        assert!((uo[0].value - 100.0 * (2.0 * 0.4 + 0.5) / 7.0).abs() < 1e-9);
+
+        assert!(calculate_ultimate_oscillator(&prices[..3], 1, 2, 3).is_empty());
+        assert!(calculate_ultimate_oscillator(&prices, 7, 14, 28).is_empty());
+    }
+
+    #[test]
+    fn test_cmf_weights_close_location_by_volume() {
+        let prices = vec![
+            bar(1, 10.0, 8.0, 9.5, 100), // multiplier 0.5
+            bar(2, 11.0, 9.0, 9.5, 300), // multiplier -0.5
+            bar(3, 12.0, 10.0, 12.0, 200), // multiplier 1
+        ];
+        let cmf = calculate_cmf(&prices, 2);
+
+        assert_eq!(cmf.len(), 2);
+        assert_eq!(cmf[0].indicator_name, "CMF_2");
+        assert!((cmf[0].value - (50.0 - 150.0) / 400.0).abs() < 1e-9);
+        assert!((cmf[1].value - (-150.0 + 200.0) / 500.0).abs() < 1e-9);
+
+        assert!(calculate_cmf(&prices, 20).is_empty());
+        assert!(calculate_cmf(&prices, 0).is_empty());
+    }
+
+    #[test]
+    fn test_resample_weekly_collapses_one_week() {
+        // 2026-01-05 is a Monday; the following Monday starts a partial week
+        let week = vec![
+            DailyPrice { open: 100.0, ..bar(5, 102.0, 99.0, 101.0, 1000) },
+            DailyPrice { open: 101.0, ..bar(6, 105.0, 100.0, 104.0, 1100) },
+            DailyPrice { open: 104.0, ..bar(7, 106.0, 98.0, 99.0, 1200) },
+            DailyPrice { open: 99.0, ..bar(8, 103.0, 97.5, 102.0, 1300) },
+            DailyPrice { open: 102.0, ..bar(9, 104.0, 101.0, 103.5, 1400) },
+        ];
+        let weekly = resample(&week, Timeframe::Weekly);
+
+        assert_eq!(weekly.len(), 1);
+        let w = &weekly[0];
+        assert_eq!(w.date, NaiveDate::from_ymd_opt(2026, 1, 9).unwrap());
+        assert_eq!((w.open, w.high, w.low, w.close), (100.0, 106.0, 97.5, 103.5));
+        assert_eq!(w.volume, 6000);
+
+        let mut with_partial = week.clone();
+        with_partial.push(DailyPrice { open: 104.0, ..bar(12, 107.0, 103.0, 106.0, 900) });
+        let weekly = resample(&with_partial, Timeframe::Weekly);
+        assert_eq!(weekly.len(), 2);
+        assert_eq!((weekly[1].open, weekly[1].close, weekly[1].volume), (104.0, 106.0, 900));
+        assert_eq!(resample(&with_partial, Timeframe::Monthly).len(), 1);
+    }
 }
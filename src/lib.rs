@@ -41,34 +41,51 @@ pub mod claude;
 pub mod finnhub;
 pub mod ollama;
 pub mod ai_trader;
+pub mod search;
+pub mod ratelimit;
+pub mod webhook;
+pub mod report;
+#[cfg(test)]
+mod test_http;
 
 // Re-exports for convenience
-pub use db::Database;
-pub use error::{PipelineError, Result};
-pub use fred::Fred;
+pub use db::{Database, DatabaseManager, DatabaseOptions, DatabasePool, PORTFOLIO_TEAM, PRUNABLE_TABLES, SNAPSHOT_TEAMS, STOP_ATR_PERIOD, CONFIG_EXPORT_VERSION};
+pub use error::{CommandError, PipelineError, Result};
+pub use fred::{Fred, Frequency};
+pub use ratelimit::{RateLimit, RateLimiter};
+pub use webhook::{sign_payload, WebhookDispatcher};
+pub use report::{write_backtest_report, write_portfolio_report, HoldingLine, REPORT_ROWS_PER_PAGE};
 pub use indicators::{
-    calculate_adx, calculate_all, calculate_atr, calculate_bollinger_bands, calculate_cci,
-    calculate_ema, calculate_macd, calculate_mfi, calculate_obv, calculate_roc, calculate_rsi,
-    calculate_sma, calculate_stochastic, calculate_williams_r,
+    calculate_adhoc, calculate_adx, calculate_all, calculate_aroon, calculate_all_with_config, calculate_atr, calculate_bollinger_bands, calculate_cci, calculate_cmf, calculate_donchian,
+    calculate_ema, calculate_incremental, calculate_incremental_with_config, calculate_macd, calculate_mfi, calculate_obv, calculate_roc, calculate_rsi,
+    calculate_historical_volatility, calculate_ichimoku, calculate_keltner, calculate_sma, calculate_stochastic, calculate_stoch_rsi, calculate_supertrend, calculate_trix, calculate_ultimate_oscillator, calculate_vwap, calculate_williams_r, canonical_indicator_name, correlation_matrix,
+    fib_levels, heikin_ashi, is_projected_indicator, pivot_points, resample, sanitize_prices, BadBarPolicy, FibLevel, IndicatorConfig, PivotPoints,
+    Timeframe, FIB_RATIOS, LEGACY_INDICATOR_NAMES, MIN_INDICATOR_BARS,
 };
 pub use models::{
-    AlertCondition, BacktestResult, BacktestTrade, DailyPrice, IndicatorAlert,
+    AlertCondition, AllocationBucket, AssetClass, BacktestResult, BacktestTrade, DailyPrice, Dividend, IndicatorAlert, IntradayPrice,
     IndicatorAlertCondition, IndicatorAlertType, MacroData, PerformanceMetrics, Position,
     PositionType, PriceAlert, Signal, SignalDirection, SignalType, Strategy,
-    StrategyConditionType, Symbol, TechnicalIndicator, TradeDirection, Watchlist,
-    PaperWallet, PaperPosition, PaperTrade, PaperTradeAction,
+    StrategyConditionType, Symbol, SymbolMatch, TechnicalIndicator, IndicatorRunSummary, TradeDirection, Watchlist,
+    PortfolioBacktestResult, SymbolContribution, WalkForwardResult, Condition, StrategyRule,
+    PaperWallet, PaperPosition, PaperTrade, PaperTradeAction, PaperOrder, PaperOrderStatus, PaperOrderType, CostBasisMethod, TradeLogEntry,
+    Conversation, ConversationMessage, DataFreshness, EarningsEvent, PortfolioAllocation, DuplicateSymbol, PriceStats, PrunedTable, StopSuggestion, RebalanceTrade, RefreshSettings,
+    Webhook, WebhookEvent, AuditLogEntry, normalize_symbol,
 };
-pub use backtest::{BacktestConfig, BacktestEngine};
-pub use signals::{SignalConfig, SignalEngine};
+pub use backtest::{
+    condition_met, rule_met, BacktestConfig, BacktestEngine, MonteCarloResult, OptimizationObjective, OptimizationResult,
+    ParamGrid, Percentiles, MAX_OPTIMIZATION_COMBINATIONS, MONTE_CARLO_RUIN_FRACTION,
+};
+pub use signals::{find_unfilled_gaps, PriceGap, SignalConfig, SignalEngine, SignalWeights};
 pub use trends::{GoogleTrends, TrendData};
-pub use vectors::{VectorStore, MarketEvent, PricePattern, SearchResult as VectorSearchResult, ChatMessage, ChatResponse};
-pub use yahoo::YahooFinance;
-pub use claude::{ClaudeClient, ClaudeMessage, ChatResult, FinancialContext, PriceContext};
-pub use finnhub::{FinnhubClient, NewsItem, SimpleNewsItem, Quote, Candles, PriceReaction};
-pub use ai_trader::AiTrader;
+pub use vectors::{event_id, VectorStore, Embedder, HashEmbedder, OllamaEmbedder, MarketEvent, PricePattern, SearchResult as VectorSearchResult, SearchFilter as VectorSearchFilter, ChatMessage, ChatResponse};
+pub use yahoo::{PriceFetcher, YahooFinance};
+pub use claude::{ClaudeClient, ClaudeMessage, ChatResult, FinancialContext, PriceContext, estimate_claude_cost, estimate_tokens, trim_history};
+pub use finnhub::{CacheTtl, FinnhubCache, FinnhubClient, NewsItem, SimpleNewsItem, Quote, Candles, PriceReaction};
+pub use ai_trader::{AiTrader, AiReplayResult, DecisionSource, GuardrailEngine, GuardrailInputs, TradeRejection, DecisionProvider, Clock, SystemClock, FixedClock, ModelDecisions, ScriptedDecisions, risk_sized_quantity};
 pub use models::{
     AiTraderConfig, AiTradingSession, AiTradeDecision, AiPerformanceSnapshot,
     AiPredictionAccuracy, AiTraderStatus, BenchmarkComparison, CompoundingForecast,
     // DC Trader types
-    DcWallet, DcPosition, DcTrade, PortfolioSnapshot, TeamConfig, ImportResult, CompetitionStats,
+    DcWallet, DcPosition, DcTrade, PortfolioSnapshot, TeamConfig, TradeFees, ImportResult, CompetitionStats, ConfigExport, WatchlistConfig,
 };
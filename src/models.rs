@@ -1,6 +1,6 @@
 //! Data models for Financial Pipeline
 
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
 /// Stock symbol metadata
@@ -18,6 +18,91 @@ pub struct Symbol {
     pub asset_class: Option<String>,
 }
 
+/// Canonical form of a user- or file-supplied ticker: trimmed, `$` prefix
+/// dropped, uppercased. `None` if anything outside `A-Z0-9.-=^` remains.
+pub fn normalize_symbol(raw: &str) -> Option<String> {
+    let trimmed = raw.trim();
+    let symbol = trimmed.strip_prefix('$').unwrap_or(trimmed).to_uppercase();
+    let valid = !symbol.is_empty()
+        && symbol
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '=' | '^'));
+    valid.then_some(symbol)
+}
+
+/// Broad instrument type stored in `Symbol::asset_class`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AssetClass {
+    Equity,
+    Etf,
+    MutualFund,
+    Crypto,
+    Forex,
+    Index,
+    Future,
+}
+
+/// Quote currencies Yahoo uses for crypto pairs (`BTC-USD`, `ETH-BTC`, ...)
+const CRYPTO_QUOTE_CURRENCIES: &[&str] = &["USD", "EUR", "GBP", "JPY", "CAD", "AUD", "USDT", "BTC", "ETH"];
+
+impl AssetClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AssetClass::Equity => "Equity",
+            AssetClass::Etf => "ETF",
+            AssetClass::MutualFund => "Mutual Fund",
+            AssetClass::Crypto => "Crypto",
+            AssetClass::Forex => "Forex",
+            AssetClass::Index => "Index",
+            AssetClass::Future => "Future",
+        }
+    }
+
+    /// Map Yahoo's quoteType (EQUITY, CRYPTOCURRENCY, ...) to an asset class
+    pub fn from_quote_type(quote_type: &str) -> Option<Self> {
+        match quote_type {
+            "EQUITY" => Some(AssetClass::Equity),
+            "ETF" => Some(AssetClass::Etf),
+            "MUTUALFUND" => Some(AssetClass::MutualFund),
+            "CRYPTOCURRENCY" => Some(AssetClass::Crypto),
+            "CURRENCY" => Some(AssetClass::Forex),
+            "INDEX" => Some(AssetClass::Index),
+            "FUTURE" => Some(AssetClass::Future),
+            _ => None,
+        }
+    }
+
+    /// Infer the asset class from Yahoo's symbol conventions: `EURUSD=X` is forex,
+    /// `BTC-USD` crypto, `^GSPC` an index and `CL=F` a future. Anything else
+    /// (including share classes like `BRK-B`) is treated as an equity.
+    pub fn from_symbol(symbol: &str) -> Self {
+        let symbol = symbol.trim().to_uppercase();
+        if symbol.starts_with('^') {
+            return AssetClass::Index;
+        }
+        if symbol.ends_with("=X") {
+            return AssetClass::Forex;
+        }
+        if symbol.ends_with("=F") {
+            return AssetClass::Future;
+        }
+        match symbol.rsplit_once('-') {
+            Some((base, quote)) if !base.is_empty() && CRYPTO_QUOTE_CURRENCIES.contains(&quote) => {
+                AssetClass::Crypto
+            }
+            _ => AssetClass::Equity,
+        }
+    }
+}
+
+/// Ranked symbol search hit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolMatch {
+    pub symbol: String,
+    pub name: Option<String>,
+    pub score: f64,
+}
+
 /// Daily price data (OHLCV)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DailyPrice {
@@ -28,6 +113,43 @@ pub struct DailyPrice {
     pub low: f64,
     pub close: f64,
     pub volume: i64,
+    #[serde(default)]
+    pub adjusted_close: Option<f64>, // split/dividend adjusted close, when the source provides it
+    pub source: String,
+}
+
+impl DailyPrice {
+    /// Return a copy with OHLC scaled to the adjusted close
+    /// Bars without an adjusted close are returned unchanged
+    pub fn adjusted(&self) -> DailyPrice {
+        let factor = match self.adjusted_close {
+            Some(adj) if self.close != 0.0 => adj / self.close,
+            _ => return self.clone(),
+        };
+
+        DailyPrice {
+            open: self.open * factor,
+            high: self.high * factor,
+            low: self.low * factor,
+            close: self.close * factor,
+            ..self.clone()
+        }
+    }
+}
+
+/// Intraday price bar (OHLCV) at a sub-daily resolution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntradayPrice {
+    pub symbol: String,
+    /// Bar open time
+    pub timestamp: DateTime<Utc>,
+    /// Finnhub resolution code: 1, 5, 15, 30 or 60 minutes
+    pub resolution: String,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: i64,
     pub source: String,
 }
 
@@ -38,6 +160,31 @@ pub struct MacroData {
     pub date: NaiveDate,
     pub value: f64,
     pub source: String,
+    /// FRED frequency code (d/w/m/q/a), or None for the series' native frequency
+    pub frequency: Option<String>,
+}
+
+/// Scheduled or reported earnings release
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EarningsEvent {
+    pub symbol: String,
+    pub date: NaiveDate,
+    /// "bmo" (before market open), "amc" (after market close) or "dmh" (during market hours)
+    pub hour: Option<String>,
+    pub quarter: Option<i32>,
+    pub year: Option<i32>,
+    pub eps_estimate: Option<f64>,
+    pub eps_actual: Option<f64>,
+    pub revenue_estimate: Option<f64>,
+    pub revenue_actual: Option<f64>,
+}
+
+/// Cash dividend paid per share, keyed by ex-dividend date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dividend {
+    pub symbol: String,
+    pub ex_date: NaiveDate,
+    pub amount: f64,
 }
 
 /// Watchlist definition
@@ -67,11 +214,63 @@ pub struct TechnicalIndicator {
     pub value: f64,
 }
 
+/// Outcome for one symbol of a bulk indicator run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorRunSummary {
+    pub symbol: String,
+    /// Daily bars the symbol has stored
+    pub bars: usize,
+    /// Indicator values written (0 when already up to date)
+    pub indicators: usize,
+    /// Too little history to calculate anything
+    pub skipped: bool,
+}
+
 /// Price alert condition
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum AlertCondition {
     Above,
     Below,
+    /// Close rises at least this many percent versus the previous bar
+    PercentChangeUp(f64),
+    /// Close falls at least this many percent versus the previous bar
+    PercentChangeDown(f64),
+    /// Volume is at least this multiple of the previous bar's volume
+    VolumeMultiple(f64),
+}
+
+impl AlertCondition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertCondition::Above => "above",
+            AlertCondition::Below => "below",
+            AlertCondition::PercentChangeUp(_) => "pct_up",
+            AlertCondition::PercentChangeDown(_) => "pct_down",
+            AlertCondition::VolumeMultiple(_) => "volume_multiple",
+        }
+    }
+
+    /// Parse a condition string; `value` is the percent or multiple for relative conditions
+    pub fn from_str(s: &str, value: f64) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "above" => Some(AlertCondition::Above),
+            "below" => Some(AlertCondition::Below),
+            "pct_up" => Some(AlertCondition::PercentChangeUp(value)),
+            "pct_down" => Some(AlertCondition::PercentChangeDown(value)),
+            "volume_multiple" => Some(AlertCondition::VolumeMultiple(value)),
+            _ => None,
+        }
+    }
+
+    /// Threshold carried by relative conditions (percent or volume multiple)
+    pub fn threshold(&self) -> Option<f64> {
+        match self {
+            AlertCondition::PercentChangeUp(v)
+            | AlertCondition::PercentChangeDown(v)
+            | AlertCondition::VolumeMultiple(v) => Some(*v),
+            AlertCondition::Above | AlertCondition::Below => None,
+        }
+    }
 }
 
 /// Price alert
@@ -79,6 +278,7 @@ pub enum AlertCondition {
 pub struct PriceAlert {
     pub id: i64,
     pub symbol: String,
+    /// Price level for above/below alerts; percent or multiple for relative conditions
     pub target_price: f64,
     pub condition: AlertCondition,
     pub triggered: bool,
@@ -104,6 +304,47 @@ pub struct Position {
     pub notes: Option<String>,
 }
 
+/// One recorded change to a position, paper trade or price alert. `before` and
+/// `after` hold the row as JSON; creates have no `before`, deletes no `after`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub id: i64,
+    /// "position", "paper_trade" or "price_alert"
+    pub entity_type: String,
+    pub entity_id: i64,
+    /// "create", "update", "delete" or "restore"
+    pub action: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub created_at: String,
+}
+
+/// Share of portfolio value held in one sector or asset class
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllocationBucket {
+    pub name: String,
+    pub value: f64,
+    pub percent: f64,
+}
+
+/// Portfolio value broken down by sector and by asset class
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioAllocation {
+    pub total_value: f64,
+    pub by_sector: Vec<AllocationBucket>,
+    pub by_asset_class: Vec<AllocationBucket>,
+}
+
+/// One trade in a rebalancing plan
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RebalanceTrade {
+    pub symbol: String,
+    pub action: PositionType,
+    pub shares: f64,
+    pub price: f64,
+    pub est_value: f64,
+}
+
 // ============================================================================
 // Signal Generation Types
 // ============================================================================
@@ -138,6 +379,29 @@ pub enum SignalType {
     // MFI signals
     MfiOverbought,
     MfiOversold,
+    // Ultimate Oscillator signals
+    UoOverbought,
+    UoOversold,
+    // Parabolic SAR signals (direction tells which way it flipped)
+    PsarFlip,
+    // Aroon signals
+    AroonBullish,
+    AroonBearish,
+    // Donchian Channel signals (direction tells which side broke)
+    DonchianBreakout,
+    // Supertrend signals (direction tells which way it flipped)
+    SupertrendFlip,
+    // Divergence between price and an oscillator (RSI, MFI, OBV)
+    BearishDivergence,
+    BullishDivergence,
+    // Overnight gaps (open vs prior close)
+    GapUp,
+    GapDown,
+    // Bollinger bandwidth at a multi-period low (volatility contraction)
+    BollingerSqueeze,
+    // Stochastic RSI signals
+    StochRsiOverbought,
+    StochRsiOversold,
 }
 
 impl SignalType {
@@ -161,6 +425,20 @@ impl SignalType {
             SignalType::CciOversold => "CCI_OVERSOLD",
             SignalType::MfiOverbought => "MFI_OVERBOUGHT",
             SignalType::MfiOversold => "MFI_OVERSOLD",
+            SignalType::UoOverbought => "UO_OVERBOUGHT",
+            SignalType::UoOversold => "UO_OVERSOLD",
+            SignalType::PsarFlip => "PSAR_FLIP",
+            SignalType::AroonBullish => "AROON_BULLISH_CROSS",
+            SignalType::AroonBearish => "AROON_BEARISH_CROSS",
+            SignalType::DonchianBreakout => "DONCHIAN_BREAKOUT",
+            SignalType::SupertrendFlip => "SUPERTREND_FLIP",
+            SignalType::BearishDivergence => "BEARISH_DIVERGENCE",
+            SignalType::BullishDivergence => "BULLISH_DIVERGENCE",
+            SignalType::GapUp => "GAP_UP",
+            SignalType::GapDown => "GAP_DOWN",
+            SignalType::BollingerSqueeze => "BB_SQUEEZE",
+            SignalType::StochRsiOverbought => "STOCHRSI_OVERBOUGHT",
+            SignalType::StochRsiOversold => "STOCHRSI_OVERSOLD",
         }
     }
 
@@ -184,6 +462,20 @@ impl SignalType {
             "CCI_OVERSOLD" => Some(SignalType::CciOversold),
             "MFI_OVERBOUGHT" => Some(SignalType::MfiOverbought),
             "MFI_OVERSOLD" => Some(SignalType::MfiOversold),
+            "UO_OVERBOUGHT" => Some(SignalType::UoOverbought),
+            "UO_OVERSOLD" => Some(SignalType::UoOversold),
+            "PSAR_FLIP" => Some(SignalType::PsarFlip),
+            "AROON_BULLISH_CROSS" => Some(SignalType::AroonBullish),
+            "AROON_BEARISH_CROSS" => Some(SignalType::AroonBearish),
+            "DONCHIAN_BREAKOUT" => Some(SignalType::DonchianBreakout),
+            "SUPERTREND_FLIP" => Some(SignalType::SupertrendFlip),
+            "BEARISH_DIVERGENCE" => Some(SignalType::BearishDivergence),
+            "BULLISH_DIVERGENCE" => Some(SignalType::BullishDivergence),
+            "GAP_UP" => Some(SignalType::GapUp),
+            "GAP_DOWN" => Some(SignalType::GapDown),
+            "BB_SQUEEZE" => Some(SignalType::BollingerSqueeze),
+            "STOCHRSI_OVERBOUGHT" => Some(SignalType::StochRsiOverbought),
+            "STOCHRSI_OVERSOLD" => Some(SignalType::StochRsiOversold),
             _ => None,
         }
     }
@@ -222,6 +514,7 @@ pub struct Signal {
     pub symbol: String,
     pub signal_type: SignalType,
     pub direction: SignalDirection,
+    /// Weighted detector confidence, 0.0 to 1.0
     pub strength: f64,
     pub price_at_signal: f64,
     pub triggered_by: String,
@@ -231,6 +524,13 @@ pub struct Signal {
     pub acknowledged: bool,
 }
 
+impl Signal {
+    /// Strength on the 0 to 100 scale shown in the UI
+    pub fn strength_score(&self) -> f64 {
+        self.strength * 100.0
+    }
+}
+
 // ============================================================================
 // Indicator Alert Types
 // ============================================================================
@@ -360,6 +660,49 @@ impl StrategyConditionType {
     }
 }
 
+/// One condition within a strategy rule
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    pub condition: StrategyConditionType,
+    #[serde(default)]
+    pub threshold: f64,
+}
+
+/// Composite entry/exit rule: every condition (All) or at least one (Any)
+/// must hold on the same bar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyRule {
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+impl StrategyRule {
+    /// A single condition, as used by the classic one-condition strategy shape
+    pub fn single(condition: StrategyConditionType, threshold: f64) -> Self {
+        StrategyRule::All(vec![Condition { condition, threshold }])
+    }
+
+    pub fn conditions(&self) -> &[Condition] {
+        match self {
+            StrategyRule::All(conditions) | StrategyRule::Any(conditions) => conditions,
+        }
+    }
+
+    /// Short label used as a trade's entry/exit reason, e.g. "rsi_oversold+price_above_sma"
+    pub fn describe(&self) -> String {
+        let separator = match self {
+            StrategyRule::All(_) => "+",
+            StrategyRule::Any(_) => "|",
+        };
+        self.conditions()
+            .iter()
+            .map(|c| c.condition.as_str())
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+}
+
 /// A trading strategy definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Strategy {
@@ -372,13 +715,38 @@ pub struct Strategy {
     pub exit_threshold: f64,
     pub stop_loss_percent: Option<f64>,
     pub take_profit_percent: Option<f64>,
+    #[serde(default)]
+    pub trailing_stop_percent: Option<f64>, // % below the high-water mark (above the low for shorts)
     pub position_size_percent: f64, // % of capital per trade
+    #[serde(default)]
+    pub direction: TradeDirection, // Long buys on entry, Short sells on entry
+    #[serde(default)]
+    pub entry_rule: Option<StrategyRule>, // overrides entry_condition/entry_threshold when set
+    #[serde(default)]
+    pub exit_rule: Option<StrategyRule>, // overrides exit_condition/exit_threshold when set
     pub created_at: String,
 }
 
+impl Strategy {
+    /// Entry rule, falling back to the single entry condition
+    pub fn resolved_entry_rule(&self) -> StrategyRule {
+        self.entry_rule
+            .clone()
+            .unwrap_or_else(|| StrategyRule::single(self.entry_condition, self.entry_threshold))
+    }
+
+    /// Exit rule, falling back to the single exit condition
+    pub fn resolved_exit_rule(&self) -> StrategyRule {
+        self.exit_rule
+            .clone()
+            .unwrap_or_else(|| StrategyRule::single(self.exit_condition, self.exit_threshold))
+    }
+}
+
 /// Trade direction
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum TradeDirection {
+    #[default]
     Long,
     Short,
 }
@@ -447,22 +815,111 @@ pub struct BacktestResult {
     pub final_capital: f64,
     pub metrics: PerformanceMetrics,
     pub trades: Vec<BacktestTrade>,
+    #[serde(default)]
+    pub equity_curve: Vec<(NaiveDate, f64)>, // portfolio value marked to market each bar
+    #[serde(default)]
+    pub drawdown_curve: Vec<(NaiveDate, f64)>, // % below the running equity peak
     pub created_at: String,
 }
 
+/// Per-symbol share of a portfolio backtest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolContribution {
+    pub symbol: String,
+    pub trades: usize,
+    pub profit_loss: f64,
+    pub contribution_percent: f64, // P&L as % of initial portfolio capital
+}
+
+/// Result of running one strategy across several symbols with a shared capital pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioBacktestResult {
+    pub strategy_id: i64,
+    pub strategy_name: String,
+    pub symbols: Vec<String>,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub initial_capital: f64,
+    pub final_capital: f64,
+    pub metrics: PerformanceMetrics,
+    pub trades: Vec<BacktestTrade>,
+    pub contributions: Vec<SymbolContribution>,
+}
+
+/// In-sample vs out-of-sample backtest over a chronological split
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalkForwardResult {
+    pub strategy_id: i64,
+    pub strategy_name: String,
+    pub symbol: String,
+    pub train_ratio: f64,
+    pub split_date: NaiveDate, // first date of the out-of-sample window
+    pub in_sample: BacktestResult,
+    pub out_of_sample: BacktestResult,
+    pub degradation_ratio: f64, // OOS total return / IS total return (0 if IS return is 0)
+}
+
 // ============================================================================
 // Paper Trading Types
 // ============================================================================
 
-/// Paper trading wallet (tracks cash balance)
+/// Paper trading wallet (tracks cash balance with starting capital)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaperWallet {
     pub id: i64,
     pub cash: f64,
+    pub starting_capital: f64,
+    #[serde(default)]
+    pub fees: TradeFees,
     pub created_at: String,
     pub updated_at: String,
 }
 
+impl PaperWallet {
+    /// Percent gain of `total_equity` over the capital the account was funded with
+    pub fn pnl_percent(&self, total_equity: f64) -> f64 {
+        pnl_percent(self.starting_capital, total_equity)
+    }
+}
+
+/// Commission charged on every trade of a competition account; zero means commission-free
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct TradeFees {
+    /// Flat amount per trade
+    pub per_trade_fee: f64,
+    /// Percent of the trade's notional value (0.1 = 0.1%)
+    pub fee_percent: f64,
+}
+
+impl TradeFees {
+    /// Fee for a trade worth `notional`
+    pub fn fee_for(&self, notional: f64) -> f64 {
+        self.per_trade_fee + notional * self.fee_percent / 100.0
+    }
+
+    /// Most whole shares `cash` buys at `price` once the fee is paid (0 if not even one)
+    pub fn max_affordable_shares(&self, cash: f64, price: f64) -> f64 {
+        if price <= 0.0 {
+            return 0.0;
+        }
+        let per_share = price * (1.0 + self.fee_percent / 100.0);
+        let mut shares = ((cash - self.per_trade_fee) / per_share).floor().max(0.0);
+        // Guard against the division rounding up past what the cash covers
+        while shares > 0.0 && shares * price + self.fee_for(shares * price) > cash {
+            shares -= 1.0;
+        }
+        shares
+    }
+}
+
+fn pnl_percent(starting_capital: f64, total_equity: f64) -> f64 {
+    if starting_capital > 0.0 {
+        (total_equity - starting_capital) / starting_capital * 100.0
+    } else {
+        0.0
+    }
+}
+
 /// Paper trading position (open position)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaperPosition {
@@ -472,6 +929,10 @@ pub struct PaperPosition {
     pub entry_price: f64,
     pub entry_date: String,
     pub linked_event_id: Option<i64>,
+    /// Close automatically when the latest price falls to or below this level
+    pub stop_price: Option<f64>,
+    /// Close automatically when the latest price rises to or above this level
+    pub take_price: Option<f64>,
 }
 
 /// Paper trading action type
@@ -506,11 +967,126 @@ pub struct PaperTrade {
     pub quantity: f64,
     pub price: f64,
     pub pnl: Option<f64>,           // Calculated on SELL
+    pub fee: f64,
     pub timestamp: String,
     pub linked_event_id: Option<i64>,
     pub notes: Option<String>,
 }
 
+/// How realized P&L is computed when selling part of a position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CostBasisMethod {
+    /// Sell at the position's running average entry price
+    #[default]
+    Average,
+    /// Sell the oldest buy lots first
+    Fifo,
+}
+
+impl CostBasisMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CostBasisMethod::Average => "average",
+            CostBasisMethod::Fifo => "fifo",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "fifo" => CostBasisMethod::Fifo,
+            _ => CostBasisMethod::Average,
+        }
+    }
+}
+
+/// Pending paper order type (market orders fill immediately via execute_paper_trade)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaperOrderType {
+    /// Buy at or below / sell at or above the limit price
+    Limit,
+    /// Buy at or above / sell at or below the stop price
+    Stop,
+}
+
+impl PaperOrderType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaperOrderType::Limit => "LIMIT",
+            PaperOrderType::Stop => "STOP",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_uppercase().as_str() {
+            "LIMIT" => Some(PaperOrderType::Limit),
+            "STOP" => Some(PaperOrderType::Stop),
+            _ => None,
+        }
+    }
+}
+
+/// Lifecycle state of a paper order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaperOrderStatus {
+    Pending,
+    Filled,
+    Cancelled,
+}
+
+impl PaperOrderStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaperOrderStatus::Pending => "PENDING",
+            PaperOrderStatus::Filled => "FILLED",
+            PaperOrderStatus::Cancelled => "CANCELLED",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Self {
+        match s.to_uppercase().as_str() {
+            "FILLED" => PaperOrderStatus::Filled,
+            "CANCELLED" => PaperOrderStatus::Cancelled,
+            _ => PaperOrderStatus::Pending,
+        }
+    }
+}
+
+/// Queued limit/stop order awaiting a qualifying price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaperOrder {
+    pub id: i64,
+    pub symbol: String,
+    pub action: PaperTradeAction,
+    pub order_type: PaperOrderType,
+    pub quantity: f64,
+    pub limit_price: Option<f64>,
+    pub stop_price: Option<f64>,
+    pub status: PaperOrderStatus,
+    pub paper_trade_id: Option<i64>,  // Set once filled
+    pub created_at: String,
+    pub filled_at: Option<String>,
+}
+
+impl PaperOrder {
+    /// Whether the order should fill at `price`
+    pub fn is_triggered(&self, price: f64) -> bool {
+        match (self.order_type, self.action) {
+            (PaperOrderType::Limit, PaperTradeAction::Buy) => {
+                self.limit_price.is_some_and(|limit| price <= limit)
+            }
+            (PaperOrderType::Limit, PaperTradeAction::Sell) => {
+                self.limit_price.is_some_and(|limit| price >= limit)
+            }
+            (PaperOrderType::Stop, PaperTradeAction::Buy) => {
+                self.stop_price.is_some_and(|stop| price >= stop)
+            }
+            (PaperOrderType::Stop, PaperTradeAction::Sell) => {
+                self.stop_price.is_some_and(|stop| price <= stop)
+            }
+        }
+    }
+}
+
 // ============================================================================
 // DC Trader Types (Separate from KALIC AI paper trading)
 // ============================================================================
@@ -521,10 +1097,19 @@ pub struct DcWallet {
     pub id: i64,
     pub cash: f64,
     pub starting_capital: f64,
+    #[serde(default)]
+    pub fees: TradeFees,
     pub created_at: String,
     pub updated_at: String,
 }
 
+impl DcWallet {
+    /// Percent gain of `total_equity` over the capital the account was funded with
+    pub fn pnl_percent(&self, total_equity: f64) -> f64 {
+        pnl_percent(self.starting_capital, total_equity)
+    }
+}
+
 /// DC trading position
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DcPosition {
@@ -533,12 +1118,39 @@ pub struct DcPosition {
     pub quantity: f64,
     pub entry_price: f64,
     pub entry_date: String,
+    pub stop_price: Option<f64>,
+    pub take_price: Option<f64>,
+}
+
+/// Volatility-based stop level for one open long position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StopSuggestion {
+    pub symbol: String,
+    pub entry_price: f64,
+    /// Latest stored close
+    pub current_price: f64,
+    pub atr: f64,
+    /// `current_price` minus the requested multiple of `atr`
+    pub suggested_stop: f64,
 }
 
 /// DC trade history record
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DcTrade {
     pub id: i64,
+    pub symbol: String,
+    pub action: String,
+    pub quantity: f64,
+    pub price: f64,
+    pub pnl: Option<f64>,
+    pub fee: f64,
+    pub timestamp: String,
+    pub notes: Option<String>,
+}
+
+/// Exported trade row, shared by the paper (KALIC) and DC trade logs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeLogEntry {
     pub symbol: String,
     pub action: String,
     pub quantity: f64,
@@ -567,9 +1179,38 @@ pub struct TeamConfig {
     pub description: Option<String>,
     pub kalic_starting_capital: f64,
     pub dc_starting_capital: f64,
+    #[serde(default)]
+    pub kalic_fees: TradeFees,
+    #[serde(default)]
+    pub dc_fees: TradeFees,
     pub created_at: String,
 }
 
+/// Watchlist as stored in a configuration export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchlistConfig {
+    pub name: String,
+    pub description: Option<String>,
+    pub symbols: Vec<String>,
+}
+
+/// Portable user configuration (no prices or trades). Ids and timestamps
+/// are carried along but ignored on import; rows are matched by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigExport {
+    pub version: u32,
+    #[serde(default)]
+    pub watchlists: Vec<WatchlistConfig>,
+    #[serde(default)]
+    pub strategies: Vec<Strategy>,
+    #[serde(default)]
+    pub price_alerts: Vec<PriceAlert>,
+    #[serde(default)]
+    pub indicator_alerts: Vec<IndicatorAlert>,
+    #[serde(default)]
+    pub team_configs: Vec<TeamConfig>,
+}
+
 /// Result of batch import operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportResult {
@@ -586,12 +1227,17 @@ pub struct CompetitionStats {
     pub kalic_positions: f64,
     pub kalic_pnl_pct: f64,
     pub kalic_trades: i32,
+    /// Commissions paid so far (already taken out of cash and totals)
+    pub kalic_fees_paid: f64,
     pub dc_total: f64,
     pub dc_cash: f64,
     pub dc_positions: f64,
     pub dc_pnl_pct: f64,
     pub dc_trades: i32,
+    pub dc_fees_paid: f64,
+    /// Team with the higher P&L percent, so unequal starting capitals compare fairly
     pub leader: String,
+    /// Gap between the two P&L percents, in percentage points
     pub lead_amount: f64,
 }
 
@@ -663,6 +1309,8 @@ pub struct AiTraderConfig {
     pub max_position_size_percent: f64,
     pub stop_loss_percent: f64,
     pub take_profit_percent: f64,
+    /// Most of equity a BUY may lose if its stop is hit, in percent
+    pub risk_per_trade_percent: f64,
     pub session_duration_minutes: u32,
     pub benchmark_symbol: String,
     pub model_priority: Vec<String>,
@@ -677,6 +1325,9 @@ pub struct AiTraderConfig {
     pub max_single_trade_value: f64,
     pub require_confluence: bool,
     pub blocked_hours: String,
+    /// Fill a BUY with as many whole shares as cash allows instead of rejecting it
+    #[serde(default)]
+    pub allow_partial_fills: bool,
 }
 
 impl Default for AiTraderConfig {
@@ -686,6 +1337,7 @@ impl Default for AiTraderConfig {
             max_position_size_percent: 10.0,
             stop_loss_percent: 5.0,
             take_profit_percent: 15.0,
+            risk_per_trade_percent: 1.0,
             session_duration_minutes: 60,
             benchmark_symbol: "SPY".to_string(),
             model_priority: vec![
@@ -701,6 +1353,7 @@ impl Default for AiTraderConfig {
             max_single_trade_value: 50_000.0,
             require_confluence: true,
             blocked_hours: "09:30-09:45,15:45-16:00".to_string(),
+            allow_partial_fills: false,
         }
     }
 }
@@ -739,6 +1392,9 @@ pub struct AiTradeDecision {
     pub actual_price_at_timeframe: Option<f64>,
     pub prediction_accurate: Option<bool>,
     pub paper_trade_id: Option<i64>,
+    /// Recorded by a dry-run cycle; no trade was executed
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 /// AI Performance Snapshot for charting
@@ -829,6 +1485,148 @@ impl CompoundingForecast {
     }
 }
 
+/// Saved chat conversation (Claude or Ollama)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub id: i64,
+    pub title: String,
+    pub provider: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub message_count: i64,
+}
+
+/// Single turn in a saved conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationMessage {
+    pub id: i64,
+    pub conversation_id: i64,
+    pub role: String,
+    pub content: String,
+    pub created_at: String,
+}
+
+/// Background refresh of favorited symbols' prices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RefreshSettings {
+    pub enabled: bool,
+    pub interval_minutes: u32,
+    /// Calendar days without a new daily bar before a symbol's data counts as stale
+    #[serde(default = "default_stale_after_days")]
+    pub stale_after_days: u32,
+}
+
+fn default_stale_after_days() -> u32 {
+    4
+}
+
+impl Default for RefreshSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_minutes: 15,
+            stale_after_days: default_stale_after_days(),
+        }
+    }
+}
+
+/// Rows removed from one table by a retention prune
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrunedTable {
+    pub table: String,
+    /// Window applied to this table (its override, or the prune default)
+    pub retention_days: u32,
+    pub deleted: usize,
+}
+
+/// Spellings of one symbol that differ only by case
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateSymbol {
+    /// Upper-case form, the merge target
+    pub canonical: String,
+    /// Every stored spelling, including `canonical` when present
+    pub variants: Vec<String>,
+}
+
+/// How current a symbol's stored daily prices are
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataFreshness {
+    pub symbol: String,
+    pub last_date: NaiveDate,
+    /// Calendar days between `last_date` and today
+    pub days_stale: i64,
+    /// `days_stale` exceeds the configured `stale_after_days`
+    pub stale: bool,
+}
+
+/// Range and averages over a symbol's most recent daily bars
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceStats {
+    pub symbol: String,
+    /// Bars the stats cover (fewer than requested when the history is shorter)
+    pub bars: u32,
+    pub high: f64,
+    pub high_date: NaiveDate,
+    pub low: f64,
+    pub low_date: NaiveDate,
+    pub avg_close: f64,
+    pub avg_volume: f64,
+}
+
+/// Kind of trigger forwarded to webhooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    Signal,
+    PriceAlert,
+    IndicatorAlert,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::Signal => "signal",
+            WebhookEvent::PriceAlert => "price_alert",
+            WebhookEvent::IndicatorAlert => "indicator_alert",
+        }
+    }
+}
+
+impl std::str::FromStr for WebhookEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "signal" => Ok(WebhookEvent::Signal),
+            "price_alert" => Ok(WebhookEvent::PriceAlert),
+            "indicator_alert" => Ok(WebhookEvent::IndicatorAlert),
+            _ => Err(format!(
+                "Unknown webhook event '{}' (expected signal, price_alert or indicator_alert)",
+                s
+            )),
+        }
+    }
+}
+
+/// HTTP endpoint that receives triggered signals/alerts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: i64,
+    pub url: String,
+    /// Only forward this kind of event (None = all events)
+    pub event_type: Option<WebhookEvent>,
+    /// Shared secret used to sign payloads (None = unsigned)
+    pub secret: Option<String>,
+    pub enabled: bool,
+    pub created_at: String,
+}
+
+impl Webhook {
+    /// Whether this webhook should receive the given event
+    pub fn accepts(&self, event: WebhookEvent) -> bool {
+        self.enabled && self.event_type.is_none_or(|e| e == event)
+    }
+}
+
 /// Yahoo Finance chart response structures
 pub mod yahoo {
     use serde::Deserialize;
@@ -855,6 +1653,20 @@ pub mod yahoo {
         pub meta: ChartMeta,
         pub timestamp: Option<Vec<i64>>,
         pub indicators: Indicators,
+        /// Only present when requested with `events=div`
+        pub events: Option<ChartEvents>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct ChartEvents {
+        /// Keyed by ex-date timestamp (as a string)
+        pub dividends: Option<std::collections::HashMap<String, DividendEvent>>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct DividendEvent {
+        pub amount: f64,
+        pub date: i64,
     }
 
     #[derive(Debug, Deserialize)]
@@ -884,4 +1696,74 @@ pub mod yahoo {
     pub struct AdjClose {
         pub adjclose: Vec<Option<f64>>,
     }
+
+    #[derive(Debug, Deserialize)]
+    pub struct QuoteSummaryResponse {
+        #[serde(rename = "quoteSummary")]
+        pub quote_summary: QuoteSummary,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct QuoteSummary {
+        pub result: Option<Vec<QuoteSummaryResult>>,
+        pub error: Option<ChartError>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct QuoteSummaryResult {
+        #[serde(rename = "assetProfile")]
+        pub asset_profile: Option<AssetProfile>,
+        pub price: Option<PriceModule>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct AssetProfile {
+        pub sector: Option<String>,
+        pub industry: Option<String>,
+        pub country: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct PriceModule {
+        #[serde(rename = "longName")]
+        pub long_name: Option<String>,
+        #[serde(rename = "shortName")]
+        pub short_name: Option<String>,
+        #[serde(rename = "exchangeName")]
+        pub exchange_name: Option<String>,
+        pub currency: Option<String>,
+        #[serde(rename = "marketCap")]
+        pub market_cap: Option<RawValue>,
+        #[serde(rename = "quoteType")]
+        pub quote_type: Option<String>,
+    }
+
+    /// Yahoo's `{"raw": 123.0, "fmt": "123"}` number wrapper
+    #[derive(Debug, Deserialize)]
+    pub struct RawValue {
+        pub raw: Option<f64>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_symbol_cleans_messy_input() {
+        assert_eq!(normalize_symbol("$aapl").as_deref(), Some("AAPL"));
+        assert_eq!(normalize_symbol(" aapl ").as_deref(), Some("AAPL"));
+        assert_eq!(normalize_symbol(" $msft\n").as_deref(), Some("MSFT"));
+        assert_eq!(normalize_symbol("brk.b").as_deref(), Some("BRK.B"));
+        assert_eq!(normalize_symbol("^gspc").as_deref(), Some("^GSPC"));
+        assert_eq!(normalize_symbol("eurusd=x").as_deref(), Some("EURUSD=X"));
+        assert_eq!(normalize_symbol("btc-usd").as_deref(), Some("BTC-USD"));
+    }
+
+    #[test]
+    fn test_normalize_symbol_rejects_garbage() {
+        for raw in ["", "   ", "$", "$$AAPL", "aa pl", "ab;c", "AAPL'--", "\u{00e9}tf"] {
+            assert_eq!(normalize_symbol(raw), None, "{:?} should be rejected", raw);
+        }
+    }
 }
@@ -25,6 +25,11 @@ pub const MODEL_BALANCED: &str = "gpt-oss:120b-cloud";
 /// Heavy model for complex quant analysis (pattern recognition, backtesting advice)
 pub const MODEL_HEAVY: &str = "deepseek-v3.2:cloud";
 
+/// Embedding model used for vector search
+pub const MODEL_EMBED: &str = "nomic-embed-text";
+
+const ANSWER_SYSTEM_PROMPT: &str = "You are a helpful financial assistant. Answer questions based on the provided context. Be concise and accurate.";
+
 // ============================================================================
 // Result Types
 // ============================================================================
@@ -72,6 +77,17 @@ pub struct PatternExplanation {
     pub confidence_level: String,
 }
 
+/// Locally installed model reported by `/api/tags`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaModel {
+    pub name: String,
+    /// Size on disk in bytes
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub modified_at: Option<String>,
+}
+
 // ============================================================================
 // Ollama API Types
 // ============================================================================
@@ -92,6 +108,96 @@ struct GenerateResponse {
     done: bool,
 }
 
+/// One NDJSON line of a streaming `/api/generate` response
+#[derive(Debug, Deserialize)]
+struct GenerateStreamChunk {
+    #[serde(default)]
+    response: String,
+    #[serde(default)]
+    done: bool,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// Streaming failures worth telling apart from a generic API error
+#[derive(Debug, thiserror::Error)]
+pub enum OllamaStreamError {
+    #[error("Ollama model '{0}' not found (pull it with `ollama pull {0}`)")]
+    ModelNotFound(String),
+    #[error("Ollama stream closed before the answer finished ({0} bytes received)")]
+    ClosedEarly(usize),
+}
+
+/// Reassembles NDJSON lines from network chunks and hands each token to a callback
+#[derive(Debug, Default)]
+struct TokenStream {
+    pending: Vec<u8>,
+    text: String,
+    done: bool,
+}
+
+impl TokenStream {
+    /// Feed raw bytes; every complete line is parsed and its token forwarded
+    fn push(&mut self, bytes: &[u8], on_token: &mut impl FnMut(&str)) -> Result<()> {
+        self.pending.extend_from_slice(bytes);
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.handle_line(&line, on_token)?;
+        }
+        Ok(())
+    }
+
+    fn handle_line(&mut self, line: &[u8], on_token: &mut impl FnMut(&str)) -> Result<()> {
+        let line = line.trim_ascii();
+        if line.is_empty() || self.done {
+            return Ok(());
+        }
+
+        let chunk: GenerateStreamChunk =
+            serde_json::from_slice(line).context("Failed to parse Ollama stream chunk")?;
+        if let Some(error) = chunk.error {
+            anyhow::bail!("Ollama API error: {}", error);
+        }
+        if !chunk.response.is_empty() {
+            on_token(&chunk.response);
+            self.text.push_str(&chunk.response);
+        }
+        self.done = chunk.done;
+        Ok(())
+    }
+
+    /// Flush a trailing line without a newline and return the full text,
+    /// or `ClosedEarly` if the `done` line never arrived
+    fn finish(mut self, on_token: &mut impl FnMut(&str)) -> Result<String> {
+        let rest = std::mem::take(&mut self.pending);
+        // A trailing fragment that doesn't parse was cut off mid-line
+        if serde_json::from_slice::<GenerateStreamChunk>(rest.trim_ascii()).is_ok() {
+            self.handle_line(&rest, on_token)?;
+        }
+        if !self.done {
+            return Err(OllamaStreamError::ClosedEarly(self.text.len()).into());
+        }
+        Ok(self.text)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<OllamaModel>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct EmbeddingsRequest<'a> {
+    pub model: &'a str,
+    pub prompt: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct EmbeddingsResponse {
+    pub embedding: Vec<f32>,
+}
+
 // ============================================================================
 // OllamaClient
 // ============================================================================
@@ -141,6 +247,40 @@ impl OllamaClient {
         self
     }
 
+    /// Base URL of the Ollama server
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Generate an embedding vector for text (defaults to MODEL_EMBED)
+    pub async fn embed(&self, text: &str, model: Option<&str>) -> Result<Vec<f32>> {
+        let request = EmbeddingsRequest {
+            model: model.unwrap_or(MODEL_EMBED),
+            prompt: text,
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/api/embeddings", self.base_url))
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send embeddings request to Ollama")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Ollama API error: {} - {}", status, body);
+        }
+
+        let embed_response: EmbeddingsResponse = response
+            .json()
+            .await
+            .context("Failed to parse Ollama embeddings response")?;
+
+        Ok(embed_response.embedding)
+    }
+
     /// Check if Ollama is available (2-second timeout)
     pub async fn is_available(&self) -> bool {
         let check = async {
@@ -158,6 +298,39 @@ impl OllamaClient {
             .unwrap_or(false)
     }
 
+    /// List models installed on the Ollama server
+    pub async fn list_models(&self) -> Result<Vec<OllamaModel>> {
+        let response = self
+            .client
+            .get(format!("{}/api/tags", self.base_url))
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .context("Failed to list Ollama models")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Ollama API error: {} - {}", status, body);
+        }
+
+        let tags: TagsResponse = response
+            .json()
+            .await
+            .context("Failed to parse Ollama model list")?;
+
+        Ok(tags.models)
+    }
+
+    fn generate_request(&self, prompt: &str, system: Option<&str>, model: Option<&str>) -> GenerateRequest {
+        GenerateRequest {
+            model: model.unwrap_or(&self.default_model).to_string(),
+            prompt: prompt.to_string(),
+            system: system.map(|s| s.to_string()),
+            stream: false,
+        }
+    }
+
     /// Raw LLM query with optional system prompt
     pub async fn query(
         &self,
@@ -165,14 +338,7 @@ impl OllamaClient {
         system: Option<&str>,
         model: Option<&str>,
     ) -> Result<String> {
-        let model = model.unwrap_or(&self.default_model);
-
-        let request = GenerateRequest {
-            model: model.to_string(),
-            prompt: prompt.to_string(),
-            system: system.map(|s| s.to_string()),
-            stream: false,
-        };
+        let request = self.generate_request(prompt, system, model);
 
         let response = self
             .client
@@ -196,6 +362,47 @@ impl OllamaClient {
         Ok(gen_response.response)
     }
 
+    /// Like `query`, but streams the answer: `on_token` gets each fragment as it arrives
+    /// and the full text is returned once Ollama reports `done`
+    pub async fn query_stream(
+        &self,
+        prompt: &str,
+        system: Option<&str>,
+        model: Option<&str>,
+        mut on_token: impl FnMut(&str),
+    ) -> Result<String> {
+        let mut request = self.generate_request(prompt, system, model);
+        request.stream = true;
+
+        let mut response = self
+            .client
+            .post(format!("{}/api/generate", self.base_url))
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send request to Ollama")?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(OllamaStreamError::ModelNotFound(request.model).into());
+        }
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Ollama API error: {} - {}", status, body);
+        }
+
+        let mut stream = TokenStream::default();
+        loop {
+            match response.chunk().await {
+                Ok(Some(bytes)) => stream.push(&bytes, &mut on_token)?,
+                Ok(None) => break,
+                // Connection dropped mid-answer
+                Err(_) => return Err(OllamaStreamError::ClosedEarly(stream.text.len()).into()),
+            }
+        }
+        stream.finish(&mut on_token)
+    }
+
     /// Analyze sentiment of text (news, social media, etc.)
     pub async fn analyze_sentiment(&self, text: &str) -> Result<SentimentResult> {
         let system = "You are a financial sentiment analyzer. Analyze text and respond ONLY with valid JSON.";
@@ -351,11 +558,8 @@ Provide a concise 2-3 sentence summary covering:
         self.query(&prompt, Some(system), None).await
     }
 
-    /// Natural language Q&A about financial data
-    pub async fn answer_query(&self, question: &str, context: &str) -> Result<String> {
-        let system = "You are a helpful financial assistant. Answer questions based on the provided context. Be concise and accurate.";
-
-        let prompt = format!(
+    fn answer_prompt(question: &str, context: &str) -> String {
+        format!(
             r#"Context (financial data):
 {}
 
@@ -363,9 +567,24 @@ Question: {}
 
 Answer the question based only on the provided context. If the context doesn't contain relevant information, say so."#,
             context, question
-        );
+        )
+    }
 
-        self.query(&prompt, Some(system), None).await
+    /// Natural language Q&A about financial data
+    pub async fn answer_query(&self, question: &str, context: &str) -> Result<String> {
+        let prompt = Self::answer_prompt(question, context);
+        self.query(&prompt, Some(ANSWER_SYSTEM_PROMPT), None).await
+    }
+
+    /// `answer_query`, streaming tokens to `on_token` as they are generated
+    pub async fn answer_query_stream(
+        &self,
+        question: &str,
+        context: &str,
+        on_token: impl FnMut(&str),
+    ) -> Result<String> {
+        let prompt = Self::answer_prompt(question, context);
+        self.query_stream(&prompt, Some(ANSWER_SYSTEM_PROMPT), None, on_token).await
     }
 
     // ========================================================================
@@ -545,4 +764,61 @@ mod tests {
         assert_eq!(custom.base_url, "http://custom:11434");
         assert_eq!(custom.default_model, MODEL_HEAVY);
     }
+
+    #[test]
+    fn test_generate_request_uses_selected_model() {
+        let client = OllamaClient::new().with_model("llama3.1:8b");
+        let request = client.generate_request("Is AAPL bullish?", Some("system"), None);
+        let body = serde_json::to_string(&request).unwrap();
+        assert!(body.contains(r#""model":"llama3.1:8b""#));
+
+        // Per-call override still wins over the client default
+        let request = client.generate_request("Is AAPL bullish?", None, Some(MODEL_FAST));
+        assert_eq!(request.model, MODEL_FAST);
+    }
+
+    #[test]
+    fn test_token_stream_reassembles_split_lines() {
+        let ndjson = concat!(
+            r#"{"model":"m","response":"The ","done":false}"#, "\n",
+            r#"{"model":"m","response":"trend is ","done":false}"#, "\n",
+            r#"{"model":"m","response":"up.","done":false}"#, "\n",
+            r#"{"model":"m","response":"","done":true,"eval_count":3}"#, "\n",
+        );
+
+        let mut tokens = Vec::new();
+        let mut on_token = |t: &str| tokens.push(t.to_string());
+        let mut stream = TokenStream::default();
+        // Network chunks rarely line up with NDJSON lines
+        for chunk in ndjson.as_bytes().chunks(7) {
+            stream.push(chunk, &mut on_token).unwrap();
+        }
+        let full = stream.finish(&mut on_token).unwrap();
+
+        assert_eq!(full, "The trend is up.");
+        assert_eq!(tokens, vec!["The ", "trend is ", "up."]);
+        assert_eq!(tokens.concat(), full);
+    }
+
+    #[test]
+    fn test_token_stream_reports_early_close() {
+        // Connection dropped partway through the second line
+        let mut stream = TokenStream::default();
+        stream.push(b"{\"response\":\"Par\",\"done\":false}\n{\"resp", &mut |_| {}).unwrap();
+        let err = stream.finish(&mut |_| {}).unwrap_err();
+        assert!(matches!(err.downcast_ref::<OllamaStreamError>(), Some(OllamaStreamError::ClosedEarly(3))));
+
+        let mut stream = TokenStream::default();
+        let err = stream.push(b"{\"error\":\"model overloaded\"}\n", &mut |_| {}).unwrap_err();
+        assert!(err.downcast_ref::<OllamaStreamError>().is_none());
+    }
+
+    #[test]
+    fn test_parse_tags_response() {
+        let json = r#"{"models": [{"name": "llama3.1:8b", "size": 4920753328, "modified_at": "2026-01-10T12:00:00Z", "digest": "abc"}]}"#;
+        let tags: TagsResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(tags.models.len(), 1);
+        assert_eq!(tags.models[0].name, "llama3.1:8b");
+        assert_eq!(tags.models[0].size, 4_920_753_328);
+    }
 }
@@ -0,0 +1,167 @@
+//! API rate limiting
+//!
+//! Checks per-source request caps against the `api_calls` log before each request
+//! and logs the request when it is allowed.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{Duration, Utc};
+
+use crate::db::Database;
+use crate::error::{PipelineError, Result};
+
+/// Source names used in `api_calls`
+pub const SOURCE_YAHOO: &str = "yahoo_finance";
+pub const SOURCE_FRED: &str = "FRED";
+pub const SOURCE_FINNHUB: &str = "finnhub";
+
+/// Request caps for one source (`None` = uncapped)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub struct RateLimit {
+    pub per_minute: Option<u32>,
+    pub per_day: Option<u32>,
+}
+
+impl RateLimit {
+    pub fn per_minute(calls: u32) -> Self {
+        Self {
+            per_minute: Some(calls),
+            per_day: None,
+        }
+    }
+}
+
+/// Default caps: Finnhub's free tier (60/min) and conservative limits for FRED and Yahoo
+pub fn default_limits() -> HashMap<String, RateLimit> {
+    HashMap::from([
+        (SOURCE_FINNHUB.to_string(), RateLimit::per_minute(60)),
+        (SOURCE_FRED.to_string(), RateLimit::per_minute(120)),
+        (
+            SOURCE_YAHOO.to_string(),
+            RateLimit {
+                per_minute: Some(120),
+                per_day: Some(5000),
+            },
+        ),
+    ])
+}
+
+/// Shared limiter consulted by the API clients before every request.
+/// Holds its own database handle so clients without one (Finnhub) can be limited too.
+pub struct RateLimiter {
+    db: Mutex<Database>,
+    limits: Mutex<HashMap<String, RateLimit>>,
+}
+
+impl RateLimiter {
+    /// Limiter over the `api_calls` table of `db`, starting with `default_limits()`
+    pub fn new(db: Database) -> Self {
+        Self {
+            db: Mutex::new(db),
+            limits: Mutex::new(default_limits()),
+        }
+    }
+
+    /// Replace the caps for a source
+    pub fn with_limit(self, source: &str, limit: RateLimit) -> Self {
+        self.set_limit(source, limit);
+        self
+    }
+
+    /// Replace the caps for a source on a shared limiter
+    pub fn set_limit(&self, source: &str, limit: RateLimit) {
+        if let Ok(mut limits) = self.limits.lock() {
+            limits.insert(source.to_string(), limit);
+        }
+    }
+
+    /// Caps currently configured for a source
+    pub fn limit(&self, source: &str) -> RateLimit {
+        self.limits
+            .lock()
+            .ok()
+            .and_then(|limits| limits.get(source).copied())
+            .unwrap_or_default()
+    }
+
+    /// Calls logged for a source today
+    pub fn calls_today(&self, source: &str) -> Result<u32> {
+        self.lock_db()?.calls_today(source)
+    }
+
+    /// Record a request to `source`, or fail with `RateLimited` if a cap is already used up
+    pub fn acquire(&self, source: &str, endpoint: &str, symbol: &str) -> Result<()> {
+        let limit = self.limit(source);
+        let db = self.lock_db()?;
+
+        if let Some(cap) = limit.per_minute {
+            if db.calls_since(source, Utc::now() - Duration::minutes(1))? >= cap {
+                return Err(PipelineError::RateLimited(format!(
+                    "{} allows {} calls per minute",
+                    source, cap
+                )));
+            }
+        }
+        if let Some(cap) = limit.per_day {
+            if db.calls_today(source)? >= cap {
+                return Err(PipelineError::RateLimited(format!(
+                    "{} allows {} calls per day",
+                    source, cap
+                )));
+            }
+        }
+
+        db.log_api_call(source, endpoint, symbol)
+    }
+
+    fn lock_db(&self) -> Result<std::sync::MutexGuard<'_, Database>> {
+        self.db
+            .lock()
+            .map_err(|e| PipelineError::ApiError(format!("Rate limiter lock poisoned: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        RateLimiter::new(db)
+    }
+
+    #[test]
+    fn test_per_minute_cap() {
+        let limiter = limiter().with_limit(SOURCE_FINNHUB, RateLimit::per_minute(60));
+
+        for _ in 0..60 {
+            limiter.acquire(SOURCE_FINNHUB, "quote", "AAPL").unwrap();
+        }
+        let err = limiter.acquire(SOURCE_FINNHUB, "quote", "AAPL").unwrap_err();
+        assert!(matches!(err, PipelineError::RateLimited(_)));
+
+        // Rejected calls aren't logged, and other sources have their own budget
+        assert_eq!(limiter.calls_today(SOURCE_FINNHUB).unwrap(), 60);
+        limiter.acquire(SOURCE_FRED, "graph", "DFF").unwrap();
+    }
+
+    #[test]
+    fn test_daily_cap() {
+        let limiter = limiter().with_limit(
+            SOURCE_YAHOO,
+            RateLimit {
+                per_minute: None,
+                per_day: Some(2),
+            },
+        );
+
+        limiter.acquire(SOURCE_YAHOO, "history", "AAPL").unwrap();
+        limiter.acquire(SOURCE_YAHOO, "history", "MSFT").unwrap();
+        assert!(matches!(
+            limiter.acquire(SOURCE_YAHOO, "history", "NVDA"),
+            Err(PipelineError::RateLimited(_))
+        ));
+    }
+}
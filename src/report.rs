@@ -0,0 +1,462 @@
+//! PDF reports for backtests and the portfolio
+//!
+//! Pages only use the standard Helvetica font, which every PDF reader provides,
+//! so reports are written directly without a rendering library.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+use crate::db::Database;
+use crate::error::{PipelineError, Result};
+use crate::models::{BacktestResult, Strategy, StrategyConditionType, StrategyRule};
+
+/// US Letter, in points
+const PAGE_WIDTH: f64 = 612.0;
+const PAGE_HEIGHT: f64 = 792.0;
+const MARGIN: f64 = 50.0;
+
+/// Table rows per page for trades and holdings
+pub const REPORT_ROWS_PER_PAGE: usize = 40;
+
+/// One page's drawing operations, written top-down from a cursor
+struct Page {
+    content: String,
+    y: f64,
+}
+
+impl Page {
+    fn new() -> Self {
+        Self {
+            content: String::new(),
+            y: PAGE_HEIGHT - MARGIN,
+        }
+    }
+
+    fn text_at(&mut self, x: f64, y: f64, size: f64, text: &str) {
+        let _ = writeln!(self.content, "BT /F1 {} Tf {:.1} {:.1} Td ({}) Tj ET", size, x, y, escape(text));
+    }
+
+    /// Write a line at the cursor and move down
+    fn line(&mut self, size: f64, text: &str) {
+        self.text_at(MARGIN, self.y, size, text);
+        self.y -= size * 1.4;
+    }
+
+    fn heading(&mut self, text: &str) {
+        self.y -= 6.0;
+        self.line(14.0, text);
+    }
+
+    /// Write one table row with cells starting at the given x offsets
+    fn row(&mut self, columns: &[f64], cells: &[String]) {
+        for (x, cell) in columns.iter().zip(cells) {
+            self.text_at(MARGIN + x, self.y, 8.0, cell);
+        }
+        self.y -= 12.0;
+    }
+
+    fn polyline(&mut self, points: &[(f64, f64)]) {
+        for (i, (x, y)) in points.iter().enumerate() {
+            let op = if i == 0 { "m" } else { "l" };
+            let _ = writeln!(self.content, "{:.1} {:.1} {}", x, y, op);
+        }
+        self.content.push_str("S\n");
+    }
+
+    fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, fill: bool) {
+        let op = if fill { "f" } else { "S" };
+        let _ = writeln!(self.content, "{:.1} {:.1} {:.1} {:.1} re {}", x, y, width, height, op);
+    }
+}
+
+/// PDF string literal escaping; characters outside ASCII become '?'
+fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{}", c),
+            c if c.is_ascii() && !c.is_ascii_control() => c.to_string(),
+            _ => "?".to_string(),
+        })
+        .collect()
+}
+
+/// Serialize pages into a PDF file: catalog, page tree and font, then one
+/// page object and content stream per page, followed by the xref table
+fn render(pages: &[Page]) -> Vec<u8> {
+    let page_ids: Vec<usize> = (0..pages.len()).map(|i| 4 + i * 2).collect();
+    let kids = page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" ");
+
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, pages.len()),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+    ];
+    for (page, id) in pages.iter().zip(&page_ids) {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH,
+            PAGE_HEIGHT,
+            id + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            page.content.len(),
+            page.content
+        ));
+    }
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        let _ = write!(out, "{} 0 obj\n{}\nendobj\n", i + 1, object);
+    }
+    let xref = out.len();
+    let _ = write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(out, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        out,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    out.into_bytes()
+}
+
+fn money(value: f64) -> String {
+    if value < 0.0 {
+        format!("-${:.2}", -value)
+    } else {
+        format!("${:.2}", value)
+    }
+}
+
+fn rule_text(rule: Option<&StrategyRule>, condition: StrategyConditionType, threshold: f64) -> String {
+    match rule {
+        Some(rule) => {
+            let joiner = match rule {
+                StrategyRule::All(_) => " AND ",
+                StrategyRule::Any(_) => " OR ",
+            };
+            rule.conditions()
+                .iter()
+                .map(|c| format!("{} ({})", c.condition.as_str(), c.threshold))
+                .collect::<Vec<_>>()
+                .join(joiner)
+        }
+        None => format!("{} ({})", condition.as_str(), threshold),
+    }
+}
+
+fn optional_percent(value: Option<f64>) -> String {
+    value.map_or_else(|| "none".to_string(), |v| format!("{:.1}%", v))
+}
+
+/// Draw `curve` inside a box under the cursor, labelled with its range and dates
+fn equity_chart(page: &mut Page, curve: &[(NaiveDate, f64)]) {
+    if curve.len() < 2 {
+        page.line(10.0, "No equity curve was recorded for this backtest.");
+        return;
+    }
+
+    let (left, width, height) = (MARGIN + 60.0, PAGE_WIDTH - 2.0 * MARGIN - 60.0, 300.0);
+    let bottom = page.y - height;
+    let low = curve.iter().map(|(_, v)| *v).fold(f64::INFINITY, f64::min);
+    let high = curve.iter().map(|(_, v)| *v).fold(f64::NEG_INFINITY, f64::max);
+    let span = if high > low { high - low } else { 1.0 };
+
+    page.rect(left, bottom, width, height, false);
+    let last = (curve.len() - 1) as f64;
+    let points: Vec<(f64, f64)> = curve
+        .iter()
+        .enumerate()
+        .map(|(i, (_, v))| (left + width * i as f64 / last, bottom + height * (v - low) / span))
+        .collect();
+    page.polyline(&points);
+
+    page.text_at(MARGIN, bottom + height - 8.0, 8.0, &money(high));
+    page.text_at(MARGIN, bottom, 8.0, &money(low));
+    page.text_at(left, bottom - 14.0, 8.0, &curve[0].0.to_string());
+    page.text_at(left + width - 45.0, bottom - 14.0, 8.0, &curve[curve.len() - 1].0.to_string());
+    page.y = bottom - 30.0;
+}
+
+/// Pages of a backtest report: summary with strategy parameters and metrics,
+/// the equity curve, then the trade table
+fn backtest_pages(result: &BacktestResult, strategy: Option<&Strategy>) -> Vec<Page> {
+    let mut summary = Page::new();
+    summary.line(18.0, &format!("Backtest Report: {} on {}", result.strategy_name, result.symbol));
+    summary.line(10.0, &format!("{} to {}", result.start_date, result.end_date));
+
+    summary.heading("Strategy Parameters");
+    match strategy {
+        Some(s) => {
+            summary.line(10.0, &format!("Direction: {}", s.direction.as_str()));
+            summary.line(10.0, &format!("Entry: {}", rule_text(s.entry_rule.as_ref(), s.entry_condition, s.entry_threshold)));
+            summary.line(10.0, &format!("Exit: {}", rule_text(s.exit_rule.as_ref(), s.exit_condition, s.exit_threshold)));
+            summary.line(10.0, &format!("Position size: {:.1}% of capital", s.position_size_percent));
+            summary.line(10.0, &format!("Stop loss: {}", optional_percent(s.stop_loss_percent)));
+            summary.line(10.0, &format!("Take profit: {}", optional_percent(s.take_profit_percent)));
+            summary.line(10.0, &format!("Trailing stop: {}", optional_percent(s.trailing_stop_percent)));
+        }
+        None => summary.line(10.0, "The strategy has since been deleted."),
+    }
+
+    let m = &result.metrics;
+    summary.heading("Performance");
+    for text in [
+        format!("Initial capital: {}", money(result.initial_capital)),
+        format!("Final capital: {}", money(result.final_capital)),
+        format!("Total return: {:.2}% ({})", m.total_return, money(m.total_return_dollars)),
+        format!("Max drawdown: {:.2}%", m.max_drawdown),
+        format!("Sharpe ratio: {:.2}", m.sharpe_ratio),
+        format!("Win rate: {:.1}% ({} won, {} lost of {})", m.win_rate, m.winning_trades, m.losing_trades, m.total_trades),
+        format!("Average win: {:.2}%  Average loss: {:.2}%", m.avg_win_percent, m.avg_loss_percent),
+        format!("Profit factor: {:.2}", m.profit_factor),
+        format!("Average trade duration: {:.1} days", m.avg_trade_duration_days),
+    ] {
+        summary.line(10.0, &text);
+    }
+
+    let mut chart = Page::new();
+    chart.line(18.0, "Equity Curve");
+    chart.y -= 10.0;
+    equity_chart(&mut chart, &result.equity_curve);
+
+    let mut pages = vec![summary, chart];
+    let columns = [0.0, 60.0, 120.0, 160.0, 215.0, 270.0, 325.0, 390.0, 440.0];
+    let chunks: Vec<_> = result.trades.chunks(REPORT_ROWS_PER_PAGE).collect();
+    let table_pages = chunks.len().max(1);
+    for n in 0..table_pages {
+        let mut page = Page::new();
+        page.line(18.0, &format!("Trades ({} of {})", n + 1, table_pages));
+        page.y -= 4.0;
+        page.row(
+            &columns,
+            &["Entry", "Exit", "Side", "Shares", "Entry $", "Exit $", "P/L", "P/L %", "Exit reason"]
+                .map(String::from),
+        );
+        let Some(trades) = chunks.get(n) else {
+            page.line(10.0, "No trades.");
+            pages.push(page);
+            continue;
+        };
+        for t in *trades {
+            page.row(
+                &columns,
+                &[
+                    t.entry_date.to_string(),
+                    t.exit_date.map_or_else(|| "open".to_string(), |d| d.to_string()),
+                    t.direction.as_str().to_string(),
+                    format!("{:.2}", t.shares),
+                    format!("{:.2}", t.entry_price),
+                    t.exit_price.map_or_else(|| "-".to_string(), |p| format!("{:.2}", p)),
+                    t.profit_loss.map_or_else(|| "-".to_string(), money),
+                    t.profit_loss_percent.map_or_else(|| "-".to_string(), |p| format!("{:.2}%", p)),
+                    t.exit_reason.clone().unwrap_or_default(),
+                ],
+            );
+        }
+        pages.push(page);
+    }
+    pages
+}
+
+/// Render a stored backtest to a PDF at `path`, returning the page count
+pub fn write_backtest_report(db: &Database, backtest_id: i64, path: impl AsRef<Path>) -> Result<usize> {
+    let result = db
+        .get_backtest_detail(backtest_id)?
+        .ok_or_else(|| PipelineError::NoData(format!("backtest {}", backtest_id)))?;
+    let strategy = db.get_strategy(&result.strategy_name)?;
+
+    let pages = backtest_pages(&result, strategy.as_ref());
+    std::fs::write(path, render(&pages))?;
+    Ok(pages.len())
+}
+
+/// One position lot for the portfolio report. Shorts carry negative quantity and value.
+#[derive(Debug, Clone)]
+pub struct HoldingLine {
+    pub symbol: String,
+    pub quantity: f64,
+    pub cost_basis: f64,
+    pub current_value: f64,
+    pub profit_loss: f64,
+}
+
+/// Pages of a portfolio report: totals, then holdings per symbol with their share
+/// of gross exposure as a bar
+fn portfolio_pages(holdings: &[HoldingLine], as_of: NaiveDate) -> Vec<Page> {
+    let mut by_symbol: BTreeMap<&str, HoldingLine> = BTreeMap::new();
+    for h in holdings {
+        let entry = by_symbol.entry(&h.symbol).or_insert_with(|| HoldingLine {
+            symbol: h.symbol.clone(),
+            quantity: 0.0,
+            cost_basis: 0.0,
+            current_value: 0.0,
+            profit_loss: 0.0,
+        });
+        entry.quantity += h.quantity;
+        entry.cost_basis += h.cost_basis;
+        entry.current_value += h.current_value;
+        entry.profit_loss += h.profit_loss;
+    }
+    let mut rows: Vec<HoldingLine> = by_symbol.into_values().collect();
+    rows.sort_by(|a, b| b.current_value.abs().total_cmp(&a.current_value.abs()));
+
+    let gross: f64 = rows.iter().map(|h| h.current_value.abs()).sum();
+    let net: f64 = rows.iter().map(|h| h.current_value).sum();
+    let profit_loss: f64 = rows.iter().map(|h| h.profit_loss).sum();
+
+    let columns = [0.0, 60.0, 125.0, 200.0, 275.0, 345.0];
+    let chunks: Vec<_> = rows.chunks(REPORT_ROWS_PER_PAGE).collect();
+    let mut pages = Vec::new();
+    for n in 0..chunks.len().max(1) {
+        let mut page = Page::new();
+        if n == 0 {
+            page.line(18.0, "Portfolio Report");
+            page.line(10.0, &format!("As of {}", as_of));
+            page.line(10.0, &format!("Net value: {}  Gross exposure: {}", money(net), money(gross)));
+            page.line(10.0, &format!("Unrealized P/L: {}", money(profit_loss)));
+            page.heading("Holdings and Allocation");
+        }
+        page.row(&columns, &["Symbol", "Quantity", "Value", "P/L", "Allocation"].map(String::from));
+        let Some(chunk) = chunks.get(n) else {
+            page.line(10.0, "No open positions.");
+            pages.push(page);
+            continue;
+        };
+        for h in *chunk {
+            let share = if gross > 0.0 { h.current_value.abs() / gross } else { 0.0 };
+            page.rect(MARGIN + columns[5], page.y, 150.0 * share, 7.0, true);
+            page.row(
+                &columns,
+                &[
+                    h.symbol.clone(),
+                    format!("{:.2}", h.quantity),
+                    money(h.current_value),
+                    money(h.profit_loss),
+                    format!("{:.1}%", share * 100.0),
+                ],
+            );
+        }
+        pages.push(page);
+    }
+    pages
+}
+
+/// Render current holdings to a PDF at `path`, returning the page count
+pub fn write_portfolio_report(holdings: &[HoldingLine], as_of: NaiveDate, path: impl AsRef<Path>) -> Result<usize> {
+    let pages = portfolio_pages(holdings, as_of);
+    std::fs::write(path, render(&pages))?;
+    Ok(pages.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::BacktestEngine;
+    use crate::models::{BacktestTrade, DailyPrice, TradeDirection};
+
+    fn page_count(pdf: &[u8]) -> usize {
+        String::from_utf8_lossy(pdf).matches("/Type /Page /Parent").count()
+    }
+
+    #[test]
+    fn test_backtest_report_pages_trades() {
+        let db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+        let strategy = Strategy {
+            id: 0,
+            name: "report".to_string(),
+            description: None,
+            entry_condition: StrategyConditionType::RsiOversold,
+            entry_threshold: 30.0,
+            exit_condition: StrategyConditionType::RsiOverbought,
+            exit_threshold: 70.0,
+            stop_loss_percent: Some(5.0),
+            take_profit_percent: None,
+            trailing_stop_percent: None,
+            position_size_percent: 100.0,
+            direction: TradeDirection::Long,
+            entry_rule: None,
+            exit_rule: None,
+            created_at: String::new(),
+        };
+        let strategy_id = db.save_strategy(&strategy).unwrap();
+        let prices: Vec<DailyPrice> = [100.0, 90.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| DailyPrice {
+                symbol: "TEST".to_string(),
+                date: NaiveDate::from_ymd_opt(2026, 1, 5 + i as u32).unwrap(),
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: 1000,
+                adjusted_close: None,
+                source: "test".to_string(),
+            })
+            .collect();
+        let mut result = BacktestEngine::default().run(&strategy, "TEST", &prices, &[]);
+        result.strategy_id = strategy_id;
+        // One more trade than fits on a table page
+        result.trades = (0..=REPORT_ROWS_PER_PAGE)
+            .map(|i| BacktestTrade {
+                id: 0,
+                backtest_id: 0,
+                symbol: "TEST".to_string(),
+                direction: TradeDirection::Long,
+                entry_date: prices[0].date,
+                entry_price: 100.0,
+                exit_date: Some(prices[1].date),
+                exit_price: Some(90.0 + i as f64),
+                shares: 10.0,
+                entry_reason: "rsi_oversold (test)".to_string(),
+                exit_reason: Some("rsi_overbought".to_string()),
+                profit_loss: Some(-100.0 + 10.0 * i as f64),
+                profit_loss_percent: Some(-10.0 + i as f64),
+            })
+            .collect();
+        let id = db.save_backtest_result(&result).unwrap();
+
+        let path = std::env::temp_dir().join(format!("fp_backtest_report_{}.pdf", std::process::id()));
+        let pages = write_backtest_report(&db, id, &path).unwrap();
+        let pdf = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        // Summary, equity curve and two pages of trades
+        assert_eq!(pages, 4);
+        assert_eq!(page_count(&pdf), 4);
+        assert!(pdf.starts_with(b"%PDF-1.4"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        assert!(String::from_utf8_lossy(&pdf).contains("(Entry: rsi_oversold \\(30\\)) Tj"));
+
+        assert!(write_backtest_report(&db, id + 1, &path).is_err());
+    }
+
+    #[test]
+    fn test_portfolio_report_groups_lots_by_symbol() {
+        let lot = |symbol: &str, value: f64| HoldingLine {
+            symbol: symbol.to_string(),
+            quantity: 1.0,
+            cost_basis: value,
+            current_value: value,
+            profit_loss: 0.0,
+        };
+        let as_of = NaiveDate::from_ymd_opt(2026, 1, 5).unwrap();
+        let pages = portfolio_pages(&[lot("AAPL", 300.0), lot("MSFT", 200.0), lot("AAPL", 500.0)], as_of);
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].content.contains("(AAPL) Tj"));
+        assert!(pages[0].content.contains("(80.0%) Tj"));
+        assert_eq!(pages[0].content.matches("(AAPL) Tj").count(), 1);
+
+        assert_eq!(portfolio_pages(&[], as_of).len(), 1);
+    }
+}
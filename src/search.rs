@@ -0,0 +1,94 @@
+//! Fuzzy text matching for symbol search
+//!
+//! Scores how well a user query matches a company name, alias or ticker,
+//! tolerating typos via Levenshtein distance.
+
+/// Minimum score for a candidate to count as a match
+pub const MIN_MATCH_SCORE: f64 = 0.5;
+
+/// Edit distance (insertions, deletions, substitutions) between two strings
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// Similarity in 0.0..=1.0 derived from edit distance
+fn similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 0.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / longest as f64
+}
+
+/// Score a query against a candidate name, alias or ticker (case-insensitive).
+///
+/// Exact matches score 1.0, prefixes 0.9, whole-word matches 0.85 and substrings 0.75.
+/// Otherwise the best edit-distance similarity against the full candidate or any of
+/// its words is scaled to at most 0.8.
+pub fn match_score(query: &str, candidate: &str) -> f64 {
+    let query = query.trim().to_lowercase();
+    let candidate = candidate.trim().to_lowercase();
+    if query.is_empty() || candidate.is_empty() {
+        return 0.0;
+    }
+
+    if candidate == query {
+        return 1.0;
+    }
+    if candidate.starts_with(&query) {
+        return 0.9;
+    }
+
+    let words: Vec<&str> = candidate
+        .split(|c: char| !c.is_alphanumeric() && c != '&')
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.contains(&query.as_str()) {
+        return 0.85;
+    }
+    if query.len() >= 3 && candidate.contains(&query) {
+        return 0.75;
+    }
+
+    let best = words
+        .iter()
+        .map(|word| similarity(&query, word))
+        .fold(similarity(&query, &candidate), f64::max);
+    best * 0.8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("microsoft", "microsoft"), 0);
+        assert_eq!(levenshtein("microsft", "microsoft"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_match_score_ordering() {
+        assert_eq!(match_score("Apple", "apple"), 1.0);
+        assert!(match_score("gold", "goldman sachs") > match_score("sachs", "goldman sachs"));
+        assert!(match_score("sachs", "goldman sachs") > match_score("nvidai", "nvidia"));
+        assert!(match_score("nvidai", "nvidia") >= MIN_MATCH_SCORE);
+        assert!(match_score("tesla", "exxon") < MIN_MATCH_SCORE);
+    }
+}
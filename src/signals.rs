@@ -2,15 +2,90 @@
 //!
 //! Detects trading signals from technical indicators
 
+use crate::backtest::rule_met;
+use crate::error::{PipelineError, Result};
 use crate::models::{
     ConfluenceConfig, ConfluenceSignal, DailyPrice, IndicatorVote, Signal, SignalDirection,
-    SignalType, TechnicalIndicator,
+    SignalType, StrategyRule, TechnicalIndicator,
 };
 use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Indicators checked for price divergence by `generate_signals`
+pub const DIVERGENCE_INDICATORS: [&str; 3] = ["RSI_14", "MFI_14", "OBV"];
+
+/// Bars of history a new price extreme is compared against
+pub const DEFAULT_DIVERGENCE_LOOKBACK: usize = 20;
+
+/// Indicator gap (relative to its value at the prior extreme) that counts as full strength
+const DIVERGENCE_FULL_STRENGTH_GAP: f64 = 0.2;
+
+/// Multiple of the gap threshold at which a gap signal reaches full strength
+const GAP_FULL_STRENGTH_MULTIPLE: f64 = 3.0;
+
+/// Bars of Bollinger bandwidth history a squeeze must undercut (about six months)
+pub const SQUEEZE_LOOKBACK: usize = 120;
+
+/// An overnight gap: a bar that opened `gap_percent` away from the prior close
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceGap {
+    pub date: NaiveDate,
+    /// Bullish for a gap up, bearish for a gap down
+    pub direction: SignalDirection,
+    /// Prior close; the gap is filled once price trades back to it
+    pub prev_close: f64,
+    pub open: f64,
+    pub gap_percent: f64,
+}
+
+/// Open-vs-prior-close gaps of at least `min_pct` percent in date-sorted bars
+fn find_gaps(prices: &[DailyPrice], min_pct: f64) -> impl Iterator<Item = (usize, PriceGap)> + '_ {
+    prices.windows(2).enumerate().filter_map(move |(i, pair)| {
+        let (prev, bar) = (&pair[0], &pair[1]);
+        if prev.close <= 0.0 {
+            return None;
+        }
+        let gap_percent = (bar.open - prev.close) / prev.close * 100.0;
+        if gap_percent.abs() < min_pct {
+            return None;
+        }
+        let direction = if gap_percent > 0.0 {
+            SignalDirection::Bullish
+        } else {
+            SignalDirection::Bearish
+        };
+        Some((
+            i + 1,
+            PriceGap {
+                date: bar.date,
+                direction,
+                prev_close: prev.close,
+                open: bar.open,
+                gap_percent,
+            },
+        ))
+    })
+}
+
+/// Gaps of at least `min_pct` percent that price hasn't traded back through: no bar
+/// from the gap bar on has a low (gap up) or high (gap down) reaching the prior close.
+/// `prices` must be sorted by date; gaps are returned oldest first.
+pub fn find_unfilled_gaps(prices: &[DailyPrice], min_pct: f64) -> Vec<PriceGap> {
+    find_gaps(prices, min_pct)
+        .filter(|(start, gap)| {
+            let later = &prices[*start..];
+            match gap.direction {
+                SignalDirection::Bullish => later.iter().all(|p| p.low > gap.prev_close),
+                _ => later.iter().all(|p| p.high < gap.prev_close),
+            }
+        })
+        .map(|(_, gap)| gap)
+        .collect()
+}
+
 /// Configuration for signal detection thresholds
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SignalConfig {
     pub rsi_overbought: f64,
     pub rsi_oversold: f64,
@@ -24,6 +99,40 @@ pub struct SignalConfig {
     pub cci_oversold: f64,
     pub mfi_overbought: f64,
     pub mfi_oversold: f64,
+    /// Minimum MACD/signal spread for a crossover to count (0 = any cross)
+    #[serde(default)]
+    pub macd_min_spread: f64,
+    #[serde(default = "default_uo_overbought")]
+    pub uo_overbought: f64,
+    #[serde(default = "default_uo_oversold")]
+    pub uo_oversold: f64,
+    /// Open-vs-prior-close move, in percent, that counts as a gap
+    #[serde(default = "default_gap_min_percent")]
+    pub gap_min_percent: f64,
+    #[serde(default = "default_stoch_rsi_overbought")]
+    pub stoch_rsi_overbought: f64,
+    #[serde(default = "default_stoch_rsi_oversold")]
+    pub stoch_rsi_oversold: f64,
+}
+
+fn default_uo_overbought() -> f64 {
+    70.0
+}
+
+fn default_uo_oversold() -> f64 {
+    30.0
+}
+
+fn default_gap_min_percent() -> f64 {
+    2.0
+}
+
+fn default_stoch_rsi_overbought() -> f64 {
+    80.0
+}
+
+fn default_stoch_rsi_oversold() -> f64 {
+    20.0
 }
 
 impl Default for SignalConfig {
@@ -41,7 +150,98 @@ impl Default for SignalConfig {
             cci_oversold: -100.0,
             mfi_overbought: 80.0,
             mfi_oversold: 20.0,
+            macd_min_spread: 0.0,
+            uo_overbought: default_uo_overbought(),
+            uo_oversold: default_uo_oversold(),
+            gap_min_percent: default_gap_min_percent(),
+            stoch_rsi_overbought: default_stoch_rsi_overbought(),
+            stoch_rsi_oversold: default_stoch_rsi_oversold(),
+        }
+    }
+}
+
+impl SignalConfig {
+    /// Reject configs whose oversold/overbought (or weak/strong) levels are inverted
+    pub fn validate(&self) -> Result<()> {
+        let pairs = [
+            ("RSI", self.rsi_oversold, self.rsi_overbought),
+            ("Stochastic", self.stoch_oversold, self.stoch_overbought),
+            ("Williams %R", self.willr_oversold, self.willr_overbought),
+            ("CCI", self.cci_oversold, self.cci_overbought),
+            ("MFI", self.mfi_oversold, self.mfi_overbought),
+            ("Ultimate Oscillator", self.uo_oversold, self.uo_overbought),
+            ("Stochastic RSI", self.stoch_rsi_oversold, self.stoch_rsi_overbought),
+        ];
+        for (name, oversold, overbought) in pairs {
+            if oversold >= overbought {
+                return Err(PipelineError::Config(format!(
+                    "{} oversold level ({}) must be below overbought level ({})",
+                    name, oversold, overbought
+                )));
+            }
         }
+        if self.adx_weak_trend >= self.adx_strong_trend {
+            return Err(PipelineError::Config(format!(
+                "ADX weak trend level ({}) must be below strong trend level ({})",
+                self.adx_weak_trend, self.adx_strong_trend
+            )));
+        }
+        if self.macd_min_spread < 0.0 {
+            return Err(PipelineError::Config(format!(
+                "MACD minimum spread ({}) cannot be negative",
+                self.macd_min_spread
+            )));
+        }
+        if self.gap_min_percent <= 0.0 {
+            return Err(PipelineError::Config(format!(
+                "Gap threshold ({}%) must be positive",
+                self.gap_min_percent
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Per signal type multipliers applied to detected strength, keyed by `SignalType::as_str`.
+/// Types without an entry keep a weight of 1.0; weighted strength is capped at 1.0.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SignalWeights(HashMap<String, f64>);
+
+impl SignalWeights {
+    /// Multiplier for `signal_type`
+    pub fn get(&self, signal_type: SignalType) -> f64 {
+        self.0.get(signal_type.as_str()).copied().unwrap_or(1.0)
+    }
+
+    pub fn set(&mut self, signal_type: SignalType, weight: f64) {
+        self.0.insert(signal_type.as_str().to_string(), weight);
+    }
+
+    /// Explicitly weighted signal types and their multipliers
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.0.iter().map(|(name, weight)| (name.as_str(), *weight))
+    }
+
+    /// Reject unknown signal types and negative or non-finite weights
+    pub fn validate(&self) -> Result<()> {
+        for (name, weight) in self.iter() {
+            if SignalType::from_str(name).is_none() {
+                return Err(PipelineError::Config(format!("Unknown signal type: {}", name)));
+            }
+            if !weight.is_finite() || weight < 0.0 {
+                return Err(PipelineError::Config(format!(
+                    "Weight for {} ({}) must be a non-negative number",
+                    name, weight
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Scale a signal's strength by its type's weight
+    pub fn apply(&self, signal: &mut Signal) {
+        signal.strength = (signal.strength * self.get(signal.signal_type)).clamp(0.0, 1.0);
     }
 }
 
@@ -49,6 +249,7 @@ impl Default for SignalConfig {
 pub struct SignalEngine {
     config: SignalConfig,
     confluence_config: ConfluenceConfig,
+    weights: SignalWeights,
 }
 
 impl Default for SignalEngine {
@@ -62,6 +263,7 @@ impl SignalEngine {
         Self {
             config: SignalConfig::default(),
             confluence_config: ConfluenceConfig::default(),
+            weights: SignalWeights::default(),
         }
     }
 
@@ -69,6 +271,7 @@ impl SignalEngine {
         Self {
             config,
             confluence_config: ConfluenceConfig::default(),
+            weights: SignalWeights::default(),
         }
     }
 
@@ -77,6 +280,11 @@ impl SignalEngine {
         self
     }
 
+    pub fn with_weights(mut self, weights: SignalWeights) -> Self {
+        self.weights = weights;
+        self
+    }
+
     /// Build a map of indicators by date for O(1) lookups
     fn build_indicator_map(
         &self,
@@ -190,11 +398,250 @@ impl SignalEngine {
             {
                 signals.push(sig);
             }
+
+            // Ultimate Oscillator signals
+            if let Some(sig) =
+                self.detect_uo_signal(symbol, *date, price, indicators_today, indicators_prev)
+            {
+                signals.push(sig);
+            }
+
+            // Stochastic RSI signals
+            if let Some(sig) =
+                self.detect_stoch_rsi_signal(symbol, *date, price, indicators_today, indicators_prev)
+            {
+                signals.push(sig);
+            }
+
+            // Parabolic SAR reversals
+            if let Some(sig) =
+                self.detect_psar_signal(symbol, *date, price, indicators_today, indicators_prev)
+            {
+                signals.push(sig);
+            }
+
+            // Aroon crossovers
+            if let Some(sig) =
+                self.detect_aroon_signal(symbol, *date, price, indicators_today, indicators_prev)
+            {
+                signals.push(sig);
+            }
+
+            // Donchian channel breakouts
+            if let Some(sig) = self.detect_donchian_signal(symbol, *date, price, indicators_prev) {
+                signals.push(sig);
+            }
+
+            // Supertrend direction changes
+            if let Some(sig) =
+                self.detect_supertrend_signal(symbol, *date, price, indicators_today, indicators_prev)
+            {
+                signals.push(sig);
+            }
         }
 
+        // Price/oscillator divergences
+        for name in DIVERGENCE_INDICATORS {
+            let series: Vec<TechnicalIndicator> = indicators
+                .iter()
+                .filter(|ind| ind.indicator_name == name)
+                .cloned()
+                .collect();
+            signals.extend(self.detect_divergence(prices, &series, DEFAULT_DIVERGENCE_LOOKBACK));
+        }
+
+        // Overnight gaps
+        signals.extend(self.detect_gap_signals(symbol, prices));
+
+        // Volatility squeezes
+        let mut bandwidth: Vec<&TechnicalIndicator> = indicators
+            .iter()
+            .filter(|ind| ind.indicator_name == "BB_BANDWIDTH_20")
+            .collect();
+        bandwidth.sort_by_key(|ind| ind.date);
+        signals.extend(self.detect_squeeze_signals(symbol, &bandwidth, &price_map, SQUEEZE_LOOKBACK));
+
+        for signal in &mut signals {
+            self.weights.apply(signal);
+        }
         signals
     }
 
+    /// Detect the first bar on which Bollinger bandwidth drops to its lowest in the
+    /// previous `lookback` bars. Later bars that keep making new lows extend the same
+    /// squeeze rather than firing again. Strength is how far below the lookback
+    /// average the bandwidth has contracted.
+    fn detect_squeeze_signals(
+        &self,
+        symbol: &str,
+        bandwidth: &[&TechnicalIndicator],
+        price_map: &HashMap<NaiveDate, &DailyPrice>,
+        lookback: usize,
+    ) -> Vec<Signal> {
+        let at_low = |i: usize| {
+            i >= lookback
+                && bandwidth[i - lookback..i]
+                    .iter()
+                    .all(|prior| bandwidth[i].value < prior.value)
+        };
+
+        (lookback..bandwidth.len())
+            .filter(|&i| at_low(i) && !at_low(i - 1))
+            .map(|i| {
+                let today = bandwidth[i];
+                let window = &bandwidth[i - lookback..i];
+                let average = window.iter().map(|ind| ind.value).sum::<f64>() / lookback as f64;
+                Signal {
+                    id: 0,
+                    symbol: symbol.to_string(),
+                    signal_type: SignalType::BollingerSqueeze,
+                    direction: SignalDirection::Neutral, // Breakout direction is unknown
+                    strength: if average > 0.0 { (1.0 - today.value / average).clamp(0.0, 1.0) } else { 0.0 },
+                    price_at_signal: price_map.get(&today.date).map(|p| p.close).unwrap_or(0.0),
+                    triggered_by: today.indicator_name.clone(),
+                    trigger_value: today.value,
+                    timestamp: today.date,
+                    created_at: String::new(),
+                    acknowledged: false,
+                }
+            })
+            .collect()
+    }
+
+    /// Detect opens at least `gap_min_percent` beyond the prior close
+    fn detect_gap_signals(&self, symbol: &str, prices: &[DailyPrice]) -> Vec<Signal> {
+        let threshold = self.config.gap_min_percent;
+        find_gaps(prices, threshold)
+            .map(|(i, gap)| {
+                let signal_type = if gap.direction == SignalDirection::Bullish {
+                    SignalType::GapUp
+                } else {
+                    SignalType::GapDown
+                };
+                Signal {
+                    id: 0,
+                    symbol: symbol.to_string(),
+                    signal_type,
+                    direction: gap.direction,
+                    strength: (gap.gap_percent.abs() / (threshold * GAP_FULL_STRENGTH_MULTIPLE)).min(1.0),
+                    price_at_signal: prices[i].close,
+                    triggered_by: "GAP_PERCENT".to_string(),
+                    trigger_value: gap.gap_percent,
+                    timestamp: gap.date,
+                    created_at: String::new(),
+                    acknowledged: false,
+                }
+            })
+            .collect()
+    }
+
+    /// Detect divergences between price and a single indicator series.
+    ///
+    /// A close above every close in the previous `lookback` bars while the indicator
+    /// sits below its value at that prior high is a bearish divergence; the mirror
+    /// image at a new low is bullish. Strength scales with the indicator gap.
+    pub fn detect_divergence(
+        &self,
+        prices: &[DailyPrice],
+        indicator_series: &[TechnicalIndicator],
+        lookback: usize,
+    ) -> Vec<Signal> {
+        let Some(name) = indicator_series.first().map(|ind| ind.indicator_name.as_str()) else {
+            return vec![];
+        };
+        if lookback == 0 {
+            return vec![];
+        }
+        let values: HashMap<NaiveDate, f64> = indicator_series
+            .iter()
+            .filter(|ind| ind.indicator_name == name)
+            .map(|ind| (ind.date, ind.value))
+            .collect();
+
+        let mut aligned: Vec<(&DailyPrice, f64)> = prices
+            .iter()
+            .filter_map(|p| values.get(&p.date).map(|&v| (p, v)))
+            .collect();
+        aligned.sort_by_key(|(p, _)| p.date);
+
+        let mut signals = Vec::new();
+        for i in lookback..aligned.len() {
+            let (bar, value) = aligned[i];
+            let window = &aligned[i - lookback..i];
+            let (high, high_value) = window
+                .iter()
+                .max_by(|a, b| a.0.close.total_cmp(&b.0.close))
+                .map(|(p, v)| (p.close, *v))
+                .expect("window is non-empty");
+            let (low, low_value) = window
+                .iter()
+                .min_by(|a, b| a.0.close.total_cmp(&b.0.close))
+                .map(|(p, v)| (p.close, *v))
+                .expect("window is non-empty");
+
+            let (signal_type, direction, reference) = if bar.close > high && value < high_value {
+                (SignalType::BearishDivergence, SignalDirection::Bearish, high_value)
+            } else if bar.close < low && value > low_value {
+                (SignalType::BullishDivergence, SignalDirection::Bullish, low_value)
+            } else {
+                continue;
+            };
+            let gap = (value - reference).abs();
+            let strength = if reference.abs() > f64::EPSILON {
+                (gap / reference.abs() / DIVERGENCE_FULL_STRENGTH_GAP).min(1.0)
+            } else {
+                1.0
+            };
+
+            signals.push(Signal {
+                id: 0,
+                symbol: bar.symbol.clone(),
+                signal_type,
+                direction,
+                strength,
+                price_at_signal: bar.close,
+                triggered_by: name.to_string(),
+                trigger_value: value,
+                timestamp: bar.date,
+                created_at: String::new(),
+                acknowledged: false,
+            });
+        }
+
+        signals
+    }
+
+    /// Dates on which a strategy rule holds, evaluated the same way as in backtests
+    pub fn evaluate_rule(
+        &self,
+        rule: &StrategyRule,
+        indicators: &[TechnicalIndicator],
+        prices: &[DailyPrice],
+    ) -> Vec<NaiveDate> {
+        let indicator_map = self.build_indicator_map(indicators);
+        let price_map: HashMap<NaiveDate, f64> = prices.iter().map(|p| (p.date, p.close)).collect();
+
+        let mut dates: Vec<_> = indicator_map.keys().copied().collect();
+        dates.sort();
+
+        dates
+            .iter()
+            .enumerate()
+            .filter(|(i, date)| {
+                let Some(&price) = price_map.get(date) else {
+                    return false;
+                };
+                let prev = if *i > 0 {
+                    indicator_map.get(&dates[i - 1])
+                } else {
+                    None
+                };
+                rule_met(rule, price, &indicator_map[date], prev)
+            })
+            .map(|(_, date)| *date)
+            .collect()
+    }
+
     /// Detect RSI overbought/oversold signals
     fn detect_rsi_signal(
         &self,
@@ -259,9 +706,13 @@ impl SignalEngine {
         prev: Option<&HashMap<String, f64>>,
     ) -> Option<Signal> {
         let macd = *today.get("MACD_12_26")?;
-        let signal = *today.get("MACD_SIGNAL_9")?;
+        let signal = *today.get("MACD_SIGNAL_12_26_9")?;
         let prev_macd = prev.and_then(|p| p.get("MACD_12_26").copied())?;
-        let prev_signal = prev.and_then(|p| p.get("MACD_SIGNAL_9").copied())?;
+        let prev_signal = prev.and_then(|p| p.get("MACD_SIGNAL_12_26_9").copied())?;
+
+        if (macd - signal).abs() < self.config.macd_min_spread {
+            return None;
+        }
 
         // Bullish crossover: MACD crosses above signal
         if prev_macd <= prev_signal && macd > signal {
@@ -467,9 +918,9 @@ impl SignalEngine {
         prev: Option<&HashMap<String, f64>>,
     ) -> Option<Signal> {
         let k = *today.get("STOCH_K_14")?;
-        let d = *today.get("STOCH_D_3")?;
+        let d = *today.get("STOCH_D_14_3")?;
         let prev_k = prev.and_then(|p| p.get("STOCH_K_14").copied())?;
-        let prev_d = prev.and_then(|p| p.get("STOCH_D_3").copied())?;
+        let prev_d = prev.and_then(|p| p.get("STOCH_D_14_3").copied())?;
 
         // Bullish crossover from oversold
         if prev_k <= prev_d && k > d && k < self.config.stoch_oversold + 20.0 {
@@ -671,24 +1122,269 @@ impl SignalEngine {
         None
     }
 
-    // ========================================================================
-    // Confluence Signal Detection
-    // ========================================================================
+    /// Detect Stochastic RSI overbought/oversold signals (on %K)
+    fn detect_stoch_rsi_signal(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        price: f64,
+        today: &HashMap<String, f64>,
+        prev: Option<&HashMap<String, f64>>,
+    ) -> Option<Signal> {
+        let k = *today.get("STOCHRSI_K_14_14_3")?;
+        let prev_k = prev.and_then(|p| p.get("STOCHRSI_K_14_14_3").copied());
 
-    /// Detect confluence signal when 3+ indicators agree on direction
-    /// Returns ConfluenceSignal if enough indicators agree, None otherwise
-    pub fn detect_confluence_signal(
+        let (signal_type, direction, strength) = if k > self.config.stoch_rsi_overbought {
+            if prev_k.is_some_and(|p| p > self.config.stoch_rsi_overbought) {
+                return None;
+            }
+            let strength = ((k - self.config.stoch_rsi_overbought) / 20.0).min(1.0);
+            (SignalType::StochRsiOverbought, SignalDirection::Bearish, strength)
+        } else if k < self.config.stoch_rsi_oversold {
+            if prev_k.is_some_and(|p| p < self.config.stoch_rsi_oversold) {
+                return None;
+            }
+            let strength = ((self.config.stoch_rsi_oversold - k) / 20.0).min(1.0);
+            (SignalType::StochRsiOversold, SignalDirection::Bullish, strength)
+        } else {
+            return None;
+        };
+
+        Some(Signal {
+            id: 0,
+            symbol: symbol.to_string(),
+            signal_type,
+            direction,
+            strength,
+            price_at_signal: price,
+            triggered_by: "STOCHRSI_K_14_14_3".to_string(),
+            trigger_value: k,
+            timestamp: date,
+            created_at: String::new(),
+            acknowledged: false,
+        })
+    }
+
+    /// Detect Ultimate Oscillator signals
+    fn detect_uo_signal(
         &self,
         symbol: &str,
         date: NaiveDate,
         price: f64,
-        indicators: &HashMap<String, f64>,
-    ) -> Option<ConfluenceSignal> {
+        today: &HashMap<String, f64>,
+        prev: Option<&HashMap<String, f64>>,
+    ) -> Option<Signal> {
+        let uo = *today.get("UO_7_14_28")?;
+        let prev_uo = prev.and_then(|p| p.get("UO_7_14_28").copied());
+
+        let (signal_type, direction, strength) = if uo > self.config.uo_overbought {
+            if prev_uo.is_some_and(|p| p > self.config.uo_overbought) {
+                return None;
+            }
+            let strength = ((uo - self.config.uo_overbought) / 20.0).min(1.0);
+            (SignalType::UoOverbought, SignalDirection::Bearish, strength)
+        } else if uo < self.config.uo_oversold {
+            if prev_uo.is_some_and(|p| p < self.config.uo_oversold) {
+                return None;
+            }
+            let strength = ((self.config.uo_oversold - uo) / 20.0).min(1.0);
+            (SignalType::UoOversold, SignalDirection::Bullish, strength)
+        } else {
+            return None;
+        };
+
+        Some(Signal {
+            id: 0,
+            symbol: symbol.to_string(),
+            signal_type,
+            direction,
+            strength,
+            price_at_signal: price,
+            triggered_by: "UO_7_14_28".to_string(),
+            trigger_value: uo,
+            timestamp: date,
+            created_at: String::new(),
+            acknowledged: false,
+        })
+    }
+
+    /// Detect Parabolic SAR flips (PSAR_TREND changing sign)
+    fn detect_psar_signal(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        price: f64,
+        today: &HashMap<String, f64>,
+        prev: Option<&HashMap<String, f64>>,
+    ) -> Option<Signal> {
+        let trend = *today.get("PSAR_TREND")?;
+        let prev_trend = *prev?.get("PSAR_TREND")?;
+        if trend == prev_trend {
+            return None;
+        }
+
+        let sar = *today.get("PSAR")?;
+        let direction = if trend > 0.0 {
+            SignalDirection::Bullish
+        } else {
+            SignalDirection::Bearish
+        };
+        // A SAR 2% or more away from price counts as a full-strength reversal
+        let strength = if price > 0.0 {
+            ((price - sar).abs() / price / 0.02).min(1.0)
+        } else {
+            0.5
+        };
+
+        Some(Signal {
+            id: 0,
+            symbol: symbol.to_string(),
+            signal_type: SignalType::PsarFlip,
+            direction,
+            strength,
+            price_at_signal: price,
+            triggered_by: "PSAR".to_string(),
+            trigger_value: sar,
+            timestamp: date,
+            created_at: String::new(),
+            acknowledged: false,
+        })
+    }
+
+    /// Detect Aroon Up/Down crossovers
+    fn detect_aroon_signal(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        price: f64,
+        today: &HashMap<String, f64>,
+        prev: Option<&HashMap<String, f64>>,
+    ) -> Option<Signal> {
+        let up = *today.get("AROON_UP_25")?;
+        let down = *today.get("AROON_DOWN_25")?;
+        let prev = prev?;
+        let prev_up = *prev.get("AROON_UP_25")?;
+        let prev_down = *prev.get("AROON_DOWN_25")?;
+
+        let (signal_type, direction, trigger_value) = if prev_up <= prev_down && up > down {
+            (SignalType::AroonBullish, SignalDirection::Bullish, up)
+        } else if prev_up >= prev_down && up < down {
+            (SignalType::AroonBearish, SignalDirection::Bearish, down)
+        } else {
+            return None;
+        };
+
+        Some(Signal {
+            id: 0,
+            symbol: symbol.to_string(),
+            signal_type,
+            direction,
+            // Full strength when one line is at 100 and the other at 0
+            strength: ((up - down).abs() / 100.0).min(1.0),
+            price_at_signal: price,
+            triggered_by: if direction == SignalDirection::Bullish {
+                "AROON_UP_25".to_string()
+            } else {
+                "AROON_DOWN_25".to_string()
+            },
+            trigger_value,
+            timestamp: date,
+            created_at: String::new(),
+            acknowledged: false,
+        })
+    }
+
+    /// Detect Supertrend flips (SUPERTREND_DIR_10 changing sign)
+    fn detect_supertrend_signal(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        price: f64,
+        today: &HashMap<String, f64>,
+        prev: Option<&HashMap<String, f64>>,
+    ) -> Option<Signal> {
+        let dir = *today.get("SUPERTREND_DIR_10")?;
+        if dir == *prev?.get("SUPERTREND_DIR_10")? {
+            return None;
+        }
+
+        let line = *today.get("SUPERTREND_10")?;
+        let direction = if dir > 0.0 {
+            SignalDirection::Bullish
+        } else {
+            SignalDirection::Bearish
+        };
+        // The new band starts 3 ATRs from the midpoint; a close 5% clear of it is decisive
+        let strength = if price > 0.0 {
+            ((price - line).abs() / price / 0.05).min(1.0)
+        } else {
+            0.5
+        };
+
+        Some(Signal {
+            id: 0,
+            symbol: symbol.to_string(),
+            signal_type: SignalType::SupertrendFlip,
+            direction,
+            strength,
+            price_at_signal: price,
+            triggered_by: "SUPERTREND_10".to_string(),
+            trigger_value: line,
+            timestamp: date,
+            created_at: String::new(),
+            acknowledged: false,
+        })
+    }
+
+    /// Detect closes beyond the prior bar's Donchian channel (a new 20-bar high or low)
+    fn detect_donchian_signal(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        price: f64,
+        prev: Option<&HashMap<String, f64>>,
+    ) -> Option<Signal> {
+        let prev = prev?;
+        let upper = *prev.get("DONCHIAN_UPPER_20")?;
+        let lower = *prev.get("DONCHIAN_LOWER_20")?;
+        let width = upper - lower;
+
+        let (direction, triggered_by, level) = if price > upper {
+            (SignalDirection::Bullish, "DONCHIAN_UPPER_20", upper)
+        } else if price < lower {
+            (SignalDirection::Bearish, "DONCHIAN_LOWER_20", lower)
+        } else {
+            return None;
+        };
+        // A close a quarter of the channel width beyond the band is a full-strength breakout
+        let strength = if width > 0.0 {
+            ((price - level).abs() / (width * 0.25)).min(1.0)
+        } else {
+            0.5
+        };
+
+        Some(Signal {
+            id: 0,
+            symbol: symbol.to_string(),
+            signal_type: SignalType::DonchianBreakout,
+            direction,
+            strength,
+            price_at_signal: price,
+            triggered_by: triggered_by.to_string(),
+            trigger_value: level,
+            timestamp: date,
+            created_at: String::new(),
+            acknowledged: false,
+        })
+    }
+
+    // ========================================================================
+    // Confluence Signal Detection
+    // ========================================================================
+
+    /// Directional votes from each oscillator/band indicator on a single bar
+    fn collect_votes(&self, price: f64, indicators: &HashMap<String, f64>) -> Vec<IndicatorVote> {
         let mut votes: Vec<IndicatorVote> = Vec::new();
-        let mut bullish_count = 0usize;
-        let mut bearish_count = 0usize;
-        let mut bullish_strength_sum = 0.0f64;
-        let mut bearish_strength_sum = 0.0f64;
 
         // RSI vote
         if let Some(&rsi) = indicators.get("RSI_14") {
@@ -700,8 +1396,6 @@ impl SignalEngine {
                     strength,
                     value: rsi,
                 });
-                bullish_count += 1;
-                bullish_strength_sum += strength;
             } else if rsi > self.confluence_config.rsi_overbought {
                 let strength = ((rsi - self.confluence_config.rsi_overbought) / 30.0).min(1.0);
                 votes.push(IndicatorVote {
@@ -710,15 +1404,13 @@ impl SignalEngine {
                     strength,
                     value: rsi,
                 });
-                bearish_count += 1;
-                bearish_strength_sum += strength;
             }
         }
 
         // MACD vote (bullish if MACD > Signal, bearish if MACD < Signal)
         if let (Some(&macd), Some(&signal)) = (
             indicators.get("MACD_12_26"),
-            indicators.get("MACD_SIGNAL_9"),
+            indicators.get("MACD_SIGNAL_12_26_9"),
         ) {
             let diff = macd - signal;
             if diff > 0.0 {
@@ -729,8 +1421,6 @@ impl SignalEngine {
                     strength,
                     value: macd,
                 });
-                bullish_count += 1;
-                bullish_strength_sum += strength;
             } else if diff < 0.0 {
                 let strength = (diff.abs() / price.max(1.0) * 100.0).min(1.0);
                 votes.push(IndicatorVote {
@@ -739,8 +1429,6 @@ impl SignalEngine {
                     strength,
                     value: macd,
                 });
-                bearish_count += 1;
-                bearish_strength_sum += strength;
             }
         }
 
@@ -758,8 +1446,6 @@ impl SignalEngine {
                     strength,
                     value: price,
                 });
-                bullish_count += 1;
-                bullish_strength_sum += strength;
             } else if price > upper {
                 let middle = (upper + lower) / 2.0;
                 let strength = ((price - upper) / (upper - middle).max(0.01)).min(1.0);
@@ -769,8 +1455,6 @@ impl SignalEngine {
                     strength,
                     value: price,
                 });
-                bearish_count += 1;
-                bearish_strength_sum += strength;
             }
         }
 
@@ -785,8 +1469,6 @@ impl SignalEngine {
                     strength,
                     value: stoch_k,
                 });
-                bullish_count += 1;
-                bullish_strength_sum += strength;
             } else if stoch_k > self.confluence_config.stoch_overbought {
                 let strength =
                     ((stoch_k - self.confluence_config.stoch_overbought) / 20.0).min(1.0);
@@ -796,8 +1478,6 @@ impl SignalEngine {
                     strength,
                     value: stoch_k,
                 });
-                bearish_count += 1;
-                bearish_strength_sum += strength;
             }
         }
 
@@ -812,8 +1492,6 @@ impl SignalEngine {
                     strength,
                     value: cci,
                 });
-                bullish_count += 1;
-                bullish_strength_sum += strength;
             } else if cci > self.confluence_config.cci_overbought {
                 let strength =
                     ((cci - self.confluence_config.cci_overbought) / 100.0).abs().min(1.0);
@@ -823,8 +1501,37 @@ impl SignalEngine {
                     strength,
                     value: cci,
                 });
-                bearish_count += 1;
-                bearish_strength_sum += strength;
+            }
+        }
+
+        votes
+    }
+
+    /// Detect confluence signal when 3+ indicators agree on direction
+    /// Returns ConfluenceSignal if enough indicators agree, None otherwise
+    pub fn detect_confluence_signal(
+        &self,
+        symbol: &str,
+        date: NaiveDate,
+        price: f64,
+        indicators: &HashMap<String, f64>,
+    ) -> Option<ConfluenceSignal> {
+        let votes = self.collect_votes(price, indicators);
+        let mut bullish_count = 0usize;
+        let mut bearish_count = 0usize;
+        let mut bullish_strength_sum = 0.0f64;
+        let mut bearish_strength_sum = 0.0f64;
+        for vote in &votes {
+            match vote.direction {
+                SignalDirection::Bullish => {
+                    bullish_count += 1;
+                    bullish_strength_sum += vote.strength;
+                }
+                SignalDirection::Bearish => {
+                    bearish_count += 1;
+                    bearish_strength_sum += vote.strength;
+                }
+                SignalDirection::Neutral => {}
             }
         }
 
@@ -898,6 +1605,44 @@ impl SignalEngine {
 
         (individual_signals, confluence_signals)
     }
+
+    /// Net agreement of indicator votes on the latest bar, in -1.0 (all bearish) to 1.0 (all bullish).
+    ///
+    /// Votes are weighted by strength; fewer votes than `min_agreeing_indicators`
+    /// scale the score down proportionally.
+    pub fn confluence_score(
+        &self,
+        indicators: &[TechnicalIndicator],
+        prices: &[DailyPrice],
+    ) -> f64 {
+        let indicator_map = self.build_indicator_map(indicators);
+        let Some((date, latest)) = indicator_map.iter().max_by_key(|(date, _)| **date) else {
+            return 0.0;
+        };
+        let price = prices
+            .iter()
+            .find(|p| p.date == *date)
+            .map(|p| p.close)
+            .unwrap_or(0.0);
+
+        let votes = self.collect_votes(price, latest);
+        let total: f64 = votes.iter().map(|v| v.strength).sum();
+        if total <= 0.0 {
+            return 0.0;
+        }
+        let net: f64 = votes
+            .iter()
+            .map(|v| match v.direction {
+                SignalDirection::Bullish => v.strength,
+                SignalDirection::Bearish => -v.strength,
+                SignalDirection::Neutral => 0.0,
+            })
+            .sum();
+
+        let min_required = self.confluence_config.min_agreeing_indicators.max(1) as f64;
+        let coverage = (votes.len() as f64 / min_required).min(1.0);
+        (net / total * coverage).clamp(-1.0, 1.0)
+    }
 }
 
 #[cfg(test)]
@@ -914,7 +1659,7 @@ mod tests {
         let mut indicators = HashMap::new();
         indicators.insert("RSI_14".to_string(), 25.0); // Below 30 = Bullish
         indicators.insert("MACD_12_26".to_string(), 1.5); // MACD > Signal = Bullish
-        indicators.insert("MACD_SIGNAL_9".to_string(), 1.0);
+        indicators.insert("MACD_SIGNAL_12_26_9".to_string(), 1.0);
         indicators.insert("STOCH_K_14".to_string(), 15.0); // Below 20 = Bullish
         indicators.insert("CCI_20".to_string(), -150.0); // Below -100 = Bullish
         indicators.insert("BB_UPPER_20".to_string(), 110.0);
@@ -943,7 +1688,7 @@ mod tests {
         let mut indicators = HashMap::new();
         indicators.insert("RSI_14".to_string(), 80.0); // Above 70 = Bearish
         indicators.insert("MACD_12_26".to_string(), 0.5); // MACD < Signal = Bearish
-        indicators.insert("MACD_SIGNAL_9".to_string(), 1.0);
+        indicators.insert("MACD_SIGNAL_12_26_9".to_string(), 1.0);
         indicators.insert("STOCH_K_14".to_string(), 85.0); // Above 80 = Bearish
         indicators.insert("CCI_20".to_string(), 150.0); // Above 100 = Bearish
         indicators.insert("BB_UPPER_20".to_string(), 100.0);
@@ -968,7 +1713,7 @@ mod tests {
         let mut indicators = HashMap::new();
         indicators.insert("RSI_14".to_string(), 25.0); // Bullish
         indicators.insert("MACD_12_26".to_string(), 0.5); // Bearish (MACD < Signal)
-        indicators.insert("MACD_SIGNAL_9".to_string(), 1.0);
+        indicators.insert("MACD_SIGNAL_12_26_9".to_string(), 1.0);
         indicators.insert("STOCH_K_14".to_string(), 15.0); // Bullish
         indicators.insert("CCI_20".to_string(), 50.0); // Neutral (between -100 and 100)
         indicators.insert("BB_UPPER_20".to_string(), 110.0);
@@ -990,7 +1735,7 @@ mod tests {
         let mut indicators_weak_adx = HashMap::new();
         indicators_weak_adx.insert("RSI_14".to_string(), 25.0);
         indicators_weak_adx.insert("MACD_12_26".to_string(), 1.5);
-        indicators_weak_adx.insert("MACD_SIGNAL_9".to_string(), 1.0);
+        indicators_weak_adx.insert("MACD_SIGNAL_12_26_9".to_string(), 1.0);
         indicators_weak_adx.insert("STOCH_K_14".to_string(), 15.0);
         indicators_weak_adx.insert("CCI_20".to_string(), -150.0);
         indicators_weak_adx.insert("ADX_14".to_string(), 15.0); // Weak trend
@@ -1016,4 +1761,403 @@ mod tests {
             "Strong ADX should boost strength"
         );
     }
+
+    #[test]
+    fn test_evaluate_all_rule_needs_both_on_same_date() {
+        use crate::models::{Condition, StrategyConditionType};
+
+        let engine = SignalEngine::new();
+        let date = |d| NaiveDate::from_ymd_opt(2026, 1, d).unwrap();
+        let ind = |d, name: &str, value| TechnicalIndicator {
+            symbol: "TEST".to_string(),
+            date: date(d),
+            indicator_name: name.to_string(),
+            value,
+        };
+        let prices: Vec<DailyPrice> = (1..=3)
+            .map(|d| DailyPrice {
+                symbol: "TEST".to_string(),
+                date: date(d),
+                open: 100.0,
+                high: 101.0,
+                low: 99.0,
+                close: 100.0,
+                volume: 1000,
+                adjusted_close: None,
+                source: "test".to_string(),
+            })
+            .collect();
+        let indicators = vec![
+            ind(1, "RSI_14", 25.0),
+            ind(1, "SMA_20", 105.0),
+            ind(2, "RSI_14", 50.0),
+            ind(2, "SMA_20", 95.0),
+            ind(3, "RSI_14", 25.0),
+            ind(3, "SMA_20", 95.0),
+        ];
+        let rule = StrategyRule::All(vec![
+            Condition { condition: StrategyConditionType::RsiOversold, threshold: 30.0 },
+            Condition { condition: StrategyConditionType::PriceAboveSma, threshold: 0.0 },
+        ]);
+
+        assert_eq!(engine.evaluate_rule(&rule, &indicators, &prices), vec![date(3)]);
+    }
+
+    fn confluence_fixture(values: &[(&str, f64)]) -> (Vec<TechnicalIndicator>, Vec<DailyPrice>) {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 21).unwrap();
+        let indicators = values
+            .iter()
+            .map(|(name, value)| TechnicalIndicator {
+                symbol: "TEST".to_string(),
+                date,
+                indicator_name: name.to_string(),
+                value: *value,
+            })
+            .collect();
+        let prices = vec![DailyPrice {
+            symbol: "TEST".to_string(),
+            date,
+            open: 100.0,
+            high: 101.0,
+            low: 99.0,
+            close: 100.0,
+            volume: 1000,
+            adjusted_close: None,
+            source: "test".to_string(),
+        }];
+        (indicators, prices)
+    }
+
+    #[test]
+    fn test_confluence_score_agreeing_bullish() {
+        let engine = SignalEngine::new();
+        let (indicators, prices) =
+            confluence_fixture(&[("RSI_14", 15.0), ("STOCH_K_14", 5.0), ("CCI_20", -180.0)]);
+
+        let score = engine.confluence_score(&indicators, &prices);
+        assert!(score > 0.9, "three bullish votes should score near 1.0, got {}", score);
+    }
+
+    #[test]
+    fn test_confluence_score_mixed_near_zero() {
+        let engine = SignalEngine::new();
+        let (indicators, prices) = confluence_fixture(&[
+            ("RSI_14", 15.0),     // bullish, strength 0.5
+            ("STOCH_K_14", 90.0), // bearish, strength 0.5
+            ("CCI_20", -150.0),   // bullish, strength 0.5
+            ("MACD_12_26", 0.5),  // bearish, strength 0.5
+            ("MACD_SIGNAL_12_26_9", 1.0),
+        ]);
+
+        let score = engine.confluence_score(&indicators, &prices);
+        assert!(score.abs() < 0.1, "opposing votes should cancel out, got {}", score);
+    }
+
+    #[test]
+    fn test_rsi_oversold_level_is_configurable() {
+        let date = |d| NaiveDate::from_ymd_opt(2026, 1, d).unwrap();
+        let indicators: Vec<TechnicalIndicator> = [50.0, 35.0, 50.0, 25.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &rsi)| TechnicalIndicator {
+                symbol: "TEST".to_string(),
+                date: date(i as u32 + 1),
+                indicator_name: "RSI_14".to_string(),
+                value: rsi,
+            })
+            .collect();
+        let (_, prices) = confluence_fixture(&[]);
+        let oversold_dates = |engine: &SignalEngine| -> Vec<NaiveDate> {
+            engine
+                .generate_signals("TEST", &indicators, &prices)
+                .into_iter()
+                .filter(|s| s.signal_type == SignalType::RsiOversold)
+                .map(|s| s.timestamp)
+                .collect()
+        };
+
+        assert_eq!(oversold_dates(&SignalEngine::new()), vec![date(4)]);
+
+        let config = SignalConfig {
+            rsi_oversold: 40.0,
+            ..SignalConfig::default()
+        };
+        assert!(config.validate().is_ok());
+        assert_eq!(
+            oversold_dates(&SignalEngine::with_config(config)),
+            vec![date(2), date(4)]
+        );
+
+        let inverted = SignalConfig {
+            rsi_oversold: 75.0,
+            ..SignalConfig::default()
+        };
+        assert!(inverted.validate().is_err());
+    }
+
+    #[test]
+    fn test_signal_weights_scale_strength_by_type() {
+        let date = |d| NaiveDate::from_ymd_opt(2026, 1, d).unwrap();
+        let indicators: Vec<TechnicalIndicator> = [
+            (1, "RSI_14", 50.0),
+            (2, "RSI_14", 20.0), // oversold, strength 1/3
+            (1, "CCI_20", 0.0),
+            (2, "CCI_20", -130.0), // oversold, strength 0.3
+        ]
+        .iter()
+        .map(|&(day, name, value)| TechnicalIndicator {
+            symbol: "TEST".to_string(),
+            date: date(day),
+            indicator_name: name.to_string(),
+            value,
+        })
+        .collect();
+        let (_, prices) = confluence_fixture(&[]);
+        let strength_of = |engine: &SignalEngine, signal_type: SignalType| -> f64 {
+            engine
+                .generate_signals("TEST", &indicators, &prices)
+                .into_iter()
+                .find(|s| s.signal_type == signal_type)
+                .map(|s| s.strength)
+                .unwrap()
+        };
+
+        let mut weights = SignalWeights::default();
+        weights.set(SignalType::RsiOversold, 2.0);
+        let plain = SignalEngine::new();
+        let weighted = SignalEngine::new().with_weights(weights.clone());
+
+        let rsi_ratio = strength_of(&weighted, SignalType::RsiOversold) / strength_of(&plain, SignalType::RsiOversold);
+        let cci_ratio = strength_of(&weighted, SignalType::CciOversold) / strength_of(&plain, SignalType::CciOversold);
+        assert!((rsi_ratio - 2.0).abs() < 1e-9);
+        assert!((cci_ratio - 1.0).abs() < 1e-9);
+
+        weights.set(SignalType::CciOversold, -1.0);
+        assert!(weights.validate().is_err());
+    }
+
+    #[test]
+    fn test_psar_flip_signal() {
+        let date = |d| NaiveDate::from_ymd_opt(2026, 1, d).unwrap();
+        let indicators: Vec<TechnicalIndicator> = [(95.0, 1.0), (96.0, 1.0), (104.0, -1.0)]
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &(sar, trend))| {
+                [("PSAR", sar), ("PSAR_TREND", trend)].map(|(name, value)| TechnicalIndicator {
+                    symbol: "TEST".to_string(),
+                    date: date(i as u32 + 1),
+                    indicator_name: name.to_string(),
+                    value,
+                })
+            })
+            .collect();
+        let (_, prices) = confluence_fixture(&[]);
+
+        let flips: Vec<Signal> = SignalEngine::new()
+            .generate_signals("TEST", &indicators, &prices)
+            .into_iter()
+            .filter(|s| s.signal_type == SignalType::PsarFlip)
+            .collect();
+
+        assert_eq!(flips.len(), 1);
+        assert_eq!(flips[0].timestamp, date(3));
+        assert_eq!(flips[0].direction, SignalDirection::Bearish);
+        assert_eq!(flips[0].trigger_value, 104.0);
+    }
+
+    #[test]
+    fn test_aroon_bullish_cross_signal() {
+        let date = |d| NaiveDate::from_ymd_opt(2026, 1, d).unwrap();
+        let indicators: Vec<TechnicalIndicator> = [(20.0, 80.0), (40.0, 60.0), (96.0, 52.0), (100.0, 48.0)]
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &(up, down))| {
+                [("AROON_UP_25", up), ("AROON_DOWN_25", down)].map(|(name, value)| TechnicalIndicator {
+                    symbol: "TEST".to_string(),
+                    date: date(i as u32 + 1),
+                    indicator_name: name.to_string(),
+                    value,
+                })
+            })
+            .collect();
+        let (_, prices) = confluence_fixture(&[]);
+
+        let aroon: Vec<Signal> = SignalEngine::new()
+            .generate_signals("TEST", &indicators, &prices)
+            .into_iter()
+            .filter(|s| matches!(s.signal_type, SignalType::AroonBullish | SignalType::AroonBearish))
+            .collect();
+
+        assert_eq!(aroon.len(), 1);
+        assert_eq!(aroon[0].signal_type, SignalType::AroonBullish);
+        assert_eq!(aroon[0].direction, SignalDirection::Bullish);
+        assert_eq!(aroon[0].timestamp, date(3));
+        assert_eq!(aroon[0].trigger_value, 96.0);
+        assert_eq!(
+            SignalType::from_str(aroon[0].signal_type.as_str()),
+            Some(SignalType::AroonBullish)
+        );
+    }
+
+    #[test]
+    fn test_donchian_breakout_uses_prior_bar_channel() {
+        let date = |d| NaiveDate::from_ymd_opt(2026, 1, d).unwrap();
+        // Channel of 95-105 on each day; only day 3's close clears the prior upper band
+        let indicators: Vec<TechnicalIndicator> = (1..=3)
+            .flat_map(|d| {
+                [("DONCHIAN_UPPER_20", 105.0), ("DONCHIAN_LOWER_20", 95.0)].map(|(name, value)| {
+                    TechnicalIndicator {
+                        symbol: "TEST".to_string(),
+                        date: date(d),
+                        indicator_name: name.to_string(),
+                        value,
+                    }
+                })
+            })
+            .collect();
+        let prices: Vec<DailyPrice> = [100.0, 104.0, 107.5]
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| DailyPrice {
+                symbol: "TEST".to_string(),
+                date: date(i as u32 + 1),
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: 1000,
+                source: "test".to_string(),
+                adjusted_close: None,
+            })
+            .collect();
+
+        let breakouts: Vec<Signal> = SignalEngine::new()
+            .generate_signals("TEST", &indicators, &prices)
+            .into_iter()
+            .filter(|s| s.signal_type == SignalType::DonchianBreakout)
+            .collect();
+
+        assert_eq!(breakouts.len(), 1);
+        assert_eq!(breakouts[0].timestamp, date(3));
+        assert_eq!(breakouts[0].direction, SignalDirection::Bullish);
+        assert_eq!(breakouts[0].trigger_value, 105.0);
+        assert_eq!(breakouts[0].strength, 1.0);
+    }
+
+    #[test]
+    fn test_bearish_divergence_when_price_rises_and_rsi_falls() {
+        let date = |d: u32| NaiveDate::from_ymd_opt(2026, 1, d + 1).unwrap();
+        // Price grinds to a new high every bar while RSI fades from 80 toward 60
+        let prices: Vec<DailyPrice> = (0..15)
+            .map(|d| {
+                let close = 100.0 + d as f64;
+                DailyPrice {
+                    symbol: "TEST".to_string(),
+                    date: date(d),
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    volume: 1000,
+                    source: "test".to_string(),
+                    adjusted_close: None,
+                }
+            })
+            .collect();
+        let rsi: Vec<TechnicalIndicator> = (0..15)
+            .map(|d| TechnicalIndicator {
+                symbol: "TEST".to_string(),
+                date: date(d),
+                indicator_name: "RSI_14".to_string(),
+                value: 80.0 - d as f64 * 1.5,
+            })
+            .collect();
+
+        let signals = SignalEngine::new().detect_divergence(&prices, &rsi, 5);
+
+        assert_eq!(signals.len(), 10);
+        assert!(signals.iter().all(|s| s.signal_type == SignalType::BearishDivergence
+            && s.direction == SignalDirection::Bearish
+            && s.triggered_by == "RSI_14"));
+        let last = signals.last().unwrap();
+        assert_eq!(last.timestamp, date(14));
+        assert_eq!(last.trigger_value, 59.0);
+        // Prior high on day 13 had RSI 60.5: a 1.5 point gap, ~2.5% of 60.5
+        assert!((last.strength - 1.5 / 60.5 / 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_only_unfilled_gaps_are_returned() {
+        let date = |d| NaiveDate::from_ymd_opt(2026, 2, d).unwrap();
+        // (open, high, low, close): day 2 gaps up from 100 and never looks back;
+        // day 4 gaps down from 111 and day 5 trades back up through 111
+        let bars = [
+            (99.0, 101.0, 98.0, 100.0),
+            (105.0, 108.0, 104.0, 107.0),
+            (108.0, 112.0, 106.0, 111.0),
+            (106.0, 107.0, 104.0, 105.0),
+            (105.0, 112.0, 104.5, 111.5),
+            (111.0, 113.0, 110.0, 112.0),
+        ];
+        let prices: Vec<DailyPrice> = bars
+            .iter()
+            .enumerate()
+            .map(|(i, &(open, high, low, close))| DailyPrice {
+                symbol: "TEST".to_string(),
+                date: date(i as u32 + 2),
+                open,
+                high,
+                low,
+                close,
+                volume: 1000,
+                source: "test".to_string(),
+                adjusted_close: None,
+            })
+            .collect();
+
+        let gaps = find_unfilled_gaps(&prices, 2.0);
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].date, date(3));
+        assert_eq!(gaps[0].direction, SignalDirection::Bullish);
+        assert_eq!(gaps[0].prev_close, 100.0);
+        assert!((gaps[0].gap_percent - 5.0).abs() < 1e-9);
+
+        // Both gaps still signal on the day they open
+        let indicators = vec![TechnicalIndicator {
+            symbol: "TEST".to_string(),
+            date: date(2),
+            indicator_name: "RSI_14".to_string(),
+            value: 50.0,
+        }];
+        let gap_signals: Vec<SignalType> = SignalEngine::new()
+            .generate_signals("TEST", &indicators, &prices)
+            .into_iter()
+            .map(|s| s.signal_type)
+            .filter(|t| matches!(t, SignalType::GapUp | SignalType::GapDown))
+            .collect();
+        assert_eq!(gap_signals, [SignalType::GapUp, SignalType::GapDown]);
+    }
+
+    #[test]
+    fn test_squeeze_fires_once_when_bandwidth_hits_a_low() {
+        let date = |d| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        let bandwidth: Vec<TechnicalIndicator> = [0.10, 0.12, 0.11, 0.09, 0.08, 0.07, 0.09]
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| TechnicalIndicator {
+                symbol: "TEST".to_string(),
+                date: date(i as u32 + 1),
+                indicator_name: "BB_BANDWIDTH_20".to_string(),
+                value,
+            })
+            .collect();
+        let series: Vec<&TechnicalIndicator> = bandwidth.iter().collect();
+
+        let signals = SignalEngine::new().detect_squeeze_signals("TEST", &series, &HashMap::new(), 3);
+        // Day 4 undercuts the prior three bars; days 5-6 continue the same squeeze
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].timestamp, date(4));
+        assert_eq!(signals[0].signal_type, SignalType::BollingerSqueeze);
+        assert!((signals[0].strength - (1.0 - 0.09 / 0.11)).abs() < 1e-9);
+    }
 }
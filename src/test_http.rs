@@ -0,0 +1,81 @@
+//! Loopback HTTP server for the API client tests
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+
+/// Serve canned `(status, body)` responses in order, one per connection.
+/// Returns the base URL and a receiver of each raw request (head and body).
+pub fn serve(responses: Vec<(u16, String)>) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        for (status, body) in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = tx.send(read_request(&mut stream));
+            respond(&mut stream, status, &body);
+        }
+    });
+
+    (format!("http://{}", addr), rx)
+}
+
+/// Serve `body` to every connection, counting how many requests arrive
+pub fn serve_counting(body: &'static str) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            read_request(&mut stream);
+            counter.fetch_add(1, Ordering::SeqCst);
+            respond(&mut stream, 200, body);
+        }
+    });
+
+    (format!("http://{}", addr), hits)
+}
+
+/// Read until the headers and the full Content-Length body have arrived
+fn read_request(stream: &mut TcpStream) -> String {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut buf).unwrap_or(0);
+        request.extend_from_slice(&buf[..n]);
+        let text = String::from_utf8_lossy(&request);
+        if let Some(end) = text.find("\r\n\r\n") {
+            let length = text[..end]
+                .lines()
+                .find_map(|l| {
+                    let l = l.to_lowercase();
+                    l.strip_prefix("content-length:")?.trim().parse::<usize>().ok()
+                })
+                .unwrap_or(0);
+            if request.len() >= end + 4 + length {
+                break;
+            }
+        }
+        if n == 0 {
+            break;
+        }
+    }
+    String::from_utf8_lossy(&request).to_string()
+}
+
+fn respond(stream: &mut TcpStream, status: u16, body: &str) {
+    let reply = format!(
+        "HTTP/1.1 {} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(reply.as_bytes());
+}
@@ -2,10 +2,14 @@
 // Uses SQLite for storage with simple cosine similarity search
 // Provides semantic search, pattern matching, and AI chat capabilities
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::path::Path;
+use std::time::Duration;
+
+use crate::ollama::{EmbeddingsRequest, EmbeddingsResponse, OllamaClient, MODEL_EMBED};
 
 const EMBEDDING_DIM: usize = 128; // Smaller dimension for simple hash-based embeddings
 
@@ -21,6 +25,20 @@ pub struct MarketEvent {
     pub metadata: Option<String>,
 }
 
+/// Content-addressed id for a market event: `symbol-type-date-` followed by the first
+/// 16 hex digits of a SHA-256 over symbol, date, title and content. Re-adding the
+/// same article yields the same id (and replaces the row); distinct articles don't collide.
+pub fn event_id(symbol: &str, event_type: &str, date: &str, title: &str, content: &str) -> String {
+    let mut hasher = Sha256::new();
+    for field in [symbol, date, title, content] {
+        hasher.update(field.as_bytes());
+        // Separator so ("ab", "c") and ("a", "bc") hash differently
+        hasher.update([0u8]);
+    }
+    let digest = hex::encode(hasher.finalize());
+    format!("{}-{}-{}-{}", symbol, event_type, date, &digest[..16])
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PricePattern {
     pub id: String,
@@ -44,6 +62,17 @@ pub struct SearchResult {
     pub metadata: Option<String>,
 }
 
+/// Narrows a search before ranking; `None` fields match everything.
+/// Dates are inclusive `YYYY-MM-DD` bounds (patterns are matched on their start date).
+/// Setting `event_type` limits results to market events of that type.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchFilter {
+    pub symbol: Option<String>,
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub event_type: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
     pub role: String,
@@ -56,25 +85,141 @@ pub struct ChatResponse {
     pub sources: Vec<SearchResult>,
 }
 
+/// Turns text into an embedding vector for similarity search
+pub trait Embedder: Send + Sync {
+    fn embed(&self, text: &str) -> Result<Vec<f32>>;
+}
+
+/// Hash-based bag-of-words embedder (no model needed, lexical matches only)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HashEmbedder;
+
+impl Embedder for HashEmbedder {
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        Ok(VectorStore::generate_embedding(text))
+    }
+}
+
+/// Embedder backed by Ollama's `/api/embeddings` endpoint
+pub struct OllamaEmbedder {
+    client: reqwest::blocking::Client,
+    base_url: String,
+    model: String,
+}
+
+impl OllamaEmbedder {
+    /// Use the same Ollama server as `ollama`, with the default embedding model
+    pub fn new(ollama: &OllamaClient) -> Self {
+        Self {
+            client: reqwest::blocking::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_else(|_| reqwest::blocking::Client::new()),
+            base_url: ollama.base_url().to_string(),
+            model: MODEL_EMBED.to_string(),
+        }
+    }
+
+    /// Set the embedding model (e.g. "nomic-embed-text", "mxbai-embed-large")
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.model = model.to_string();
+        self
+    }
+}
+
+impl Embedder for OllamaEmbedder {
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let response = self
+            .client
+            .post(format!("{}/api/embeddings", self.base_url))
+            .json(&EmbeddingsRequest {
+                model: &self.model,
+                prompt: text,
+            })
+            .send()
+            .context("Failed to send embeddings request to Ollama")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Ollama embeddings error: {}", response.status());
+        }
+
+        let parsed: EmbeddingsResponse = response
+            .json()
+            .context("Failed to parse Ollama embeddings response")?;
+        Ok(parsed.embedding)
+    }
+}
+
 pub struct VectorStore {
     conn: Connection,
+    embedder: Box<dyn Embedder>,
 }
 
 impl VectorStore {
-    pub fn new(db_path: &str) -> Result<Self> {
+    /// Open a store using the built-in hash embedder
+    pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        Self::with_embedder(db_path, HashEmbedder)
+    }
+
+    /// Open a store that embeds text with the given backend
+    pub fn with_embedder<P: AsRef<Path>, E: Embedder + 'static>(
+        db_path: P,
+        embedder: E,
+    ) -> Result<Self> {
+        let db_path = db_path.as_ref();
+
         // Ensure directory exists
-        if let Some(parent) = Path::new(db_path).parent() {
+        if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
         let conn = Connection::open(db_path)?;
 
-        let store = Self { conn };
+        let store = Self {
+            conn,
+            embedder: Box::new(embedder),
+        };
         store.init_tables()?;
 
         Ok(store)
     }
 
+    /// Embed text with the configured backend
+    /// Failures (e.g. Ollama down) yield an empty vector so rows still get stored
+    /// and are matched by keyword instead
+    fn embed(&self, text: &str) -> Vec<f32> {
+        match self.embedder.embed(text) {
+            Ok(embedding) => embedding,
+            Err(e) => {
+                println!("[WARN] Embedding failed, falling back to keyword search: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Score a stored row against the query: cosine similarity when both embeddings
+    /// come from the same model, otherwise the fraction of query words found in the text
+    fn score(query: &str, query_embedding: &[f32], text: &str, embedding: &[f32]) -> f32 {
+        if !query_embedding.is_empty() && query_embedding.len() == embedding.len() {
+            return Self::cosine_similarity(query_embedding, embedding);
+        }
+        Self::keyword_score(query, text)
+    }
+
+    /// Fraction of query words that appear in the text
+    fn keyword_score(query: &str, text: &str) -> f32 {
+        let text_lower = text.to_lowercase();
+        let words: Vec<String> = query
+            .split_whitespace()
+            .map(|w| w.to_lowercase())
+            .collect();
+        if words.is_empty() {
+            return 0.0;
+        }
+        let hits = words.iter().filter(|w| text_lower.contains(w.as_str())).count();
+        hits as f32 / words.len() as f32
+    }
+
     fn init_tables(&self) -> Result<()> {
         // Market events table
         self.conn.execute(
@@ -124,7 +269,7 @@ impl VectorStore {
     }
 
     /// Generate a simple embedding from text using hash-based approach
-    /// In production, replace with actual embedding model (local Ollama or API)
+    /// Used by `HashEmbedder`; see `OllamaEmbedder` for semantic embeddings
     fn generate_embedding(text: &str) -> Vec<f32> {
         let mut embedding = vec![0.0f32; EMBEDDING_DIM];
         let text_lower = text.to_lowercase();
@@ -192,7 +337,7 @@ impl VectorStore {
     /// Add a market event to the vector store
     pub fn add_market_event(&self, event: &MarketEvent) -> Result<()> {
         let text = format!("{} {} {}", event.title, event.content, event.event_type);
-        let embedding = Self::generate_embedding(&text);
+        let embedding = self.embed(&text);
         let embedding_bytes = Self::embedding_to_bytes(&embedding);
 
         self.conn.execute(
@@ -217,14 +362,28 @@ impl VectorStore {
 
     /// Search for similar market events
     pub fn search_events(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
-        let query_embedding = Self::generate_embedding(query);
+        self.search_events_filtered(query, limit, &SearchFilter::default())
+    }
+
+    /// Search market events matching `filter`, ranked by similarity
+    fn search_events_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<SearchResult>> {
+        let query_embedding = self.embed(query);
 
         let mut stmt = self.conn.prepare(
             "SELECT id, symbol, event_type, title, content, date, metadata, embedding
-             FROM market_events"
+             FROM market_events
+             WHERE (?1 IS NULL OR symbol = ?1)
+               AND (?2 IS NULL OR date >= ?2)
+               AND (?3 IS NULL OR date <= ?3)
+               AND (?4 IS NULL OR event_type = ?4)"
         )?;
 
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map(params![filter.symbol, filter.from_date, filter.to_date, filter.event_type], |row| {
             let id: String = row.get(0)?;
             let symbol: String = row.get(1)?;
             let event_type: String = row.get(2)?;
@@ -239,9 +398,10 @@ impl VectorStore {
         let mut results: Vec<SearchResult> = Vec::new();
 
         for row in rows {
-            let (id, symbol, _event_type, title, content, date, metadata, embedding_bytes) = row?;
+            let (id, symbol, event_type, title, content, date, metadata, embedding_bytes) = row?;
             let embedding = Self::bytes_to_embedding(&embedding_bytes);
-            let score = Self::cosine_similarity(&query_embedding, &embedding);
+            let text = format!("{} {} {}", title, content, event_type);
+            let score = Self::score(query, &query_embedding, &text, &embedding);
 
             results.push(SearchResult {
                 id,
@@ -268,7 +428,7 @@ impl VectorStore {
             pattern.pattern_type, pattern.description,
             pattern.price_change_percent, pattern.volume_change_percent
         );
-        let embedding = Self::generate_embedding(&text);
+        let embedding = self.embed(&text);
         let embedding_bytes = Self::embedding_to_bytes(&embedding);
 
         self.conn.execute(
@@ -294,14 +454,27 @@ impl VectorStore {
 
     /// Search for similar price patterns
     pub fn search_patterns(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
-        let query_embedding = Self::generate_embedding(query);
+        self.search_patterns_filtered(query, limit, &SearchFilter::default())
+    }
+
+    /// Search price patterns matching `filter` (its `event_type` is ignored), ranked by similarity
+    fn search_patterns_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<SearchResult>> {
+        let query_embedding = self.embed(query);
 
         let mut stmt = self.conn.prepare(
             "SELECT id, symbol, pattern_type, start_date, end_date, description, embedding
-             FROM price_patterns"
+             FROM price_patterns
+             WHERE (?1 IS NULL OR symbol = ?1)
+               AND (?2 IS NULL OR start_date >= ?2)
+               AND (?3 IS NULL OR start_date <= ?3)"
         )?;
 
-        let rows = stmt.query_map([], |row| {
+        let rows = stmt.query_map(params![filter.symbol, filter.from_date, filter.to_date], |row| {
             let id: String = row.get(0)?;
             let symbol: String = row.get(1)?;
             let pattern_type: String = row.get(2)?;
@@ -317,7 +490,8 @@ impl VectorStore {
         for row in rows {
             let (id, symbol, pattern_type, start_date, description, embedding_bytes) = row?;
             let embedding = Self::bytes_to_embedding(&embedding_bytes);
-            let score = Self::cosine_similarity(&query_embedding, &embedding);
+            let text = format!("{} {}", pattern_type, description);
+            let score = Self::score(query, &query_embedding, &text, &embedding);
 
             results.push(SearchResult {
                 id,
@@ -339,16 +513,28 @@ impl VectorStore {
 
     /// Combined search across all tables
     pub fn search_all(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        self.search_filtered(query, limit, &SearchFilter::default())
+    }
+
+    /// Combined search across all tables, restricted to rows matching `filter`
+    pub fn search_filtered(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &SearchFilter,
+    ) -> Result<Vec<SearchResult>> {
         let mut all_results = Vec::new();
 
         // Search events
-        if let Ok(events) = self.search_events(query, limit) {
+        if let Ok(events) = self.search_events_filtered(query, limit, filter) {
             all_results.extend(events);
         }
 
-        // Search patterns
-        if let Ok(patterns) = self.search_patterns(query, limit) {
-            all_results.extend(patterns);
+        // Search patterns (they have no event type, so a type filter excludes them)
+        if filter.event_type.is_none() {
+            if let Ok(patterns) = self.search_patterns_filtered(query, limit, filter) {
+                all_results.extend(patterns);
+            }
         }
 
         // Sort by score descending
@@ -360,6 +546,34 @@ impl VectorStore {
         Ok(all_results)
     }
 
+    /// Delete a market event by ID, returning the number of rows removed
+    pub fn delete_event(&self, id: &str) -> Result<usize> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM market_events WHERE id = ?1", params![id])?)
+    }
+
+    /// Delete a price pattern by ID, returning the number of rows removed
+    pub fn delete_pattern(&self, id: &str) -> Result<usize> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM price_patterns WHERE id = ?1", params![id])?)
+    }
+
+    /// Delete all market events for a symbol, returning the number of rows removed
+    pub fn delete_events_by_symbol(&self, symbol: &str) -> Result<usize> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM market_events WHERE symbol = ?1", params![symbol])?)
+    }
+
+    /// Remove every event and pattern, returning the total number of rows removed
+    pub fn clear_all(&self) -> Result<usize> {
+        let events = self.conn.execute("DELETE FROM market_events", [])?;
+        let patterns = self.conn.execute("DELETE FROM price_patterns", [])?;
+        Ok(events + patterns)
+    }
+
     /// Get table statistics
     pub fn get_stats(&self) -> Result<(usize, usize)> {
         let events_count: usize = self.conn.query_row(
@@ -382,6 +596,25 @@ impl VectorStore {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_event_id_is_content_addressed() {
+        let id = event_id("AAPL", "news", "2026-01-05", "Apple beats", "Strong iPhone sales");
+        assert_eq!(id, event_id("AAPL", "news", "2026-01-05", "Apple beats", "Strong iPhone sales"));
+        assert!(id.starts_with("AAPL-news-2026-01-05-"));
+        assert_eq!(id.len(), "AAPL-news-2026-01-05-".len() + 16);
+
+        // "Ab" and "BC" collided under the old `(h + b) * 31` rolling hash
+        assert_ne!(
+            event_id("AAPL", "news", "2026-01-05", "Apple beats", "Ab"),
+            event_id("AAPL", "news", "2026-01-05", "Apple beats", "BC")
+        );
+        assert_ne!(
+            event_id("AAPL", "news", "2026-01-05", "ab", "c"),
+            event_id("AAPL", "news", "2026-01-05", "a", "bc")
+        );
+        assert_ne!(id, event_id("MSFT", "news", "2026-01-05", "Apple beats", "Strong iPhone sales"));
+    }
+
     #[test]
     fn test_vector_store() {
         let store = VectorStore::new("./test_vectors.db").unwrap();
@@ -403,4 +636,99 @@ mod tests {
         assert!(!results.is_empty());
         assert!(results[0].score > 0.0);
     }
+
+    /// Maps words onto a few topic axes so related words embed close together
+    struct TopicEmbedder;
+
+    impl Embedder for TopicEmbedder {
+        fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            let topics: [&[&str]; 3] = [
+                &["earnings", "profit", "revenue", "quarter"],
+                &["lawsuit", "court", "regulator", "fine"],
+                &["acquisition", "merger", "buyout", "takeover"],
+            ];
+            let text = text.to_lowercase();
+            Ok(topics
+                .iter()
+                .map(|words| words.iter().filter(|w| text.contains(*w)).count() as f32 + 0.01)
+                .collect())
+        }
+    }
+
+    fn event(id: &str, title: &str) -> MarketEvent {
+        MarketEvent {
+            id: id.to_string(),
+            symbol: "ACME".to_string(),
+            event_type: "news".to_string(),
+            title: title.to_string(),
+            content: String::new(),
+            date: "2026-01-15".to_string(),
+            sentiment: None,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_embedder_ranks_semantic_match_first() {
+        let path = std::env::temp_dir().join(format!("fp_embed_test_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store = VectorStore::with_embedder(&path, TopicEmbedder).unwrap();
+
+        store.add_market_event(&event("e1", "Quarterly revenue beats estimates")).unwrap();
+        store.add_market_event(&event("e2", "Regulator files lawsuit in federal court")).unwrap();
+        store.add_market_event(&event("e3", "Board approves acquisition by rival")).unwrap();
+
+        // No word overlap with "acquisition", but same topic
+        let results = store.search_all("takeover merger talks", 3).unwrap();
+
+        assert_eq!(results[0].id, "e3");
+        assert!(results[0].score > results[1].score);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_search_filtered_by_symbol() {
+        let path = std::env::temp_dir().join(format!("fp_filter_test_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store = VectorStore::new(&path).unwrap();
+
+        store.add_market_event(&event("a1", "Earnings beat estimates")).unwrap();
+        store.add_market_event(&event("a2", "Earnings guidance raised")).unwrap();
+        let other = MarketEvent { symbol: "BETA".to_string(), ..event("b1", "Earnings beat estimates") };
+        store.add_market_event(&other).unwrap();
+
+        assert_eq!(store.search_all("earnings", 10).unwrap().len(), 3);
+
+        let filter = SearchFilter { symbol: Some("BETA".to_string()), ..Default::default() };
+        let results = store.search_filtered("earnings", 10, &filter).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "b1");
+
+        let filter = SearchFilter {
+            symbol: Some("ACME".to_string()),
+            to_date: Some("2026-01-14".to_string()),
+            ..Default::default()
+        };
+        assert!(store.search_filtered("earnings", 10, &filter).unwrap().is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_delete_event_updates_stats() {
+        let path = std::env::temp_dir().join(format!("fp_delete_test_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store = VectorStore::new(&path).unwrap();
+
+        store.add_market_event(&event("d1", "First headline")).unwrap();
+        store.add_market_event(&event("d2", "Second headline")).unwrap();
+        assert_eq!(store.get_stats().unwrap(), (2, 0));
+
+        assert_eq!(store.delete_event("d1").unwrap(), 1);
+        assert_eq!(store.delete_event("missing").unwrap(), 0);
+        assert_eq!(store.get_stats().unwrap(), (1, 0));
+
+        assert_eq!(store.clear_all().unwrap(), 1);
+        assert_eq!(store.get_stats().unwrap(), (0, 0));
+        let _ = std::fs::remove_file(&path);
+    }
 }
@@ -0,0 +1,219 @@
+//! Webhook delivery
+//!
+//! POSTs triggered signals and alerts as JSON to user-registered endpoints.
+//! When a webhook has a secret, the body is signed with HMAC-SHA256 and sent in
+//! the `X-Pipeline-Signature` header as `sha256=<hex digest>`.
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+
+use crate::error::{PipelineError, Result};
+use crate::models::{Webhook, WebhookEvent};
+
+/// Header carrying the HMAC-SHA256 signature of the request body
+pub const SIGNATURE_HEADER: &str = "X-Pipeline-Signature";
+/// Header carrying the event type, so receivers can route without parsing the body
+pub const EVENT_HEADER: &str = "X-Pipeline-Event";
+
+const DEFAULT_RETRIES: u32 = 3;
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(500);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// JSON body sent for each trigger
+#[derive(Serialize)]
+struct Payload<'a, T: Serialize> {
+    event: &'static str,
+    sent_at: String,
+    data: &'a T,
+}
+
+/// Signature header value for a body: `sha256=` followed by the hex HMAC
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Sends trigger payloads to webhooks, retrying transient failures
+pub struct WebhookDispatcher {
+    client: Client,
+    max_attempts: u32,
+    retry_delay: Duration,
+}
+
+impl Default for WebhookDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebhookDispatcher {
+    pub fn new() -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("Failed to create HTTP client"),
+            max_attempts: DEFAULT_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+        }
+    }
+
+    /// Set the number of attempts made for each delivery (minimum 1)
+    pub fn with_retries(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Set the base delay for exponential backoff between attempts
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// POST one payload per item to every webhook that accepts `event`.
+    /// Failed deliveries are logged and skipped; returns how many succeeded.
+    pub fn dispatch<T: Serialize>(
+        &self,
+        webhooks: &[Webhook],
+        event: WebhookEvent,
+        items: &[T],
+    ) -> usize {
+        let mut delivered = 0;
+
+        for item in items {
+            let payload = Payload {
+                event: event.as_str(),
+                sent_at: Utc::now().to_rfc3339(),
+                data: item,
+            };
+            let body = match serde_json::to_vec(&payload) {
+                Ok(body) => body,
+                Err(e) => {
+                    println!("[WARN] Failed to serialize {} webhook payload: {}", event.as_str(), e);
+                    continue;
+                }
+            };
+
+            for webhook in webhooks.iter().filter(|w| w.accepts(event)) {
+                match self.post_with_retry(webhook, event, &body) {
+                    Ok(()) => delivered += 1,
+                    Err(e) => println!("[WARN] Webhook {} delivery failed: {}", webhook.id, e),
+                }
+            }
+        }
+
+        delivered
+    }
+
+    /// POST a body, retrying timeouts, connection failures, 429 and 5xx responses
+    fn post_with_retry(&self, webhook: &Webhook, event: WebhookEvent, body: &[u8]) -> Result<()> {
+        let mut attempt = 1;
+
+        loop {
+            let mut request = self
+                .client
+                .post(&webhook.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, event.as_str())
+                .body(body.to_vec());
+            if let Some(secret) = &webhook.secret {
+                request = request.header(SIGNATURE_HEADER, sign_payload(secret, body));
+            }
+
+            let retry_reason = match request.send() {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => {
+                    let status = response.status();
+                    let retryable =
+                        status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+                    if !retryable || attempt >= self.max_attempts {
+                        return Err(PipelineError::ApiError(format!(
+                            "HTTP {} from {} after {} attempt(s)",
+                            status, webhook.url, attempt
+                        )));
+                    }
+                    format!("HTTP {}", status)
+                }
+                Err(e) => {
+                    if !(e.is_timeout() || e.is_connect()) || attempt >= self.max_attempts {
+                        return Err(PipelineError::ApiError(format!(
+                            "POST to {} failed after {} attempt(s): {}",
+                            webhook.url, attempt, e
+                        )));
+                    }
+                    e.to_string()
+                }
+            };
+
+            let delay = self.retry_delay * 2u32.saturating_pow(attempt - 1);
+            println!(
+                "[RETRY] Webhook {} attempt {}/{} failed ({}), retrying in {}ms",
+                webhook.id,
+                attempt,
+                self.max_attempts,
+                retry_reason,
+                delay.as_millis()
+            );
+            std::thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_http::serve;
+
+    fn webhook(url: &str, event_type: Option<WebhookEvent>) -> Webhook {
+        Webhook {
+            id: 1,
+            url: url.to_string(),
+            event_type,
+            secret: Some("s3cret".to_string()),
+            enabled: true,
+            created_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_dispatch_signs_payload_and_retries() {
+        let (base, requests) = serve(vec![(503, String::new()), (200, String::new())]);
+        let url = format!("{}/hook", base);
+        let dispatcher = WebhookDispatcher::new().with_retry_delay(Duration::from_millis(1));
+        let alert = serde_json::json!({"symbol": "AAPL", "target_price": 150.0});
+
+        let delivered = dispatcher.dispatch(
+            &[webhook(&url, None), webhook(&url, Some(WebhookEvent::Signal))],
+            WebhookEvent::PriceAlert,
+            &[alert],
+        );
+        assert_eq!(delivered, 1, "signal-only webhook should be skipped");
+
+        let _failed = requests.recv().unwrap();
+        let request = requests.recv().unwrap();
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        let head = head.to_lowercase();
+        assert!(head.starts_with("post /hook"));
+        assert!(head.contains("x-pipeline-event: price_alert"));
+
+        let expected = sign_payload("s3cret", body.as_bytes());
+        assert!(
+            head.contains(&format!("x-pipeline-signature: {}", expected)),
+            "missing or wrong signature in {}",
+            head
+        );
+
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["event"], "price_alert");
+        assert_eq!(payload["data"]["symbol"], "AAPL");
+        assert_eq!(payload["data"]["target_price"], 150.0);
+    }
+}
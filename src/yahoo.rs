@@ -4,16 +4,31 @@
 //! FREE and UNLIMITED - no API key required!
 
 use chrono::{DateTime, Utc};
-use reqwest::blocking::Client;
+use reqwest::blocking::{Client, Response};
+use reqwest::StatusCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::db::Database;
 use crate::error::{PipelineError, Result};
-use crate::models::yahoo::ChartResponse;
-use crate::models::DailyPrice;
+use crate::models::yahoo::{ChartResponse, QuoteSummaryResponse};
+use crate::models::{AssetClass, DailyPrice, Dividend, Symbol};
+use crate::ratelimit::{RateLimiter, SOURCE_YAHOO};
+
+const BASE_URL: &str = "https://query1.finance.yahoo.com";
+const DEFAULT_RETRIES: u32 = 3;
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_CONCURRENT_FETCHES: usize = 8;
 
 /// Yahoo Finance API client
 pub struct YahooFinance {
     client: Client,
+    base_url: String,
+    max_attempts: u32,
+    retry_delay: Duration,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl Default for YahooFinance {
@@ -30,6 +45,95 @@ impl YahooFinance {
                 .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
                 .build()
                 .expect("Failed to create HTTP client"),
+            base_url: BASE_URL.to_string(),
+            max_attempts: DEFAULT_RETRIES,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            rate_limiter: None,
+        }
+    }
+
+    /// Set the number of attempts made for each request (minimum 1)
+    pub fn with_retries(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Set the base delay for exponential backoff between attempts
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Check (and log) every request, including retries, against a shared rate limiter
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Point the client at a different host (used for testing)
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Whether a failed HTTP status is worth retrying
+    fn is_retryable_status(status: StatusCode) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+    }
+
+    /// Backoff before the given retry: base * 2^(attempt-1), plus up to 50% jitter
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let base = self.retry_delay * 2u32.saturating_pow(attempt - 1);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let jitter = base.mul_f64((nanos % 1000) as f64 / 2000.0);
+        base + jitter
+    }
+
+    /// GET a URL, retrying timeouts, connection failures, 429 and 5xx responses
+    fn get_with_retry(&self, url: &str, endpoint: &str, symbol: &str) -> Result<Response> {
+        let mut attempt = 1;
+
+        loop {
+            if let Some(limiter) = &self.rate_limiter {
+                limiter.acquire(SOURCE_YAHOO, endpoint, symbol)?;
+            }
+            let retry_reason = match self.client.get(url).send() {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let status = response.status();
+                    if !Self::is_retryable_status(status) || attempt >= self.max_attempts {
+                        return Err(PipelineError::NoData(format!(
+                            "HTTP {} for {} after {} attempt(s)",
+                            status, symbol, attempt
+                        )));
+                    }
+                    format!("HTTP {}", status)
+                }
+                Err(e) => {
+                    if !(e.is_timeout() || e.is_connect()) || attempt >= self.max_attempts {
+                        return Err(PipelineError::ApiError(format!(
+                            "Request for {} failed after {} attempt(s): {}",
+                            symbol, attempt, e
+                        )));
+                    }
+                    e.to_string()
+                }
+            };
+
+            let delay = self.backoff_delay(attempt);
+            println!(
+                "[RETRY] {} attempt {}/{} failed ({}), retrying in {}ms",
+                symbol,
+                attempt,
+                self.max_attempts,
+                retry_reason,
+                delay.as_millis()
+            );
+            std::thread::sleep(delay);
+            attempt += 1;
         }
     }
 
@@ -49,19 +153,11 @@ impl YahooFinance {
 
         // Yahoo Finance API endpoint
         let url = format!(
-            "https://query1.finance.yahoo.com/v8/finance/chart/{}?interval=1d&range={}",
-            symbol, period
+            "{}/v8/finance/chart/{}?interval=1d&range={}",
+            self.base_url, symbol, period
         );
 
-        let response = self.client.get(&url).send()?;
-
-        if !response.status().is_success() {
-            return Err(PipelineError::NoData(format!(
-                "HTTP {} for {}",
-                response.status(),
-                symbol
-            )));
-        }
+        let response = self.get_with_retry(&url, "history", symbol)?;
 
         let chart_response: ChartResponse = response.json()?;
 
@@ -89,6 +185,12 @@ impl YahooFinance {
             .ok_or_else(|| PipelineError::NoData(symbol.to_string()))?;
 
         let quote = &data.indicators.quote[0];
+        let adjclose = data
+            .indicators
+            .adjclose
+            .as_ref()
+            .and_then(|a| a.first())
+            .map(|a| &a.adjclose);
 
         let mut prices = Vec::with_capacity(timestamps.len());
 
@@ -111,6 +213,7 @@ impl YahooFinance {
                 None => continue,
             };
             let volume = quote.volume.get(i).and_then(|v| *v).unwrap_or(0);
+            let adjusted_close = adjclose.and_then(|a| a.get(i).copied().flatten());
 
             // Convert Unix timestamp to date
             let datetime = DateTime::from_timestamp(ts, 0)
@@ -125,6 +228,7 @@ impl YahooFinance {
                 low,
                 close,
                 volume,
+                adjusted_close,
                 source: "yahoo_finance".to_string(),
             });
         }
@@ -133,6 +237,118 @@ impl YahooFinance {
         Ok(prices)
     }
 
+    /// Fetch a symbol's full cash dividend history, oldest first
+    pub fn fetch_dividends(&self, symbol: &str) -> Result<Vec<Dividend>> {
+        let url = format!(
+            "{}/v8/finance/chart/{}?interval=1d&range=max&events=div",
+            self.base_url, symbol
+        );
+
+        let response = self.get_with_retry(&url, "dividends", symbol)?;
+        let chart_response: ChartResponse = response.json()?;
+
+        if let Some(err) = &chart_response.chart.error {
+            return Err(PipelineError::NoData(format!(
+                "{}: {}",
+                err.code, err.description
+            )));
+        }
+
+        let mut dividends: Vec<Dividend> = chart_response
+            .chart
+            .result
+            .and_then(|r| r.into_iter().next())
+            .and_then(|r| r.events)
+            .and_then(|e| e.dividends)
+            .unwrap_or_default()
+            .into_values()
+            .filter_map(|event| {
+                Some(Dividend {
+                    symbol: symbol.to_string(),
+                    ex_date: DateTime::from_timestamp(event.date, 0)?.date_naive(),
+                    amount: event.amount,
+                })
+            })
+            .collect();
+        dividends.sort_by_key(|d| d.ex_date);
+
+        println!("[OK] Fetched {} dividends for {}", dividends.len(), symbol);
+        Ok(dividends)
+    }
+
+    /// Fetch and store a symbol's dividend history
+    pub fn fetch_and_store_dividends(&self, db: &mut Database, symbol: &str) -> Result<usize> {
+        let dividends = self.fetch_dividends(symbol)?;
+        let count = db.upsert_dividends(&dividends)?;
+        if self.rate_limiter.is_none() {
+            db.log_api_call(SOURCE_YAHOO, "dividends", symbol)?;
+        }
+        Ok(count)
+    }
+
+    /// Fetch company profile (name, sector, industry, exchange, ...) for a symbol
+    pub fn fetch_profile(&self, symbol: &str) -> Result<Symbol> {
+        let url = format!(
+            "{}/v10/finance/quoteSummary/{}?modules=assetProfile,price",
+            self.base_url, symbol
+        );
+
+        let response = self.get_with_retry(&url, "profile", symbol)?;
+        let summary: QuoteSummaryResponse = response.json()?;
+
+        if let Some(err) = &summary.quote_summary.error {
+            return Err(PipelineError::NoData(format!(
+                "{}: {}",
+                err.code, err.description
+            )));
+        }
+
+        let result = summary
+            .quote_summary
+            .result
+            .and_then(|r| r.into_iter().next())
+            .ok_or_else(|| PipelineError::NoData(symbol.to_string()))?;
+
+        let profile = result.asset_profile;
+        let price = result.price;
+        let quote_type = price.as_ref().and_then(|p| p.quote_type.as_deref());
+
+        Ok(Symbol {
+            symbol: symbol.to_string(),
+            name: price
+                .as_ref()
+                .and_then(|p| p.long_name.clone().or_else(|| p.short_name.clone())),
+            sector: profile.as_ref().and_then(|p| p.sector.clone()),
+            industry: profile.as_ref().and_then(|p| p.industry.clone()),
+            market_cap: price
+                .as_ref()
+                .and_then(|p| p.market_cap.as_ref())
+                .and_then(|m| m.raw),
+            country: profile.as_ref().and_then(|p| p.country.clone()),
+            exchange: price.as_ref().and_then(|p| p.exchange_name.clone()),
+            currency: price.as_ref().and_then(|p| p.currency.clone()),
+            isin: None,
+            asset_class: quote_type.map(Self::asset_class_for),
+        })
+    }
+
+    /// Map Yahoo's quoteType to the asset class stored on the symbol
+    fn asset_class_for(quote_type: &str) -> String {
+        AssetClass::from_quote_type(quote_type)
+            .map(|class| class.as_str().to_string())
+            .unwrap_or_else(|| quote_type.to_string())
+    }
+
+    /// Fetch and store a symbol's profile, keeping any metadata already stored
+    pub fn fetch_and_store_profile(&self, db: &Database, symbol: &str) -> Result<Symbol> {
+        let profile = self.fetch_profile(symbol)?;
+        db.upsert_symbol(&profile)?;
+        if self.rate_limiter.is_none() {
+            db.log_api_call(SOURCE_YAHOO, "profile", symbol)?;
+        }
+        Ok(db.get_symbol(symbol)?.unwrap_or(profile))
+    }
+
     /// Fetch and store prices directly to database
     pub fn fetch_and_store(
         &self,
@@ -141,12 +357,96 @@ impl YahooFinance {
         period: &str,
     ) -> Result<usize> {
         let prices = self.fetch_prices(symbol, period)?;
-        let count = db.upsert_daily_prices(&prices)?;
-        db.log_api_call("yahoo_finance", "history", symbol)?;
+        self.store_prices(db, symbol, &prices)
+    }
+
+    /// Write fetched prices for one symbol (single transaction) and log the call.
+    /// Symbols without a stored name also get their profile fetched; a failed
+    /// profile lookup does not fail the price fetch.
+    fn store_prices(&self, db: &mut Database, symbol: &str, prices: &[DailyPrice]) -> Result<usize> {
+        let count = db.upsert_daily_prices(prices)?;
+        // With a limiter each request was logged as it was made
+        if self.rate_limiter.is_none() {
+            db.log_api_call(SOURCE_YAHOO, "history", symbol)?;
+        }
         println!("[OK] Stored {} records for {}", count, symbol);
+
+        if db.get_symbol(symbol)?.is_none_or(|s| s.name.is_none()) {
+            if let Err(e) = self.fetch_and_store_profile(db, symbol) {
+                println!("[WARN] Could not fetch profile for {}: {}", symbol, e);
+            }
+        }
+
+        // Profiles are often missing for crypto/forex pairs; fall back to the symbol's shape
+        if db.get_symbol(symbol)?.is_none_or(|s| s.asset_class.is_none()) {
+            db.upsert_symbol(&Symbol {
+                symbol: symbol.to_string(),
+                name: None,
+                sector: None,
+                industry: None,
+                market_cap: None,
+                country: None,
+                exchange: None,
+                currency: None,
+                isin: None,
+                asset_class: Some(AssetClass::from_symbol(symbol).as_str().to_string()),
+            })?;
+        }
+
         Ok(count)
     }
 
+    /// Fetch several symbols concurrently (at most 8 requests in flight) and store them
+    ///
+    /// HTTP requests run on worker threads; database writes stay on the calling
+    /// thread so SQLite only ever sees one writer. Returns a result per symbol,
+    /// in the same order as `symbols`.
+    pub fn fetch_and_store_batch(
+        &self,
+        db: &mut Database,
+        symbols: &[String],
+        period: &str,
+    ) -> Vec<(String, Result<usize>)> {
+        let next = AtomicUsize::new(0);
+        let (tx, rx) = mpsc::channel();
+        let mut results: Vec<Option<Result<usize>>> = symbols.iter().map(|_| None).collect();
+
+        std::thread::scope(|scope| {
+            for _ in 0..MAX_CONCURRENT_FETCHES.min(symbols.len()) {
+                let tx = tx.clone();
+                let next = &next;
+                scope.spawn(move || loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(symbol) = symbols.get(i) else {
+                        break;
+                    };
+                    if tx.send((i, self.fetch_prices(symbol, period))).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(tx);
+
+            for (i, fetched) in rx {
+                results[i] = Some(
+                    fetched.and_then(|prices| self.store_prices(db, &symbols[i], &prices)),
+                );
+            }
+        });
+
+        symbols
+            .iter()
+            .cloned()
+            .zip(results)
+            .map(|(symbol, result)| {
+                let result = result.unwrap_or_else(|| {
+                    Err(PipelineError::ApiError(format!("{} was not fetched", symbol)))
+                });
+                (symbol, result)
+            })
+            .collect()
+    }
+
     /// Batch fetch multiple symbols
     pub fn fetch_batch(
         &self,
@@ -188,9 +488,21 @@ impl YahooFinance {
     }
 }
 
+/// Source of daily bars for symbols that aren't stored yet: Yahoo, or a stub in tests
+pub trait PriceFetcher {
+    fn fetch_prices(&self, symbol: &str, period: &str) -> Result<Vec<DailyPrice>>;
+}
+
+impl PriceFetcher for YahooFinance {
+    fn fetch_prices(&self, symbol: &str, period: &str) -> Result<Vec<DailyPrice>> {
+        YahooFinance::fetch_prices(self, symbol, period)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_http::serve;
 
     #[test]
     fn test_fetch_aapl() {
@@ -199,4 +511,151 @@ mod tests {
         assert!(!prices.is_empty());
         assert_eq!(prices[0].symbol, "AAPL");
     }
+
+    fn chart_body() -> String {
+        r#"{"chart":{"result":[{"meta":{"symbol":"TEST"},"timestamp":[1767225600,1767312000],
+            "indicators":{"quote":[{"open":[10.0,11.0],"high":[10.5,11.5],"low":[9.5,10.5],
+            "close":[10.2,11.2],"volume":[1000,2000]}]}}],"error":null}}"#
+            .to_string()
+    }
+
+    #[test]
+    fn test_retries_rate_limit_then_stores() {
+        let (url, _) = serve(vec![
+            (429, String::new()),
+            (429, String::new()),
+            (200, chart_body()),
+        ]);
+        let client = YahooFinance::new()
+            .with_base_url(&url)
+            .with_retry_delay(Duration::from_millis(1));
+        let mut db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+
+        let count = client.fetch_and_store(&mut db, "TEST", "5d").unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(db.get_prices("TEST").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_no_retry_on_not_found() {
+        let (url, _) = serve(vec![(404, String::new()), (200, chart_body())]);
+        let client = YahooFinance::new()
+            .with_base_url(&url)
+            .with_retry_delay(Duration::from_millis(1));
+
+        let err = client.fetch_prices("TEST", "5d").unwrap_err();
+
+        assert!(err.to_string().contains("after 1 attempt(s)"), "{}", err);
+    }
+
+    #[test]
+    fn test_gives_up_after_max_attempts() {
+        let (url, _) = serve(vec![(503, String::new()), (503, String::new())]);
+        let client = YahooFinance::new()
+            .with_base_url(&url)
+            .with_retries(2)
+            .with_retry_delay(Duration::from_millis(1));
+
+        let err = client.fetch_prices("TEST", "5d").unwrap_err();
+
+        assert!(err.to_string().contains("after 2 attempt(s)"), "{}", err);
+    }
+
+    #[test]
+    fn test_fetch_and_store_batch_reports_per_symbol() {
+        let (url, _) = serve(vec![(200, chart_body()), (404, String::new())]);
+        let client = YahooFinance::new()
+            .with_base_url(&url)
+            .with_retry_delay(Duration::from_millis(1));
+        let mut db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+
+        let results = client.fetch_and_store_batch(
+            &mut db,
+            &["TEST".to_string(), "OTHER".to_string()],
+            "5d",
+        );
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "TEST");
+        assert_eq!(results[1].0, "OTHER");
+        // One request got the chart, the other the 404
+        assert_eq!(results.iter().filter(|(_, r)| r.is_ok()).count(), 1);
+        assert_eq!(results.iter().filter(|(_, r)| r.is_err()).count(), 1);
+    }
+
+    #[test]
+    fn test_fetch_and_store_dividends() {
+        let body = r#"{"chart":{"result":[{"meta":{"symbol":"TEST"},"timestamp":[1767225600],
+            "indicators":{"quote":[{"open":[10.0],"high":[10.5],"low":[9.5],"close":[10.2],"volume":[1000]}]},
+            "events":{"dividends":{"1770940800":{"amount":0.26,"date":1770940800},
+            "1762905600":{"amount":0.25,"date":1762905600}}}}],"error":null}}"#;
+        let (url, _) = serve(vec![(200, body.to_string())]);
+        let client = YahooFinance::new().with_base_url(&url);
+        let mut db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+
+        assert_eq!(client.fetch_and_store_dividends(&mut db, "TEST").unwrap(), 2);
+
+        let stored = db.get_dividends("TEST", None).unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[0].ex_date.to_string(), "2025-11-12");
+        assert_eq!(stored[1].amount, 0.26);
+    }
+
+    fn profile_body(sector: &str) -> String {
+        format!(
+            r#"{{"quoteSummary":{{"result":[{{"assetProfile":{{"sector":"{}","industry":"Consumer Electronics","country":"United States"}},
+            "price":{{"longName":"Test Corp","shortName":"Test","exchangeName":"NasdaqGS","currency":"USD",
+            "marketCap":{{"raw":1500000000.0,"fmt":"1.5B"}},"quoteType":"EQUITY"}}}}],"error":null}}}}"#,
+            sector
+        )
+    }
+
+    #[test]
+    fn test_fetch_and_store_populates_profile_without_overwriting() {
+        let (url, _) = serve(vec![
+            (200, chart_body()),
+            (200, profile_body("Technology")),
+            (200, profile_body("Utilities")),
+        ]);
+        let client = YahooFinance::new()
+            .with_base_url(&url)
+            .with_retry_delay(Duration::from_millis(1));
+        let mut db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+
+        client.fetch_and_store(&mut db, "TEST", "5d").unwrap();
+
+        let symbol = db.get_symbol("TEST").unwrap().unwrap();
+        assert_eq!(symbol.name.as_deref(), Some("Test Corp"));
+        assert_eq!(symbol.sector.as_deref(), Some("Technology"));
+        assert_eq!(symbol.industry.as_deref(), Some("Consumer Electronics"));
+        assert_eq!(symbol.asset_class.as_deref(), Some("Equity"));
+        assert_eq!(symbol.market_cap, Some(1_500_000_000.0));
+
+        // Re-fetching keeps the sector already on file
+        let refetched = client.fetch_and_store_profile(&db, "TEST").unwrap();
+        assert_eq!(refetched.sector.as_deref(), Some("Technology"));
+    }
+
+    #[test]
+    fn test_fetch_crypto_tags_asset_class_from_symbol() {
+        // No profile for the pair: the asset class comes from the symbol's shape
+        let (url, _) = serve(vec![(200, chart_body()), (404, String::new())]);
+        let client = YahooFinance::new()
+            .with_base_url(&url)
+            .with_retry_delay(Duration::from_millis(1));
+        let mut db = Database::open_in_memory().unwrap();
+        db.init_schema().unwrap();
+
+        client.fetch_and_store(&mut db, "BTC-USD", "5d").unwrap();
+
+        let symbol = db.get_symbol("BTC-USD").unwrap().unwrap();
+        assert_eq!(symbol.asset_class.as_deref(), Some(AssetClass::Crypto.as_str()));
+        assert_eq!(AssetClass::from_symbol("EURUSD=X"), AssetClass::Forex);
+        assert_eq!(AssetClass::from_symbol("BRK-B"), AssetClass::Equity);
+    }
 }
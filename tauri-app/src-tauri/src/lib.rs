@@ -1,39 +1,68 @@
 //! Tauri GUI backend for Financial Pipeline
 
 use financial_pipeline::{
-    calculate_all, AlertCondition, BacktestConfig, BacktestEngine, Database, Fred, GoogleTrends,
-    IndicatorAlert, IndicatorAlertCondition, IndicatorAlertType, PositionType, SignalEngine,
-    Strategy, StrategyConditionType, YahooFinance,
-    VectorStore, MarketEvent, PricePattern,
-    ClaudeClient, FinancialContext, PriceContext as ClaudePriceContext,
-    FinnhubClient, SimpleNewsItem, PriceReaction,
-    PaperWallet, PaperPosition, PaperTrade, PaperTradeAction,
-    AiTrader, AiTraderConfig, AiTradingSession, AiTradeDecision, AiPerformanceSnapshot,
+    calculate_adhoc, calculate_all, calculate_incremental, correlation_matrix, fib_levels, heikin_ashi, is_projected_indicator, pivot_points, resample, Timeframe, AlertCondition, AllocationBucket, BacktestConfig, BacktestEngine, DailyPrice, Database, DatabaseOptions, DatabasePool, MonteCarloResult, Fred, GoogleTrends, MacroData,
+    OptimizationObjective, ParamGrid,
+    IndicatorAlert, IndicatorAlertCondition, IndicatorAlertType, PositionType, PriceAlert, SignalConfig, SignalEngine, SignalWeights, find_unfilled_gaps,
+    Strategy, StrategyConditionType, StrategyRule, Symbol, SymbolMatch, TradeDirection, YahooFinance, IndicatorConfig,
+    VectorStore, VectorSearchFilter, MarketEvent, PricePattern, event_id,
+    ClaudeClient, ClaudeMessage, ChatResult, FinancialContext, PriceContext as ClaudePriceContext, trim_history,
+    estimate_claude_cost,
+    Conversation, ConversationMessage, DataFreshness, Dividend, DuplicateSymbol, PortfolioAllocation, PriceStats, PrunedTable, StopSuggestion, RebalanceTrade, RefreshSettings,
+    Webhook, WebhookDispatcher, WebhookEvent, CommandError, PipelineError, normalize_symbol, AuditLogEntry,
+    write_backtest_report, write_portfolio_report, HoldingLine, IndicatorRunSummary,
+    FinnhubCache, FinnhubClient, SimpleNewsItem, PriceReaction, EarningsEvent,
+    PaperWallet, PaperPosition, PaperTrade, PaperTradeAction, PaperOrder, PaperOrderStatus, PaperOrderType, CostBasisMethod,
+    AiTrader, AiReplayResult, ModelDecisions, AiTraderConfig, AiTradingSession, AiTradeDecision, AiPerformanceSnapshot,
     AiPredictionAccuracy, AiTraderStatus, BenchmarkComparison, CompoundingForecast,
     // DC Trader types
-    DcWallet, DcPosition, DcTrade, PortfolioSnapshot, TeamConfig, ImportResult, CompetitionStats,
+    DcWallet, DcPosition, DcTrade, PortfolioSnapshot, TeamConfig, TradeFees, ImportResult, CompetitionStats,
 };
-use financial_pipeline::ollama::{OllamaClient, SentimentResult, PatternExplanation};
-use chrono::Utc;
-use serde::Serialize;
-use std::sync::Mutex;
+use financial_pipeline::ollama::{OllamaClient, OllamaModel, OllamaStreamError, SentimentResult, PatternExplanation};
+use financial_pipeline::claude::{DEFAULT_HISTORY_TOKEN_BUDGET, DEFAULT_MAX_CONTEXT_TOKENS};
+use financial_pipeline::ratelimit::{RateLimit, RateLimiter, SOURCE_FINNHUB, SOURCE_FRED, SOURCE_YAHOO};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use tauri::State;
 
 /// Get the absolute path to a data file
-/// Uses FP_DATA_DIR env var if set, otherwise defaults to absolute path for development
-fn get_data_path(filename: &str) -> String {
-    let base = std::env::var("FP_DATA_DIR")
-        .unwrap_or_else(|_| {
-            // Default to absolute Windows path for the app (runs as Windows exe)
-            // Note: Use Windows path format (X:\...) not WSL format (/mnt/x/...)
-            r"X:\dev\financial-pipeline-rs\data".to_string()
-        });
-    format!("{}\\{}", base, filename)
+/// Uses FP_DATA_DIR env var if set (relative values resolve against the executable's
+/// directory), otherwise the platform data directory (e.g. %APPDATA%, ~/.local/share)
+fn get_data_path(filename: &str) -> PathBuf {
+    data_dir().join(filename)
+}
+
+/// Resolve the base data directory
+fn data_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("FP_DATA_DIR").filter(|d| !d.is_empty()) {
+        let dir = PathBuf::from(dir);
+        if dir.is_absolute() {
+            return dir;
+        }
+        let exe_dir = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+            .unwrap_or_default();
+        return exe_dir.join(dir);
+    }
+
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("financial-pipeline")
 }
 
 /// Application state holding the database connection
 struct AppState {
-    db: Mutex<Database>,
+    /// Pooled connections to finance.db, so a slow command or background refresh
+    /// doesn't make every other command wait
+    db: DatabasePool,
+    /// Finnhub responses, persisted in finance.db and shared by every Finnhub command
+    finnhub_cache: Arc<FinnhubCache>,
+    /// Per-source request caps checked by the Yahoo, FRED and Finnhub clients
+    rate_limiter: Arc<RateLimiter>,
 }
 
 /// Symbol with latest price and percent change
@@ -44,6 +73,7 @@ struct SymbolPrice {
     change_percent: f64,
     change_direction: String, // "up", "down", or "unchanged"
     favorited: bool,          // moon icon for auto-refresh
+    stale: bool,              // no new daily bar within the staleness threshold
 }
 
 /// Command result
@@ -53,6 +83,13 @@ struct CommandResult {
     message: String,
 }
 
+/// Result of a command that inserts a row, carrying the new row's id
+#[derive(Serialize)]
+struct CreatedResponse {
+    id: i64,
+    message: String,
+}
+
 /// Indicator data for frontend
 #[derive(Serialize)]
 struct IndicatorData {
@@ -68,19 +105,60 @@ struct MacroDataResponse {
     value: f64,
     date: String,
     source: String,
+    frequency: Option<String>,
+}
+
+impl From<MacroData> for MacroDataResponse {
+    fn from(d: MacroData) -> Self {
+        Self {
+            indicator: d.indicator,
+            value: d.value,
+            date: d.date.to_string(),
+            source: d.source,
+            frequency: d.frequency,
+        }
+    }
+}
+
+/// Maximum number of open connections to finance.db
+const DB_POOL_SIZE: u32 = 8;
+
+/// Run a blocking database read on a pooled connection, off the async runtime's threads
+async fn with_pooled_db<T, F>(state: &AppState, read: F) -> Result<T, CommandError>
+where
+    T: Send + 'static,
+    F: FnOnce(&Database) -> Result<T, CommandError> + Send + 'static,
+{
+    let pool = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let db = pool.get().map_err(CommandError::from)?;
+        read(&db)
+    })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 /// Get all symbols with their latest prices and percent change
 #[tauri::command]
-fn get_symbols(state: State<AppState>) -> Result<Vec<SymbolPrice>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+async fn get_symbols(state: State<'_, AppState>) -> Result<Vec<SymbolPrice>, CommandError> {
+    with_pooled_db(&state, load_symbol_prices).await
+}
 
-    let symbols = db.get_symbols_with_data().map_err(|e| e.to_string())?;
+fn load_symbol_prices(db: &Database) -> Result<Vec<SymbolPrice>, CommandError> {
+    let symbols = db.get_symbols_with_data().map_err(CommandError::from)?;
+    let stale_symbols: std::collections::HashSet<String> = db
+        .get_data_freshness()
+        .map_err(CommandError::from)?
+        .into_iter()
+        .filter(|f| f.stale)
+        .map(|f| f.symbol)
+        .collect();
 
     let mut result = Vec::new();
     for symbol in symbols {
         // Check if favorited
         let favorited = db.is_symbol_favorited(&symbol).unwrap_or(false);
+        let stale = stale_symbols.contains(&symbol);
 
         // Get price history to calculate percent change
         if let Ok(prices) = db.get_prices(&symbol) {
@@ -108,6 +186,7 @@ fn get_symbols(state: State<AppState>) -> Result<Vec<SymbolPrice>, String> {
                     change_percent,
                     change_direction,
                     favorited,
+                    stale,
                 });
             } else if let Some(price) = prices.last() {
                 result.push(SymbolPrice {
@@ -116,6 +195,7 @@ fn get_symbols(state: State<AppState>) -> Result<Vec<SymbolPrice>, String> {
                     change_percent: 0.0,
                     change_direction: "unchanged".to_string(),
                     favorited,
+                    stale,
                 });
             }
         }
@@ -124,25 +204,43 @@ fn get_symbols(state: State<AppState>) -> Result<Vec<SymbolPrice>, String> {
     Ok(result)
 }
 
+/// Latest daily bar per symbol and how stale it is, oldest first
+#[tauri::command]
+fn get_data_freshness(state: State<AppState>) -> Result<Vec<DataFreshness>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.get_data_freshness().map_err(CommandError::from)
+}
+
+/// High/low and average close/volume over a symbol's latest `days` bars (252 = 52 weeks)
+#[tauri::command]
+fn get_price_stats(state: State<AppState>, symbol: String, days: u32) -> Result<PriceStats, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = normalize_symbol(&symbol)
+        .ok_or_else(|| CommandError::invalid_input(format!("Invalid symbol: {}", symbol)))?;
+    db.get_price_stats(&symbol, days)
+        .map_err(CommandError::from)?
+        .ok_or_else(|| CommandError::no_data(format!("No price data for {}", symbol)))
+}
+
 /// Toggle symbol favorite status (moon icon)
 #[tauri::command]
-fn toggle_favorite(state: State<AppState>, symbol: String) -> Result<bool, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.toggle_symbol_favorite(&symbol).map_err(|e| e.to_string())
+fn toggle_favorite(state: State<AppState>, symbol: String) -> Result<bool, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.toggle_symbol_favorite(&symbol).map_err(CommandError::from)
 }
 
 /// Get all favorited symbols
 #[tauri::command]
-fn get_favorited_symbols(state: State<AppState>) -> Result<Vec<String>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.get_favorited_symbols().map_err(|e| e.to_string())
+fn get_favorited_symbols(state: State<AppState>) -> Result<Vec<String>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.get_favorited_symbols().map_err(CommandError::from)
 }
 
 /// Favorite all DC position symbols for auto-refresh
 #[tauri::command]
-fn favorite_dc_positions(state: State<AppState>) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let symbols = db.favorite_dc_positions().map_err(|e| e.to_string())?;
+fn favorite_dc_positions(state: State<AppState>) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbols = db.favorite_dc_positions().map_err(CommandError::from)?;
     Ok(CommandResult {
         success: true,
         message: format!("Added {} DC symbols to auto-refresh: {}", symbols.len(), symbols.join(", ")),
@@ -151,9 +249,9 @@ fn favorite_dc_positions(state: State<AppState>) -> Result<CommandResult, String
 
 /// Favorite all KALIC position symbols for auto-refresh
 #[tauri::command]
-fn favorite_paper_positions(state: State<AppState>) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let symbols = db.favorite_paper_positions().map_err(|e| e.to_string())?;
+fn favorite_paper_positions(state: State<AppState>) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbols = db.favorite_paper_positions().map_err(CommandError::from)?;
     Ok(CommandResult {
         success: true,
         message: format!("Added {} KALIC symbols to auto-refresh: {}", symbols.len(), symbols.join(", ")),
@@ -166,14 +264,17 @@ fn fetch_prices(
     state: State<AppState>,
     symbols: String,
     period: String,
-) -> Result<CommandResult, String> {
-    let mut db = state.db.lock().map_err(|e| e.to_string())?;
-
-    let symbol_list: Vec<String> = symbols
-        .split(',')
-        .map(|s| s.trim().to_uppercase())
-        .filter(|s| !s.is_empty())
-        .collect();
+) -> Result<CommandResult, CommandError> {
+    let mut db = state.db.get().map_err(CommandError::from)?;
+
+    let mut symbol_list: Vec<String> = Vec::new();
+    for raw in symbols.split(',').filter(|s| !s.trim().is_empty()) {
+        let symbol = normalize_symbol(raw)
+            .ok_or_else(|| CommandError::invalid_input(format!("Invalid symbol: {}", raw.trim())))?;
+        if !symbol_list.contains(&symbol) {
+            symbol_list.push(symbol);
+        }
+    }
 
     if symbol_list.is_empty() {
         return Ok(CommandResult {
@@ -182,33 +283,94 @@ fn fetch_prices(
         });
     }
 
-    let yahoo = YahooFinance::new();
+    let yahoo = YahooFinance::new().with_rate_limiter(state.rate_limiter.clone());
 
-    let mut success_count = 0;
-    let mut fail_count = 0;
+    let results = yahoo.fetch_and_store_batch(&mut db, &symbol_list, &period);
+    let failed: Vec<String> = results
+        .iter()
+        .filter_map(|(symbol, result)| {
+            result.as_ref().err().map(|e| format!("{} ({})", symbol, e))
+        })
+        .collect();
+    let success_count = results.len() - failed.len();
 
-    for symbol in &symbol_list {
-        match yahoo.fetch_and_store(&mut db, symbol, &period) {
-            Ok(_) => success_count += 1,
-            Err(_) => fail_count += 1,
-        }
+    // New prices may trigger queued limit/stop orders
+    let filled = db.process_pending_paper_orders().map_err(CommandError::from)?;
+    if !filled.is_empty() {
+        println!("[OK] Filled {} pending paper orders", filled.len());
     }
 
-    Ok(CommandResult {
-        success: fail_count == 0,
-        message: format!(
-            "Fetched {} symbols ({} success, {} failed)",
+    let message = if failed.is_empty() {
+        format!(
+            "Fetched {} symbols ({} success, 0 failed)",
+            symbol_list.len(),
+            success_count
+        )
+    } else {
+        format!(
+            "Fetched {} symbols ({} success, {} failed: {})",
             symbol_list.len(),
             success_count,
-            fail_count
-        ),
+            failed.len(),
+            failed.join(", ")
+        )
+    };
+
+    Ok(CommandResult {
+        success: failed.is_empty(),
+        message,
     })
 }
 
+/// Symbol metadata for frontend
+#[derive(Serialize)]
+struct SymbolProfileResponse {
+    symbol: String,
+    name: Option<String>,
+    sector: Option<String>,
+    industry: Option<String>,
+    market_cap: Option<f64>,
+    country: Option<String>,
+    exchange: Option<String>,
+    currency: Option<String>,
+    asset_class: Option<String>,
+}
+
+impl From<Symbol> for SymbolProfileResponse {
+    fn from(s: Symbol) -> Self {
+        Self {
+            symbol: s.symbol,
+            name: s.name,
+            sector: s.sector,
+            industry: s.industry,
+            market_cap: s.market_cap,
+            country: s.country,
+            exchange: s.exchange,
+            currency: s.currency,
+            asset_class: s.asset_class,
+        }
+    }
+}
+
+/// Fetch a symbol's profile (name, sector, industry) from Yahoo and store it
+#[tauri::command]
+fn fetch_symbol_profile(state: State<AppState>, symbol: String) -> Result<SymbolProfileResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.trim().to_uppercase();
+
+    let profile = YahooFinance::new()
+        .with_rate_limiter(state.rate_limiter.clone())
+        .fetch_and_store_profile(&db, &symbol)
+        .map_err(CommandError::from)?;
+
+    println!("[OK] Stored profile for {}", symbol);
+    Ok(profile.into())
+}
+
 /// Fetch FRED macro data
 #[tauri::command]
-fn fetch_fred(state: State<AppState>, indicators: String) -> Result<CommandResult, String> {
-    let mut db = state.db.lock().map_err(|e| e.to_string())?;
+fn fetch_fred(state: State<AppState>, indicators: String) -> Result<CommandResult, CommandError> {
+    let mut db = state.db.get().map_err(CommandError::from)?;
 
     let indicator_list: Vec<&str> = indicators
         .split(',')
@@ -223,7 +385,7 @@ fn fetch_fred(state: State<AppState>, indicators: String) -> Result<CommandResul
         });
     }
 
-    let fred = Fred::new();
+    let fred = Fred::new().with_rate_limiter(state.rate_limiter.clone());
 
     let mut success_count = 0;
     let mut fail_count = 0;
@@ -248,38 +410,56 @@ fn fetch_fred(state: State<AppState>, indicators: String) -> Result<CommandResul
 
 /// Get macro data summary (latest value for each indicator)
 #[tauri::command]
-fn get_macro_data(state: State<AppState>) -> Result<Vec<MacroDataResponse>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_macro_data(state: State<AppState>) -> Result<Vec<MacroDataResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let data = db.get_macro_summary().map_err(|e| e.to_string())?;
+    let data = db.get_macro_summary().map_err(CommandError::from)?;
 
-    Ok(data
-        .into_iter()
-        .map(|d| MacroDataResponse {
-            indicator: d.indicator,
-            value: d.value,
-            date: d.date.to_string(),
-            source: d.source,
-        })
-        .collect())
+    Ok(data.into_iter().map(MacroDataResponse::from).collect())
+}
+
+/// Get an indicator's observations over the last N days, oldest first (for charting)
+#[tauri::command]
+fn get_macro_history(
+    state: State<AppState>,
+    indicator: String,
+    days: i32,
+) -> Result<Vec<MacroDataResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let data = db
+        .get_macro_history(&indicator.to_uppercase(), days)
+        .map_err(CommandError::from)?;
+
+    Ok(data.into_iter().map(MacroDataResponse::from).collect())
 }
 
 /// Get price for a single symbol
 #[tauri::command]
-fn get_price(state: State<AppState>, symbol: String) -> Result<Option<f64>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_price(state: State<AppState>, symbol: String) -> Result<Option<f64>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     db.get_latest_price(&symbol.to_uppercase())
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
 /// Calculate indicators for a symbol
 #[tauri::command]
-fn calculate_indicators(state: State<AppState>, symbol: String) -> Result<CommandResult, String> {
-    let mut db = state.db.lock().map_err(|e| e.to_string())?;
+fn calculate_indicators(
+    state: State<AppState>,
+    symbol: String,
+    use_adjusted: Option<bool>,
+    full_recompute: Option<bool>,
+) -> Result<CommandResult, CommandError> {
+    let mut db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
-    // Get price history
-    let prices = db.get_prices(&symbol).map_err(|e| e.to_string())?;
+    // Get price history (adjusted closes avoid false signals around dividends/splits)
+    let prices = if use_adjusted.unwrap_or(false) {
+        db.get_adjusted_prices(&symbol)
+    } else {
+        db.get_prices(&symbol)
+    }
+    .map_err(CommandError::from)?;
 
     if prices.is_empty() {
         return Ok(CommandResult {
@@ -288,13 +468,18 @@ fn calculate_indicators(state: State<AppState>, symbol: String) -> Result<Comman
         });
     }
 
-    // Calculate all indicators
-    let indicators = calculate_all(&prices);
+    // Only extend stored series unless asked to rebuild (e.g. after switching to adjusted prices)
+    let indicators = if full_recompute.unwrap_or(false) {
+        calculate_all(&prices)
+    } else {
+        let existing = db.get_latest_indicators(&symbol).map_err(CommandError::from)?;
+        calculate_incremental(&prices, &existing)
+    };
     let count = indicators.len();
 
     // Store them
     db.upsert_indicators(&indicators)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     println!("[OK] Calculated {} indicator values for {}", count, symbol);
 
@@ -304,15 +489,77 @@ fn calculate_indicators(state: State<AppState>, symbol: String) -> Result<Comman
     })
 }
 
+/// Calculate indicators for every symbol with price data (nightly refresh)
+#[tauri::command]
+fn calculate_all_indicators(
+    state: State<AppState>,
+    full_recompute: Option<bool>,
+) -> Result<Vec<IndicatorRunSummary>, CommandError> {
+    let mut db = state.db.get().map_err(CommandError::from)?;
+
+    let summary = db
+        .calculate_all_indicators(full_recompute.unwrap_or(false))
+        .map_err(CommandError::from)?;
+
+    let written: usize = summary.iter().map(|s| s.indicators).sum();
+    let skipped = summary.iter().filter(|s| s.skipped).count();
+    println!(
+        "[OK] Calculated {} indicator values across {} symbols ({} skipped)",
+        written,
+        summary.len(),
+        skipped
+    );
+
+    Ok(summary)
+}
+
+/// Calculate indicators for pasted/what-if OHLCV bars without touching the database
+#[tauri::command]
+fn compute_indicators_adhoc(
+    prices: Vec<PricePoint>,
+    config: Option<IndicatorConfig>,
+) -> Result<Vec<IndicatorData>, CommandError> {
+    let prices = prices
+        .into_iter()
+        .map(|p| {
+            let date = NaiveDate::parse_from_str(&p.date, "%Y-%m-%d")
+                .map_err(|e| CommandError::invalid_input(format!("Invalid date '{}': {}", p.date, e)))?;
+            Ok(DailyPrice {
+                symbol: "ADHOC".to_string(),
+                date,
+                open: p.open,
+                high: p.high,
+                low: p.low,
+                close: p.close,
+                volume: p.volume,
+                adjusted_close: None,
+                source: "adhoc".to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, CommandError>>()?;
+
+    let indicators = calculate_adhoc(&prices, &config.unwrap_or_default())
+        .map_err(CommandError::invalid_input)?;
+
+    Ok(indicators
+        .into_iter()
+        .map(|i| IndicatorData {
+            name: i.indicator_name,
+            value: i.value,
+            date: i.date.to_string(),
+        })
+        .collect())
+}
+
 /// Get latest indicators for a symbol
 #[tauri::command]
-fn get_indicators(state: State<AppState>, symbol: String) -> Result<Vec<IndicatorData>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_indicators(state: State<AppState>, symbol: String) -> Result<Vec<IndicatorData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
     let indicators = db
         .get_latest_indicators(&symbol)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(indicators
         .into_iter()
@@ -330,13 +577,13 @@ fn get_indicator_history(
     state: State<AppState>,
     symbol: String,
     indicator_name: String,
-) -> Result<Vec<IndicatorData>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<IndicatorData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
     let indicators = db
         .get_indicator_history(&symbol, &indicator_name)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(indicators
         .into_iter()
@@ -348,24 +595,76 @@ fn get_indicator_history(
         .collect())
 }
 
-/// Price point for charting
-#[derive(Serialize)]
+/// List the indicator series stored for a symbol
+#[tauri::command]
+fn get_available_indicators(state: State<AppState>, symbol: String) -> Result<Vec<String>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.get_available_indicators(&symbol.to_uppercase())
+        .map_err(CommandError::from)
+}
+
+/// Price point for charting, also accepted as ad-hoc input
+#[derive(Serialize, Deserialize)]
 struct PricePoint {
     date: String,
     open: f64,
     high: f64,
     low: f64,
     close: f64,
+    #[serde(default)]
     volume: i64,
 }
 
 /// Get price history for charting
+/// `offset`/`limit` page through the series (oldest first); omitted returns everything
+#[tauri::command]
+fn get_price_history(
+    state: State<AppState>,
+    symbol: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<Vec<PricePoint>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    let prices = db
+        .get_prices_paginated(&symbol, offset.unwrap_or(0), limit.unwrap_or(usize::MAX))
+        .map_err(CommandError::from)?;
+
+    Ok(prices
+        .into_iter()
+        .map(|p| PricePoint {
+            date: p.date.to_string(),
+            open: p.open,
+            high: p.high,
+            low: p.low,
+            close: p.close,
+            volume: p.volume,
+        })
+        .collect())
+}
+
+/// Get price history between two dates (YYYY-MM-DD, inclusive)
 #[tauri::command]
-fn get_price_history(state: State<AppState>, symbol: String) -> Result<Vec<PricePoint>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_price_range(
+    state: State<AppState>,
+    symbol: String,
+    from_date: String,
+    to_date: String,
+) -> Result<Vec<PricePoint>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
-    let prices = db.get_prices(&symbol).map_err(|e| e.to_string())?;
+    let from = NaiveDate::parse_from_str(&from_date, "%Y-%m-%d")
+        .map_err(|e| {
+            CommandError::invalid_input(format!("Invalid from_date '{}': {}", from_date, e))
+        })?;
+    let to = NaiveDate::parse_from_str(&to_date, "%Y-%m-%d")
+        .map_err(|e| {
+            CommandError::invalid_input(format!("Invalid to_date '{}': {}", to_date, e))
+        })?;
+
+    let prices = db.get_price_range(&symbol, from, to).map_err(CommandError::from)?;
 
     Ok(prices
         .into_iter()
@@ -380,14 +679,195 @@ fn get_price_history(state: State<AppState>, symbol: String) -> Result<Vec<Price
         .collect())
 }
 
+/// Get Heikin-Ashi candles for charting (oldest first)
+#[tauri::command]
+fn get_heikin_ashi(state: State<AppState>, symbol: String) -> Result<Vec<PricePoint>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    let prices = db.get_prices(&symbol).map_err(CommandError::from)?;
+
+    Ok(heikin_ashi(&prices)
+        .into_iter()
+        .map(|p| PricePoint {
+            date: p.date.to_string(),
+            open: p.open,
+            high: p.high,
+            low: p.low,
+            close: p.close,
+            volume: p.volume,
+        })
+        .collect())
+}
+
+/// Stored daily prices aggregated to the requested timeframe
+fn load_resampled(db: &Database, symbol: &str, timeframe: &str) -> Result<Vec<DailyPrice>, CommandError> {
+    let timeframe: Timeframe = timeframe.parse().map_err(CommandError::invalid_input)?;
+    let prices = db.get_prices(symbol).map_err(CommandError::from)?;
+    Ok(resample(&prices, timeframe))
+}
+
+/// Get weekly or monthly bars built from stored daily prices (oldest first)
+#[tauri::command]
+fn get_resampled_prices(
+    state: State<AppState>,
+    symbol: String,
+    timeframe: String,
+) -> Result<Vec<PricePoint>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    Ok(load_resampled(&db, &symbol, &timeframe)?
+        .into_iter()
+        .map(|p| PricePoint {
+            date: p.date.to_string(),
+            open: p.open,
+            high: p.high,
+            low: p.low,
+            close: p.close,
+            volume: p.volume,
+        })
+        .collect())
+}
+
+/// Calculate the standard indicator set on weekly or monthly bars (not stored)
+/// Forward-projected Ichimoku spans and √252-annualized volatility assume daily bars,
+/// so they are left out.
+#[tauri::command]
+fn get_resampled_indicators(
+    state: State<AppState>,
+    symbol: String,
+    timeframe: String,
+) -> Result<Vec<IndicatorData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    let bars = load_resampled(&db, &symbol, &timeframe)?;
+    Ok(calculate_all(&bars)
+        .into_iter()
+        .filter(|i| !is_projected_indicator(&i.indicator_name) && !i.indicator_name.starts_with("HV_"))
+        .map(|i| IndicatorData {
+            name: i.indicator_name,
+            value: i.value,
+            date: i.date.to_string(),
+        })
+        .collect())
+}
+
+/// Pivot levels for the latest session, derived from the bar before it
+#[derive(Serialize)]
+struct PivotPointsResponse {
+    symbol: String,
+    /// Session the levels apply to
+    date: String,
+    /// Bar whose high/low/close produced them
+    based_on: String,
+    pivot: f64,
+    r1: f64,
+    r2: f64,
+    r3: f64,
+    s1: f64,
+    s2: f64,
+    s3: f64,
+}
+
+/// Get classic daily pivot points for a symbol
+#[tauri::command]
+fn get_pivot_points(state: State<AppState>, symbol: String) -> Result<PivotPointsResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    let prices = db.get_prices(&symbol).map_err(CommandError::from)?;
+    let [.., prev, latest] = prices.as_slice() else {
+        return Err(CommandError::no_data(format!(
+            "Need at least two bars of price data for {}",
+            symbol
+        )));
+    };
+    let levels = pivot_points(prev.high, prev.low, prev.close);
+
+    Ok(PivotPointsResponse {
+        symbol,
+        date: latest.date.to_string(),
+        based_on: prev.date.to_string(),
+        pivot: levels.pivot,
+        r1: levels.r1,
+        r2: levels.r2,
+        r3: levels.r3,
+        s1: levels.s1,
+        s2: levels.s2,
+        s3: levels.s3,
+    })
+}
+
+/// Bars scanned for the swing when no lookback is given
+const DEFAULT_FIB_LOOKBACK: usize = 60;
+
+/// Single Fibonacci retracement level
+#[derive(Serialize)]
+struct FibLevelResponse {
+    ratio: f64,
+    price: f64,
+}
+
+/// Fibonacci retracements over the highest high and lowest low of a lookback window
+#[derive(Serialize)]
+struct FibLevelsResponse {
+    symbol: String,
+    swing_high: f64,
+    swing_high_date: String,
+    swing_low: f64,
+    swing_low_date: String,
+    levels: Vec<FibLevelResponse>,
+}
+
+/// Get Fibonacci retracement levels over the last `lookback` bars
+#[tauri::command]
+fn get_fib_levels(
+    state: State<AppState>,
+    symbol: String,
+    lookback: Option<usize>,
+) -> Result<FibLevelsResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    let prices = db.get_prices(&symbol).map_err(CommandError::from)?;
+    let lookback = lookback.unwrap_or(DEFAULT_FIB_LOOKBACK).max(1);
+    let window = &prices[prices.len().saturating_sub(lookback)..];
+
+    let high = window
+        .iter()
+        .max_by(|a, b| a.high.total_cmp(&b.high))
+        .ok_or_else(|| CommandError::no_data(format!("No price data for {}", symbol)))?;
+    let low = window
+        .iter()
+        .min_by(|a, b| a.low.total_cmp(&b.low))
+        .ok_or_else(|| CommandError::no_data(format!("No price data for {}", symbol)))?;
+
+    Ok(FibLevelsResponse {
+        levels: fib_levels(high.high, low.low)
+            .into_iter()
+            .map(|l| FibLevelResponse {
+                ratio: l.ratio,
+                price: l.price,
+            })
+            .collect(),
+        symbol,
+        swing_high: high.high,
+        swing_high_date: high.date.to_string(),
+        swing_low: low.low,
+        swing_low_date: low.date.to_string(),
+    })
+}
+
 /// Export data to CSV
 #[tauri::command]
-fn export_csv(state: State<AppState>, symbol: String) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn export_csv(state: State<AppState>, symbol: String) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
     // Get price data
-    let prices = db.get_prices(&symbol).map_err(|e| e.to_string())?;
+    let prices = db.get_prices(&symbol).map_err(CommandError::from)?;
     if prices.is_empty() {
         return Ok(CommandResult {
             success: false,
@@ -396,27 +876,27 @@ fn export_csv(state: State<AppState>, symbol: String) -> Result<CommandResult, S
     }
 
     // Get indicators
-    let indicators = db.get_latest_indicators(&symbol).map_err(|e| e.to_string())?;
+    let indicators = db.get_latest_indicators(&symbol).map_err(CommandError::from)?;
 
     // Create export directory
     std::fs::create_dir_all("exports").ok();
 
     // Export prices
     let price_file = format!("exports/{}_prices.csv", symbol);
-    let mut wtr = std::fs::File::create(&price_file).map_err(|e| e.to_string())?;
+    let mut wtr = std::fs::File::create(&price_file).map_err(PipelineError::from)?;
     use std::io::Write;
-    writeln!(wtr, "date,open,high,low,close,volume").map_err(|e| e.to_string())?;
+    writeln!(wtr, "date,open,high,low,close,volume").map_err(PipelineError::from)?;
     for p in &prices {
         writeln!(wtr, "{},{},{},{},{},{}", p.date, p.open, p.high, p.low, p.close, p.volume)
-            .map_err(|e| e.to_string())?;
+            .map_err(PipelineError::from)?;
     }
 
     // Export indicators
     let ind_file = format!("exports/{}_indicators.csv", symbol);
-    let mut wtr = std::fs::File::create(&ind_file).map_err(|e| e.to_string())?;
-    writeln!(wtr, "indicator,value,date").map_err(|e| e.to_string())?;
+    let mut wtr = std::fs::File::create(&ind_file).map_err(PipelineError::from)?;
+    writeln!(wtr, "indicator,value,date").map_err(PipelineError::from)?;
     for i in &indicators {
-        writeln!(wtr, "{},{},{}", i.indicator_name, i.value, i.date).map_err(|e| e.to_string())?;
+        writeln!(wtr, "{},{},{}", i.indicator_name, i.value, i.date).map_err(PipelineError::from)?;
     }
 
     println!("[OK] Exported {} to CSV", symbol);
@@ -427,115 +907,150 @@ fn export_csv(state: State<AppState>, symbol: String) -> Result<CommandResult, S
     })
 }
 
-/// Company name to symbol mapping for fuzzy search
-fn get_symbol_mapping() -> std::collections::HashMap<&'static str, &'static str> {
-    let mut map = std::collections::HashMap::new();
-    // Tech
-    map.insert("apple", "AAPL");
-    map.insert("microsoft", "MSFT");
-    map.insert("google", "GOOGL");
-    map.insert("alphabet", "GOOGL");
-    map.insert("amazon", "AMZN");
-    map.insert("meta", "META");
-    map.insert("facebook", "META");
-    map.insert("nvidia", "NVDA");
-    map.insert("tesla", "TSLA");
-    map.insert("netflix", "NFLX");
-    map.insert("intel", "INTC");
-    map.insert("amd", "AMD");
-    map.insert("cisco", "CSCO");
-    map.insert("oracle", "ORCL");
-    map.insert("ibm", "IBM");
-    map.insert("salesforce", "CRM");
-    map.insert("adobe", "ADBE");
-    map.insert("paypal", "PYPL");
-    map.insert("uber", "UBER");
-    map.insert("airbnb", "ABNB");
-    map.insert("spotify", "SPOT");
-    map.insert("snap", "SNAP");
-    map.insert("snapchat", "SNAP");
-    map.insert("twitter", "X");
-    map.insert("palantir", "PLTR");
-    // Finance
-    map.insert("jpmorgan", "JPM");
-    map.insert("jp morgan", "JPM");
-    map.insert("goldman", "GS");
-    map.insert("goldman sachs", "GS");
-    map.insert("morgan stanley", "MS");
-    map.insert("bank of america", "BAC");
-    map.insert("wells fargo", "WFC");
-    map.insert("visa", "V");
-    map.insert("mastercard", "MA");
-    map.insert("berkshire", "BRK.B");
-    // Retail/Consumer
-    map.insert("walmart", "WMT");
-    map.insert("costco", "COST");
-    map.insert("target", "TGT");
-    map.insert("home depot", "HD");
-    map.insert("lowes", "LOW");
-    map.insert("nike", "NKE");
-    map.insert("starbucks", "SBUX");
-    map.insert("mcdonalds", "MCD");
-    map.insert("coca cola", "KO");
-    map.insert("coke", "KO");
-    map.insert("pepsi", "PEP");
-    map.insert("disney", "DIS");
-    // Healthcare
-    map.insert("johnson", "JNJ");
-    map.insert("pfizer", "PFE");
-    map.insert("moderna", "MRNA");
-    map.insert("unitedhealth", "UNH");
-    // Energy
-    map.insert("exxon", "XOM");
-    map.insert("chevron", "CVX");
-    // ETFs
-    map.insert("s&p", "SPY");
-    map.insert("s&p 500", "SPY");
-    map.insert("spy", "SPY");
-    map.insert("nasdaq", "QQQ");
-    map.insert("qqq", "QQQ");
-    map.insert("dow", "DIA");
-    map.insert("dow jones", "DIA");
-    map
-}
-
-/// Search for symbol by name (fuzzy match)
-#[tauri::command]
-fn search_symbol(query: String) -> Result<Vec<String>, String> {
-    let query = query.to_lowercase();
-    let mapping = get_symbol_mapping();
-
-    let mut results = Vec::new();
-
-    // Direct match first
-    if let Some(symbol) = mapping.get(query.as_str()) {
-        results.push(symbol.to_string());
-    }
-
-    // Partial match
-    for (name, symbol) in &mapping {
-        if name.contains(&query) || query.contains(name) {
-            if !results.contains(&symbol.to_string()) {
-                results.push(symbol.to_string());
-            }
-        }
-    }
+/// Back up the whole database to a user-chosen path
+#[tauri::command]
+fn backup_database(state: State<AppState>, path: String) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.backup_to(&path).map_err(CommandError::from)?;
 
-    // If query looks like a symbol, add it directly
-    if query.len() <= 5 && query.chars().all(|c| c.is_alphabetic()) {
-        let upper = query.to_uppercase();
-        if !results.contains(&upper) {
-            results.push(upper);
-        }
+    Ok(CommandResult {
+        success: true,
+        message: format!("Database backed up to {}", path),
+    })
+}
+
+/// Replace the database with a backup (refused if the backup is unreadable or from another schema version)
+#[tauri::command]
+fn restore_database(state: State<AppState>, path: String) -> Result<CommandResult, CommandError> {
+    let mut db = state.db.get().map_err(CommandError::from)?;
+
+    if let Err(e) = db.restore_from(&path) {
+        println!("[WARN] Restore from {} refused: {}", path, e);
+        return Ok(CommandResult {
+            success: false,
+            message: format!("Restore failed: {}", e),
+        });
     }
 
-    Ok(results)
+    Ok(CommandResult {
+        success: true,
+        message: format!("Database restored from {}", path),
+    })
 }
 
-/// Alert data for frontend
-#[derive(Serialize)]
-struct AlertData {
-    id: i64,
+/// Write watchlists, strategies, alerts and team configs to a JSON file (no prices or trades)
+#[tauri::command]
+fn export_config_file(state: State<AppState>, path: String) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let config = db.export_config().map_err(CommandError::from)?;
+    let json = serde_json::to_string_pretty(&config).map_err(PipelineError::from)?;
+    std::fs::write(&path, json).map_err(PipelineError::from)?;
+
+    println!("[OK] Exported configuration to {}", path);
+
+    Ok(CommandResult {
+        success: true,
+        message: format!("Configuration exported to {}", path),
+    })
+}
+
+/// Load a configuration file; `merge` upserts by name, otherwise existing configuration is replaced
+#[tauri::command]
+fn import_config_file(
+    state: State<AppState>,
+    path: String,
+    merge: bool,
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let json = std::fs::read_to_string(&path).map_err(PipelineError::from)?;
+    let config: serde_json::Value = serde_json::from_str(&json)
+        .map_err(|e| CommandError::invalid_input(format!("{} is not valid JSON: {}", path, e)))?;
+    let written = db.import_config(config, merge).map_err(CommandError::from)?;
+
+    println!("[OK] Imported {} configuration rows from {}", written, path);
+
+    Ok(CommandResult {
+        success: true,
+        message: format!(
+            "{} {} configuration rows from {}",
+            if merge { "Merged" } else { "Imported" },
+            written,
+            path
+        ),
+    })
+}
+
+/// Symbol search hit for frontend
+#[derive(Serialize)]
+struct SymbolMatchResponse {
+    symbol: String,
+    name: Option<String>,
+    score: f64,
+}
+
+impl From<SymbolMatch> for SymbolMatchResponse {
+    fn from(m: SymbolMatch) -> Self {
+        Self {
+            symbol: m.symbol,
+            name: m.name,
+            score: m.score,
+        }
+    }
+}
+
+/// Search for symbol by ticker, alias or company name (fuzzy match, best first)
+#[tauri::command]
+fn search_symbol(
+    state: State<AppState>,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SymbolMatchResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let query = query.trim();
+
+    let mut results: Vec<SymbolMatchResponse> = db
+        .search_symbols(query, limit.unwrap_or(10))
+        .map_err(CommandError::from)?
+        .into_iter()
+        .map(SymbolMatchResponse::from)
+        .collect();
+
+    // If query looks like a symbol, offer it directly
+    if query.len() <= 5 && !query.is_empty() && query.chars().all(|c| c.is_alphabetic()) {
+        let upper = query.to_uppercase();
+        if !results.iter().any(|r| r.symbol == upper) {
+            results.push(SymbolMatchResponse {
+                symbol: upper,
+                name: None,
+                score: 0.0,
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+/// Add a search alias (company name or nickname) for a symbol
+#[tauri::command]
+fn add_symbol_alias(state: State<AppState>, alias: String, symbol: String) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    match db.add_symbol_alias(&alias, &symbol) {
+        Ok(()) => Ok(CommandResult {
+            success: true,
+            message: format!("\"{}\" now finds {}", alias.trim(), symbol.trim().to_uppercase()),
+        }),
+        Err(e) => Ok(CommandResult {
+            success: false,
+            message: format!("Failed to add alias: {}", e),
+        }),
+    }
+}
+
+/// Alert data for frontend
+#[derive(Serialize)]
+struct AlertData {
+    id: i64,
     symbol: String,
     target_price: f64,
     condition: String,
@@ -543,6 +1058,19 @@ struct AlertData {
     created_at: String,
 }
 
+impl From<PriceAlert> for AlertData {
+    fn from(a: PriceAlert) -> Self {
+        Self {
+            id: a.id,
+            symbol: a.symbol,
+            target_price: a.target_price,
+            condition: a.condition.as_str().to_string(),
+            triggered: a.triggered,
+            created_at: a.created_at,
+        }
+    }
+}
+
 /// Add a price alert
 #[tauri::command]
 fn add_alert(
@@ -550,56 +1078,54 @@ fn add_alert(
     symbol: String,
     target_price: f64,
     condition: String,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CreatedResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
-    let alert_condition = match condition.to_lowercase().as_str() {
-        "above" => AlertCondition::Above,
-        "below" => AlertCondition::Below,
-        _ => return Err("Invalid condition. Use 'above' or 'below'".to_string()),
-    };
+    let alert_condition = AlertCondition::from_str(&condition, target_price).ok_or_else(|| {
+        CommandError::invalid_input("Invalid condition. Use 'above', 'below', 'pct_up', 'pct_down', or 'volume_multiple'")
+    })?;
 
-    db.add_alert(&symbol, target_price, alert_condition)
-        .map_err(|e| e.to_string())?;
+    let id = db
+        .add_alert(&symbol, target_price, alert_condition)
+        .map_err(CommandError::from)?;
 
-    println!("[OK] Added alert for {} {} ${:.2}", symbol, condition, target_price);
+    let description = describe_alert_condition(alert_condition, target_price);
+    println!("[OK] Added alert for {} {}", symbol, description);
 
-    Ok(CommandResult {
-        success: true,
-        message: format!("Alert set: {} {} ${:.2}", symbol, condition, target_price),
+    Ok(CreatedResponse {
+        id,
+        message: format!("Alert set: {} {}", symbol, description),
     })
 }
 
+/// Human-readable form of an alert condition, e.g. "above $150.00" or "down 5.0% in a bar"
+fn describe_alert_condition(condition: AlertCondition, target_price: f64) -> String {
+    match condition {
+        AlertCondition::Above => format!("above ${:.2}", target_price),
+        AlertCondition::Below => format!("below ${:.2}", target_price),
+        AlertCondition::PercentChangeUp(pct) => format!("up {:.1}% in a bar", pct),
+        AlertCondition::PercentChangeDown(pct) => format!("down {:.1}% in a bar", pct),
+        AlertCondition::VolumeMultiple(multiple) => format!("volume {:.1}x previous bar", multiple),
+    }
+}
+
 /// Get all alerts
 #[tauri::command]
-fn get_alerts(state: State<AppState>, only_active: bool) -> Result<Vec<AlertData>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_alerts(state: State<AppState>, only_active: bool) -> Result<Vec<AlertData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let alerts = db.get_alerts(only_active).map_err(|e| e.to_string())?;
+    let alerts = db.get_alerts(only_active).map_err(CommandError::from)?;
 
-    Ok(alerts
-        .into_iter()
-        .map(|a| AlertData {
-            id: a.id,
-            symbol: a.symbol,
-            target_price: a.target_price,
-            condition: match a.condition {
-                AlertCondition::Above => "above".to_string(),
-                AlertCondition::Below => "below".to_string(),
-            },
-            triggered: a.triggered,
-            created_at: a.created_at,
-        })
-        .collect())
+    Ok(alerts.into_iter().map(|a| a.into()).collect())
 }
 
 /// Delete an alert
 #[tauri::command]
-fn delete_alert(state: State<AppState>, alert_id: i64) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn delete_alert(state: State<AppState>, alert_id: i64) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    db.delete_alert(alert_id).map_err(|e| e.to_string())?;
+    db.delete_alert(alert_id).map_err(CommandError::from)?;
 
     Ok(CommandResult {
         success: true,
@@ -609,25 +1135,13 @@ fn delete_alert(state: State<AppState>, alert_id: i64) -> Result<CommandResult,
 
 /// Check alerts against current prices
 #[tauri::command]
-fn check_alerts(state: State<AppState>) -> Result<Vec<AlertData>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn check_alerts(state: State<AppState>) -> Result<Vec<AlertData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let triggered = db.check_alerts().map_err(|e| e.to_string())?;
+    let triggered = db.check_alerts().map_err(CommandError::from)?;
+    forward_to_webhooks(&db, WebhookEvent::PriceAlert, triggered.clone());
 
-    Ok(triggered
-        .into_iter()
-        .map(|a| AlertData {
-            id: a.id,
-            symbol: a.symbol,
-            target_price: a.target_price,
-            condition: match a.condition {
-                AlertCondition::Above => "above".to_string(),
-                AlertCondition::Below => "below".to_string(),
-            },
-            triggered: a.triggered,
-            created_at: a.created_at,
-        })
-        .collect())
+    Ok(triggered.into_iter().map(|a| a.into()).collect())
 }
 
 /// Position data for frontend
@@ -645,6 +1159,8 @@ struct PositionData {
     cost_basis: f64,
     profit_loss: f64,
     profit_loss_percent: f64,
+    /// Dividends earned (or owed, for shorts) on this lot since it was opened
+    dividend_income: f64,
 }
 
 /// Portfolio summary for frontend
@@ -655,6 +1171,7 @@ struct PortfolioSummary {
     total_cost: f64,
     total_profit_loss: f64,
     total_profit_loss_percent: f64,
+    total_dividend_income: f64,
 }
 
 /// Add a portfolio position
@@ -667,26 +1184,32 @@ fn add_position(
     position_type: String,
     date: String,
     notes: Option<String>,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let symbol = symbol.to_uppercase();
+) -> Result<CreatedResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = normalize_symbol(&symbol)
+        .ok_or_else(|| CommandError::invalid_input(format!("Invalid symbol: {}", symbol)))?;
 
     let pos_type = match position_type.to_lowercase().as_str() {
         "buy" => PositionType::Buy,
         "sell" => PositionType::Sell,
-        _ => return Err("Invalid position type. Use 'buy' or 'sell'".to_string()),
+        _ => {
+            return Err(CommandError::invalid_input(
+                "Invalid position type. Use 'buy' or 'sell'",
+            ))
+        }
     };
 
-    db.add_position(&symbol, quantity, price, pos_type, &date, notes.as_deref())
-        .map_err(|e| e.to_string())?;
+    let id = db
+        .add_position(&symbol, quantity, price, pos_type, &date, notes.as_deref())
+        .map_err(CommandError::from)?;
 
     println!(
         "[OK] Added {} position: {} x {} @ ${:.2}",
         position_type, quantity, symbol, price
     );
 
-    Ok(CommandResult {
-        success: true,
+    Ok(CreatedResponse {
+        id,
         message: format!(
             "Added {} {} shares of {} @ ${:.2}",
             position_type, quantity, symbol, price
@@ -696,21 +1219,77 @@ fn add_position(
 
 /// Get portfolio with current values and P&L
 #[tauri::command]
-fn get_portfolio(state: State<AppState>) -> Result<PortfolioSummary, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+async fn get_portfolio(state: State<'_, AppState>) -> Result<PortfolioSummary, CommandError> {
+    with_pooled_db(&state, build_portfolio_summary).await
+}
+
+/// Write a PDF report of current holdings and their allocation
+#[tauri::command]
+fn generate_portfolio_report(state: State<AppState>, path: String) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let summary = build_portfolio_summary(&db)?;
+
+    // Shorts count against the portfolio, as in the summary totals
+    let holdings: Vec<HoldingLine> = summary
+        .positions
+        .into_iter()
+        .map(|p| {
+            let sign = if p.position_type == "sell" { -1.0 } else { 1.0 };
+            HoldingLine {
+                symbol: p.symbol,
+                quantity: sign * p.quantity,
+                cost_basis: sign * p.cost_basis,
+                current_value: sign * p.current_value,
+                profit_loss: p.profit_loss,
+            }
+        })
+        .collect();
 
-    let positions = db.get_positions().map_err(|e| e.to_string())?;
+    let pages = write_portfolio_report(&holdings, Utc::now().date_naive(), &path).map_err(CommandError::from)?;
+
+    println!("[OK] Wrote {}-page portfolio report to {}", pages, path);
+
+    Ok(CommandResult {
+        success: true,
+        message: format!("Saved {}-page portfolio report to {}", pages, path),
+    })
+}
+
+fn build_portfolio_summary(db: &Database) -> Result<PortfolioSummary, CommandError> {
+    let positions = db.get_positions().map_err(CommandError::from)?;
 
     let mut position_data = Vec::new();
     let mut total_value = 0.0;
     let mut total_cost = 0.0;
+    let mut total_dividend_income = 0.0;
+    let mut dividends: std::collections::HashMap<String, Vec<Dividend>> = std::collections::HashMap::new();
 
     for pos in positions {
         let current_price = db
             .get_latest_price(&pos.symbol)
-            .map_err(|e| e.to_string())?
+            .map_err(CommandError::from)?
             .unwrap_or(pos.price);
 
+        if !dividends.contains_key(&pos.symbol) {
+            let history = db.get_dividends(&pos.symbol, None).map_err(CommandError::from)?;
+            dividends.insert(pos.symbol.clone(), history);
+        }
+        // Dividends with an ex-date after the lot was opened; shorts pay them
+        let opened = pos
+            .date
+            .get(..10)
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+        let per_share: f64 = dividends[&pos.symbol]
+            .iter()
+            .filter(|d| opened.is_some_and(|opened| d.ex_date > opened))
+            .map(|d| d.amount)
+            .sum();
+        let dividend_income = match pos.position_type {
+            PositionType::Buy => per_share * pos.quantity,
+            PositionType::Sell => -per_share * pos.quantity,
+        };
+        total_dividend_income += dividend_income;
+
         let cost_basis = pos.quantity * pos.price;
         let current_value = pos.quantity * current_price;
 
@@ -758,6 +1337,7 @@ fn get_portfolio(state: State<AppState>) -> Result<PortfolioSummary, String> {
             cost_basis,
             profit_loss,
             profit_loss_percent,
+            dividend_income,
         });
     }
 
@@ -774,15 +1354,166 @@ fn get_portfolio(state: State<AppState>) -> Result<PortfolioSummary, String> {
         total_cost,
         total_profit_loss,
         total_profit_loss_percent,
+        total_dividend_income,
+    })
+}
+
+/// Fetch a symbol's dividend history from Yahoo Finance and store it
+#[tauri::command]
+fn fetch_dividends(state: State<AppState>, symbol: String) -> Result<CommandResult, CommandError> {
+    let mut db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    let count = YahooFinance::new()
+        .with_rate_limiter(state.rate_limiter.clone())
+        .fetch_and_store_dividends(&mut db, &symbol)
+        .map_err(CommandError::from)?;
+
+    println!("[OK] Stored {} dividends for {}", count, symbol);
+
+    Ok(CommandResult {
+        success: true,
+        message: format!("Stored {} dividends for {}", count, symbol),
+    })
+}
+
+/// One sector or asset class share of the portfolio
+#[derive(Serialize)]
+struct AllocationBucketData {
+    name: String,
+    value: f64,
+    percent: f64,
+}
+
+/// Portfolio allocation breakdowns for frontend
+#[derive(Serialize)]
+struct PortfolioAllocationResponse {
+    total_value: f64,
+    by_sector: Vec<AllocationBucketData>,
+    by_asset_class: Vec<AllocationBucketData>,
+}
+
+impl From<AllocationBucket> for AllocationBucketData {
+    fn from(b: AllocationBucket) -> Self {
+        Self {
+            name: b.name,
+            value: b.value,
+            percent: b.percent,
+        }
+    }
+}
+
+impl From<PortfolioAllocation> for PortfolioAllocationResponse {
+    fn from(a: PortfolioAllocation) -> Self {
+        Self {
+            total_value: a.total_value,
+            by_sector: a.by_sector.into_iter().map(|b| b.into()).collect(),
+            by_asset_class: a.by_asset_class.into_iter().map(|b| b.into()).collect(),
+        }
+    }
+}
+
+/// Get portfolio value broken down by sector and asset class
+#[tauri::command]
+async fn get_portfolio_allocation(
+    state: State<'_, AppState>,
+) -> Result<PortfolioAllocationResponse, CommandError> {
+    with_pooled_db(&state, |db| {
+        let allocation = db.get_portfolio_allocation().map_err(CommandError::from)?;
+        Ok(allocation.into())
+    })
+    .await
+}
+
+/// Lookback for portfolio benchmark comparisons when none is given
+const DEFAULT_BENCHMARK_DAYS: i64 = 365;
+
+/// Portfolio performance against a benchmark symbol
+#[derive(Serialize)]
+struct PortfolioBenchmarkResponse {
+    benchmark: String,
+    portfolio_return_percent: f64,
+    benchmark_return_percent: f64,
+    alpha: f64,
+    tracking_data: Vec<(String, f64, f64)>, // (date, portfolio, benchmark), both rebased to 100
+}
+
+/// Compare portfolio positions with a benchmark over the last `days` days
+#[tauri::command]
+async fn get_portfolio_benchmark(
+    state: State<'_, AppState>,
+    benchmark: String,
+    days: Option<i64>,
+) -> Result<PortfolioBenchmarkResponse, CommandError> {
+    let benchmark = benchmark.trim().to_uppercase();
+    with_pooled_db(&state, move |db| {
+        let comparison = db
+            .portfolio_vs_benchmark(&benchmark, days.unwrap_or(DEFAULT_BENCHMARK_DAYS))
+            .map_err(CommandError::from)?;
+        Ok(PortfolioBenchmarkResponse {
+            benchmark,
+            portfolio_return_percent: comparison.portfolio_return_percent,
+            benchmark_return_percent: comparison.benchmark_return_percent,
+            alpha: comparison.alpha,
+            tracking_data: comparison.tracking_data,
+        })
     })
+    .await
+}
+
+/// Suggested rebalancing trade for frontend
+#[derive(Serialize)]
+struct RebalanceTradeResponse {
+    symbol: String,
+    action: String,
+    shares: f64,
+    price: f64,
+    est_value: f64,
+}
+
+impl From<RebalanceTrade> for RebalanceTradeResponse {
+    fn from(t: RebalanceTrade) -> Self {
+        Self {
+            symbol: t.symbol,
+            action: match t.action {
+                PositionType::Buy => "buy".to_string(),
+                PositionType::Sell => "sell".to_string(),
+            },
+            shares: t.shares,
+            price: t.price,
+            est_value: t.est_value,
+        }
+    }
+}
+
+/// Minimum trade value in a rebalancing plan when none is given
+const DEFAULT_MIN_REBALANCE_TRADE: f64 = 100.0;
+
+/// Trades that bring the portfolio to target allocations.
+/// `targets` is a JSON object of symbol -> percent, e.g. {"AAPL": 40, "MSFT": 30}.
+#[tauri::command]
+fn get_rebalance_plan(
+    state: State<AppState>,
+    targets: String,
+    min_trade_value: Option<f64>,
+) -> Result<Vec<RebalanceTradeResponse>, CommandError> {
+    let targets: std::collections::HashMap<String, f64> = serde_json::from_str(&targets)
+        .map_err(|e| CommandError::invalid_input(format!("Invalid targets JSON: {}", e)))?;
+
+    let db = state.db.get().map_err(CommandError::from)?;
+    let plan = db
+        .rebalance_plan(&targets, min_trade_value.unwrap_or(DEFAULT_MIN_REBALANCE_TRADE))
+        .map_err(CommandError::from)?;
+
+    Ok(plan.into_iter().map(|t| t.into()).collect())
 }
 
 /// Delete a portfolio position
 #[tauri::command]
-fn delete_position(state: State<AppState>, position_id: i64) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn delete_position(state: State<AppState>, position_id: i64) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    db.delete_position(position_id).map_err(|e| e.to_string())?;
+    db.delete_position(position_id).map_err(CommandError::from)?;
 
     Ok(CommandResult {
         success: true,
@@ -790,6 +1521,28 @@ fn delete_position(state: State<AppState>, position_id: i64) -> Result<CommandRe
     })
 }
 
+/// Undo a position delete
+#[tauri::command]
+fn restore_position(state: State<AppState>, position_id: i64) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    if !db.restore_position(position_id).map_err(CommandError::from)? {
+        return Err(CommandError::no_data(format!("No deleted position with id {}", position_id)));
+    }
+
+    Ok(CommandResult {
+        success: true,
+        message: "Position restored".to_string(),
+    })
+}
+
+/// Recent create/update/delete history for positions, paper trades and alerts
+#[tauri::command]
+fn get_audit_log(state: State<AppState>, limit: Option<usize>) -> Result<Vec<AuditLogEntry>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.get_audit_log(limit.unwrap_or(100)).map_err(CommandError::from)
+}
+
 /// Trend data point for frontend
 #[derive(Serialize)]
 struct TrendPoint {
@@ -799,8 +1552,8 @@ struct TrendPoint {
 
 /// Fetch Google Trends data for a keyword
 #[tauri::command]
-fn fetch_trends(state: State<AppState>, keyword: String) -> Result<CommandResult, String> {
-    let mut db = state.db.lock().map_err(|e| e.to_string())?;
+fn fetch_trends(state: State<AppState>, keyword: String) -> Result<CommandResult, CommandError> {
+    let mut db = state.db.get().map_err(CommandError::from)?;
 
     let trends = GoogleTrends::new();
 
@@ -824,10 +1577,10 @@ fn fetch_trends(state: State<AppState>, keyword: String) -> Result<CommandResult
 
 /// Get stored trends data for a keyword
 #[tauri::command]
-fn get_trends(state: State<AppState>, keyword: String) -> Result<Vec<TrendPoint>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_trends(state: State<AppState>, keyword: String) -> Result<Vec<TrendPoint>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let trends = db.get_trends(&keyword).map_err(|e| e.to_string())?;
+    let trends = db.get_trends(&keyword).map_err(CommandError::from)?;
 
     Ok(trends
         .into_iter()
@@ -838,6 +1591,23 @@ fn get_trends(state: State<AppState>, keyword: String) -> Result<Vec<TrendPoint>
         .collect())
 }
 
+/// Correlation of a keyword's search interest with a symbol's daily return `lag`
+/// trading days later; null when the series overlap too little
+#[tauri::command]
+fn get_trends_correlation(
+    state: State<AppState>,
+    keyword: String,
+    symbol: String,
+    lag: usize,
+) -> Result<Option<f64>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = normalize_symbol(&symbol)
+        .ok_or_else(|| CommandError::invalid_input(format!("Invalid symbol: {}", symbol)))?;
+
+    db.trends_price_correlation(&keyword, &symbol, lag)
+        .map_err(CommandError::from)
+}
+
 // ============================================================================
 // Signal Commands
 // ============================================================================
@@ -849,6 +1619,7 @@ struct SignalData {
     symbol: String,
     signal_type: String,
     direction: String,
+    /// 0 to 100 (see `Signal::strength_score`)
     strength: f64,
     price_at_signal: f64,
     triggered_by: String,
@@ -860,13 +1631,22 @@ struct SignalData {
 
 /// Generate signals for a symbol
 #[tauri::command]
-fn generate_signals(state: State<AppState>, symbol: String) -> Result<CommandResult, String> {
-    let mut db = state.db.lock().map_err(|e| e.to_string())?;
+fn generate_signals(
+    state: State<AppState>,
+    symbol: String,
+    use_adjusted: Option<bool>,
+) -> Result<CommandResult, CommandError> {
+    let mut db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
     // Get prices and indicators
-    let prices = db.get_prices(&symbol).map_err(|e| e.to_string())?;
-    let indicators = db.get_all_indicators(&symbol).map_err(|e| e.to_string())?;
+    let prices = if use_adjusted.unwrap_or(false) {
+        db.get_adjusted_prices(&symbol)
+    } else {
+        db.get_prices(&symbol)
+    }
+    .map_err(CommandError::from)?;
+    let indicators = db.get_all_indicators(&symbol).map_err(CommandError::from)?;
 
     if prices.is_empty() {
         return Ok(CommandResult {
@@ -882,13 +1662,29 @@ fn generate_signals(state: State<AppState>, symbol: String) -> Result<CommandRes
         });
     }
 
-    // Generate signals
-    let engine = SignalEngine::new();
+    // Generate signals with the user's stored thresholds and weights
+    let config = db.get_signal_config().map_err(CommandError::from)?;
+    let weights = db.get_signal_weights().map_err(CommandError::from)?;
+    let engine = SignalEngine::with_config(config).with_weights(weights);
     let signals = engine.generate_signals(&symbol, &indicators, &prices);
     let count = signals.len();
 
+    // Only signals not already stored are forwarded to webhooks
+    let existing: std::collections::HashSet<(&'static str, NaiveDate)> = db
+        .get_signals(&symbol, false)
+        .map_err(CommandError::from)?
+        .iter()
+        .map(|s| (s.signal_type.as_str(), s.timestamp))
+        .collect();
+    let new_signals: Vec<_> = signals
+        .iter()
+        .filter(|s| !existing.contains(&(s.signal_type.as_str(), s.timestamp)))
+        .cloned()
+        .collect();
+
     // Store signals
-    db.upsert_signals(&signals).map_err(|e| e.to_string())?;
+    db.upsert_signals(&signals).map_err(CommandError::from)?;
+    forward_to_webhooks(&db, WebhookEvent::Signal, new_signals);
 
     println!("[OK] Generated {} signals for {}", count, symbol);
 
@@ -904,64 +1700,171 @@ fn get_signals(
     state: State<AppState>,
     symbol: String,
     only_unacknowledged: bool,
-) -> Result<Vec<SignalData>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<SignalData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
     let signals = db
         .get_signals(&symbol, only_unacknowledged)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(signals
         .into_iter()
-        .map(|s| SignalData {
-            id: s.id,
-            symbol: s.symbol,
-            signal_type: s.signal_type.as_str().to_string(),
-            direction: s.direction.as_str().to_string(),
-            strength: s.strength,
-            price_at_signal: s.price_at_signal,
-            triggered_by: s.triggered_by,
-            trigger_value: s.trigger_value,
-            timestamp: s.timestamp.to_string(),
-            created_at: s.created_at,
-            acknowledged: s.acknowledged,
+        .map(|s| {
+            let strength = s.strength_score();
+            SignalData {
+                id: s.id,
+                symbol: s.symbol,
+                signal_type: s.signal_type.as_str().to_string(),
+                direction: s.direction.as_str().to_string(),
+                strength,
+                price_at_signal: s.price_at_signal,
+                triggered_by: s.triggered_by,
+                trigger_value: s.trigger_value,
+                timestamp: s.timestamp.to_string(),
+                created_at: s.created_at,
+                acknowledged: s.acknowledged,
+            }
+        })
+        .collect())
+}
+
+/// Get the stored signal detection thresholds
+#[tauri::command]
+fn get_signal_config(state: State<AppState>) -> Result<SignalConfig, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.get_signal_config().map_err(CommandError::from)
+}
+
+/// Update signal detection thresholds (rejected if oversold/overbought levels are inverted)
+#[tauri::command]
+fn update_signal_config(
+    state: State<AppState>,
+    config: SignalConfig,
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.update_signal_config(&config).map_err(CommandError::from)?;
+
+    println!(
+        "[OK] Signal config updated (RSI {}/{})",
+        config.rsi_oversold, config.rsi_overbought
+    );
+
+    Ok(CommandResult {
+        success: true,
+        message: "Signal thresholds updated".to_string(),
+    })
+}
+
+/// Get the per signal type strength multipliers
+#[tauri::command]
+fn get_signal_weights(state: State<AppState>) -> Result<SignalWeights, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.get_signal_weights().map_err(CommandError::from)
+}
+
+/// Replace the signal weights (rejected for unknown signal types or negative weights)
+#[tauri::command]
+fn update_signal_weights(
+    state: State<AppState>,
+    weights: SignalWeights,
+) -> Result<CommandResult, CommandError> {
+    let mut db = state.db.get().map_err(CommandError::from)?;
+    db.update_signal_weights(&weights).map_err(CommandError::from)?;
+
+    println!("[OK] Signal weights updated ({} weighted types)", weights.iter().count());
+
+    Ok(CommandResult {
+        success: true,
+        message: "Signal weights updated".to_string(),
+    })
+}
+
+/// Overnight gap for frontend
+#[derive(Serialize)]
+struct GapData {
+    date: String,
+    direction: String,
+    prev_close: f64,
+    open: f64,
+    gap_percent: f64,
+}
+
+/// Gaps price hasn't traded back through; `min_pct` defaults to the signal config's gap threshold
+#[tauri::command]
+fn get_unfilled_gaps(
+    state: State<AppState>,
+    symbol: String,
+    min_pct: Option<f64>,
+) -> Result<Vec<GapData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    let min_pct = match min_pct {
+        Some(pct) => pct,
+        None => db.get_signal_config().map_err(CommandError::from)?.gap_min_percent,
+    };
+    let prices = db.get_prices(&symbol).map_err(CommandError::from)?;
+
+    Ok(find_unfilled_gaps(&prices, min_pct)
+        .into_iter()
+        .map(|g| GapData {
+            date: g.date.to_string(),
+            direction: g.direction.as_str().to_string(),
+            prev_close: g.prev_close,
+            open: g.open,
+            gap_percent: g.gap_percent,
         })
         .collect())
 }
 
+/// Get the confluence score (-1 bearish .. 1 bullish) for a symbol's latest bar
+#[tauri::command]
+fn get_confluence_score(state: State<AppState>, symbol: String) -> Result<f64, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    let prices = db.get_prices(&symbol).map_err(CommandError::from)?;
+    let indicators = db.get_all_indicators(&symbol).map_err(CommandError::from)?;
+
+    Ok(SignalEngine::new().confluence_score(&indicators, &prices))
+}
+
 /// Get all recent signals across all symbols
 #[tauri::command]
-fn get_all_signals(state: State<AppState>, limit: usize) -> Result<Vec<SignalData>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_all_signals(state: State<AppState>, limit: usize) -> Result<Vec<SignalData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let signals = db.get_recent_signals(limit).map_err(|e| e.to_string())?;
+    let signals = db.get_recent_signals(limit).map_err(CommandError::from)?;
 
     Ok(signals
         .into_iter()
-        .map(|s| SignalData {
-            id: s.id,
-            symbol: s.symbol,
-            signal_type: s.signal_type.as_str().to_string(),
-            direction: s.direction.as_str().to_string(),
-            strength: s.strength,
-            price_at_signal: s.price_at_signal,
-            triggered_by: s.triggered_by,
-            trigger_value: s.trigger_value,
-            timestamp: s.timestamp.to_string(),
-            created_at: s.created_at,
-            acknowledged: s.acknowledged,
+        .map(|s| {
+            let strength = s.strength_score();
+            SignalData {
+                id: s.id,
+                symbol: s.symbol,
+                signal_type: s.signal_type.as_str().to_string(),
+                direction: s.direction.as_str().to_string(),
+                strength,
+                price_at_signal: s.price_at_signal,
+                triggered_by: s.triggered_by,
+                trigger_value: s.trigger_value,
+                timestamp: s.timestamp.to_string(),
+                created_at: s.created_at,
+                acknowledged: s.acknowledged,
+            }
         })
         .collect())
 }
 
 /// Acknowledge a signal
 #[tauri::command]
-fn acknowledge_signal(state: State<AppState>, signal_id: i64) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn acknowledge_signal(state: State<AppState>, signal_id: i64) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     db.acknowledge_signal(signal_id)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(CommandResult {
         success: true,
@@ -974,12 +1877,12 @@ fn acknowledge_signal(state: State<AppState>, signal_id: i64) -> Result<CommandR
 fn acknowledge_all_signals(
     state: State<AppState>,
     symbol: String,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
     db.acknowledge_all_signals(&symbol)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(CommandResult {
         success: true,
@@ -1007,6 +1910,24 @@ struct IndicatorAlertData {
     message: Option<String>,
 }
 
+impl From<IndicatorAlert> for IndicatorAlertData {
+    fn from(a: IndicatorAlert) -> Self {
+        Self {
+            id: a.id,
+            symbol: a.symbol,
+            alert_type: a.alert_type.as_str().to_string(),
+            indicator_name: a.indicator_name,
+            secondary_indicator: a.secondary_indicator,
+            condition: a.condition.as_str().to_string(),
+            threshold: a.threshold,
+            triggered: a.triggered,
+            last_value: a.last_value,
+            created_at: a.created_at,
+            message: a.message,
+        }
+    }
+}
+
 /// Add an indicator alert
 #[tauri::command]
 fn add_indicator_alert(
@@ -1018,15 +1939,19 @@ fn add_indicator_alert(
     condition: String,
     threshold: Option<f64>,
     message: Option<String>,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CreatedResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
     let alert_type_enum = IndicatorAlertType::from_str(&alert_type)
-        .ok_or_else(|| "Invalid alert type. Use 'threshold', 'crossover', or 'band_touch'".to_string())?;
+        .ok_or_else(|| {
+            CommandError::invalid_input("Invalid alert type. Use 'threshold', 'crossover', or 'band_touch'")
+        })?;
 
     let condition_enum = IndicatorAlertCondition::from_str(&condition)
-        .ok_or_else(|| "Invalid condition. Use 'crosses_above', 'crosses_below', 'bullish_crossover', or 'bearish_crossover'".to_string())?;
+        .ok_or_else(|| {
+            CommandError::invalid_input("Invalid condition. Use 'crosses_above', 'crosses_below', 'bullish_crossover', or 'bearish_crossover'")
+        })?;
 
     let alert = IndicatorAlert {
         id: 0,
@@ -1042,15 +1967,15 @@ fn add_indicator_alert(
         message,
     };
 
-    db.add_indicator_alert(&alert).map_err(|e| e.to_string())?;
+    let id = db.add_indicator_alert(&alert).map_err(CommandError::from)?;
 
     println!(
         "[OK] Added indicator alert for {} {} {} {}",
         symbol, indicator_name, condition, threshold.map(|t| format!("{}", t)).unwrap_or_default()
     );
 
-    Ok(CommandResult {
-        success: true,
+    Ok(CreatedResponse {
+        id,
         message: format!(
             "Indicator alert set: {} {} {} {}",
             symbol, indicator_name, condition, threshold.map(|t| format!("{}", t)).unwrap_or_default()
@@ -1063,10 +1988,10 @@ fn add_indicator_alert(
 fn get_indicator_alerts(
     state: State<AppState>,
     only_active: bool,
-) -> Result<Vec<IndicatorAlertData>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<IndicatorAlertData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let alerts = db.get_indicator_alerts(only_active).map_err(|e| e.to_string())?;
+    let alerts = db.get_indicator_alerts(only_active).map_err(CommandError::from)?;
 
     Ok(alerts
         .into_iter()
@@ -1091,10 +2016,10 @@ fn get_indicator_alerts(
 fn delete_indicator_alert(
     state: State<AppState>,
     alert_id: i64,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    db.delete_indicator_alert(alert_id).map_err(|e| e.to_string())?;
+    db.delete_indicator_alert(alert_id).map_err(CommandError::from)?;
 
     Ok(CommandResult {
         success: true,
@@ -1104,29 +2029,561 @@ fn delete_indicator_alert(
 
 /// Check all indicator alerts, returns triggered alerts
 #[tauri::command]
-fn check_indicator_alerts(state: State<AppState>) -> Result<Vec<IndicatorAlertData>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn check_indicator_alerts(state: State<AppState>) -> Result<Vec<IndicatorAlertData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let triggered = db.check_indicator_alerts().map_err(CommandError::from)?;
+    forward_to_webhooks(&db, WebhookEvent::IndicatorAlert, triggered.clone());
+
+    Ok(triggered.into_iter().map(|a| a.into()).collect())
+}
+
+// ============================================================================
+// Alert Notifications
+// ============================================================================
+
+const SETTING_NOTIFICATIONS_ENABLED: &str = "notifications_enabled";
+const SETTING_NOTIFICATION_INTERVAL: &str = "notification_interval_secs";
+const DEFAULT_NOTIFICATION_INTERVAL_SECS: u64 = 60;
+const MIN_NOTIFICATION_INTERVAL_SECS: u64 = 5;
+
+/// Notification settings for frontend
+#[derive(Serialize)]
+struct NotificationSettings {
+    enabled: bool,
+    interval_secs: u64,
+}
+
+/// Result of an alert check with notifications
+#[derive(Serialize)]
+struct AlertNotificationResult {
+    price_alerts: Vec<AlertData>,
+    indicator_alerts: Vec<IndicatorAlertData>,
+    notified: usize,
+}
+
+fn load_notification_settings(db: &Database) -> NotificationSettings {
+    let enabled = db
+        .get_setting(SETTING_NOTIFICATIONS_ENABLED)
+        .ok()
+        .flatten()
+        .map(|v| v == "true")
+        .unwrap_or(false);
+    let interval_secs = db
+        .get_setting(SETTING_NOTIFICATION_INTERVAL)
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_NOTIFICATION_INTERVAL_SECS)
+        .max(MIN_NOTIFICATION_INTERVAL_SECS);
+
+    NotificationSettings {
+        enabled,
+        interval_secs,
+    }
+}
+
+/// Run price and indicator alert checks and build a notification for each newly triggered alert.
+/// The checks flag alerts as triggered in the database, so an alert is only notified once.
+/// Only touches the database; callers release the connection before showing the notifications.
+fn collect_triggered_alerts(
+    db: &Database,
+) -> Result<(AlertNotificationResult, Vec<(String, String)>), CommandError> {
+    let price_alerts = db.check_alerts().map_err(CommandError::from)?;
+    let indicator_alerts = db.check_indicator_alerts().map_err(CommandError::from)?;
+    forward_to_webhooks(db, WebhookEvent::PriceAlert, price_alerts.clone());
+    forward_to_webhooks(db, WebhookEvent::IndicatorAlert, indicator_alerts.clone());
+
+    let mut notifications: Vec<(String, String)> = Vec::new();
+
+    for alert in &price_alerts {
+        let current = db
+            .get_latest_price(&alert.symbol)
+            .ok()
+            .flatten()
+            .map(|p| format!(" (now ${:.2})", p))
+            .unwrap_or_default();
+        notifications.push((
+            format!("{} price alert", alert.symbol),
+            format!(
+                "{} {}{}",
+                alert.symbol,
+                describe_alert_condition(alert.condition, alert.target_price),
+                current
+            ),
+        ));
+    }
+
+    for alert in &indicator_alerts {
+        let body = alert.message.clone().unwrap_or_else(|| {
+            format!(
+                "{} {} {}",
+                alert.indicator_name,
+                alert.condition.as_str(),
+                alert.threshold.map(|t| format!("{:.2}", t)).unwrap_or_default()
+            )
+        });
+        notifications.push((format!("{} indicator alert", alert.symbol), body));
+    }
+
+    let result = AlertNotificationResult {
+        notified: notifications.len(),
+        price_alerts: price_alerts.into_iter().map(|a| a.into()).collect(),
+        indicator_alerts: indicator_alerts.into_iter().map(|a| a.into()).collect(),
+    };
+    Ok((result, notifications))
+}
+
+/// Show desktop notifications collected by `collect_triggered_alerts`
+fn show_alert_notifications(app: &tauri::AppHandle, notifications: &[(String, String)]) {
+    use tauri_plugin_notification::NotificationExt;
+
+    for (title, body) in notifications {
+        if let Err(e) = app.notification().builder().title(title).body(body).show() {
+            println!("[WARN] Failed to show notification: {}", e);
+        }
+    }
+
+    if !notifications.is_empty() {
+        println!("[OK] Sent {} alert notification(s)", notifications.len());
+    }
+}
+
+/// Check price and indicator alerts, sending a desktop notification for each new trigger
+#[tauri::command]
+fn check_and_notify_alerts(
+    app: tauri::AppHandle,
+    state: State<AppState>,
+) -> Result<AlertNotificationResult, CommandError> {
+    let (result, notifications) = {
+        let db = state.db.get().map_err(CommandError::from)?;
+        collect_triggered_alerts(&db)?
+    };
+    show_alert_notifications(&app, &notifications);
+    Ok(result)
+}
+
+/// Get alert notification settings
+#[tauri::command]
+fn get_notification_settings(state: State<AppState>) -> Result<NotificationSettings, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    Ok(load_notification_settings(&db))
+}
+
+/// Update alert notification settings (takes effect on the next background tick)
+#[tauri::command]
+fn set_notification_settings(
+    state: State<AppState>,
+    enabled: bool,
+    interval_secs: Option<u64>,
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    db.set_setting(SETTING_NOTIFICATIONS_ENABLED, if enabled { "true" } else { "false" })
+        .map_err(CommandError::from)?;
+    if let Some(secs) = interval_secs {
+        let secs = secs.max(MIN_NOTIFICATION_INTERVAL_SECS);
+        db.set_setting(SETTING_NOTIFICATION_INTERVAL, &secs.to_string())
+            .map_err(CommandError::from)?;
+    }
+
+    Ok(CommandResult {
+        success: true,
+        message: format!(
+            "Alert notifications {}",
+            if enabled { "enabled" } else { "disabled" }
+        ),
+    })
+}
+
+/// Background loop that checks alerts on the configured interval while notifications are enabled
+fn spawn_alert_notifier(app: tauri::AppHandle) {
+    use tauri::Manager;
+
+    std::thread::spawn(move || loop {
+        // The connection is returned to the pool before any notification is shown
+        let (interval_secs, notifications) = {
+            let state = app.state::<AppState>();
+            // A busy pool only skips this tick
+            match state.db.get() {
+                Ok(db) => {
+                    let settings = load_notification_settings(&db);
+                    let mut notifications = Vec::new();
+                    if settings.enabled {
+                        match collect_triggered_alerts(&db) {
+                            Ok((_, collected)) => notifications = collected,
+                            Err(e) => println!("[WARN] Background alert check failed: {}", e),
+                        }
+                    }
+                    (settings.interval_secs, notifications)
+                }
+                Err(e) => {
+                    println!("[WARN] Background alert check skipped: {}", e);
+                    (DEFAULT_NOTIFICATION_INTERVAL_SECS, Vec::new())
+                }
+            }
+        };
+        show_alert_notifications(&app, &notifications);
+
+        std::thread::sleep(std::time::Duration::from_secs(interval_secs));
+    });
+}
+
+/// How often the snapshot job checks whether today's snapshots exist
+const SNAPSHOT_CHECK_INTERVAL_SECS: u64 = 3600;
+
+/// Background job that records one snapshot per team per day: once at startup,
+/// then hourly so a session running past midnight picks up the new day
+fn spawn_snapshot_recorder(app: tauri::AppHandle) {
+    use tauri::Manager;
+
+    std::thread::spawn(move || {
+        while !SHUTTING_DOWN.load(Ordering::SeqCst) {
+            {
+                let state = app.state::<AppState>();
+                match state.db.get().map_err(CommandError::from).and_then(|db| {
+                    db.record_missing_snapshots().map_err(CommandError::from)
+                }) {
+                    Ok(0) => {}
+                    Ok(written) => println!("[OK] Recorded {} daily portfolio snapshot(s)", written),
+                    Err(e) => println!("[WARN] Daily snapshot check failed: {}", e),
+                }
+            }
+
+            for _ in 0..SNAPSHOT_CHECK_INTERVAL_SECS {
+                if SHUTTING_DOWN.load(Ordering::SeqCst) {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            }
+        }
+    });
+}
+
+// ============================================================================
+// Webhooks
+// ============================================================================
+
+/// Post new triggers to matching webhooks on a background thread, so slow
+/// endpoints and their retries never tie up a database connection
+fn forward_to_webhooks<T: Serialize + Send + 'static>(db: &Database, event: WebhookEvent, items: Vec<T>) {
+    if items.is_empty() {
+        return;
+    }
+    let webhooks = match db.get_webhooks_for_event(event) {
+        Ok(webhooks) if !webhooks.is_empty() => webhooks,
+        Ok(_) => return,
+        Err(e) => {
+            println!("[WARN] Failed to load webhooks: {}", e);
+            return;
+        }
+    };
+
+    std::thread::spawn(move || {
+        let delivered = WebhookDispatcher::new().dispatch(&webhooks, event, &items);
+        if delivered > 0 {
+            println!("[OK] Delivered {} {} webhook(s)", delivered, event.as_str());
+        }
+    });
+}
+
+/// Webhook for frontend (the secret itself is never sent back)
+#[derive(Serialize)]
+struct WebhookResponse {
+    id: i64,
+    url: String,
+    event_type: String,
+    has_secret: bool,
+    enabled: bool,
+    created_at: String,
+}
+
+impl From<Webhook> for WebhookResponse {
+    fn from(w: Webhook) -> Self {
+        Self {
+            id: w.id,
+            url: w.url,
+            event_type: w.event_type.map_or("all", |e| e.as_str()).to_string(),
+            has_secret: w.secret.is_some(),
+            enabled: w.enabled,
+            created_at: w.created_at,
+        }
+    }
+}
+
+/// Register a webhook; `event_type` is signal, price_alert, indicator_alert or all
+#[tauri::command]
+fn add_webhook(
+    state: State<AppState>,
+    url: String,
+    event_type: Option<String>,
+    secret: Option<String>,
+) -> Result<CommandResult, CommandError> {
+    let event = match event_type.as_deref().map(str::trim) {
+        None | Some("") | Some("all") => None,
+        Some(name) => Some(name.parse::<WebhookEvent>().map_err(CommandError::invalid_input)?),
+    };
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let id = db
+        .add_webhook(url.trim(), event, secret.as_deref())
+        .map_err(CommandError::from)?;
+
+    println!("[OK] Added webhook {} for {}", id, event.map_or("all", |e| e.as_str()));
+
+    Ok(CommandResult {
+        success: true,
+        message: format!("Webhook {} added", id),
+    })
+}
+
+/// List registered webhooks
+#[tauri::command]
+fn list_webhooks(state: State<AppState>) -> Result<Vec<WebhookResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let webhooks = db.list_webhooks().map_err(CommandError::from)?;
+
+    Ok(webhooks.into_iter().map(|w| w.into()).collect())
+}
+
+/// Pause or resume deliveries to a webhook
+#[tauri::command]
+fn set_webhook_enabled(
+    state: State<AppState>,
+    webhook_id: i64,
+    enabled: bool,
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    db.set_webhook_enabled(webhook_id, enabled)
+        .map_err(CommandError::from)?;
+
+    Ok(CommandResult {
+        success: true,
+        message: format!(
+            "Webhook {} {}",
+            webhook_id,
+            if enabled { "enabled" } else { "disabled" }
+        ),
+    })
+}
+
+/// Delete a webhook
+#[tauri::command]
+fn delete_webhook(state: State<AppState>, webhook_id: i64) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    db.delete_webhook(webhook_id).map_err(CommandError::from)?;
+
+    Ok(CommandResult {
+        success: true,
+        message: "Webhook deleted".to_string(),
+    })
+}
+
+/// Set once the app starts exiting so background loops stop touching the database
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// How often a disabled auto-refresh loop rechecks its settings
+const REFRESH_DISABLED_POLL_SECS: u64 = 60;
+
+/// Period requested from Yahoo on each auto-refresh (enough to cover weekends/holidays)
+const REFRESH_PERIOD: &str = "5d";
+
+/// Payload of the `prices-updated` event
+#[derive(Serialize, Clone)]
+struct PricesUpdatedEvent {
+    symbols: Vec<String>,
+}
+
+/// Get favorited-symbol auto-refresh settings
+#[tauri::command]
+fn get_refresh_settings(state: State<AppState>) -> Result<RefreshSettings, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.get_refresh_settings().map_err(CommandError::from)
+}
+
+/// Update auto-refresh settings (takes effect on the next background tick)
+#[tauri::command]
+fn update_refresh_settings(
+    state: State<AppState>,
+    settings: RefreshSettings,
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.update_refresh_settings(&settings)
+        .map_err(CommandError::from)?;
+
+    println!(
+        "[OK] Auto-refresh {} (every {} min)",
+        if settings.enabled { "enabled" } else { "disabled" },
+        settings.interval_minutes
+    );
+
+    Ok(CommandResult {
+        success: true,
+        message: format!(
+            "Auto-refresh {}",
+            if settings.enabled { "enabled" } else { "disabled" }
+        ),
+    })
+}
+
+/// Delete signals, API call logs and AI snapshots older than `days` (per-table overrides win)
+#[tauri::command]
+fn prune_database(state: State<AppState>, days: u32) -> Result<Vec<PrunedTable>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let pruned = db.prune_old_data(days).map_err(CommandError::from)?;
+
+    let total: usize = pruned.iter().map(|p| p.deleted).sum();
+    println!("[OK] Pruned {} old rows across {} tables", total, pruned.len());
+    Ok(pruned)
+}
+
+/// Set (or clear with `days = None`) the retention window of one prunable table
+#[tauri::command]
+fn set_retention_days(
+    state: State<AppState>,
+    table: String,
+    days: Option<u32>,
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.set_retention_override(&table, days)
+        .map_err(CommandError::from)?;
+
+    Ok(CommandResult {
+        success: true,
+        message: match days {
+            Some(days) => format!("{} keeps {} days of rows", table, days),
+            None => format!("{} uses the default retention", table),
+        },
+    })
+}
+
+/// Symbols stored under more than one casing
+#[tauri::command]
+fn find_duplicate_symbols(state: State<AppState>) -> Result<Vec<DuplicateSymbol>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.find_duplicate_symbols().map_err(CommandError::from)
+}
+
+/// Move every row of `from` onto `to`, combining open paper/DC positions.
+/// Symbols are taken verbatim so a lower-case `from` can be addressed.
+#[tauri::command]
+fn merge_symbol(
+    state: State<AppState>,
+    from: String,
+    to: String,
+) -> Result<CommandResult, CommandError> {
+    let mut db = state.db.get().map_err(CommandError::from)?;
+    let moved = db.merge_symbol(&from, &to).map_err(CommandError::from)?;
+
+    println!("[OK] Merged {} rows from {} into {}", moved, from, to);
+
+    Ok(CommandResult {
+        success: true,
+        message: format!("Merged {} rows from {} into {}", moved, from, to),
+    })
+}
+
+/// Fetch recent prices for every favorited symbol and return those whose latest close changed.
+/// A pooled connection is only held for reads and writes, never across HTTP requests.
+fn refresh_favorited_symbols(app: &tauri::AppHandle) -> Result<Vec<String>, CommandError> {
+    use tauri::Manager;
+
+    let state = app.state::<AppState>();
+    let (symbols, before) = {
+        let db = state.db.get().map_err(CommandError::from)?;
+        let symbols = db.get_favorited_symbols().map_err(CommandError::from)?;
+        let before: Vec<Option<f64>> = symbols
+            .iter()
+            .map(|s| db.get_latest_price(s).ok().flatten())
+            .collect();
+        (symbols, before)
+    };
+    if symbols.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let yahoo = YahooFinance::new().with_rate_limiter(state.rate_limiter.clone());
+    let mut fetched = Vec::new();
+    for symbol in &symbols {
+        if SHUTTING_DOWN.load(Ordering::SeqCst) {
+            return Ok(Vec::new());
+        }
+        match yahoo.fetch_prices(symbol, REFRESH_PERIOD) {
+            Ok(prices) => fetched.push(prices),
+            Err(e) => println!("[WARN] Auto-refresh of {} failed: {}", symbol, e),
+        }
+    }
+    if SHUTTING_DOWN.load(Ordering::SeqCst) {
+        return Ok(Vec::new());
+    }
+
+    let mut db = state.db.get().map_err(CommandError::from)?;
+    for prices in &fetched {
+        db.upsert_daily_prices(prices).map_err(CommandError::from)?;
+    }
 
-    let triggered = db.check_indicator_alerts().map_err(|e| e.to_string())?;
+    // New prices may trigger queued limit/stop orders and per-position exit levels
+    let filled = db.process_pending_paper_orders().map_err(CommandError::from)?;
+    if !filled.is_empty() {
+        println!("[OK] Filled {} pending paper orders", filled.len());
+    }
+    db.check_paper_stops(None, None).map_err(CommandError::from)?;
+    db.check_dc_stops(None, None).map_err(CommandError::from)?;
 
-    Ok(triggered
+    Ok(symbols
         .into_iter()
-        .map(|a| IndicatorAlertData {
-            id: a.id,
-            symbol: a.symbol,
-            alert_type: a.alert_type.as_str().to_string(),
-            indicator_name: a.indicator_name,
-            secondary_indicator: a.secondary_indicator,
-            condition: a.condition.as_str().to_string(),
-            threshold: a.threshold,
-            triggered: a.triggered,
-            last_value: a.last_value,
-            created_at: a.created_at,
-            message: a.message,
-        })
+        .zip(before)
+        .filter(|(symbol, before)| db.get_latest_price(symbol).ok().flatten() != *before)
+        .map(|(symbol, _)| symbol)
         .collect())
 }
 
+/// Background loop that refreshes favorited symbols on the configured interval and
+/// emits `prices-updated` with the symbols whose prices changed
+fn spawn_favorites_refresher(app: tauri::AppHandle) {
+    use tauri::{Emitter, Manager};
+
+    std::thread::spawn(move || {
+        while !SHUTTING_DOWN.load(Ordering::SeqCst) {
+            let settings = {
+                let state = app.state::<AppState>();
+                // If no connection is free, treat this tick as disabled and check again later
+                let db = state.db.get();
+                db.ok()
+                    .and_then(|db| db.get_refresh_settings().ok())
+                    .unwrap_or_default()
+            };
+
+            let wait_secs = if settings.enabled {
+                match refresh_favorited_symbols(&app) {
+                    Ok(symbols) if !symbols.is_empty() && !SHUTTING_DOWN.load(Ordering::SeqCst) => {
+                        println!("[OK] Auto-refreshed {} favorited symbol(s)", symbols.len());
+                        if let Err(e) = app.emit("prices-updated", PricesUpdatedEvent { symbols }) {
+                            println!("[WARN] Failed to emit prices-updated: {}", e);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => println!("[WARN] Auto-refresh failed: {}", e),
+                }
+                u64::from(settings.interval_minutes.max(1)) * 60
+            } else {
+                REFRESH_DISABLED_POLL_SECS
+            };
+
+            // Sleep in short steps so shutdown isn't held up by a long interval
+            for _ in 0..wait_secs {
+                if SHUTTING_DOWN.load(Ordering::SeqCst) {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            }
+        }
+    });
+}
+
 // ============================================================================
 // Backtest Commands
 // ============================================================================
@@ -1143,7 +2600,11 @@ struct StrategyData {
     exit_threshold: f64,
     stop_loss_percent: Option<f64>,
     take_profit_percent: Option<f64>,
+    trailing_stop_percent: Option<f64>,
     position_size_percent: f64,
+    direction: String,
+    entry_rule: Option<StrategyRule>,
+    exit_rule: Option<StrategyRule>,
     created_at: String,
 }
 
@@ -1181,6 +2642,44 @@ struct MetricsData {
     avg_trade_duration_days: f64,
 }
 
+impl From<financial_pipeline::PerformanceMetrics> for MetricsData {
+    fn from(m: financial_pipeline::PerformanceMetrics) -> Self {
+        Self {
+            total_return: m.total_return,
+            total_return_dollars: m.total_return_dollars,
+            max_drawdown: m.max_drawdown,
+            sharpe_ratio: m.sharpe_ratio,
+            win_rate: m.win_rate,
+            total_trades: m.total_trades,
+            winning_trades: m.winning_trades,
+            losing_trades: m.losing_trades,
+            avg_win_percent: m.avg_win_percent,
+            avg_loss_percent: m.avg_loss_percent,
+            profit_factor: m.profit_factor,
+            avg_trade_duration_days: m.avg_trade_duration_days,
+        }
+    }
+}
+
+impl From<financial_pipeline::BacktestTrade> for BacktestTradeData {
+    fn from(t: financial_pipeline::BacktestTrade) -> Self {
+        Self {
+            id: t.id,
+            symbol: t.symbol,
+            direction: t.direction.as_str().to_string(),
+            entry_date: t.entry_date.to_string(),
+            entry_price: t.entry_price,
+            entry_reason: t.entry_reason,
+            exit_date: t.exit_date.map(|d| d.to_string()),
+            exit_price: t.exit_price,
+            exit_reason: t.exit_reason,
+            shares: t.shares,
+            profit_loss: t.profit_loss,
+            profit_loss_percent: t.profit_loss_percent,
+        }
+    }
+}
+
 /// Backtest result data for frontend
 #[derive(Serialize)]
 struct BacktestResultData {
@@ -1194,9 +2693,57 @@ struct BacktestResultData {
     final_capital: f64,
     metrics: MetricsData,
     trades: Vec<BacktestTradeData>,
+    equity_curve: Vec<(String, f64)>,
+    drawdown_curve: Vec<(String, f64)>,
     created_at: String,
 }
 
+impl From<financial_pipeline::BacktestResult> for BacktestResultData {
+    fn from(r: financial_pipeline::BacktestResult) -> Self {
+        Self {
+            id: r.id,
+            strategy_id: r.strategy_id,
+            strategy_name: r.strategy_name,
+            symbol: r.symbol,
+            start_date: r.start_date.to_string(),
+            end_date: r.end_date.to_string(),
+            initial_capital: r.initial_capital,
+            final_capital: r.final_capital,
+            metrics: r.metrics.into(),
+            trades: r.trades.into_iter().map(|t| t.into()).collect(),
+            equity_curve: r
+                .equity_curve
+                .into_iter()
+                .map(|(d, v)| (d.to_string(), v))
+                .collect(),
+            drawdown_curve: r
+                .drawdown_curve
+                .into_iter()
+                .map(|(d, v)| (d.to_string(), v))
+                .collect(),
+            created_at: r.created_at,
+        }
+    }
+}
+
+/// Build a backtest config, falling back to the engine defaults for unset costs
+fn build_backtest_config(
+    initial_capital: f64,
+    commission_per_trade: Option<f64>,
+    commission_percent: Option<f64>,
+    slippage_bps: Option<f64>,
+    min_commission: Option<f64>,
+) -> BacktestConfig {
+    let defaults = BacktestConfig::default();
+    BacktestConfig {
+        initial_capital,
+        commission_per_trade: commission_per_trade.unwrap_or(defaults.commission_per_trade),
+        commission_percent: commission_percent.unwrap_or(defaults.commission_percent),
+        slippage_bps: slippage_bps.unwrap_or(defaults.slippage_bps),
+        min_commission: min_commission.unwrap_or(defaults.min_commission),
+    }
+}
+
 /// Save a strategy
 #[tauri::command]
 fn save_strategy(
@@ -1210,13 +2757,21 @@ fn save_strategy(
     stop_loss_percent: Option<f64>,
     take_profit_percent: Option<f64>,
     position_size_percent: f64,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    direction: Option<String>,
+    trailing_stop_percent: Option<f64>,
+    entry_rule: Option<StrategyRule>,
+    exit_rule: Option<StrategyRule>,
+) -> Result<CreatedResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let entry_cond = StrategyConditionType::from_str(&entry_condition)
-        .ok_or_else(|| format!("Invalid entry condition: {}", entry_condition))?;
+        .ok_or_else(|| {
+            CommandError::invalid_input(format!("Invalid entry condition: {}", entry_condition))
+        })?;
     let exit_cond = StrategyConditionType::from_str(&exit_condition)
-        .ok_or_else(|| format!("Invalid exit condition: {}", exit_condition))?;
+        .ok_or_else(|| {
+            CommandError::invalid_input(format!("Invalid exit condition: {}", exit_condition))
+        })?;
 
     let strategy = Strategy {
         id: 0,
@@ -1228,26 +2783,34 @@ fn save_strategy(
         exit_threshold,
         stop_loss_percent,
         take_profit_percent,
+        trailing_stop_percent,
         position_size_percent,
+        direction: direction
+            .as_deref()
+            .map(TradeDirection::from_str)
+            .unwrap_or_default(),
+        entry_rule,
+        exit_rule,
         created_at: String::new(),
     };
 
-    db.save_strategy(&strategy).map_err(|e| e.to_string())?;
+    // Re-saving an existing name updates it in place and returns its existing id
+    let id = db.save_strategy(&strategy).map_err(CommandError::from)?;
 
     println!("[OK] Saved strategy: {}", name);
 
-    Ok(CommandResult {
-        success: true,
+    Ok(CreatedResponse {
+        id,
         message: format!("Strategy '{}' saved", name),
     })
 }
 
 /// Get all strategies
 #[tauri::command]
-fn get_strategies(state: State<AppState>) -> Result<Vec<StrategyData>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_strategies(state: State<AppState>) -> Result<Vec<StrategyData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let strategies = db.get_strategies().map_err(|e| e.to_string())?;
+    let strategies = db.get_strategies().map_err(CommandError::from)?;
 
     Ok(strategies
         .into_iter()
@@ -1261,7 +2824,11 @@ fn get_strategies(state: State<AppState>) -> Result<Vec<StrategyData>, String> {
             exit_threshold: s.exit_threshold,
             stop_loss_percent: s.stop_loss_percent,
             take_profit_percent: s.take_profit_percent,
+            trailing_stop_percent: s.trailing_stop_percent,
             position_size_percent: s.position_size_percent,
+            direction: s.direction.as_str().to_string(),
+            entry_rule: s.entry_rule,
+            exit_rule: s.exit_rule,
             created_at: s.created_at,
         })
         .collect())
@@ -1269,10 +2836,10 @@ fn get_strategies(state: State<AppState>) -> Result<Vec<StrategyData>, String> {
 
 /// Delete a strategy
 #[tauri::command]
-fn delete_strategy(state: State<AppState>, name: String) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn delete_strategy(state: State<AppState>, name: String) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    db.delete_strategy(&name).map_err(|e| e.to_string())?;
+    db.delete_strategy(&name).map_err(CommandError::from)?;
 
     Ok(CommandResult {
         success: true,
@@ -1287,41 +2854,54 @@ fn run_backtest(
     strategy_name: String,
     symbol: String,
     initial_capital: f64,
-) -> Result<BacktestResultData, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+    commission_per_trade: Option<f64>,
+    commission_percent: Option<f64>,
+    slippage_bps: Option<f64>,
+    min_commission: Option<f64>,
+    use_adjusted: Option<bool>,
+) -> Result<BacktestResultData, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
     // Get strategy
     let strategy = db
         .get_strategy(&strategy_name)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("Strategy '{}' not found", strategy_name))?;
+        .map_err(CommandError::from)?
+        .ok_or_else(|| CommandError::no_data(format!("Strategy '{}' not found", strategy_name)))?;
 
     // Get prices and indicators
-    let prices = db.get_prices(&symbol).map_err(|e| e.to_string())?;
-    let indicators = db.get_all_indicators(&symbol).map_err(|e| e.to_string())?;
+    let prices = if use_adjusted.unwrap_or(false) {
+        db.get_adjusted_prices(&symbol)
+    } else {
+        db.get_prices(&symbol)
+    }
+    .map_err(CommandError::from)?;
+    let indicators = db.get_all_indicators(&symbol).map_err(CommandError::from)?;
 
     if prices.is_empty() {
-        return Err(format!("No price data for {}", symbol));
+        return Err(CommandError::no_data(format!("No price data for {}", symbol)));
     }
 
     if indicators.is_empty() {
-        return Err(format!(
+        return Err(CommandError::no_data(format!(
             "No indicator data for {}. Calculate indicators first.",
             symbol
-        ));
+        )));
     }
 
     // Run backtest
-    let config = BacktestConfig {
+    let config = build_backtest_config(
         initial_capital,
-        commission_per_trade: 0.0,
-    };
+        commission_per_trade,
+        commission_percent,
+        slippage_bps,
+        min_commission,
+    );
     let engine = BacktestEngine::new(config);
     let result = engine.run(&strategy, &symbol, &prices, &indicators);
 
     // Save result
-    db.save_backtest_result(&result).map_err(|e| e.to_string())?;
+    db.save_backtest_result(&result).map_err(CommandError::from)?;
 
     println!(
         "[OK] Backtest completed for {} on {}: {:.2}% return",
@@ -1329,51 +2909,342 @@ fn run_backtest(
     );
 
     // Convert to frontend format
-    Ok(BacktestResultData {
-        id: result.id,
-        strategy_id: result.strategy_id,
+    Ok(result.into())
+}
+
+/// Per-symbol contribution for frontend
+#[derive(Serialize)]
+struct SymbolContributionData {
+    symbol: String,
+    trades: usize,
+    profit_loss: f64,
+    contribution_percent: f64,
+}
+
+/// Portfolio backtest result for frontend
+#[derive(Serialize)]
+struct PortfolioBacktestData {
+    strategy_name: String,
+    symbols: Vec<String>,
+    start_date: String,
+    end_date: String,
+    initial_capital: f64,
+    final_capital: f64,
+    metrics: MetricsData,
+    trades: Vec<BacktestTradeData>,
+    contributions: Vec<SymbolContributionData>,
+}
+
+/// Run a strategy across every symbol in a watchlist with one shared capital pool
+#[tauri::command]
+fn run_portfolio_backtest(
+    state: State<AppState>,
+    strategy_name: String,
+    watchlist_name: String,
+    initial_capital: f64,
+    commission_per_trade: Option<f64>,
+    commission_percent: Option<f64>,
+    slippage_bps: Option<f64>,
+    min_commission: Option<f64>,
+    use_adjusted: Option<bool>,
+) -> Result<PortfolioBacktestData, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let strategy = db
+        .get_strategy(&strategy_name)
+        .map_err(CommandError::from)?
+        .ok_or_else(|| CommandError::no_data(format!("Strategy '{}' not found", strategy_name)))?;
+
+    let symbols = db.get_watchlist(&watchlist_name).map_err(CommandError::from)?;
+    if symbols.is_empty() {
+        return Err(CommandError::no_data(
+            format!("Watchlist '{}' is empty or does not exist", watchlist_name),
+        ));
+    }
+
+    let mut prices_map = std::collections::HashMap::new();
+    let mut indicators_map = std::collections::HashMap::new();
+    let mut skipped = Vec::new();
+
+    for symbol in &symbols {
+        let prices = if use_adjusted.unwrap_or(false) {
+            db.get_adjusted_prices(symbol)
+        } else {
+            db.get_prices(symbol)
+        }
+        .map_err(CommandError::from)?;
+        let indicators = db.get_all_indicators(symbol).map_err(CommandError::from)?;
+
+        if prices.is_empty() || indicators.is_empty() {
+            skipped.push(symbol.clone());
+            continue;
+        }
+        prices_map.insert(symbol.clone(), prices);
+        indicators_map.insert(symbol.clone(), indicators);
+    }
+
+    if !skipped.is_empty() {
+        println!(
+            "[WARN] Portfolio backtest skipping symbols without prices/indicators: {}",
+            skipped.join(", ")
+        );
+    }
+
+    let tradable: Vec<&str> = symbols
+        .iter()
+        .filter(|s| prices_map.contains_key(*s))
+        .map(|s| s.as_str())
+        .collect();
+    if tradable.is_empty() {
+        return Err(CommandError::no_data(format!(
+            "No symbols in '{}' have price and indicator data. Fetch prices and calculate indicators first.",
+            watchlist_name
+        )));
+    }
+
+    let config = build_backtest_config(
+        initial_capital,
+        commission_per_trade,
+        commission_percent,
+        slippage_bps,
+        min_commission,
+    );
+    let result = BacktestEngine::new(config).run_portfolio(
+        &strategy,
+        &tradable,
+        &prices_map,
+        &indicators_map,
+    );
+
+    println!(
+        "[OK] Portfolio backtest completed for {} on {} ({} symbols): {:.2}% return",
+        strategy_name,
+        watchlist_name,
+        tradable.len(),
+        result.metrics.total_return
+    );
+
+    Ok(PortfolioBacktestData {
         strategy_name: result.strategy_name,
-        symbol: result.symbol,
+        symbols: result.symbols,
         start_date: result.start_date.to_string(),
         end_date: result.end_date.to_string(),
         initial_capital: result.initial_capital,
         final_capital: result.final_capital,
-        metrics: MetricsData {
-            total_return: result.metrics.total_return,
-            total_return_dollars: result.metrics.total_return_dollars,
-            max_drawdown: result.metrics.max_drawdown,
-            sharpe_ratio: result.metrics.sharpe_ratio,
-            win_rate: result.metrics.win_rate,
-            total_trades: result.metrics.total_trades,
-            winning_trades: result.metrics.winning_trades,
-            losing_trades: result.metrics.losing_trades,
-            avg_win_percent: result.metrics.avg_win_percent,
-            avg_loss_percent: result.metrics.avg_loss_percent,
-            profit_factor: result.metrics.profit_factor,
-            avg_trade_duration_days: result.metrics.avg_trade_duration_days,
-        },
-        trades: result
-            .trades
+        metrics: result.metrics.into(),
+        trades: result.trades.into_iter().map(|t| t.into()).collect(),
+        contributions: result
+            .contributions
             .into_iter()
-            .map(|t| BacktestTradeData {
-                id: t.id,
-                symbol: t.symbol,
-                direction: t.direction.as_str().to_string(),
-                entry_date: t.entry_date.to_string(),
-                entry_price: t.entry_price,
-                entry_reason: t.entry_reason,
-                exit_date: t.exit_date.map(|d| d.to_string()),
-                exit_price: t.exit_price,
-                exit_reason: t.exit_reason,
-                shares: t.shares,
-                profit_loss: t.profit_loss,
-                profit_loss_percent: t.profit_loss_percent,
+            .map(|c| SymbolContributionData {
+                symbol: c.symbol,
+                trades: c.trades,
+                profit_loss: c.profit_loss,
+                contribution_percent: c.contribution_percent,
             })
             .collect(),
-        created_at: result.created_at,
     })
 }
 
+/// Walk-forward result for frontend
+#[derive(Serialize)]
+struct WalkForwardData {
+    strategy_name: String,
+    symbol: String,
+    train_ratio: f64,
+    split_date: String,
+    in_sample: BacktestResultData,
+    out_of_sample: BacktestResultData,
+    degradation_ratio: f64,
+}
+
+/// Run a backtest on an in-sample window and report it against the held-out remainder
+#[tauri::command]
+fn run_walk_forward_backtest(
+    state: State<AppState>,
+    strategy_name: String,
+    symbol: String,
+    initial_capital: f64,
+    train_ratio: f64,
+    commission_per_trade: Option<f64>,
+    commission_percent: Option<f64>,
+    slippage_bps: Option<f64>,
+    min_commission: Option<f64>,
+    use_adjusted: Option<bool>,
+) -> Result<WalkForwardData, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    if !(train_ratio > 0.0 && train_ratio < 1.0) {
+        return Err(CommandError::invalid_input("train_ratio must be between 0 and 1"));
+    }
+
+    let strategy = db
+        .get_strategy(&strategy_name)
+        .map_err(CommandError::from)?
+        .ok_or_else(|| CommandError::no_data(format!("Strategy '{}' not found", strategy_name)))?;
+
+    let prices = if use_adjusted.unwrap_or(false) {
+        db.get_adjusted_prices(&symbol)
+    } else {
+        db.get_prices(&symbol)
+    }
+    .map_err(CommandError::from)?;
+    let indicators = db.get_all_indicators(&symbol).map_err(CommandError::from)?;
+
+    if prices.len() < 2 {
+        return Err(CommandError::no_data(format!("Not enough price data for {} to split", symbol)));
+    }
+
+    if indicators.is_empty() {
+        return Err(CommandError::no_data(format!(
+            "No indicator data for {}. Calculate indicators first.",
+            symbol
+        )));
+    }
+
+    let config = build_backtest_config(
+        initial_capital,
+        commission_per_trade,
+        commission_percent,
+        slippage_bps,
+        min_commission,
+    );
+    let result = BacktestEngine::new(config).run_walk_forward(
+        &strategy,
+        &symbol,
+        &prices,
+        &indicators,
+        train_ratio,
+    );
+
+    println!(
+        "[OK] Walk-forward for {} on {}: IS {:.2}%, OOS {:.2}% (split {})",
+        strategy_name,
+        symbol,
+        result.in_sample.metrics.total_return,
+        result.out_of_sample.metrics.total_return,
+        result.split_date
+    );
+
+    Ok(WalkForwardData {
+        strategy_name: result.strategy_name,
+        symbol: result.symbol,
+        train_ratio: result.train_ratio,
+        split_date: result.split_date.to_string(),
+        in_sample: result.in_sample.into(),
+        out_of_sample: result.out_of_sample.into(),
+        degradation_ratio: result.degradation_ratio,
+    })
+}
+
+/// One ranked parameter combination for frontend
+#[derive(Serialize)]
+struct OptimizationResultData {
+    rank: usize,
+    entry_threshold: f64,
+    exit_threshold: f64,
+    stop_loss_percent: Option<f64>,
+    take_profit_percent: Option<f64>,
+    score: f64,
+    final_capital: f64,
+    metrics: MetricsData,
+}
+
+/// Sweep thresholds and stops around a saved strategy and return the best combinations.
+/// Stop/take-profit values of 0 mean "none"; omitted ranges keep the saved strategy's value.
+#[tauri::command]
+fn optimize_strategy(
+    state: State<AppState>,
+    symbol: String,
+    base_strategy: String,
+    objective: String,
+    max_results: usize,
+    entry_thresholds: Option<Vec<f64>>,
+    exit_thresholds: Option<Vec<f64>>,
+    stop_loss_percents: Option<Vec<f64>>,
+    take_profit_percents: Option<Vec<f64>>,
+    initial_capital: Option<f64>,
+    use_adjusted: Option<bool>,
+) -> Result<Vec<OptimizationResultData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    let objective = objective
+        .parse::<OptimizationObjective>()
+        .map_err(CommandError::invalid_input)?;
+
+    let strategy = db
+        .get_strategy(&base_strategy)
+        .map_err(CommandError::from)?
+        .ok_or_else(|| CommandError::no_data(format!("Strategy '{}' not found", base_strategy)))?;
+
+    let prices = if use_adjusted.unwrap_or(false) {
+        db.get_adjusted_prices(&symbol)
+    } else {
+        db.get_prices(&symbol)
+    }
+    .map_err(CommandError::from)?;
+    let indicators = db.get_all_indicators(&symbol).map_err(CommandError::from)?;
+
+    if prices.is_empty() {
+        return Err(CommandError::no_data(format!("No price data for {}", symbol)));
+    }
+
+    if indicators.is_empty() {
+        return Err(CommandError::no_data(format!(
+            "No indicator data for {}. Calculate indicators first.",
+            symbol
+        )));
+    }
+
+    let optional_percents = |values: Option<Vec<f64>>| -> Vec<Option<f64>> {
+        values
+            .unwrap_or_default()
+            .into_iter()
+            .map(|v| if v > 0.0 { Some(v) } else { None })
+            .collect()
+    };
+    let grid = ParamGrid {
+        entry_thresholds: entry_thresholds.unwrap_or_default(),
+        exit_thresholds: exit_thresholds.unwrap_or_default(),
+        stop_loss_percents: optional_percents(stop_loss_percents),
+        take_profit_percents: optional_percents(take_profit_percents),
+    };
+
+    let config = BacktestConfig {
+        initial_capital: initial_capital.unwrap_or(BacktestConfig::default().initial_capital),
+        ..BacktestConfig::default()
+    };
+    let results = BacktestEngine::new(config)
+        .optimize(&strategy, &symbol, &prices, &indicators, &grid, objective)
+        .map_err(CommandError::from)?;
+
+    println!(
+        "[OK] Optimized {} on {}: {} combinations ranked by {}",
+        base_strategy,
+        symbol,
+        results.len(),
+        objective.as_str()
+    );
+
+    Ok(results
+        .into_iter()
+        .take(max_results)
+        .enumerate()
+        .map(|(i, r)| OptimizationResultData {
+            rank: i + 1,
+            entry_threshold: r.strategy.entry_threshold,
+            exit_threshold: r.strategy.exit_threshold,
+            stop_loss_percent: r.strategy.stop_loss_percent,
+            take_profit_percent: r.strategy.take_profit_percent,
+            score: r.score,
+            final_capital: r.final_capital,
+            metrics: r.metrics.into(),
+        })
+        .collect())
+}
+
 /// Get backtest history
 #[tauri::command]
 fn get_backtest_results(
@@ -1381,8 +3252,8 @@ fn get_backtest_results(
     strategy_name: Option<String>,
     symbol: Option<String>,
     limit: usize,
-) -> Result<Vec<BacktestResultData>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<BacktestResultData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let results = db
         .get_backtest_results(
@@ -1390,7 +3261,7 @@ fn get_backtest_results(
             symbol.as_deref(),
             limit,
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(results
         .into_iter()
@@ -1403,21 +3274,10 @@ fn get_backtest_results(
             end_date: r.end_date.to_string(),
             initial_capital: r.initial_capital,
             final_capital: r.final_capital,
-            metrics: MetricsData {
-                total_return: r.metrics.total_return,
-                total_return_dollars: r.metrics.total_return_dollars,
-                max_drawdown: r.metrics.max_drawdown,
-                sharpe_ratio: r.metrics.sharpe_ratio,
-                win_rate: r.metrics.win_rate,
-                total_trades: r.metrics.total_trades,
-                winning_trades: r.metrics.winning_trades,
-                losing_trades: r.metrics.losing_trades,
-                avg_win_percent: r.metrics.avg_win_percent,
-                avg_loss_percent: r.metrics.avg_loss_percent,
-                profit_factor: r.metrics.profit_factor,
-                avg_trade_duration_days: r.metrics.avg_trade_duration_days,
-            },
+            metrics: r.metrics.into(),
             trades: Vec::new(), // Trades not loaded in list view
+            equity_curve: Vec::new(),
+            drawdown_curve: Vec::new(),
             created_at: r.created_at,
         })
         .collect())
@@ -1428,64 +3288,78 @@ fn get_backtest_results(
 fn get_backtest_detail(
     state: State<AppState>,
     backtest_id: i64,
-) -> Result<Option<BacktestResultData>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<Option<BacktestResultData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let result = db
         .get_backtest_detail(backtest_id)
-        .map_err(|e| e.to_string())?;
-
-    Ok(result.map(|r| BacktestResultData {
-        id: r.id,
-        strategy_id: r.strategy_id,
-        strategy_name: r.strategy_name,
-        symbol: r.symbol,
-        start_date: r.start_date.to_string(),
-        end_date: r.end_date.to_string(),
-        initial_capital: r.initial_capital,
-        final_capital: r.final_capital,
-        metrics: MetricsData {
-            total_return: r.metrics.total_return,
-            total_return_dollars: r.metrics.total_return_dollars,
-            max_drawdown: r.metrics.max_drawdown,
-            sharpe_ratio: r.metrics.sharpe_ratio,
-            win_rate: r.metrics.win_rate,
-            total_trades: r.metrics.total_trades,
-            winning_trades: r.metrics.winning_trades,
-            losing_trades: r.metrics.losing_trades,
-            avg_win_percent: r.metrics.avg_win_percent,
-            avg_loss_percent: r.metrics.avg_loss_percent,
-            profit_factor: r.metrics.profit_factor,
-            avg_trade_duration_days: r.metrics.avg_trade_duration_days,
-        },
-        trades: r
-            .trades
-            .into_iter()
-            .map(|t| BacktestTradeData {
-                id: t.id,
-                symbol: t.symbol,
-                direction: t.direction.as_str().to_string(),
-                entry_date: t.entry_date.to_string(),
-                entry_price: t.entry_price,
-                entry_reason: t.entry_reason,
-                exit_date: t.exit_date.map(|d| d.to_string()),
-                exit_price: t.exit_price,
-                exit_reason: t.exit_reason,
-                shares: t.shares,
-                profit_loss: t.profit_loss,
-                profit_loss_percent: t.profit_loss_percent,
-            })
-            .collect(),
-        created_at: r.created_at,
-    }))
+        .map_err(CommandError::from)?;
+
+    Ok(result.map(|r| r.into()))
+}
+
+/// Write a PDF report of a stored backtest (parameters, metrics, equity curve, trades)
+#[tauri::command]
+fn generate_backtest_report(
+    state: State<AppState>,
+    backtest_id: i64,
+    path: String,
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let pages = write_backtest_report(&db, backtest_id, &path).map_err(CommandError::from)?;
+
+    println!("[OK] Wrote {}-page backtest report to {}", pages, path);
+
+    Ok(CommandResult {
+        success: true,
+        message: format!("Saved {}-page backtest report to {}", pages, path),
+    })
+}
+
+/// Monte Carlo runs when none are requested
+const DEFAULT_MONTE_CARLO_RUNS: usize = 1000;
+/// Upper bound on Monte Carlo runs per request
+const MAX_MONTE_CARLO_RUNS: usize = 100_000;
+
+/// Resample a stored backtest's trades to estimate the spread of outcomes
+#[tauri::command]
+fn run_monte_carlo(
+    state: State<AppState>,
+    backtest_id: i64,
+    runs: Option<usize>,
+) -> Result<MonteCarloResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let backtest = db
+        .get_backtest_detail(backtest_id)
+        .map_err(CommandError::from)?
+        .ok_or_else(|| CommandError::no_data(format!("Backtest {} not found", backtest_id)))?;
+    drop(db);
+
+    if backtest.trades.iter().all(|t| t.exit_date.is_none()) {
+        return Err(CommandError::no_data(format!("Backtest {} has no closed trades", backtest_id)));
+    }
+
+    let runs = runs.unwrap_or(DEFAULT_MONTE_CARLO_RUNS).clamp(1, MAX_MONTE_CARLO_RUNS);
+    let result = BacktestEngine::monte_carlo(&backtest.trades, runs, backtest.initial_capital);
+
+    println!(
+        "[OK] Monte Carlo for backtest {}: {} runs, ruin probability {:.1}%",
+        backtest_id,
+        runs,
+        result.probability_of_ruin * 100.0
+    );
+
+    Ok(result)
 }
 
 /// Delete a backtest result
 #[tauri::command]
-fn delete_backtest(state: State<AppState>, backtest_id: i64) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn delete_backtest(state: State<AppState>, backtest_id: i64) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    db.delete_backtest(backtest_id).map_err(|e| e.to_string())?;
+    db.delete_backtest(backtest_id).map_err(CommandError::from)?;
 
     Ok(CommandResult {
         success: true,
@@ -1516,6 +3390,13 @@ struct WatchlistSummary {
     symbol_count: i64,
 }
 
+/// Pairwise return correlations for a watchlist; null where the overlap is too short
+#[derive(Serialize)]
+struct CorrelationMatrixResponse {
+    symbols: Vec<String>,
+    matrix: Vec<Vec<Option<f64>>>,
+}
+
 /// Create a new watchlist/symbol group
 #[tauri::command]
 fn create_watchlist(
@@ -1523,13 +3404,13 @@ fn create_watchlist(
     name: String,
     symbols: Vec<String>,
     description: Option<String>,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let symbols_upper: Vec<String> = symbols.iter().map(|s| s.to_uppercase()).collect();
 
     db.create_watchlist(&name, &symbols_upper, description.as_deref())
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     println!("[OK] Created watchlist '{}' with {} symbols", name, symbols_upper.len());
 
@@ -1541,10 +3422,10 @@ fn create_watchlist(
 
 /// Get all watchlists (summary view)
 #[tauri::command]
-fn get_all_watchlists(state: State<AppState>) -> Result<Vec<WatchlistSummary>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_all_watchlists(state: State<AppState>) -> Result<Vec<WatchlistSummary>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let watchlists = db.get_all_watchlists().map_err(|e| e.to_string())?;
+    let watchlists = db.get_all_watchlists().map_err(CommandError::from)?;
 
     Ok(watchlists
         .into_iter()
@@ -1559,10 +3440,10 @@ fn get_all_watchlists(state: State<AppState>) -> Result<Vec<WatchlistSummary>, S
 
 /// Get a watchlist with its symbols
 #[tauri::command]
-fn get_watchlist_detail(state: State<AppState>, name: String) -> Result<Option<WatchlistData>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_watchlist_detail(state: State<AppState>, name: String) -> Result<Option<WatchlistData>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let result = db.get_watchlist_full(&name).map_err(|e| e.to_string())?;
+    let result = db.get_watchlist_full(&name).map_err(CommandError::from)?;
 
     Ok(result.map(|(id, name, description, symbols)| WatchlistData {
         id,
@@ -1575,10 +3456,10 @@ fn get_watchlist_detail(state: State<AppState>, name: String) -> Result<Option<W
 
 /// Delete a watchlist
 #[tauri::command]
-fn delete_watchlist(state: State<AppState>, name: String) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn delete_watchlist(state: State<AppState>, name: String) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let deleted = db.delete_watchlist(&name).map_err(|e| e.to_string())?;
+    let deleted = db.delete_watchlist(&name).map_err(CommandError::from)?;
 
     if deleted {
         println!("[OK] Deleted watchlist '{}'", name);
@@ -1600,13 +3481,13 @@ fn add_symbol_to_watchlist(
     state: State<AppState>,
     watchlist_name: String,
     symbol: String,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
     let success = db
         .add_symbol_to_watchlist(&watchlist_name, &symbol)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     if success {
         println!("[OK] Added {} to watchlist '{}'", symbol, watchlist_name);
@@ -1628,13 +3509,13 @@ fn remove_symbol_from_watchlist(
     state: State<AppState>,
     watchlist_name: String,
     symbol: String,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
     let success = db
         .remove_symbol_from_watchlist(&watchlist_name, &symbol)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     if success {
         println!("[OK] Removed {} from watchlist '{}'", symbol, watchlist_name);
@@ -1656,12 +3537,12 @@ fn update_watchlist_description(
     state: State<AppState>,
     name: String,
     description: Option<String>,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let success = db
         .update_watchlist_description(&name, description.as_deref())
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     if success {
         Ok(CommandResult {
@@ -1682,12 +3563,12 @@ fn rename_watchlist(
     state: State<AppState>,
     old_name: String,
     new_name: String,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let success = db
         .rename_watchlist(&old_name, &new_name)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     if success {
         println!("[OK] Renamed watchlist '{}' to '{}'", old_name, new_name);
@@ -1703,6 +3584,29 @@ fn rename_watchlist(
     }
 }
 
+/// Correlation of daily log returns between every pair of symbols in a watchlist
+#[tauri::command]
+fn get_correlation_matrix(
+    state: State<AppState>,
+    watchlist_name: String,
+) -> Result<CorrelationMatrixResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let symbols = db.get_watchlist(&watchlist_name).map_err(CommandError::from)?;
+    let series = symbols
+        .iter()
+        .map(|symbol| Ok((symbol.clone(), db.get_prices(symbol)?)))
+        .collect::<financial_pipeline::error::Result<Vec<_>>>()
+        .map_err(CommandError::from)?;
+
+    let matrix = correlation_matrix(&series)
+        .into_iter()
+        .map(|row| row.into_iter().map(|v| (!v.is_nan()).then_some(v)).collect())
+        .collect();
+
+    Ok(CorrelationMatrixResponse { symbols, matrix })
+}
+
 // ============================================================================
 // VECTOR DATABASE COMMANDS
 // ============================================================================
@@ -1725,12 +3629,29 @@ struct VectorStatsResponse {
     patterns_count: usize,
 }
 
-/// Search the vector database for relevant market events and patterns
+/// Search the vector database for relevant market events and patterns,
+/// optionally scoped to a symbol, inclusive date range, or event type
 #[tauri::command]
-fn vector_search(query: String, limit: usize) -> Result<Vec<VectorSearchResponse>, String> {
-    let store = VectorStore::new(&get_data_path("vectors.db")).map_err(|e| e.to_string())?;
+fn vector_search(
+    query: String,
+    limit: usize,
+    symbol: Option<String>,
+    from_date: Option<String>,
+    to_date: Option<String>,
+    event_type: Option<String>,
+) -> Result<Vec<VectorSearchResponse>, CommandError> {
+    let symbol = match symbol {
+        Some(s) => Some(
+            normalize_symbol(&s)
+                .ok_or_else(|| CommandError::invalid_input(format!("Invalid symbol: {}", s)))?,
+        ),
+        None => None,
+    };
+    let filter = VectorSearchFilter { symbol, from_date, to_date, event_type };
+
+    let store = VectorStore::new(get_data_path("vectors.db")).map_err(CommandError::from)?;
 
-    let results = store.search_all(&query, limit).map_err(|e| e.to_string())?;
+    let results = store.search_filtered(&query, limit, &filter).map_err(CommandError::from)?;
 
     Ok(results
         .into_iter()
@@ -1754,16 +3675,12 @@ fn add_market_event(
     content: String,
     date: String,
     sentiment: Option<f32>,
-) -> Result<CommandResult, String> {
-    let store = VectorStore::new(&get_data_path("vectors.db")).map_err(|e| e.to_string())?;
-
-    // Generate deterministic ID from content - same article always gets same ID
-    // This allows INSERT OR REPLACE to work correctly and prevent duplicates
-    let title_hash: u32 = title.bytes().fold(0u32, |acc, b| acc.wrapping_add(b as u32).wrapping_mul(31));
-    let content_hash: u32 = content.bytes().fold(0u32, |acc, b| acc.wrapping_add(b as u32).wrapping_mul(31));
+) -> Result<CommandResult, CommandError> {
+    let store = VectorStore::new(get_data_path("vectors.db")).map_err(CommandError::from)?;
 
+    // Same article always gets the same ID, so INSERT OR REPLACE dedupes it
     let event = MarketEvent {
-        id: format!("{}-{}-{}-{:x}{:x}", symbol, event_type, date, title_hash, content_hash),
+        id: event_id(&symbol, &event_type, &date, &title, &content),
         symbol,
         event_type,
         title,
@@ -1773,7 +3690,7 @@ fn add_market_event(
         metadata: None,
     };
 
-    store.add_market_event(&event).map_err(|e| e.to_string())?;
+    store.add_market_event(&event).map_err(CommandError::from)?;
 
     Ok(CommandResult {
         success: true,
@@ -1791,8 +3708,8 @@ fn add_price_pattern(
     price_change_percent: f32,
     volume_change_percent: f32,
     description: String,
-) -> Result<CommandResult, String> {
-    let store = VectorStore::new(&get_data_path("vectors.db")).map_err(|e| e.to_string())?;
+) -> Result<CommandResult, CommandError> {
+    let store = VectorStore::new(get_data_path("vectors.db")).map_err(CommandError::from)?;
 
     let pattern = PricePattern {
         id: format!("{}-{}-{}", symbol, pattern_type, start_date),
@@ -1805,7 +3722,7 @@ fn add_price_pattern(
         description,
     };
 
-    store.add_price_pattern(&pattern).map_err(|e| e.to_string())?;
+    store.add_price_pattern(&pattern).map_err(CommandError::from)?;
 
     Ok(CommandResult {
         success: true,
@@ -1813,19 +3730,92 @@ fn add_price_pattern(
     })
 }
 
-/// Response for add_market_event_with_pattern command
-#[derive(Serialize)]
-struct EventWithPatternResponse {
-    success: bool,
-    message: String,
-    event_id: String,
-    pattern_id: Option<String>,
-    price_change_percent: Option<f64>,
-    pattern_error: Option<String>,  // Capture actual error reason
+/// Response for add_market_event_with_pattern command
+#[derive(Serialize)]
+struct EventWithPatternResponse {
+    success: bool,
+    message: String,
+    event_id: String,
+    pattern_id: Option<String>,
+    price_change_percent: Option<f64>,
+    pattern_error: Option<String>,  // Capture actual error reason
+    earnings_event_ids: Vec<String>,
+}
+
+/// Event time for intraday matching: RFC 3339, or "YYYY-MM-DD HH:MM[:SS]" taken as UTC.
+/// A bare date has no time of day, so it yields `None`.
+fn parse_event_time(date: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(date) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|fmt| chrono::NaiveDateTime::parse_from_str(date, fmt).ok())
+        .map(|dt| dt.and_utc())
+}
+
+/// Reaction from daily closes: the close nearest before the event date against the
+/// last close within `days_window` days after it
+fn daily_price_reaction(
+    prices: &[DailyPrice],
+    symbol: &str,
+    date: &str,
+    days_window: i64,
+) -> Result<PriceReaction, String> {
+    if prices.is_empty() {
+        return Err(format!("No local price data for {}", symbol));
+    }
+    // Accept timestamps too; only the calendar day matters here
+    let event_date = date
+        .get(..10)
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+        .ok_or_else(|| format!("Invalid date format: {}", date))?;
+
+    let mut pre: Option<&DailyPrice> = None;
+    let mut post: Option<&DailyPrice> = None;
+    let mut candle_count = 0;
+    for price in prices {
+        let days_diff = (price.date - event_date).num_days();
+        if days_diff.abs() <= days_window {
+            candle_count += 1;
+        }
+        // Closest price on or before the event (within window)
+        if (-days_window..=0).contains(&days_diff) {
+            pre = Some(price);
+        }
+        // Furthest price on or after the event (within window)
+        if (0..=days_window).contains(&days_diff) {
+            post = Some(price);
+        }
+    }
+
+    let (Some(pre), Some(post)) = (pre, post) else {
+        return Err(format!("No price data found around {} for {}", date, symbol));
+    };
+    let volume_change_percent = if pre.volume > 0 {
+        ((post.volume - pre.volume) as f64 / pre.volume as f64) * 100.0
+    } else {
+        0.0
+    };
+
+    Ok(PriceReaction {
+        symbol: symbol.to_string(),
+        event_date: event_date.to_string(),
+        start_date: pre.date.to_string(),
+        end_date: post.date.to_string(),
+        pre_price: pre.close,
+        post_price: post.close,
+        price_change_percent: ((post.close - pre.close) / pre.close) * 100.0,
+        volume_change_percent,
+        candle_count,
+    })
 }
 
 /// Add a market event with an auto-linked price pattern
 /// Uses local Yahoo price data for pattern linking (Finnhub free tier doesn't allow candle access)
+/// With `resolution`, stored intraday bars within `hours_window` hours of the event time are
+/// used instead of daily closes (falling back to daily when there are none)
+/// With `include_earnings`, upcoming earnings stored for the symbol are added as events too
 #[tauri::command]
 fn add_market_event_with_pattern(
     state: State<AppState>,
@@ -1838,13 +3828,14 @@ fn add_market_event_with_pattern(
     _api_key: Option<String>,  // Kept for API compatibility but not used
     link_pattern: bool,
     days_window: Option<i64>,
-) -> Result<EventWithPatternResponse, String> {
-    let store = VectorStore::new(&get_data_path("vectors.db")).map_err(|e| e.to_string())?;
+    include_earnings: Option<bool>,
+    resolution: Option<String>,
+    hours_window: Option<i64>,
+) -> Result<EventWithPatternResponse, CommandError> {
+    let store = VectorStore::new(get_data_path("vectors.db")).map_err(CommandError::from)?;
 
     // Generate deterministic ID from content
-    let title_hash: u32 = title.bytes().fold(0u32, |acc, b| acc.wrapping_add(b as u32).wrapping_mul(31));
-    let content_hash: u32 = content.bytes().fold(0u32, |acc, b| acc.wrapping_add(b as u32).wrapping_mul(31));
-    let event_id = format!("{}-{}-{}-{:x}{:x}", symbol, event_type, date, title_hash, content_hash);
+    let event_id = event_id(&symbol, &event_type, &date, &title, &content);
 
     // Create and store the event
     let event = MarketEvent {
@@ -1858,7 +3849,7 @@ fn add_market_event_with_pattern(
         metadata: None,
     };
 
-    store.add_market_event(&event).map_err(|e| e.to_string())?;
+    store.add_market_event(&event).map_err(CommandError::from)?;
 
     // If link_pattern is true, use local Yahoo price data to calculate reaction
     let mut pattern_id = None;
@@ -1866,84 +3857,65 @@ fn add_market_event_with_pattern(
     let mut pattern_error: Option<String> = None;
 
     if link_pattern {
-        let window = days_window.unwrap_or(3) as i32;
-
-        // Get local price data from Yahoo (already fetched)
-        let db = state.db.lock().map_err(|e| e.to_string())?;
-        let prices = db.get_prices(&symbol).map_err(|e| e.to_string())?;
+        let db = state.db.get().map_err(CommandError::from)?;
+
+        // Intraday bars capture same-day reactions; daily closes are the fallback
+        let intraday = match (resolution.as_deref(), parse_event_time(&date)) {
+            (Some(res), Some(event_time)) => db
+                .intraday_price_reaction(&symbol, res, event_time, hours_window.unwrap_or(4))
+                .map_err(CommandError::from)?,
+            _ => None,
+        };
+        let reaction = match intraday {
+            Some(reaction) => Ok(reaction),
+            None => {
+                // Get local price data from Yahoo (already fetched)
+                let prices = db.get_prices(&symbol).map_err(CommandError::from)?;
+                daily_price_reaction(&prices, &symbol, &date, days_window.unwrap_or(3))
+            }
+        };
 
-        if prices.is_empty() {
-            pattern_error = Some(format!("No local price data for {}", symbol));
-        } else {
-            // Parse event date
-            use chrono::NaiveDate;
-            if let Ok(event_date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
-                // Find prices around the event date
-                let mut pre_price: Option<f64> = None;
-                let mut post_price: Option<f64> = None;
-                let mut start_date_str = String::new();
-                let mut end_date_str = String::new();
-                let mut pre_volume: i64 = 0;
-                let mut post_volume: i64 = 0;
-
-                for price in &prices {
-                    if let Ok(price_date) = NaiveDate::parse_from_str(&price.date.to_string(), "%Y-%m-%d") {
-                        let days_diff = (price_date - event_date).num_days();
-
-                        // Find closest price BEFORE event (within window)
-                        if days_diff >= -(window as i64) && days_diff <= 0 {
-                            if pre_price.is_none() || days_diff > -(window as i64) {
-                                pre_price = Some(price.close);
-                                start_date_str = price.date.to_string();
-                                pre_volume = price.volume;
-                            }
-                        }
+        match reaction {
+            Ok(reaction) => {
+                // Create linked pattern
+                let pid = format!("news-reaction-{}", event_id);
+                let pattern = PricePattern {
+                    id: pid.clone(),
+                    symbol: symbol.clone(),
+                    pattern_type: "news_reaction".to_string(),
+                    start_date: reaction.start_date.clone(),
+                    end_date: reaction.end_date.clone(),
+                    price_change_percent: reaction.price_change_percent as f32,
+                    volume_change_percent: reaction.volume_change_percent as f32,
+                    description: format!(
+                        "Price reaction to: {} | Pre: ${:.2} → Post: ${:.2} ({:+.2}%)",
+                        title,
+                        reaction.pre_price,
+                        reaction.post_price,
+                        reaction.price_change_percent
+                    ),
+                };
 
-                        // Find closest price AFTER event (within window)
-                        if days_diff >= 0 && days_diff <= window as i64 {
-                            post_price = Some(price.close);
-                            end_date_str = price.date.to_string();
-                            post_volume = price.volume;
-                        }
-                    }
+                if store.add_price_pattern(&pattern).is_ok() {
+                    pattern_id = Some(pid);
+                    price_change = Some(reaction.price_change_percent);
                 }
+            }
+            Err(e) => pattern_error = Some(e),
+        }
+    }
 
-                if let (Some(pre), Some(post)) = (pre_price, post_price) {
-                    let price_change_pct = ((post - pre) / pre) * 100.0;
-                    let volume_change_pct = if pre_volume > 0 {
-                        ((post_volume - pre_volume) as f64 / pre_volume as f64) * 100.0
-                    } else {
-                        0.0
-                    };
-
-                    // Create linked pattern
-                    let pid = format!("news-reaction-{}", event_id);
-                    let pattern = PricePattern {
-                        id: pid.clone(),
-                        symbol: symbol.clone(),
-                        pattern_type: "news_reaction".to_string(),
-                        start_date: start_date_str.clone(),
-                        end_date: end_date_str.clone(),
-                        price_change_percent: price_change_pct as f32,
-                        volume_change_percent: volume_change_pct as f32,
-                        description: format!(
-                            "Price reaction to: {} | Pre: ${:.2} → Post: ${:.2} ({:+.2}%)",
-                            title,
-                            pre,
-                            post,
-                            price_change_pct
-                        ),
-                    };
-
-                    if store.add_price_pattern(&pattern).is_ok() {
-                        pattern_id = Some(pid);
-                        price_change = Some(price_change_pct);
-                    }
-                } else {
-                    pattern_error = Some(format!("No price data found around {} for {}", date, symbol));
-                }
-            } else {
-                pattern_error = Some(format!("Invalid date format: {}", date));
+    let mut earnings_event_ids = Vec::new();
+    if include_earnings.unwrap_or(false) {
+        let db = state.db.get().map_err(CommandError::from)?;
+        let upcoming = db
+            .get_upcoming_earnings(&symbol.to_uppercase(), Utc::now().date_naive())
+            .map_err(CommandError::from)?;
+
+        for earnings in upcoming {
+            let event = earnings_market_event(&earnings);
+            if store.add_market_event(&event).is_ok() {
+                earnings_event_ids.push(event.id);
             }
         }
     }
@@ -1970,15 +3942,70 @@ fn add_market_event_with_pattern(
         pattern_id,
         price_change_percent: price_change,
         pattern_error,
+        earnings_event_ids,
+    })
+}
+
+/// Market event describing a scheduled (or reported) earnings release
+fn earnings_market_event(earnings: &EarningsEvent) -> MarketEvent {
+    let timing = match earnings.hour.as_deref() {
+        Some("bmo") => " before market open",
+        Some("amc") => " after market close",
+        Some("dmh") => " during market hours",
+        _ => "",
+    };
+    let mut content = format!("{} reports earnings on {}{}.", earnings.symbol, earnings.date, timing);
+    if let Some(eps) = earnings.eps_estimate {
+        content.push_str(&format!(" EPS estimate: {:.2}.", eps));
+    }
+    if let Some(revenue) = earnings.revenue_estimate {
+        content.push_str(&format!(" Revenue estimate: ${:.2}B.", revenue / 1e9));
+    }
+
+    MarketEvent {
+        id: format!("{}-earnings-{}", earnings.symbol, earnings.date),
+        symbol: earnings.symbol.clone(),
+        event_type: "earnings".to_string(),
+        title: format!("{} earnings", earnings.symbol),
+        content,
+        date: earnings.date.to_string(),
+        sentiment: None,
+        metadata: None,
+    }
+}
+
+/// Delete a market event from the vector database
+#[tauri::command]
+fn delete_market_event(id: String) -> Result<CommandResult, CommandError> {
+    let store = VectorStore::new(get_data_path("vectors.db")).map_err(CommandError::from)?;
+
+    let removed = store.delete_event(&id).map_err(CommandError::from)?;
+
+    Ok(CommandResult {
+        success: removed > 0,
+        message: format!("Removed {} market event(s)", removed),
+    })
+}
+
+/// Delete a price pattern from the vector database
+#[tauri::command]
+fn delete_price_pattern(id: String) -> Result<CommandResult, CommandError> {
+    let store = VectorStore::new(get_data_path("vectors.db")).map_err(CommandError::from)?;
+
+    let removed = store.delete_pattern(&id).map_err(CommandError::from)?;
+
+    Ok(CommandResult {
+        success: removed > 0,
+        message: format!("Removed {} price pattern(s)", removed),
     })
 }
 
 /// Get vector database statistics
 #[tauri::command]
-fn get_vector_stats() -> Result<VectorStatsResponse, String> {
-    let store = VectorStore::new(&get_data_path("vectors.db")).map_err(|e| e.to_string())?;
+fn get_vector_stats() -> Result<VectorStatsResponse, CommandError> {
+    let store = VectorStore::new(get_data_path("vectors.db")).map_err(CommandError::from)?;
 
-    let (events_count, patterns_count) = store.get_stats().map_err(|e| e.to_string())?;
+    let (events_count, patterns_count) = store.get_stats().map_err(CommandError::from)?;
 
     Ok(VectorStatsResponse {
         events_count,
@@ -1998,20 +4025,43 @@ struct ClaudeChatResponse {
     input_tokens: u32,
     output_tokens: u32,
     conversation_id: String,
+    estimated_cost_usd: f64,
 }
 
-/// Chat with Claude using financial context from the database
-#[tauri::command]
-fn claude_chat(
-    state: State<AppState>,
-    query: String,
-    api_key: String,
-) -> Result<ClaudeChatResponse, String> {
-    // Build financial context from database
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+impl From<ChatResult> for ClaudeChatResponse {
+    fn from(result: ChatResult) -> Self {
+        Self {
+            estimated_cost_usd: estimate_claude_cost(result.input_tokens, result.output_tokens, &result.model),
+            response: result.response,
+            model: result.model,
+            input_tokens: result.input_tokens,
+            output_tokens: result.output_tokens,
+            conversation_id: result.conversation_id,
+        }
+    }
+}
 
+const SETTING_CLAUDE_MAX_CONTEXT_TOKENS: &str = "claude_max_context_tokens";
+
+/// Create a Claude client using the configured market context token budget
+fn claude_client(db: &Database, api_key: String) -> Result<ClaudeClient, CommandError> {
+    let max_context_tokens = db
+        .get_setting(SETTING_CLAUDE_MAX_CONTEXT_TOKENS)
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_CONTEXT_TOKENS);
+
+    Ok(ClaudeClient::with_api_key(api_key)
+        .map_err(CommandError::from)?
+        .with_max_context_tokens(max_context_tokens))
+}
+
+/// Build the financial context (tracked symbols and latest prices) sent with Claude queries
+fn build_financial_context(db: &Database, query: &str) -> Result<FinancialContext, CommandError> {
     // Get tracked symbols and their prices
-    let symbols = db.get_symbols_with_data().map_err(|e| e.to_string())?;
+    let symbols = db.get_symbols_with_data().map_err(CommandError::from)?;
+    let favorites = db.get_favorited_symbols().map_err(CommandError::from)?;
 
     let mut price_contexts = Vec::new();
     for symbol in &symbols {
@@ -2030,6 +4080,7 @@ fn claude_chat(
                     price: current.close,
                     change_percent: Some(change_pct),
                     date: current.date.to_string(),
+                    favorited: favorites.contains(symbol),
                 });
             } else if let Some(price) = prices.last() {
                 price_contexts.push(ClaudePriceContext {
@@ -2037,30 +4088,38 @@ fn claude_chat(
                     price: price.close,
                     change_percent: None,
                     date: price.date.to_string(),
+                    favorited: favorites.contains(symbol),
                 });
             }
         }
     }
 
-    // Drop the db lock before making the API call
-    drop(db);
-
-    let context = FinancialContext {
+    Ok(FinancialContext {
         symbols,
         recent_prices: price_contexts,
-        query: query.clone(),
-    };
+        query: query.to_string(),
+    })
+}
 
-    // Create Claude client and query
-    let client = ClaudeClient::with_api_key(api_key)
-        .map_err(|e| e.to_string())?;
+/// Chat with Claude using financial context from the database
+#[tauri::command]
+fn claude_chat(
+    state: State<AppState>,
+    query: String,
+    api_key: String,
+) -> Result<ClaudeChatResponse, CommandError> {
+    // Build financial context and client from database, releasing the connection before the API call
+    let (context, client) = {
+        let db = state.db.get().map_err(CommandError::from)?;
+        (build_financial_context(&db, &query)?, claude_client(&db, api_key)?)
+    };
 
     let result = client
         .query_with_context(&query, Some(&context), None)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     // Store the conversation in vector database for future reference
-    let store = VectorStore::new(&get_data_path("vectors.db")).map_err(|e| e.to_string())?;
+    let store = VectorStore::new(get_data_path("vectors.db")).map_err(CommandError::from)?;
 
     let event = MarketEvent {
         id: format!("chat-{}", result.conversation_id),
@@ -2076,13 +4135,7 @@ fn claude_chat(
     // Store but don't fail if it errors
     let _ = store.add_market_event(&event);
 
-    Ok(ClaudeChatResponse {
-        response: result.response,
-        model: result.model,
-        input_tokens: result.input_tokens,
-        output_tokens: result.output_tokens,
-        conversation_id: result.conversation_id,
-    })
+    Ok(result.into())
 }
 
 /// Simple Claude query without financial context
@@ -2090,23 +4143,213 @@ fn claude_chat(
 fn claude_query(
     query: String,
     api_key: String,
-) -> Result<ClaudeChatResponse, String> {
+) -> Result<ClaudeChatResponse, CommandError> {
     let client = ClaudeClient::with_api_key(api_key)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     let result = client
         .query(&query)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
+
+    Ok(result.into())
+}
+
+/// Get the token budget for market context sent with Claude chats
+#[tauri::command]
+fn get_claude_context_budget(state: State<AppState>) -> Result<usize, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    Ok(db
+        .get_setting(SETTING_CLAUDE_MAX_CONTEXT_TOKENS)
+        .map_err(CommandError::from)?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_CONTEXT_TOKENS))
+}
+
+/// Set the token budget for market context sent with Claude chats
+#[tauri::command]
+fn set_claude_context_budget(state: State<AppState>, max_context_tokens: usize) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.set_setting(SETTING_CLAUDE_MAX_CONTEXT_TOKENS, &max_context_tokens.to_string())
+        .map_err(CommandError::from)?;
+
+    Ok(CommandResult {
+        success: true,
+        message: format!("Claude context budget set to {} tokens", max_context_tokens),
+    })
+}
+
+/// Saved conversation summary for frontend
+#[derive(Serialize)]
+struct ConversationResponse {
+    id: i64,
+    title: String,
+    provider: String,
+    created_at: String,
+    updated_at: String,
+    message_count: i64,
+}
+
+impl From<Conversation> for ConversationResponse {
+    fn from(c: Conversation) -> Self {
+        Self {
+            id: c.id,
+            title: c.title,
+            provider: c.provider,
+            created_at: c.created_at,
+            updated_at: c.updated_at,
+            message_count: c.message_count,
+        }
+    }
+}
+
+/// Conversation message for frontend
+#[derive(Serialize)]
+struct ConversationMessageResponse {
+    id: i64,
+    role: String,
+    content: String,
+    created_at: String,
+}
+
+impl From<ConversationMessage> for ConversationMessageResponse {
+    fn from(m: ConversationMessage) -> Self {
+        Self {
+            id: m.id,
+            role: m.role,
+            content: m.content,
+            created_at: m.created_at,
+        }
+    }
+}
+
+/// Conversation with its full message history
+#[derive(Serialize)]
+struct ConversationDetailResponse {
+    conversation: ConversationResponse,
+    messages: Vec<ConversationMessageResponse>,
+}
+
+/// Reply within a saved conversation
+#[derive(Serialize)]
+struct ConversationChatResponse {
+    conversation_id: i64,
+    response: String,
+    model: String,
+    input_tokens: u32,
+    output_tokens: u32,
+    estimated_cost_usd: f64,
+}
+
+/// Chat with Claude inside a saved conversation, sending prior turns as history.
+/// Starts a new conversation when `conversation_id` is not given.
+#[tauri::command]
+fn claude_chat_in_conversation(
+    state: State<AppState>,
+    conversation_id: Option<i64>,
+    query: String,
+    api_key: String,
+) -> Result<ConversationChatResponse, CommandError> {
+    let (conversation_id, history, context, client) = {
+        let db = state.db.get().map_err(CommandError::from)?;
+
+        let conversation_id = match conversation_id {
+            Some(id) => {
+                if db.get_conversation(id).map_err(CommandError::from)?.is_none() {
+                    return Err(CommandError::no_data(format!("Conversation {} not found", id)));
+                }
+                id
+            }
+            None => {
+                let title: String = query.chars().take(60).collect();
+                db.create_conversation(&title, "claude").map_err(CommandError::from)?
+            }
+        };
+
+        let history: Vec<ClaudeMessage> = db
+            .get_conversation_messages(conversation_id)
+            .map_err(CommandError::from)?
+            .into_iter()
+            .map(|m| ClaudeMessage { role: m.role, content: m.content })
+            .collect();
+
+        let context = build_financial_context(&db, &query)?;
+        (conversation_id, history, context, claude_client(&db, api_key)?)
+    };
+
+    let history = trim_history(&history, DEFAULT_HISTORY_TOKEN_BUDGET);
 
-    Ok(ClaudeChatResponse {
+    let result = client
+        .query_with_context(&query, Some(&context), Some(&history))
+        .map_err(CommandError::from)?;
+
+    // Only persist the exchange once Claude has answered
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.add_conversation_message(conversation_id, "user", &query)
+        .map_err(CommandError::from)?;
+    db.add_conversation_message(conversation_id, "assistant", &result.response)
+        .map_err(CommandError::from)?;
+
+    Ok(ConversationChatResponse {
+        conversation_id,
+        estimated_cost_usd: estimate_claude_cost(result.input_tokens, result.output_tokens, &result.model),
         response: result.response,
         model: result.model,
         input_tokens: result.input_tokens,
         output_tokens: result.output_tokens,
-        conversation_id: result.conversation_id,
     })
 }
 
+/// List saved conversations, most recent first
+#[tauri::command]
+fn list_conversations(state: State<AppState>) -> Result<Vec<ConversationResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let conversations = db.list_conversations().map_err(CommandError::from)?;
+    Ok(conversations.into_iter().map(ConversationResponse::from).collect())
+}
+
+/// Get a saved conversation with all of its messages
+#[tauri::command]
+fn get_conversation(
+    state: State<AppState>,
+    conversation_id: i64,
+) -> Result<ConversationDetailResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let conversation = db
+        .get_conversation(conversation_id)
+        .map_err(CommandError::from)?
+        .ok_or_else(|| {
+            CommandError::no_data(format!("Conversation {} not found", conversation_id))
+        })?;
+    let messages = db
+        .get_conversation_messages(conversation_id)
+        .map_err(CommandError::from)?;
+
+    Ok(ConversationDetailResponse {
+        conversation: conversation.into(),
+        messages: messages.into_iter().map(ConversationMessageResponse::from).collect(),
+    })
+}
+
+/// Delete a saved conversation and its messages
+#[tauri::command]
+fn delete_conversation(state: State<AppState>, conversation_id: i64) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    match db.delete_conversation(conversation_id) {
+        Ok(true) => Ok(CommandResult {
+            success: true,
+            message: format!("Deleted conversation {}", conversation_id),
+        }),
+        Ok(false) => Ok(CommandResult {
+            success: false,
+            message: format!("Conversation {} not found", conversation_id),
+        }),
+        Err(e) => Ok(CommandResult {
+            success: false,
+            message: format!("Failed to delete conversation: {}", e),
+        }),
+    }
+}
+
 // ============================================================================
 // Ollama LLM Commands (local AI via localhost:11434)
 // ============================================================================
@@ -2118,26 +4361,98 @@ async fn ollama_available() -> bool {
     client.is_available().await
 }
 
-/// Analyze sentiment of text using local Ollama
+/// Ollama client using the chosen model, or the default model when none is given
+fn ollama_client(model: Option<String>) -> OllamaClient {
+    match model.filter(|m| !m.trim().is_empty()) {
+        Some(model) => OllamaClient::new().with_model(model.trim()),
+        None => OllamaClient::new(),
+    }
+}
+
+/// List models installed in the local Ollama server
 #[tauri::command]
-async fn ollama_sentiment(text: String) -> Result<SentimentResult, String> {
+async fn ollama_list_models() -> Result<Vec<OllamaModel>, CommandError> {
     let client = OllamaClient::new();
-    client.analyze_sentiment(&text).await.map_err(|e| e.to_string())
+    client.list_models().await.map_err(CommandError::from)
+}
+
+/// Analyze sentiment of text using local Ollama
+#[tauri::command]
+async fn ollama_sentiment(text: String, model: Option<String>) -> Result<SentimentResult, CommandError> {
+    let client = ollama_client(model);
+    client.analyze_sentiment(&text).await.map_err(CommandError::from)
 }
 
 /// Explain a technical pattern using local Ollama
 #[tauri::command]
-async fn ollama_explain(pattern: String, context: Option<String>) -> Result<PatternExplanation, String> {
-    let client = OllamaClient::new();
+async fn ollama_explain(
+    pattern: String,
+    context: Option<String>,
+    model: Option<String>,
+) -> Result<PatternExplanation, CommandError> {
+    let client = ollama_client(model);
     let ctx = context.unwrap_or_default();
-    client.explain_pattern(&pattern, &ctx).await.map_err(|e| e.to_string())
+    client.explain_pattern(&pattern, &ctx).await.map_err(CommandError::from)
 }
 
 /// Ask Ollama a question with financial context
 #[tauri::command]
-async fn ollama_ask(question: String, context: String) -> Result<String, String> {
-    let client = OllamaClient::new();
-    client.answer_query(&question, &context).await.map_err(|e| e.to_string())
+async fn ollama_ask(question: String, context: String, model: Option<String>) -> Result<String, CommandError> {
+    let client = ollama_client(model);
+    client.answer_query(&question, &context).await.map_err(CommandError::from)
+}
+
+/// Payload of each `ollama-token` event
+#[derive(Clone, Serialize)]
+struct OllamaTokenEvent {
+    token: String,
+}
+
+/// Payload of the `ollama-done` event: the full answer, or why it stopped
+#[derive(Clone, Serialize)]
+struct OllamaDoneEvent {
+    response: Option<String>,
+    error: Option<CommandError>,
+}
+
+/// Map streaming failures to codes the UI can act on (pull the model vs. retry)
+fn ollama_stream_error(e: anyhow::Error) -> CommandError {
+    match e.downcast_ref::<OllamaStreamError>() {
+        Some(OllamaStreamError::ModelNotFound(_)) => CommandError::new("MODEL_NOT_FOUND", e.to_string(), false),
+        Some(OllamaStreamError::ClosedEarly(_)) => CommandError::new("STREAM_CLOSED", e.to_string(), true),
+        None => CommandError::from(e),
+    }
+}
+
+/// Ask Ollama a question, emitting `ollama-token` per generated fragment and
+/// `ollama-done` when finished (also on failure); returns the full answer
+#[tauri::command]
+async fn ollama_ask_stream(
+    app: tauri::AppHandle,
+    question: String,
+    context: String,
+    model: Option<String>,
+) -> Result<String, CommandError> {
+    use tauri::Emitter;
+
+    let client = ollama_client(model);
+    let result = client
+        .answer_query_stream(&question, &context, |token| {
+            if let Err(e) = app.emit("ollama-token", OllamaTokenEvent { token: token.to_string() }) {
+                println!("[WARN] Failed to emit ollama-token: {}", e);
+            }
+        })
+        .await
+        .map_err(ollama_stream_error);
+
+    let done = match &result {
+        Ok(response) => OllamaDoneEvent { response: Some(response.clone()), error: None },
+        Err(e) => OllamaDoneEvent { response: None, error: Some(e.clone()) },
+    };
+    if let Err(e) = app.emit("ollama-done", done) {
+        println!("[WARN] Failed to emit ollama-done: {}", e);
+    }
+    result
 }
 
 /// Response for fetch_news command
@@ -2147,29 +4462,171 @@ struct FetchNewsResponse {
     count: usize,
 }
 
+/// Finnhub client backed by the shared response cache
+fn finnhub_client(state: &State<AppState>, api_key: String) -> Result<FinnhubClient, CommandError> {
+    Ok(FinnhubClient::new(api_key)
+        .map_err(CommandError::from)?
+        .with_cache(state.finnhub_cache.clone())
+        .with_rate_limiter(state.rate_limiter.clone()))
+}
+
 /// Fetch news for a symbol from Finnhub API
 #[tauri::command]
 fn fetch_news(
+    state: State<AppState>,
     symbol: String,
     api_key: String,
     limit: Option<usize>,
-) -> Result<FetchNewsResponse, String> {
+) -> Result<FetchNewsResponse, CommandError> {
     if api_key.is_empty() {
-        return Err("Finnhub API key is required. Get one free at https://finnhub.io".to_string());
+        return Err(PipelineError::Config(
+            "Finnhub API key is required. Get one free at https://finnhub.io".to_string(),
+        )
+        .into());
     }
 
-    let client = FinnhubClient::new(api_key)
-        .map_err(|e| e.to_string())?;
+    let client = finnhub_client(&state, api_key)?;
 
     let news_limit = limit.unwrap_or(5);
     let news = client.fetch_simple_news(&symbol, news_limit)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     let count = news.len();
 
     Ok(FetchNewsResponse { news, count })
 }
 
+/// Fetch the earnings calendar for a symbol from Finnhub and store it
+/// Defaults to the next 90 days when no range is given
+#[tauri::command]
+fn fetch_earnings(
+    state: State<AppState>,
+    symbol: String,
+    api_key: String,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Vec<EarningsEvent>, CommandError> {
+    if api_key.is_empty() {
+        return Err(PipelineError::Config(
+            "Finnhub API key is required. Get one free at https://finnhub.io".to_string(),
+        )
+        .into());
+    }
+
+    let parse = |d: &str| {
+        NaiveDate::parse_from_str(d, "%Y-%m-%d")
+            .map_err(|_| CommandError::invalid_input(format!("Invalid date: {}", d)))
+    };
+    let today = Utc::now().date_naive();
+    let from = from.as_deref().map(parse).transpose()?.unwrap_or(today);
+    let to = to
+        .as_deref()
+        .map(parse)
+        .transpose()?
+        .unwrap_or(today + chrono::Duration::days(90));
+
+    let client = finnhub_client(&state, api_key)?;
+    let events = client
+        .fetch_earnings(&symbol, from, to)
+        .map_err(CommandError::from)?;
+
+    let mut db = state.db.get().map_err(CommandError::from)?;
+    let count = db.upsert_earnings(&events).map_err(CommandError::from)?;
+
+    println!("[OK] Stored {} earnings events for {}", count, symbol.to_uppercase());
+    Ok(events)
+}
+
+/// Clear cached Finnhub responses so the next fetches hit the API
+#[tauri::command]
+fn clear_finnhub_cache(state: State<AppState>) -> Result<CommandResult, CommandError> {
+    let removed = state.finnhub_cache.clear().map_err(CommandError::from)?;
+
+    println!("[OK] Cleared {} cached Finnhub responses", removed);
+    Ok(CommandResult {
+        success: true,
+        message: format!("Cleared {} cached Finnhub responses", removed),
+    })
+}
+
+const RATE_LIMITED_SOURCES: [&str; 3] = [SOURCE_YAHOO, SOURCE_FRED, SOURCE_FINNHUB];
+
+/// Settings key holding a source's rate limit as JSON
+fn rate_limit_setting(source: &str) -> String {
+    format!("rate_limit_{}", source)
+}
+
+/// Rate limiter over finance.db's api_calls, with caps saved in settings applied
+fn build_rate_limiter(db: &Database, db_path: &Path) -> RateLimiter {
+    let limiter = RateLimiter::new(Database::open(db_path).expect("Failed to open rate limiter database"));
+    for source in RATE_LIMITED_SOURCES {
+        let saved = db
+            .get_setting(&rate_limit_setting(source))
+            .ok()
+            .flatten()
+            .and_then(|json| serde_json::from_str::<RateLimit>(&json).ok());
+        if let Some(limit) = saved {
+            limiter.set_limit(source, limit);
+        }
+    }
+    limiter
+}
+
+/// Rate limit and today's usage for one API source
+#[derive(Serialize)]
+struct RateLimitStatus {
+    source: String,
+    per_minute: Option<u32>,
+    per_day: Option<u32>,
+    calls_today: u32,
+}
+
+/// Get configured rate limits and today's call counts
+#[tauri::command]
+fn get_rate_limits(state: State<AppState>) -> Result<Vec<RateLimitStatus>, CommandError> {
+    RATE_LIMITED_SOURCES
+        .iter()
+        .map(|&source| {
+            let limit = state.rate_limiter.limit(source);
+            Ok(RateLimitStatus {
+                source: source.to_string(),
+                per_minute: limit.per_minute,
+                per_day: limit.per_day,
+                calls_today: state.rate_limiter.calls_today(source).map_err(CommandError::from)?,
+            })
+        })
+        .collect()
+}
+
+/// Set the request caps for an API source (null = uncapped)
+#[tauri::command]
+fn set_rate_limit(
+    state: State<AppState>,
+    source: String,
+    per_minute: Option<u32>,
+    per_day: Option<u32>,
+) -> Result<CommandResult, CommandError> {
+    let Some(&source) = RATE_LIMITED_SOURCES.iter().find(|s| s.eq_ignore_ascii_case(&source)) else {
+        return Ok(CommandResult {
+            success: false,
+            message: format!("Unknown API source: {}", source),
+        });
+    };
+
+    let limit = RateLimit { per_minute, per_day };
+    let json = serde_json::to_string(&limit).map_err(PipelineError::from)?;
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.set_setting(&rate_limit_setting(source), &json)
+        .map_err(CommandError::from)?;
+    state.rate_limiter.set_limit(source, limit);
+
+    println!("[OK] Rate limit for {} set to {:?}/min, {:?}/day", source, per_minute, per_day);
+    Ok(CommandResult {
+        success: true,
+        message: format!("Rate limit updated for {}", source),
+    })
+}
+
 /// Response for price reaction command
 #[derive(Serialize)]
 struct PriceReactionResponse {
@@ -2188,21 +4645,21 @@ struct PriceReactionResponse {
 /// Returns price change from days_before to days_after the event
 #[tauri::command]
 fn fetch_price_reaction(
+    state: State<AppState>,
     symbol: String,
     event_date: String,
     api_key: String,
     days_window: Option<i64>,
-) -> Result<PriceReactionResponse, String> {
+) -> Result<PriceReactionResponse, CommandError> {
     if api_key.is_empty() {
-        return Err("Finnhub API key is required".to_string());
+        return Err(PipelineError::Config("Finnhub API key is required".to_string()).into());
     }
 
-    let client = FinnhubClient::new(api_key)
-        .map_err(|e| e.to_string())?;
+    let client = finnhub_client(&state, api_key)?;
 
     let window = days_window.unwrap_or(3);
     let reaction = client.fetch_price_reaction(&symbol, &event_date, window)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(PriceReactionResponse {
         symbol: reaction.symbol,
@@ -2228,28 +4685,33 @@ struct CandleDataResponse {
     volume: Vec<i64>,
     timestamp: Vec<i64>,
     dates: Vec<String>,  // Human-readable dates (YYYY-MM-DD)
+    datetimes: Vec<String>,  // Full bar times, RFC 3339 in UTC
+    stored: usize,  // Bars written to intraday_prices (0 unless persist was requested)
 }
 
 /// Fetch OHLCV candle data for a symbol and date range
 /// Returns raw candle data for charting and analysis
+/// With `persist`, the bars are also stored in intraday_prices under their full timestamp
 #[tauri::command]
 fn fetch_candles(
+    state: State<AppState>,
     symbol: String,
     from_date: String,
     to_date: String,
     api_key: String,
     resolution: Option<String>,
-) -> Result<CandleDataResponse, String> {
+    persist: Option<bool>,
+) -> Result<CandleDataResponse, CommandError> {
     if api_key.is_empty() {
-        return Err("Finnhub API key is required".to_string());
+        return Err(PipelineError::Config("Finnhub API key is required".to_string()).into());
     }
 
     use chrono::NaiveDate;
 
     let from = NaiveDate::parse_from_str(&from_date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid from_date: {}", e))?;
+        .map_err(|e| CommandError::invalid_input(format!("Invalid from_date: {}", e)))?;
     let to = NaiveDate::parse_from_str(&to_date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid to_date: {}", e))?;
+        .map_err(|e| CommandError::invalid_input(format!("Invalid to_date: {}", e)))?;
 
     let from_ts = from.and_hms_opt(0, 0, 0)
         .ok_or("Failed to create start timestamp")?
@@ -2260,20 +4722,32 @@ fn fetch_candles(
         .and_utc()
         .timestamp();
 
-    let client = FinnhubClient::new(api_key)
-        .map_err(|e| e.to_string())?;
+    let client = finnhub_client(&state, api_key)?;
 
     let res = resolution.unwrap_or_else(|| "D".to_string());
     let candles = client.fetch_candles(&symbol, &res, from_ts, to_ts)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
+
+    let stored = if persist.unwrap_or(false) {
+        let bars = candles.to_intraday_prices(&symbol, &res);
+        let mut db = state.db.get().map_err(CommandError::from)?;
+        let count = db.upsert_intraday_prices(&bars).map_err(CommandError::from)?;
+        println!("[OK] Stored {} candles for {} (resolution {})", count, symbol.to_uppercase(), res);
+        count
+    } else {
+        0
+    };
 
-    // Convert timestamps to human-readable dates
-    let dates: Vec<String> = candles.timestamp.iter()
-        .filter_map(|&ts| {
-            chrono::DateTime::from_timestamp(ts, 0)
-                .map(|dt| dt.format("%Y-%m-%d").to_string())
+    // Convert timestamps to human-readable dates and full UTC times
+    let (dates, datetimes): (Vec<String>, Vec<String>) = candles.timestamp.iter()
+        .map(|&ts| {
+            let dt = chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default();
+            (
+                dt.format("%Y-%m-%d").to_string(),
+                dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            )
         })
-        .collect();
+        .unzip();
 
     Ok(CandleDataResponse {
         symbol: symbol.to_uppercase(),
@@ -2284,6 +4758,8 @@ fn fetch_candles(
         volume: candles.volume,
         timestamp: candles.timestamp,
         dates,
+        datetimes,
+        stored,
     })
 }
 
@@ -2298,8 +4774,13 @@ struct PaperWalletResponse {
     positions_value: f64,
     total_equity: f64,
     starting_capital: f64,
+    fees: TradeFees,
     total_pnl: f64,
     total_pnl_percent: f64,
+    /// Booked on closed (sold) shares
+    realized_pnl: f64,
+    /// Open positions marked to market
+    unrealized_pnl: f64,
 }
 
 /// Paper position with current price and P&L
@@ -2310,6 +4791,8 @@ struct PaperPositionResponse {
     quantity: f64,
     entry_price: f64,
     entry_date: String,
+    stop_price: Option<f64>,
+    take_price: Option<f64>,
     current_price: f64,
     current_value: f64,
     cost_basis: f64,
@@ -2326,49 +4809,49 @@ struct PaperTradeResponse {
     quantity: f64,
     price: f64,
     pnl: Option<f64>,
+    fee: f64,
     timestamp: String,
     notes: Option<String>,
 }
 
 /// Get paper wallet balance and portfolio summary
 #[tauri::command]
-fn get_paper_balance(state: State<AppState>) -> Result<PaperWalletResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_paper_balance(state: State<AppState>) -> Result<PaperWalletResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
+    let wallet = db.get_paper_wallet().map_err(CommandError::from)?;
     let (cash, positions_value, total_equity) = db
         .get_paper_portfolio_value()
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
-    let starting_capital = 100000.0; // Default starting capital
-    let total_pnl = total_equity - starting_capital;
-    let total_pnl_percent = if starting_capital > 0.0 {
-        (total_pnl / starting_capital) * 100.0
-    } else {
-        0.0
-    };
+    let realized_pnl = db.get_paper_realized_pnl().map_err(CommandError::from)?;
+    let unrealized_pnl = db.get_paper_unrealized_pnl().map_err(CommandError::from)?;
 
     Ok(PaperWalletResponse {
         cash,
         positions_value,
         total_equity,
-        starting_capital,
-        total_pnl,
-        total_pnl_percent,
+        starting_capital: wallet.starting_capital,
+        total_pnl: total_equity - wallet.starting_capital,
+        fees: wallet.fees,
+        total_pnl_percent: wallet.pnl_percent(total_equity),
+        realized_pnl,
+        unrealized_pnl,
     })
 }
 
 /// Get all paper positions with current values
 #[tauri::command]
-fn get_paper_positions(state: State<AppState>) -> Result<Vec<PaperPositionResponse>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_paper_positions(state: State<AppState>) -> Result<Vec<PaperPositionResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let positions = db.get_paper_positions().map_err(|e| e.to_string())?;
+    let positions = db.get_paper_positions().map_err(CommandError::from)?;
 
     let mut result = Vec::new();
     for pos in positions {
         let current_price = db
             .get_latest_price(&pos.symbol)
-            .map_err(|e| e.to_string())?
+            .map_err(CommandError::from)?
             .unwrap_or(pos.entry_price);
 
         let cost_basis = pos.quantity * pos.entry_price;
@@ -2386,6 +4869,8 @@ fn get_paper_positions(state: State<AppState>) -> Result<Vec<PaperPositionRespon
             quantity: pos.quantity,
             entry_price: pos.entry_price,
             entry_date: pos.entry_date,
+            stop_price: pos.stop_price,
+            take_price: pos.take_price,
             current_price,
             current_value,
             cost_basis,
@@ -2397,7 +4882,8 @@ fn get_paper_positions(state: State<AppState>) -> Result<Vec<PaperPositionRespon
     Ok(result)
 }
 
-/// Execute a paper trade
+/// Execute a paper trade. With `allow_partial` a BUY the cash can't fully cover fills
+/// the largest affordable whole-share quantity instead of failing.
 #[tauri::command]
 fn execute_paper_trade(
     state: State<AppState>,
@@ -2406,23 +4892,29 @@ fn execute_paper_trade(
     quantity: f64,
     price: Option<f64>,
     notes: Option<String>,
-) -> Result<PaperTradeResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let symbol = symbol.to_uppercase();
+    allow_partial: Option<bool>,
+) -> Result<PaperTradeResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = normalize_symbol(&symbol)
+        .ok_or_else(|| CommandError::invalid_input(format!("Invalid symbol: {}", symbol)))?;
 
     // Get current price if not provided
     let trade_price = match price {
         Some(p) => p,
         None => db
             .get_latest_price(&symbol)
-            .map_err(|e| e.to_string())?
-            .ok_or_else(|| format!("No price data for {}. Fetch prices first or specify price.", symbol))?,
+            .map_err(CommandError::from)?
+            .ok_or_else(|| {
+                CommandError::no_data(format!("No price data for {}. Fetch prices first or specify price.", symbol))
+            })?,
     };
 
     let trade_action = PaperTradeAction::from_str(&action);
 
-    let trade = db
-        .execute_paper_trade(
+    let trade = if allow_partial.unwrap_or(false) && trade_action == PaperTradeAction::Buy {
+        db.execute_paper_buy_affordable(&symbol, quantity, trade_price, None, notes.as_deref())
+    } else {
+        db.execute_paper_trade(
             &symbol,
             trade_action,
             quantity,
@@ -2430,7 +4922,8 @@ fn execute_paper_trade(
             None,
             notes.as_deref(),
         )
-        .map_err(|e| e.to_string())?;
+    }
+    .map_err(CommandError::from)?;
 
     println!(
         "[OK] Paper trade: {} {} {} @ ${:.2}",
@@ -2447,6 +4940,7 @@ fn execute_paper_trade(
         quantity: trade.quantity,
         price: trade.price,
         pnl: trade.pnl,
+        fee: trade.fee,
         timestamp: trade.timestamp,
         notes: trade.notes,
     })
@@ -2458,12 +4952,12 @@ fn get_paper_trades(
     state: State<AppState>,
     symbol: Option<String>,
     limit: Option<usize>,
-) -> Result<Vec<PaperTradeResponse>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<PaperTradeResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let trades = db
         .get_paper_trades(symbol.as_deref(), limit.unwrap_or(100))
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(trades
         .into_iter()
@@ -2474,22 +4968,226 @@ fn get_paper_trades(
             quantity: t.quantity,
             price: t.price,
             pnl: t.pnl,
+            fee: t.fee,
             timestamp: t.timestamp,
             notes: t.notes,
         })
         .collect())
 }
 
+/// Set or clear a paper position's stop-loss/take-profit prices
+#[tauri::command]
+fn set_paper_position_exits(
+    state: State<AppState>,
+    symbol: String,
+    stop_price: Option<f64>,
+    take_price: Option<f64>,
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    let found = db
+        .set_paper_position_exits(&symbol, stop_price, take_price)
+        .map_err(CommandError::from)?;
+
+    Ok(CommandResult {
+        success: found,
+        message: if found {
+            format!("Exit levels updated for {}", symbol)
+        } else {
+            format!("No open paper position in {}", symbol)
+        },
+    })
+}
+
+/// Close paper positions that hit their stop-loss or take-profit at the latest price.
+/// `stop_pct`/`take_pct` apply to positions without their own levels.
+#[tauri::command]
+fn check_paper_stops(
+    state: State<AppState>,
+    stop_pct: Option<f64>,
+    take_pct: Option<f64>,
+) -> Result<Vec<PaperTradeResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let trades = db
+        .check_paper_stops(stop_pct, take_pct)
+        .map_err(CommandError::from)?;
+
+    Ok(trades
+        .into_iter()
+        .map(|t| PaperTradeResponse {
+            id: t.id,
+            symbol: t.symbol,
+            action: t.action.as_str().to_string(),
+            quantity: t.quantity,
+            price: t.price,
+            pnl: t.pnl,
+            fee: t.fee,
+            timestamp: t.timestamp,
+            notes: t.notes,
+        })
+        .collect())
+}
+
+/// Paper order response
+#[derive(Serialize)]
+struct PaperOrderResponse {
+    id: i64,
+    symbol: String,
+    action: String,
+    order_type: String,
+    quantity: f64,
+    limit_price: Option<f64>,
+    stop_price: Option<f64>,
+    status: String,
+    paper_trade_id: Option<i64>,
+    created_at: String,
+    filled_at: Option<String>,
+}
+
+impl From<PaperOrder> for PaperOrderResponse {
+    fn from(o: PaperOrder) -> Self {
+        Self {
+            id: o.id,
+            symbol: o.symbol,
+            action: o.action.as_str().to_string(),
+            order_type: o.order_type.as_str().to_string(),
+            quantity: o.quantity,
+            limit_price: o.limit_price,
+            stop_price: o.stop_price,
+            status: o.status.as_str().to_string(),
+            paper_trade_id: o.paper_trade_id,
+            created_at: o.created_at,
+            filled_at: o.filled_at,
+        }
+    }
+}
+
+/// Queue a limit or stop paper order
+#[tauri::command]
+fn place_paper_order(
+    state: State<AppState>,
+    symbol: String,
+    action: String,
+    order_type: String,
+    quantity: f64,
+    limit_price: Option<f64>,
+    stop_price: Option<f64>,
+) -> Result<PaperOrderResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    let order_type = PaperOrderType::from_str(&order_type)
+        .ok_or_else(|| {
+            CommandError::invalid_input(format!("Invalid order type: {}. Must be LIMIT or STOP", order_type))
+        })?;
+
+    let order = db
+        .place_paper_order(
+            &symbol,
+            PaperTradeAction::from_str(&action),
+            order_type,
+            quantity,
+            limit_price,
+            stop_price,
+        )
+        .map_err(CommandError::from)?;
+
+    println!(
+        "[OK] Paper order #{}: {} {} {} {}",
+        order.id,
+        order.order_type.as_str(),
+        order.action.as_str(),
+        order.quantity,
+        order.symbol
+    );
+
+    Ok(order.into())
+}
+
+/// Cancel a pending paper order
+#[tauri::command]
+fn cancel_paper_order(state: State<AppState>, id: i64) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let cancelled = db.cancel_paper_order(id).map_err(CommandError::from)?;
+
+    Ok(CommandResult {
+        success: cancelled,
+        message: if cancelled {
+            format!("Order #{} cancelled", id)
+        } else {
+            format!("Order #{} is not pending", id)
+        },
+    })
+}
+
+/// Get paper orders, optionally only those with the given status
+#[tauri::command]
+fn get_paper_orders(
+    state: State<AppState>,
+    status: Option<String>,
+) -> Result<Vec<PaperOrderResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let orders = db
+        .get_paper_orders(status.as_deref().map(PaperOrderStatus::from_str))
+        .map_err(CommandError::from)?;
+
+    Ok(orders.into_iter().map(PaperOrderResponse::from).collect())
+}
+
+/// Fill any pending paper orders triggered by the latest stored prices
+#[tauri::command]
+fn process_paper_orders(state: State<AppState>) -> Result<Vec<PaperOrderResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let filled = db.process_pending_paper_orders().map_err(CommandError::from)?;
+
+    Ok(filled.into_iter().map(PaperOrderResponse::from).collect())
+}
+
+/// Get the cost basis method ("average" or "fifo") used for paper and DC sells
+#[tauri::command]
+fn get_cost_basis_method(state: State<AppState>) -> Result<String, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let method = db.get_cost_basis_method().map_err(CommandError::from)?;
+    Ok(method.as_str().to_string())
+}
+
+/// Set the cost basis method for subsequent paper and DC sells
+#[tauri::command]
+fn set_cost_basis_method(state: State<AppState>, method: String) -> Result<CommandResult, CommandError> {
+    let valid_methods = ["average", "fifo"];
+    if !valid_methods.contains(&method.to_lowercase().as_str()) {
+        return Err(CommandError::invalid_input(
+            format!("Invalid cost basis method: {}. Must be one of: {:?}", method, valid_methods),
+        ));
+    }
+
+    let db = state.db.get().map_err(CommandError::from)?;
+    let method = CostBasisMethod::from_str(&method);
+    db.set_cost_basis_method(method).map_err(CommandError::from)?;
+
+    println!("[OK] Cost basis method set to {}", method.as_str());
+
+    Ok(CommandResult {
+        success: true,
+        message: format!("Cost basis method set to {}", method.as_str()),
+    })
+}
+
 /// Reset paper trading account
 #[tauri::command]
 fn reset_paper_account(
     state: State<AppState>,
     starting_cash: Option<f64>,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let cash = starting_cash.unwrap_or(100000.0);
-    db.reset_paper_account(cash).map_err(|e| e.to_string())?;
+    db.reset_paper_account(cash).map_err(CommandError::from)?;
 
     Ok(CommandResult {
         success: true,
@@ -2508,8 +5206,13 @@ struct DcWalletResponse {
     positions_value: f64,
     total_equity: f64,
     starting_capital: f64,
+    fees: TradeFees,
     total_pnl: f64,
     total_pnl_percent: f64,
+    /// Booked on closed (sold) shares
+    realized_pnl: f64,
+    /// Open positions marked to market
+    unrealized_pnl: f64,
 }
 
 /// DC position with current price and P&L
@@ -2520,6 +5223,8 @@ struct DcPositionResponse {
     quantity: f64,
     entry_price: f64,
     entry_date: String,
+    stop_price: Option<f64>,
+    take_price: Option<f64>,
     current_price: f64,
     current_value: f64,
     cost_basis: f64,
@@ -2536,6 +5241,7 @@ struct DcTradeResponse {
     quantity: f64,
     price: f64,
     pnl: Option<f64>,
+    fee: f64,
     timestamp: String,
     notes: Option<String>,
 }
@@ -2567,6 +5273,8 @@ struct TeamConfigResponse {
     description: Option<String>,
     kalic_starting_capital: f64,
     dc_starting_capital: f64,
+    kalic_fees: TradeFees,
+    dc_fees: TradeFees,
     created_at: String,
 }
 
@@ -2578,54 +5286,54 @@ struct CompetitionStatsResponse {
     kalic_positions: f64,
     kalic_pnl_pct: f64,
     kalic_trades: i32,
+    kalic_fees_paid: f64,
     dc_total: f64,
     dc_cash: f64,
     dc_positions: f64,
     dc_pnl_pct: f64,
     dc_trades: i32,
+    dc_fees_paid: f64,
     leader: String,
     lead_amount: f64,
 }
 
 /// Get DC wallet balance and portfolio summary
 #[tauri::command]
-fn get_dc_balance(state: State<AppState>) -> Result<DcWalletResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_dc_balance(state: State<AppState>) -> Result<DcWalletResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let wallet = db.get_dc_wallet().map_err(|e| e.to_string())?;
+    let wallet = db.get_dc_wallet().map_err(CommandError::from)?;
     let (cash, positions_value, total_equity) = db
         .get_dc_portfolio_value()
-        .map_err(|e| e.to_string())?;
-
-    let total_pnl = total_equity - wallet.starting_capital;
-    let total_pnl_percent = if wallet.starting_capital > 0.0 {
-        (total_pnl / wallet.starting_capital) * 100.0
-    } else {
-        0.0
-    };
+        .map_err(CommandError::from)?;
+    let realized_pnl = db.get_dc_realized_pnl().map_err(CommandError::from)?;
+    let unrealized_pnl = db.get_dc_unrealized_pnl().map_err(CommandError::from)?;
 
     Ok(DcWalletResponse {
         cash,
         positions_value,
         total_equity,
         starting_capital: wallet.starting_capital,
-        total_pnl,
-        total_pnl_percent,
+        total_pnl: total_equity - wallet.starting_capital,
+        fees: wallet.fees,
+        total_pnl_percent: wallet.pnl_percent(total_equity),
+        realized_pnl,
+        unrealized_pnl,
     })
 }
 
 /// Get all DC positions with current values
 #[tauri::command]
-fn get_dc_positions(state: State<AppState>) -> Result<Vec<DcPositionResponse>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_dc_positions(state: State<AppState>) -> Result<Vec<DcPositionResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let positions = db.get_dc_positions().map_err(|e| e.to_string())?;
+    let positions = db.get_dc_positions().map_err(CommandError::from)?;
 
     let mut result = Vec::new();
     for pos in positions {
         let current_price = db
             .get_latest_price(&pos.symbol)
-            .map_err(|e| e.to_string())?
+            .map_err(CommandError::from)?
             .unwrap_or(pos.entry_price);
 
         let cost_basis = pos.quantity * pos.entry_price;
@@ -2643,6 +5351,8 @@ fn get_dc_positions(state: State<AppState>) -> Result<Vec<DcPositionResponse>, S
             quantity: pos.quantity,
             entry_price: pos.entry_price,
             entry_date: pos.entry_date,
+            stop_price: pos.stop_price,
+            take_price: pos.take_price,
             current_price,
             current_value,
             cost_basis,
@@ -2654,7 +5364,7 @@ fn get_dc_positions(state: State<AppState>) -> Result<Vec<DcPositionResponse>, S
     Ok(result)
 }
 
-/// Execute a DC trade
+/// Execute a DC trade (`allow_partial` as for paper trades)
 #[tauri::command]
 fn execute_dc_trade(
     state: State<AppState>,
@@ -2663,28 +5373,35 @@ fn execute_dc_trade(
     quantity: f64,
     price: Option<f64>,
     notes: Option<String>,
-) -> Result<DcTradeResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let symbol = symbol.to_uppercase();
+    allow_partial: Option<bool>,
+) -> Result<DcTradeResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = normalize_symbol(&symbol)
+        .ok_or_else(|| CommandError::invalid_input(format!("Invalid symbol: {}", symbol)))?;
 
     // Get current price if not provided
     let trade_price = match price {
         Some(p) => p,
         None => db
             .get_latest_price(&symbol)
-            .map_err(|e| e.to_string())?
-            .ok_or_else(|| format!("No price data for {}. Fetch prices first or specify price.", symbol))?,
+            .map_err(CommandError::from)?
+            .ok_or_else(|| {
+                CommandError::no_data(format!("No price data for {}. Fetch prices first or specify price.", symbol))
+            })?,
     };
 
-    let trade = db
-        .execute_dc_trade(
+    let trade = if allow_partial.unwrap_or(false) && action.eq_ignore_ascii_case("BUY") {
+        db.execute_dc_buy_affordable(&symbol, quantity, trade_price, notes.as_deref())
+    } else {
+        db.execute_dc_trade(
             &symbol,
             &action,
             quantity,
             trade_price,
             notes.as_deref(),
         )
-        .map_err(|e| e.to_string())?;
+    }
+    .map_err(CommandError::from)?;
 
     println!(
         "[OK] DC trade: {} {} {} @ ${:.2}",
@@ -2701,6 +5418,7 @@ fn execute_dc_trade(
         quantity: trade.quantity,
         price: trade.price,
         pnl: trade.pnl,
+        fee: trade.fee,
         timestamp: trade.timestamp,
         notes: trade.notes,
     })
@@ -2711,12 +5429,66 @@ fn execute_dc_trade(
 fn get_dc_trades(
     state: State<AppState>,
     limit: Option<usize>,
-) -> Result<Vec<DcTradeResponse>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<DcTradeResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let trades = db
         .get_dc_trades(limit.unwrap_or(100))
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
+
+    Ok(trades
+        .into_iter()
+        .map(|t| DcTradeResponse {
+            id: t.id,
+            symbol: t.symbol,
+            action: t.action,
+            quantity: t.quantity,
+            price: t.price,
+            pnl: t.pnl,
+            fee: t.fee,
+            timestamp: t.timestamp,
+            notes: t.notes,
+        })
+        .collect())
+}
+
+/// Set or clear a DC position's stop-loss/take-profit prices
+#[tauri::command]
+fn set_dc_position_exits(
+    state: State<AppState>,
+    symbol: String,
+    stop_price: Option<f64>,
+    take_price: Option<f64>,
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let symbol = symbol.to_uppercase();
+
+    let found = db
+        .set_dc_position_exits(&symbol, stop_price, take_price)
+        .map_err(CommandError::from)?;
+
+    Ok(CommandResult {
+        success: found,
+        message: if found {
+            format!("Exit levels updated for {}", symbol)
+        } else {
+            format!("No open DC position in {}", symbol)
+        },
+    })
+}
+
+/// Close DC positions that hit their stop-loss or take-profit at the latest price
+#[tauri::command]
+fn check_dc_stops(
+    state: State<AppState>,
+    stop_pct: Option<f64>,
+    take_pct: Option<f64>,
+) -> Result<Vec<DcTradeResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let trades = db
+        .check_dc_stops(stop_pct, take_pct)
+        .map_err(CommandError::from)?;
 
     Ok(trades
         .into_iter()
@@ -2727,22 +5499,36 @@ fn get_dc_trades(
             quantity: t.quantity,
             price: t.price,
             pnl: t.pnl,
+            fee: t.fee,
             timestamp: t.timestamp,
             notes: t.notes,
         })
         .collect())
 }
 
+/// ATR-based stop suggestions for a team's long positions ("KALIC", "DC" or "PORTFOLIO")
+#[tauri::command]
+fn get_suggested_stops(
+    state: State<AppState>,
+    atr_multiple: f64,
+    team: String,
+) -> Result<Vec<StopSuggestion>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let team = team.to_uppercase();
+
+    db.suggest_stops(&team, atr_multiple).map_err(CommandError::from)
+}
+
 /// Reset DC trading account
 #[tauri::command]
 fn reset_dc_account(
     state: State<AppState>,
     starting_cash: Option<f64>,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let cash = starting_cash.unwrap_or(1000000.0);
-    db.reset_dc_account(cash).map_err(|e| e.to_string())?;
+    db.reset_dc_account(cash).map_err(CommandError::from)?;
 
     Ok(CommandResult {
         success: true,
@@ -2756,10 +5542,10 @@ fn import_dc_trades_csv(
     state: State<AppState>,
     #[allow(non_snake_case)]
     csvContent: String,
-) -> Result<ImportResultResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<ImportResultResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let result = db.import_dc_trades_csv(&csvContent).map_err(|e| e.to_string())?;
+    let result = db.import_dc_trades_csv(&csvContent).map_err(CommandError::from)?;
 
     Ok(ImportResultResponse {
         success_count: result.success_count,
@@ -2768,16 +5554,57 @@ fn import_dc_trades_csv(
     })
 }
 
+/// Export a team's trade log ("KALIC" or "DC") as CSV to a user-chosen path
+#[tauri::command]
+fn export_trades_csv(
+    state: State<AppState>,
+    team: String,
+    path: String,
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let team = team.to_uppercase();
+
+    let csv = db.export_trades_csv(&team).map_err(CommandError::from)?;
+    std::fs::write(&path, csv).map_err(PipelineError::from)?;
+
+    println!("[OK] Exported {} trades to {}", team, path);
+
+    Ok(CommandResult {
+        success: true,
+        message: format!("Exported {} trades to {}", team, path),
+    })
+}
+
+/// Export a team's trade log as JSON (re-importable as DC trades).
+/// Also written to `path` when one is given.
+#[tauri::command]
+fn export_trades_json(
+    state: State<AppState>,
+    team: String,
+    path: Option<String>,
+) -> Result<String, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let team = team.to_uppercase();
+
+    let json = db.export_trades_json(&team).map_err(CommandError::from)?;
+    if let Some(path) = &path {
+        std::fs::write(path, &json).map_err(PipelineError::from)?;
+        println!("[OK] Exported {} trades to {}", team, path);
+    }
+
+    Ok(json)
+}
+
 /// Import DC trades from JSON
 #[tauri::command]
 fn import_dc_trades_json(
     state: State<AppState>,
     #[allow(non_snake_case)]
     jsonContent: String,
-) -> Result<ImportResultResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<ImportResultResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let result = db.import_dc_trades_json(&jsonContent).map_err(|e| e.to_string())?;
+    let result = db.import_dc_trades_json(&jsonContent).map_err(CommandError::from)?;
 
     Ok(ImportResultResponse {
         success_count: result.success_count,
@@ -2791,13 +5618,13 @@ fn import_dc_trades_json(
 fn lookup_current_price(
     state: State<AppState>,
     symbol: String,
-) -> Result<f64, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<f64, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let symbol = symbol.to_uppercase();
 
     db.get_latest_price(&symbol)
-        .map_err(|e| e.to_string())?
-        .ok_or_else(|| format!("No price data for {}", symbol))
+        .map_err(CommandError::from)?
+        .ok_or_else(|| CommandError::no_data(format!("No price data for {}", symbol)))
 }
 
 /// Record portfolio snapshot for a team
@@ -2805,10 +5632,10 @@ fn lookup_current_price(
 fn record_portfolio_snapshot(
     state: State<AppState>,
     team: String,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    db.record_portfolio_snapshot(&team).map_err(|e| e.to_string())?;
+    db.record_portfolio_snapshot(&team).map_err(CommandError::from)?;
 
     Ok(CommandResult {
         success: true,
@@ -2816,18 +5643,36 @@ fn record_portfolio_snapshot(
     })
 }
 
+/// Days reconstructed by `backfill_snapshots` when none is given
+const DEFAULT_BACKFILL_DAYS: i64 = 90;
+
+/// Reconstruct missing KALIC, DC and portfolio snapshots from stored prices
+#[tauri::command]
+fn backfill_snapshots(state: State<AppState>, days: Option<i64>) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+
+    let days = days.unwrap_or(DEFAULT_BACKFILL_DAYS);
+    let written = db.backfill_snapshots(days).map_err(CommandError::from)?;
+    println!("[OK] Backfilled {} snapshot(s) over {} days", written, days);
+
+    Ok(CommandResult {
+        success: true,
+        message: format!("Backfilled {} snapshot(s) over the last {} days", written, days),
+    })
+}
+
 /// Get portfolio snapshots for charting
 #[tauri::command]
 fn get_portfolio_snapshots(
     state: State<AppState>,
     team: Option<String>,
     days: Option<i32>,
-) -> Result<Vec<PortfolioSnapshotResponse>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<PortfolioSnapshotResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let snapshots = db
         .get_portfolio_snapshots(team.as_deref(), days.unwrap_or(30))
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(snapshots
         .into_iter()
@@ -2848,11 +5693,11 @@ fn save_team_config(
     state: State<AppState>,
     name: String,
     description: Option<String>,
-) -> Result<i64, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<i64, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     db.save_team_config(&name, description.as_deref())
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
 /// Load team configuration
@@ -2860,10 +5705,10 @@ fn save_team_config(
 fn load_team_config(
     state: State<AppState>,
     name: String,
-) -> Result<TeamConfigResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<TeamConfigResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let config = db.load_team_config(&name).map_err(|e| e.to_string())?;
+    let config = db.load_team_config(&name).map_err(CommandError::from)?;
 
     Ok(TeamConfigResponse {
         id: config.id,
@@ -2871,16 +5716,18 @@ fn load_team_config(
         description: config.description,
         kalic_starting_capital: config.kalic_starting_capital,
         dc_starting_capital: config.dc_starting_capital,
+        kalic_fees: config.kalic_fees,
+        dc_fees: config.dc_fees,
         created_at: config.created_at,
     })
 }
 
 /// List all team configurations
 #[tauri::command]
-fn list_team_configs(state: State<AppState>) -> Result<Vec<TeamConfigResponse>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn list_team_configs(state: State<AppState>) -> Result<Vec<TeamConfigResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let configs = db.list_team_configs().map_err(|e| e.to_string())?;
+    let configs = db.list_team_configs().map_err(CommandError::from)?;
 
     Ok(configs
         .into_iter()
@@ -2890,6 +5737,8 @@ fn list_team_configs(state: State<AppState>) -> Result<Vec<TeamConfigResponse>,
             description: c.description,
             kalic_starting_capital: c.kalic_starting_capital,
             dc_starting_capital: c.dc_starting_capital,
+            kalic_fees: c.kalic_fees,
+            dc_fees: c.dc_fees,
             created_at: c.created_at,
         })
         .collect())
@@ -2897,10 +5746,10 @@ fn list_team_configs(state: State<AppState>) -> Result<Vec<TeamConfigResponse>,
 
 /// Get competition stats
 #[tauri::command]
-fn get_competition_stats(state: State<AppState>) -> Result<CompetitionStatsResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn get_competition_stats(state: State<AppState>) -> Result<CompetitionStatsResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let stats = db.get_competition_stats().map_err(|e| e.to_string())?;
+    let stats = db.get_competition_stats().map_err(CommandError::from)?;
 
     Ok(CompetitionStatsResponse {
         kalic_total: stats.kalic_total,
@@ -2908,16 +5757,37 @@ fn get_competition_stats(state: State<AppState>) -> Result<CompetitionStatsRespo
         kalic_positions: stats.kalic_positions,
         kalic_pnl_pct: stats.kalic_pnl_pct,
         kalic_trades: stats.kalic_trades,
+        kalic_fees_paid: stats.kalic_fees_paid,
         dc_total: stats.dc_total,
         dc_cash: stats.dc_cash,
         dc_positions: stats.dc_positions,
         dc_pnl_pct: stats.dc_pnl_pct,
         dc_trades: stats.dc_trades,
+        dc_fees_paid: stats.dc_fees_paid,
         leader: stats.leader,
         lead_amount: stats.lead_amount,
     })
 }
 
+/// Set the commission a team ("KALIC" or "DC") pays on each trade; zero for both is commission-free
+#[tauri::command]
+fn set_trade_fees(
+    state: State<AppState>,
+    team: String,
+    per_trade_fee: f64,
+    fee_percent: f64,
+) -> Result<(), CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let fees = TradeFees { per_trade_fee, fee_percent };
+
+    match team.to_uppercase().as_str() {
+        "KALIC" => db.set_paper_fees(&fees),
+        "DC" => db.set_dc_fees(&fees),
+        _ => return Err(CommandError::invalid_input(format!("Invalid team: {}", team))),
+    }
+    .map_err(CommandError::from)
+}
+
 // ============================================================================
 // AI TRADER COMMANDS
 // ============================================================================
@@ -2972,6 +5842,7 @@ struct AiDecisionResponse {
     actual_price_at_timeframe: Option<f64>,
     prediction_accurate: Option<bool>,
     paper_trade_id: Option<i64>,
+    dry_run: bool,
 }
 
 impl From<AiTradeDecision> for AiDecisionResponse {
@@ -2994,6 +5865,7 @@ impl From<AiTradeDecision> for AiDecisionResponse {
             actual_price_at_timeframe: d.actual_price_at_timeframe,
             prediction_accurate: d.prediction_accurate,
             paper_trade_id: d.paper_trade_id,
+            dry_run: d.dry_run,
         }
     }
 }
@@ -3090,20 +5962,22 @@ struct AiConfigResponse {
     max_position_size_percent: f64,
     stop_loss_percent: f64,
     take_profit_percent: f64,
+    risk_per_trade_percent: f64,
     session_duration_minutes: u32,
     benchmark_symbol: String,
     model_priority: Vec<String>,
+    allow_partial_fills: bool,
 }
 
 /// Get AI trader status
 #[tauri::command]
-fn ai_trader_get_status(state: State<AppState>) -> Result<AiStatusResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn ai_trader_get_status(state: State<AppState>) -> Result<AiStatusResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let config = db.get_ai_trader_config().map_err(|e| e.to_string())?;
+    let config = db.get_ai_trader_config().map_err(CommandError::from)?;
     let trader = AiTrader::new(config);
 
-    let status = trader.get_status(&db).map_err(|e| e.to_string())?;
+    let status = trader.get_status(&db).map_err(CommandError::from)?;
 
     Ok(AiStatusResponse {
         is_running: status.is_running,
@@ -3120,31 +5994,33 @@ fn ai_trader_get_status(state: State<AppState>) -> Result<AiStatusResponse, Stri
 
 /// Get AI trader configuration
 #[tauri::command]
-fn ai_trader_get_config(state: State<AppState>) -> Result<AiConfigResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn ai_trader_get_config(state: State<AppState>) -> Result<AiConfigResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let config = db.get_ai_trader_config().map_err(|e| e.to_string())?;
+    let config = db.get_ai_trader_config().map_err(CommandError::from)?;
 
     Ok(AiConfigResponse {
         starting_capital: config.starting_capital,
         max_position_size_percent: config.max_position_size_percent,
         stop_loss_percent: config.stop_loss_percent,
         take_profit_percent: config.take_profit_percent,
+        risk_per_trade_percent: config.risk_per_trade_percent,
         session_duration_minutes: config.session_duration_minutes,
         benchmark_symbol: config.benchmark_symbol,
         model_priority: config.model_priority,
+        allow_partial_fills: config.allow_partial_fills,
     })
 }
 
 /// Start a new AI trading session
 #[tauri::command]
-fn ai_trader_start_session(state: State<AppState>) -> Result<AiSessionResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn ai_trader_start_session(state: State<AppState>) -> Result<AiSessionResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let config = db.get_ai_trader_config().map_err(|e| e.to_string())?;
+    let config = db.get_ai_trader_config().map_err(CommandError::from)?;
     let trader = AiTrader::new(config);
 
-    let session = trader.start_session(&db).map_err(|e| e.to_string())?;
+    let session = trader.start_session(&db).map_err(CommandError::from)?;
 
     println!("[AI Trader] Session {} started", session.id);
 
@@ -3156,15 +6032,15 @@ fn ai_trader_start_session(state: State<AppState>) -> Result<AiSessionResponse,
 fn ai_trader_end_session(
     state: State<AppState>,
     notes: Option<String>,
-) -> Result<Option<AiSessionResponse>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<Option<AiSessionResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let config = db.get_ai_trader_config().map_err(|e| e.to_string())?;
+    let config = db.get_ai_trader_config().map_err(CommandError::from)?;
     let trader = AiTrader::new(config);
 
     let session = trader
         .end_session(&db, notes.as_deref())
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     if let Some(ref s) = session {
         println!("[AI Trader] Session {} ended", s.id);
@@ -3173,29 +6049,113 @@ fn ai_trader_end_session(
     Ok(session.map(|s| s.into()))
 }
 
-/// Run one AI trading cycle (gather context, query AI, execute trades)
+/// Run one AI trading cycle (gather context, query AI, execute trades).
+/// A dry run records the decisions without executing any trades.
 #[tauri::command]
-async fn ai_trader_run_cycle() -> Result<Vec<AiDecisionResponse>, String> {
-    // Open a separate database connection for async operations
-    // This is necessary because MutexGuard can't be held across await points
-    let db_path = get_data_path("finance.db");
-    let mut db = Database::open(&db_path).map_err(|e| e.to_string())?;
+async fn ai_trader_run_cycle(
+    state: State<'_, AppState>,
+    dry_run: Option<bool>,
+) -> Result<Vec<AiDecisionResponse>, CommandError> {
+    let dry_run = dry_run.unwrap_or(false);
+    // A pooled connection is held for the whole cycle without blocking other commands
+    let mut db = state.db.get().map_err(CommandError::from)?;
 
-    let config = db.get_ai_trader_config().map_err(|e| e.to_string())?;
-    let trader = AiTrader::new(config);
+    let config = db.get_ai_trader_config().map_err(CommandError::from)?;
+    let mut trader = AiTrader::new(config);
 
     // Check if Ollama is available
     if !trader.check_ollama().await {
-        return Err("Ollama is not available. Start it with: ollama serve".to_string());
+        return Err(CommandError::unavailable(
+            "Ollama is not available. Start it with: ollama serve",
+        ));
     }
 
-    let decisions = trader.run_cycle(&mut db).await.map_err(|e| e.to_string())?;
+    let decisions = trader.run_cycle(&mut db, dry_run).await.map_err(CommandError::from)?;
 
-    println!("[AI Trader] Cycle completed with {} decisions", decisions.len());
+    println!(
+        "[AI Trader] {} completed with {} decisions",
+        if dry_run { "Dry-run cycle" } else { "Cycle" },
+        decisions.len()
+    );
 
     Ok(decisions.into_iter().map(|d| d.into()).collect())
 }
 
+/// Replay result for frontend
+#[derive(Serialize)]
+struct AiReplayResponse {
+    symbol: String,
+    start_date: String,
+    end_date: String,
+    initial_capital: f64,
+    final_value: f64,
+    decisions_count: usize,
+    metrics: MetricsData,
+    trades: Vec<BacktestTradeData>,
+    equity_curve: Vec<(String, f64)>,
+}
+
+impl From<AiReplayResult> for AiReplayResponse {
+    fn from(r: AiReplayResult) -> Self {
+        Self {
+            symbol: r.symbol,
+            start_date: r.start_date.to_string(),
+            end_date: r.end_date.to_string(),
+            initial_capital: r.initial_capital,
+            final_value: r.final_value,
+            decisions_count: r.decisions_count,
+            metrics: r.metrics.into(),
+            trades: r.trades.into_iter().map(|t| t.into()).collect(),
+            equity_curve: r
+                .equity_curve
+                .into_iter()
+                .map(|(d, v)| (d.to_string(), v))
+                .collect(),
+        }
+    }
+}
+
+/// Replay the AI trader's decision loop over a past window (simulated, paper account untouched)
+#[tauri::command]
+async fn ai_trader_replay(
+    state: State<'_, AppState>,
+    symbol: String,
+    from_date: String,
+    to_date: String,
+) -> Result<AiReplayResponse, CommandError> {
+    let from = NaiveDate::parse_from_str(&from_date, "%Y-%m-%d")
+        .map_err(|e| CommandError::invalid_input(e.to_string()))?;
+    let to = NaiveDate::parse_from_str(&to_date, "%Y-%m-%d")
+        .map_err(|e| CommandError::invalid_input(e.to_string()))?;
+    if from > to {
+        return Err(CommandError::invalid_input("from_date must be on or before to_date"));
+    }
+
+    let mut db = state.db.get().map_err(CommandError::from)?;
+
+    let config = db.get_ai_trader_config().map_err(CommandError::from)?;
+    let trader = AiTrader::new(config);
+
+    if !trader.check_ollama().await {
+        return Err(CommandError::unavailable(
+            "Ollama is not available. Start it with: ollama serve",
+        ));
+    }
+
+    let symbol = symbol.to_uppercase();
+    let result = trader
+        .replay(&mut db, &symbol, from, to, &mut ModelDecisions::new(&trader))
+        .await
+        .map_err(CommandError::from)?;
+
+    println!(
+        "[AI Trader] Replay {} {}..{}: {:.2} -> {:.2}",
+        symbol, result.start_date, result.end_date, result.initial_capital, result.final_value
+    );
+
+    Ok(result.into())
+}
+
 /// Get AI trading decisions
 #[tauri::command]
 fn ai_trader_get_decisions(
@@ -3203,12 +6163,12 @@ fn ai_trader_get_decisions(
     session_id: Option<i64>,
     symbol: Option<String>,
     limit: Option<usize>,
-) -> Result<Vec<AiDecisionResponse>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<AiDecisionResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let decisions = db
         .get_ai_decisions(session_id, symbol.as_deref(), limit.unwrap_or(100))
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(decisions.into_iter().map(|d| d.into()).collect())
 }
@@ -3218,27 +6178,28 @@ fn ai_trader_get_decisions(
 fn ai_trader_get_performance_history(
     state: State<AppState>,
     days: Option<u32>,
-) -> Result<Vec<AiSnapshotResponse>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<AiSnapshotResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let snapshots = db
         .get_ai_performance_snapshots(days.unwrap_or(30))
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(snapshots.into_iter().map(|s| s.into()).collect())
 }
 
 /// Get benchmark comparison (portfolio vs SPY)
 #[tauri::command]
-fn ai_trader_get_benchmark_comparison(state: State<AppState>) -> Result<AiBenchmarkResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn ai_trader_get_benchmark_comparison(state: State<AppState>) -> Result<AiBenchmarkResponse, CommandError> {
+    let mut db = state.db.get().map_err(CommandError::from)?;
 
-    let config = db.get_ai_trader_config().map_err(|e| e.to_string())?;
+    let config = db.get_ai_trader_config().map_err(CommandError::from)?;
     let trader = AiTrader::new(config);
+    let yahoo = YahooFinance::new().with_rate_limiter(state.rate_limiter.clone());
 
     let comparison = trader
-        .get_benchmark_comparison(&db)
-        .map_err(|e| e.to_string())?;
+        .get_benchmark_comparison(&mut db, &yahoo)
+        .map_err(CommandError::from)?;
 
     Ok(AiBenchmarkResponse {
         portfolio_return_percent: comparison.portfolio_return_percent,
@@ -3248,17 +6209,42 @@ fn ai_trader_get_benchmark_comparison(state: State<AppState>) -> Result<AiBenchm
     })
 }
 
+/// Change the symbol the AI trader is compared against. Symbols without stored
+/// prices are fetched first, so an unknown ticker is rejected here.
+#[tauri::command]
+fn ai_trader_set_benchmark(state: State<AppState>, symbol: String) -> Result<CommandResult, CommandError> {
+    let symbol = normalize_symbol(&symbol)
+        .ok_or_else(|| CommandError::invalid_input(format!("Invalid symbol: {}", symbol)))?;
+    let mut db = state.db.get().map_err(CommandError::from)?;
+
+    if db.get_latest_price(&symbol).map_err(CommandError::from)?.is_none() {
+        YahooFinance::new()
+            .with_rate_limiter(state.rate_limiter.clone())
+            .fetch_and_store(&mut db, &symbol, "1y")
+            .map_err(|e| CommandError::invalid_input(format!("Could not fetch benchmark {}: {}", symbol, e)))?;
+    }
+
+    let mut config = db.get_ai_trader_config().map_err(CommandError::from)?;
+    config.benchmark_symbol = symbol.clone();
+    db.update_ai_trader_config(&config).map_err(CommandError::from)?;
+
+    Ok(CommandResult {
+        success: true,
+        message: format!("Benchmark set to {}", symbol),
+    })
+}
+
 /// Get compounding forecast
 #[tauri::command]
-fn ai_trader_get_compounding_forecast(state: State<AppState>) -> Result<AiForecastResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn ai_trader_get_compounding_forecast(state: State<AppState>) -> Result<AiForecastResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let config = db.get_ai_trader_config().map_err(|e| e.to_string())?;
+    let config = db.get_ai_trader_config().map_err(CommandError::from)?;
     let trader = AiTrader::new(config);
 
     let forecast = trader
         .get_compounding_forecast(&db)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(AiForecastResponse {
         current_daily_return: forecast.current_daily_return,
@@ -3273,10 +6259,10 @@ fn ai_trader_get_compounding_forecast(state: State<AppState>) -> Result<AiForeca
 
 /// Get AI prediction accuracy
 #[tauri::command]
-fn ai_trader_get_prediction_accuracy(state: State<AppState>) -> Result<AiAccuracyResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+fn ai_trader_get_prediction_accuracy(state: State<AppState>) -> Result<AiAccuracyResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
-    let accuracy = db.get_ai_prediction_accuracy().map_err(|e| e.to_string())?;
+    let accuracy = db.get_ai_prediction_accuracy().map_err(CommandError::from)?;
 
     Ok(AiAccuracyResponse {
         total_predictions: accuracy.total_predictions,
@@ -3287,15 +6273,15 @@ fn ai_trader_get_prediction_accuracy(state: State<AppState>) -> Result<AiAccurac
 
 /// Evaluate pending AI predictions that have reached their timeframe
 #[tauri::command]
-fn ai_trader_evaluate_predictions(state: State<AppState>) -> Result<u32, String> {
-    let mut db = state.db.lock().map_err(|e| e.to_string())?;
+fn ai_trader_evaluate_predictions(state: State<AppState>) -> Result<u32, CommandError> {
+    let mut db = state.db.get().map_err(CommandError::from)?;
 
-    let config = db.get_ai_trader_config().map_err(|e| e.to_string())?;
+    let config = db.get_ai_trader_config().map_err(CommandError::from)?;
     let trader = AiTrader::new(config);
 
     let evaluated = trader
         .evaluate_predictions(&mut db)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     if evaluated > 0 {
         println!("[AI Trader] Evaluated {} predictions", evaluated);
@@ -3309,11 +6295,11 @@ fn ai_trader_evaluate_predictions(state: State<AppState>) -> Result<u32, String>
 fn ai_trader_reset(
     state: State<AppState>,
     starting_capital: Option<f64>,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     let capital = starting_capital.unwrap_or(1_000_000.0);
-    db.reset_ai_trading(capital).map_err(|e| e.to_string())?;
+    db.reset_ai_trading(capital).map_err(CommandError::from)?;
 
     println!("[AI Trader] Reset with ${:.0} starting capital", capital);
 
@@ -3343,8 +6329,9 @@ struct CircuitBreakerResponse {
     daily_loss_threshold: f64,
     consecutive_loss_limit: i32,
     auto_conservative_on_trigger: bool,
-    // Current state (from runtime, not DB)
     is_triggered: bool,
+    /// When a tripped breaker lets trading resume (RFC 3339)
+    resume_at: Option<String>,
 }
 
 /// Response for trade rejection
@@ -3360,9 +6347,9 @@ struct TradeRejectionResponse {
 
 /// Get current trading mode and guardrails
 #[tauri::command]
-fn ai_trader_get_mode(state: State<AppState>) -> Result<TradingModeResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let config = db.get_ai_trader_config().map_err(|e| e.to_string())?;
+fn ai_trader_get_mode(state: State<AppState>) -> Result<TradingModeResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let config = db.get_ai_trader_config().map_err(CommandError::from)?;
 
     Ok(TradingModeResponse {
         mode: config.trading_mode,
@@ -3378,14 +6365,16 @@ fn ai_trader_get_mode(state: State<AppState>) -> Result<TradingModeResponse, Str
 fn ai_trader_switch_mode(
     state: State<AppState>,
     mode: String,
-) -> Result<CommandResult, String> {
+) -> Result<CommandResult, CommandError> {
     let valid_modes = ["aggressive", "normal", "conservative", "paused"];
     if !valid_modes.contains(&mode.as_str()) {
-        return Err(format!("Invalid mode: {}. Must be one of: {:?}", mode, valid_modes));
+        return Err(CommandError::invalid_input(
+            format!("Invalid mode: {}. Must be one of: {:?}", mode, valid_modes),
+        ));
     }
 
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    db.update_trading_mode(&mode).map_err(|e| e.to_string())?;
+    let db = state.db.get().map_err(CommandError::from)?;
+    db.update_trading_mode(&mode).map_err(CommandError::from)?;
 
     println!("[AI Trader] Mode switched to: {}", mode);
 
@@ -3397,15 +6386,19 @@ fn ai_trader_switch_mode(
 
 /// Get circuit breaker settings
 #[tauri::command]
-fn ai_trader_get_circuit_breaker(state: State<AppState>) -> Result<CircuitBreakerResponse, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
-    let config = db.get_ai_trader_config().map_err(|e| e.to_string())?;
+fn ai_trader_get_circuit_breaker(state: State<AppState>) -> Result<CircuitBreakerResponse, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
+    let config = db.get_ai_trader_config().map_err(CommandError::from)?;
+    let (triggered, until) = db.get_circuit_breaker_state().map_err(CommandError::from)?;
+    // An elapsed pause is cleared on the next cycle, so it no longer counts as triggered
+    let is_triggered = triggered && until.is_some_and(|t| t > Utc::now());
 
     Ok(CircuitBreakerResponse {
         daily_loss_threshold: config.daily_loss_threshold,
         consecutive_loss_limit: config.consecutive_loss_limit,
         auto_conservative_on_trigger: config.auto_conservative_on_trigger,
-        is_triggered: false, // Would need runtime state to know this
+        is_triggered,
+        resume_at: until.filter(|_| is_triggered).map(|t| t.to_rfc3339()),
     })
 }
 
@@ -3416,11 +6409,11 @@ fn ai_trader_update_circuit_breaker(
     daily_loss_threshold: f64,
     consecutive_loss_limit: i32,
     auto_conservative: bool,
-) -> Result<CommandResult, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<CommandResult, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
 
     db.update_circuit_breaker_settings(daily_loss_threshold, consecutive_loss_limit, auto_conservative)
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     println!("[AI Trader] Circuit breaker updated: threshold={:.1}%, consecutive_limit={}, auto_conservative={}",
         daily_loss_threshold, consecutive_loss_limit, auto_conservative);
@@ -3436,10 +6429,10 @@ fn ai_trader_update_circuit_breaker(
 fn ai_trader_get_rejections(
     state: State<AppState>,
     limit: Option<usize>,
-) -> Result<Vec<TradeRejectionResponse>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<TradeRejectionResponse>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let rejections = db.get_trade_rejections(limit.unwrap_or(20))
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(rejections.into_iter().map(|(id, timestamp, action, symbol, reason, rule)| {
         TradeRejectionResponse {
@@ -3453,15 +6446,58 @@ fn ai_trader_get_rejections(
     }).collect())
 }
 
+/// Guardrail verdict for a previewed trade
+#[derive(Serialize)]
+struct DryRunTradeResponse {
+    allowed: bool,
+    reason: Option<String>,
+    rule_triggered: Option<String>,
+    estimated_value: f64,
+    quantity_percent: f64,
+}
+
+/// Check whether a trade would pass the AI trader's guardrails, without executing it
+#[tauri::command]
+fn ai_trader_dry_run_trade(
+    state: State<AppState>,
+    action: String,
+    symbol: String,
+    quantity: f64,
+) -> Result<DryRunTradeResponse, CommandError> {
+    if !matches!(action.to_uppercase().as_str(), "BUY" | "SELL") {
+        return Err(CommandError::invalid_input(format!("Unknown action: {}", action)));
+    }
+    if quantity <= 0.0 {
+        return Err(CommandError::invalid_input("Quantity must be positive"));
+    }
+
+    let db = state.db.get().map_err(CommandError::from)?;
+    let config = db.get_ai_trader_config().map_err(CommandError::from)?;
+    let mut trader = AiTrader::new(config);
+    trader.load_circuit_breaker_state(&db).map_err(CommandError::from)?;
+
+    let (proposed, rejection) = trader
+        .dry_run_trade(&db, &action, &symbol.to_uppercase(), quantity)
+        .map_err(CommandError::from)?;
+
+    Ok(DryRunTradeResponse {
+        allowed: rejection.is_none(),
+        reason: rejection.as_ref().map(|r| r.reason.clone()),
+        rule_triggered: rejection.map(|r| r.rule_triggered),
+        estimated_value: proposed.estimated_value,
+        quantity_percent: proposed.quantity_percent,
+    })
+}
+
 /// Get circuit breaker event history
 #[tauri::command]
 fn ai_trader_get_circuit_breaker_events(
     state: State<AppState>,
     limit: Option<usize>,
-) -> Result<Vec<serde_json::Value>, String> {
-    let db = state.db.lock().map_err(|e| e.to_string())?;
+) -> Result<Vec<serde_json::Value>, CommandError> {
+    let db = state.db.get().map_err(CommandError::from)?;
     let events = db.get_circuit_breaker_events(limit.unwrap_or(10))
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
 
     Ok(events.into_iter().map(|(id, timestamp, trigger_type, prev_mode, new_mode, daily_pnl)| {
         serde_json::json!({
@@ -3478,13 +6514,15 @@ fn ai_trader_get_circuit_breaker_events(
 /// Open a URL in a lightweight Tauri webview window
 /// Security: Only HTTPS allowed, JavaScript sandboxed, reuses single window to save RAM
 #[tauri::command]
-async fn open_article_window(app: tauri::AppHandle, url: String, title: String) -> Result<(), String> {
+async fn open_article_window(app: tauri::AppHandle, url: String, title: String) -> Result<(), CommandError> {
     use tauri::{WebviewUrl, WebviewWindowBuilder, Manager};
 
     // SECURITY: Only allow HTTPS URLs (no HTTP, no file://, no javascript:, etc.)
-    let parsed_url: url::Url = url.parse().map_err(|e| format!("Invalid URL: {}", e))?;
+    let parsed_url: url::Url = url
+        .parse()
+        .map_err(|e| CommandError::invalid_input(format!("Invalid URL: {}", e)))?;
     if parsed_url.scheme() != "https" {
-        return Err("Only HTTPS URLs are allowed for security".to_string());
+        return Err(CommandError::invalid_input("Only HTTPS URLs are allowed for security"));
     }
 
     // SECURITY: Block potentially dangerous domains (can expand this list)
@@ -3492,7 +6530,7 @@ async fn open_article_window(app: tauri::AppHandle, url: String, title: String)
     let blocked_patterns = ["localhost", "127.0.0.1", "0.0.0.0", "file://"];
     for pattern in blocked_patterns {
         if host.contains(pattern) {
-            return Err("This URL is not allowed for security reasons".to_string());
+            return Err(CommandError::invalid_input("This URL is not allowed for security reasons"));
         }
     }
 
@@ -3533,11 +6571,22 @@ async fn open_article_window(app: tauri::AppHandle, url: String, title: String)
 pub fn run() {
     // Initialize database with absolute path to avoid CWD issues
     let db_path = get_data_path("finance.db");
-    let db = Database::open(&db_path).expect("Failed to open database");
+    let pool = Database::pool(&db_path, &DatabaseOptions::default(), DB_POOL_SIZE)
+        .expect("Failed to open database");
+    let db = pool.get().expect("Failed to open database");
     db.init_schema().expect("Failed to initialize schema");
+    let finnhub_cache = Arc::new(FinnhubCache::open(&db_path).expect("Failed to open Finnhub cache"));
+    let rate_limiter = Arc::new(build_rate_limiter(&db, &db_path));
+    drop(db);
 
     tauri::Builder::default()
-        .manage(AppState { db: Mutex::new(db) })
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_dialog::init())
+        .manage(AppState {
+            db: pool,
+            finnhub_cache,
+            rate_limiter,
+        })
         .invoke_handler(tauri::generate_handler![
             get_symbols,
             toggle_favorite,
@@ -3545,27 +6594,72 @@ pub fn run() {
             favorite_dc_positions,
             favorite_paper_positions,
             fetch_prices,
+            fetch_symbol_profile,
             fetch_fred,
             get_macro_data,
+            get_macro_history,
             get_price,
             calculate_indicators,
+            calculate_all_indicators,
+            compute_indicators_adhoc,
             get_indicators,
             get_indicator_history,
+            get_available_indicators,
             get_price_history,
+            get_price_range,
+            get_heikin_ashi,
+            get_resampled_prices,
+            get_resampled_indicators,
+            get_pivot_points,
+            get_fib_levels,
             export_csv,
+            backup_database,
+            restore_database,
+            export_config_file,
+            import_config_file,
             search_symbol,
+            add_symbol_alias,
             add_alert,
             get_alerts,
             delete_alert,
             check_alerts,
+            check_and_notify_alerts,
+            get_notification_settings,
+            set_notification_settings,
+            add_webhook,
+            list_webhooks,
+            set_webhook_enabled,
+            delete_webhook,
             add_position,
             get_portfolio,
+            generate_portfolio_report,
+            fetch_dividends,
+            get_portfolio_allocation,
+            get_portfolio_benchmark,
+            get_rebalance_plan,
             delete_position,
+            restore_position,
+            get_audit_log,
             fetch_trends,
             get_trends,
+            get_trends_correlation,
             // Signal commands
             generate_signals,
             get_signals,
+            get_confluence_score,
+            get_unfilled_gaps,
+            get_signal_config,
+            update_signal_config,
+            get_signal_weights,
+            update_signal_weights,
+            get_refresh_settings,
+            get_data_freshness,
+            get_price_stats,
+            update_refresh_settings,
+            prune_database,
+            set_retention_days,
+            find_duplicate_symbols,
+            merge_symbol,
             get_all_signals,
             acknowledge_signal,
             acknowledge_all_signals,
@@ -3579,8 +6673,13 @@ pub fn run() {
             get_strategies,
             delete_strategy,
             run_backtest,
+            run_portfolio_backtest,
+            run_walk_forward_backtest,
+            optimize_strategy,
             get_backtest_results,
             get_backtest_detail,
+            generate_backtest_report,
+            run_monte_carlo,
             delete_backtest,
             // Watchlist/Symbol Group commands
             create_watchlist,
@@ -3591,21 +6690,36 @@ pub fn run() {
             remove_symbol_from_watchlist,
             update_watchlist_description,
             rename_watchlist,
+            get_correlation_matrix,
             // Vector database commands
             vector_search,
             add_market_event,
             add_price_pattern,
+            delete_market_event,
+            delete_price_pattern,
             get_vector_stats,
             // Claude AI commands
             claude_chat,
             claude_query,
+            claude_chat_in_conversation,
+            get_claude_context_budget,
+            set_claude_context_budget,
+            list_conversations,
+            get_conversation,
+            delete_conversation,
             // Ollama local LLM commands
             ollama_available,
+            ollama_list_models,
             ollama_sentiment,
             ollama_explain,
             ollama_ask,
+            ollama_ask_stream,
             // Finnhub news commands
             fetch_news,
+            fetch_earnings,
+            clear_finnhub_cache,
+            get_rate_limits,
+            set_rate_limit,
             fetch_price_reaction,
             fetch_candles,
             // Enhanced event saving with pattern linking
@@ -3617,31 +6731,48 @@ pub fn run() {
             get_paper_positions,
             execute_paper_trade,
             get_paper_trades,
+            set_paper_position_exits,
+            check_paper_stops,
+            place_paper_order,
+            cancel_paper_order,
+            get_paper_orders,
+            process_paper_orders,
+            get_cost_basis_method,
+            set_cost_basis_method,
             reset_paper_account,
             // DC trader commands
             get_dc_balance,
             get_dc_positions,
             execute_dc_trade,
             get_dc_trades,
+            set_dc_position_exits,
+            check_dc_stops,
+            get_suggested_stops,
             reset_dc_account,
             import_dc_trades_csv,
             import_dc_trades_json,
+            export_trades_csv,
+            export_trades_json,
             lookup_current_price,
             record_portfolio_snapshot,
             get_portfolio_snapshots,
+            backfill_snapshots,
             save_team_config,
             load_team_config,
             list_team_configs,
             get_competition_stats,
+            set_trade_fees,
             // AI trader commands
             ai_trader_get_status,
             ai_trader_get_config,
             ai_trader_start_session,
             ai_trader_end_session,
             ai_trader_run_cycle,
+            ai_trader_replay,
             ai_trader_get_decisions,
             ai_trader_get_performance_history,
             ai_trader_get_benchmark_comparison,
+            ai_trader_set_benchmark,
             ai_trader_get_compounding_forecast,
             ai_trader_get_prediction_accuracy,
             ai_trader_evaluate_predictions,
@@ -3652,6 +6783,7 @@ pub fn run() {
             ai_trader_get_circuit_breaker,
             ai_trader_update_circuit_breaker,
             ai_trader_get_rejections,
+            ai_trader_dry_run_trade,
             ai_trader_get_circuit_breaker_events,
         ])
         .setup(|app| {
@@ -3662,8 +6794,33 @@ pub fn run() {
                         .build(),
                 )?;
             }
+            spawn_alert_notifier(app.handle().clone());
+            spawn_favorites_refresher(app.handle().clone());
+            spawn_snapshot_recorder(app.handle().clone());
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|_app, event| {
+            if matches!(event, tauri::RunEvent::ExitRequested { .. } | tauri::RunEvent::Exit) {
+                SHUTTING_DOWN.store(true, Ordering::SeqCst);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_data_path_uses_fp_data_dir() {
+        let base = std::env::temp_dir().join("fp-data-test");
+        std::env::set_var("FP_DATA_DIR", &base);
+
+        let path = get_data_path("finance.db");
+
+        std::env::remove_var("FP_DATA_DIR");
+        assert_eq!(path, base.join("finance.db"));
+        assert_eq!(path.file_name().and_then(|f| f.to_str()), Some("finance.db"));
+    }
 }
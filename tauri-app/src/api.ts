@@ -1,6 +1,7 @@
 // Tauri API wrapper
 
-import { invoke } from '@tauri-apps/api/core';
+import { invoke as tauriInvoke, type InvokeArgs } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 
 // Types matching Rust structs
 export interface SymbolPrice {
@@ -9,6 +10,7 @@ export interface SymbolPrice {
     change_percent: number;
     change_direction: string;
     favorited: boolean;
+    stale: boolean;  // last daily bar older than the staleness threshold
 }
 
 export interface CommandResult {
@@ -16,6 +18,12 @@ export interface CommandResult {
     message: string;
 }
 
+// Returned by commands that insert a row
+export interface CreatedResponse {
+    id: number;
+    message: string;
+}
+
 export interface IndicatorData {
     name: string;
     value: number;
@@ -36,6 +44,7 @@ export interface MacroData {
     value: number;
     date: string;
     source: string;
+    frequency: string | null;
 }
 
 export interface Alert {
@@ -46,6 +55,15 @@ export interface Alert {
     triggered: boolean;
 }
 
+export interface WebhookInfo {
+    id: number;
+    url: string;
+    event_type: string;
+    has_secret: boolean;
+    enabled: boolean;
+    created_at: string;
+}
+
 export interface Position {
     id: number;
     symbol: string;
@@ -57,6 +75,7 @@ export interface Position {
     current_value: number;
     profit_loss: number;
     profit_loss_percent: number;
+    dividend_income: number;
 }
 
 export interface Portfolio {
@@ -64,6 +83,46 @@ export interface Portfolio {
     total_value: number;
     total_profit_loss: number;
     total_profit_loss_percent: number;
+    total_dividend_income: number;
+}
+
+// Error payload returned by every command (mirrors Rust CommandError)
+export class CommandError extends Error {
+    code: string;
+    retryable: boolean;
+
+    constructor(code: string, message: string, retryable: boolean) {
+        super(message);
+        this.name = 'CommandError';
+        this.code = code;
+        this.retryable = retryable;
+    }
+
+    // Keep `${error}` rendering as the plain message
+    toString(): string {
+        return this.message;
+    }
+}
+
+function toCommandError(raw: unknown): CommandError {
+    if (raw instanceof CommandError) return raw;
+    if (raw && typeof raw === 'object' && 'code' in raw && 'message' in raw) {
+        const e = raw as { code: string; message: string; retryable?: boolean };
+        return new CommandError(e.code, e.message, e.retryable ?? false);
+    }
+    return new CommandError('COMMAND_FAILED', String(raw), false);
+}
+
+export function isRetryable(error: unknown): boolean {
+    return error instanceof CommandError && error.retryable;
+}
+
+async function invoke<T>(cmd: string, args?: InvokeArgs): Promise<T> {
+    try {
+        return await tauriInvoke<T>(cmd, args);
+    } catch (raw) {
+        throw toCommandError(raw);
+    }
 }
 
 // API functions
@@ -89,10 +148,106 @@ export async function favoritePaperPositions(): Promise<CommandResult> {
     return invoke('favorite_paper_positions');
 }
 
+// Auto-refresh of favorited symbols
+export interface RefreshSettings {
+    enabled: boolean;
+    interval_minutes: number;
+    stale_after_days: number;  // days without a new bar before prices count as stale
+}
+
+export async function getRefreshSettings(): Promise<RefreshSettings> {
+    return invoke('get_refresh_settings');
+}
+
+export async function updateRefreshSettings(settings: RefreshSettings): Promise<CommandResult> {
+    return invoke('update_refresh_settings', { settings });
+}
+
+export interface PrunedTable {
+    table: string;
+    retention_days: number;  // override for this table, or the prune default
+    deleted: number;
+}
+
+// Delete rows older than `days` from signals, api_calls and ai_performance_snapshots
+export async function pruneDatabase(days: number): Promise<PrunedTable[]> {
+    return invoke('prune_database', { days });
+}
+
+// Override one table's retention window; pass null to fall back to the prune default
+export async function setRetentionDays(table: string, days: number | null): Promise<CommandResult> {
+    return invoke('set_retention_days', { table, days });
+}
+
+export interface DuplicateSymbol {
+    canonical: string;  // upper-case merge target
+    variants: string[];
+}
+
+// Symbols stored under more than one casing (e.g. 'aapl' and 'AAPL')
+export async function findDuplicateSymbols(): Promise<DuplicateSymbol[]> {
+    return invoke('find_duplicate_symbols');
+}
+
+// Move all rows of `from` onto `to`; open paper/DC positions are averaged together
+export async function mergeSymbol(from: string, to: string): Promise<CommandResult> {
+    return invoke('merge_symbol', { from, to });
+}
+
+export interface DataFreshness {
+    symbol: string;
+    last_date: string;
+    days_stale: number;
+    stale: boolean;
+}
+
+// Latest daily bar per symbol and its age in days, oldest first
+export async function getDataFreshness(): Promise<DataFreshness[]> {
+    return invoke('get_data_freshness');
+}
+
+export interface PriceStats {
+    symbol: string;
+    bars: number;  // fewer than requested when the history is shorter
+    high: number;
+    high_date: string;
+    low: number;
+    low_date: string;
+    avg_close: number;
+    avg_volume: number;
+}
+
+// High/low and average close/volume over the latest `days` bars (252 for 52 weeks)
+export async function getPriceStats(symbol: string, days = 252): Promise<PriceStats> {
+    return invoke('get_price_stats', { symbol, days });
+}
+
+// Fires after a background refresh with the symbols whose latest price changed
+export async function onPricesUpdated(handler: (symbols: string[]) => void): Promise<UnlistenFn> {
+    return listen<{ symbols: string[] }>('prices-updated', (event) => handler(event.payload.symbols));
+}
+
 export async function fetchPrices(symbols: string, period: string): Promise<CommandResult> {
     return invoke('fetch_prices', { symbols, period });
 }
 
+export interface SymbolProfile {
+    symbol: string;
+    name: string | null;
+    sector: string | null;
+    industry: string | null;
+    market_cap: number | null;
+    country: string | null;
+    exchange: string | null;
+    currency: string | null;
+    asset_class: string | null;
+}
+
+// Fetch name/sector/industry from Yahoo (existing values are kept)
+export async function fetchSymbolProfile(symbol: string): Promise<SymbolProfile> {
+    return invoke('fetch_symbol_profile', { symbol });
+}
+
 export async function fetchFred(indicators: string): Promise<CommandResult> {
     return invoke('fetch_fred', { indicators });
 }
@@ -101,10 +256,64 @@ export async function getMacroData(): Promise<MacroData[]> {
     return invoke('get_macro_data');
 }
 
+export async function getMacroHistory(indicator: string, days: number): Promise<MacroData[]> {
+    return invoke('get_macro_history', { indicator, days });
+}
+
 export async function calculateIndicators(symbol: string): Promise<CommandResult> {
     return invoke('calculate_indicators', { symbol });
 }
 
+export interface IndicatorRunSummary {
+    symbol: string;
+    bars: number;
+    indicators: number;  // values written; 0 when already up to date
+    skipped: boolean;    // too few bars to calculate
+}
+
+// Calculate indicators for every symbol with price data
+export async function calculateAllIndicators(fullRecompute?: boolean): Promise<IndicatorRunSummary[]> {
+    return invoke('calculate_all_indicators', { fullRecompute });
+}
+
+export interface UnfilledGap {
+    date: string;
+    direction: 'bullish' | 'bearish';  // gap up / gap down
+    prev_close: number;  // fill level
+    open: number;
+    gap_percent: number;
+}
+
+// Overnight gaps the price hasn't traded back through (minPct defaults to the signal config threshold)
+export async function getUnfilledGaps(symbol: string, minPct?: number): Promise<UnfilledGap[]> {
+    return invoke('get_unfilled_gaps', { symbol, minPct });
+}
+
+// Strength multiplier per signal type, e.g. { RSI_OVERSOLD: 2 }; unlisted types weigh 1
+export type SignalWeights = Record<string, number>;
+
+export async function getSignalWeights(): Promise<SignalWeights> {
+    return invoke('get_signal_weights');
+}
+
+// Replaces all weights; signal strengths are reported on a 0-100 scale after weighting
+export async function updateSignalWeights(weights: SignalWeights): Promise<CommandResult> {
+    return invoke('update_signal_weights', { weights });
+}
+
+// Lookback periods for ad-hoc calculation; omitted fields use the backend defaults
+export interface IndicatorConfig {
+    rsi_periods?: number[];
+    sma_periods?: number[];
+    ema_periods?: number[];
+    bad_bars?: 'forward_fill' | 'skip';
+}
+
+// Indicators for raw bars (e.g. pasted CSV) without storing anything; dates must ascend
+export async function computeIndicatorsAdhoc(prices: PriceData[], config?: IndicatorConfig): Promise<IndicatorData[]> {
+    return invoke('compute_indicators_adhoc', { prices, config });
+}
+
 export async function getIndicators(symbol: string): Promise<IndicatorData[]> {
     return invoke('get_indicators', { symbol });
 }
@@ -113,20 +322,103 @@ export async function getIndicatorHistory(symbol: string, indicatorName: string)
     return invoke('get_indicator_history', { symbol, indicatorName });
 }
 
+// Names of every indicator series stored for a symbol, sorted
+export async function getAvailableIndicators(symbol: string): Promise<string[]> {
+    return invoke('get_available_indicators', { symbol });
+}
+
 export async function getPriceHistory(symbol: string): Promise<PriceData[]> {
     return invoke('get_price_history', { symbol });
 }
 
-export async function searchSymbol(query: string): Promise<string[]> {
-    return invoke('search_symbol', { query });
+// Heikin-Ashi candles, same shape as getPriceHistory
+export async function getHeikinAshi(symbol: string): Promise<PriceData[]> {
+    return invoke('get_heikin_ashi', { symbol });
+}
+
+export type Timeframe = 'weekly' | 'monthly';
+
+// Daily bars aggregated by ISO week or calendar month; the last bar may be partial
+export async function getResampledPrices(symbol: string, timeframe: Timeframe): Promise<PriceData[]> {
+    return invoke('get_resampled_prices', { symbol, timeframe });
+}
+
+// Indicators calculated on the fly over resampled bars
+export async function getResampledIndicators(symbol: string, timeframe: Timeframe): Promise<IndicatorData[]> {
+    return invoke('get_resampled_indicators', { symbol, timeframe });
+}
+
+export interface PivotPoints {
+    symbol: string;
+    date: string;
+    based_on: string;
+    pivot: number;
+    r1: number;
+    r2: number;
+    r3: number;
+    s1: number;
+    s2: number;
+    s3: number;
+}
+
+export interface FibLevels {
+    symbol: string;
+    swing_high: number;
+    swing_high_date: string;
+    swing_low: number;
+    swing_low_date: string;
+    levels: { ratio: number; price: number }[];
+}
+
+// Classic pivots for the latest session, from the prior bar
+export async function getPivotPoints(symbol: string): Promise<PivotPoints> {
+    return invoke('get_pivot_points', { symbol });
+}
+
+// Retracements over the high/low of the last `lookback` bars (default 60)
+export async function getFibLevels(symbol: string, lookback?: number): Promise<FibLevels> {
+    return invoke('get_fib_levels', { symbol, lookback });
+}
+
+export interface SymbolMatch {
+    symbol: string;
+    name: string | null;
+    score: number;
+}
+
+export async function searchSymbol(query: string, limit?: number): Promise<SymbolMatch[]> {
+    return invoke('search_symbol', { query, limit });
+}
+
+export async function addSymbolAlias(alias: string, symbol: string): Promise<CommandResult> {
+    return invoke('add_symbol_alias', { alias, symbol });
 }
 
 export async function exportCsv(symbol: string): Promise<CommandResult> {
     return invoke('export_csv', { symbol });
 }
 
+export async function backupDatabase(path: string): Promise<CommandResult> {
+    return invoke('backup_database', { path });
+}
+
+// Write watchlists, strategies, alerts and team configs to a JSON file
+export async function exportConfigFile(path: string): Promise<CommandResult> {
+    return invoke('export_config_file', { path });
+}
+
+// Load a configuration file; merge upserts by name, otherwise existing config is replaced
+export async function importConfigFile(path: string, merge: boolean): Promise<CommandResult> {
+    return invoke('import_config_file', { path, merge });
+}
+
+// Refused (success: false) if the backup is unreadable or from a different schema version
+export async function restoreDatabase(path: string): Promise<CommandResult> {
+    return invoke('restore_database', { path });
+}
+
 // Alerts
-export async function addAlert(symbol: string, targetPrice: number, condition: string): Promise<CommandResult> {
+export async function addAlert(symbol: string, targetPrice: number, condition: string): Promise<CreatedResponse> {
     return invoke('add_alert', { symbol, targetPrice, condition });
 }
 
@@ -142,6 +434,23 @@ export async function checkAlerts(): Promise<Alert[]> {
     return invoke('check_alerts');
 }
 
+// Webhooks (eventType: 'signal' | 'price_alert' | 'indicator_alert' | 'all')
+export async function addWebhook(url: string, eventType?: string, secret?: string): Promise<CommandResult> {
+    return invoke('add_webhook', { url, eventType, secret });
+}
+
+export async function listWebhooks(): Promise<WebhookInfo[]> {
+    return invoke('list_webhooks');
+}
+
+export async function setWebhookEnabled(webhookId: number, enabled: boolean): Promise<CommandResult> {
+    return invoke('set_webhook_enabled', { webhookId, enabled });
+}
+
+export async function deleteWebhook(webhookId: number): Promise<CommandResult> {
+    return invoke('delete_webhook', { webhookId });
+}
+
 // Portfolio
 export async function addPosition(
     symbol: string,
@@ -150,7 +459,7 @@ export async function addPosition(
     positionType: string,
     date: string,
     notes: string | null
-): Promise<CommandResult> {
+): Promise<CreatedResponse> {
     return invoke('add_position', { symbol, quantity, price, positionType, date, notes });
 }
 
@@ -158,10 +467,86 @@ export async function getPortfolio(): Promise<Portfolio> {
     return invoke('get_portfolio');
 }
 
+// Save a PDF of current holdings and allocation to the given path
+export async function generatePortfolioReport(path: string): Promise<CommandResult> {
+    return invoke('generate_portfolio_report', { path });
+}
+
+// Save a PDF of a stored backtest (parameters, metrics, equity curve, trades) to the given path
+export async function generateBacktestReport(backtestId: number, path: string): Promise<CommandResult> {
+    return invoke('generate_backtest_report', { backtestId, path });
+}
+
+export async function fetchDividends(symbol: string): Promise<CommandResult> {
+    return invoke('fetch_dividends', { symbol });
+}
+
+export interface AllocationBucket {
+    name: string;
+    value: number;
+    percent: number;
+}
+
+export interface PortfolioAllocation {
+    total_value: number;
+    by_sector: AllocationBucket[];
+    by_asset_class: AllocationBucket[];
+}
+
+export async function getPortfolioAllocation(): Promise<PortfolioAllocation> {
+    return invoke('get_portfolio_allocation');
+}
+
+export interface PortfolioBenchmark {
+    benchmark: string;
+    portfolio_return_percent: number;
+    benchmark_return_percent: number;
+    alpha: number;
+    tracking_data: [string, number, number][]; // [date, portfolio, benchmark], rebased to 100
+}
+
+// Time-weighted portfolio return vs a benchmark over the last `days` days (default 365)
+export async function getPortfolioBenchmark(benchmark: string, days?: number): Promise<PortfolioBenchmark> {
+    return invoke('get_portfolio_benchmark', { benchmark, days });
+}
+
+export interface RebalanceTrade {
+    symbol: string;
+    action: 'buy' | 'sell';
+    shares: number;
+    price: number;
+    est_value: number;
+}
+
+// targets: symbol -> percent of portfolio value (must total 100 or less)
+export async function getRebalancePlan(targets: Record<string, number>, minTradeValue?: number): Promise<RebalanceTrade[]> {
+    return invoke('get_rebalance_plan', { targets: JSON.stringify(targets), minTradeValue });
+}
+
 export async function deletePosition(positionId: number): Promise<CommandResult> {
     return invoke('delete_position', { positionId });
 }
 
+// Undo a deletePosition (positions are soft-deleted)
+export async function restorePosition(positionId: number): Promise<CommandResult> {
+    return invoke('restore_position', { positionId });
+}
+
+export interface AuditLogEntry {
+    id: number;
+    entity_type: 'position' | 'paper_trade' | 'price_alert';
+    entity_id: number;
+    action: 'create' | 'update' | 'delete' | 'restore';
+    before: string | null;  // row as JSON before the change
+    after: string | null;   // row as JSON after the change
+    created_at: string;
+}
+
+// Recent changes to positions, paper trades and price alerts, newest first (default 100)
+export async function getAuditLog(limit?: number): Promise<AuditLogEntry[]> {
+    return invoke('get_audit_log', { limit });
+}
+
 // Google Trends
 export async function fetchTrends(keyword: string): Promise<CommandResult> {
     return invoke('fetch_trends', { keyword });
@@ -171,6 +556,11 @@ export async function getTrends(keyword: string): Promise<{ date: string; value:
     return invoke('get_trends', { keyword });
 }
 
+// Correlation of search interest with the symbol's daily return `lag` trading days later (null if too little overlap)
+export async function getTrendsCorrelation(keyword: string, symbol: string, lag: number): Promise<number | null> {
+    return invoke('get_trends_correlation', { keyword, symbol, lag });
+}
+
 // Watchlists / Symbol Groups
 export interface WatchlistSummary {
     id: number;
@@ -219,6 +609,15 @@ export async function renameWatchlist(oldName: string, newName: string): Promise
     return invoke('rename_watchlist', { oldName, newName });
 }
 
+export interface CorrelationMatrix {
+    symbols: string[];
+    matrix: (number | null)[][];
+}
+
+export async function getCorrelationMatrix(watchlistName: string): Promise<CorrelationMatrix> {
+    return invoke('get_correlation_matrix', { watchlistName });
+}
+
 // Vector Database / AI Search
 export interface VectorSearchResult {
     id: string;
@@ -234,8 +633,20 @@ export interface VectorStats {
     patterns_count: number;
 }
 
-export async function vectorSearch(query: string, limit: number = 10): Promise<VectorSearchResult[]> {
-    return invoke('vector_search', { query, limit });
+export interface VectorSearchFilter {
+    symbol?: string;
+    fromDate?: string;
+    toDate?: string;
+    eventType?: string;
+}
+
+// Omitted filter fields match everything; dates are inclusive YYYY-MM-DD
+export async function vectorSearch(
+    query: string,
+    limit: number = 10,
+    filter: VectorSearchFilter = {}
+): Promise<VectorSearchResult[]> {
+    return invoke('vector_search', { query, limit, ...filter });
 }
 
 export async function addMarketEvent(
@@ -280,6 +691,7 @@ export interface ClaudeChatResponse {
     input_tokens: number;
     output_tokens: number;
     conversation_id: string;
+    estimated_cost_usd: number;
 }
 
 export async function claudeChat(query: string, apiKey: string): Promise<ClaudeChatResponse> {
@@ -290,6 +702,124 @@ export async function claudeQuery(query: string, apiKey: string): Promise<Claude
     return invoke('claude_query', { query, apiKey });
 }
 
+export async function getClaudeContextBudget(): Promise<number> {
+    return invoke('get_claude_context_budget');
+}
+
+export async function setClaudeContextBudget(maxContextTokens: number): Promise<CommandResult> {
+    return invoke('set_claude_context_budget', { maxContextTokens });
+}
+
+// Saved conversations
+export interface Conversation {
+    id: number;
+    title: string;
+    provider: string;
+    created_at: string;
+    updated_at: string;
+    message_count: number;
+}
+
+export interface ConversationMessage {
+    id: number;
+    role: 'user' | 'assistant';
+    content: string;
+    created_at: string;
+}
+
+export interface ConversationDetail {
+    conversation: Conversation;
+    messages: ConversationMessage[];
+}
+
+export interface ConversationChatResponse {
+    conversation_id: number;
+    response: string;
+    model: string;
+    input_tokens: number;
+    output_tokens: number;
+    estimated_cost_usd: number;
+}
+
+export async function claudeChatInConversation(
+    conversationId: number | null,
+    query: string,
+    apiKey: string
+): Promise<ConversationChatResponse> {
+    return invoke('claude_chat_in_conversation', { conversationId, query, apiKey });
+}
+
+export async function listConversations(): Promise<Conversation[]> {
+    return invoke('list_conversations');
+}
+
+export async function getConversation(conversationId: number): Promise<ConversationDetail> {
+    return invoke('get_conversation', { conversationId });
+}
+
+export async function deleteConversation(conversationId: number): Promise<CommandResult> {
+    return invoke('delete_conversation', { conversationId });
+}
+
+// Ollama (local LLM); model defaults to the server-side default when omitted
+export interface OllamaModel {
+    name: string;
+    size: number;
+    modified_at: string | null;
+}
+
+export interface SentimentResult {
+    sentiment: 'Bullish' | 'Bearish' | 'Neutral';
+    confidence: number;
+    reasoning: string;
+}
+
+export interface PatternExplanation {
+    pattern_name: string;
+    explanation: string;
+    typical_outcome: string;
+    confidence_level: string;
+}
+
+export async function ollamaListModels(): Promise<OllamaModel[]> {
+    return invoke('ollama_list_models');
+}
+
+export async function ollamaSentiment(text: string, model?: string): Promise<SentimentResult> {
+    return invoke('ollama_sentiment', { text, model });
+}
+
+export async function ollamaExplain(pattern: string, context?: string, model?: string): Promise<PatternExplanation> {
+    return invoke('ollama_explain', { pattern, context, model });
+}
+
+export async function ollamaAsk(question: string, context: string, model?: string): Promise<string> {
+    return invoke('ollama_ask', { question, context, model });
+}
+
+export interface OllamaDone {
+    response: string | null;
+    error: CommandError | null;
+}
+
+// Resolves with the full answer; subscribe with onOllamaToken/onOllamaDone first to render it live
+export async function ollamaAskStream(question: string, context: string, model?: string): Promise<string> {
+    return invoke('ollama_ask_stream', { question, context, model });
+}
+
+export async function onOllamaToken(handler: (token: string) => void): Promise<UnlistenFn> {
+    return listen<{ token: string }>('ollama-token', (event) => handler(event.payload.token));
+}
+
+export async function onOllamaDone(handler: (done: OllamaDone) => void): Promise<UnlistenFn> {
+    return listen<{ response: string | null; error: unknown }>('ollama-done', (event) =>
+        handler({
+            response: event.payload.response,
+            error: event.payload.error ? toCommandError(event.payload.error) : null,
+        })
+    );
+}
+
 // Finnhub News
 export interface SimpleNewsItem {
     headline: string;
@@ -309,6 +839,46 @@ export async function fetchNews(symbol: string, apiKey: string, limit: number =
     return invoke('fetch_news', { symbol, apiKey, limit });
 }
 
+export interface EarningsEvent {
+    symbol: string;
+    date: string;
+    hour: string | null;  // bmo / amc / dmh
+    quarter: number | null;
+    year: number | null;
+    eps_estimate: number | null;
+    eps_actual: number | null;
+    revenue_estimate: number | null;
+    revenue_actual: number | null;
+}
+
+export async function fetchEarnings(
+    symbol: string,
+    apiKey: string,
+    from: string | null = null,
+    to: string | null = null
+): Promise<EarningsEvent[]> {
+    return invoke('fetch_earnings', { symbol, apiKey, from, to });
+}
+
+export async function clearFinnhubCache(): Promise<CommandResult> {
+    return invoke('clear_finnhub_cache');
+}
+
+export interface RateLimitStatus {
+    source: string;
+    per_minute: number | null;
+    per_day: number | null;
+    calls_today: number;
+}
+
+export async function getRateLimits(): Promise<RateLimitStatus[]> {
+    return invoke('get_rate_limits');
+}
+
+export async function setRateLimit(source: string, perMinute: number | null, perDay: number | null): Promise<CommandResult> {
+    return invoke('set_rate_limit', { source, perMinute, perDay });
+}
+
 // Price Reaction (candle data around an event)
 export interface PriceReactionResponse {
     symbol: string;
@@ -341,6 +911,8 @@ export interface CandleDataResponse {
     volume: number[];
     timestamp: number[];
     dates: string[];  // YYYY-MM-DD format
+    datetimes: string[];  // RFC 3339 UTC, e.g. 2026-01-05T14:30:00Z
+    stored: number;  // bars saved to intraday storage when persist is set
 }
 
 export async function fetchCandles(
@@ -348,9 +920,10 @@ export async function fetchCandles(
     fromDate: string,
     toDate: string,
     apiKey: string,
-    resolution: string = 'D'
+    resolution: string = 'D',
+    persist: boolean = false
 ): Promise<CandleDataResponse> {
-    return invoke('fetch_candles', { symbol, fromDate, toDate, apiKey, resolution });
+    return invoke('fetch_candles', { symbol, fromDate, toDate, apiKey, resolution, persist });
 }
 
 // Enhanced event saving with pattern linking
@@ -361,6 +934,7 @@ export interface EventWithPatternResponse {
     pattern_id: string | null;
     price_change_percent: number | null;
     pattern_error: string | null;  // Actual error reason for debugging
+    earnings_event_ids: string[];
 }
 
 export async function addMarketEventWithPattern(
@@ -372,8 +946,13 @@ export async function addMarketEventWithPattern(
     sentiment: number | null,
     apiKey: string | null,
     linkPattern: boolean,
-    daysWindow: number = 3
+    daysWindow: number = 3,
+    includeEarnings: boolean = false,
+    resolution: string | null = null,
+    hoursWindow: number = 4
 ): Promise<EventWithPatternResponse> {
+    // With a resolution ('5', '15', ...) and a timestamped date, the reaction is measured
+    // from stored intraday bars within hoursWindow; otherwise from daily closes
     return invoke('add_market_event_with_pattern', {
         symbol,
         eventType,
@@ -383,7 +962,10 @@ export async function addMarketEventWithPattern(
         sentiment,
         apiKey,
         linkPattern,
-        daysWindow
+        daysWindow,
+        includeEarnings,
+        resolution,
+        hoursWindow
     });
 }
 
@@ -396,13 +978,22 @@ export async function openArticleWindow(url: string, title: string): Promise<voi
 // PAPER TRADING
 // ============================================================================
 
+// Commission per trade: flat amount plus a percent of notional (0.1 = 0.1%)
+export interface TradeFees {
+    per_trade_fee: number;
+    fee_percent: number;
+}
+
 export interface PaperWalletBalance {
     cash: number;
     positions_value: number;
     total_equity: number;
     starting_capital: number;
+    fees: TradeFees;
     total_pnl: number;
     total_pnl_percent: number;
+    realized_pnl: number;
+    unrealized_pnl: number;
 }
 
 export interface PaperPosition {
@@ -411,6 +1002,8 @@ export interface PaperPosition {
     quantity: number;
     entry_price: number;
     entry_date: string;
+    stop_price: number | null;
+    take_price: number | null;
     current_price: number;
     current_value: number;
     cost_basis: number;
@@ -425,6 +1018,7 @@ export interface PaperTrade {
     quantity: number;
     price: number;
     pnl: number | null;
+    fee: number;
     timestamp: string;
     notes: string | null;
 }
@@ -445,9 +1039,10 @@ export async function executePaperTrade(
     action: 'BUY' | 'SELL',
     quantity: number,
     price?: number,
-    notes?: string
+    notes?: string,
+    allowPartial = false  // fill as many whole shares as cash allows instead of failing a BUY
 ): Promise<PaperTrade> {
-    return invoke('execute_paper_trade', { symbol, action, quantity, price, notes });
+    return invoke('execute_paper_trade', { symbol, action, quantity, price, notes, allowPartial });
 }
 
 // Get paper trade history
@@ -455,6 +1050,66 @@ export async function getPaperTrades(symbol?: string, limit?: number): Promise<P
     return invoke('get_paper_trades', { symbol, limit });
 }
 
+// Set or clear (null) a paper position's stop-loss/take-profit prices
+export async function setPaperPositionExits(symbol: string, stopPrice: number | null, takePrice: number | null): Promise<CommandResult> {
+    return invoke('set_paper_position_exits', { symbol, stopPrice, takePrice });
+}
+
+// Close paper positions at their stop/take levels; percents apply to positions without their own
+export async function checkPaperStops(stopPct?: number, takePct?: number): Promise<PaperTrade[]> {
+    return invoke('check_paper_stops', { stopPct, takePct });
+}
+
+export interface PaperOrder {
+    id: number;
+    symbol: string;
+    action: 'BUY' | 'SELL';
+    order_type: 'LIMIT' | 'STOP';
+    quantity: number;
+    limit_price: number | null;
+    stop_price: number | null;
+    status: 'PENDING' | 'FILLED' | 'CANCELLED';
+    paper_trade_id: number | null;
+    created_at: string;
+    filled_at: string | null;
+}
+
+// Queue a limit or stop paper order
+export async function placePaperOrder(
+    symbol: string,
+    action: 'BUY' | 'SELL',
+    orderType: 'LIMIT' | 'STOP',
+    quantity: number,
+    limitPrice?: number,
+    stopPrice?: number
+): Promise<PaperOrder> {
+    return invoke('place_paper_order', { symbol, action, orderType, quantity, limitPrice, stopPrice });
+}
+
+// Cancel a pending paper order
+export async function cancelPaperOrder(id: number): Promise<CommandResult> {
+    return invoke('cancel_paper_order', { id });
+}
+
+// Get paper orders (optionally filtered by status)
+export async function getPaperOrders(status?: PaperOrder['status']): Promise<PaperOrder[]> {
+    return invoke('get_paper_orders', { status });
+}
+
+// Fill pending paper orders triggered by the latest prices
+export async function processPaperOrders(): Promise<PaperOrder[]> {
+    return invoke('process_paper_orders');
+}
+
+// Cost basis method used for paper and DC sells
+export async function getCostBasisMethod(): Promise<'average' | 'fifo'> {
+    return invoke('get_cost_basis_method');
+}
+
+export async function setCostBasisMethod(method: 'average' | 'fifo'): Promise<CommandResult> {
+    return invoke('set_cost_basis_method', { method });
+}
+
 // Reset paper trading account
 export async function resetPaperAccount(startingCash?: number): Promise<CommandResult> {
     return invoke('reset_paper_account', { startingCash });
@@ -469,8 +1124,11 @@ export interface DcWalletBalance {
     positions_value: number;
     total_equity: number;
     starting_capital: number;
+    fees: TradeFees;
     total_pnl: number;
     total_pnl_percent: number;
+    realized_pnl: number;
+    unrealized_pnl: number;
 }
 
 export interface DcPosition {
@@ -479,6 +1137,8 @@ export interface DcPosition {
     quantity: number;
     entry_price: number;
     entry_date: string;
+    stop_price: number | null;
+    take_price: number | null;
     current_price: number;
     current_value: number;
     cost_basis: number;
@@ -493,6 +1153,7 @@ export interface DcTrade {
     quantity: number;
     price: number;
     pnl: number | null;
+    fee: number;
     timestamp: string;
     notes: string | null;
 }
@@ -518,6 +1179,8 @@ export interface TeamConfig {
     description: string | null;
     kalic_starting_capital: number;
     dc_starting_capital: number;
+    kalic_fees: TradeFees;
+    dc_fees: TradeFees;
     created_at: string;
 }
 
@@ -527,13 +1190,15 @@ export interface CompetitionStats {
     kalic_positions: number;
     kalic_pnl_pct: number;
     kalic_trades: number;
+    kalic_fees_paid: number;
     dc_total: number;
     dc_cash: number;
     dc_positions: number;
     dc_pnl_pct: number;
     dc_trades: number;
+    dc_fees_paid: number;
     leader: string;
-    lead_amount: number;
+    lead_amount: number; // P&L percentage points
 }
 
 // Get DC wallet balance and portfolio summary
@@ -552,9 +1217,10 @@ export async function executeDcTrade(
     action: 'BUY' | 'SELL',
     quantity: number,
     price?: number,
-    notes?: string
+    notes?: string,
+    allowPartial = false  // fill as many whole shares as cash allows instead of failing a BUY
 ): Promise<DcTrade> {
-    return invoke('execute_dc_trade', { symbol, action, quantity, price, notes });
+    return invoke('execute_dc_trade', { symbol, action, quantity, price, notes, allowPartial });
 }
 
 // Get DC trade history
@@ -562,6 +1228,29 @@ export async function getDcTrades(limit?: number): Promise<DcTrade[]> {
     return invoke('get_dc_trades', { limit });
 }
 
+// Set or clear (null) a DC position's stop-loss/take-profit prices
+export async function setDcPositionExits(symbol: string, stopPrice: number | null, takePrice: number | null): Promise<CommandResult> {
+    return invoke('set_dc_position_exits', { symbol, stopPrice, takePrice });
+}
+
+// Close DC positions at their stop/take levels; percents apply to positions without their own
+export async function checkDcStops(stopPct?: number, takePct?: number): Promise<DcTrade[]> {
+    return invoke('check_dc_stops', { stopPct, takePct });
+}
+
+export interface StopSuggestion {
+    symbol: string;
+    entry_price: number;
+    current_price: number;  // latest stored close
+    atr: number;  // 14-day ATR
+    suggested_stop: number;  // current_price - atrMultiple * atr
+}
+
+// Volatility stops for a team's longs; positions with under 15 bars of history are left out
+export async function getSuggestedStops(atrMultiple: number, team: 'KALIC' | 'DC' | 'PORTFOLIO'): Promise<StopSuggestion[]> {
+    return invoke('get_suggested_stops', { atrMultiple, team });
+}
+
 // Reset DC trading account
 export async function resetDcAccount(startingCash?: number): Promise<CommandResult> {
     return invoke('reset_dc_account', { starting_cash: startingCash });
@@ -577,21 +1266,36 @@ export async function importDcTradesJson(jsonContent: string): Promise<ImportRes
     return invoke('import_dc_trades_json', { jsonContent });
 }
 
+// Export a team's trade log ("KALIC" paper or "DC") as CSV to the given path
+export async function exportTradesCsv(team: 'KALIC' | 'DC', path: string): Promise<CommandResult> {
+    return invoke('export_trades_csv', { team, path });
+}
+
+// Export a team's trade log as JSON, also writing it to path when given
+export async function exportTradesJson(team: 'KALIC' | 'DC', path?: string): Promise<string> {
+    return invoke('export_trades_json', { team, path });
+}
+
 // Lookup current price for a symbol
 export async function lookupCurrentPrice(symbol: string): Promise<number> {
     return invoke('lookup_current_price', { symbol });
 }
 
 // Record portfolio snapshot for a team
-export async function recordPortfolioSnapshot(team: 'KALIC' | 'DC'): Promise<CommandResult> {
+export async function recordPortfolioSnapshot(team: 'KALIC' | 'DC' | 'PORTFOLIO'): Promise<CommandResult> {
     return invoke('record_portfolio_snapshot', { team });
 }
 
 // Get portfolio snapshots for charting
-export async function getPortfolioSnapshots(team?: 'KALIC' | 'DC', days?: number): Promise<PortfolioSnapshot[]> {
+export async function getPortfolioSnapshots(team?: 'KALIC' | 'DC' | 'PORTFOLIO', days?: number): Promise<PortfolioSnapshot[]> {
     return invoke('get_portfolio_snapshots', { team, days });
 }
 
+// Reconstruct missing snapshots from stored prices (default 90 days)
+export async function backfillSnapshots(days?: number): Promise<CommandResult> {
+    return invoke('backfill_snapshots', { days });
+}
+
 // Save team configuration
 export async function saveTeamConfig(name: string, description?: string): Promise<number> {
     return invoke('save_team_config', { name, description });
@@ -612,6 +1316,11 @@ export async function getCompetitionStats(): Promise<CompetitionStats> {
     return invoke('get_competition_stats');
 }
 
+// Set a team's commission; applies to trades from now on (all zero = commission-free)
+export async function setTradeFees(team: 'KALIC' | 'DC', perTradeFee: number, feePercent: number): Promise<void> {
+    return invoke('set_trade_fees', { team, perTradeFee, feePercent });
+}
+
 // ============================================================================
 // AI TRADER
 // ============================================================================
@@ -646,6 +1355,7 @@ export interface AiTradeDecision {
     actual_price_at_timeframe: number | null;
     prediction_accurate: boolean | null;
     paper_trade_id: number | null;
+    dry_run: boolean;  // recorded by a dry-run cycle, no trade executed
 }
 
 export interface AiPerformanceSnapshot {
@@ -706,9 +1416,11 @@ export interface AiTraderConfig {
     max_position_size_percent: number;
     stop_loss_percent: number;
     take_profit_percent: number;
+    risk_per_trade_percent: number;  // max % of equity lost if a BUY's stop is hit
     session_duration_minutes: number;
     benchmark_symbol: string;
     model_priority: string[];
+    allow_partial_fills: boolean;  // BUYs fill what cash allows instead of being rejected
 }
 
 // Get AI trader status
@@ -731,9 +1443,54 @@ export async function aiTraderEndSession(notes?: string): Promise<AiTradingSessi
     return invoke('ai_trader_end_session', { notes });
 }
 
-// Run one AI trading cycle
-export async function aiTraderRunCycle(): Promise<AiTradeDecision[]> {
-    return invoke('ai_trader_run_cycle');
+// Run one AI trading cycle; a dry run records decisions without executing trades
+export async function aiTraderRunCycle(dryRun = false): Promise<AiTradeDecision[]> {
+    return invoke('ai_trader_run_cycle', { dryRun });
+}
+
+export interface AiReplayTrade {
+    id: number;
+    symbol: string;
+    direction: string;
+    entry_date: string;
+    entry_price: number;
+    entry_reason: string;
+    exit_date: string | null;
+    exit_price: number | null;
+    exit_reason: string | null;
+    shares: number;
+    profit_loss: number | null;
+    profit_loss_percent: number | null;
+}
+
+export interface AiReplayResult {
+    symbol: string;
+    start_date: string;
+    end_date: string;
+    initial_capital: number;
+    final_value: number;
+    decisions_count: number;
+    metrics: {
+        total_return: number;
+        total_return_dollars: number;
+        max_drawdown: number;
+        sharpe_ratio: number;
+        win_rate: number;
+        total_trades: number;
+        winning_trades: number;
+        losing_trades: number;
+        avg_win_percent: number;
+        avg_loss_percent: number;
+        profit_factor: number;
+        avg_trade_duration_days: number;
+    };
+    trades: AiReplayTrade[];
+    equity_curve: [string, number][];
+}
+
+// Replay the AI trader over a historical window (dates as YYYY-MM-DD)
+export async function aiTraderReplay(symbol: string, fromDate: string, toDate: string): Promise<AiReplayResult> {
+    return invoke('ai_trader_replay', { symbol, fromDate, toDate });
 }
 
 // Get AI trading decisions
@@ -755,6 +1512,11 @@ export async function aiTraderGetBenchmarkComparison(): Promise<AiBenchmarkCompa
     return invoke('ai_trader_get_benchmark_comparison');
 }
 
+// Compare the AI trader against a different symbol (fetched first if not stored)
+export async function aiTraderSetBenchmark(symbol: string): Promise<CommandResult> {
+    return invoke('ai_trader_set_benchmark', { symbol });
+}
+
 // Get compounding forecast
 export async function aiTraderGetCompoundingForecast(): Promise<AiCompoundingForecast> {
     return invoke('ai_trader_get_compounding_forecast');
@@ -770,6 +1532,20 @@ export async function aiTraderEvaluatePredictions(): Promise<number> {
     return invoke('ai_trader_evaluate_predictions');
 }
 
+// Guardrail verdict for a trade that was only previewed
+export interface DryRunTradeResult {
+    allowed: boolean;
+    reason: string | null;
+    rule_triggered: 'circuit_breaker' | 'max_position' | 'max_daily_trades' | 'blocked_hours' | 'confluence' | null;
+    estimated_value: number;  // quantity at the latest price
+    quantity_percent: number;  // share of total paper equity
+}
+
+// Check whether a trade would pass the AI trader's guardrails without executing it
+export async function aiTraderDryRunTrade(action: 'BUY' | 'SELL', symbol: string, quantity: number): Promise<DryRunTradeResult> {
+    return invoke('ai_trader_dry_run_trade', { action, symbol, quantity });
+}
+
 // Reset AI trading
 export async function aiTraderReset(startingCapital?: number): Promise<CommandResult> {
     return invoke('ai_trader_reset', { startingCapital });
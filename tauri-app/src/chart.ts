@@ -312,6 +312,7 @@ export class IndicatorChart {
             else if (indicatorName.startsWith('STOCH')) color = '#06b6d4';
             else if (indicatorName.startsWith('ADX')) color = '#f97316';
             else if (indicatorName === 'OBV') color = '#a855f7';
+            else if (indicatorName.startsWith('ICHIMOKU')) color = '#14b8a6';
 
             const lineData: LineData[] = data.map(d => ({
                 time: d.date as Time,
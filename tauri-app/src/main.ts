@@ -1,6 +1,7 @@
 // Main entry point
 import './styles.css';
 import * as api from './api';
+import { open, save } from '@tauri-apps/plugin-dialog';
 import { TradingViewChart, IndicatorChart } from './chart';
 
 // =============================================================================
@@ -30,11 +31,12 @@ function sanitizeNumber(value: number, decimals: number = 2): string {
 }
 
 /**
- * Validates and sanitizes a symbol string (uppercase alphanumeric + dots/dashes only)
+ * Validates and sanitizes a symbol string (uppercase alphanumeric + dots/dashes,
+ * plus Yahoo's `=` for forex/futures and leading `^` for indices)
  */
 function sanitizeSymbol(symbol: string): string {
     if (typeof symbol !== 'string') return '';
-    return symbol.toUpperCase().replace(/[^A-Z0-9.\-]/g, '').substring(0, 20);
+    return symbol.toUpperCase().replace(/[^A-Z0-9.\-=^]/g, '').substring(0, 20);
 }
 
 // S&P 100 Symbol List (OEX constituents)
@@ -287,6 +289,17 @@ async function loadMacroData(): Promise<void> {
 }
 
 // Alerts
+function describeAlert(a: { condition: string; target_price: number }): string {
+    switch (a.condition) {
+        case 'above': return `>= $${a.target_price.toFixed(2)}`;
+        case 'below': return `<= $${a.target_price.toFixed(2)}`;
+        case 'pct_up': return `up ${a.target_price.toFixed(1)}% in a day`;
+        case 'pct_down': return `down ${a.target_price.toFixed(1)}% in a day`;
+        case 'volume_multiple': return `volume ${a.target_price.toFixed(1)}x previous day`;
+        default: return `${a.condition} ${a.target_price}`;
+    }
+}
+
 async function loadAlerts(): Promise<void> {
     try {
         const alerts = await api.getAlerts(false);
@@ -300,7 +313,7 @@ async function loadAlerts(): Promise<void> {
                     <div>
                         <span class="symbol-ticker">${a.symbol}</span>
                         <span style="color: var(--text-secondary); margin-left: 10px;">
-                            ${a.condition === 'above' ? '>=' : '<='} $${a.target_price.toFixed(2)}
+                            ${describeAlert(a)}
                         </span>
                     </div>
                     <div>
@@ -333,14 +346,14 @@ async function addAlert(): Promise<void> {
     }
 
     if (isNaN(price) || price <= 0) {
-        alert('Please enter a valid price');
+        alert('Please enter a valid price, percent, or volume multiple');
         return;
     }
 
     try {
-        log(`Adding alert: ${symbol} ${condition} $${price.toFixed(2)}...`, 'info');
+        log(`Adding alert: ${symbol} ${describeAlert({ condition, target_price: price })}...`, 'info');
         const result = await api.addAlert(symbol, price, condition);
-        log(result.message, result.success ? 'success' : 'error');
+        log(result.message, 'success');
         alert(result.message);
 
         // Clear form and reload
@@ -429,7 +442,7 @@ async function addPosition(): Promise<void> {
     try {
         log(`Adding ${positionType} position: ${quantity} x ${symbol} @ $${price.toFixed(2)}...`, 'info');
         const result = await api.addPosition(symbol, quantity, price, positionType, date, notes);
-        log(result.message, result.success ? 'success' : 'error');
+        log(result.message, 'success');
         alert(result.message);
 
         // Clear form and reload
@@ -493,10 +506,10 @@ async function searchCompany(query: string): Promise<void> {
     }
 
     try {
-        const symbols = await api.searchSymbol(query);
-        if (symbols && symbols.length > 0) {
-            resultsDiv.innerHTML = symbols.map(s =>
-                `<button class="btn-secondary search-result" data-symbol="${s}">${s}</button>`
+        const matches = await api.searchSymbol(query);
+        if (matches && matches.length > 0) {
+            resultsDiv.innerHTML = matches.map(m =>
+                `<button class="btn-secondary search-result" data-symbol="${m.symbol}" title="${m.name || ''}">${m.symbol}</button>`
             ).join('');
         } else {
             resultsDiv.innerHTML = '<span style="color: var(--text-secondary);">No matches</span>';
@@ -885,7 +898,7 @@ async function autoRefreshPrices(): Promise<void> {
         const triggered = await api.checkAlerts();
         if (triggered && triggered.length > 0) {
             const messages = triggered.map(a =>
-                `${a.symbol} ${a.condition === 'above' ? 'reached' : 'dropped to'} $${a.target_price.toFixed(2)}`
+                `${a.symbol} ${describeAlert(a)}`
             ).join('\n');
             alert(`Alerts triggered!\n\n${messages}`);
             log(`${triggered.length} alerts triggered!`, 'success');
@@ -2143,7 +2156,7 @@ function updateCompetitionStats(stats: api.CompetitionStats): void {
         if (stats.leader === 'TIE') {
             leaderEl.textContent = 'TIE';
         } else {
-            leaderEl.textContent = `LEADER: ${stats.leader} (+$${stats.lead_amount.toLocaleString('en-US', { minimumFractionDigits: 0, maximumFractionDigits: 0 })})`;
+            leaderEl.textContent = `LEADER: ${stats.leader} (+${stats.lead_amount.toFixed(2)} pts)`;
         }
     }
 }
@@ -2264,6 +2277,78 @@ async function importDcTradesJson(): Promise<void> {
     }
 }
 
+async function exportTrades(format: 'csv' | 'json'): Promise<void> {
+    const teamEl = document.getElementById('trade-export-team') as HTMLSelectElement;
+    const team = (teamEl?.value || 'DC') as 'KALIC' | 'DC';
+
+    const path = await save({
+        defaultPath: `${team.toLowerCase()}_trades.${format}`,
+        filters: [{ name: format.toUpperCase(), extensions: [format] }],
+    });
+    if (!path) return;
+
+    try {
+        if (format === 'csv') {
+            const result = await api.exportTradesCsv(team, path);
+            log(result.message, result.success ? 'success' : 'error');
+        } else {
+            await api.exportTradesJson(team, path);
+            log(`Exported ${team} trades to ${path}`, 'success');
+        }
+    } catch (error) {
+        log(`Export error: ${error}`, 'error');
+    }
+}
+
+async function savePortfolioReport(): Promise<void> {
+    const stamp = new Date().toISOString().slice(0, 10);
+    const path = await save({
+        defaultPath: `portfolio_report_${stamp}.pdf`,
+        filters: [{ name: 'PDF', extensions: ['pdf'] }],
+    });
+    if (!path) return;
+
+    try {
+        const result = await api.generatePortfolioReport(path);
+        log(result.message, result.success ? 'success' : 'error');
+    } catch (error) {
+        log(`Report error: ${error}`, 'error');
+    }
+}
+
+async function backupDatabase(): Promise<void> {
+    const stamp = new Date().toISOString().slice(0, 10);
+    const path = await save({
+        defaultPath: `finance_backup_${stamp}.db`,
+        filters: [{ name: 'SQLite', extensions: ['db'] }],
+    });
+    if (!path) return;
+
+    try {
+        const result = await api.backupDatabase(path);
+        log(result.message, result.success ? 'success' : 'error');
+    } catch (error) {
+        log(`Backup error: ${error}`, 'error');
+    }
+}
+
+async function restoreDatabase(): Promise<void> {
+    const path = await open({
+        multiple: false,
+        filters: [{ name: 'SQLite', extensions: ['db'] }],
+    });
+    if (!path || Array.isArray(path)) return;
+    if (!confirm('Replace all current data with this backup?')) return;
+
+    try {
+        const result = await api.restoreDatabase(path);
+        log(result.message, result.success ? 'success' : 'error');
+        if (result.success) await refreshSymbolList();
+    } catch (error) {
+        log(`Restore error: ${error}`, 'error');
+    }
+}
+
 async function resetDcAccount(): Promise<void> {
     try {
         await api.resetDcAccount(1000000);
@@ -2404,6 +2489,8 @@ function setupEventListeners(): void {
 
     // S&P 100 and ASX 100 buttons
     document.getElementById('sp100-btn')?.addEventListener('click', fetchSP100);
+    document.getElementById('backup-db-btn')?.addEventListener('click', backupDatabase);
+    document.getElementById('restore-db-btn')?.addEventListener('click', restoreDatabase);
     document.getElementById('asx100-btn')?.addEventListener('click', fetchASX100);
 
     // Chart controls
@@ -2479,7 +2566,7 @@ function setupEventListeners(): void {
             const triggered = await api.checkAlerts();
             if (triggered && triggered.length > 0) {
                 const messages = triggered.map(a =>
-                    `${a.symbol} ${a.condition === 'above' ? 'reached' : 'dropped to'} $${a.target_price.toFixed(2)}`
+                    `${a.symbol} ${describeAlert(a)}`
                 ).join('\n');
                 alert(`Alerts triggered!\n\n${messages}`);
                 log(`${triggered.length} alerts triggered!`, 'success');
@@ -2512,6 +2599,7 @@ function setupEventListeners(): void {
 
     // Portfolio
     document.getElementById('add-position-btn')?.addEventListener('click', addPosition);
+    document.getElementById('portfolio-report-btn')?.addEventListener('click', savePortfolioReport);
 
     // Position deletion (event delegation)
     document.getElementById('portfolio-list')?.addEventListener('click', async (e) => {
@@ -2640,6 +2728,8 @@ function setupEventListeners(): void {
     document.getElementById('dc-lookup-price-btn')?.addEventListener('click', lookupDcPrice);
     document.getElementById('dc-import-csv-btn')?.addEventListener('click', importDcTradesCsv);
     document.getElementById('dc-import-json-btn')?.addEventListener('click', importDcTradesJson);
+    document.getElementById('trade-export-csv-btn')?.addEventListener('click', () => exportTrades('csv'));
+    document.getElementById('trade-export-json-btn')?.addEventListener('click', () => exportTrades('json'));
     document.getElementById('dc-reset-account-btn')?.addEventListener('click', resetDcAccount);
     document.getElementById('dc-save-config-btn')?.addEventListener('click', saveTeamConfig);
     document.getElementById('dc-sync-prices-btn')?.addEventListener('click', syncDcPrices);